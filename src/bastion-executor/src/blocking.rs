@@ -86,7 +86,12 @@ impl BlockingRunner {
         {
             self.runtime_handle.spawn_blocking(|| task.run());
         }
-        #[cfg(not(feature = "tokio-runtime"))]
+        // The `tokio-runtime` feature takes precedence if both are enabled.
+        #[cfg(all(feature = "async-std-runtime", not(feature = "tokio-runtime")))]
+        {
+            async_std::task::spawn_blocking(|| task.run());
+        }
+        #[cfg(not(any(feature = "tokio-runtime", feature = "async-std-runtime")))]
         {
             task.run();
         }
@@ -0,0 +1,281 @@
+//!
+//! Cooperative scheduling budget of the lightweight processes
+//!
+//! Every time a worker runs a process, the process is given a budget of
+//! polls. Futures which might stay ready for a long time (like a mailbox
+//! receiving an endless stream of messages) consume a unit of this budget
+//! with [`consume`] and yield back to the worker once it is exhausted,
+//! giving a chance to the other processes of the same worker to run.
+//!
+//! The budget can be configured with the env var `BASTION_POLL_BUDGET` (a
+//! positive number, the default budget being used otherwise) or with
+//! [`set_poll_budget`] before spawning processes. It is multiplied by
+//! the weight of the process (see [`ProcStack::with_weight`]), so that
+//! processes with a greater weight get proportionally more polls when they
+//! share their worker with others.
+//!
+//! [`ProcStack::with_weight`]: lightproc::proc_stack::ProcStack::with_weight
+
+use once_cell::sync::Lazy;
+use std::cell::Cell;
+use std::env;
+use std::ffi::OsStr;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use tracing::warn;
+
+/// If the poll budget isn't configured this is the default value.
+const DEFAULT_POLL_BUDGET: usize = 128;
+
+static POLL_BUDGET: Lazy<AtomicUsize> = Lazy::new(|| {
+    let budget = env::var_os("BASTION_POLL_BUDGET")
+        .map(|budget| parse_poll_budget(&budget))
+        .unwrap_or(DEFAULT_POLL_BUDGET);
+
+    AtomicUsize::new(budget)
+});
+
+// Parses the value of `BASTION_POLL_BUDGET`, falling back to the
+// default budget if it isn't a positive number.
+fn parse_poll_budget(budget: &OsStr) -> usize {
+    match budget.to_str().and_then(|budget| budget.parse().ok()) {
+        Some(budget) if budget > 0 => budget,
+        _ => {
+            warn!(
+                "Invalid BASTION_POLL_BUDGET {:?}, using the default budget of {}.",
+                budget, DEFAULT_POLL_BUDGET
+            );
+            DEFAULT_POLL_BUDGET
+        }
+    }
+}
+
+thread_local! {
+    // The budget left to the process being run by this thread, if any.
+    static BUDGET: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+///
+/// Returns the number of units of budget every process is given each
+/// time it is run.
+pub fn poll_budget() -> usize {
+    POLL_BUDGET.load(Ordering::Relaxed)
+}
+
+///
+/// Sets the number of units of budget every process is given each time
+/// it is run. A budget of `0` disables the cooperative scheduling.
+pub fn set_poll_budget(budget: usize) {
+    POLL_BUDGET.store(budget, Ordering::Relaxed);
+}
+
+/// Runs `f` (which runs a process of the given `weight`) with a fresh
+/// budget, restoring the budget of the enclosing process afterwards.
+pub(crate) fn with_budget<F, R>(weight: u32, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    struct ResetBudget(Option<usize>);
+
+    impl Drop for ResetBudget {
+        fn drop(&mut self) {
+            BUDGET.with(|budget| budget.set(self.0));
+        }
+    }
+
+    let budget = match poll_budget() {
+        0 => None,
+        budget => Some(budget.saturating_mul(weight.max(1) as usize)),
+    };
+
+    let _guard = ResetBudget(BUDGET.with(|cell| cell.replace(budget)));
+    f()
+}
+
+///
+/// Consumes a unit of the current process' budget, returning
+/// `Poll::Pending` (after having woken the process up) if it is
+/// exhausted.
+///
+/// Always returns `Poll::Ready` outside of a process run by a worker.
+pub fn poll_consume(cx: &mut Context) -> Poll<()> {
+    let exhausted = BUDGET.with(|cell| match cell.get() {
+        Some(0) => true,
+        Some(budget) => {
+            cell.set(Some(budget - 1));
+            false
+        }
+        None => false,
+    });
+
+    if exhausted {
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    } else {
+        Poll::Ready(())
+    }
+}
+
+///
+/// Consumes a unit of the current process' budget, yielding back to the
+/// worker if it is exhausted.
+///
+/// # Example
+/// ```rust
+/// use bastion_executor::budget;
+/// use bastion_executor::prelude::*;
+/// use lightproc::prelude::*;
+///
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    start();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    start();
+/// # }
+/// #
+/// # fn start() {
+/// let handle = spawn(
+///     async {
+///         for _ in 0..1_000 {
+///             // Lets the other processes run every once in a while.
+///             budget::consume().await;
+///         }
+///     },
+///     ProcStack::default(),
+/// );
+///
+/// run(handle, ProcStack::default());
+/// # }
+/// ```
+pub fn consume() -> Consume {
+    Consume { consumed: false }
+}
+
+///
+/// Future returned by [`consume`].
+#[derive(Debug)]
+pub struct Consume {
+    consumed: bool,
+}
+
+impl Future for Consume {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // The process is given a new budget when woken up after
+        // having yielded.
+        if self.consumed {
+            return Poll::Ready(());
+        }
+
+        match poll_consume(cx) {
+            Poll::Ready(()) => Poll::Ready(()),
+            Poll::Pending => {
+                self.consumed = true;
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::task::noop_waker;
+    use std::sync::Mutex;
+
+    // The tests changing the poll budget can't run along with the
+    // ones reading it.
+    static LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+    fn consume_all(cx: &mut Context) -> usize {
+        let mut consumed = 0;
+        while poll_consume(cx).is_ready() {
+            consumed += 1;
+        }
+
+        consumed
+    }
+
+    #[test]
+    fn budget_is_given_to_the_processes() {
+        let _lock = LOCK.lock().unwrap();
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        // Outside of a process, the budget is never exhausted.
+        for _ in 0..poll_budget() * 2 {
+            assert!(poll_consume(&mut cx).is_ready());
+        }
+
+        with_budget(1, || assert_eq!(consume_all(&mut cx), poll_budget()));
+        with_budget(3, || assert_eq!(consume_all(&mut cx), poll_budget() * 3));
+    }
+
+    #[test]
+    fn budget_of_the_enclosing_process_is_restored() {
+        let _lock = LOCK.lock().unwrap();
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        with_budget(1, || {
+            assert!(poll_consume(&mut cx).is_ready());
+            with_budget(1, || consume_all(&mut cx));
+            assert_eq!(consume_all(&mut cx), poll_budget() - 1);
+        });
+        assert!(poll_consume(&mut cx).is_ready());
+    }
+
+    #[test]
+    fn parses_the_poll_budget() {
+        assert_eq!(parse_poll_budget(OsStr::new("256")), 256);
+        assert_eq!(parse_poll_budget(OsStr::new("0")), DEFAULT_POLL_BUDGET);
+        assert_eq!(parse_poll_budget(OsStr::new("-1")), DEFAULT_POLL_BUDGET);
+        assert_eq!(parse_poll_budget(OsStr::new("many")), DEFAULT_POLL_BUDGET);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ignores_a_non_utf8_poll_budget() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let budget = OsStr::from_bytes(&[0x31, 0xff]);
+        assert_eq!(parse_poll_budget(budget), DEFAULT_POLL_BUDGET);
+    }
+
+    #[test]
+    fn zero_budget_disables_the_cooperative_scheduling() {
+        let _lock = LOCK.lock().unwrap();
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let budget = poll_budget();
+        set_poll_budget(0);
+        with_budget(1, || {
+            for _ in 0..budget * 2 {
+                assert!(poll_consume(&mut cx).is_ready());
+            }
+        });
+        set_poll_budget(budget);
+    }
+
+    #[test]
+    fn consume_yields_once_exhausted() {
+        let _lock = LOCK.lock().unwrap();
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        with_budget(1, || {
+            consume_all(&mut cx);
+            let mut consume = consume();
+            assert!(Pin::new(&mut consume).poll(&mut cx).is_pending());
+            assert!(Pin::new(&mut consume).poll(&mut cx).is_ready());
+        });
+    }
+}
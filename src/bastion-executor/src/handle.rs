@@ -0,0 +1,310 @@
+//!
+//! Handles to the executors lightweight processes can be spawned onto
+//!
+//! By default every process is spawned onto the global pool (see [`pool::spawn`]).
+//! An [`ExecutorHandle`] allows to spawn processes onto a dedicated pool of
+//! threads instead, isolating them from the rest of the workload, or onto
+//! a thread-per-core pool where every worker is pinned to its own core and
+//! owns its own run queue.
+//!
+//! [`pool::spawn`]: crate::pool::spawn
+
+use crate::placement::{self, CoreId};
+use crate::{budget, load_balancer, pool};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use lightproc::lightproc::LightProc;
+use lightproc::proc_stack::ProcStack;
+use lightproc::recoverable_handle::RecoverableHandle;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use tracing::trace;
+
+///
+/// A handle to an executor which can be cloned and shared between
+/// the places that need to spawn processes onto it.
+///
+/// # Example
+/// ```rust
+/// use bastion_executor::prelude::*;
+/// use lightproc::prelude::*;
+///
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    start();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    start();
+/// # }
+/// #
+/// # fn start() {
+/// let executor = ExecutorHandle::dedicated("latency-critical", 2);
+///
+/// let handle = executor.spawn(async { 1 + 1 }, ProcStack::default());
+///
+/// let result = run(handle, ProcStack::default());
+/// assert_eq!(result, Some(2));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ExecutorHandle {
+    kind: ExecutorKind,
+}
+
+#[derive(Clone)]
+enum ExecutorKind {
+    Global,
+    Dedicated(Arc<DedicatedPool>),
+    PerCore(Arc<PerCorePool>),
+}
+
+/// A fixed-size pool of threads that are only running the processes
+/// spawned through the [`ExecutorHandle`]s referencing it.
+struct DedicatedPool {
+    name: String,
+    threads: usize,
+    sender: Sender<LightProc>,
+}
+
+/// A pool of one thread per core, each of them being pinned to its
+/// core and running the processes of its own local run queue.
+struct PerCorePool {
+    name: String,
+    workers: Vec<Sender<LightProc>>,
+    // The worker the next process spawned without a specific
+    // worker will be sent to.
+    next: AtomicUsize,
+}
+
+impl ExecutorHandle {
+    ///
+    /// Returns a handle to the global pool, which is used when no
+    /// other executor is specified.
+    pub fn global() -> Self {
+        ExecutorHandle {
+            kind: ExecutorKind::Global,
+        }
+    }
+
+    ///
+    /// Creates a new pool of `threads` threads which will only run the
+    /// processes spawned onto the returned handle (or its clones).
+    ///
+    /// The threads are named after `name` and stop once the handle, its
+    /// clones and all the processes spawned onto it are dropped.
+    pub fn dedicated(name: impl Into<String>, threads: usize) -> Self {
+        let pool = DedicatedPool::new(name.into(), threads.max(1));
+        ExecutorHandle {
+            kind: ExecutorKind::Dedicated(Arc::new(pool)),
+        }
+    }
+
+    ///
+    /// Creates a new pool of one thread per core, each thread being
+    /// pinned to its core and running its own local run queue.
+    ///
+    /// Processes spawned with [`spawn_on`] always run on the same
+    /// worker, which allows to build shared-nothing, thread-per-core
+    /// architectures; [`spawn`] distributes them in a round-robin
+    /// fashion.
+    ///
+    /// [`spawn_on`]: Self::spawn_on
+    /// [`spawn`]: Self::spawn
+    pub fn thread_per_core(name: impl Into<String>) -> Self {
+        let pool = PerCorePool::new(name.into(), load_balancer::get_cores());
+        ExecutorHandle {
+            kind: ExecutorKind::PerCore(Arc::new(pool)),
+        }
+    }
+
+    ///
+    /// Returns the number of pinned workers if this handle references a
+    /// thread-per-core pool.
+    pub fn pinned_workers(&self) -> Option<usize> {
+        match &self.kind {
+            ExecutorKind::PerCore(pool) => Some(pool.workers.len()),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Returns `true` if this handle references the global pool.
+    pub fn is_global(&self) -> bool {
+        matches!(self.kind, ExecutorKind::Global)
+    }
+
+    ///
+    /// Spawn a process (which contains future + process stack) onto the
+    /// executor referenced by this handle.
+    pub fn spawn<F, T>(&self, future: F, stack: ProcStack) -> RecoverableHandle<T>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        match &self.kind {
+            ExecutorKind::Global => pool::spawn(future, stack),
+            ExecutorKind::Dedicated(pool) => pool.spawn(future, stack),
+            ExecutorKind::PerCore(pool) => {
+                let worker = pool.next.fetch_add(1, Ordering::Relaxed);
+                pool.spawn_on(worker, future, stack)
+            }
+        }
+    }
+
+    ///
+    /// Spawn a process onto the `worker`-th pinned worker (modulo the
+    /// number of workers) if this handle references a thread-per-core
+    /// pool, or the same way [`spawn`] would otherwise.
+    ///
+    /// [`spawn`]: Self::spawn
+    pub fn spawn_on<F, T>(&self, worker: usize, future: F, stack: ProcStack) -> RecoverableHandle<T>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        match &self.kind {
+            ExecutorKind::PerCore(pool) => pool.spawn_on(worker, future, stack),
+            _ => self.spawn(future, stack),
+        }
+    }
+}
+
+/// Spawns a thread running the processes received from `receiver`
+/// until every sender (the pools and the processes' schedule
+/// functions) has been dropped.
+fn spawn_worker(name: String, receiver: Receiver<LightProc>, core: Option<CoreId>) {
+    #[cfg(feature = "tokio-runtime")]
+    let runtime_handle = tokio::runtime::Handle::current();
+
+    thread::Builder::new()
+        .name(name)
+        .spawn(move || {
+            #[cfg(feature = "tokio-runtime")]
+            let _guard = runtime_handle.enter();
+
+            if let Some(core) = core {
+                placement::set_for_current(core);
+            }
+
+            for task in receiver.iter() {
+                trace!("worker thread: running task");
+                let weight = task.stack().weight();
+                budget::with_budget(weight, || task.run());
+            }
+            trace!("worker thread: quitting.");
+        })
+        .expect("couldn't spawn worker thread");
+}
+
+/// Spawns a process through `sender`, which is kept alive by the
+/// process' schedule function.
+fn spawn_through<F, T>(
+    sender: &Sender<LightProc>,
+    future: F,
+    stack: ProcStack,
+) -> RecoverableHandle<T>
+where
+    F: Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    let sender = sender.clone();
+    let schedule = move |proc: LightProc| {
+        // The receivers are only dropped once every sender is.
+        sender.send(proc).unwrap();
+    };
+
+    let (task, handle) = LightProc::recoverable(future, schedule, stack);
+    task.schedule();
+    handle
+}
+
+impl DedicatedPool {
+    fn new(name: String, threads: usize) -> Self {
+        let (sender, receiver) = unbounded();
+        for index in 0..threads {
+            let thread_name = format!("bastion-{}-{}", name, index);
+            spawn_worker(thread_name, receiver.clone(), None);
+        }
+
+        DedicatedPool {
+            name,
+            threads,
+            sender,
+        }
+    }
+
+    fn spawn<F, T>(&self, future: F, stack: ProcStack) -> RecoverableHandle<T>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        spawn_through(&self.sender, future, stack)
+    }
+}
+
+impl PerCorePool {
+    fn new(name: String, cores: &[CoreId]) -> Self {
+        // Pinning isn't going to be enabled for single core systems.
+        let pin = cores.len() > 1;
+
+        let mut workers = Vec::with_capacity(cores.len().max(1));
+        for (index, core) in cores.iter().enumerate() {
+            let (sender, receiver) = unbounded();
+            let thread_name = format!("bastion-{}-core-{}", name, core.id);
+            spawn_worker(thread_name, receiver, if pin { Some(*core) } else { None });
+            workers.push(sender);
+
+            trace!("per-core pool {}: spawned worker {}", name, index);
+        }
+
+        if workers.is_empty() {
+            let (sender, receiver) = unbounded();
+            spawn_worker(format!("bastion-{}-core-0", name), receiver, None);
+            workers.push(sender);
+        }
+
+        PerCorePool {
+            name,
+            workers,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    fn spawn_on<F, T>(&self, worker: usize, future: F, stack: ProcStack) -> RecoverableHandle<T>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let sender = &self.workers[worker % self.workers.len()];
+        spawn_through(sender, future, stack)
+    }
+}
+
+impl Default for ExecutorHandle {
+    fn default() -> Self {
+        ExecutorHandle::global()
+    }
+}
+
+impl Debug for ExecutorHandle {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match &self.kind {
+            ExecutorKind::Global => fmt.debug_struct("ExecutorHandle::Global").finish(),
+            ExecutorKind::Dedicated(pool) => fmt
+                .debug_struct("ExecutorHandle::Dedicated")
+                .field("name", &pool.name)
+                .field("threads", &pool.threads)
+                .finish(),
+            ExecutorKind::PerCore(pool) => fmt
+                .debug_struct("ExecutorHandle::PerCore")
+                .field("name", &pool.name)
+                .field("workers", &pool.workers.len())
+                .finish(),
+        }
+    }
+}
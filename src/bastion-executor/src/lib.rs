@@ -19,6 +19,12 @@
 //!
 //! [lightproc]: https://docs.rs/lightproc
 //!
+//! The `tokio-runtime` and `async-std-runtime` features make the processes run within
+//! the context of the respective runtime, so that their timers, IO types and spawning
+//! functions can be used from the futures running on Bastion Executor. If both are
+//! enabled (eg. because two dependencies each enabled one of them), `tokio-runtime`
+//! takes precedence and the processes run within the context of Tokio.
+//!
 
 #![doc(
     html_logo_url = "https://raw.githubusercontent.com/bastion-rs/bastion/master/img/bastion-logo.png"
@@ -30,7 +36,10 @@
 #![warn(missing_debug_implementations)]
 
 pub mod blocking;
+pub mod budget;
+pub mod handle;
 pub mod load_balancer;
+pub mod local;
 pub mod placement;
 pub mod pool;
 pub mod run;
@@ -43,6 +52,11 @@ pub mod worker;
 /// Prelude of Bastion Executor
 pub mod prelude {
     pub use crate::blocking::*;
+    pub use crate::handle::*;
+    pub use crate::local::*;
     pub use crate::pool::*;
     pub use crate::run::*;
+    // Both `blocking` and `pool` have a `spawn_blocking`; the one of
+    // the prelude spawns onto the pool dedicated to blocking tasks.
+    pub use crate::blocking::spawn_blocking;
 }
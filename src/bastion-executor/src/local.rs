@@ -0,0 +1,301 @@
+//!
+//! Single threaded executor for futures which aren't `Send`
+//!
+//! A [`LocalExecutor`] owns a dedicated thread onto which futures are created
+//! and polled, which allows them to hold `Rc`, `RefCell` or FFI handles.
+//! Only the closure creating the future and its output need to be `Send`.
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread;
+use tracing::{trace, warn};
+
+type LocalFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+enum LocalMessage {
+    Spawn {
+        id: usize,
+        create: Box<dyn FnOnce() -> LocalFuture + Send>,
+        abort: Arc<dyn Abort + Send + Sync>,
+    },
+    Poll(usize),
+    Cancel(usize),
+}
+
+///
+/// An executor running futures which aren't `Send` on its own thread.
+///
+/// # Example
+/// ```rust
+/// use bastion_executor::prelude::*;
+/// use lightproc::prelude::*;
+/// use std::rc::Rc;
+///
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    start();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    start();
+/// # }
+/// #
+/// # fn start() {
+/// let executor = LocalExecutor::new("ffi");
+///
+/// let handle = executor.spawn(|| {
+///     let shared = Rc::new(1);
+///     async move { *shared + 1 }
+/// });
+///
+/// let result = run(handle, ProcStack::default());
+/// assert_eq!(result, Some(2));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct LocalExecutor {
+    name: Arc<String>,
+    sender: Arc<Sender<LocalMessage>>,
+    next_id: Arc<AtomicUsize>,
+}
+
+///
+/// A handle to a future spawned onto a [`LocalExecutor`], resolving to
+/// its output or to `None` if it panicked.
+///
+/// Polling the handle also polls the future on the executor's thread,
+/// the same way a process polls the futures it is made of. Dropping
+/// the handle cancels the future.
+pub struct LocalHandle<T> {
+    id: usize,
+    shared: Arc<Shared<T>>,
+    sender: Arc<Sender<LocalMessage>>,
+}
+
+struct Shared<T> {
+    state: Mutex<SharedState<T>>,
+}
+
+struct SharedState<T> {
+    // `Some(None)` if the future panicked.
+    output: Option<Option<T>>,
+    waker: Option<Waker>,
+}
+
+trait Abort {
+    fn abort(&self);
+}
+
+struct LocalWaker {
+    id: usize,
+    // Wakers don't keep the executor alive.
+    sender: Weak<Sender<LocalMessage>>,
+}
+
+impl LocalExecutor {
+    ///
+    /// Spawns the thread of a new executor, named after `name`.
+    ///
+    /// The thread stops once the executor, its clones and all the
+    /// futures spawned onto it are dropped.
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = Arc::new(name.into());
+        let (sender, receiver) = unbounded();
+        let sender = Arc::new(sender);
+
+        #[cfg(feature = "tokio-runtime")]
+        let runtime_handle = tokio::runtime::Handle::current();
+
+        let thread_sender = Arc::downgrade(&sender);
+        thread::Builder::new()
+            .name(format!("bastion-local-{}", name))
+            .spawn(move || {
+                #[cfg(feature = "tokio-runtime")]
+                let _guard = runtime_handle.enter();
+
+                run_local(receiver, thread_sender);
+            })
+            .expect("couldn't spawn local executor thread");
+
+        LocalExecutor {
+            name,
+            sender,
+            next_id: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    ///
+    /// Spawns the future returned by `create` onto this executor's
+    /// thread. `create` is called on the executor's thread.
+    pub fn spawn<C, F>(&self, create: C) -> LocalHandle<F::Output>
+    where
+        C: FnOnce() -> F + Send + 'static,
+        F: Future + 'static,
+        F::Output: Send + 'static,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let shared = Arc::new(Shared {
+            state: Mutex::new(SharedState {
+                output: None,
+                waker: None,
+            }),
+        });
+
+        let completed = shared.clone();
+        let create = Box::new(move || {
+            let future = create();
+            let local: LocalFuture = Box::pin(async move {
+                let output = future.await;
+                completed.complete(Some(output));
+            });
+
+            local
+        });
+
+        let abort = shared.clone();
+        // The receiver is only dropped once every sender is.
+        self.sender
+            .send(LocalMessage::Spawn { id, create, abort })
+            .unwrap();
+
+        LocalHandle {
+            id,
+            shared,
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+/// Runs the futures spawned onto the executor until the executor
+/// and its handles have all been dropped.
+fn run_local(receiver: Receiver<LocalMessage>, sender: Weak<Sender<LocalMessage>>) {
+    let mut tasks: HashMap<usize, (LocalFuture, Arc<dyn Abort + Send + Sync>)> = HashMap::new();
+
+    for msg in receiver.iter() {
+        let id = match msg {
+            LocalMessage::Spawn { id, create, abort } => {
+                match panic::catch_unwind(AssertUnwindSafe(create)) {
+                    Ok(future) => {
+                        tasks.insert(id, (future, abort));
+                    }
+                    Err(_) => {
+                        warn!("local executor: creating a future panicked");
+                        abort.abort();
+                    }
+                }
+
+                id
+            }
+            LocalMessage::Poll(id) => id,
+            LocalMessage::Cancel(id) => {
+                trace!("local executor: cancelling task {}", id);
+                tasks.remove(&id);
+                continue;
+            }
+        };
+
+        if let Some((future, abort)) = tasks.get_mut(&id) {
+            let waker = Waker::from(Arc::new(LocalWaker {
+                id,
+                sender: sender.clone(),
+            }));
+            let mut cx = Context::from_waker(&waker);
+
+            match panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(&mut cx))) {
+                Ok(Poll::Ready(())) => {
+                    tasks.remove(&id);
+                }
+                Ok(Poll::Pending) => (),
+                Err(_) => {
+                    warn!("local executor: task {} panicked", id);
+                    abort.abort();
+                    tasks.remove(&id);
+                }
+            }
+        }
+    }
+
+    trace!("local executor: quitting.");
+}
+
+impl<T> Shared<T> {
+    fn complete(&self, output: Option<T>) {
+        // FIXME: panics
+        let mut state = self.state.lock().unwrap();
+        state.output = Some(output);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T: Send> Abort for Shared<T> {
+    fn abort(&self) {
+        self.complete(None);
+    }
+}
+
+impl Wake for LocalWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        // The executor might have stopped already.
+        if let Some(sender) = self.sender.upgrade() {
+            sender.send(LocalMessage::Poll(self.id)).ok();
+        }
+    }
+}
+
+impl<T> Future for LocalHandle<T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // FIXME: panics
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(output) = state.output.take() {
+            return Poll::Ready(output);
+        }
+
+        state.waker = Some(cx.waker().clone());
+        drop(state);
+
+        self.sender.send(LocalMessage::Poll(self.id)).ok();
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for LocalHandle<T> {
+    fn drop(&mut self) {
+        // FIXME: panics
+        if self.shared.state.lock().unwrap().output.is_none() {
+            self.sender.send(LocalMessage::Cancel(self.id)).ok();
+        }
+    }
+}
+
+impl Debug for LocalExecutor {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("LocalExecutor")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl<T> Debug for LocalHandle<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("LocalHandle")
+            .field("id", &self.id)
+            .finish()
+    }
+}
@@ -7,6 +7,7 @@
 //! [`spawn`]: crate::pool::spawn
 //! [`Worker`]: crate::run_queue::Worker
 
+use crate::budget;
 use crate::thread_manager::{DynamicPoolManager, DynamicRunner};
 use crate::worker;
 use crossbeam_channel::{unbounded, Receiver, Sender};
@@ -125,20 +126,43 @@ pub(crate) fn schedule(t: LightProc) {
 ///
 /// Low watermark value, defines the bare minimum of the pool.
 /// Spawns initial thread set.
-/// Can be configurable with env var `BASTION_BLOCKING_THREADS` at runtime.
+/// Can be configurable with env var `BASTION_BLOCKING_THREADS` at runtime,
+/// or with [`set_pool_threads`] before the pool is used.
 #[inline]
 fn low_watermark() -> &'static u64 {
     lazy_static! {
         static ref LOW_WATERMARK: u64 = {
-            env::var_os("BASTION_BLOCKING_THREADS")
-                .map(|x| x.to_str().unwrap().parse::<u64>().unwrap())
-                .unwrap_or(DEFAULT_LOW_WATERMARK)
+            match POOL_THREADS.get() {
+                Some(threads) => *threads,
+                None => env::var_os("BASTION_BLOCKING_THREADS")
+                    .map(|x| x.to_str().unwrap().parse::<u64>().unwrap())
+                    .unwrap_or(DEFAULT_LOW_WATERMARK),
+            }
         };
     }
 
     &*LOW_WATERMARK
 }
 
+/// The number of threads the pool starts with, if set with
+/// [`set_pool_threads`].
+static POOL_THREADS: OnceCell<u64> = OnceCell::new();
+
+///
+/// Sets the number of threads the pool starts with, instead of the
+/// value of the env var `BASTION_BLOCKING_THREADS`.
+///
+/// This returns `Err(())` if the pool was already used or if the number
+/// of threads was already set.
+#[allow(clippy::result_unit_err)]
+pub fn set_pool_threads(threads: usize) -> Result<(), ()> {
+    if DYNAMIC_POOL_MANAGER.get().is_some() {
+        return Err(());
+    }
+
+    POOL_THREADS.set(threads as u64).map_err(|_| ())
+}
+
 /// If low watermark isn't configured this is the default scaler value.
 /// This value is used for the heuristics of the scaler
 const DEFAULT_LOW_WATERMARK: u64 = 2;
@@ -193,13 +217,20 @@ impl DynamicRunner for AsyncRunner {
 
 impl AsyncRunner {
     fn run(&self, task: LightProc) {
+        let weight = task.stack().weight();
         #[cfg(feature = "tokio-runtime")]
         {
-            self.runtime_handle.spawn_blocking(|| task.run());
+            self.runtime_handle
+                .spawn_blocking(move || budget::with_budget(weight, || task.run()));
+        }
+        // The `tokio-runtime` feature takes precedence if both are enabled.
+        #[cfg(all(feature = "async-std-runtime", not(feature = "tokio-runtime")))]
+        {
+            async_std::task::spawn_blocking(move || budget::with_budget(weight, || task.run()));
         }
-        #[cfg(not(feature = "tokio-runtime"))]
+        #[cfg(not(any(feature = "tokio-runtime", feature = "async-std-runtime")))]
         {
-            task.run();
+            budget::with_budget(weight, || task.run());
         }
     }
 }
@@ -0,0 +1,65 @@
+use bastion_executor::handle::ExecutorHandle;
+use bastion_executor::run::run;
+use lightproc::proc_stack::ProcStack;
+use std::thread;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_handle() {
+        super::run_test()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn test_handle() {
+        super::run_test()
+    }
+}
+
+// Returns the name of the thread `handle` runs its processes on.
+fn thread_name(handle: &ExecutorHandle, worker: Option<usize>) -> Option<String> {
+    let name = async { thread::current().name().map(str::to_string) };
+    let spawned = match worker {
+        Some(worker) => handle.spawn_on(worker, name, ProcStack::default()),
+        None => handle.spawn(name, ProcStack::default()),
+    };
+
+    run(spawned, ProcStack::default()).unwrap()
+}
+
+fn run_test() {
+    let global = ExecutorHandle::default();
+    assert!(global.is_global());
+    assert_eq!(global.pinned_workers(), None);
+    let output = run(
+        global.spawn(async { 42 }, ProcStack::default()),
+        ProcStack::default(),
+    );
+    assert_eq!(output, Some(42));
+
+    let dedicated = ExecutorHandle::dedicated("dedicated", 2);
+    assert!(!dedicated.is_global());
+    assert_eq!(dedicated.pinned_workers(), None);
+    for _ in 0..4 {
+        let name = thread_name(&dedicated, None).unwrap();
+        assert!(name.starts_with("bastion-dedicated-"), "{}", name);
+    }
+    // Every process runs on the dedicated threads, whatever the worker.
+    let name = thread_name(&dedicated, Some(1)).unwrap();
+    assert!(name.starts_with("bastion-dedicated-"), "{}", name);
+
+    let per_core = ExecutorHandle::thread_per_core("per-core");
+    let workers = per_core.pinned_workers().unwrap();
+    assert!(workers >= 1);
+    let names = (0..workers)
+        .map(|worker| thread_name(&per_core, Some(worker)).unwrap())
+        .collect::<Vec<_>>();
+    for name in &names {
+        assert!(name.starts_with("bastion-per-core-core-"), "{}", name);
+    }
+    // The workers are picked modulo their number.
+    assert_eq!(thread_name(&per_core, Some(workers)).unwrap(), names[0]);
+}
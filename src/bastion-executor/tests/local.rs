@@ -0,0 +1,68 @@
+use bastion_executor::local::LocalExecutor;
+use bastion_executor::run::run;
+use futures_timer::Delay;
+use lightproc::proc_stack::ProcStack;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_local() {
+        super::run_test()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn test_local() {
+        super::run_test()
+    }
+}
+
+fn run_test() {
+    let executor = LocalExecutor::new("local");
+
+    // The futures can hold values which aren't `Send` across awaits.
+    let handle = executor.spawn(|| async {
+        let values = Rc::new(RefCell::new(vec![]));
+        for value in 0..3 {
+            Delay::new(Duration::from_millis(1)).await;
+            values.borrow_mut().push(value);
+        }
+
+        let sum: i32 = values.borrow().iter().sum();
+        (thread::current().name().map(str::to_string), sum)
+    });
+    let (name, sum) = run(handle, ProcStack::default()).unwrap();
+    assert_eq!(name.as_deref(), Some("bastion-local-local"));
+    assert_eq!(sum, 3);
+
+    // The futures panicking, or whose creation panicked, don't have
+    // an output.
+    let panicked = executor.spawn(|| async { panic!("The future panicked.") });
+    assert_eq!(run(panicked, ProcStack::default()), None::<()>);
+    let panicked =
+        executor.spawn(|| -> futures::future::Ready<()> { panic!("The creation panicked.") });
+    assert_eq!(run(panicked, ProcStack::default()), None);
+
+    // Dropping the handle cancels the future.
+    let completed = Arc::new(AtomicBool::new(false));
+    let cancelled = completed.clone();
+    let handle = executor.spawn(move || async move {
+        Delay::new(Duration::from_millis(50)).await;
+        cancelled.store(true, Ordering::SeqCst);
+    });
+    drop(handle);
+    thread::sleep(Duration::from_millis(150));
+    assert!(!completed.load(Ordering::SeqCst));
+
+    // The executor keeps running the other futures.
+    let handle = executor.spawn(|| async { 42 });
+    assert_eq!(run(handle, ProcStack::default()), Some(42));
+}
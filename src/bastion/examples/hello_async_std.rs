@@ -0,0 +1,81 @@
+#[cfg(feature = "async-std-runtime")]
+use bastion::prelude::*;
+#[cfg(feature = "async-std-runtime")]
+use std::time::Duration;
+#[cfg(feature = "async-std-runtime")]
+use tracing::{error, warn, Level};
+
+/// `cargo run --features=async-std-runtime --example hello_async_std`
+///
+/// This is the async-std flavored version of the `hello_tokio.rs` example.
+/// If you would like to understand how the rest works,
+/// Have a look at the `hello_world.rs` example instead :)
+#[cfg(feature = "async-std-runtime")]
+#[async_std::main]
+async fn main() {
+    // Initialize tracing logger
+    // so we get nice output on the console.
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(Level::WARN)
+        .finish();
+    tracing::subscriber::set_global_default(subscriber).unwrap();
+
+    Bastion::init();
+    Bastion::start();
+    let workers = Bastion::children(|children| {
+        children.with_exec(|ctx: BastionContext| {
+            async move {
+                warn!("just spawned!");
+                async_std::task::sleep(Duration::from_secs(1)).await;
+                warn!("Ok let's handle a message now.");
+                msg! {
+                    ctx.recv().await?,
+                    msg: &'static str => {
+                        // Printing the incoming msg
+                        error!("just received {}", msg);
+
+                        warn!("sleeping for 2 seconds without using the bastion executor");
+                        async_std::task::sleep(Duration::from_secs(2)).await;
+                        warn!("and done!");
+
+                        // let's wait until an async-std powered future is complete
+                        run!(blocking! {
+                            warn!("let's sleep for 5 seconds within a blocking block");
+                            async_std::task::sleep(Duration::from_secs(5)).await;
+                            warn!("awaited 5 seconds");
+                        });
+                        error!("waited for the blocking! to be complete!");
+
+                        // let's spawn a task onto async-std and move on
+                        async_std::task::spawn(async {
+                            warn!("let's sleep for 10 seconds within an async-std task");
+                            async_std::task::sleep(Duration::from_secs(10)).await;
+                            warn!("the async-std task is complete");
+                        });
+                        error!("not waiting for the async-std task to be complete, moving on!");
+                    };
+                    _: _ => ();
+                }
+                Ok(())
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    workers.elems()[0]
+        .tell_anonymously("hello, world!")
+        .expect("Couldn't send the message.");
+
+    // Let's wait until the blocking! and the task are complete on the child side.
+    async_std::task::sleep(Duration::from_secs(20)).await;
+
+    warn!("we're done, asking bastion to stop!");
+    // We are done, stopping the bastion!
+    Bastion::stop();
+    warn!("bastion stopped!");
+}
+
+#[cfg(not(feature = "async-std-runtime"))]
+fn main() {
+    panic!("this example requires the async-std-runtime feature: `cargo run --features=async-std-runtime --example hello_async_std`")
+}
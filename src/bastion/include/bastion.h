@@ -0,0 +1,169 @@
+#ifndef BASTION_H
+#define BASTION_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The status returned when a call succeeded.
+ */
+#define BASTION_OK 0
+
+/**
+ * The status returned when a call failed.
+ */
+#define BASTION_ERROR -1
+
+/**
+ * The status returned when a question wasn't answered on time.
+ */
+#define BASTION_TIMEOUT -2
+
+/**
+ * A children group spawned with [`bastion_spawn_group`].
+ */
+typedef struct BastionGroup BastionGroup;
+
+/**
+ * The answer to a question asked with [`bastion_ask`], set by the
+ * callback of the element with [`bastion_reply`].
+ */
+typedef struct BastionReply BastionReply;
+
+/**
+ * The callback called by the elements of a group spawned with
+ * [`bastion_spawn_group`] for every message they receive, with the
+ * `user_data` given to [`bastion_spawn_group`] and the bytes of the
+ * message.
+ *
+ * `reply` is null unless the message was asked with [`bastion_ask`],
+ * in which case the callback can answer it with [`bastion_reply`].
+ * The element faults (and is restarted by its supervisor) if the
+ * callback returns anything other than [`BASTION_OK`].
+ */
+typedef int (*BastionHandler)(void *user_data,
+                              const uint8_t *data,
+                              size_t len,
+                              struct BastionReply *reply);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Initializes the system (see [`Bastion::init`]).
+ */
+int bastion_init(void);
+
+/**
+ * Starts the system (see [`Bastion::start`]).
+ */
+int bastion_start(void);
+
+/**
+ * Stops the system and blocks until it stopped (see
+ * [`Bastion::stop`] and [`Bastion::block_until_stopped`]).
+ */
+int bastion_stop(void);
+
+/**
+ * Spawns a children group of `redundancy` elements calling
+ * `handler` with `user_data` for every message they receive.
+ *
+ * This function returns the group, which must be freed with
+ * [`bastion_group_free`], or null if it couldn't be spawned.
+ *
+ * # Safety
+ *
+ * `handler` is called with `user_data` by every element of the
+ * group, concurrently and from the threads of the executor, so both
+ * must be safe to use from any thread, and `user_data` must stay
+ * valid until the group is stopped.
+ */
+struct BastionGroup *bastion_spawn_group(size_t redundancy,
+                                         BastionHandler handler,
+                                         void *user_data);
+
+/**
+ * Sends a copy of the `len` bytes at `data` to the next element of
+ * `group`.
+ *
+ * # Safety
+ *
+ * `group` must have been returned by [`bastion_spawn_group`] and not
+ * freed, and `data` must point to `len` readable bytes.
+ */
+int bastion_tell(const struct BastionGroup *group, const uint8_t *data, size_t len);
+
+/**
+ * Asks a copy of the `len` bytes at `data` to the next element of
+ * `group` and blocks until it answers or `timeout_ms` milliseconds
+ * elapsed.
+ *
+ * If it succeeded, the answer is written to `out_data` and
+ * `out_len`, and must be freed with [`bastion_bytes_free`].
+ *
+ * # Safety
+ *
+ * `group` must have been returned by [`bastion_spawn_group`] and not
+ * freed, `data` must point to `len` readable bytes, and `out_data`
+ * and `out_len` must be writable.
+ */
+int bastion_ask(const struct BastionGroup *group,
+                const uint8_t *data,
+                size_t len,
+                uint64_t timeout_ms,
+                uint8_t **out_data,
+                size_t *out_len);
+
+/**
+ * Answers the question being handled with a copy of the `len` bytes
+ * at `data`, replacing any previous answer.
+ *
+ * # Safety
+ *
+ * `reply` must be the one given to the callback being called, and
+ * `data` must point to `len` readable bytes.
+ */
+int bastion_reply(struct BastionReply *reply, const uint8_t *data, size_t len);
+
+/**
+ * Stops the elements of `group` (see [`ChildrenRef::stop`]).
+ *
+ * # Safety
+ *
+ * `group` must have been returned by [`bastion_spawn_group`] and not
+ * freed.
+ */
+int bastion_group_stop(const struct BastionGroup *group);
+
+/**
+ * Frees `group`, without stopping its elements.
+ *
+ * # Safety
+ *
+ * `group` must have been returned by [`bastion_spawn_group`] and not
+ * freed yet.
+ */
+void bastion_group_free(struct BastionGroup *group);
+
+/**
+ * Frees the answer written by [`bastion_ask`].
+ *
+ * # Safety
+ *
+ * `data` and `len` must have been written by [`bastion_ask`] and not
+ * freed yet.
+ */
+void bastion_bytes_free(uint8_t *data, size_t len);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* BASTION_H */
@@ -0,0 +1,120 @@
+//!
+//! Aliases naming children groups or their elements, which can be
+//! atomically repointed to other targets.
+//!
+//! An alias registered with [`Bastion::alias`] is resolved every time
+//! a message is sent through it (with [`Bastion::resolve`]), so the
+//! code sending messages to a well-known name (eg. `"db-writer"`)
+//! doesn't keep the references to its targets. Registering the alias
+//! again atomically repoints it, which allows to switch the messages
+//! over to a new group (eg. a blue/green deployment) without
+//! restarting their senders.
+//!
+//! [`Bastion::alias`]: crate::Bastion::alias
+//! [`Bastion::resolve`]: crate::Bastion::resolve
+use crate::child_ref::ChildRef;
+use crate::children_ref::ChildrenRef;
+use crate::message::Message;
+use crate::path::BastionPath;
+use fxhash::FxHashMap;
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+#[derive(Debug, Clone)]
+/// The target an alias points to.
+pub enum AliasTarget {
+    /// An element of a children group.
+    Child(ChildRef),
+    /// A children group.
+    Children(ChildrenRef),
+}
+
+#[derive(Debug, Default)]
+/// The aliases registered in the system, along with their targets.
+pub(crate) struct Aliases {
+    targets: Mutex<FxHashMap<String, AliasTarget>>,
+}
+
+impl AliasTarget {
+    /// Sends a message to the target without its sender being able
+    /// to receive an answer, telling it to the element or
+    /// broadcasting it to the elements of the group.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(msg)`
+    /// otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message to send.
+    pub fn tell_anonymously<M: Message>(&self, msg: M) -> Result<(), M> {
+        match self {
+            AliasTarget::Child(child) => child.tell_anonymously(msg),
+            AliasTarget::Children(children) => children.broadcast(msg),
+        }
+    }
+
+    /// Returns the element the alias points to, if it points to one.
+    pub fn as_child(&self) -> Option<&ChildRef> {
+        match self {
+            AliasTarget::Child(child) => Some(child),
+            AliasTarget::Children(_) => None,
+        }
+    }
+
+    /// Returns the group the alias points to, if it points to one.
+    pub fn as_children(&self) -> Option<&ChildrenRef> {
+        match self {
+            AliasTarget::Child(_) => None,
+            AliasTarget::Children(children) => Some(children),
+        }
+    }
+
+    /// Returns the [`BastionPath`] of the target.
+    pub fn path(&self) -> &Arc<BastionPath> {
+        match self {
+            AliasTarget::Child(child) => child.path(),
+            AliasTarget::Children(children) => children.path(),
+        }
+    }
+}
+
+impl From<ChildRef> for AliasTarget {
+    fn from(child: ChildRef) -> Self {
+        AliasTarget::Child(child)
+    }
+}
+
+impl From<ChildrenRef> for AliasTarget {
+    fn from(children: ChildrenRef) -> Self {
+        AliasTarget::Children(children)
+    }
+}
+
+impl Aliases {
+    pub(crate) fn new() -> Self {
+        Aliases::default()
+    }
+
+    /// Points the alias named `name` to `target`, returning its
+    /// previous target if it was already registered.
+    pub(crate) fn set(&self, name: String, target: AliasTarget) -> Option<AliasTarget> {
+        debug!("Aliases: Pointing '{}' to {:?}.", name, target.path());
+        // FIXME: panics?
+        self.targets.lock().unwrap().insert(name, target)
+    }
+
+    /// Returns the target of the alias named `name`, if it is
+    /// registered.
+    pub(crate) fn get(&self, name: &str) -> Option<AliasTarget> {
+        // FIXME: panics?
+        self.targets.lock().unwrap().get(name).cloned()
+    }
+
+    /// Unregisters the alias named `name`, returning its target if it
+    /// was registered.
+    pub(crate) fn remove(&self, name: &str) -> Option<AliasTarget> {
+        debug!("Aliases: Removing '{}'.", name);
+        // FIXME: panics?
+        self.targets.lock().unwrap().remove(name)
+    }
+}
@@ -0,0 +1,357 @@
+//!
+//! Combinators awaiting the answers to questions fanned out to
+//! several elements, with a deadline shared by all of them.
+//!
+//! [`join_answers`] waits for all the answers, [`first_ok`] for the
+//! first successful one and [`quorum`] for a given number of them,
+//! sparing the callers the bookkeeping of the pending answers and of
+//! their timeouts.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use bastion::prelude::*;
+//! # use bastion::answers;
+//! # use std::time::Duration;
+//! #
+//! # #[cfg(feature = "tokio-runtime")]
+//! # #[tokio::main]
+//! # async fn main() {
+//! #    run();
+//! # }
+//! #
+//! # #[cfg(not(feature = "tokio-runtime"))]
+//! # fn main() {
+//! #    run();
+//! # }
+//! #
+//! # fn run() {
+//! # Bastion::init();
+//! #
+//! let replicas = Bastion::children(|children| {
+//!     children
+//!         .with_redundancy(3)
+//!         .with_exec(|ctx: BastionContext| async move {
+//!             loop {
+//!                 msg! { ctx.recv().await?,
+//!                     _key: &'static str =!> {
+//!                         answer!(ctx, "value").ok();
+//!                     };
+//!                     _: _ => ();
+//!                 }
+//!             }
+//!         })
+//! }).expect("Couldn't create the children group.");
+//!
+//! # Bastion::start();
+//! # run!(async move {
+//! let asked = replicas
+//!     .elems()
+//!     .iter()
+//!     .filter_map(|replica| replica.ask_anonymously("key").ok());
+//!
+//! // Waits for two of the three replicas to answer within 1s.
+//! match answers::quorum(asked, 2, Duration::from_secs(1)).await {
+//!     Ok(replies) => {
+//!         // Handle the answers...
+//!     }
+//!     Err(err) => {
+//!         // Not enough replicas answered...
+//!     }
+//! }
+//! # });
+//! #
+//! # Bastion::stop();
+//! # Bastion::block_until_stopped();
+//! # }
+//! ```
+use crate::envelope::SignedMessage;
+use crate::errors::AskError;
+use crate::message::Answer;
+use futures::future::{self, Either, FutureExt};
+use futures::stream::{FuturesUnordered, StreamExt};
+use futures_timer::Delay;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+use tracing::trace;
+
+// The answers which weren't received yet, along with their index.
+type Pending =
+    FuturesUnordered<Pin<Box<dyn Future<Output = (usize, Result<SignedMessage, ()>)> + Send>>>;
+
+// The outcome of waiting for the next pending answer.
+enum Next {
+    Answered(usize, Result<SignedMessage, ()>),
+    Exhausted,
+    Elapsed,
+}
+
+/// Waits for all the `answers` until `timeout` elapses, returning
+/// their outcomes in the same order.
+///
+/// The answers which weren't received on time are returned as
+/// `Err(AskError::Timeout)`, and the ones which will never be (eg.
+/// because the element dropped the question) as
+/// `Err(AskError::Unavailable)`.
+///
+/// # Arguments
+///
+/// * `answers` - The answers to wait for.
+/// * `timeout` - How long the answers are waited for.
+pub async fn join_answers<I>(answers: I, timeout: Duration) -> Vec<Result<SignedMessage, AskError>>
+where
+    I: IntoIterator<Item = Answer>,
+{
+    let mut pending = pending(answers);
+    let mut replies: Vec<_> = (0..pending.len())
+        .map(|_| Err(AskError::Timeout(timeout)))
+        .collect();
+
+    let mut deadline = Delay::new(timeout);
+    loop {
+        match next(&mut pending, &mut deadline).await {
+            Next::Answered(idx, reply) => {
+                replies[idx] = reply.map_err(|_| AskError::Unavailable);
+            }
+            Next::Exhausted => break,
+            Next::Elapsed => {
+                trace!(
+                    "Answers: {} answers weren't received within {:?}.",
+                    pending.len(),
+                    timeout
+                );
+                break;
+            }
+        }
+    }
+
+    replies
+}
+
+/// Waits for the first of the `answers` to be received until
+/// `timeout` elapses, ignoring the questions which won't be
+/// answered.
+///
+/// This method returns the first answer if one was received on time,
+/// `Err(AskError::Unavailable)` if none of them will be, or
+/// `Err(AskError::Timeout)` otherwise.
+///
+/// # Arguments
+///
+/// * `answers` - The answers to wait for.
+/// * `timeout` - How long the answers are waited for.
+pub async fn first_ok<I>(answers: I, timeout: Duration) -> Result<SignedMessage, AskError>
+where
+    I: IntoIterator<Item = Answer>,
+{
+    let mut pending = pending(answers);
+    let mut deadline = Delay::new(timeout);
+    loop {
+        match next(&mut pending, &mut deadline).await {
+            Next::Answered(_, Ok(reply)) => return Ok(reply),
+            Next::Answered(_, Err(())) => continue,
+            Next::Exhausted => return Err(AskError::Unavailable),
+            Next::Elapsed => return Err(AskError::Timeout(timeout)),
+        }
+    }
+}
+
+/// Waits for `n` of the `answers` to be received until `timeout`
+/// elapses, ignoring the questions which won't be answered.
+///
+/// This method returns the first `n` answers, in the order they were
+/// received, if they were received on time, `Err(AskError::Unavailable)`
+/// as soon as too many questions won't be answered for `n` answers to
+/// be received, or `Err(AskError::Timeout)` otherwise.
+///
+/// # Arguments
+///
+/// * `answers` - The answers to wait for.
+/// * `n` - The number of answers to receive.
+/// * `timeout` - How long the answers are waited for.
+pub async fn quorum<I>(
+    answers: I,
+    n: usize,
+    timeout: Duration,
+) -> Result<Vec<SignedMessage>, AskError>
+where
+    I: IntoIterator<Item = Answer>,
+{
+    let mut pending = pending(answers);
+    let mut replies = Vec::with_capacity(n);
+    if pending.len() < n {
+        return Err(AskError::Unavailable);
+    }
+
+    let mut deadline = Delay::new(timeout);
+    while replies.len() < n {
+        match next(&mut pending, &mut deadline).await {
+            Next::Answered(_, Ok(reply)) => replies.push(reply),
+            Next::Answered(_, Err(())) if replies.len() + pending.len() < n => {
+                return Err(AskError::Unavailable);
+            }
+            Next::Answered(_, Err(())) => continue,
+            Next::Exhausted => return Err(AskError::Unavailable),
+            Next::Elapsed => {
+                trace!(
+                    "Answers: {}/{} answers were received within {:?}.",
+                    replies.len(),
+                    n,
+                    timeout
+                );
+                return Err(AskError::Timeout(timeout));
+            }
+        }
+    }
+
+    Ok(replies)
+}
+
+fn pending<I>(answers: I) -> Pending
+where
+    I: IntoIterator<Item = Answer>,
+{
+    answers
+        .into_iter()
+        .enumerate()
+        .map(|(idx, answer)| answer.map(move |reply| (idx, reply)).boxed())
+        .collect()
+}
+
+// Waits for the next of the `pending` answers, unless `deadline`
+// elapses first.
+async fn next(pending: &mut Pending, deadline: &mut Delay) -> Next {
+    if pending.is_empty() {
+        return Next::Exhausted;
+    }
+
+    match future::select(pending.next(), deadline).await {
+        Either::Left((Some((idx, reply)), _)) => Next::Answered(idx, reply),
+        Either::Left((None, _)) => Next::Exhausted,
+        Either::Right(_) => Next::Elapsed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::RefAddr;
+    use crate::message::{AnswerSender, Msg};
+    use crate::path::BastionPath;
+    use futures::channel::mpsc;
+    use futures::executor;
+    use std::sync::Arc;
+
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    // Asks a question, returning the sender answering it and its
+    // answer.
+    fn ask() -> (AnswerSender, Answer) {
+        let (sender, _) = mpsc::unbounded();
+        let sign = RefAddr::new(Arc::new(BastionPath::root()), sender);
+        let (mut msg, answer) = Msg::ask("question", sign);
+        (msg.take_sender().unwrap(), answer)
+    }
+
+    // Returns the answer to a question answered with `reply`.
+    fn answered(reply: usize) -> Answer {
+        let (sender, answer) = ask();
+        sender.reply(reply).unwrap();
+        answer
+    }
+
+    // Returns the answer to a question which won't be answered.
+    fn dropped() -> Answer {
+        ask().1
+    }
+
+    fn content(reply: SignedMessage) -> usize {
+        let (msg, _) = reply.extract();
+        msg.downcast::<usize>().unwrap()
+    }
+
+    #[test]
+    fn join_answers_when_all_are_ok() {
+        let answers = vec![answered(0), answered(1), answered(2)];
+        let replies = executor::block_on(join_answers(answers, TIMEOUT));
+        let replies: Vec<_> = replies
+            .into_iter()
+            .map(|reply| content(reply.unwrap()))
+            .collect();
+        assert_eq!(replies, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn join_answers_when_one_fails() {
+        let (_unanswered, unanswered) = ask();
+        let answers = vec![answered(0), dropped(), unanswered];
+        let mut replies = executor::block_on(join_answers(answers, TIMEOUT)).into_iter();
+        assert_eq!(content(replies.next().unwrap().unwrap()), 0);
+        assert!(matches!(replies.next(), Some(Err(AskError::Unavailable))));
+        assert!(matches!(replies.next(), Some(Err(AskError::Timeout(_)))));
+        assert!(replies.next().is_none());
+    }
+
+    #[test]
+    fn first_ok_when_all_are_ok() {
+        let answers = vec![answered(0), answered(1)];
+        let reply = executor::block_on(first_ok(answers, TIMEOUT)).unwrap();
+        assert!(content(reply) < 2);
+    }
+
+    #[test]
+    fn first_ok_when_one_fails() {
+        let answers = vec![dropped(), answered(1)];
+        let reply = executor::block_on(first_ok(answers, TIMEOUT)).unwrap();
+        assert_eq!(content(reply), 1);
+    }
+
+    #[test]
+    fn first_ok_when_all_fail() {
+        let answers = vec![dropped(), dropped()];
+        let reply = executor::block_on(first_ok(answers, TIMEOUT));
+        assert!(matches!(reply, Err(AskError::Unavailable)));
+
+        let (_unanswered, unanswered) = ask();
+        let reply = executor::block_on(first_ok(vec![dropped(), unanswered], TIMEOUT));
+        assert!(matches!(reply, Err(AskError::Timeout(_))));
+    }
+
+    #[test]
+    fn quorum_when_all_are_ok() {
+        let answers = vec![answered(0), answered(1), answered(2)];
+        let replies = executor::block_on(quorum(answers, 2, TIMEOUT)).unwrap();
+        assert_eq!(replies.len(), 2);
+    }
+
+    #[test]
+    fn quorum_when_one_fails() {
+        let answers = vec![answered(0), dropped(), answered(2)];
+        let replies = executor::block_on(quorum(answers, 2, TIMEOUT)).unwrap();
+        let mut replies: Vec<_> = replies.into_iter().map(content).collect();
+        replies.sort_unstable();
+        assert_eq!(replies, vec![0, 2]);
+    }
+
+    #[test]
+    fn quorum_when_unreachable() {
+        let answers = vec![answered(0), dropped(), dropped()];
+        let replies = executor::block_on(quorum(answers, 2, TIMEOUT));
+        assert!(matches!(replies, Err(AskError::Unavailable)));
+
+        let answers = vec![answered(0), answered(1)];
+        let replies = executor::block_on(quorum(answers, 3, TIMEOUT));
+        assert!(matches!(replies, Err(AskError::Unavailable)));
+    }
+
+    #[test]
+    fn quorum_of_zero() {
+        let (_unanswered, unanswered) = ask();
+        let replies = executor::block_on(quorum(vec![unanswered], 0, TIMEOUT)).unwrap();
+        assert!(replies.is_empty());
+
+        let replies = executor::block_on(quorum(vec![], 0, TIMEOUT)).unwrap();
+        assert!(replies.is_empty());
+    }
+}
@@ -1,18 +1,29 @@
+use crate::alias::AliasTarget;
 use crate::broadcast::{Broadcast, Parent};
 use crate::children::Children;
 use crate::children_ref::ChildrenRef;
-use crate::config::Config;
+use crate::config::{self, Config};
 use crate::context::{BastionContext, BastionId};
 use crate::envelope::Envelope;
+use crate::errors::{ConfigError, StillRunning};
+use crate::inbox::Inbox;
 use crate::message::{BastionMessage, Message};
 use crate::path::BastionPathElement;
+use crate::payload;
+use crate::pool::Pool;
+use crate::registry;
 use crate::supervisor::{Supervisor, SupervisorRef};
 use crate::system::SYSTEM;
+use crate::tree::SupervisorStats;
 
+use bastion_executor::pool;
 use core::future::Future;
-use tracing::{debug, trace};
+use futures::future;
+use lightproc::proc_panic;
+use tracing::{debug, trace, warn};
 
 use std::fmt::{self, Debug, Formatter};
+use std::time::Duration;
 
 distributed_api! {
     use std::sync::Arc;
@@ -251,14 +262,98 @@ impl Bastion {
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `config` is invalid (see
+    /// [`Bastion::try_init_with`]).
     pub fn init_with(config: Config) {
+        if let Err(err) = Bastion::try_init_with(config) {
+            panic!("Bastion: Invalid configuration: {}", err);
+        }
+    }
+
+    /// Initializes the system if it hasn't already been done, using
+    /// the specified [`Config`] once validated.
+    ///
+    /// This method returns `Ok(())` if it succeeded, or a
+    /// [`ConfigError`] describing why `config` can't be used
+    /// otherwise, in which case the system isn't initialized.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration used to initialize the system.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    ///
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// let config = Config::new()
+    ///     .with_executor_threads(0);
+    ///
+    /// assert!(Bastion::try_init_with(config).is_err());
+    ///
+    /// let config = Config::new()
+    ///     .with_mailbox_capacity(1024);
+    ///
+    /// Bastion::try_init_with(config).expect("Couldn't initialize the system.");
+    ///
+    /// // You can now use bastion...
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`ConfigError`]: crate::errors::ConfigError
+    pub fn try_init_with(config: Config) -> Result<(), ConfigError> {
         debug!("Bastion: Initializing with config: {:?}", config);
+        config.validate()?;
+
+        if let Some(threads) = config.executor_threads() {
+            debug!("Bastion: Starting the executor with {} threads.", threads);
+            pool::set_pool_threads(threads).map_err(|()| ConfigError::ExecutorStarted)?;
+        }
+
         if config.backtraces().is_hide() {
             debug!("Bastion: Hiding backtraces.");
             std::panic::set_hook(Box::new(|_| ()));
         }
 
+        // Chained after the hook hiding the backtraces (if any), to
+        // keep the locations of the panics of the children.
+        proc_panic::install_hook();
+
+        if let Some(threshold) = config.offload_threshold() {
+            payload::set_threshold(threshold);
+        }
+
+        let strategy = config.supervision_strategy().cloned();
+        config::set_current(config);
+
         lazy_static::initialize(&SYSTEM);
+
+        if let Some(strategy) = strategy {
+            if SYSTEM.supervisor().strategy(strategy).is_err() {
+                warn!("Bastion: Couldn't set the strategy of the root supervisor.");
+            }
+        }
+
+        Ok(())
     }
 
     /// Creates a new [`Supervisor`], passes it through the specified
@@ -316,6 +411,7 @@ impl Bastion {
         let supervisor = Supervisor::new(bcast);
         let supervisor = init(supervisor);
         debug!("Supervisor({}): Initialized.", supervisor.id());
+        supervisor.register_name();
         let supervisor_ref = supervisor.as_ref();
 
         debug!("Bastion: Deploying Supervisor({}).", supervisor.id());
@@ -327,6 +423,55 @@ impl Bastion {
         Ok(supervisor_ref)
     }
 
+    /// Returns a [`SupervisorRef`] referencing the running supervisor
+    /// named `name` with [`Supervisor::with_name`], if there is one,
+    /// which allows to supervise new children groups (or supervisors)
+    /// with an existing supervisor without passing its reference
+    /// around.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the supervisor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::supervisor(|sp| sp.with_name("workers"))
+    ///     .expect("Couldn't create the supervisor.");
+    ///
+    /// if let Some(workers) = Bastion::find_supervisor("workers") {
+    ///     workers.children(|children| {
+    ///         // ...
+    ///         # children
+    ///     }).expect("Couldn't create the children group.");
+    /// }
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn find_supervisor(name: &str) -> Option<SupervisorRef> {
+        debug!("Bastion: Looking up supervisor named '{}'.", name);
+        SYSTEM.named_supervisors().get(name)
+    }
+
     /// Creates a new [`Children`], passes it through the specified
     /// `init` closure and then sends it to the system's default
     /// supervisor for it to start supervising it.
@@ -391,6 +536,79 @@ impl Bastion {
         SYSTEM.supervisor().children(init)
     }
 
+    /// Creates many [`Children`] at once, passes each of them through
+    /// `defaults` and then through its own closure from `specs`, and
+    /// then sends all of them to the system's default supervisor.
+    ///
+    /// The groups are sent together, so that either all of them or
+    /// none are, which is cheaper than calling [`Bastion::children`]
+    /// for each of them when bootstrapping many groups.
+    ///
+    /// This method returns the [`ChildrenRef`]s referencing the newly
+    /// created children groups, in the order of `specs`, if they were
+    /// created, otherwise returns an `Err(())`.
+    ///
+    /// # Arguments
+    ///
+    /// * `defaults` - The closure configuring every new `Children`
+    ///   before its own closure from `specs` does.
+    /// * `specs` - The closures taking a new `Children` as an
+    ///     argument and returning it once configured.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// let tenants = vec!["acme", "globex", "initech"];
+    /// let groups: Vec<ChildrenRef> = Bastion::children_batch(
+    ///     // Every group uses the same mailbox capacity...
+    ///     |children| children.with_mailbox_capacity(1024),
+    ///     // ...but serves its own tenant.
+    ///     tenants.into_iter().map(|tenant| {
+    ///         move |children: Children| {
+    ///             children
+    ///                 .with_name(tenant)
+    ///                 .with_exec(|ctx: BastionContext| {
+    ///                     async move {
+    ///                         // ...
+    ///                         Ok(())
+    ///                     }
+    ///                 })
+    ///         }
+    ///     }),
+    /// ).expect("Couldn't create the children groups.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    #[allow(clippy::result_unit_err)]
+    pub fn children_batch<D, I, C>(defaults: D, specs: I) -> Result<Vec<ChildrenRef>, ()>
+    where
+        D: Fn(Children) -> Children,
+        I: IntoIterator<Item = C>,
+        C: FnOnce(Children) -> Children,
+    {
+        debug!("Bastion: Creating children groups.");
+        SYSTEM.supervisor().children_batch(defaults, specs)
+    }
+
     /// Creates a new [`Children`] which will have the given closure
     /// as action and then sends it to the system's default supervisor.
     ///
@@ -441,6 +659,68 @@ impl Bastion {
     {
         Bastion::children(|ch| ch.with_redundancy(1).with_exec(action))
     }
+
+    /// Creates a new children group of `size` elements handling
+    /// requests of type `Req` with `handler`, sends it to the system's
+    /// default supervisor, and returns a typed [`Pool`] to submit
+    /// requests to them.
+    ///
+    /// The elements have a bounded mailbox, the requests being
+    /// submitted to the next element which can receive them, and are
+    /// restarted after a delay if they crash.
+    ///
+    /// This method returns the [`Pool`] if the children group was
+    /// created, otherwise returns an `Err(())`.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The number of elements of the pool.
+    /// * `handler` - The closure returning the future which handles
+    ///   a request and resolves to its response.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::pool::Pool;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// let pool: Pool<String, usize> = Bastion::pool(4, |line: String| {
+    ///     async move {
+    ///         line.split_whitespace().count()
+    ///     }
+    /// }).expect("Couldn't create the pool.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    #[allow(clippy::result_unit_err)]
+    pub fn pool<Req, Resp, H, F>(size: usize, handler: H) -> Result<Pool<Req, Resp>, ()>
+    where
+        Req: Message,
+        Resp: Message,
+        H: Fn(Req) -> F + Send + Sync + 'static,
+        F: Future<Output = Resp> + Send + 'static,
+    {
+        debug!("Bastion: Creating pool of {} workers.", size);
+        let children = Bastion::children(|ch| Pool::init(ch, size, handler))?;
+        Ok(Pool::new(children))
+    }
     distributed_api! {
         // FIXME!
         #[allow(missing_docs)]
@@ -449,7 +729,33 @@ impl Bastion {
             I: Fn(Arc<DistributedContext>) -> F + Send + Sync + 'static,
             F: Future<Output = Result<(), ()>> + Send + 'static,
         {
-            cluster_actor(cluster_config, action)
+            cluster_actor(cluster_config, ClusterOptions::new(), action)
+        }
+
+        /// Creates a distributed cluster actor like [`Bastion::distributed`],
+        /// using the given bastion-specific options of the current node.
+        ///
+        /// Those allow to declare the roles of the node, which are announced
+        /// to the other members of the cluster so that they can choose where
+        /// to place their work, and to seal the payloads sent to them with
+        /// a [`PayloadCodec`] (eg. to encrypt and/or sign them end-to-end).
+        ///
+        /// # Arguments
+        ///
+        /// * `cluster_config` - The configuration of the cluster.
+        /// * `options` - The [`ClusterOptions`] of the current node.
+        /// * `action` - The closure returning the future of the cluster actor.
+        #[allow(clippy::result_unit_err)]
+        pub fn distributed_with<I, F>(
+            cluster_config: &'static ArtilleryAPClusterConfig,
+            options: ClusterOptions,
+            action: I,
+        ) -> Result<ChildrenRef, ()>
+        where
+            I: Fn(Arc<DistributedContext>) -> F + Send + Sync + 'static,
+            F: Future<Output = Result<(), ()>> + Send + 'static,
+        {
+            cluster_actor(cluster_config, options, action)
         }
     }
 
@@ -521,6 +827,323 @@ impl Bastion {
             .map_err(|err| err.into_inner().into_msg().unwrap())
     }
 
+    /// Creates a new [`Inbox`] allowing code running outside of
+    /// bastion to send messages to actors and to receive their
+    /// replies, without having to create a children group.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// let mut inbox = Bastion::inbox();
+    ///
+    /// // Give the address of the inbox to an actor...
+    /// let addr = inbox.addr();
+    /// Bastion::children(|children| {
+    ///     children.with_exec(move |ctx: BastionContext| {
+    ///         let addr = addr.clone();
+    ///         async move {
+    ///             ctx.tell(&addr, "A message containing data.").ok();
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    ///
+    /// # Bastion::start();
+    /// # run!(async move {
+    /// // ...and receive its messages.
+    /// let msg = inbox.recv().await.expect("Couldn't receive the message.");
+    /// # });
+    /// #
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Inbox`]: crate::inbox::Inbox
+    pub fn inbox() -> Inbox {
+        debug!("Bastion: Creating an inbox.");
+        Inbox::new()
+    }
+
+    /// Registers an alias named `name` pointing to `target` (a
+    /// [`ChildRef`] or a [`ChildrenRef`]), which can then be
+    /// resolved with [`resolve`].
+    ///
+    /// If the alias was already registered, it is atomically
+    /// repointed to `target` and its previous target is returned,
+    /// which allows to switch the messages sent through it over to a
+    /// new group (eg. a blue/green deployment).
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the alias.
+    /// * `target` - The element or group the alias points to.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// let blue = Bastion::children(|children| children).unwrap();
+    /// Bastion::alias("db-writer", blue.clone());
+    ///
+    /// // The senders resolve the alias every time they send a message...
+    /// if let Some(writer) = Bastion::resolve("db-writer") {
+    ///     writer.tell_anonymously("INSERT ...").ok();
+    /// }
+    ///
+    /// // ...so the messages are switched over to the new group once
+    /// // the alias is repointed.
+    /// let green = Bastion::children(|children| children).unwrap();
+    /// Bastion::alias("db-writer", green);
+    /// blue.stop().ok();
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`ChildRef`]: crate::child_ref::ChildRef
+    /// [`ChildrenRef`]: crate::children_ref::ChildrenRef
+    /// [`resolve`]: Self::resolve
+    pub fn alias(name: impl Into<String>, target: impl Into<AliasTarget>) -> Option<AliasTarget> {
+        SYSTEM.aliases().set(name.into(), target.into())
+    }
+
+    /// Returns the target the alias named `name` currently points
+    /// to, or `None` if it isn't registered.
+    ///
+    /// See [`alias`] for an example.
+    ///
+    /// [`alias`]: Self::alias
+    pub fn resolve(name: &str) -> Option<AliasTarget> {
+        trace!("Bastion: Resolving the '{}' alias.", name);
+        SYSTEM.aliases().get(name)
+    }
+
+    /// Unregisters the alias named `name`, returning the target it
+    /// pointed to, or `None` if it wasn't registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the alias.
+    pub fn unalias(name: &str) -> Option<AliasTarget> {
+        SYSTEM.aliases().remove(name)
+    }
+
+    /// Registers the message type `T`, so that its messages can be
+    /// sent to the other members of a cluster and their contents
+    /// shown by [`SignedMessage::describe`], in the logs of the dead
+    /// letters and by the inspector.
+    ///
+    /// This method returns whether `T` was already registered.
+    ///
+    /// See the [`registry`] module for more information.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// #[derive(Debug, serde::Serialize, serde::Deserialize)]
+    /// struct Deposit {
+    ///     account: u64,
+    ///     amount: u64,
+    /// }
+    ///
+    /// Bastion::register_message_type::<Deposit>();
+    ///
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             let msg: SignedMessage = ctx.recv().await?;
+    ///             if let Some(contents) = msg.describe() {
+    ///                 // Prints `{"account":42,"amount":100}`...
+    ///                 println!("{}", contents);
+    ///             }
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`SignedMessage::describe`]: crate::envelope::SignedMessage::describe
+    /// [`registry`]: crate::registry
+    pub fn register_message_type<T>() -> bool
+    where
+        T: Message + serde::Serialize + serde::de::DeserializeOwned,
+    {
+        registry::register::<T>()
+    }
+
+    /// Sets the handler called when a fault escalates all the way
+    /// up to the system, ie. when one of the top-level supervisors
+    /// faults (eg. because it exceeded its restart intensity),
+    /// replacing the previous one if there was one.
+    ///
+    /// The handler is given the identifier of the faulted supervisor,
+    /// which the system restarts once the handler returns. It is
+    /// called by the system itself, so it shouldn't block (calling
+    /// [`stop`] or [`kill`] is fine, but waiting for the system to
+    /// stop would deadlock).
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - The closure called with the identifier of the
+    ///   faulted supervisor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    ///
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// Bastion::init();
+    ///
+    /// Bastion::on_unhandled_failure(|supervisor| {
+    ///     eprintln!("Supervisor({}) faulted, shutting down.", supervisor);
+    ///     Bastion::stop();
+    /// });
+    ///
+    /// Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`stop`]: Self::stop
+    /// [`kill`]: Self::kill
+    pub fn on_unhandled_failure<F>(handler: F)
+    where
+        F: Fn(&BastionId) + Send + Sync + 'static,
+    {
+        trace!("Bastion: Setting the unhandled failure handler.");
+        SYSTEM.set_failure_handler(handler)
+    }
+
+    /// Returns a snapshot of the whole supervision tree: one
+    /// [`SupervisorStats`] for the system's default supervisor
+    /// (which supervises the groups created with
+    /// [`Bastion::children`]) and one for each of the top-level
+    /// supervisors, with the children groups and supervisors they
+    /// supervise, their number of running elements, restarts and
+    /// last faults.
+    ///
+    /// See [`SupervisorRef::stats`] to get a snapshot of a subtree.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # Bastion::start();
+    /// #
+    /// fn print(supervisor: &SupervisorStats, depth: usize) {
+    ///     println!("{:indent$}{}", "", supervisor.path(), indent = depth * 2);
+    ///     for group in supervisor.groups() {
+    ///         println!(
+    ///             "{:indent$}{} ({} running, {} restarts)",
+    ///             "",
+    ///             group.name(),
+    ///             group.children(),
+    ///             group.restarts(),
+    ///             indent = depth * 2 + 2
+    ///         );
+    ///     }
+    ///     for supervisor in supervisor.supervisors() {
+    ///         print(supervisor, depth + 1);
+    ///     }
+    /// }
+    ///
+    /// for supervisor in Bastion::tree() {
+    ///     print(&supervisor, 0);
+    /// }
+    /// #
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`SupervisorRef::stats`]: crate::supervisor::SupervisorRef::stats
+    pub fn tree() -> Vec<SupervisorStats> {
+        trace!("Bastion: Taking a snapshot of the supervision tree.");
+        SYSTEM.tree()
+    }
+
     /// Sends a message to the system to tell it to start
     /// handling messages and running children.
     ///
@@ -695,6 +1318,100 @@ impl Bastion {
         debug!("Bastion: Blocking until system is stopped.");
         SYSTEM.wait_until_stopped();
     }
+
+    /// Blocks the current thread until the system is stopped
+    /// (either by calling [`Bastion::stop`] or [`Bastion::kill`])
+    /// or until `timeout` elapsed.
+    ///
+    /// This method returns `()` if the system stopped, or
+    /// `Err(StillRunning)` if it was still running once `timeout`
+    /// elapsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum time to wait for the system to stop.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// use bastion::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// Bastion::init();
+    ///
+    /// // Use bastion, spawn children and supervisors...
+    ///
+    /// Bastion::start();
+    /// // Send messages to children and/or do some
+    /// // work...
+    ///
+    /// Bastion::stop();
+    /// if Bastion::block_until_stopped_timeout(Duration::from_secs(5)).is_err() {
+    ///     // Some children refused to stop...
+    ///     Bastion::kill();
+    /// }
+    /// # }
+    /// ```
+    pub fn block_until_stopped_timeout(timeout: Duration) -> Result<(), StillRunning> {
+        debug!(
+            "Bastion: Blocking until system is stopped or {:?} elapsed.",
+            timeout
+        );
+        if SYSTEM.wait_until_stopped_timeout(timeout) {
+            Ok(())
+        } else {
+            Err(StillRunning)
+        }
+    }
+
+    /// Returns a future resolving once the system is stopped
+    /// (either by calling [`Bastion::stop`] or [`Bastion::kill`]).
+    ///
+    /// This is the asynchronous counterpart of
+    /// [`Bastion::block_until_stopped`]; it can be combined with a
+    /// timer to avoid waiting forever.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// use bastion::prelude::*;
+    ///
+    /// Bastion::init();
+    /// Bastion::start();
+    ///
+    /// Bastion::stop();
+    /// run!(Bastion::stopped());
+    /// // The system is now stopped...
+    /// # }
+    /// ```
+    pub async fn stopped() {
+        debug!("Bastion: Waiting until system is stopped.");
+        future::poll_fn(|ctx| SYSTEM.poll_stopped(ctx)).await
+    }
 }
 
 impl Debug for Bastion {
@@ -0,0 +1,314 @@
+//!
+//! Bridges between tokio's `broadcast` and `watch` channels and the
+//! dispatchers, keeping the state shared with the parts of an
+//! application which don't run on bastion in sync.
+//!
+//! [`from_broadcast`] and [`from_watch`] republish the values sent
+//! through a tokio channel to the actors registered to a dispatcher,
+//! while [`to_broadcast`] and [`to_watch`] return the closure of a
+//! children group (to pass to [`Children::with_exec`]) which sends the
+//! messages its elements receive through a tokio channel.
+//!
+//! [`Children::with_exec`]: crate::children::Children::with_exec
+use crate::context::BastionContext;
+use crate::dispatcher::BroadcastTarget;
+use crate::envelope::{RefAddr, SignedMessage};
+use crate::message::{Message, Msg};
+use crate::system::SYSTEM;
+use futures::prelude::*;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::watch;
+use tracing::{debug, trace, warn};
+
+/// The future returned by the closures built by [`to_broadcast`] and
+/// [`to_watch`].
+pub type BridgeFuture = Pin<Box<dyn Future<Output = Result<(), ()>> + Send>>;
+
+/// Broadcasts every value received through `receiver` to `target`,
+/// until every sender of the channel is dropped.
+///
+/// The values the receiver lagged behind on are skipped.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::bridge;
+/// # use tokio::sync::broadcast;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// # Bastion::start();
+/// #
+/// let (sender, receiver) = broadcast::channel::<String>(16);
+///
+/// spawn!(bridge::from_broadcast(
+///     receiver,
+///     BroadcastTarget::Group("config".to_string()),
+/// ));
+///
+/// // The actors registered to the "config" dispatcher receive it.
+/// sender.send("reload".to_string()).ok();
+/// #
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub async fn from_broadcast<M>(mut receiver: broadcast::Receiver<M>, target: BroadcastTarget)
+where
+    M: Message + Clone,
+{
+    debug!("Bridge: Republishing a broadcast channel to {:?}.", target);
+    loop {
+        match receiver.recv().await {
+            Ok(msg) => publish(&target, msg),
+            Err(RecvError::Lagged(skipped)) => {
+                warn!(
+                    "Bridge: Skipped {} messages of a broadcast channel.",
+                    skipped
+                )
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+
+    debug!("Bridge: The broadcast channel to {:?} closed.", target);
+}
+
+/// Broadcasts the current value of `receiver` to `target`, and then
+/// every new value it sees, until the sender of the channel is
+/// dropped.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::bridge;
+/// # use tokio::sync::watch;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// # Bastion::start();
+/// #
+/// let (sender, receiver) = watch::channel(1u64);
+///
+/// spawn!(bridge::from_watch(
+///     receiver,
+///     BroadcastTarget::Group("config".to_string()),
+/// ));
+///
+/// // The actors registered to the "config" dispatcher receive `1`
+/// // and then `2`.
+/// sender.send(2).ok();
+/// #
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub async fn from_watch<M>(mut receiver: watch::Receiver<M>, target: BroadcastTarget)
+where
+    M: Message + Clone,
+{
+    debug!("Bridge: Republishing a watch channel to {:?}.", target);
+    loop {
+        let msg = receiver.borrow().clone();
+        publish(&target, msg);
+
+        if receiver.changed().await.is_err() {
+            break;
+        }
+    }
+
+    debug!("Bridge: The watch channel to {:?} closed.", target);
+}
+
+/// Returns the closure of a children group whose elements send the
+/// messages of type `M` they receive through `sender`.
+///
+/// The messages of other types are dropped.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::bridge;
+/// # use tokio::sync::broadcast;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let (sender, mut receiver) = broadcast::channel::<String>(16);
+///
+/// Bastion::children(|children| {
+///     children
+///         .with_dispatcher(Dispatcher::with_type(DispatcherType::Named("events".to_string())))
+///         .with_exec(bridge::to_broadcast(sender))
+/// }).expect("Couldn't create the children group.");
+///
+/// // `receiver` now receives the messages broadcasted to "events".
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub fn to_broadcast<M>(
+    sender: broadcast::Sender<M>,
+) -> impl Fn(BastionContext) -> BridgeFuture + Send + Sync + 'static
+where
+    M: Message + Clone,
+{
+    move |ctx: BastionContext| -> BridgeFuture {
+        let sender = sender.clone();
+        Box::pin(async move {
+            let mut msgs = ctx.stream();
+            while let Some(msg) = msgs.next().await {
+                if let Some(msg) = extract::<M>(msg) {
+                    // No receiver is listening for now.
+                    if sender.send(msg).is_err() {
+                        trace!("Bridge: No receiver for a broadcast channel.");
+                    }
+                }
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// Returns the closure of a children group whose elements send the
+/// messages of type `M` they receive through `sender`, making them
+/// the new value of the channel.
+///
+/// The messages of other types are dropped.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::bridge;
+/// # use tokio::sync::watch;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let (sender, receiver) = watch::channel(0u64);
+///
+/// Bastion::children(|children| {
+///     children
+///         .with_dispatcher(Dispatcher::with_type(DispatcherType::Named("state".to_string())))
+///         .with_exec(bridge::to_watch(sender))
+/// }).expect("Couldn't create the children group.");
+///
+/// // `receiver` now sees the last value broadcasted to "state".
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub fn to_watch<M>(
+    sender: watch::Sender<M>,
+) -> impl Fn(BastionContext) -> BridgeFuture + Send + Sync + 'static
+where
+    M: Message + Clone,
+{
+    let sender = Arc::new(sender);
+    move |ctx: BastionContext| -> BridgeFuture {
+        let sender = sender.clone();
+        Box::pin(async move {
+            let mut msgs = ctx.stream();
+            while let Some(msg) = msgs.next().await {
+                if let Some(msg) = extract::<M>(msg) {
+                    // No receiver is listening anymore.
+                    if sender.send(msg).is_err() {
+                        trace!("Bridge: No receiver for a watch channel.");
+                    }
+                }
+            }
+
+            Ok(())
+        })
+    }
+}
+
+fn publish<M: Message>(target: &BroadcastTarget, msg: M) {
+    trace!("Bridge: Broadcasting to {:?}: {:?}", target, msg);
+    let msg = Arc::new(SignedMessage::new(
+        Msg::broadcast(msg),
+        RefAddr::dead_letters(),
+    ));
+
+    SYSTEM.dispatcher().broadcast_message(target.clone(), &msg);
+}
+
+// Extracts a message of type `M` whether it was told, asked,
+// broadcasted or delivered by a dispatcher.
+fn extract<M: Message + Clone>(msg: SignedMessage) -> Option<M> {
+    let (msg, _) = msg.extract();
+    let msg = match msg.downcast::<M>() {
+        Ok(msg) => return Some(msg),
+        Err(msg) => msg,
+    };
+
+    if let Some(msg) = msg.downcast_ref::<M>() {
+        return Some(msg.as_ref().clone());
+    }
+
+    match msg.downcast::<Arc<SignedMessage>>() {
+        Ok(signed) => signed
+            .msg
+            .downcast_ref::<M>()
+            .map(|msg| msg.as_ref().clone()),
+        Err(msg) => {
+            debug!("Bridge: Dropping message: {:?}", msg);
+            None
+        }
+    }
+}
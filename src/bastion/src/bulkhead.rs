@@ -0,0 +1,218 @@
+//!
+//! A bulkhead bounding the number of questions asked at once to a
+//! children group.
+//!
+//! A [`Bulkhead`] protects a slow group from being buried by the
+//! questions of callers fanning out to it: at most a given number of
+//! questions wait for an answer at once, the others being queued until
+//! one of them is answered (or times out), or rejected with
+//! [`AskError::Rejected`] once the queue is full.
+//!
+//! [`AskError::Rejected`]: crate::errors::AskError::Rejected
+use crate::children_ref::ChildrenRef;
+use crate::demand::Demand;
+use crate::envelope::SignedMessage;
+use crate::errors::AskError;
+use crate::message::Message;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::debug;
+
+/// The default time given to the elements to answer a question.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+/// A cloneable bulkhead asking questions to the elements of a
+/// children group, in a round-robin fashion, with at most a given
+/// number of them waiting for an answer at once.
+///
+/// Cloning a `Bulkhead` returns a new bulkhead sharing the same
+/// bounds.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::bulkhead::Bulkhead;
+/// # use std::time::Duration;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let database = Bastion::children(|children| {
+///     children
+///         .with_exec(|ctx: BastionContext| async move {
+///             loop {
+///                 msg! { ctx.recv().await?,
+///                     _query: &'static str =!> {
+///                         answer!(ctx, "rows").ok();
+///                     };
+///                     _: _ => ();
+///                 }
+///             }
+///         })
+/// }).expect("Couldn't create the children group.");
+///
+/// // Up to 8 queries at once, 32 more waiting for their turn and
+/// // the others being rejected.
+/// let bulkhead = Bulkhead::new(database, 8)
+///     .with_max_queued(32)
+///     .with_timeout(Duration::from_secs(1));
+///
+/// # Bastion::start();
+/// # run!(async move {
+/// match bulkhead.ask("SELECT 1").await {
+///     Ok(answer) => {
+///         // Handle the answer...
+///     }
+///     Err(AskError::Rejected) => {
+///         // The database is overloaded...
+///     }
+///     Err(err) => {
+///         // The query wasn't answered...
+///     }
+/// }
+/// # });
+/// #
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Bulkhead {
+    children: ChildrenRef,
+    // The index of the next element to ask.
+    next: Arc<AtomicUsize>,
+    // The number of questions which can still be asked
+    // without waiting.
+    permits: Demand,
+    max_concurrent: usize,
+    // The number of questions waiting for a permit.
+    queued: Arc<AtomicUsize>,
+    max_queued: Option<usize>,
+    timeout: Duration,
+}
+
+// Gives its permit back once the question was answered (or
+// timed out), even if the caller was cancelled.
+struct Permit<'a> {
+    permits: &'a Demand,
+}
+
+// Stops counting a question as queued once it got its permit,
+// even if the caller was cancelled.
+struct Queued<'a> {
+    queued: &'a AtomicUsize,
+}
+
+impl Bulkhead {
+    /// Creates a new bulkhead asking questions to the elements of
+    /// `children`, with up to `max_concurrent` of them waiting for an
+    /// answer at once and the others being queued.
+    pub fn new(children: ChildrenRef, max_concurrent: usize) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        let permits = Demand::new();
+        permits.request(max_concurrent);
+
+        Bulkhead {
+            children,
+            next: Arc::new(AtomicUsize::new(0)),
+            permits,
+            max_concurrent,
+            queued: Arc::new(AtomicUsize::new(0)),
+            max_queued: None,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Sets how many questions can be queued waiting for one of the
+    /// others to be answered, the questions going over this bound
+    /// being rejected. A bound of `0` rejects every question which
+    /// can't be asked right away.
+    pub fn with_max_queued(mut self, max_queued: usize) -> Self {
+        self.max_queued = Some(max_queued);
+        self
+    }
+
+    /// Sets how long the elements are given to answer a question,
+    /// `5s` by default.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns the children group the questions are asked to.
+    pub fn children(&self) -> &ChildrenRef {
+        &self.children
+    }
+
+    /// Returns the number of questions waiting for an answer.
+    pub fn in_flight(&self) -> usize {
+        self.max_concurrent - self.permits.available().min(self.max_concurrent)
+    }
+
+    /// Returns the number of questions queued.
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+
+    /// Asks `msg` to the next element of the group once fewer than
+    /// the maximum number of questions wait for an answer, and waits
+    /// for its answer.
+    ///
+    /// This method returns the answer if it succeeded, or an
+    /// [`AskError`] otherwise: [`AskError::Rejected`] if the queue
+    /// was full, or the reason why the question failed.
+    pub async fn ask<M: Message>(&self, msg: M) -> Result<SignedMessage, AskError> {
+        if !self.permits.try_acquire() {
+            let queued = self.queued.fetch_add(1, Ordering::SeqCst);
+            let _queued = Queued {
+                queued: &self.queued,
+            };
+
+            if let Some(max_queued) = self.max_queued {
+                if queued >= max_queued {
+                    debug!(
+                        "Bulkhead: Rejecting a question to Children({}): {} questions queued.",
+                        self.children.id(),
+                        queued
+                    );
+                    return Err(AskError::Rejected);
+                }
+            }
+
+            self.permits.acquire().await;
+        }
+
+        let _permit = Permit {
+            permits: &self.permits,
+        };
+
+        self.children
+            .ask_round_robin(&self.next, msg, Some(self.timeout))
+            .await
+    }
+}
+
+impl<'a> Drop for Permit<'a> {
+    fn drop(&mut self) {
+        self.permits.request(1);
+    }
+}
+
+impl<'a> Drop for Queued<'a> {
+    fn drop(&mut self) {
+        self.queued.fetch_sub(1, Ordering::SeqCst);
+    }
+}
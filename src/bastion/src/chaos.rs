@@ -0,0 +1,440 @@
+//!
+//! Fault injection allowing to verify that supervision strategies
+//! actually recover from failures.
+//!
+//! A [`Chaos`] configuration can be attached to a children group with
+//! [`Children::with_chaos`] to make its elements randomly delay the
+//! delivery of their messages, drop some of them, or panic at random
+//! intervals.
+//!
+//! Tests can also inject faults on demand with [`fault`] and
+//! [`kill_random_child`], which make children fault the same way they
+//! would if their future returned an error, or with [`partition`],
+//! which makes a dispatcher drop the messages broadcasted through it.
+//!
+//! [`Children::with_chaos`]: crate::children::Children::with_chaos
+use crate::child_ref::ChildRef;
+use crate::children_ref::ChildrenRef;
+use crate::dispatcher::DispatcherType;
+use crate::envelope::Envelope;
+use crate::message::BastionMessage;
+use crate::system::SYSTEM;
+use futures_timer::Delay;
+use std::time::Duration;
+use tracing::debug;
+
+#[derive(Debug, Clone, Default)]
+/// The faults injected into the elements of a children group.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::chaos::Chaos;
+/// # use std::time::Duration;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let chaos = Chaos::new()
+///     // Delays every message by up to 100ms...
+///     .with_delay(Duration::from_millis(100))
+///     // ...drops 5% of them...
+///     .with_drop_rate(0.05)
+///     // ...and panics every 10 to 30 seconds.
+///     .with_panic_interval(Duration::from_secs(10), Duration::from_secs(30));
+///
+/// Bastion::children(|children| {
+///     children
+///         .with_chaos(chaos)
+///         .with_exec(|ctx| {
+///             async move {
+///                 // ...
+///                 # Ok(())
+///             }
+///         })
+/// }).expect("Couldn't create the children group.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Chaos {
+    delay: Option<Duration>,
+    drop_rate: f64,
+    panic_interval: Option<(Duration, Duration)>,
+    seed: Option<u64>,
+}
+
+impl Chaos {
+    /// Creates a new configuration which doesn't inject any fault.
+    pub fn new() -> Self {
+        Chaos::default()
+    }
+
+    /// Delays the delivery of every message by a random duration
+    /// up to `max`.
+    pub fn with_delay(mut self, max: Duration) -> Self {
+        self.delay = Some(max);
+        self
+    }
+
+    /// Drops the given ratio of the messages (between `0.0` and
+    /// `1.0`) instead of delivering them.
+    pub fn with_drop_rate(mut self, rate: f64) -> Self {
+        self.drop_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Makes every element panic after a random duration between
+    /// `min` and `max` since it was started.
+    pub fn with_panic_interval(mut self, min: Duration, max: Duration) -> Self {
+        self.panic_interval = Some((min, max.max(min)));
+        self
+    }
+
+    /// Seeds the random number generator of the elements, to make
+    /// the faults reproducible.
+    ///
+    /// The seed is mixed with the index of every element (in the
+    /// order the group launched them, restarts included), so that
+    /// the elements don't all inject the same faults at once.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Returns the configuration of the `index`-th element launched
+    /// by a group, whose seed is mixed with `index`.
+    pub(crate) fn for_element(&self, index: usize) -> Chaos {
+        let seed = self.seed.map(|seed| {
+            XorShift::new(seed ^ (index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)).next_u64()
+        });
+
+        Chaos {
+            seed,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Injects the faults configured by a [`Chaos`] into an element
+/// of a children group.
+pub(crate) struct ChaosMonkey {
+    chaos: Chaos,
+    rng: XorShift,
+    panic_timer: Option<Delay>,
+}
+
+impl ChaosMonkey {
+    pub(crate) fn new(chaos: Chaos) -> Self {
+        let seed = chaos.seed.unwrap_or_else(rand::random);
+        let mut rng = XorShift::new(seed);
+
+        let panic_timer = chaos
+            .panic_interval
+            .map(|(min, max)| Delay::new(rng.duration_between(min, max)));
+
+        ChaosMonkey {
+            chaos,
+            rng,
+            panic_timer,
+        }
+    }
+
+    /// Returns `true` if the message being delivered should be
+    /// dropped.
+    pub(crate) fn should_drop(&mut self) -> bool {
+        self.chaos.drop_rate > 0.0 && self.rng.next_f64() < self.chaos.drop_rate
+    }
+
+    /// Returns how long the delivery of the message being delivered
+    /// should be delayed, if it should.
+    pub(crate) fn delay(&mut self) -> Option<Duration> {
+        let max = self.chaos.delay?;
+        Some(self.rng.duration_between(Duration::from_secs(0), max))
+    }
+
+    /// Returns the timer after which the element should panic, if
+    /// it should.
+    pub(crate) fn panic_timer(&mut self) -> Option<&mut Delay> {
+        self.panic_timer.as_mut()
+    }
+}
+
+/// Makes the given child fault, as if its future returned an error,
+/// which lets its supervisor restart it according to its strategy.
+///
+/// This method returns `()` if it succeeded, or `Err(())` otherwise.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::chaos;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let children_ref = Bastion::children(|children| {
+///     children.with_exec(|ctx| {
+///         async move {
+///             // ...
+///             # Ok(())
+///         }
+///     })
+/// }).expect("Couldn't create the children group.");
+///
+/// # Bastion::start();
+/// for child_ref in children_ref.elems() {
+///     chaos::fault(child_ref).expect("Couldn't fault the child.");
+/// }
+/// #
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+#[allow(clippy::result_unit_err)]
+pub fn fault(child: &ChildRef) -> Result<(), ()> {
+    debug!("Chaos: Faulting Child({}).", child.id());
+    let env = Envelope::from_dead_letters(BastionMessage::fault());
+    child.send(env).map_err(|_| ())
+}
+
+/// Makes a randomly chosen element of the given children group
+/// fault (see [`fault`]), returning a reference to it.
+///
+/// This method returns the [`ChildRef`] of the faulted element if it
+/// succeeded, or `Err(())` otherwise (eg. if the group is empty).
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::chaos;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let children_ref = Bastion::children(|children| {
+///     children
+///         .with_redundancy(4)
+///         .with_exec(|ctx| {
+///             async move {
+///                 // ...
+///                 # Ok(())
+///             }
+///         })
+/// }).expect("Couldn't create the children group.");
+///
+/// # Bastion::start();
+/// let faulted = chaos::kill_random_child(&children_ref)
+///     .expect("Couldn't fault a child.");
+/// #
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`ChildRef`]: crate::child_ref::ChildRef
+#[allow(clippy::result_unit_err)]
+pub fn kill_random_child(children: &ChildrenRef) -> Result<ChildRef, ()> {
+    let elems = children.elems();
+    if elems.is_empty() {
+        return Err(());
+    }
+
+    let mut rng = XorShift::new(rand::random());
+    let child = &elems[(rng.next_u64() % elems.len() as u64) as usize];
+    fault(child)?;
+
+    Ok(child.clone())
+}
+
+/// Makes the dispatcher of the given type drop every message
+/// broadcasted through it, as if its actors were unreachable, until
+/// the returned [`Partition`] is healed or dropped.
+///
+/// This method returns the [`Partition`] if it succeeded, or `Err(())`
+/// if no dispatcher of this type is registered.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::chaos;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let dispatcher = DispatcherType::Named("workers".to_string());
+///
+/// Bastion::children(|children| {
+///     children
+///         .with_dispatcher(Dispatcher::with_type(dispatcher.clone()))
+///         .with_exec(|ctx| {
+///             async move {
+///                 // ...
+///                 # Ok(())
+///             }
+///         })
+/// }).expect("Couldn't create the children group.");
+///
+/// # Bastion::start();
+/// let partition = chaos::partition(dispatcher).expect("Couldn't partition the dispatcher.");
+/// // The messages broadcasted to "workers" are dropped...
+/// partition.heal();
+/// // ...and delivered again.
+/// #
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+#[must_use = "the partition is healed as soon as it is dropped"]
+#[allow(clippy::result_unit_err)]
+pub fn partition(dispatcher: DispatcherType) -> Result<Partition, ()> {
+    let instance = SYSTEM.dispatcher().dispatchers.get(&dispatcher).ok_or(())?;
+    debug!("Chaos: Partitioning the {:?} dispatcher.", dispatcher);
+    instance.set_partitioned(true);
+
+    Ok(Partition { dispatcher })
+}
+
+#[derive(Debug)]
+#[must_use = "the partition is healed as soon as it is dropped"]
+/// A partitioned dispatcher, returned by [`partition`].
+///
+/// The dispatcher delivers the messages broadcasted through it again
+/// once the partition is healed with [`Partition::heal`] or dropped.
+pub struct Partition {
+    dispatcher: DispatcherType,
+}
+
+impl Partition {
+    /// Returns the type of the partitioned dispatcher.
+    pub fn dispatcher(&self) -> &DispatcherType {
+        &self.dispatcher
+    }
+
+    /// Heals the partition, making the dispatcher deliver the
+    /// messages broadcasted through it again.
+    pub fn heal(self) {}
+}
+
+impl Drop for Partition {
+    fn drop(&mut self) {
+        if let Some(instance) = SYSTEM.dispatcher().dispatchers.get(&self.dispatcher) {
+            debug!("Chaos: Healing the {:?} dispatcher.", self.dispatcher);
+            instance.set_partitioned(false);
+        }
+    }
+}
+
+#[derive(Debug)]
+/// A xorshift* random number generator, which is good enough to
+/// inject faults.
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // The state must never be zero.
+        XorShift(seed | 1)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn duration_between(&mut self, min: Duration, max: Duration) -> Duration {
+        min + (max - min).mul_f64(self.next_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drops(chaos: Chaos) -> Vec<bool> {
+        let mut monkey = ChaosMonkey::new(chaos);
+        (0..64).map(|_| monkey.should_drop()).collect()
+    }
+
+    #[test]
+    fn seeds_are_mixed_with_the_element_index() {
+        let chaos = Chaos::new().with_drop_rate(0.5).with_seed(42);
+
+        assert_eq!(chaos.for_element(0).seed, chaos.for_element(0).seed);
+        assert_ne!(chaos.for_element(0).seed, chaos.for_element(1).seed);
+        assert_eq!(drops(chaos.for_element(3)), drops(chaos.for_element(3)));
+        assert_ne!(drops(chaos.for_element(0)), drops(chaos.for_element(1)));
+    }
+
+    #[test]
+    fn unseeded_elements_stay_unseeded() {
+        assert_eq!(Chaos::new().for_element(1).seed, None);
+    }
+
+    #[test]
+    fn delays_are_bounded() {
+        let chaos = Chaos::new()
+            .with_delay(Duration::from_millis(10))
+            .with_seed(7);
+        let mut monkey = ChaosMonkey::new(chaos);
+        for _ in 0..64 {
+            assert!(monkey.delay().unwrap() <= Duration::from_millis(10));
+        }
+    }
+}
@@ -2,30 +2,51 @@
 //! Child is a element of Children group executing user-defined computation
 use crate::broadcast::Broadcast;
 use crate::callbacks::{CallbackType, Callbacks};
+#[cfg(feature = "chaos")]
+use crate::chaos::{Chaos, ChaosMonkey};
 use crate::child_ref::ChildRef;
 use crate::context::{BastionContext, BastionId, ContextState};
 use crate::envelope::Envelope;
+use crate::errors::ExecError;
+#[cfg(feature = "journal")]
+use crate::journal::Recorder;
 use crate::message::BastionMessage;
 #[cfg(feature = "scaling")]
 use crate::resizer::ActorGroupStats;
+use crate::supervisor::FaultReason;
 use crate::system::SYSTEM;
 use anyhow::Result as AnyResult;
 
-use bastion_executor::pool;
+use bastion_executor::budget;
+use bastion_executor::handle::ExecutorHandle;
+use bastion_executor::local::LocalExecutor;
 use futures::pending;
 use futures::poll;
 use futures::prelude::*;
+#[cfg(feature = "chaos")]
+use futures_timer::Delay;
 use lightproc::prelude::*;
 use lightproc::proc_state::EmptyProcState;
+use std::error::Error;
 use std::fmt::{self, Debug, Formatter};
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 use tracing::{debug, error, trace, warn};
 
 pub(crate) struct Init(pub(crate) Box<dyn Fn(BastionContext) -> Exec + Send>);
-pub(crate) struct Exec(pub(crate) Pin<Box<dyn Future<Output = Result<(), ()>> + Send>>);
+pub(crate) struct Exec(pub(crate) Pin<Box<dyn Future<Output = Result<(), FaultReason>> + Send>>);
+
+type PollHook = Arc<dyn Fn(&BastionId) + Send + Sync>;
+
+#[derive(Default, Clone)]
+/// The hooks called around every poll of a children group's
+/// elements.
+pub(crate) struct PollHooks {
+    pub(crate) before_poll: Option<PollHook>,
+    pub(crate) after_poll: Option<PollHook>,
+}
 
 #[derive(Debug)]
 pub(crate) struct Child {
@@ -47,6 +68,18 @@ pub(crate) struct Child {
     // A shortcut for accessing to this actor by others.
     child_ref: ChildRef,
     started: bool,
+    // The hooks called around every poll of this child.
+    poll_hooks: PollHooks,
+    // The scheduling weight of this child's process.
+    scheduling_weight: u32,
+    #[cfg(feature = "journal")]
+    // Records the messages delivered to this child, if its
+    // group is journaled.
+    journal: Option<Recorder>,
+    #[cfg(feature = "chaos")]
+    // Injects faults into this child, if its group is
+    // configured to.
+    chaos: Option<ChaosMonkey>,
 }
 
 impl Init {
@@ -57,7 +90,45 @@ impl Init {
     {
         let init = Box::new(move |ctx: BastionContext| {
             let fut = init(ctx);
-            let exec = Box::pin(fut);
+            let exec = Box::pin(fut.map(|out| out.map_err(|()| FaultReason::Errored)));
+
+            Exec(exec)
+        });
+
+        Init(init)
+    }
+
+    pub(crate) fn new_fallible<C, F, E>(init: C) -> Self
+    where
+        C: Fn(BastionContext) -> F + Send + 'static,
+        F: Future<Output = Result<(), E>> + Send + 'static,
+        E: Error + Send + Sync + 'static,
+    {
+        let init = Box::new(move |ctx: BastionContext| {
+            let fut = init(ctx);
+            let exec = Box::pin(
+                fut.map(|out| out.map_err(|err| FaultReason::Failed(ExecError::new(err)))),
+            );
+
+            Exec(exec)
+        });
+
+        Init(init)
+    }
+
+    pub(crate) fn new_local<C, F>(executor: LocalExecutor, init: C) -> Self
+    where
+        C: Fn(BastionContext) -> F + Send + Sync + 'static,
+        F: Future<Output = Result<(), ()>> + 'static,
+    {
+        let init = Arc::new(init);
+        let init = Box::new(move |ctx: BastionContext| {
+            let init = init.clone();
+            let handle = executor.spawn(move || init(ctx));
+            // A panic on the executor's thread is a fault like any other.
+            let exec = Box::pin(
+                handle.map(|out| out.unwrap_or(Err(())).map_err(|()| FaultReason::Errored)),
+            );
 
             Exec(exec)
         });
@@ -86,9 +157,37 @@ impl Child {
             pre_start_msgs,
             child_ref,
             started,
+            poll_hooks: PollHooks::default(),
+            scheduling_weight: 1,
+            #[cfg(feature = "journal")]
+            journal: None,
+            #[cfg(feature = "chaos")]
+            chaos: None,
         }
     }
 
+    #[cfg(feature = "chaos")]
+    pub(crate) fn with_chaos(mut self, chaos: Option<Chaos>) -> Self {
+        self.chaos = chaos.map(ChaosMonkey::new);
+        self
+    }
+
+    #[cfg(feature = "journal")]
+    pub(crate) fn with_journal(mut self, journal: Option<Recorder>) -> Self {
+        self.journal = journal;
+        self
+    }
+
+    pub(crate) fn with_poll_hooks(mut self, poll_hooks: PollHooks) -> Self {
+        self.poll_hooks = poll_hooks;
+        self
+    }
+
+    pub(crate) fn with_scheduling_weight(mut self, weight: u32) -> Self {
+        self.scheduling_weight = weight;
+        self
+    }
+
     fn stack(&self) -> ProcStack {
         trace!("Child({}): Creating ProcStack.", self.id());
         let id = self.bcast.id().clone();
@@ -101,8 +200,55 @@ impl Child {
         let child_ref_inner = self.child_ref.clone();
 
         // FIXME: with_pid
-        ProcStack::default().with_after_panic(move |_state: &mut EmptyProcState| {
-            warn!("Child({}): Panicked.", id);
+        let stack = ProcStack::default()
+            .with_stats(self.child_ref.stats().clone())
+            .with_weight(self.scheduling_weight);
+
+        // The poll site is tracked to diagnose the children which
+        // don't stop after being killed.
+        let poll_site = self.child_ref.poll_site().clone();
+        let before_poll = self.poll_hooks.before_poll.clone();
+        let before_id = id.clone();
+        let stack = stack.with_before_poll(move |_state: &mut EmptyProcState| {
+            poll_site.enter();
+            if let Some(before_poll) = &before_poll {
+                before_poll(&before_id);
+            }
+        });
+        let poll_site = self.child_ref.poll_site().clone();
+        let after_poll = self.poll_hooks.after_poll.clone();
+        let after_id = id.clone();
+        let stack = stack.with_after_poll(move |_state: &mut EmptyProcState| {
+            if let Some(after_poll) = &after_poll {
+                after_poll(&after_id);
+            }
+            poll_site.exit();
+        });
+
+        // The panic is described to the supervisor along with the
+        // restart request.
+        let panicked = Arc::new(Mutex::new(None));
+        let panic_info = panicked.clone();
+        let stack = stack.with_panic_hook(move |panic: &ProcPanic| {
+            let reason = FaultReason::Panicked {
+                message: panic.message().map(str::to_string),
+                location: panic.location().map(str::to_string),
+            };
+            // FIXME: panics?
+            *panic_info.lock().unwrap() = Some(reason);
+        });
+
+        stack.with_after_panic(move |_state: &mut EmptyProcState| {
+            // FIXME: panics?
+            let reason = panicked
+                .lock()
+                .unwrap()
+                .take()
+                .unwrap_or(FaultReason::Panicked {
+                    message: None,
+                    location: None,
+                });
+            warn!("Child({}): {}.", id, reason);
 
             if let Some(parent) = &parent_inner {
                 let used_dispatchers = parent.dispatchers();
@@ -111,7 +257,7 @@ impl Child {
             }
 
             let id = id.clone();
-            let msg = BastionMessage::restart_required(id, parent.id().clone());
+            let msg = BastionMessage::restart_required(id, parent.id().clone(), reason);
             let env = Envelope::new(msg, path.clone(), sender.clone());
             // TODO: handle errors
             parent.send(env).ok();
@@ -128,7 +274,7 @@ impl Child {
         self.bcast.stopped();
     }
 
-    fn faulted(&mut self) {
+    fn faulted(&mut self, reason: FaultReason) {
         debug!("Child({}): Faulted.", self.id());
         self.remove_from_dispatchers();
 
@@ -136,13 +282,15 @@ impl Child {
         let path = self.bcast.path().clone();
         let sender = self.bcast.sender().clone();
 
-        let msg = BastionMessage::restart_required(self.id().clone(), parent.id().clone());
+        let msg = BastionMessage::restart_required(self.id().clone(), parent.id().clone(), reason);
         let env = Envelope::new(msg, path, sender);
         // TODO: handle errors
         parent.send(env).ok();
     }
 
     async fn handle(&mut self, env: Envelope) -> Result<(), ()> {
+        self.child_ref.last_seen_handle().touch();
+
         match env {
             Envelope {
                 msg: BastionMessage::Start,
@@ -200,6 +348,28 @@ impl Child {
                 sign,
             } => {
                 debug!("Child({}): Received a message: {:?}", self.id(), msg);
+                #[cfg(feature = "chaos")]
+                {
+                    if let Some(chaos) = &mut self.chaos {
+                        if chaos.should_drop() {
+                            warn!("Child({}): Chaos: Dropping message.", self.id());
+                            return Ok(());
+                        }
+
+                        if let Some(delay) = chaos.delay() {
+                            trace!("Child({}): Chaos: Delaying message: {:?}", self.id(), delay);
+                            Delay::new(delay).await;
+                        }
+                    }
+                }
+
+                #[cfg(feature = "journal")]
+                {
+                    if let Some(journal) = &self.journal {
+                        journal.record(self.id(), &msg, &sign);
+                    }
+                }
+
                 self.state.push_message(msg, sign);
             }
             Envelope {
@@ -239,10 +409,36 @@ impl Child {
                 msg: BastionMessage::Faulted { .. },
                 ..
             } => unimplemented!(),
+            // The child was seen by handling it.
             Envelope {
                 msg: BastionMessage::Heartbeat,
                 ..
+            } => {}
+            Envelope {
+                msg: BastionMessage::Reconfigure(_),
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::Upgrade(_),
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::PauseRestarts | BastionMessage::ResumeRestarts,
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::CircuitTimeout { .. },
+                ..
             } => unreachable!(),
+            #[cfg(feature = "chaos")]
+            Envelope {
+                msg: BastionMessage::Fault,
+                ..
+            } => {
+                warn!("Child({}): Chaos: Faulting.", self.id());
+                self.faulted(FaultReason::Errored);
+                return Err(());
+            }
         }
 
         Ok(())
@@ -301,12 +497,20 @@ impl Child {
 
     async fn run(mut self) {
         debug!("Child({}): Launched.", self.id());
-        if let Err(e) = self.register_in_dispatchers() {
-            error!("couldn't add actor to the registry: {}", e);
-            return;
-        };
+        // The elements waiting to signal their readiness are
+        // registered once their group is started instead.
+        if self.state.readiness().is_none() {
+            if let Err(e) = self.register_in_dispatchers() {
+                error!("couldn't add actor to the registry: {}", e);
+                return;
+            };
+        }
 
         loop {
+            // Yields every once in a while when the mailbox is never
+            // empty, to let the other processes of the worker run.
+            budget::consume().await;
+
             #[cfg(feature = "scaling")]
             self.update_stats().await;
 
@@ -362,6 +566,15 @@ impl Child {
                 continue;
             }
 
+            #[cfg(feature = "chaos")]
+            {
+                if let Some(timer) = self.chaos.as_mut().and_then(ChaosMonkey::panic_timer) {
+                    if poll!(timer).is_ready() {
+                        panic!("Child({}): Chaos: Panicking.", self.id());
+                    }
+                }
+            }
+
             match poll!(&mut self.exec) {
                 Poll::Ready(Ok(())) => {
                     debug!(
@@ -370,9 +583,13 @@ impl Child {
                     );
                     return self.stopped();
                 }
-                Poll::Ready(Err(())) => {
-                    warn!("Child({}): The future returned an error.", self.id());
-                    return self.faulted();
+                Poll::Ready(Err(reason)) => {
+                    warn!(
+                        "Child({}): The future returned an error: {}",
+                        self.id(),
+                        reason
+                    );
+                    return self.faulted(reason);
                 }
                 Poll::Pending => (),
             }
@@ -381,9 +598,18 @@ impl Child {
         }
     }
 
-    pub(crate) fn launch(self) -> RecoverableHandle<()> {
+    /// Spawns the child onto `executor`, on the given pinned `worker`
+    /// if the executor is a thread-per-core pool.
+    pub(crate) fn launch(
+        self,
+        executor: &ExecutorHandle,
+        worker: Option<usize>,
+    ) -> RecoverableHandle<()> {
         let stack = self.stack();
-        pool::spawn(self.run(), stack)
+        match worker {
+            Some(worker) => executor.spawn_on(worker, self.run(), stack),
+            None => executor.spawn(self.run(), stack),
+        }
     }
 
     /// Adds the actor into each registry declared in the parent node.
@@ -421,7 +647,7 @@ impl Child {
 }
 
 impl Future for Exec {
-    type Output = Result<(), ()>;
+    type Output = Result<(), FaultReason>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
         Pin::new(&mut self.get_mut().0).poll(ctx)
@@ -440,6 +666,15 @@ impl Debug for Init {
     }
 }
 
+impl Debug for PollHooks {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("PollHooks")
+            .field("before_poll", &self.before_poll.is_some())
+            .field("after_poll", &self.after_poll.is_some())
+            .finish()
+    }
+}
+
 impl Debug for Exec {
     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
         fmt.debug_struct("Exec").finish()
@@ -2,15 +2,30 @@
 //! Allows users to communicate with Child through the mailboxes.
 use crate::broadcast::Sender;
 use crate::context::BastionId;
-use crate::envelope::{Envelope, RefAddr};
+use crate::envelope::{Envelope, RefAddr, SignedMessage};
+use crate::errors::AskError;
+use crate::executor;
+#[cfg(feature = "inspector")]
+use crate::inspector::{Inspection, MailboxProbe, QueuedMessage};
 use crate::message::{Answer, BastionMessage, Message};
 use crate::path::BastionPath;
+use futures::future::{self, Either};
+use futures_timer::Delay;
+use lazy_static::lazy_static;
+use lightproc::proc_stack::ProcStats;
 use std::cmp::{Eq, PartialEq};
 use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::{debug, trace};
 
+lazy_static! {
+    // The instant `LastSeen` timestamps are relative to.
+    static ref EPOCH: Instant = Instant::now();
+}
+
 #[derive(Debug, Clone)]
 /// A "reference" to an element of a children group, allowing to
 /// communicate with it.
@@ -23,6 +38,43 @@ pub struct ChildRef {
     // use `ChildRef::new_internal` to set it to false, for internal use children,
     // such as the heartbeat children for example
     is_public: bool,
+    // The poll accounting of the child, updated by the executor
+    // every time the child is polled.
+    stats: Arc<ProcStats>,
+    // The last time the child handled a message, updated by the
+    // child itself.
+    last_seen: Arc<LastSeen>,
+    // Where the child is being polled, updated by the executor
+    // around every poll and by the child's context state.
+    poll_site: Arc<PollSite>,
+    // The number of messages waiting in the mailbox of the child,
+    // updated by the child's context state.
+    mailbox_depth: Arc<MailboxDepth>,
+    // The messages waiting in the mailbox of the child, updated by
+    // the child's context state.
+    #[cfg(feature = "inspector")]
+    mailbox: Arc<MailboxProbe>,
+}
+
+#[derive(Debug)]
+/// The last time a child handled a message (including the
+/// heartbeats of its children group).
+pub(crate) struct LastSeen(AtomicU64);
+
+#[derive(Debug, Default)]
+/// The number of messages waiting in the mailbox of a child.
+pub(crate) struct MailboxDepth(AtomicUsize);
+
+#[derive(Debug, Default)]
+/// Where a child is being polled: since when its current poll
+/// started (if it is being polled) and the type of the last message
+/// it took out of its mailbox, to diagnose the children which don't
+/// reach an await point anymore.
+pub(crate) struct PollSite {
+    // The time the current poll started at, relative to `EPOCH`, or
+    // `0` if the child isn't being polled.
+    polling_since: AtomicU64,
+    handling: Mutex<Option<&'static str>>,
 }
 
 impl ChildRef {
@@ -38,6 +90,12 @@ impl ChildRef {
             name,
             path,
             is_public: false,
+            stats: Arc::new(ProcStats::default()),
+            last_seen: Arc::new(LastSeen::new()),
+            poll_site: Arc::new(PollSite::default()),
+            mailbox_depth: Arc::new(MailboxDepth::default()),
+            #[cfg(feature = "inspector")]
+            mailbox: Arc::new(MailboxProbe::new()),
         }
     }
 
@@ -53,6 +111,12 @@ impl ChildRef {
             name,
             path,
             is_public: true,
+            stats: Arc::new(ProcStats::default()),
+            last_seen: Arc::new(LastSeen::new()),
+            poll_site: Arc::new(PollSite::default()),
+            mailbox_depth: Arc::new(MailboxDepth::default()),
+            #[cfg(feature = "inspector")]
+            mailbox: Arc::new(MailboxProbe::new()),
         }
     }
 
@@ -313,6 +377,106 @@ impl ChildRef {
         Ok(answer)
     }
 
+    /// Sends a message to the child this `ChildRef` is referencing,
+    /// allowing it to answer, and blocks the current thread until
+    /// the answer is received or `timeout` elapses.
+    ///
+    /// This allows the synchronous code running outside of Bastion
+    /// (eg. legacy code or FFI callbacks) to ask questions to the
+    /// elements, the answer being awaited on the executor. It must
+    /// not be called from the futures of the elements, which should
+    /// use [`ask_anonymously`] instead.
+    ///
+    /// The question is asked with a deadline of `timeout` (see
+    /// [`BastionContext::deadline`]).
+    ///
+    /// This method returns the answer if it succeeded, or
+    /// `Err(AskError)` otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message to send.
+    /// * `timeout` - How long the child is given to answer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # let children_ref =
+    /// # Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             msg! { ctx.recv().await?,
+    ///                 _msg: &'static str =!> {
+    ///                     answer!(ctx, "pong").ok();
+    ///                 };
+    ///                 _: _ => ();
+    ///             }
+    ///
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// # Bastion::start();
+    /// # let child_ref = &children_ref.elems()[0];
+    /// // From a thread which isn't driven by an executor...
+    /// match child_ref.ask_blocking("ping", Duration::from_secs(1)) {
+    ///     Ok(answer) => println!("Answered: {:?}", answer),
+    ///     Err(err) => println!("Couldn't get an answer: {:?}", err),
+    /// }
+    /// #
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`ask_anonymously`]: Self::ask_anonymously
+    /// [`BastionContext::deadline`]: crate::context::BastionContext::deadline
+    pub fn ask_blocking<M: Message>(
+        &self,
+        msg: M,
+        timeout: Duration,
+    ) -> Result<SignedMessage, AskError> {
+        debug!(
+            "ChildRef({}): Asking message and blocking for {:?}: {:?}",
+            self.id(),
+            timeout,
+            msg
+        );
+        let deadline = Instant::now() + timeout;
+        let (msg, answer) = BastionMessage::ask_with_deadline(msg, self.addr(), Some(deadline));
+        let env = Envelope::from_dead_letters(msg);
+        // FIXME: panics?
+        self.send(env).map_err(|_| AskError::Unavailable)?;
+
+        // The answer is awaited on the executor while the current
+        // thread only waits for its outcome.
+        let waiting = executor::spawn(async move {
+            match future::select(answer, Delay::new(timeout)).await {
+                Either::Left((Ok(reply), _)) => Ok(reply),
+                Either::Left((Err(()), _)) => Err(AskError::Unavailable),
+                Either::Right(_) => Err(AskError::Timeout(timeout)),
+            }
+        });
+
+        executor::run(waiting).unwrap_or(Err(AskError::Unavailable))
+    }
+
     /// Sends a message to the child this `ChildRef` is referencing
     /// to tell it to stop its execution.
     ///
@@ -413,6 +577,291 @@ impl ChildRef {
         self.send(env).map_err(|_| ())
     }
 
+    /// Returns the number of times the child this `ChildRef` is
+    /// referencing has been polled by the executor.
+    ///
+    /// Along with [`poll_time`], this allows to find out which
+    /// children are using the most CPU time.
+    ///
+    /// Note that the poll accounting is reset when the child is
+    /// restarted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// let mut elems = children_ref.elems().to_vec();
+    /// // Sorts the elements from the most to the least CPU intensive...
+    /// elems.sort_by_key(|elem| std::cmp::Reverse(elem.poll_time()));
+    ///
+    /// for elem in elems {
+    ///     println!("{}: {} polls, {:?}", elem.id(), elem.poll_count(), elem.poll_time());
+    /// }
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`poll_time`]: Self::poll_time
+    pub fn poll_count(&self) -> u64 {
+        self.stats.polls()
+    }
+
+    /// Returns the cumulative time the executor spent polling the
+    /// child this `ChildRef` is referencing.
+    ///
+    /// See [`poll_count`] for an example.
+    ///
+    /// [`poll_count`]: Self::poll_count
+    pub fn poll_time(&self) -> Duration {
+        self.stats.poll_time()
+    }
+
+    /// Returns the last time the child this `ChildRef` is
+    /// referencing handled a message.
+    ///
+    /// Children groups send heartbeats to their elements every
+    /// heartbeat tick, so this allows to find out which children
+    /// stopped making progress.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// for elem in children_ref.elems() {
+    ///     if elem.last_seen().elapsed() > Duration::from_secs(300) {
+    ///         println!("{} seems to be stalled.", elem.id());
+    ///     }
+    /// }
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn last_seen(&self) -> Instant {
+        self.last_seen.get()
+    }
+
+    pub(crate) fn last_seen_handle(&self) -> &Arc<LastSeen> {
+        &self.last_seen
+    }
+
+    pub(crate) fn with_last_seen(mut self, last_seen: Arc<LastSeen>) -> Self {
+        self.last_seen = last_seen;
+        self
+    }
+
+    pub(crate) fn poll_site(&self) -> &Arc<PollSite> {
+        &self.poll_site
+    }
+
+    pub(crate) fn with_poll_site(mut self, poll_site: Arc<PollSite>) -> Self {
+        self.poll_site = poll_site;
+        self
+    }
+
+    /// Returns the number of messages waiting in the mailbox of the
+    /// child this `ChildRef` is referencing.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// for child_ref in children_ref.elems() {
+    ///     println!("Child({}): {} messages queued.", child_ref.id(), child_ref.mailbox_len());
+    /// }
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn mailbox_len(&self) -> usize {
+        self.mailbox_depth.get()
+    }
+
+    pub(crate) fn with_mailbox_depth(mut self, mailbox_depth: Arc<MailboxDepth>) -> Self {
+        self.mailbox_depth = mailbox_depth;
+        self
+    }
+
+    /// Returns the type names and the enqueue times of the messages
+    /// waiting in the mailbox of the child this `ChildRef` is
+    /// referencing, from the oldest to the newest, without taking
+    /// them out of the mailbox.
+    ///
+    /// This allows to find out what a child which doesn't handle its
+    /// messages anymore is stuck on.
+    ///
+    /// This method is available only with the `inspector` feature
+    /// flag.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// let children_ref = Bastion::children(|children| {
+    ///     // ...
+    /// # children
+    /// }).expect("Couldn't create the children group.");
+    ///
+    /// for elem in children_ref.elems() {
+    ///     for msg in elem.inspect_mailbox() {
+    ///         if msg.age() > Duration::from_secs(10) {
+    ///             println!("{}: {} waiting for {:?}", elem.id(), msg.type_name(), msg.age());
+    ///         }
+    ///     }
+    /// }
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    #[cfg(feature = "inspector")]
+    pub fn inspect_mailbox(&self) -> Vec<QueuedMessage> {
+        self.mailbox.snapshot()
+    }
+
+    /// Returns the messages waiting in the mailbox of the child this
+    /// `ChildRef` is referencing (see [`inspect_mailbox`]) along with
+    /// its [`poll_count`] and [`poll_time`].
+    ///
+    /// A child whose poll count doesn't increase while messages are
+    /// waiting in its mailbox is stuck on a future which isn't woken
+    /// up, while one whose poll time keeps increasing is busy.
+    ///
+    /// This method is available only with the `inspector` feature
+    /// flag.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// for elem in children_ref.elems() {
+    ///     let inspection = elem.inspect();
+    ///     println!(
+    ///         "{}: {} messages waiting, {} polls, {:?}",
+    ///         elem.id(),
+    ///         inspection.mailbox().len(),
+    ///         inspection.poll_count(),
+    ///         inspection.poll_time(),
+    ///     );
+    /// }
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`inspect_mailbox`]: Self::inspect_mailbox
+    /// [`poll_count`]: Self::poll_count
+    /// [`poll_time`]: Self::poll_time
+    #[cfg(feature = "inspector")]
+    pub fn inspect(&self) -> Inspection {
+        Inspection::new(self.inspect_mailbox(), self.poll_count(), self.poll_time())
+    }
+
+    #[cfg(feature = "inspector")]
+    pub(crate) fn with_mailbox_probe(mut self, mailbox: Arc<MailboxProbe>) -> Self {
+        self.mailbox = mailbox;
+        self
+    }
+
+    pub(crate) fn stats(&self) -> &Arc<ProcStats> {
+        &self.stats
+    }
+
+    pub(crate) fn with_stats(mut self, stats: Arc<ProcStats>) -> Self {
+        self.stats = stats;
+        self
+    }
+
     /// Returns [`RefAddr`] for the child
     pub fn addr(&self) -> RefAddr {
         RefAddr::new(self.path.clone(), self.sender.clone())
@@ -429,6 +878,12 @@ impl ChildRef {
         &self.sender
     }
 
+    /// Returns `true` if the child this `ChildRef` is referencing
+    /// isn't running anymore (its mailbox has been dropped).
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.sender.is_closed()
+    }
+
     /// Returns the [`BastionPath`] of the child
     pub fn path(&self) -> &Arc<BastionPath> {
         &self.path
@@ -440,6 +895,82 @@ impl ChildRef {
     }
 }
 
+impl LastSeen {
+    pub(crate) fn new() -> Self {
+        let last_seen = LastSeen(AtomicU64::new(0));
+        last_seen.touch();
+        last_seen
+    }
+
+    /// Sets the last time the child was seen to now.
+    pub(crate) fn touch(&self) {
+        let elapsed = EPOCH.elapsed().as_nanos() as u64;
+        self.0.store(elapsed, Ordering::Relaxed);
+    }
+
+    pub(crate) fn get(&self) -> Instant {
+        *EPOCH + Duration::from_nanos(self.0.load(Ordering::Relaxed))
+    }
+}
+
+impl MailboxDepth {
+    pub(crate) fn set(&self, len: usize) {
+        self.0.store(len, Ordering::Relaxed);
+    }
+
+    pub(crate) fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl PollSite {
+    /// Records that a poll of the child started.
+    pub(crate) fn enter(&self) {
+        // `max(1)` keeps a poll started right at `EPOCH` distinct
+        // from no poll at all.
+        let elapsed = (EPOCH.elapsed().as_nanos() as u64).max(1);
+        self.polling_since.store(elapsed, Ordering::Relaxed);
+    }
+
+    /// Records that the current poll of the child returned.
+    pub(crate) fn exit(&self) {
+        self.polling_since.store(0, Ordering::Relaxed);
+    }
+
+    /// Records that the child took a message of type `type_name` out
+    /// of its mailbox.
+    pub(crate) fn handling(&self, type_name: &'static str) {
+        // FIXME: panics?
+        *self.handling.lock().unwrap() = Some(type_name);
+    }
+
+    /// Returns the instant the current poll of the child started at,
+    /// if it is being polled.
+    pub(crate) fn polling_since(&self) -> Option<Instant> {
+        match self.polling_since.load(Ordering::Relaxed) {
+            0 => None,
+            since => Some(*EPOCH + Duration::from_nanos(since)),
+        }
+    }
+
+    /// Returns a description of where the child is stuck, to be
+    /// logged.
+    pub(crate) fn describe(&self) -> String {
+        let polling = match self.polling_since() {
+            Some(since) => format!("stuck in a poll started {:?} ago", since.elapsed()),
+            None => "not being polled".to_string(),
+        };
+        // FIXME: panics?
+        match *self.handling.lock().unwrap() {
+            Some(type_name) => format!(
+                "{}, last message taken out of its mailbox: {}",
+                polling, type_name
+            ),
+            None => format!("{}, no message taken out of its mailbox", polling),
+        }
+    }
+}
+
 impl PartialEq for ChildRef {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
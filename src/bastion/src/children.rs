@@ -2,20 +2,43 @@
 //! Children are a group of child supervised under a supervisor
 use crate::broadcast::{Broadcast, Parent, Sender};
 use crate::callbacks::{CallbackType, Callbacks};
-use crate::child::{Child, Init};
-use crate::child_ref::ChildRef;
-use crate::children_ref::ChildrenRef;
-use crate::context::{BastionContext, BastionId, ContextState};
-use crate::dispatcher::Dispatcher;
+#[cfg(feature = "chaos")]
+use crate::chaos::Chaos;
+use crate::child::{Child, Init, PollHooks};
+use crate::child_ref::{ChildRef, LastSeen, PollSite};
+use crate::children_ref::{ChildrenRef, Readiness};
+use crate::config;
+use crate::context::{BastionContext, BastionId, ContextState, Replay};
+use crate::demand::Demand;
+use crate::dispatcher::{Dispatcher, DispatcherType};
+use crate::drain::Drain;
 use crate::envelope::Envelope;
-use crate::message::BastionMessage;
+use crate::fairness::FairPolling;
+#[cfg(feature = "journal")]
+use crate::journal::{Journal, Recorder};
+use crate::message::{BastionMessage, Message};
+#[cfg(feature = "metrics")]
+use crate::metrics::{self, GroupMetrics};
+use crate::parking::Parking;
 use crate::path::BastionPathElement;
+#[cfg(feature = "process")]
+use crate::process::Process;
+use crate::quota::{Quota, QuotaState};
 #[cfg(feature = "scaling")]
-use crate::resizer::{ActorGroupStats, OptimalSizeExploringResizer, ScalingRule};
+use crate::resizer::{ActorGroupStats, OptimalSizeExploringResizer, ScalingRule, UpperBound};
+use crate::resource::{Resource, Resources};
+use crate::retry::{Redelivery, RedeliveryQueue, RetryPolicy};
+use crate::sampling::{Sampler, TraceSampling};
+use crate::supervisor::FaultReason;
 use crate::system::SYSTEM;
+use crate::tree::GroupNode;
+use crate::watermark::{WatermarkState, Watermarks};
 use anyhow::Result as AnyResult;
 
+use bastion_executor::handle::ExecutorHandle;
+use bastion_executor::local::LocalExecutor;
 use bastion_executor::pool;
+use futures::future::{self, Either};
 use futures::pending;
 use futures::poll;
 use futures::prelude::*;
@@ -23,14 +46,29 @@ use futures::stream::FuturesOrdered;
 use futures_timer::Delay;
 use fxhash::FxHashMap;
 use lightproc::prelude::*;
+use pin_utils::pin_mut;
+use std::error::Error;
 use std::fmt::Debug;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
-use std::task::Poll;
-use std::time::Duration;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 use tracing::{debug, trace, warn};
 
+/// The default time interval between two heartbeats.
+const DEFAULT_HEARTBEAT_TICK: Duration = Duration::from_secs(60);
+/// The default time the elements are given to stop once killed,
+/// before the ones still running are reported.
+const DEFAULT_KILL_DEADLINE: Duration = Duration::from_secs(5);
+/// How long the old elements of a group being upgraded are given to
+/// drain their mailboxes before being stopped.
+const UPGRADE_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often the mailbox of an old element being drained is checked.
+const UPGRADE_DRAIN_TICK: Duration = Duration::from_millis(10);
+/// How often the work in flight of a group being stopped is checked.
+const STOP_DRAIN_TICK: Duration = Duration::from_millis(10);
+
 #[derive(Debug)]
 /// A children group that will contain a defined number of
 /// elements (set with [`with_redundancy`] or `1` by default)
@@ -106,19 +144,204 @@ pub struct Children {
     started: bool,
     // List of dispatchers attached to each actor in the group.
     dispatchers: Vec<Arc<Box<Dispatcher>>>,
+    // The dispatchers shared by the children groups of the
+    // supervisors enclosing the group, which are removed from the
+    // registry by the supervisors instead of by the group.
+    #[allow(clippy::redundant_allocation)]
+    supervisor_dispatchers: Vec<Arc<Box<Dispatcher>>>,
+    // The names of the dispatcher scopes the group is in, from the
+    // outermost to the innermost one.
+    dispatcher_scope: Vec<String>,
     // The name of children
     name: Option<String>,
+    // The name of the children group if it isn't given one.
+    anonymous_name: String,
     #[cfg(feature = "scaling")]
     // Resizer for dynamic actor group scaling up/down.
     resizer: Box<OptimalSizeExploringResizer>,
     // Defines how often do heartbeat checks. By default checks will
     // be done each 60 seconds.
     hearbeat_tick: Duration,
+    // The helper actor sending the heartbeats, once launched.
+    heartbeat: Option<BastionId>,
+    // The number of heartbeat ticks after which an element which
+    // didn't handle any message is restarted, if any.
+    stalled_ticks: Option<u32>,
+    // Whether the heartbeat helper is launched along with the
+    // elements.
+    heartbeats: bool,
+    // The number of messages the mailbox of every element can
+    // hold, if bounded.
+    mailbox_capacity: Option<usize>,
+    // The watermarks of the mailbox of every element, if any.
+    watermarks: Option<Watermarks>,
+    // The last time each launched element handled a message.
+    last_seen: FxHashMap<BastionId, Arc<LastSeen>>,
+    // Where each launched element is being polled.
+    poll_sites: FxHashMap<BastionId, Arc<PollSite>>,
+    // The state of each launched element, checked to know when
+    // their mailboxes are drained while upgrading the group.
+    states: FxHashMap<BastionId, Arc<Pin<Box<ContextState>>>>,
+    // How long the elements are given to stop once killed before
+    // the ones still running (eg. stuck in blocking code) are
+    // reported.
+    kill_deadline: Duration,
+    // How long the faulted elements wait before being restarted,
+    // if they do.
+    restart_delay: Option<Duration>,
+    // The instants after which the faulted elements waiting for
+    // the restart delay are restarted.
+    delayed_restarts: FxHashMap<BastionId, Instant>,
     // Special kind for actors that not going to be visible for others
     // parts of the cluster, but required for extra behaviour for the
     // Children instance. For example for heartsbeat checks, collecting
     // stats, etc.
     helper_actors: FxHashMap<BastionId, (Sender, RecoverableHandle<()>)>,
+    // The closures returning the futures of the user defined helper
+    // actors, launched along with the heartbeat one.
+    helpers: Vec<Init>,
+    // The executor the group and its elements are spawned onto.
+    // Defaults to the global pool.
+    executor: ExecutorHandle,
+    // The pinned worker each element runs on, when the executor
+    // is a thread-per-core pool.
+    pinned_workers: FxHashMap<BastionId, usize>,
+    // The hooks called around every poll of the group's elements.
+    poll_hooks: PollHooks,
+    // The scheduling weight of the group's elements.
+    scheduling_weight: u32,
+    // The last processed messages the elements replay after
+    // having faulted, if any.
+    replay: Option<Replay>,
+    // How the messages the elements fail to handle are redelivered,
+    // if they are.
+    redelivery: Option<Arc<RedeliveryQueue>>,
+    // The resources owned by the group, shared by its elements.
+    resources: Resources,
+    // The demand the elements advertise, along with the number
+    // of messages each of them can take at a time, if any.
+    demand: Option<(Demand, usize)>,
+    // The usage of the quota of the group, shared by its
+    // elements, if any.
+    quota: Option<Arc<QuotaState>>,
+    // How long the group can go without its elements taking any
+    // message out of their mailboxes before it is stopped, if any.
+    idle_timeout: Option<Duration>,
+    // The last time an element of the group took a message out of
+    // its mailbox.
+    activity: Arc<LastSeen>,
+    // Whether the launch of the elements is deferred until the
+    // group receives its first message.
+    lazy: bool,
+    // The readiness of the elements, if the group waits for them
+    // to signal it before being considered started.
+    readiness: Option<Arc<Readiness>>,
+    // Whether the panics thrown while handling a message with
+    // `BastionContext::handle` are isolated.
+    isolate_panics: bool,
+    // Whether the faulted elements are relaunched by the group
+    // itself instead of by its supervisor.
+    isolate_faults: bool,
+    // The elements whose handles have to be polled, if the group
+    // parks its idle elements.
+    parking: Option<Parking>,
+    // The groups which have to be started before the supervisor
+    // starts this one.
+    start_after: Vec<ChildrenRef>,
+    // Decides which of the messages handled by the elements are
+    // traced, if they are sampled.
+    sampler: Option<Arc<Sampler>>,
+    // How the elements poll their mailboxes and the queues of the
+    // prioritized broadcasts, if they poll them in turn.
+    fair_polling: Option<FairPolling>,
+    // Whether the elements are restarted when they stop or fault,
+    // unless set for an element in `element_restarts`.
+    child_restart: ChildRestart,
+    // Whether the element launched at a given index is restarted,
+    // when it differs from `child_restart`.
+    element_restarts: FxHashMap<usize, ChildRestart>,
+    // Whether each launched element is restarted, when it differs
+    // from `child_restart`.
+    restarts: FxHashMap<BastionId, ChildRestart>,
+    // Whether the restarted elements keep the state of the ones
+    // they replace, if set for the group instead of following
+    // its supervisor.
+    restart_state: Option<RestartState>,
+    // The work in flight of the elements, along with how long it
+    // is given to be done once the group is told to stop, if it
+    // is drained.
+    drain: Option<(Arc<Drain>, Duration)>,
+    // The live counterpart of the group's snapshot in the
+    // supervision tree, once its supervisor deployed it.
+    tree: Option<Arc<GroupNode>>,
+    #[cfg(feature = "journal")]
+    // Records the messages delivered to the elements, if the
+    // group is journaled.
+    journal: Option<Recorder>,
+    #[cfg(feature = "chaos")]
+    // The faults injected into the elements, if any.
+    chaos: Option<Chaos>,
+    #[cfg(feature = "chaos")]
+    // The number of elements launched so far, whose seeds are
+    // mixed with the index they were launched at.
+    chaos_launched: usize,
+    #[cfg(feature = "metrics")]
+    // The states of the elements and the number of restarts,
+    // sampled by the stats collector.
+    metrics: Arc<GroupMetrics>,
+    #[cfg(feature = "metrics")]
+    // How often the stats collector publishes the metrics of
+    // the group, if it has one.
+    stats_collector: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+/// Whether an element of a children group is restarted when it
+/// stops or faults, set with [`Children::with_child_restart`] or
+/// [`Children::with_element_restart`].
+///
+/// The restarts are still done by the group's supervisor, following
+/// its [`RestartStrategy`].
+///
+/// The default is `Transient`.
+///
+/// [`RestartStrategy`]: crate::supervisor::RestartStrategy
+pub enum ChildRestart {
+    /// The element is restarted whenever it stops, even if it
+    /// returned `Ok(())`.
+    Permanent,
+    /// The element is restarted when it faults, and dropped when
+    /// it returns `Ok(())`.
+    #[default]
+    Transient,
+    /// The element is never restarted, and dropped from the group
+    /// when it stops or faults.
+    Temporary,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+/// Whether the restarted elements of a children group keep the state
+/// of the elements they replace, set with
+/// [`Children::with_restart_state`] or, for all the groups of a
+/// supervisor, with [`Supervisor::with_restart_state`].
+///
+/// The state of an element is what its [`BastionContext`] holds
+/// between two messages: the messages waiting in its mailbox, the
+/// last processed messages it replays and its place in the
+/// prioritized queues.
+///
+/// The default is `Restore`.
+///
+/// [`Supervisor::with_restart_state`]: crate::supervisor::Supervisor::with_restart_state
+pub enum RestartState {
+    /// The restarted element keeps the state of the element it
+    /// replaces, so the messages it didn't handle yet aren't lost.
+    #[default]
+    Restore,
+    /// The restarted element starts with a brand new state, and the
+    /// messages waiting in the mailbox of the element it replaces
+    /// are dropped.
+    Fresh,
 }
 
 impl Children {
@@ -131,11 +354,52 @@ impl Children {
         let pre_start_msgs = Vec::new();
         let started = false;
         let dispatchers = Vec::new();
+        let supervisor_dispatchers = Vec::new();
+        let dispatcher_scope = Vec::new();
         let name = None;
         #[cfg(feature = "scaling")]
         let resizer = Box::new(OptimalSizeExploringResizer::default());
-        let hearbeat_tick = Duration::from_secs(60);
+        let config = config::current();
+        let anonymous_name = config.anonymous_naming().generate(bcast.path());
+        let hearbeat_tick = config.heartbeat_tick().unwrap_or(DEFAULT_HEARTBEAT_TICK);
+        let heartbeat = None;
+        let stalled_ticks = config.stalled_ticks();
+        let heartbeats = config.heartbeats();
+        let mailbox_capacity = config.mailbox_capacity();
+        let watermarks = None;
+        let last_seen = FxHashMap::default();
+        let poll_sites = FxHashMap::default();
+        let states = FxHashMap::default();
+        let kill_deadline = DEFAULT_KILL_DEADLINE;
+        let restart_delay = None;
+        let delayed_restarts = FxHashMap::default();
         let helper_actors = FxHashMap::default();
+        let helpers = Vec::new();
+        let executor = ExecutorHandle::global();
+        let pinned_workers = FxHashMap::default();
+        let poll_hooks = PollHooks::default();
+        let scheduling_weight = 1;
+        let replay = None;
+        let redelivery = None;
+        let resources = Resources::default();
+        let demand = None;
+        let quota = None;
+        let idle_timeout = None;
+        let activity = Arc::new(LastSeen::new());
+        let lazy = false;
+        let readiness = None;
+        let isolate_panics = false;
+        let isolate_faults = false;
+        let parking = None;
+        let start_after = Vec::new();
+        let sampler = None;
+        let fair_polling = None;
+        let child_restart = ChildRestart::default();
+        let restart_state = None;
+        let element_restarts = FxHashMap::default();
+        let restarts = FxHashMap::default();
+        let drain = None;
+        let tree = None;
 
         Children {
             bcast,
@@ -146,11 +410,61 @@ impl Children {
             pre_start_msgs,
             started,
             dispatchers,
+            supervisor_dispatchers,
+            dispatcher_scope,
             name,
+            anonymous_name,
             #[cfg(feature = "scaling")]
             resizer,
             hearbeat_tick,
+            heartbeat,
+            stalled_ticks,
+            heartbeats,
+            mailbox_capacity,
+            watermarks,
+            last_seen,
+            poll_sites,
+            states,
+            kill_deadline,
+            restart_delay,
+            delayed_restarts,
             helper_actors,
+            helpers,
+            executor,
+            pinned_workers,
+            poll_hooks,
+            scheduling_weight,
+            replay,
+            redelivery,
+            resources,
+            demand,
+            quota,
+            idle_timeout,
+            activity,
+            lazy,
+            readiness,
+            isolate_panics,
+            isolate_faults,
+            parking,
+            start_after,
+            sampler,
+            fair_polling,
+            child_restart,
+            element_restarts,
+            restarts,
+            restart_state,
+            drain,
+            tree,
+            #[cfg(feature = "journal")]
+            journal: None,
+            #[cfg(feature = "chaos")]
+            chaos: None,
+            #[cfg(feature = "chaos")]
+            chaos_launched: 0,
+            #[cfg(feature = "metrics")]
+            metrics: Arc::new(GroupMetrics::default()),
+            #[cfg(feature = "metrics")]
+            stats_collector: None,
         }
     }
 
@@ -211,7 +525,7 @@ impl Children {
         if let Some(name) = &self.name {
             name.clone()
         } else {
-            "__Anonymous__".into()
+            self.anonymous_name.clone()
         }
     }
 
@@ -227,20 +541,36 @@ impl Children {
         let path = self.bcast.path().clone();
 
         let mut children = Vec::with_capacity(self.launched.len());
-        for (id, (sender, _)) in &self.launched {
+        for (id, (sender, launched)) in &self.launched {
             trace!("Children({}): Creating new ChildRef({}).", self.id(), id);
             // TODO: clone or ref?
-            let child = ChildRef::new(id.clone(), sender.clone(), self.name(), path.clone());
+            let mut child = ChildRef::new(id.clone(), sender.clone(), self.name(), path.clone())
+                .with_stats(launched.stack().stats().clone());
+            if let Some(last_seen) = self.last_seen.get(id) {
+                child = child.with_last_seen(last_seen.clone());
+            }
+            if let Some(poll_site) = self.poll_sites.get(id) {
+                child = child.with_poll_site(poll_site.clone());
+            }
+            if let Some(state) = self.states.get(id) {
+                child = child.with_mailbox_depth(state.mailbox_depth().clone());
+            }
+
             children.push(child);
         }
 
-        let dispatchers = self
-            .dispatchers
-            .iter()
-            .map(|dispatcher| dispatcher.dispatcher_type())
-            .collect();
+        let dispatchers = self.dispatcher_types();
 
         ChildrenRef::new(id, sender, path, children, dispatchers)
+            .with_readiness(self.readiness.clone())
+    }
+
+    fn dispatcher_types(&self) -> Vec<DispatcherType> {
+        self.supervisor_dispatchers
+            .iter()
+            .chain(self.dispatchers.iter())
+            .map(|dispatcher| dispatcher.dispatcher_type())
+            .collect()
     }
 
     /// Sets the name of this children group.
@@ -313,6 +643,274 @@ impl Children {
         self
     }
 
+    /// Sets the closure taking a [`BastionContext`] and returning a
+    /// [`Future`] that every element of this children group will
+    /// execute, like [`with_exec`] does, except that the returned
+    /// future can fail with an error of any type instead of `()`.
+    ///
+    /// The error is given to the group's supervisor (see
+    /// [`FaultReason::Failed`]), which can map it to what to do
+    /// about the fault with [`Supervisor::with_directive_fn`].
+    ///
+    /// # Arguments
+    ///
+    /// * `init` - The closure taking a [`BastionContext`] and
+    ///   returning a [`Future`] that every element of this
+    ///   children group will execute.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::io;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_fallible_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             // Read from a socket...
+    ///             Err(io::Error::from(io::ErrorKind::ConnectionReset))
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`with_exec`]: Self::with_exec
+    /// [`FaultReason::Failed`]: crate::supervisor::FaultReason::Failed
+    /// [`Supervisor::with_directive_fn`]: crate::supervisor::Supervisor::with_directive_fn
+    pub fn with_fallible_exec<I, F, E>(mut self, init: I) -> Self
+    where
+        I: Fn(BastionContext) -> F + Send + 'static,
+        F: Future<Output = Result<(), E>> + Send + 'static,
+        E: Error + Send + Sync + 'static,
+    {
+        trace!("Children({}): Setting fallible exec closure.", self.id());
+        self.init = Init::new_fallible(init);
+        self
+    }
+
+    /// Sets the closure taking a [`BastionContext`] and returning a
+    /// [`Future`] that every element of this children group will
+    /// execute, like [`with_exec`] does, except that the returned
+    /// future doesn't need to be `Send`.
+    ///
+    /// The futures are created and polled on a single thread
+    /// dedicated to this children group, which allows them to hold
+    /// `Rc`s, `RefCell`s or thread-bound FFI handles across `.await`s.
+    /// A panic happening on this thread is handled like any other
+    /// failure of the element.
+    ///
+    /// # Arguments
+    ///
+    /// * `init` - The closure taking a [`BastionContext`] and returning
+    ///     a [`Future`] that will be used by every element of this
+    ///     children group.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::cell::RefCell;
+    /// # use std::rc::Rc;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec_local(|ctx| {
+    ///         async move {
+    ///             // `Rc` and `RefCell` can be held across `.await`s...
+    ///             let received = Rc::new(RefCell::new(0));
+    ///             while ctx.recv().await.is_ok() {
+    ///                 *received.borrow_mut() += 1;
+    ///             }
+    ///
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`with_exec`]: Self::with_exec
+    pub fn with_exec_local<I, F>(mut self, init: I) -> Self
+    where
+        I: Fn(BastionContext) -> F + Send + Sync + 'static,
+        F: Future<Output = Result<(), ()>> + 'static,
+    {
+        trace!("Children({}): Setting local exec closure.", self.id());
+        let executor = LocalExecutor::new(self.name());
+        self.init = Init::new_local(executor, init);
+        self
+    }
+
+    /// Makes every element of this children group run and supervise
+    /// the given external OS process, instead of executing a closure.
+    ///
+    /// The elements pipe the standard output and error of their
+    /// process into `tracing`, stop when it exits successfully and
+    /// fault when it fails (so that they are restarted, along with
+    /// the process, according to the restart strategy of their
+    /// supervisor). The process of an element is killed when the
+    /// element is stopped or killed.
+    ///
+    /// This method is available only with the `process` feature
+    /// flag.
+    ///
+    /// # Arguments
+    ///
+    /// * `process` - The process every element of this children group
+    ///   will run.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::process::Process;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_process(Process::new("redis-server").with_args(&["--port", "6380"]))
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    #[cfg(feature = "process")]
+    pub fn with_process(self, process: Process) -> Self {
+        trace!(
+            "Children({}): Setting process: {:?}",
+            self.id(),
+            process.program()
+        );
+        self.with_exec(move |ctx: BastionContext| {
+            let process = process.clone();
+            async move { process.run(ctx).await }
+        })
+    }
+
+    /// Attaches a helper actor to this children group, whose future
+    /// is returned by `init`.
+    ///
+    /// Helper actors share the lifecycle of the group (they are
+    /// launched, restarted and stopped along with it) but aren't
+    /// part of its elements: they aren't returned by
+    /// [`ChildrenRef::elems`], don't receive the messages sent to
+    /// the group and aren't registered in its dispatchers. This
+    /// makes them suitable for sidecar tasks like scraping metrics
+    /// or warming caches.
+    ///
+    /// # Arguments
+    ///
+    /// * `init` - The closure taking a [`BastionContext`] and
+    ///   returning the [`Future`] that the helper actor will run.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_helper(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // The helper can inspect the elements of the group...
+    ///                 for elem in ctx.parent().elems() {
+    ///                     println!("{}: {} polls", elem.id(), elem.poll_count());
+    ///                 }
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`ChildrenRef::elems`]: crate::children_ref::ChildrenRef::elems
+    /// [`Future`]: std::future::Future
+    pub fn with_helper<I, F>(mut self, init: I) -> Self
+    where
+        I: Fn(BastionContext) -> F + Send + 'static,
+        F: Future<Output = Result<(), ()>> + Send + 'static,
+    {
+        trace!("Children({}): Attaching a helper actor.", self.id());
+        self.helpers.push(Init::new(init));
+        self
+    }
+
     /// Sets the number of elements this children group will
     /// contain. Each element will call the closure passed in
     /// [`with_exec`] and run the returned future until it stops,
@@ -378,6 +976,10 @@ impl Children {
     ///
     /// By default supervised elements aren't added to any of dispatcher.
     ///
+    /// If the group is supervised in a dispatcher scope (see
+    /// [`Supervisor::with_dispatcher_scope`]), the name of a `Named`
+    /// dispatcher is prefixed with the ones of the scopes.
+    ///
     /// # Arguments
     ///
     /// * `dispatcher` - An instance of struct that implements the
@@ -415,11 +1017,44 @@ impl Children {
     /// # }
     /// ```
     /// [`DispatcherHandler`]: crate::dispatcher::DispatcherHandler
+    /// [`Supervisor::with_dispatcher_scope`]: crate::supervisor::Supervisor::with_dispatcher_scope
     pub fn with_dispatcher(mut self, dispatcher: Dispatcher) -> Self {
+        let dispatcher = match dispatcher.dispatcher_type() {
+            DispatcherType::Named(name) if !self.dispatcher_scope.is_empty() => {
+                let dispatcher_type = DispatcherType::scoped(&self.dispatcher_scope, &name);
+                dispatcher.with_dispatcher_type(dispatcher_type)
+            }
+            _ => dispatcher,
+        };
+
         self.dispatchers.push(Arc::new(Box::new(dispatcher)));
         self
     }
 
+    pub(crate) fn in_dispatcher_scope(mut self, dispatcher_scope: Vec<String>) -> Self {
+        self.dispatcher_scope = dispatcher_scope;
+        self
+    }
+
+    pub(crate) fn in_supervisor_dispatchers(
+        mut self,
+        dispatchers: Vec<Arc<Box<Dispatcher>>>,
+    ) -> Self {
+        self.supervisor_dispatchers = dispatchers;
+        self
+    }
+
+    pub(crate) fn in_tree(mut self, tree: Arc<GroupNode>) -> Self {
+        // The elements might have been launched before the group was
+        // deployed.
+        for (id, (_, launched)) in &self.launched {
+            tree.launched(id.clone(), launched.stack().stats().clone());
+        }
+
+        self.tree = Some(tree);
+        self
+    }
+
     #[cfg(feature = "scaling")]
     /// Sets a custom resizer for the Children.
     ///
@@ -530,19 +1165,26 @@ impl Children {
         self
     }
 
-    /// Overrides the default time interval for heartbeat onto
-    /// the user defined.
+    /// Makes every element of this children group keep the `last`
+    /// messages of type `M` it processed, and receive them again
+    /// (before the messages still in its mailbox) when it is
+    /// restarted after a fault.
     ///
+    /// This helps elements that build ephemeral caches or state out
+    /// of the messages they receive to warm up after a crash. Note
+    /// that replayed questions can't be answered again, they are
+    /// replayed as if they were sent with `tell`, and that the
+    /// message the element was processing when it faulted isn't
+    /// replayed, since it might be the one that made it fault.
     ///
     /// # Arguments
     ///
-    /// * `interval` - The value of the [`std::time::Duration`] type
+    /// * `last` - The number of processed messages to replay.
     ///
     /// # Example
     ///
     /// ```rust
     /// # use bastion::prelude::*;
-    /// # use std::time::Duration;
     /// #
     /// # #[cfg(feature = "tokio-runtime")]
     /// # #[tokio::main]
@@ -558,17 +1200,19 @@ impl Children {
     /// # fn run() {
     /// # Bastion::init();
     /// #
+    /// #[derive(Debug, Clone)]
+    /// struct Put(String, u64);
+    ///
     /// Bastion::children(|children| {
-    /// children
-    ///     .with_heartbeat_tick(Duration::from_secs(5))
-    ///     .with_exec(|ctx| {
-    ///         // -- Children group started.
-    ///         async move {
-    ///             // ...
-    ///             # Ok(())
-    ///         }
-    ///         // -- Children group stopped.
-    ///     })
+    ///     children
+    ///         // The last 100 `Put`s will be replayed after a crash...
+    ///         .with_message_replay::<Put>(100)
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
     /// }).expect("Couldn't create the children group.");
     /// #
     /// # Bastion::start();
@@ -576,25 +1220,1994 @@ impl Children {
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
-    pub fn with_heartbeat_tick(mut self, interval: Duration) -> Self {
+    pub fn with_message_replay<M: Message + Clone>(mut self, last: usize) -> Self {
         trace!(
-            "Children({}): Set heartbeat tick to {:?}",
+            "Children({}): Replaying the last {} messages after faults.",
             self.id(),
-            interval
+            last
         );
-        self.hearbeat_tick = interval;
+        self.replay = Some(Replay::new::<M>(last));
         self
     }
 
-    /// Returns executable code for the actor that will trigger heartbeat
-    fn get_heartbeat_fut(&self) -> Init {
-        let interval = self.hearbeat_tick;
-
-        let exec_fut = move |ctx: BastionContext| async move {
-            let self_path = ctx.current().path();
-            let self_sender = ctx.current().sender();
-
-            loop {
+    /// Makes the messages of type `M` that the elements of this
+    /// children group fail to handle with
+    /// [`BastionContext::handle_retriable`] be redelivered to them
+    /// according to `policy`, instead of requiring the handlers to
+    /// keep track of their retries.
+    ///
+    /// Every time a handler returns a [`Retriable`] error, the
+    /// message is delivered again to the same element once the
+    /// policy's backoff delay elapsed, until the policy's number of
+    /// retries is exhausted and the message is sent to the dead
+    /// letters. The policy's timeout and time budget are ignored.
+    ///
+    /// If the element was restarted in the meantime, the message is
+    /// delivered to the first element of the group taking it
+    /// instead, and it is sent to the dead letters if the group was
+    /// stopped.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The policy defining how many times and how often
+    ///   the messages are redelivered.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::retry::RetryPolicy;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// #[derive(Debug, Clone)]
+    /// struct Put(String, u64);
+    ///
+    /// Bastion::children(|children| {
+    ///     children
+    ///         // The `Put`s are redelivered up to 5 times, waiting
+    ///         // 100ms before the first redelivery...
+    ///         .with_redelivery::<Put>(
+    ///             RetryPolicy::new()
+    ///                 .with_max_retries(5)
+    ///                 .with_backoff(Duration::from_millis(100), 2.0),
+    ///         )
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`BastionContext::handle_retriable`]: crate::context::BastionContext::handle_retriable
+    /// [`Retriable`]: crate::retry::Retriable
+    pub fn with_redelivery<M: Message + Clone>(mut self, policy: RetryPolicy) -> Self {
+        trace!(
+            "Children({}): Redelivering the messages with: {:?}",
+            self.id(),
+            policy
+        );
+        let redelivery = Redelivery::new::<M>(policy);
+        self.redelivery = Some(Arc::new(RedeliveryQueue::new(redelivery)));
+        self
+    }
+
+    /// Makes the elements of this children group advertise the
+    /// number of messages they can take in `demand`, so that the
+    /// producers pushing messages according to it (see the
+    /// [`demand`] module) don't flood their mailboxes.
+    ///
+    /// Every element requests `window` messages when it is launched
+    /// and then one more every time it takes a message out of its
+    /// mailbox.
+    ///
+    /// # Arguments
+    ///
+    /// * `demand` - The demand the elements advertise.
+    /// * `window` - The number of messages each element can take
+    ///   at a time.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::demand::Demand;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// let demand = Demand::new();
+    ///
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_demand(demand.clone(), 32)
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    ///
+    /// // A producer only sends a message if the group can take it.
+    /// if demand.try_acquire() {
+    ///     // ...
+    /// }
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`demand`]: crate::demand
+    pub fn with_demand(mut self, demand: Demand, window: usize) -> Self {
+        trace!(
+            "Children({}): Advertising a demand of {} messages per element.",
+            self.id(),
+            window
+        );
+        self.demand = Some((demand, window));
+        self
+    }
+
+    /// Bounds the resources used by the elements of this children
+    /// group (see the [`quota`] module).
+    ///
+    /// # Arguments
+    ///
+    /// * `quota` - The bounds of the resources the group can use and
+    ///   what happens when it reaches them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::quota::{Quota, QuotaAction};
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_quota(
+    ///             Quota::new()
+    ///                 .with_max_in_flight_asks(100)
+    ///                 .with_action(QuotaAction::Log),
+    ///         )
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`quota`]: crate::quota
+    pub fn with_quota(mut self, quota: Quota) -> Self {
+        trace!("Children({}): Setting quota: {:?}", self.id(), quota);
+        self.quota = Some(Arc::new(QuotaState::new(quota, self.id().clone())));
+        self
+    }
+
+    /// Makes this children group own `resource`, which its elements
+    /// share by getting it with [`BastionContext::resource`].
+    ///
+    /// The resource is initialized the first time an element gets it,
+    /// torn down and initialized again once the elements restart
+    /// after a fault, and torn down when the group stops. A resource
+    /// of the same type replaces the previous one.
+    ///
+    /// See the [`resource`] module for more details.
+    ///
+    /// # Arguments
+    ///
+    /// * `resource` - The resource owned by the group.
+    ///
+    /// [`BastionContext::resource`]: crate::context::BastionContext::resource
+    /// [`resource`]: crate::resource
+    pub fn with_resource<T: Send + Sync + 'static>(mut self, resource: Resource<T>) -> Self {
+        trace!(
+            "Children({}): Attaching a resource: {:?}",
+            self.id(),
+            resource
+        );
+        self.resources.insert(resource);
+        self
+    }
+
+    /// Records every message of type `M` delivered to the elements
+    /// of this children group in `journal`, in the order they are
+    /// delivered.
+    ///
+    /// The journal can then be replayed to a fresh instance of the
+    /// group with [`Journal::replay`], to reproduce a bug
+    /// deterministically.
+    ///
+    /// # Arguments
+    ///
+    /// * `journal` - The journal to record the messages in.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::journal::Journal;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// let journal = Journal::<String>::new();
+    ///
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_journal(journal.clone())
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Journal::replay`]: crate::journal::Journal::replay
+    #[cfg(feature = "journal")]
+    #[cfg_attr(feature = "docs", doc(cfg(journal)))]
+    pub fn with_journal<M: Message + Clone>(mut self, journal: Journal<M>) -> Self {
+        trace!("Children({}): Setting journal.", self.id());
+        self.journal = Some(journal.recorder());
+        self
+    }
+
+    /// Injects the faults configured by `chaos` into the elements
+    /// of this children group, to verify that the supervision
+    /// strategies in place actually recover from them.
+    ///
+    /// # Arguments
+    ///
+    /// * `chaos` - The faults to inject into the elements.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::chaos::Chaos;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_chaos(Chaos::new().with_drop_rate(0.1))
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    #[cfg(feature = "chaos")]
+    #[cfg_attr(feature = "docs", doc(cfg(chaos)))]
+    pub fn with_chaos(mut self, chaos: Chaos) -> Self {
+        trace!("Children({}): Setting chaos: {:?}", self.id(), chaos);
+        self.chaos = Some(chaos);
+        self
+    }
+
+    /// Overrides the default time interval for heartbeat onto
+    /// the user defined.
+    ///
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The value of the [`std::time::Duration`] type
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    /// children
+    ///     .with_heartbeat_tick(Duration::from_secs(5))
+    ///     .with_exec(|ctx| {
+    ///         // -- Children group started.
+    ///         async move {
+    ///             // ...
+    ///             # Ok(())
+    ///         }
+    ///         // -- Children group stopped.
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_heartbeat_tick(mut self, interval: Duration) -> Self {
+        trace!(
+            "Children({}): Set heartbeat tick to {:?}",
+            self.id(),
+            interval
+        );
+        self.hearbeat_tick = interval;
+        self
+    }
+
+    /// Makes this children group restart any of its elements which
+    /// didn't handle any message (including the heartbeats the group
+    /// sends to its elements every heartbeat tick) for `ticks`
+    /// heartbeat ticks, eg. because it is stuck in a blocking call.
+    ///
+    /// Only the stalled element is restarted, the same way it would
+    /// be if it faulted.
+    ///
+    /// # Arguments
+    ///
+    /// * `ticks` - The number of heartbeat ticks after which a
+    ///   stalled element is restarted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         // Restarts the elements stalled for more than 15 seconds.
+    ///         .with_heartbeat_tick(Duration::from_secs(5))
+    ///         .with_stalled_restart(3)
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_stalled_restart(mut self, ticks: u32) -> Self {
+        trace!(
+            "Children({}): Restarting the elements stalled for {} ticks.",
+            self.id(),
+            ticks
+        );
+        self.stalled_ticks = Some(ticks.max(1));
+        self
+    }
+
+    /// Sets the number of messages the mailbox of every element of
+    /// this children group can hold, instead of the default one set
+    /// with [`Config::with_mailbox_capacity`]. The messages sent to
+    /// an element whose mailbox is full are dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of messages a mailbox can hold.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_mailbox_capacity(128)
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Config::with_mailbox_capacity`]: crate::Config::with_mailbox_capacity
+    pub fn with_mailbox_capacity(mut self, capacity: usize) -> Self {
+        trace!(
+            "Children({}): Setting the mailbox capacity to {}.",
+            self.id(),
+            capacity
+        );
+        self.mailbox_capacity = Some(capacity.max(1));
+        self
+    }
+
+    /// Sets the watermarks of the mailbox of every element of this
+    /// children group, whose crossings are reported while the
+    /// mailboxes fill up or drain (see the [`watermark`] module).
+    ///
+    /// The watermarks are in percents of the capacity of the
+    /// mailboxes, so they are ignored unless it is bounded (see
+    /// [`Children::with_mailbox_capacity`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `watermarks` - The watermarks of the mailboxes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::watermark::Watermarks;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_mailbox_capacity(128)
+    ///         .with_mailbox_watermarks(Watermarks::new().with_levels(&[75, 100]))
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`watermark`]: crate::watermark
+    pub fn with_mailbox_watermarks(mut self, watermarks: Watermarks) -> Self {
+        trace!(
+            "Children({}): Setting the mailbox watermarks: {:?}",
+            self.id(),
+            watermarks
+        );
+        self.watermarks = Some(watermarks);
+        self
+    }
+
+    /// Stops this children group (calling its callbacks as usual)
+    /// once its elements didn't take any message out of their
+    /// mailboxes for `timeout`.
+    ///
+    /// This allows the groups created on demand (eg. sharded
+    /// entities or workers) to release their resources once they
+    /// aren't needed anymore.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long the group can stay idle.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_idle_timeout(Duration::from_secs(60))
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        trace!(
+            "Children({}): Setting the idle timeout to {:?}.",
+            self.id(),
+            timeout
+        );
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Makes this children group wait for all of its elements to
+    /// signal their readiness, by calling
+    /// [`BastionContext::notify_started`], before being considered
+    /// started.
+    ///
+    /// Until then, [`ChildrenRef::wait_started`] doesn't resolve and
+    /// the elements aren't registered into the dispatchers of the
+    /// group, so that they don't receive the messages broadcasted
+    /// through them before they are able to handle them. Elements
+    /// launched after the group started (eg. restarted ones) are
+    /// registered as soon as they signal their readiness.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_readiness()
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // Opens the connections...
+    ///                 ctx.notify_started();
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`BastionContext::notify_started`]: crate::context::BastionContext::notify_started
+    /// [`ChildrenRef::wait_started`]: crate::children_ref::ChildrenRef::wait_started
+    pub fn with_readiness(mut self) -> Self {
+        trace!(
+            "Children({}): Waiting for the elements to signal their readiness.",
+            self.id()
+        );
+        self.readiness = Some(Arc::new(Readiness::new()));
+        self
+    }
+
+    /// Isolates the panics thrown while the elements of this children
+    /// group handle a message with [`BastionContext::handle`], so
+    /// that a message making its handler panic doesn't fault the
+    /// whole element (and its group).
+    ///
+    /// The message is sent to the dead letters, along with the panic,
+    /// as a [`PanickedMessage`] and the element goes on handling the
+    /// next messages. The panics thrown outside of the handlers still
+    /// fault the elements.
+    ///
+    /// Note that the state shared between the handlers (eg. captured
+    /// by reference) might be left inconsistent by a panic.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_panic_isolation()
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 loop {
+    ///                     let msg = ctx.recv().await?;
+    ///                     ctx.handle(msg, |msg| async move {
+    ///                         // ...
+    ///                     })
+    ///                     .await
+    ///                     .ok();
+    ///                 }
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`BastionContext::handle`]: crate::context::BastionContext::handle
+    /// [`PanickedMessage`]: crate::message::PanickedMessage
+    pub fn with_panic_isolation(mut self) -> Self {
+        trace!(
+            "Children({}): Isolating the panics of the handlers.",
+            self.id()
+        );
+        self.isolate_panics = true;
+        self
+    }
+
+    /// Makes this children group park its idle elements, so that
+    /// they cost it nothing until they handle a message, stop or
+    /// fault.
+    ///
+    /// By default, a group checks all its elements every time it is
+    /// woken (eg. by a heartbeat or a message sent to the group),
+    /// which gets costly for the groups with a large redundancy
+    /// where most of the elements are idle.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_redundancy(100)
+    ///         .with_idle_parking()
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // Mostly waits for messages...
+    ///                 let msg = ctx.recv().await?;
+    ///                 Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_idle_parking(mut self) -> Self {
+        trace!("Children({}): Parking the idle elements.", self.id());
+        self.parking = Some(Parking::default());
+        self
+    }
+
+    /// Sets whether the elements of this children group which fault
+    /// are relaunched in place by the group itself, instead of being
+    /// restarted by its supervisor (which might also restart the
+    /// other elements of the group, or other groups, depending on its
+    /// [`SupervisionStrategy`]).
+    ///
+    /// The other elements of the group and the resources they share
+    /// are left untouched, and the relaunched element keeps its
+    /// identifier and its mailbox. The faults aren't counted by the
+    /// supervisor's [`RestartStrategy`] then.
+    ///
+    /// # Arguments
+    ///
+    /// * `isolate` - Whether the faulted elements are relaunched by
+    ///   the group itself.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_redundancy(8)
+    ///         // An element which fails doesn't interrupt the others.
+    ///         .with_fault_isolation(true)
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // ...
+    ///                 Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`SupervisionStrategy`]: crate::supervisor::SupervisionStrategy
+    /// [`RestartStrategy`]: crate::supervisor::RestartStrategy
+    pub fn with_fault_isolation(mut self, isolate: bool) -> Self {
+        trace!(
+            "Children({}): Setting the isolation of the faults: {}",
+            self.id(),
+            isolate
+        );
+        self.isolate_faults = isolate;
+        self
+    }
+
+    /// Sets how long the elements of this children group are given
+    /// to stop once the group is killed (or stopped), before the
+    /// ones still running are reported. Five seconds by default.
+    ///
+    /// The elements are cancelled when they reach an await point, so
+    /// an element stuck in blocking code (or in a loop which never
+    /// awaits) can't be stopped. Once the deadline elapsed, a warning
+    /// is logged for every such element, telling how long its current
+    /// poll has been running and the type of the last message it took
+    /// out of its mailbox. The group still waits for them to stop.
+    ///
+    /// # Arguments
+    ///
+    /// * `deadline` - How long the elements are given to stop.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_kill_deadline(Duration::from_secs(1))
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 msg! { ctx.recv().await?,
+    ///                     path: String => {
+    ///                         // Never awaits while reading the file,
+    ///                         // so this element can't be cancelled
+    ///                         // in the meantime.
+    ///                         std::fs::read(path).ok();
+    ///                     };
+    ///                     _: _ => ();
+    ///                 }
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_kill_deadline(mut self, deadline: Duration) -> Self {
+        trace!(
+            "Children({}): Setting the kill deadline to {:?}.",
+            self.id(),
+            deadline
+        );
+        self.kill_deadline = deadline;
+        self
+    }
+
+    /// Sets how long the elements of this children group which fault
+    /// wait before being restarted (by the group's supervisor, or by
+    /// the group itself if it isolates the faults), instead of being
+    /// restarted right away.
+    ///
+    /// This gives time to the dependencies the elements rely on (eg.
+    /// a database or a message broker) to recover. The delay adds up
+    /// to the one of the supervisor's [`RestartStrategy`], if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - How long the faulted elements wait.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_restart_delay(Duration::from_secs(5))
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // Connects to a broker which might be down...
+    ///                 Err(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`RestartStrategy`]: crate::supervisor::RestartStrategy
+    pub fn with_restart_delay(mut self, delay: Duration) -> Self {
+        trace!(
+            "Children({}): Setting the restart delay to {:?}.",
+            self.id(),
+            delay
+        );
+        self.restart_delay = Some(delay);
+        self
+    }
+
+    /// Sets the fraction of the messages handled by the elements of
+    /// this children group which are traced, so that a
+    /// high-throughput group doesn't drown the tracing backend. By
+    /// default, all the messages are.
+    ///
+    /// The sampled messages are handled within a `message` span by
+    /// [`BastionContext::handle`], and [`BastionContext::is_sampled`]
+    /// tells whether the message being handled is sampled. See the
+    /// [`sampling`] module for more details.
+    ///
+    /// # Arguments
+    ///
+    /// * `sampling` - The fraction of the messages to trace.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::sampling::TraceSampling;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_trace_sampling(TraceSampling::new(0.01).with_errors_sampled(false))
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 loop {
+    ///                     let _msg = ctx.recv().await?;
+    ///                     if ctx.is_sampled() {
+    ///                         // Records custom metrics...
+    ///                     }
+    ///                     // ...
+    ///                 }
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`BastionContext::handle`]: crate::context::BastionContext::handle
+    /// [`BastionContext::is_sampled`]: crate::context::BastionContext::is_sampled
+    /// [`sampling`]: crate::sampling
+    pub fn with_trace_sampling(mut self, sampling: TraceSampling) -> Self {
+        trace!(
+            "Children({}): Setting the trace sampling: {:?}",
+            self.id(),
+            sampling
+        );
+        self.sampler = Some(Arc::new(Sampler::new(sampling)));
+        self
+    }
+
+    /// Makes the elements of this children group take their
+    /// messages from the queues of the prioritized broadcasts and
+    /// from their mailboxes in turn, up to each source's quantum,
+    /// instead of by priority (see [`fairness`]).
+    ///
+    /// This prevents a firehose of high priority broadcasts from
+    /// starving the mailboxes, and the mailboxes from starving the
+    /// low priority broadcasts.
+    ///
+    /// # Arguments
+    ///
+    /// * `polling` - The number of messages taken from each source
+    ///   in turn.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::fairness::{FairPolling, MessageSource};
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_fair_polling(FairPolling::new().with_quantum(MessageSource::HighPriority, 8))
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 loop {
+    ///                     let _msg = ctx.recv().await?;
+    ///                     // ...
+    ///                 }
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`fairness`]: crate::fairness
+    pub fn with_fair_polling(mut self, polling: FairPolling) -> Self {
+        trace!(
+            "Children({}): Setting the fair polling: {:?}",
+            self.id(),
+            polling
+        );
+        self.fair_polling = Some(polling);
+        self
+    }
+
+    /// Sets whether the elements of this children group are
+    /// restarted when they stop or fault (see [`ChildRestart`]),
+    /// unless set otherwise for some of them with
+    /// [`with_element_restart`].
+    ///
+    /// The elements are restarted when they fault and dropped when
+    /// they return `Ok(())` by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `restart` - Whether the elements are restarted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         // The elements are restarted even once they are done.
+    ///         .with_child_restart(ChildRestart::Permanent)
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // Polls a queue until it is empty...
+    ///                 Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`with_element_restart`]: Self::with_element_restart
+    pub fn with_child_restart(mut self, restart: ChildRestart) -> Self {
+        trace!(
+            "Children({}): Setting the restart of the elements: {:?}",
+            self.id(),
+            restart
+        );
+        self.child_restart = restart;
+        self
+    }
+
+    /// Sets whether the element launched at `index` (from `0` to
+    /// the group's redundancy) is restarted when it stops or
+    /// faults, instead of following the group's [`ChildRestart`].
+    ///
+    /// The setting follows the element across its restarts and
+    /// upgrades, while the elements launched when the group scales
+    /// up follow the group's.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the element.
+    /// * `restart` - Whether the element is restarted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_redundancy(4)
+    ///         // The first element runs a one-off task, which isn't
+    ///         // restarted even if it fails...
+    ///         .with_element_restart(0, ChildRestart::Temporary)
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // ...
+    ///                 Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_element_restart(mut self, index: usize, restart: ChildRestart) -> Self {
+        trace!(
+            "Children({}): Setting the restart of the element {}: {:?}",
+            self.id(),
+            index,
+            restart
+        );
+        self.element_restarts.insert(index, restart);
+        self
+    }
+
+    /// Sets whether the restarted elements of this children group
+    /// keep the state of the elements they replace (see
+    /// [`RestartState`]), instead of following the group's
+    /// supervisor.
+    ///
+    /// # Arguments
+    ///
+    /// * `restart_state` - Whether the restarted elements keep the
+    ///   state of the elements they replace.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         // The messages sent to a faulted element are dropped...
+    ///         .with_restart_state(RestartState::Fresh)
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // Handle the messages...
+    ///                 Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_restart_state(mut self, restart_state: RestartState) -> Self {
+        trace!(
+            "Children({}): Setting the state of the restarted elements: {:?}",
+            self.id(),
+            restart_state
+        );
+        self.restart_state = Some(restart_state);
+        self
+    }
+
+    /// Makes the restarted elements of this children group follow
+    /// the given default of its supervisor, unless the group set its
+    /// own.
+    pub(crate) fn inherit_restart_state(mut self, restart_state: RestartState) -> Self {
+        if self.restart_state.is_none() {
+            self.restart_state = Some(restart_state);
+        }
+
+        self
+    }
+
+    /// Makes this children group drain the work in flight of its
+    /// elements before stopping them, once it is told to stop: the
+    /// elements are told to stop accepting new work (see
+    /// [`BastionContext::is_draining`]) and the values they track
+    /// with [`BastionContext::track`] are given up to `deadline` to
+    /// be dropped.
+    ///
+    /// The elements are still stopped right away when the group is
+    /// killed (eg. to be restarted after a fault).
+    ///
+    /// # Arguments
+    ///
+    /// * `deadline` - How long the work in flight is given to be
+    ///   done once the group is told to stop.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_drain_deadline(Duration::from_secs(30))
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 while !ctx.is_draining() {
+    ///                     let _request = ctx.track(ctx.recv().await?);
+    ///                     // Handles the request...
+    ///                 }
+    ///
+    ///                 Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`BastionContext::is_draining`]: crate::context::BastionContext::is_draining
+    /// [`BastionContext::track`]: crate::context::BastionContext::track
+    pub fn with_drain_deadline(mut self, deadline: Duration) -> Self {
+        trace!(
+            "Children({}): Setting the drain deadline to {:?}.",
+            self.id(),
+            deadline
+        );
+        self.drain = Some((Arc::new(Drain::new()), deadline));
+        self
+    }
+
+    /// Defers the launch of the elements of this children group (and
+    /// of its helper actors) until it receives its first message,
+    /// which reduces the startup cost of the groups that are rarely
+    /// used.
+    ///
+    /// The messages sent to the group are buffered meanwhile and
+    /// delivered to its elements once they are launched. Note that
+    /// until then, the [`ChildrenRef`] of the group doesn't contain
+    /// any element, so the messages have to be sent to the group
+    /// itself (eg. with [`ChildrenRef::broadcast`]).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// let children_ref = Bastion::children(|children| {
+    ///     children
+    ///         .with_lazy_start()
+    ///         .with_exec(|ctx| {
+    ///             // -- Launched once the first message is received.
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    ///
+    /// children_ref.broadcast("Wake up").expect("Couldn't send the message.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`ChildrenRef`]: crate::children_ref::ChildrenRef
+    /// [`ChildrenRef::broadcast`]: crate::children_ref::ChildrenRef::broadcast
+    pub fn with_lazy_start(mut self) -> Self {
+        trace!(
+            "Children({}): Deferring the launch of the elements.",
+            self.id()
+        );
+        self.lazy = true;
+        self
+    }
+
+    /// Makes the supervisor of this children group only start it
+    /// once the group referenced by `children` started, instead of
+    /// along with the other elements it supervises. This can be
+    /// called several times for the group to start after several
+    /// other ones.
+    ///
+    /// A group is considered started once its elements signaled
+    /// their readiness if it waits for them to (see
+    /// [`with_readiness`]), or as soon as it was sent the start
+    /// message otherwise. Until this group is started, its elements
+    /// wait and the messages sent to it are buffered.
+    ///
+    /// Note that the groups mustn't depend on each other, or none of
+    /// them will ever start.
+    ///
+    /// # Arguments
+    ///
+    /// * `children` - The group which has to be started first.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// let database = Bastion::children(|children| {
+    ///     children
+    ///         .with_readiness()
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // Opens the connections...
+    ///                 ctx.notify_started();
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    ///
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_start_after(&database)
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // -- Runs once the connections are open.
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`with_readiness`]: Self::with_readiness
+    pub fn with_start_after(mut self, children: &ChildrenRef) -> Self {
+        trace!(
+            "Children({}): Starting after Children({}).",
+            self.id(),
+            children.id()
+        );
+        self.start_after.push(children.clone());
+        self
+    }
+
+    pub(crate) fn start_after(&self) -> &[ChildrenRef] {
+        &self.start_after
+    }
+
+    /// Attaches a helper actor to this children group which
+    /// publishes its metrics to the [`metrics::registry`] every
+    /// `interval`: the depth of the mailbox of every element, the
+    /// number of messages the elements received and the rate at
+    /// which they did, and the number of restarts.
+    ///
+    /// The metrics are labeled with the name (`group`) and the
+    /// identifier (`children`) of the group.
+    ///
+    /// This method is available only with the `metrics` feature flag.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - How often the metrics are published.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_name("workers")
+    ///         .with_stats_collector(Duration::from_secs(10))
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`metrics::registry`]: crate::metrics::registry
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(feature = "docs", doc(cfg(metrics)))]
+    pub fn with_stats_collector(mut self, interval: Duration) -> Self {
+        trace!(
+            "Children({}): Publishing the metrics every {:?}",
+            self.id(),
+            interval
+        );
+        self.stats_collector = Some(interval);
+        self
+    }
+
+    /// Sets the executor this children group and its elements will
+    /// be spawned onto, instead of the global pool which is shared
+    /// with every other group.
+    ///
+    /// This allows to isolate latency-critical actors from noisy
+    /// workers by giving them their own threads.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - The [`ExecutorHandle`] referencing the executor
+    ///   to use.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// let executor = ExecutorHandle::dedicated("latency-critical", 2);
+    ///
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_executor(executor)
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_executor(mut self, executor: ExecutorHandle) -> Self {
+        trace!("Children({}): Setting executor: {:?}", self.id(), executor);
+        self.executor = executor;
+        self
+    }
+
+    /// Sets the scheduling weight of the elements of this children
+    /// group, `1` by default.
+    ///
+    /// Every time a worker runs an element, the element is given a
+    /// number of polls proportional to its weight before yielding
+    /// back to the worker. Elements of groups with a greater weight
+    /// thus get proportionally more polling opportunities than the
+    /// ones of the other groups sharing the same workers, which
+    /// allows to favor latency-critical groups over batch ones
+    /// without giving them their own executor.
+    ///
+    /// # Arguments
+    ///
+    /// * `weight` - The scheduling weight of the elements. A weight
+    ///   of `0` is treated as `1`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         // Polled four times as much as the batch group.
+    ///         .with_scheduling_weight(4)
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    ///
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_name("batch")
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_scheduling_weight(mut self, weight: u32) -> Self {
+        trace!(
+            "Children({}): Setting scheduling weight: {}",
+            self.id(),
+            weight
+        );
+        self.scheduling_weight = weight.max(1);
+        self
+    }
+
+    /// Makes this children group run in thread-per-core mode: its
+    /// redundancy is set to the number of cores and each of its
+    /// elements runs pinned on its own worker thread, with its own
+    /// local run queue.
+    ///
+    /// Restarted elements keep running on the worker they were
+    /// pinned to, which allows to build shared-nothing architectures.
+    ///
+    /// Note that calling [`with_redundancy`] or [`with_executor`]
+    /// afterwards overrides this mode's settings.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_thread_per_core()
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // Each element owns its own core...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`with_redundancy`]: Self::with_redundancy
+    /// [`with_executor`]: Self::with_executor
+    pub fn with_thread_per_core(self) -> Self {
+        let executor = ExecutorHandle::thread_per_core(self.name());
+        let cores = executor.pinned_workers().unwrap_or(1);
+        trace!(
+            "Children({}): Running in thread-per-core mode on {} cores.",
+            self.id(),
+            cores
+        );
+
+        self.with_redundancy(cores).with_executor(executor)
+    }
+
+    /// Sets a hook that will be called with the identifier of the
+    /// element being polled, every time before one of the
+    /// elements of this children group is polled by the executor.
+    ///
+    /// This allows to implement custom instrumentation, like
+    /// recording how long each poll takes or setting up and tearing
+    /// down thread-local context, along with [`with_after_poll`].
+    ///
+    /// # Arguments
+    ///
+    /// * `hook` - The closure taking the identifier of the element
+    ///   being polled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::cell::Cell;
+    /// # use std::time::Instant;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// thread_local! {
+    ///     static POLL_START: Cell<Option<Instant>> = Cell::new(None);
+    /// }
+    ///
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_before_poll(|_| POLL_START.with(|start| start.set(Some(Instant::now()))))
+    ///         .with_after_poll(|id| {
+    ///             if let Some(start) = POLL_START.with(|start| start.take()) {
+    ///                 println!("Child({}) polled in {:?}", id, start.elapsed());
+    ///             }
+    ///         })
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`with_after_poll`]: Self::with_after_poll
+    pub fn with_before_poll<C>(mut self, hook: C) -> Self
+    where
+        C: Fn(&BastionId) + Send + Sync + 'static,
+    {
+        trace!("Children({}): Setting before poll hook.", self.id());
+        self.poll_hooks.before_poll = Some(Arc::new(hook));
+        self
+    }
+
+    /// Sets a hook that will be called with the identifier of the
+    /// element being polled, every time after one of the
+    /// elements of this children group is polled by the executor.
+    ///
+    /// This allows to implement custom instrumentation, like
+    /// recording how long each poll takes or setting up and tearing
+    /// down thread-local context, along with [`with_before_poll`].
+    ///
+    /// # Arguments
+    ///
+    /// * `hook` - The closure taking the identifier of the element
+    ///   being polled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::cell::Cell;
+    /// # use std::time::Instant;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// thread_local! {
+    ///     static POLL_START: Cell<Option<Instant>> = Cell::new(None);
+    /// }
+    ///
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_before_poll(|_| POLL_START.with(|start| start.set(Some(Instant::now()))))
+    ///         .with_after_poll(|id| {
+    ///             if let Some(start) = POLL_START.with(|start| start.take()) {
+    ///                 println!("Child({}) polled in {:?}", id, start.elapsed());
+    ///             }
+    ///         })
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`with_before_poll`]: Self::with_before_poll
+    pub fn with_after_poll<C>(mut self, hook: C) -> Self
+    where
+        C: Fn(&BastionId) + Send + Sync + 'static,
+    {
+        trace!("Children({}): Setting after poll hook.", self.id());
+        self.poll_hooks.after_poll = Some(Arc::new(hook));
+        self
+    }
+
+    /// Returns the pinned worker the element with the given
+    /// identifier should run on, if the group runs in thread-per-core
+    /// mode. Elements being restarted keep their worker, new ones are
+    /// given the first worker without any element.
+    fn pin_worker(&mut self, id: &BastionId) -> Option<usize> {
+        let workers = self.executor.pinned_workers()?;
+        if let Some(worker) = self.pinned_workers.get(id) {
+            return Some(*worker);
+        }
+
+        let worker = (0..workers)
+            .find(|worker| !self.pinned_workers.values().any(|used| used == worker))
+            .unwrap_or(self.pinned_workers.len() % workers);
+        self.pinned_workers.insert(id.clone(), worker);
+
+        Some(worker)
+    }
+
+    /// Returns the faults to inject into the next launched element,
+    /// if the group injects any.
+    #[cfg(feature = "chaos")]
+    fn next_chaos(&mut self) -> Option<Chaos> {
+        let index = self.chaos_launched;
+        self.chaos_launched += 1;
+        self.chaos.as_ref().map(|chaos| chaos.for_element(index))
+    }
+
+    /// Returns executable code for the actor that will trigger heartbeat
+    fn get_heartbeat_fut(&self) -> Init {
+        let interval = self.hearbeat_tick;
+
+        let exec_fut = move |ctx: BastionContext| async move {
+            let self_path = ctx.current().path();
+            let self_sender = ctx.current().sender();
+
+            loop {
                 Delay::new(interval).await;
 
                 let msg = BastionMessage::heartbeat();
@@ -606,7 +3219,166 @@ impl Children {
         Init::new(exec_fut)
     }
 
+    fn get_idle_watcher_fut(&self, timeout: Duration) -> Init {
+        let activity = self.activity.clone();
+
+        let exec_fut = move |ctx: BastionContext| {
+            let activity = activity.clone();
+
+            async move {
+                loop {
+                    let idle = activity.get().elapsed();
+                    if idle >= timeout {
+                        debug!(
+                            "Children({}): Idle for {:?}, stopping.",
+                            ctx.parent().id(),
+                            idle
+                        );
+                        ctx.parent().stop().ok();
+                        return Ok(());
+                    }
+
+                    Delay::new(timeout - idle).await;
+                }
+            }
+        };
+
+        Init::new(exec_fut)
+    }
+
+    #[cfg(feature = "metrics")]
+    fn get_stats_collector_fut(&self, interval: Duration) -> Init {
+        let group_metrics = self.metrics.clone();
+        let group = self.name();
+        let children_id = self.id().to_string();
+
+        let exec_fut = move |_: BastionContext| {
+            let group_metrics = group_metrics.clone();
+            let group = group.clone();
+            let children_id = children_id.clone();
+
+            async move {
+                let registry = metrics::registry();
+                let labels = [
+                    ("group", group.as_str()),
+                    ("children", children_id.as_str()),
+                ];
+                let processed = registry.counter("bastion_messages_processed_total", &labels);
+                let rate = registry.gauge("bastion_messages_per_second", &labels);
+                let restarts = registry.counter("bastion_restarts_total", &labels);
+
+                let mut last_processed = 0;
+                let mut last_processed_by_type = FxHashMap::default();
+                let mut last_watermark_crossings = FxHashMap::default();
+                let mut last_restarts = 0;
+                let mut last_children = Vec::new();
+                let mut last_polls = FxHashMap::default();
+                loop {
+                    Delay::new(interval).await;
+
+                    let sample = group_metrics.sample();
+                    // The count goes down when elements are dropped.
+                    let delta = sample.processed.saturating_sub(last_processed);
+                    processed.increment(delta);
+                    rate.set(delta as f64 / interval.as_secs_f64());
+                    restarts.increment(sample.restarts.saturating_sub(last_restarts));
+                    last_processed = sample.processed;
+                    last_restarts = sample.restarts;
+
+                    for (name, count) in sample.processed_by_type.iter() {
+                        let last = last_processed_by_type.get(name).copied().unwrap_or(0);
+                        registry
+                            .counter(
+                                "bastion_messages_by_type_total",
+                                &[
+                                    ("group", group.as_str()),
+                                    ("children", children_id.as_str()),
+                                    ("message", *name),
+                                ],
+                            )
+                            .increment(count.saturating_sub(last));
+                    }
+                    last_processed_by_type = sample.processed_by_type;
+
+                    for (level, count) in sample.watermark_crossings.iter() {
+                        let last = last_watermark_crossings.get(level).copied().unwrap_or(0);
+                        let level = level.to_string();
+                        registry
+                            .counter(
+                                "bastion_mailbox_watermark_crossings_total",
+                                &[
+                                    ("group", group.as_str()),
+                                    ("children", children_id.as_str()),
+                                    ("level", level.as_str()),
+                                ],
+                            )
+                            .increment(count.saturating_sub(last));
+                    }
+                    last_watermark_crossings = sample.watermark_crossings;
+
+                    let children = sample
+                        .mailboxes
+                        .iter()
+                        .map(|(id, depth)| {
+                            let child = id.to_string();
+                            registry
+                                .gauge(
+                                    "bastion_mailbox_depth",
+                                    &[
+                                        ("group", group.as_str()),
+                                        ("children", children_id.as_str()),
+                                        ("child", child.as_str()),
+                                    ],
+                                )
+                                .set(*depth as f64);
+
+                            child
+                        })
+                        .collect::<Vec<_>>();
+
+                    for (id, polls, poll_time) in sample.polls.iter() {
+                        let child = id.to_string();
+                        let labels = [
+                            ("group", group.as_str()),
+                            ("children", children_id.as_str()),
+                            ("child", child.as_str()),
+                        ];
+                        let poll_time = poll_time.as_micros() as u64;
+                        // The poll accounting is reset when the element
+                        // is restarted.
+                        let (last_polls, last_poll_time) = last_polls
+                            .insert(id.clone(), (*polls, poll_time))
+                            .unwrap_or((0, 0));
+                        let (polls, poll_time) = if *polls < last_polls {
+                            (*polls, poll_time)
+                        } else {
+                            (polls - last_polls, poll_time.saturating_sub(last_poll_time))
+                        };
+                        registry
+                            .counter("bastion_polls_total", &labels)
+                            .increment(polls);
+                        registry
+                            .counter("bastion_poll_time_microseconds_total", &labels)
+                            .increment(poll_time);
+                    }
+                    let polled = &sample.polls;
+                    last_polls.retain(|id, _| polled.iter().any(|(polled, ..)| polled == id));
+
+                    // Stops publishing the metrics of the dropped elements.
+                    for child in last_children.iter().filter(|id| !children.contains(id)) {
+                        registry.remove_labeled("child", child);
+                    }
+                    last_children = children;
+                }
+            }
+        };
+
+        Init::new(exec_fut)
+    }
+
     async fn disable_helper_actors(&mut self) {
+        self.heartbeat = None;
+
         let mut children = FuturesOrdered::new();
         for (_, (_, launched)) in self.helper_actors.drain() {
             launched.cancel();
@@ -626,19 +3398,52 @@ impl Children {
         debug!("Children({}): Killing.", self.id());
         self.bcast.kill_children();
 
+        self.pinned_workers.clear();
+        self.last_seen.clear();
+        self.restarts.clear();
+        self.delayed_restarts.clear();
+        #[cfg(feature = "metrics")]
+        self.metrics.clear();
+        if let Some(tree) = &self.tree {
+            tree.dropped(None);
+        }
+
         let mut children = FuturesOrdered::new();
-        for (_, (_, launched)) in self.launched.drain() {
+        let mut senders = Vec::with_capacity(self.launched.len());
+        for (id, (sender, launched)) in self.launched.drain() {
             launched.cancel();
 
             children.push(launched);
+            senders.push((id, sender));
         }
 
-        let id = self.id();
-        children
-            .for_each_concurrent(None, |_| async {
-                trace!("Children({}): Unknown child stopped.", id);
-            })
-            .await;
+        let id = self.id().clone();
+        let stopping = children.for_each_concurrent(None, |_| async {
+            trace!("Children({}): Unknown child stopped.", id);
+        });
+        pin_mut!(stopping);
+        let deadline = Delay::new(self.kill_deadline);
+        if let Either::Right((_, stopping)) = future::select(stopping, deadline).await {
+            // The elements are only cancelled once they reach an
+            // await point, which those which are still running might
+            // never do.
+            for (child_id, _) in senders.iter().filter(|(_, sender)| !sender.is_closed()) {
+                let site = match self.poll_sites.get(child_id) {
+                    Some(poll_site) => poll_site.describe(),
+                    None => "no poll site available".to_string(),
+                };
+                warn!(
+                    "Children({}): Child({}) didn't stop {:?} after being killed: {}.",
+                    id, child_id, self.kill_deadline, site
+                );
+            }
+
+            stopping.await;
+        }
+
+        self.poll_sites.clear();
+        self.states.clear();
+        self.resources.release().await;
     }
 
     fn stopped(&mut self) {
@@ -664,6 +3469,34 @@ impl Children {
         Err(())
     }
 
+    /// Tells the elements to stop accepting new work and waits for
+    /// the work they track to be done, or for the drain deadline to
+    /// pass, if the group is drained before being stopped.
+    async fn drain(&mut self) {
+        let (drain, deadline) = match &self.drain {
+            Some((drain, deadline)) => (drain.clone(), *deadline),
+            None => return,
+        };
+
+        debug!("Children({}): Draining.", self.id());
+        drain.start();
+
+        let mut drained = Duration::from_secs(0);
+        while drain.in_flight() > 0 && drained < deadline {
+            Delay::new(STOP_DRAIN_TICK).await;
+            drained += STOP_DRAIN_TICK;
+        }
+
+        if drain.in_flight() > 0 {
+            warn!(
+                "Children({}): {} tracked values weren't dropped within {:?}.",
+                self.id(),
+                drain.in_flight(),
+                deadline
+            );
+        }
+    }
+
     async fn stop_children(&mut self) -> Result<(), ()> {
         self.disable_helper_actors().await;
         self.kill().await;
@@ -674,21 +3507,47 @@ impl Children {
     async fn handle_stopped_child(&mut self, id: &BastionId) -> Result<(), ()> {
         // FIXME: Err if false?
         if self.launched.contains_key(&id) {
-            debug!("Children({}): Child({}) stopped.", self.id(), id);
-            self.drop_child(id);
-
-            let msg = BastionMessage::finished_child(id.clone(), self.bcast.id().clone());
-            let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
-            self.bcast.send_parent(env).ok();
+            if self.child_restart(id) == ChildRestart::Permanent {
+                debug!(
+                    "Children({}): Child({}) stopped, restarting it.",
+                    self.id(),
+                    id
+                );
+                let parent_id = self.bcast.id().clone();
+                self.request_restarting_child(id, &parent_id, FaultReason::Stopped);
+            } else {
+                debug!("Children({}): Child({}) stopped.", self.id(), id);
+                self.finish_child(id);
+            }
         }
 
         Ok(())
     }
 
+    /// Drops the element from the group and tells the supervisor
+    /// that it is done.
+    fn finish_child(&mut self, id: &BastionId) {
+        self.drop_child(id);
+
+        let msg = BastionMessage::finished_child(id.clone(), self.bcast.id().clone());
+        let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
+        self.bcast.send_parent(env).ok();
+    }
+
+    /// Returns whether the element is restarted when it stops or
+    /// faults.
+    fn child_restart(&self, id: &BastionId) -> ChildRestart {
+        self.restarts.get(id).copied().unwrap_or(self.child_restart)
+    }
+
     async fn handle_faulted_child(&mut self, id: &BastionId) -> Result<(), ()> {
         // FIXME: Err if false?
         if self.launched.contains_key(id) {
             warn!("Children({}): Child({}) faulted.", self.id(), id);
+            if self.relaunch_child(id) {
+                return Ok(());
+            }
+
             self.kill().await;
             self.faulted();
 
@@ -698,15 +3557,228 @@ impl Children {
         Ok(())
     }
 
-    fn request_restarting_child(&mut self, id: &BastionId, parent_id: &BastionId) {
+    /// Restarts the elements which didn't handle any message for
+    /// too long, and sends a heartbeat to the others.
+    fn handle_heartbeat(&mut self) {
+        if let Some(ticks) = self.stalled_ticks {
+            let limit = self.hearbeat_tick * ticks;
+            let stalled = self
+                .last_seen
+                .iter()
+                .filter(|(_, last_seen)| last_seen.get().elapsed() >= limit)
+                .map(|(id, _)| id.clone())
+                .collect::<Vec<_>>();
+
+            for id in stalled {
+                warn!(
+                    "Children({}): Child({}) stalled, restarting it.",
+                    self.id(),
+                    id
+                );
+                if let Some((_, launched)) = self.launched.get(&id) {
+                    launched.cancel();
+                }
+
+                // Prevents the element from being restarted again
+                // before it actually is.
+                self.last_seen.insert(id.clone(), Arc::new(LastSeen::new()));
+                let parent_id = self.bcast.id().clone();
+                self.request_restarting_child(&id, &parent_id, FaultReason::Stalled);
+            }
+        }
+
+        for id in self.launched.keys() {
+            let msg = BastionMessage::heartbeat();
+            let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
+            self.bcast.send_child(id, env);
+        }
+    }
+
+    /// Applies the settings of `reconfigure`, relaunching the
+    /// heartbeat helper if its tick changed.
+    fn reconfigure(&mut self, reconfigure: Reconfigure) {
+        debug!("Children({}): Reconfiguring: {:?}", self.id(), reconfigure);
+        if let Some(tick) = reconfigure.heartbeat_tick {
+            self.hearbeat_tick = tick;
+            if let Some(id) = self.heartbeat.take() {
+                if let Some((_, launched)) = self.helper_actors.remove(&id) {
+                    launched.cancel();
+                }
+                self.launch_heartbeat();
+            }
+        }
+
+        if let Some(ticks) = reconfigure.stalled_ticks {
+            self.stalled_ticks = ticks;
+        }
+
+        #[cfg(feature = "scaling")]
+        {
+            if let Some(lower_bound) = reconfigure.lower_bound {
+                self.resizer.set_lower_bound(lower_bound);
+            }
+            if let Some(upper_bound) = reconfigure.upper_bound {
+                self.resizer.set_upper_bound(upper_bound);
+            }
+        }
+    }
+
+    /// Replaces the closure the elements are launched with by
+    /// `init`, then rolls the elements over to it one by one: a
+    /// replacement is launched, the old element stops receiving
+    /// broadcasts and is given some time to drain its mailbox, then
+    /// it is stopped.
+    async fn upgrade(&mut self, init: Init) {
+        debug!("Children({}): Upgrading.", self.id());
+        self.init = init;
+
+        let old: Vec<_> = self
+            .launched
+            .iter()
+            .map(|(id, (sender, _))| (id.clone(), sender.clone()))
+            .collect();
+        for (id, sender) in old {
+            let new_id = self.launch_child();
+            // The replacement is restarted like the old element was,
+            // while the old element is dropped once stopped.
+            if let Some(restart) = self.restarts.remove(&id) {
+                self.restarts.insert(new_id, restart);
+            }
+            self.restarts.insert(id.clone(), ChildRestart::Transient);
+
+            // The old element stops receiving the broadcasts while it
+            // drains its mailbox.
+            let path = self.bcast.path().clone();
+            let child_ref = ChildRef::new(id.clone(), sender, self.name(), path);
+            SYSTEM
+                .dispatcher()
+                .remove(&self.dispatcher_types(), &child_ref);
+
+            if let Some(state) = self.states.get(&id).cloned() {
+                let mut drained = Duration::from_secs(0);
+                while !state.is_mailbox_empty() && drained < UPGRADE_DRAIN_TIMEOUT {
+                    Delay::new(UPGRADE_DRAIN_TICK).await;
+                    drained += UPGRADE_DRAIN_TICK;
+                }
+
+                if !state.is_mailbox_empty() {
+                    warn!(
+                        "Children({}): Child({}) didn't drain its mailbox within {:?}.",
+                        self.id(),
+                        id,
+                        UPGRADE_DRAIN_TIMEOUT
+                    );
+                }
+            }
+
+            debug!("Children({}): Stopping upgraded Child({}).", self.id(), id);
+            // The element is dropped once it reports being stopped.
+            self.bcast.stop_child(&id);
+        }
+    }
+
+    fn request_restarting_child(
+        &mut self,
+        id: &BastionId,
+        parent_id: &BastionId,
+        reason: FaultReason,
+    ) {
         if parent_id == self.bcast.id() && self.launched.contains_key(id) {
+            if let Some(tree) = &self.tree {
+                // The elements waiting for the restart delay already
+                // recorded their fault.
+                let delayed = self.delayed_restarts.contains_key(id);
+                if !delayed && !matches!(reason, FaultReason::Stopped) {
+                    tree.fault();
+                }
+            }
+
+            if self.child_restart(id) == ChildRestart::Temporary {
+                debug!(
+                    "Children({}): Child({}) faulted ({}), dropping it.",
+                    self.id(),
+                    id,
+                    reason
+                );
+                self.finish_child(id);
+                return;
+            }
+
+            if self.delay_restart(id, &reason) {
+                return;
+            }
+
+            if self.relaunch_child(id) {
+                return;
+            }
+
             let parent_id = self.bcast.id().clone();
-            let msg = BastionMessage::restart_required(id.clone(), parent_id);
+            let msg = BastionMessage::restart_required(id.clone(), parent_id, reason);
             let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
             self.bcast.send_parent(env).ok();
         }
     }
 
+    /// Makes the faulted element wait for the restart delay if the
+    /// group has one, returning whether it has to wait.
+    fn delay_restart(&mut self, id: &BastionId, reason: &FaultReason) -> bool {
+        let delay = match self.restart_delay {
+            Some(delay) => delay,
+            None => return false,
+        };
+
+        if let Some(deadline) = self.delayed_restarts.get(id) {
+            if Instant::now() < *deadline {
+                // The element already waits to be restarted.
+                return true;
+            }
+
+            self.delayed_restarts.remove(id);
+            return false;
+        }
+
+        debug!(
+            "Children({}): Restarting Child({}) in {:?}.",
+            self.id(),
+            id,
+            delay
+        );
+        self.delayed_restarts
+            .insert(id.clone(), Instant::now() + delay);
+
+        let parent_id = self.bcast.id().clone();
+        let msg = BastionMessage::restart_required(id.clone(), parent_id, reason.clone());
+        let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
+        let sender = self.bcast.sender().clone();
+        pool::spawn(
+            async move {
+                Delay::new(delay).await;
+                // FIXME: handle errors
+                sender.unbounded_send(env).ok();
+            },
+            ProcStack::default(),
+        );
+
+        true
+    }
+
+    /// Relaunches the faulted element in place if the group isolates
+    /// the faults of its elements, returning whether it did.
+    fn relaunch_child(&mut self, id: &BastionId) -> bool {
+        if !self.isolate_faults {
+            return false;
+        }
+
+        match self.states.get(id).cloned() {
+            Some(state) => {
+                debug!("Children({}): Relaunching Child({}).", self.id(), id);
+                self.restart_child(id, state);
+                true
+            }
+            None => false,
+        }
+    }
+
     fn restart_child(&mut self, old_id: &BastionId, old_state: Arc<Pin<Box<ContextState>>>) {
         let parent = Parent::children(self.as_ref());
         let bcast = Broadcast::new(parent, BastionPathElement::Child(old_id.clone()));
@@ -714,7 +3786,54 @@ impl Children {
         let id = bcast.id().clone();
         let sender = bcast.sender().clone();
         let path = bcast.path().clone();
+
+        let restart_state = self.restart_state.unwrap_or_default();
+        let state = match restart_state {
+            RestartState::Restore => {
+                old_state.replay_processed();
+                old_state
+            }
+            RestartState::Fresh => {
+                debug!(
+                    "Children({}): Dropping the state of Child({}).",
+                    self.id(),
+                    id
+                );
+                let state = Arc::new(Box::pin(self.new_state(&id)));
+
+                // The supervisor restores the new state if the element
+                // faults again.
+                let parent_id = self.bcast.id().clone();
+                let msg = BastionMessage::instantiated_child(parent_id, id.clone(), state.clone());
+                let env =
+                    Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
+                self.bcast.send_parent(env).ok();
+
+                state
+            }
+        };
+
         let child_ref = ChildRef::new(id.clone(), sender.clone(), self.name(), path);
+        #[cfg(feature = "inspector")]
+        let child_ref = child_ref.with_mailbox_probe(state.mailbox_probe().clone());
+        let child_ref = child_ref
+            .with_poll_site(state.poll_site().clone())
+            .with_mailbox_depth(state.mailbox_depth().clone());
+        self.last_seen
+            .insert(id.clone(), child_ref.last_seen_handle().clone());
+        self.poll_sites
+            .insert(id.clone(), child_ref.poll_site().clone());
+        self.states.insert(id.clone(), state.clone());
+        #[cfg(feature = "metrics")]
+        {
+            self.metrics
+                .register(id.clone(), state.clone(), child_ref.stats().clone());
+            self.metrics.restarted();
+        }
+        if let Some(tree) = &self.tree {
+            tree.restarted();
+            tree.launched(id.clone(), child_ref.stats().clone());
+        }
 
         let children = self.as_ref();
         let supervisor = self.bcast.parent().clone().into_supervisor();
@@ -724,13 +3843,14 @@ impl Children {
             child_ref.clone(),
             children,
             supervisor,
-            old_state.clone(),
-        );
+            state.clone(),
+        )
+        .with_restarted(restart_state);
         let exec = (self.init.0)(ctx);
 
         self.bcast.register(&bcast);
 
-        let msg = BastionMessage::set_state(old_state);
+        let msg = BastionMessage::set_state(state);
         let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
         self.bcast.send_child(&id, env);
 
@@ -745,14 +3865,24 @@ impl Children {
         debug!("Children({}): Restarting Child({}).", self.id(), bcast.id());
         let callbacks = self.callbacks.clone();
         let state = Arc::new(Box::pin(ContextState::new()));
-        let child = Child::new(exec, callbacks, bcast, state, child_ref);
+        let child = Child::new(exec, callbacks, bcast, state, child_ref)
+            .with_poll_hooks(self.poll_hooks.clone())
+            .with_scheduling_weight(self.scheduling_weight);
+        #[cfg(feature = "journal")]
+        let child = child.with_journal(self.journal.clone());
+        #[cfg(feature = "chaos")]
+        let child = child.with_chaos(self.next_chaos());
         debug!(
             "Children({}): Launching faulted Child({}).",
             self.id(),
             child.id(),
         );
         let id = child.id().clone();
-        let launched = child.launch();
+        let worker = self.pin_worker(&id);
+        let launched = child.launch(&self.executor, worker);
+        if let Some(parking) = &self.parking {
+            parking.unpark(&id);
+        }
         self.launched.insert(id, (sender, launched));
     }
 
@@ -763,6 +3893,17 @@ impl Children {
             id,
         );
         self.launched.remove_entry(id);
+        self.pinned_workers.remove(id);
+        self.delayed_restarts.remove(id);
+        self.last_seen.remove(id);
+        self.poll_sites.remove(id);
+        self.states.remove(id);
+        self.restarts.remove(id);
+        #[cfg(feature = "metrics")]
+        self.metrics.unregister(id);
+        if let Some(tree) = &self.tree {
+            tree.dropped(Some(id));
+        }
 
         #[cfg(feature = "scaling")]
         self.update_actors_count_stats();
@@ -777,7 +3918,10 @@ impl Children {
             Envelope {
                 msg: BastionMessage::Stop,
                 ..
-            } => self.stop_children().await?,
+            } => {
+                self.drain().await;
+                self.stop_children().await?
+            }
             Envelope {
                 msg: BastionMessage::Kill,
                 ..
@@ -809,6 +3953,15 @@ impl Children {
                 msg: BastionMessage::Message(ref message),
                 ..
             } => {
+                if self.lazy {
+                    debug!(
+                        "Children({}): Received the first message, launching.",
+                        self.id()
+                    );
+                    self.lazy = false;
+                    self.launch_elems();
+                }
+
                 debug!(
                     "Children({}): Broadcasting a message: {:?}",
                     self.id(),
@@ -817,9 +3970,14 @@ impl Children {
                 self.bcast.send_children(envelope);
             }
             Envelope {
-                msg: BastionMessage::RestartRequired { id, parent_id },
+                msg:
+                    BastionMessage::RestartRequired {
+                        id,
+                        parent_id,
+                        reason,
+                    },
                 ..
-            } => self.request_restarting_child(&id, &parent_id),
+            } => self.request_restarting_child(&id, &parent_id, reason),
             Envelope {
                 msg: BastionMessage::FinishedChild { .. },
                 ..
@@ -831,7 +3989,12 @@ impl Children {
             Envelope {
                 msg: BastionMessage::RestoreChild { id, state },
                 ..
-            } => self.restart_child(&id, state),
+            } => {
+                // The resources are initialized again by the restarted
+                // element.
+                self.resources.release().await;
+                self.restart_child(&id, state)
+            }
             Envelope {
                 msg: BastionMessage::DropChild { id },
                 ..
@@ -851,7 +4014,28 @@ impl Children {
             Envelope {
                 msg: BastionMessage::Heartbeat,
                 ..
-            } => {}
+            } => self.handle_heartbeat(),
+            Envelope {
+                msg: BastionMessage::Reconfigure(reconfigure),
+                ..
+            } => self.reconfigure(reconfigure),
+            Envelope {
+                msg: BastionMessage::Upgrade(init),
+                ..
+            } => self.upgrade(init).await,
+            Envelope {
+                msg: BastionMessage::PauseRestarts | BastionMessage::ResumeRestarts,
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::CircuitTimeout { .. },
+                ..
+            } => unreachable!(),
+            #[cfg(feature = "chaos")]
+            Envelope {
+                msg: BastionMessage::Fault,
+                ..
+            } => unreachable!(),
         }
 
         Ok(())
@@ -896,6 +4080,12 @@ impl Children {
 
     #[cfg(feature = "scaling")]
     async fn autoresize_group(&mut self) {
+        // The elements of a lazily started group are only launched
+        // once it receives its first message.
+        if self.lazy {
+            return;
+        }
+
         match self.resizer.scale(&self.launched).await {
             ScalingRule::Upscale(count) => {
                 for _ in 0..count {
@@ -919,6 +4109,30 @@ impl Children {
         state.set_actor_stats(self.resizer.actor_stats());
     }
 
+    /// Polls the handles of the elements (or only of those which
+    /// aren't parked) to be woken once they complete.
+    async fn poll_launched(&mut self) {
+        let parking = match &self.parking {
+            Some(parking) => parking,
+            None => {
+                for (_, launched) in self.launched.values_mut() {
+                    let _ = poll!(launched);
+                }
+
+                return;
+            }
+        };
+
+        let waker = future::poll_fn(|ctx| Poll::Ready(ctx.waker().clone())).await;
+        parking.register(&waker);
+        for (id, waker) in parking.ready() {
+            if let Some((_, launched)) = self.launched.get_mut(&id) {
+                let mut ctx = Context::from_waker(&waker);
+                let _ = Pin::new(launched).poll(&mut ctx);
+            }
+        }
+    }
+
     async fn run(mut self) -> Self {
         debug!("Children({}): Launched.", self.id());
 
@@ -929,8 +4143,10 @@ impl Children {
             #[cfg(feature = "scaling")]
             self.autoresize_group().await;
 
-            for (_, launched) in self.launched.values_mut() {
-                let _ = poll!(launched);
+            self.poll_launched().await;
+
+            if let Some(tree) = &self.tree {
+                tree.set_children(self.launched.len());
             }
 
             match poll!(&mut self.bcast.next()) {
@@ -973,7 +4189,58 @@ impl Children {
         }
     }
 
-    pub(crate) fn launch_child(&mut self) {
+    // Returns the state of the watermarks of the mailbox of the
+    // element with the given id, if any.
+    fn watermarks(&self, id: &BastionId) -> Option<WatermarkState> {
+        let watermarks = self.watermarks.clone()?;
+        match self.mailbox_capacity {
+            Some(capacity) => Some(WatermarkState::new(watermarks, id.clone(), capacity)),
+            None => {
+                warn!(
+                    "Children({}): Ignoring the mailbox watermarks of an unbounded mailbox.",
+                    self.id()
+                );
+                None
+            }
+        }
+    }
+
+    /// Returns a brand new state for the element with the given
+    /// identifier.
+    fn new_state(&self, id: &BastionId) -> ContextState {
+        // The dead letters are launched while the system is being
+        // initialized, without any dispatcher to look up.
+        let dispatcher_types = self.dispatcher_types();
+        let priority_queues = if dispatcher_types.is_empty() {
+            Vec::new()
+        } else {
+            SYSTEM.dispatcher().queues(&dispatcher_types)
+        };
+
+        #[allow(unused_mut)]
+        let mut state = ContextState::new()
+            .with_replay(self.replay.clone())
+            .with_redelivery(self.redelivery.clone())
+            .with_resources(self.resources.clone())
+            .with_demand(self.demand.clone())
+            .with_quota(self.quota.clone())
+            .with_mailbox_capacity(self.mailbox_capacity)
+            .with_watermarks(self.watermarks(id))
+            .with_activity(self.activity.clone())
+            .with_readiness(self.readiness.clone())
+            .with_panic_isolation(self.isolate_panics)
+            .with_dispatcher_scope(self.dispatcher_scope.clone())
+            .with_sampler(self.sampler.clone())
+            .with_fair_polling(self.fair_polling.clone())
+            .with_drain(self.drain.as_ref().map(|(drain, _)| drain.clone()))
+            .with_priority_queues(priority_queues);
+        #[cfg(feature = "scaling")]
+        self.init_data_for_scaling(&mut state);
+
+        state
+    }
+
+    pub(crate) fn launch_child(&mut self) -> BastionId {
         let name = self.name();
         let parent = Parent::children(self.as_ref());
         let bcast = Broadcast::new(parent, BastionPathElement::Child(BastionId::new()));
@@ -983,16 +4250,27 @@ impl Children {
         let sender = bcast.sender().clone();
         let path = bcast.path().clone();
         let child_ref = ChildRef::new(id.clone(), sender.clone(), name, path);
+        self.last_seen
+            .insert(id.clone(), child_ref.last_seen_handle().clone());
 
         let children = self.as_ref();
         let supervisor = self.bcast.parent().clone().into_supervisor();
 
-        #[allow(unused_mut)]
-        let mut state = ContextState::new();
-        #[cfg(feature = "scaling")]
-        self.init_data_for_scaling(&mut state);
-
-        let state = Arc::new(Box::pin(state));
+        let state = Arc::new(Box::pin(self.new_state(&id)));
+        #[cfg(feature = "metrics")]
+        self.metrics
+            .register(id.clone(), state.clone(), child_ref.stats().clone());
+        if let Some(tree) = &self.tree {
+            tree.launched(id.clone(), child_ref.stats().clone());
+        }
+        #[cfg(feature = "inspector")]
+        let child_ref = child_ref.with_mailbox_probe(state.mailbox_probe().clone());
+        let child_ref = child_ref
+            .with_poll_site(state.poll_site().clone())
+            .with_mailbox_depth(state.mailbox_depth().clone());
+        self.poll_sites
+            .insert(id.clone(), child_ref.poll_site().clone());
+        self.states.insert(id.clone(), state.clone());
 
         let ctx = BastionContext::new(
             id.clone(),
@@ -1020,14 +4298,32 @@ impl Children {
             bcast.id()
         );
         let callbacks = self.callbacks.clone();
-        let child = Child::new(exec, callbacks, bcast, state, child_ref);
+        let child = Child::new(exec, callbacks, bcast, state, child_ref)
+            .with_poll_hooks(self.poll_hooks.clone())
+            .with_scheduling_weight(self.scheduling_weight);
+        #[cfg(feature = "journal")]
+        let child = child.with_journal(self.journal.clone());
+        #[cfg(feature = "chaos")]
+        let child = child.with_chaos(self.next_chaos());
         debug!("Children({}): Launching Child({}).", self.id(), child.id());
         let id = child.id().clone();
-        let launched = child.launch();
-        self.launched.insert(id, (sender, launched));
+        let worker = self.pin_worker(&id);
+        let launched = child.launch(&self.executor, worker);
+        if let Some(parking) = &self.parking {
+            parking.unpark(&id);
+        }
+        self.launched.insert(id.clone(), (sender, launched));
+
+        id
     }
 
     pub(crate) fn launch_heartbeat(&mut self) {
+        let init = self.get_heartbeat_fut();
+        let id = self.launch_helper(&init);
+        self.heartbeat = Some(id);
+    }
+
+    fn launch_helper(&mut self, init: &Init) -> BastionId {
         let name = self.name();
         let parent = Parent::children(self.as_ref());
         let bcast = Broadcast::new(parent, BastionPathElement::Child(BastionId::new()));
@@ -1044,48 +4340,94 @@ impl Children {
         let state = Arc::new(Box::pin(ContextState::new()));
 
         let ctx = BastionContext::new(id, child_ref.clone(), children, supervisor, state.clone());
-        let init = self.get_heartbeat_fut();
         let exec = (init.0)(ctx);
         self.bcast.register(&bcast);
 
         debug!(
-            "Children({}): Initializing HeartbeatChild({}).",
+            "Children({}): Initializing HelperChild({}).",
             self.id(),
             bcast.id()
         );
         let callbacks = self.callbacks.clone();
         let child = Child::new(exec, callbacks, bcast, state, child_ref);
         debug!(
-            "Children({}): Launching HeartbeatChild({}).",
+            "Children({}): Launching HelperChild({}).",
             self.id(),
             child.id()
         );
         let id = child.id().clone();
-        let launched = child.launch();
-        self.helper_actors.insert(id, (sender, launched));
+        let launched = child.launch(&self.executor, None);
+        self.helper_actors.insert(id.clone(), (sender, launched));
+
+        id
     }
 
     pub(crate) fn launch_elems(&mut self) {
+        if self.lazy {
+            debug!(
+                "Children({}): Deferring the launch of the elements until the first message.",
+                self.id()
+            );
+            return;
+        }
+
         debug!("Children({}): Launching elements.", self.id());
-        for _ in 0..self.redundancy {
-            self.launch_child();
+        if let Some(readiness) = &self.readiness {
+            readiness.expect(self.redundancy, &self.dispatcher_types());
+        }
+
+        for index in 0..self.redundancy {
+            let id = self.launch_child();
+            if let Some(restart) = self.element_restarts.get(&index) {
+                self.restarts.insert(id, *restart);
+            }
         }
 
-        self.launch_heartbeat();
+        if self.heartbeats {
+            self.launch_heartbeat();
+        }
+
+        if let Some(timeout) = self.idle_timeout {
+            self.activity.touch();
+            let init = self.get_idle_watcher_fut(timeout);
+            self.launch_helper(&init);
+        }
+
+        #[cfg(feature = "metrics")]
+        {
+            if let Some(interval) = self.stats_collector {
+                let init = self.get_stats_collector_fut(interval);
+                self.launch_helper(&init);
+            }
+        }
+
+        let helpers = std::mem::take(&mut self.helpers);
+        for (idx, init) in helpers.iter().enumerate() {
+            if let Some(quota) = &self.quota {
+                if !quota.admit_helper(idx) {
+                    continue;
+                }
+            }
+
+            self.launch_helper(init);
+        }
+        self.helpers = helpers;
     }
 
     pub(crate) fn launch(self) -> RecoverableHandle<Self> {
         debug!("Children({}): Launching.", self.id());
         let stack = self.stack();
-        pool::spawn(self.run(), stack)
+        let executor = self.executor.clone();
+        executor.spawn(self.run(), stack)
     }
 
     /// Registers all declared local dispatchers in the global dispatcher.
     pub(crate) fn register_dispatchers(&self) -> AnyResult<()> {
-        let global_dispatcher = SYSTEM.dispatcher();
-
-        for dispatcher in self.dispatchers.iter() {
-            global_dispatcher.register_dispatcher(dispatcher)?;
+        // The system isn't accessed if there is nothing to register
+        // since the dead letters are created while it is initialized.
+        let dispatchers = self.supervisor_dispatchers.iter();
+        for dispatcher in dispatchers.chain(self.dispatchers.iter()) {
+            SYSTEM.dispatcher().register_dispatcher(dispatcher)?;
         }
         Ok(())
     }
@@ -1100,3 +4442,118 @@ impl Children {
         Ok(())
     }
 }
+
+#[derive(Debug, Clone, Default)]
+/// The settings of a running children group to change, sent to it
+/// with [`ChildrenRef::reconfigure`] to tune it without restarting
+/// it.
+///
+/// The settings which aren't set are left unchanged.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::children::Reconfigure;
+/// # use std::time::Duration;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let children_ref = Bastion::children(|children| {
+///     children.with_exec(|ctx| {
+///         async move {
+///             // ...
+///             # Ok(())
+///         }
+///     })
+/// }).expect("Couldn't create the children group.");
+///
+/// # Bastion::start();
+/// let reconfigure = Reconfigure::new()
+///     .with_heartbeat_tick(Duration::from_secs(5))
+///     .with_stalled_restart(3);
+///
+/// children_ref
+///     .reconfigure(reconfigure)
+///     .expect("Couldn't reconfigure the children group.");
+/// #
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`ChildrenRef::reconfigure`]: crate::children_ref::ChildrenRef::reconfigure
+pub struct Reconfigure {
+    heartbeat_tick: Option<Duration>,
+    stalled_ticks: Option<Option<u32>>,
+    #[cfg(feature = "scaling")]
+    lower_bound: Option<u64>,
+    #[cfg(feature = "scaling")]
+    upper_bound: Option<UpperBound>,
+}
+
+impl Reconfigure {
+    /// Creates a new reconfiguration which doesn't change any
+    /// setting.
+    pub fn new() -> Self {
+        Reconfigure::default()
+    }
+
+    /// Changes the time interval between heartbeats (see
+    /// [`Children::with_heartbeat_tick`]).
+    pub fn with_heartbeat_tick(mut self, interval: Duration) -> Self {
+        self.heartbeat_tick = Some(interval);
+        self
+    }
+
+    /// Changes the number of heartbeat ticks after which a stalled
+    /// element is restarted (see [`Children::with_stalled_restart`]).
+    pub fn with_stalled_restart(mut self, ticks: u32) -> Self {
+        self.stalled_ticks = Some(Some(ticks.max(1)));
+        self
+    }
+
+    /// Stops restarting the stalled elements.
+    pub fn without_stalled_restart(mut self) -> Self {
+        self.stalled_ticks = Some(None);
+        self
+    }
+
+    /// Changes the minimal number of elements of the group (see
+    /// [`OptimalSizeExploringResizer::with_lower_bound`]).
+    ///
+    /// This method is available only with the `scaling` feature flag.
+    ///
+    /// [`OptimalSizeExploringResizer::with_lower_bound`]: crate::resizer::OptimalSizeExploringResizer::with_lower_bound
+    #[cfg(feature = "scaling")]
+    #[cfg_attr(feature = "docs", doc(cfg(scaling)))]
+    pub fn with_lower_bound(mut self, lower_bound: u64) -> Self {
+        self.lower_bound = Some(lower_bound.max(1));
+        self
+    }
+
+    /// Changes the maximal number of elements of the group (see
+    /// [`OptimalSizeExploringResizer::with_upper_bound`]).
+    ///
+    /// This method is available only with the `scaling` feature flag.
+    ///
+    /// [`OptimalSizeExploringResizer::with_upper_bound`]: crate::resizer::OptimalSizeExploringResizer::with_upper_bound
+    #[cfg(feature = "scaling")]
+    #[cfg_attr(feature = "docs", doc(cfg(scaling)))]
+    pub fn with_upper_bound(mut self, upper_bound: UpperBound) -> Self {
+        self.upper_bound = Some(upper_bound);
+        self
+    }
+}
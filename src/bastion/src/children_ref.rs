@@ -1,17 +1,30 @@
 //!
 //! Allows users to communicate with children through the mailboxes.
 use crate::broadcast::Sender;
+use crate::child::Init;
 use crate::child_ref::ChildRef;
-use crate::context::BastionId;
+use crate::children::Reconfigure;
+use crate::context::{BastionContext, BastionId};
 use crate::dispatcher::DispatcherType;
-use crate::envelope::Envelope;
+use crate::envelope::{Envelope, SignedMessage};
+use crate::errors::AskError;
 use crate::message::{BastionMessage, Message};
 use crate::path::BastionPath;
 use crate::system::SYSTEM;
+use futures::channel::oneshot;
+use futures_timer::Delay;
+use rand::Rng;
 use std::cmp::{Eq, PartialEq};
 use std::fmt::Debug;
-use std::sync::Arc;
-use tracing::{debug, trace};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, trace, warn};
+
+// How often `stop_with_deadline` checks whether the elements
+// of the group stopped.
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(10);
 
 #[derive(Debug, Clone)]
 /// A "reference" to a children group, allowing to communicate
@@ -22,6 +35,37 @@ pub struct ChildrenRef {
     path: Arc<BastionPath>,
     children: Vec<ChildRef>,
     dispatchers: Vec<DispatcherType>,
+    readiness: Option<Arc<Readiness>>,
+}
+
+#[derive(Debug, Clone)]
+/// What is known about an element of a children group when picking
+/// one of them (see [`ChildrenRef::pick`]).
+pub struct ChildInfo {
+    child: ChildRef,
+    mailbox_len: usize,
+    last_seen: Instant,
+    alive: bool,
+}
+
+#[derive(Debug)]
+/// The readiness of the elements of a children group which is only
+/// considered started once all of them signaled it.
+pub(crate) struct Readiness {
+    inner: Mutex<ReadinessInner>,
+}
+
+#[derive(Debug, Default)]
+struct ReadinessInner {
+    // The number of elements which have to signal their readiness,
+    // once they are launched.
+    expected: Option<usize>,
+    // The elements which signaled their readiness before the group
+    // was started.
+    ready: Vec<ChildRef>,
+    started: bool,
+    // The futures waiting for the group to be started.
+    waiters: Vec<oneshot::Sender<()>>,
 }
 
 impl ChildrenRef {
@@ -38,9 +82,15 @@ impl ChildrenRef {
             path,
             children,
             dispatchers,
+            readiness: None,
         }
     }
 
+    pub(crate) fn with_readiness(mut self, readiness: Option<Arc<Readiness>>) -> Self {
+        self.readiness = readiness;
+        self
+    }
+
     /// Returns the identifier of the children group this `ChildrenRef`
     /// is referencing.
     ///
@@ -150,6 +200,127 @@ impl ChildrenRef {
         &self.children
     }
 
+    /// Returns one of the elements of the children group this
+    /// `ChildrenRef` is referencing, chosen at random among the ones
+    /// which are still alive while avoiding the busiest ones: out
+    /// of two elements picked at random, the one with the fewest
+    /// messages in its mailbox is returned.
+    ///
+    /// This spreads the messages of the senders which don't need a
+    /// dispatcher over the whole group, instead of always sending
+    /// them to the first element.
+    ///
+    /// This method returns `None` if none of the elements is alive.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// if let Some(child_ref) = children_ref.any() {
+    ///     child_ref.tell_anonymously("A message containing data.").ok();
+    /// }
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn any(&self) -> Option<ChildRef> {
+        self.pick(|infos| {
+            let alive: Vec<_> = infos
+                .iter()
+                .enumerate()
+                .filter(|(_, info)| info.is_alive())
+                .collect();
+            if alive.is_empty() {
+                return None;
+            }
+
+            let mut rng = rand::thread_rng();
+            let first = alive[rng.gen_range(0..alive.len())];
+            let second = alive[rng.gen_range(0..alive.len())];
+            if second.1.mailbox_len() < first.1.mailbox_len() {
+                Some(second.0)
+            } else {
+                Some(first.0)
+            }
+        })
+    }
+
+    /// Returns the element of the children group this `ChildrenRef`
+    /// is referencing chosen by `picker`, given what is known about
+    /// every element (whether it is alive, how many messages are
+    /// waiting in its mailbox and when it last handled a message).
+    ///
+    /// This method returns `None` if `picker` returned `None` or an
+    /// index which isn't the one of an element.
+    ///
+    /// # Arguments
+    ///
+    /// * `picker` - The function returning the index of the element
+    ///   to pick, if any.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// // Picks the alive element with the fewest queued messages.
+    /// let least_busy = children_ref.pick(|infos| {
+    ///     infos
+    ///         .iter()
+    ///         .enumerate()
+    ///         .filter(|(_, info)| info.is_alive())
+    ///         .min_by_key(|(_, info)| info.mailbox_len())
+    ///         .map(|(idx, _)| idx)
+    /// });
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn pick<F>(&self, picker: F) -> Option<ChildRef>
+    where
+        F: FnOnce(&[ChildInfo]) -> Option<usize>,
+    {
+        let infos: Vec<_> = self.children.iter().map(ChildInfo::new).collect();
+        let idx = picker(&infos)?;
+        trace!("ChildrenRef({}): Picked element {}.", self.id(), idx);
+        infos.into_iter().nth(idx).map(|info| info.child)
+    }
+
     /// Sends a message to the children group this `ChildrenRef`
     /// is referencing which will then send it to all of its
     /// elements.
@@ -307,6 +478,279 @@ impl ChildrenRef {
         self.send(env).map_err(|_| ())
     }
 
+    /// Sends a message to the children group this `ChildrenRef`
+    /// is referencing to tell it to change its settings as
+    /// described by `reconfigure`, without restarting it.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(())`
+    /// otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `reconfigure` - The settings to change.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::children::Reconfigure;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// let reconfigure = Reconfigure::new().with_heartbeat_tick(Duration::from_secs(5));
+    /// children_ref.reconfigure(reconfigure).expect("Couldn't send the message.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    #[allow(clippy::result_unit_err)]
+    pub fn reconfigure(&self, reconfigure: Reconfigure) -> Result<(), ()> {
+        debug!("ChildrenRef({}): Reconfiguring.", self.id());
+        let msg = BastionMessage::reconfigure(reconfigure);
+        let env = Envelope::from_dead_letters(msg);
+        self.send(env).map_err(|_| ())
+    }
+
+    /// Sends a message to the children group this `ChildrenRef`
+    /// is referencing to tell it to upgrade its elements to the
+    /// given closure, without downtime.
+    ///
+    /// The closure replaces the one set with [`Children::with_exec`]
+    /// and the elements are rolled over to it one by one: for each
+    /// of them, a replacement is launched, the old element stops
+    /// receiving the messages broadcasted to the group and is given
+    /// some time to drain its mailbox, then it is stopped.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(())`
+    /// otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `init` - The closure taking a [`BastionContext`] and
+    ///   returning a [`Future`] that the elements of the group
+    ///   will execute from now on.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// children_ref
+    ///     .upgrade(|ctx: BastionContext| {
+    ///         async move {
+    ///             // The new behavior of the elements...
+    ///             Ok(())
+    ///         }
+    ///     })
+    ///     .expect("Couldn't send the message.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_exec`]: crate::children::Children::with_exec
+    /// [`BastionContext`]: crate::context::BastionContext
+    #[allow(clippy::result_unit_err)]
+    pub fn upgrade<I, F>(&self, init: I) -> Result<(), ()>
+    where
+        I: Fn(BastionContext) -> F + Send + 'static,
+        F: Future<Output = Result<(), ()>> + Send + 'static,
+    {
+        debug!("ChildrenRef({}): Upgrading.", self.id());
+        let msg = BastionMessage::upgrade(Init::new(init));
+        let env = Envelope::from_dead_letters(msg);
+        self.send(env).map_err(|_| ())
+    }
+
+    /// Stops the children group this `ChildrenRef` is referencing
+    /// in two phases: every element is first asked to stop
+    /// gracefully and, once `deadline` has elapsed, the group is
+    /// killed along with the elements that were still running.
+    ///
+    /// This method returns the list of [`ChildRef`] referencing
+    /// the elements that had to be force-killed (which is empty if
+    /// every element stopped before the deadline), or `Err(())` if
+    /// the group couldn't be reached.
+    ///
+    /// # Arguments
+    ///
+    /// * `deadline` - How long the elements are given to stop
+    ///   before being killed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # Bastion::start();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// let killed = run!(children_ref.stop_with_deadline(Duration::from_millis(100)))
+    ///     .expect("Couldn't stop the children group.");
+    ///
+    /// for child in killed {
+    ///     println!("Child({}) had to be killed.", child.id());
+    /// }
+    /// #
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub async fn stop_with_deadline(&self, deadline: Duration) -> Result<Vec<ChildRef>, ()> {
+        debug!(
+            "ChildrenRef({}): Stopping with a deadline of {:?}.",
+            self.id(),
+            deadline
+        );
+        for child in self.elems() {
+            // The element might have already stopped.
+            child.stop().ok();
+        }
+
+        let started_at = Instant::now();
+        while started_at.elapsed() < deadline {
+            if self.elems().iter().all(ChildRef::is_stopped) {
+                break;
+            }
+
+            // The deadline might have elapsed since it was checked.
+            let remaining = deadline.saturating_sub(started_at.elapsed());
+            Delay::new(remaining.min(STOP_CHECK_INTERVAL)).await;
+        }
+
+        let killed: Vec<ChildRef> = self
+            .elems()
+            .iter()
+            .filter(|child| !child.is_stopped())
+            .cloned()
+            .collect();
+
+        if killed.is_empty() {
+            self.stop()?;
+        } else {
+            warn!(
+                "ChildrenRef({}): {} elements didn't stop before the deadline, killing.",
+                self.id(),
+                killed.len()
+            );
+            self.kill()?;
+        }
+
+        Ok(killed)
+    }
+
+    /// Waits until the children group this `ChildrenRef` is
+    /// referencing is started.
+    ///
+    /// If the group waits for its elements to signal their readiness
+    /// (see [`Children::with_readiness`]), this happens once all of
+    /// them called [`BastionContext::notify_started`]. Otherwise, the
+    /// group is considered started as soon as its elements were
+    /// launched and this method returns immediately.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// let children_ref = Bastion::children(|children| {
+    ///     children
+    ///         .with_readiness()
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // Warms the caches up...
+    ///                 ctx.notify_started();
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    ///
+    /// # Bastion::start();
+    /// run!(children_ref.wait_started());
+    /// // The elements of the group are ready.
+    /// #
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_readiness`]: crate::children::Children::with_readiness
+    /// [`BastionContext::notify_started`]: crate::context::BastionContext::notify_started
+    pub async fn wait_started(&self) {
+        let started = match self.readiness.as_ref().and_then(|r| r.wait()) {
+            Some(started) => started,
+            None => return,
+        };
+
+        debug!(
+            "ChildrenRef({}): Waiting for the group to start.",
+            self.id()
+        );
+        started.await.ok();
+    }
+
     pub(crate) fn send(&self, env: Envelope) -> Result<(), Envelope> {
         trace!("ChildrenRef({}): Sending message: {:?}", self.id(), env);
         self.sender.unbounded_send(env).or_else(|err| {
@@ -326,6 +770,40 @@ impl ChildrenRef {
     pub(crate) fn sender(&self) -> &Sender {
         &self.sender
     }
+
+    /// Asks `msg` to the elements of the group in a round-robin
+    /// fashion, `next` being the index of the next one to ask, and
+    /// waits for the answer for up to `timeout` if given.
+    ///
+    /// The elements which can't be asked (eg. because they were
+    /// stopped) are skipped, and [`AskError::Unavailable`] is
+    /// returned if none of them could be.
+    pub(crate) async fn ask_round_robin<M: Message>(
+        &self,
+        next: &AtomicUsize,
+        msg: M,
+        timeout: Option<Duration>,
+    ) -> Result<SignedMessage, AskError> {
+        let elems = self.elems();
+        let mut msg = msg;
+        for _ in 0..elems.len() {
+            let next = next.fetch_add(1, Ordering::Relaxed);
+            let child = &elems[next % elems.len()];
+            trace!(
+                "ChildrenRef({}): Asking Child({}): {:?}",
+                self.id(),
+                child.id(),
+                msg
+            );
+            match child.ask_anonymously(msg) {
+                Ok(answer) => return answer.within(timeout).await,
+                Err(unsent) => msg = unsent,
+            }
+        }
+
+        debug!("ChildrenRef({}): No element can be asked.", self.id());
+        Err(AskError::Unavailable)
+    }
 }
 
 impl PartialEq for ChildrenRef {
@@ -335,3 +813,113 @@ impl PartialEq for ChildrenRef {
 }
 
 impl Eq for ChildrenRef {}
+
+impl ChildInfo {
+    fn new(child: &ChildRef) -> Self {
+        ChildInfo {
+            child: child.clone(),
+            mailbox_len: child.mailbox_len(),
+            last_seen: child.last_seen(),
+            alive: !child.is_stopped(),
+        }
+    }
+
+    /// Returns the [`ChildRef`] referencing the element.
+    pub fn child(&self) -> &ChildRef {
+        &self.child
+    }
+
+    /// Returns the number of messages waiting in the mailbox of the
+    /// element.
+    pub fn mailbox_len(&self) -> usize {
+        self.mailbox_len
+    }
+
+    /// Returns the last time the element handled a message.
+    pub fn last_seen(&self) -> Instant {
+        self.last_seen
+    }
+
+    /// Returns whether the element is still running.
+    pub fn is_alive(&self) -> bool {
+        self.alive
+    }
+}
+
+impl Readiness {
+    pub(crate) fn new() -> Self {
+        Readiness {
+            inner: Mutex::new(ReadinessInner::default()),
+        }
+    }
+
+    /// Sets the number of elements which have to signal their
+    /// readiness for the group to be started.
+    pub(crate) fn expect(&self, expected: usize, dispatchers: &[DispatcherType]) {
+        // FIXME: panics?
+        let mut inner = self.inner.lock().unwrap();
+        inner.expected = Some(expected);
+        inner.try_start(dispatchers);
+    }
+
+    /// Records that `child` is ready, registering it into the
+    /// dispatchers of its group if the group is started.
+    pub(crate) fn signal(&self, child: &ChildRef, dispatchers: &[DispatcherType]) {
+        // FIXME: panics?
+        let mut inner = self.inner.lock().unwrap();
+        if inner.started {
+            register(child, dispatchers);
+            return;
+        }
+
+        if inner.ready.iter().all(|ready| ready.id() != child.id()) {
+            inner.ready.push(child.clone());
+        }
+        inner.try_start(dispatchers);
+    }
+
+    /// Returns a future resolving once the group is started, or
+    /// `None` if it already is.
+    fn wait(&self) -> Option<oneshot::Receiver<()>> {
+        // FIXME: panics?
+        let mut inner = self.inner.lock().unwrap();
+        if inner.started {
+            return None;
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        inner.waiters.push(sender);
+        Some(receiver)
+    }
+}
+
+impl ReadinessInner {
+    fn try_start(&mut self, dispatchers: &[DispatcherType]) {
+        match self.expected {
+            Some(expected) if self.ready.len() >= expected => (),
+            _ => return,
+        }
+
+        debug!(
+            "Readiness: All the {} elements are ready.",
+            self.ready.len()
+        );
+        self.started = true;
+        for child in self.ready.drain(..) {
+            register(&child, dispatchers);
+        }
+        for waiter in self.waiters.drain(..) {
+            waiter.send(()).ok();
+        }
+    }
+}
+
+// Adds the element into each registry declared by its group.
+fn register(child: &ChildRef, dispatchers: &[DispatcherType]) {
+    let global_dispatcher = SYSTEM.dispatcher();
+    // FIXME: Pass the module name explicitly?
+    let module_name = module_path!().to_string();
+    if let Err(e) = global_dispatcher.register(dispatchers, child, module_name) {
+        warn!("couldn't add actor to the registry: {}", e);
+    }
+}
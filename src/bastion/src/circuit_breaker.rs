@@ -0,0 +1,359 @@
+//!
+//! A circuit breaker protecting the callers of an element or a
+//! children group which keeps failing.
+//!
+//! A [`CircuitBreaker`] asks questions to its target and tracks
+//! whether they were answered on time. Once a number of consecutive
+//! questions failed, the circuit *opens* and every question fails
+//! fast with [`AskError::Open`] instead of waiting for a target which
+//! isn't going to answer. After a while, the circuit becomes
+//! *half-open* and lets a single question through to probe the
+//! target: the circuit *closes* again if it is answered, or opens
+//! again otherwise.
+//!
+//! [`AskError::Open`]: crate::errors::AskError::Open
+use crate::child_ref::ChildRef;
+use crate::children_ref::ChildrenRef;
+use crate::envelope::SignedMessage;
+use crate::errors::AskError;
+use crate::message::Message;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::atomic::AtomicUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, trace};
+
+/// The default number of consecutive failures opening the circuit.
+const DEFAULT_FAILURE_THRESHOLD: usize = 5;
+/// The default time the circuit stays open before being half-open.
+const DEFAULT_RESET_TIMEOUT: Duration = Duration::from_secs(30);
+/// The default time given to the target to answer a question.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+type StateChangeCallback = Arc<dyn Fn(CircuitState, CircuitState) + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The state of a [`CircuitBreaker`].
+pub enum CircuitState {
+    /// The questions are asked to the target.
+    Closed,
+    /// The questions fail fast without being asked.
+    Open,
+    /// A single question is asked to the target to probe it,
+    /// the others failing fast.
+    HalfOpen,
+}
+
+/// A cloneable circuit breaker asking questions to an element or
+/// to the elements of a children group, in a round-robin fashion.
+///
+/// Cloning a `CircuitBreaker` returns a new breaker sharing the same
+/// state.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::circuit_breaker::CircuitBreaker;
+/// # use std::time::Duration;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let payments = Bastion::children(|children| {
+///     children
+///         .with_exec(|ctx: BastionContext| async move {
+///             loop {
+///                 msg! { ctx.recv().await?,
+///                     _msg: &'static str =!> {
+///                         answer!(ctx, "paid").ok();
+///                     };
+///                     _: _ => ();
+///                 }
+///             }
+///         })
+/// }).expect("Couldn't create the children group.");
+///
+/// let breaker = CircuitBreaker::for_group(payments)
+///     .with_failure_threshold(3)
+///     .with_timeout(Duration::from_millis(500))
+///     .with_reset_timeout(Duration::from_secs(10))
+///     .with_on_state_change(|from, to| {
+///         println!("payments circuit: {:?} -> {:?}", from, to);
+///     });
+///
+/// # Bastion::start();
+/// # run!(async move {
+/// match breaker.ask("pay").await {
+///     Ok(answer) => {
+///         // Handle the answer...
+///     }
+///     Err(AskError::Open) => {
+///         // The payments are failing, don't even try...
+///     }
+///     Err(err) => {
+///         // The question wasn't answered...
+///     }
+/// }
+/// # });
+/// #
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    target: Target,
+    // The index of the next element of the group to ask.
+    next: Arc<AtomicUsize>,
+    inner: Arc<Mutex<Circuit>>,
+    failure_threshold: usize,
+    reset_timeout: Duration,
+    timeout: Duration,
+    on_state_change: Option<StateChangeCallback>,
+}
+
+#[derive(Debug, Clone)]
+enum Target {
+    Child(ChildRef),
+    Group(ChildrenRef),
+}
+
+#[derive(Debug)]
+struct Circuit {
+    state: CircuitState,
+    // The number of consecutive failures.
+    failures: usize,
+    // When the circuit was last opened.
+    opened_at: Option<Instant>,
+    // Whether the probe of the half-open circuit is being asked.
+    probing: bool,
+}
+
+// Lets another question probe the half-open circuit if the
+// probe was cancelled before having been answered.
+struct Probe<'a> {
+    breaker: &'a CircuitBreaker,
+    done: bool,
+}
+
+impl CircuitBreaker {
+    /// Creates a new circuit breaker asking questions to `child`.
+    pub fn new(child: ChildRef) -> Self {
+        CircuitBreaker::with_target(Target::Child(child))
+    }
+
+    /// Creates a new circuit breaker asking questions to the
+    /// elements of `children`, in a round-robin fashion.
+    pub fn for_group(children: ChildrenRef) -> Self {
+        CircuitBreaker::with_target(Target::Group(children))
+    }
+
+    fn with_target(target: Target) -> Self {
+        let circuit = Circuit {
+            state: CircuitState::Closed,
+            failures: 0,
+            opened_at: None,
+            probing: false,
+        };
+
+        CircuitBreaker {
+            target,
+            next: Arc::new(AtomicUsize::new(0)),
+            inner: Arc::new(Mutex::new(circuit)),
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            reset_timeout: DEFAULT_RESET_TIMEOUT,
+            timeout: DEFAULT_TIMEOUT,
+            on_state_change: None,
+        }
+    }
+
+    /// Sets how many consecutive questions have to fail for the
+    /// circuit to open, `5` by default.
+    pub fn with_failure_threshold(mut self, threshold: usize) -> Self {
+        self.failure_threshold = threshold.max(1);
+        self
+    }
+
+    /// Sets how long the circuit stays open before letting a
+    /// question probe the target, `30s` by default.
+    pub fn with_reset_timeout(mut self, reset_timeout: Duration) -> Self {
+        self.reset_timeout = reset_timeout;
+        self
+    }
+
+    /// Sets how long the target is given to answer a question,
+    /// `5s` by default.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the callback called with the previous and the new
+    /// states of the circuit every time it changes.
+    pub fn with_on_state_change<C>(mut self, callback: C) -> Self
+    where
+        C: Fn(CircuitState, CircuitState) + Send + Sync + 'static,
+    {
+        self.on_state_change = Some(Arc::new(callback));
+        self
+    }
+
+    /// Returns the current state of the circuit.
+    pub fn state(&self) -> CircuitState {
+        self.inner.lock().unwrap().state
+    }
+
+    /// Asks `msg` to the target if the circuit isn't open, and waits
+    /// for its answer.
+    ///
+    /// This method returns the answer if it succeeded, or an
+    /// [`AskError`] otherwise: [`AskError::Open`] if the circuit is
+    /// open, or the reason why the question failed.
+    pub async fn ask<M: Message>(&self, msg: M) -> Result<SignedMessage, AskError> {
+        let mut probe = self.acquire()?;
+
+        let res = match &self.target {
+            Target::Child(child) => {
+                trace!("CircuitBreaker: Asking Child({}): {:?}", child.id(), msg);
+                match child.ask_anonymously(msg) {
+                    Ok(answer) => answer.within(Some(self.timeout)).await,
+                    Err(_) => Err(AskError::Unavailable),
+                }
+            }
+            Target::Group(children) => {
+                children
+                    .ask_round_robin(&self.next, msg, Some(self.timeout))
+                    .await
+            }
+        };
+
+        self.record(res.is_ok(), probe.is_some());
+        if let Some(probe) = &mut probe {
+            probe.done = true;
+        }
+
+        res
+    }
+
+    // Returns whether a question can be asked, and its probe
+    // if the circuit is half-open.
+    fn acquire(&self) -> Result<Option<Probe<'_>>, AskError> {
+        let mut circuit = self.inner.lock().unwrap();
+        let mut changed = None;
+
+        if circuit.state == CircuitState::Open {
+            match circuit.opened_at {
+                Some(opened_at) if opened_at.elapsed() >= self.reset_timeout => {
+                    circuit.state = CircuitState::HalfOpen;
+                    changed = Some((CircuitState::Open, CircuitState::HalfOpen));
+                }
+                _ => return Err(AskError::Open),
+            }
+        }
+
+        let probe = if circuit.state == CircuitState::HalfOpen {
+            if circuit.probing {
+                drop(circuit);
+                self.changed(changed);
+                return Err(AskError::Open);
+            }
+
+            circuit.probing = true;
+            Some(Probe {
+                breaker: self,
+                done: false,
+            })
+        } else {
+            None
+        };
+
+        drop(circuit);
+        self.changed(changed);
+
+        Ok(probe)
+    }
+
+    // Updates the circuit after a question succeeded or failed,
+    // `probe` being whether it was the probe of the half-open
+    // circuit.
+    fn record(&self, succeeded: bool, probe: bool) {
+        let mut circuit = self.inner.lock().unwrap();
+        let previous = circuit.state;
+
+        // The questions asked before the circuit opened don't tell
+        // whether the target recovered, only its probe does.
+        if previous == CircuitState::HalfOpen && !probe {
+            return;
+        }
+
+        if succeeded {
+            circuit.failures = 0;
+            if previous == CircuitState::HalfOpen {
+                circuit.state = CircuitState::Closed;
+            }
+        } else {
+            circuit.failures += 1;
+            let tripped = previous == CircuitState::HalfOpen
+                || (previous == CircuitState::Closed && circuit.failures >= self.failure_threshold);
+            if tripped {
+                circuit.state = CircuitState::Open;
+                circuit.opened_at = Some(Instant::now());
+            }
+        }
+
+        if previous == CircuitState::HalfOpen {
+            circuit.probing = false;
+        }
+
+        let state = circuit.state;
+        drop(circuit);
+
+        if state != previous {
+            self.changed(Some((previous, state)));
+        }
+    }
+
+    fn changed(&self, changed: Option<(CircuitState, CircuitState)>) {
+        if let Some((from, to)) = changed {
+            info!(
+                "CircuitBreaker: Circuit changed from {:?} to {:?}.",
+                from, to
+            );
+            if let Some(callback) = &self.on_state_change {
+                callback(from, to);
+            }
+        }
+    }
+}
+
+impl<'a> Drop for Probe<'a> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.breaker.inner.lock().unwrap().probing = false;
+        }
+    }
+}
+
+impl Debug for CircuitBreaker {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("CircuitBreaker")
+            .field("target", &self.target)
+            .field("circuit", &self.inner)
+            .field("failure_threshold", &self.failure_threshold)
+            .field("reset_timeout", &self.reset_timeout)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
@@ -1,9 +1,47 @@
+use crate::errors::ConfigError;
+use crate::path::BastionPath;
+use crate::supervisor::SupervisionStrategy;
+use fxhash::FxHashSet;
+use lazy_static::lazy_static;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+/// The name of the children groups which weren't given one, with
+/// the `Fixed` naming.
+const ANONYMOUS_NAME: &str = "__Anonymous__";
+
+lazy_static! {
+    // The configuration the system was last initialized with.
+    static ref CURRENT: RwLock<Config> = RwLock::new(Config::default());
+    // The last counter given to an anonymous name, along with the
+    // names generated so far.
+    static ref ANONYMOUS_NAMES: Mutex<(u64, FxHashSet<String>)> =
+        Mutex::new((0, FxHashSet::default()));
+}
+
 #[derive(Default, Debug, Clone)]
 /// The configuration that should be used to initialize the
 /// system using [`Bastion::init_with`].
 ///
 /// The default behaviors are the following:
 /// - All backtraces are shown (see [`Config::show_backtraces`]).
+/// - Payloads larger than 64KiB are stored out-of-line (see
+///   [`Config::with_offload_threshold`]).
+/// - The executor starts with the number of threads set by the
+///   `BASTION_BLOCKING_THREADS` env var, or 2 (see
+///   [`Config::with_executor_threads`]).
+/// - The root supervisor uses the `OneForOne` strategy (see
+///   [`Config::with_supervision_strategy`]).
+/// - The mailboxes are unbounded (see [`Config::with_mailbox_capacity`]).
+/// - The children groups send a heartbeat to their elements every
+///   60 seconds and don't restart the stalled ones (see
+///   [`Config::with_heartbeat_tick`], [`Config::with_stalled_restart`]
+///   and [`Config::without_heartbeats`]).
+/// - The children groups which weren't given a name are all named
+///   `__Anonymous__` (see [`Config::with_anonymous_naming`]).
+///
+/// The configuration is validated when the system is initialized.
 ///
 /// # Example
 ///
@@ -37,6 +75,40 @@
 /// [`Bastion::init_with`]: crate::Bastion::init_with
 pub struct Config {
     backtraces: Backtraces,
+    offload_threshold: Option<usize>,
+    executor_threads: Option<usize>,
+    supervision_strategy: Option<SupervisionStrategy>,
+    mailbox_capacity: Option<usize>,
+    heartbeat_tick: Option<Duration>,
+    stalled_ticks: Option<u32>,
+    disable_heartbeats: bool,
+    anonymous_naming: AnonymousNaming,
+}
+
+/// Names a children group given its path and a counter.
+type Naming = Arc<dyn Fn(&BastionPath, u64) -> String + Send + Sync>;
+
+#[derive(Clone, Default)]
+/// How the children groups which weren't given a name (with
+/// [`Children::with_name`]) are named, in the logs and the metrics.
+///
+/// Except with `Fixed`, the generated names are unique: a name which
+/// was already generated gets a counter appended to it.
+///
+/// The default naming is `Fixed`.
+///
+/// [`Children::with_name`]: crate::children::Children::with_name
+pub enum AnonymousNaming {
+    /// The groups are all named `__Anonymous__`.
+    #[default]
+    Fixed,
+    /// The groups are named `anonymous-<n>`, `<n>` being a counter
+    /// incremented for every group.
+    Sequential,
+    /// The groups are named by a closure, taking the path of the
+    /// group and a counter incremented for every group (see
+    /// [`AnonymousNaming::custom`]).
+    Custom(Naming),
 }
 
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -53,6 +125,23 @@ impl Config {
     /// Creates a new configuration with the following default
     /// behaviors:
     /// - All backtraces are shown (see [`Config::show_backtraces`]).
+    /// - Payloads larger than 64KiB are stored out-of-line (see
+    ///   [`Config::with_offload_threshold`]).
+    /// - The executor starts with the number of threads set by the
+    ///   `BASTION_BLOCKING_THREADS` env var, or 2 (see
+    ///   [`Config::with_executor_threads`]).
+    /// - The root supervisor uses the `OneForOne` strategy (see
+    ///   [`Config::with_supervision_strategy`]).
+    /// - The mailboxes are unbounded (see
+    ///   [`Config::with_mailbox_capacity`]).
+    /// - The children groups send a heartbeat to their elements
+    ///   every 60 seconds and don't restart the stalled ones (see
+    ///   [`Config::with_heartbeat_tick`],
+    ///   [`Config::with_stalled_restart`] and
+    ///   [`Config::without_heartbeats`]).
+    /// - The children groups which weren't given a name are all
+    ///   named `__Anonymous__` (see
+    ///   [`Config::with_anonymous_naming`]).
     pub fn new() -> Self {
         Config::default()
     }
@@ -136,9 +225,492 @@ impl Config {
         self
     }
 
+    /// Sets the size (in bytes) above which the bytes of a
+    /// [`Payload`] are stored out-of-line instead of being copied
+    /// along with the messages holding it.
+    ///
+    /// The default threshold is [`DEFAULT_OFFLOAD_THRESHOLD`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    ///
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// let config = Config::new().with_offload_threshold(1024 * 1024);
+    ///
+    /// Bastion::init_with(config);
+    ///
+    /// // You can now use bastion and only payloads larger than
+    /// // 1MiB will be stored out-of-line...
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Payload`]: crate::payload::Payload
+    /// [`DEFAULT_OFFLOAD_THRESHOLD`]: crate::payload::DEFAULT_OFFLOAD_THRESHOLD
+    pub fn with_offload_threshold(mut self, threshold: usize) -> Self {
+        self.offload_threshold = Some(threshold);
+        self
+    }
+
+    /// Sets the number of threads the executor starts with, instead
+    /// of the value of the `BASTION_BLOCKING_THREADS` env var.
+    ///
+    /// The executor can't be resized once it started, so
+    /// initializing the system fails if it was already started
+    /// (eg. by a previous initialization).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    ///
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// let config = Config::new().with_executor_threads(4);
+    ///
+    /// Bastion::init_with(config);
+    ///
+    /// // You can now use bastion and its executor will start
+    /// // with 4 threads...
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_executor_threads(mut self, threads: usize) -> Self {
+        self.executor_threads = Some(threads);
+        self
+    }
+
+    /// Sets the strategy used by the root supervisor, which
+    /// supervises the children groups and supervisors created with
+    /// [`Bastion::children`] and [`Bastion::supervisor`].
+    ///
+    /// The default strategy is `OneForOne`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    ///
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// let config = Config::new().with_supervision_strategy(SupervisionStrategy::OneForAll);
+    ///
+    /// Bastion::init_with(config);
+    ///
+    /// // You can now use bastion and all the top-level children
+    /// // groups will be restarted when one of them dies...
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Bastion::children`]: crate::Bastion::children
+    /// [`Bastion::supervisor`]: crate::Bastion::supervisor
+    pub fn with_supervision_strategy(mut self, strategy: SupervisionStrategy) -> Self {
+        self.supervision_strategy = Some(strategy);
+        self
+    }
+
+    /// Sets the number of messages the mailbox of an element can
+    /// hold by default, the messages sent to a full mailbox being
+    /// dropped.
+    ///
+    /// A children group can override it with
+    /// [`Children::with_mailbox_capacity`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    ///
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// let config = Config::new().with_mailbox_capacity(1024);
+    ///
+    /// Bastion::init_with(config);
+    ///
+    /// // You can now use bastion and the mailboxes will hold
+    /// // at most 1024 messages...
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_mailbox_capacity`]: crate::children::Children::with_mailbox_capacity
+    pub fn with_mailbox_capacity(mut self, capacity: usize) -> Self {
+        self.mailbox_capacity = Some(capacity);
+        self
+    }
+
+    /// Sets how often the children groups send a heartbeat to their
+    /// elements by default, instead of every 60 seconds.
+    ///
+    /// A children group can override it with
+    /// [`Children::with_heartbeat_tick`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// let config = Config::new().with_heartbeat_tick(Duration::from_secs(5));
+    ///
+    /// Bastion::init_with(config);
+    ///
+    /// // You can now use bastion and the children groups will
+    /// // send a heartbeat to their elements every 5 seconds...
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_heartbeat_tick`]: crate::children::Children::with_heartbeat_tick
+    pub fn with_heartbeat_tick(mut self, tick: Duration) -> Self {
+        self.heartbeat_tick = Some(tick);
+        self
+    }
+
+    /// Makes the children groups restart by default their elements
+    /// which didn't handle any message for `ticks` heartbeat ticks.
+    ///
+    /// A children group can override it with
+    /// [`Children::with_stalled_restart`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// let config = Config::new()
+    ///     .with_heartbeat_tick(Duration::from_secs(5))
+    ///     .with_stalled_restart(3);
+    ///
+    /// Bastion::init_with(config);
+    ///
+    /// // You can now use bastion and the elements stalled for
+    /// // more than 15 seconds will be restarted...
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_stalled_restart`]: crate::children::Children::with_stalled_restart
+    pub fn with_stalled_restart(mut self, ticks: u32) -> Self {
+        self.stalled_ticks = Some(ticks);
+        self
+    }
+
+    /// Makes the children groups not send any heartbeat to their
+    /// elements, saving a helper actor per group. The stalled
+    /// elements can't be detected then.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    ///
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// let config = Config::new().without_heartbeats();
+    ///
+    /// Bastion::init_with(config);
+    ///
+    /// // You can now use bastion and the children groups won't
+    /// // send heartbeats...
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn without_heartbeats(mut self) -> Self {
+        self.disable_heartbeats = true;
+        self
+    }
+
+    /// Sets how the children groups which weren't given a name are
+    /// named, instead of all being named `__Anonymous__` (see
+    /// [`AnonymousNaming`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `naming` - How the anonymous groups are named.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    ///
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// let config = Config::new().with_anonymous_naming(AnonymousNaming::Sequential);
+    ///
+    /// Bastion::init_with(config);
+    ///
+    /// // You can now use bastion and the children groups created
+    /// // without a name will be named `anonymous-1`, `anonymous-2`...
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_anonymous_naming(mut self, naming: AnonymousNaming) -> Self {
+        self.anonymous_naming = naming;
+        self
+    }
+
+    /// Checks that this configuration can be used to initialize
+    /// the system, returning the first setting which can't.
+    ///
+    /// This is done by [`Bastion::init_with`] and
+    /// [`Bastion::try_init_with`], so it is only needed to check a
+    /// configuration before initializing the system.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    /// use bastion::errors::ConfigError;
+    ///
+    /// let config = Config::new().with_mailbox_capacity(0);
+    ///
+    /// assert_eq!(config.validate(), Err(ConfigError::NoMailboxCapacity));
+    /// ```
+    ///
+    /// [`Bastion::init_with`]: crate::Bastion::init_with
+    /// [`Bastion::try_init_with`]: crate::Bastion::try_init_with
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.executor_threads == Some(0) {
+            return Err(ConfigError::NoExecutorThreads);
+        }
+
+        if self.mailbox_capacity == Some(0) {
+            return Err(ConfigError::NoMailboxCapacity);
+        }
+
+        if self.heartbeat_tick == Some(Duration::from_secs(0)) {
+            return Err(ConfigError::NoHeartbeatTick);
+        }
+
+        match self.stalled_ticks {
+            Some(0) => return Err(ConfigError::NoStalledTicks),
+            Some(_) if self.disable_heartbeats => {
+                return Err(ConfigError::StalledWithoutHeartbeats)
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn backtraces(&self) -> &Backtraces {
         &self.backtraces
     }
+
+    pub(crate) fn offload_threshold(&self) -> Option<usize> {
+        self.offload_threshold
+    }
+
+    pub(crate) fn executor_threads(&self) -> Option<usize> {
+        self.executor_threads
+    }
+
+    pub(crate) fn supervision_strategy(&self) -> Option<&SupervisionStrategy> {
+        self.supervision_strategy.as_ref()
+    }
+
+    pub(crate) fn mailbox_capacity(&self) -> Option<usize> {
+        self.mailbox_capacity
+    }
+
+    pub(crate) fn heartbeat_tick(&self) -> Option<Duration> {
+        self.heartbeat_tick
+    }
+
+    pub(crate) fn stalled_ticks(&self) -> Option<u32> {
+        self.stalled_ticks
+    }
+
+    pub(crate) fn heartbeats(&self) -> bool {
+        !self.disable_heartbeats
+    }
+
+    pub(crate) fn anonymous_naming(&self) -> &AnonymousNaming {
+        &self.anonymous_naming
+    }
+}
+
+impl AnonymousNaming {
+    /// Creates a naming which names the groups with `name`, taking
+    /// the path of the group and a counter incremented for every
+    /// group.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The closure returning the name of a group.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    ///
+    /// let naming = AnonymousNaming::custom(|_path, n| format!("job-{:04}", n));
+    /// let config = Config::new().with_anonymous_naming(naming);
+    /// ```
+    pub fn custom<F>(name: F) -> Self
+    where
+        F: Fn(&BastionPath, u64) -> String + Send + Sync + 'static,
+    {
+        AnonymousNaming::Custom(Arc::new(name))
+    }
+
+    /// Returns a name for the group with the given path.
+    pub(crate) fn generate(&self, path: &BastionPath) -> String {
+        if let AnonymousNaming::Fixed = self {
+            return ANONYMOUS_NAME.to_string();
+        }
+
+        // FIXME: panics?
+        let mut names = ANONYMOUS_NAMES.lock().unwrap();
+        names.0 += 1;
+        let generated = match self {
+            AnonymousNaming::Fixed => unreachable!(),
+            AnonymousNaming::Sequential => format!("anonymous-{}", names.0),
+            AnonymousNaming::Custom(name) => name(path, names.0),
+        };
+
+        let mut name = generated.clone();
+        while names.1.contains(&name) {
+            names.0 += 1;
+            name = format!("{}-{}", generated, names.0);
+        }
+        names.1.insert(name.clone());
+
+        name
+    }
+}
+
+impl Debug for AnonymousNaming {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            AnonymousNaming::Fixed => write!(fmt, "Fixed"),
+            AnonymousNaming::Sequential => write!(fmt, "Sequential"),
+            AnonymousNaming::Custom(_) => write!(fmt, "Custom"),
+        }
+    }
+}
+
+/// Returns the configuration the system was last initialized with.
+pub(crate) fn current() -> Config {
+    CURRENT.read().unwrap().clone()
+}
+
+pub(crate) fn set_current(config: Config) {
+    *CURRENT.write().unwrap() = config;
 }
 
 impl Backtraces {
@@ -160,3 +732,50 @@ impl Default for Backtraces {
         Backtraces::Show
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_the_valid_settings() {
+        assert_eq!(Config::new().validate(), Ok(()));
+
+        let config = Config::new()
+            .with_executor_threads(1)
+            .with_mailbox_capacity(1)
+            .with_heartbeat_tick(Duration::from_millis(1))
+            .with_stalled_restart(1);
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn rejects_the_invalid_settings() {
+        let invalid = vec![
+            (
+                Config::new().with_executor_threads(0),
+                ConfigError::NoExecutorThreads,
+            ),
+            (
+                Config::new().with_mailbox_capacity(0),
+                ConfigError::NoMailboxCapacity,
+            ),
+            (
+                Config::new().with_heartbeat_tick(Duration::from_secs(0)),
+                ConfigError::NoHeartbeatTick,
+            ),
+            (
+                Config::new().with_stalled_restart(0),
+                ConfigError::NoStalledTicks,
+            ),
+            (
+                Config::new().with_stalled_restart(1).without_heartbeats(),
+                ConfigError::StalledWithoutHeartbeats,
+            ),
+        ];
+
+        for (config, error) in invalid {
+            assert_eq!(config.validate(), Err(error));
+        }
+    }
+}
@@ -2,26 +2,55 @@
 //! A context allows a child's future to access its received
 //! messages, parent and supervisor.
 
-use crate::child_ref::ChildRef;
-use crate::children_ref::ChildrenRef;
-use crate::dispatcher::{BroadcastTarget, DispatcherType, NotificationType};
+use crate::child_ref::{ChildRef, LastSeen, MailboxDepth, PollSite};
+use crate::children::RestartState;
+use crate::children_ref::{ChildrenRef, Readiness};
+use crate::demand::Demand;
+use crate::dispatcher::{
+    BroadcastTarget, DispatcherType, NotificationType, Priority, PriorityQueue,
+};
+use crate::drain::{Drain, Draining, Tracked};
 use crate::envelope::{Envelope, RefAddr, SignedMessage};
-use crate::message::{Answer, BastionMessage, Message, Msg};
+use crate::errors::AskError;
+use crate::executor;
+use crate::fairness::{FairPolling, Fairness, MessageSource};
+#[cfg(feature = "inspector")]
+use crate::inspector::MailboxProbe;
+use crate::message::{Answer, BastionMessage, ExhaustedMessage, Message, Msg, PanickedMessage};
+use crate::quota::{QuotaAction, QuotaState};
+#[cfg(feature = "inspector")]
+use crate::registry;
+use crate::resource::Resources;
+use crate::retry::{RedeliveryQueue, RedeliveryTicket, Retriable, RetryPolicy};
+use crate::sampling::Sampler;
 use crate::supervisor::SupervisorRef;
+use crate::watermark::WatermarkState;
 use crate::{prelude::ReceiveError, system::SYSTEM};
 
 use crossbeam_queue::SegQueue;
+use futures::future::{self, Either};
 use futures::pending;
+use futures::stream::{self, Stream, StreamExt};
 use futures::FutureExt;
 use futures_timer::Delay;
+#[cfg(feature = "metrics")]
+use fxhash::FxHashMap;
 #[cfg(feature = "scaling")]
 use lever::table::lotable::LOTable;
-use std::fmt::{self, Display, Formatter};
+use pin_utils::pin_mut;
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
 use std::pin::Pin;
-#[cfg(feature = "scaling")]
+#[cfg(any(feature = "scaling", feature = "metrics"))]
 use std::sync::atomic::AtomicU64;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::task::Poll;
+use std::time::Instant;
 use std::{sync::Arc, time::Duration};
-use tracing::{debug, trace};
+use tracing::{debug, debug_span, trace, warn, warn_span, Span};
 use uuid::Uuid;
 
 /// Identifier for a root supervisor and dead-letters children.
@@ -138,11 +167,104 @@ pub struct BastionContext {
     children: ChildrenRef,
     supervisor: Option<SupervisorRef>,
     state: Arc<Pin<Box<ContextState>>>,
+    // How the element was restarted, if it was.
+    restarted: Option<RestartState>,
+}
+
+/// Clones a message if it is of a given type.
+type Cloner = Arc<dyn Fn(&Msg) -> Option<Msg> + Send + Sync>;
+
+#[derive(Clone)]
+/// The number and the type of the last processed messages a children
+/// group's elements replay after having faulted.
+pub(crate) struct Replay {
+    last: usize,
+    // Clones the messages of the replayed type.
+    cloner: Cloner,
+}
+
+#[derive(Debug)]
+struct ReplayBuffer {
+    replay: Replay,
+    // The last processed messages, oldest first.
+    processed: Mutex<VecDeque<SignedMessage>>,
+    // The message being processed, which is never replayed since
+    // it might be the one that made the element fault.
+    in_flight: Mutex<Option<SignedMessage>>,
+    // The messages which are going to be replayed before
+    // the ones in the mailbox, oldest first.
+    replaying: Mutex<VecDeque<SignedMessage>>,
 }
 
 #[derive(Debug)]
 pub(crate) struct ContextState {
     messages: SegQueue<SignedMessage>,
+    replay: Option<ReplayBuffer>,
+    // The demand replenished every time a message is taken out
+    // of the mailbox, if any.
+    demand: Option<Demand>,
+    // The quota of the group and the number of bytes of the
+    // messages in the mailbox, if any.
+    quota: Option<Arc<QuotaState>>,
+    buffered_bytes: AtomicUsize,
+    // The number of messages the mailbox can hold, if bounded.
+    capacity: Option<usize>,
+    // The watermarks of the mailbox, if bounded and watched.
+    watermarks: Option<WatermarkState>,
+    // The messages waiting in the mailbox, as returned by
+    // `ChildRef::inspect_mailbox`.
+    #[cfg(feature = "inspector")]
+    probe: Arc<MailboxProbe>,
+    // Where the element is being polled, shared with its `ChildRef`
+    // to diagnose it when it doesn't stop after being killed.
+    poll_site: Arc<PollSite>,
+    // The number of messages in the mailbox, shared with its
+    // `ChildRef` to pick the least busy elements.
+    mailbox_depth: Arc<MailboxDepth>,
+    // The deadline of the last received message, if it was a
+    // question asked with one.
+    deadline: Mutex<Option<Instant>>,
+    // The last time an element of the group took a message out of
+    // its mailbox, if it is tracked.
+    activity: Option<Arc<LastSeen>>,
+    // The readiness of the elements of the group, if it waits for
+    // them to signal it.
+    readiness: Option<Arc<Readiness>>,
+    // Whether the panics thrown while handling a message with
+    // `BastionContext::handle` are isolated.
+    isolate_panics: bool,
+    // The names of the dispatcher scopes the group is in, from the
+    // outermost to the innermost one.
+    dispatcher_scope: Vec<String>,
+    // Decides which messages are traced, if the group samples them.
+    sampler: Option<Arc<Sampler>>,
+    // Whether the last message taken out of the mailbox is traced.
+    sampled: AtomicBool,
+    // The messages waiting to be redelivered, shared by the group's
+    // elements, if it redelivers the messages its handlers fail to
+    // handle.
+    redelivery: Option<Arc<RedeliveryQueue>>,
+    // The number of times the last message taken out of the mailbox
+    // was delivered.
+    deliveries: AtomicUsize,
+    // The resources owned by the group, shared by its elements.
+    resources: Resources,
+    // The queues of the messages broadcasted with a priority
+    // through the group's dispatchers.
+    queues: Vec<Arc<PriorityQueue>>,
+    // How the queues and the mailbox are polled in turn, if they
+    // aren't polled by priority.
+    fairness: Option<Fairness>,
+    // The work in flight of the group's elements, if it is
+    // drained before being stopped.
+    drain: Option<Arc<Drain>>,
+    // The number of messages taken out of the mailbox.
+    #[cfg(feature = "metrics")]
+    processed: AtomicU64,
+    // The number of messages of each type taken out of the
+    // mailbox.
+    #[cfg(feature = "metrics")]
+    processed_by_type: Mutex<FxHashMap<&'static str, u64>>,
     #[cfg(feature = "scaling")]
     stats: Arc<AtomicU64>,
     #[cfg(feature = "scaling")]
@@ -172,9 +294,15 @@ impl BastionContext {
             children,
             supervisor,
             state,
+            restarted: None,
         }
     }
 
+    pub(crate) fn with_restarted(mut self, restarted: RestartState) -> Self {
+        self.restarted = Some(restarted);
+        self
+    }
+
     /// Returns a [`ChildRef`] referencing the children group's
     /// element that is linked to this `BastionContext`.
     ///
@@ -328,6 +456,54 @@ impl BastionContext {
         self.supervisor.as_ref()
     }
 
+    /// Returns how the element linked to this `BastionContext` was
+    /// restarted, ie. whether it kept the state of the element it
+    /// replaces (see [`RestartState`]), or `None` if it wasn't.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_restart_state(RestartState::Fresh)
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 if ctx.restarted().is_some() {
+    ///                     // Initialize again what was lost...
+    ///                 }
+    ///
+    ///                 Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`RestartState`]: crate::children::RestartState
+    pub fn restarted(&self) -> Option<RestartState> {
+        self.restarted
+    }
+
     /// Tries to retrieve asynchronously a message received by
     /// the element this `BastionContext` is linked to.
     ///
@@ -531,6 +707,136 @@ impl BastionContext {
         }
     }
 
+    /// Returns a stream of the messages received by the element
+    /// this `BastionContext` is linked to, allowing to use the
+    /// [`StreamExt`] combinators (eg. `filter`, `chunks` or
+    /// `timeout`) instead of calling [`recv`] in a loop.
+    ///
+    /// The stream waits (always asynchronously) for a message if
+    /// none has been received yet and never ends.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use futures::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             // Handles the messages ten at a time...
+    ///             let mut batches = ctx.stream().chunks(10);
+    ///             while let Some(batch) = batches.next().await {
+    ///                 for msg in batch {
+    ///                     // ...
+    ///                 }
+    ///             }
+    ///
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`StreamExt`]: futures::stream::StreamExt
+    /// [`recv`]: Self::recv
+    pub fn stream(&self) -> impl Stream<Item = SignedMessage> + '_ {
+        debug!("BastionContext({}): Streaming messages.", self.id);
+        stream::poll_fn(move |_| match self.state.pop_message() {
+            Some(msg) => {
+                trace!("BastionContext({}): Received message: {:?}", self.id, msg);
+                Poll::Ready(Some(msg))
+            }
+            None => Poll::Pending,
+        })
+    }
+
+    /// Returns a stream of the messages of type `M` received by
+    /// the element this `BastionContext` is linked to, whether
+    /// they were told, asked or broadcasted (in which case they
+    /// are cloned).
+    ///
+    /// The messages of other types are dropped, and the questions
+    /// can't be answered. Use [`stream`] instead if the element
+    /// receives other messages or needs to answer them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use futures::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             let mut evens = ctx.stream_of::<u64>().filter(|n| future::ready(n % 2 == 0));
+    ///             while let Some(n) = evens.next().await {
+    ///                 // ...
+    ///             }
+    ///
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`stream`]: Self::stream
+    pub fn stream_of<M: Message + Clone>(&self) -> impl Stream<Item = M> + '_ {
+        self.stream().filter_map(move |msg| {
+            let (msg, _) = msg.extract();
+            let msg = match msg.downcast::<M>() {
+                Ok(msg) => Some(msg),
+                Err(msg) => match msg.downcast_ref::<M>() {
+                    Some(msg) => Some(msg.as_ref().clone()),
+                    None => {
+                        debug!("BastionContext({}): Dropping message: {:?}", self.id, msg);
+                        None
+                    }
+                },
+            };
+
+            future::ready(msg)
+        })
+    }
+
     /// Returns [`RefAddr`] of the current `BastionContext`
     ///
     /// # Example
@@ -578,6 +884,187 @@ impl BastionContext {
         )
     }
 
+    /// Returns the instant after which the asker of the message being
+    /// handled (the last one received) won't wait for its answer
+    /// anymore, if it is a question which was asked with a deadline
+    /// (see [`BastionContext::ask_with_deadline`]).
+    ///
+    /// Handlers can check it to skip the work for the questions whose
+    /// asker already gave up. The deadline is propagated to the
+    /// questions asked while handling the message.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.state.deadline()
+    }
+
+    /// Returns whether the message being handled (the last one
+    /// received) is traced, according to the sampling of the
+    /// children group (see [`Children::with_trace_sampling`]).
+    ///
+    /// [`BastionContext::handle`] traces the sampled messages, but
+    /// custom instrumentation can also check this to only trace the
+    /// same messages. All the messages are sampled if the group
+    /// doesn't have any sampling.
+    ///
+    /// [`Children::with_trace_sampling`]: crate::children::Children::with_trace_sampling
+    pub fn is_sampled(&self) -> bool {
+        self.state.is_sampled()
+    }
+
+    /// Returns the resource of type `T` owned by the children group
+    /// of the element linked to this `BastionContext` (see
+    /// [`Children::with_resource`]), initializing it if none of the
+    /// elements used it yet.
+    ///
+    /// This method returns `None` if the group doesn't own a resource
+    /// of this type.
+    ///
+    /// [`Children::with_resource`]: crate::children::Children::with_resource
+    pub async fn resource<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.state.resources().get::<T>().await
+    }
+
+    /// Registers `value` (eg. a connection or a request being
+    /// handled) as work in flight until the returned [`Tracked`] is
+    /// dropped, so that the children group waits for it to be done
+    /// before stopping its elements, if it has a drain deadline (see
+    /// [`Children::with_drain_deadline`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value tracked until it is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_drain_deadline(Duration::from_secs(30))
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // Stops taking new requests once the group is
+    ///                 // told to stop...
+    ///                 while !ctx.is_draining() {
+    ///                     let request = ctx.track(ctx.recv().await?);
+    ///                     spawn!(async move {
+    ///                         // ...but still handles the ones it took
+    ///                         // until they are done (and `request` is
+    ///                         // dropped).
+    ///                         let _request = request;
+    ///                     });
+    ///                 }
+    ///
+    ///                 Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_drain_deadline`]: crate::children::Children::with_drain_deadline
+    pub fn track<T>(&self, value: T) -> Tracked<T> {
+        Drain::track(self.state.drain().cloned(), value)
+    }
+
+    /// Returns whether the children group of the element linked to
+    /// this `BastionContext` was told to stop and is draining its
+    /// work in flight, in which case the element should stop
+    /// accepting new work (see [`track`]).
+    ///
+    /// [`track`]: Self::track
+    pub fn is_draining(&self) -> bool {
+        self.state.drain().is_some_and(|drain| drain.is_draining())
+    }
+
+    /// Returns a future resolving once the children group of the
+    /// element linked to this `BastionContext` was told to stop and
+    /// starts draining its work in flight (see [`track`]), to be
+    /// raced against the acceptance of new work.
+    ///
+    /// The future never resolves if the group doesn't have a drain
+    /// deadline, since its elements are then stopped right away.
+    ///
+    /// [`track`]: Self::track
+    pub fn draining(&self) -> Draining<'_> {
+        Drain::draining(self.state.drain().map(|drain| &**drain))
+    }
+
+    /// Signals that the element linked to this `BastionContext` is
+    /// ready, if its children group waits for all of its elements to
+    /// do so before being considered started (see
+    /// [`Children::with_readiness`]). This does nothing otherwise.
+    ///
+    /// Elements signaling their readiness more than once (eg. after
+    /// being restarted) are only counted once.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_readiness()
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // Loads the configuration...
+    ///                 ctx.notify_started();
+    ///
+    ///                 loop {
+    ///                     ctx.recv().await?;
+    ///                 }
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_readiness`]: crate::children::Children::with_readiness
+    pub fn notify_started(&self) {
+        if let Some(readiness) = self.state.readiness() {
+            debug!("Child({}): Signaling its readiness.", self.id);
+            readiness.signal(&self.child, self.children.dispatchers());
+        }
+    }
+
     /// Sends a message to the specified [`RefAddr`]
     ///
     /// # Arguments
@@ -638,19 +1125,26 @@ impl BastionContext {
             .map_err(|err| err.into_inner().into_msg().unwrap())
     }
 
-    /// Sends a message from behalf of current context to the addr,
-    /// allowing to addr owner answer.
+    /// Passes a received message on to the specified [`RefAddr`],
+    /// keeping the signature of its original sender instead of
+    /// signing it with the current context.
     ///
-    /// This method returns [`Answer`] if it succeeded, or `Err(msg)`
+    /// This allows routing or proxy actors to stand between the
+    /// senders and the actual handlers of their messages: the
+    /// handlers reply to the original senders, and answer the
+    /// forwarded questions directly to their askers.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(msg)`
     /// otherwise.
     ///
-    /// # Argument
+    /// # Arguments
     ///
-    /// * `msg` - The message to send.
+    /// * `msg` - The received message to forward.
+    /// * `to` - The [`RefAddr`] to forward the message to.
     ///
     /// # Example
     ///
-    /// ```
+    /// ```rust
     /// # use bastion::prelude::*;
     /// #
     /// # #[cfg(feature = "tokio-runtime")]
@@ -665,37 +1159,425 @@ impl BastionContext {
     /// # }
     /// #
     /// # fn run() {
-    ///     # Bastion::init();
-    /// // The message that will be "asked"...
-    /// const ASK_MSG: &'static str = "A message containing data (ask).";
-    /// // The message the will be "answered"...
-    /// const ANSWER_MSG: &'static str = "A message containing data (answer).";
-    ///
-    ///     # let children_ref =
-    /// // Create a new child...
-    /// Bastion::children(|children| {
+    /// # Bastion::init();
+    /// #
+    /// let backend = Bastion::children(|children| {
     ///     children.with_exec(|ctx: BastionContext| {
     ///         async move {
-    ///             // ...which will receive the message asked...
     ///             msg! { ctx.recv().await?,
-    ///                 msg: &'static str =!> {
-    ///                     assert_eq!(msg, ASK_MSG);
-    ///                     // Handle the message...
-    ///
-    ///                     // ...and eventually answer to it...
-    ///                     answer!(ctx, ANSWER_MSG);
-    ///                 };
-    ///                 // This won't happen because this example
-    ///                 // only "asks" a `&'static str`...
+    ///                 // Answers the original asker...
+    ///                 _msg: &'static str =!> { answer!(ctx, "pong").ok(); };
     ///                 _: _ => ();
     ///             }
-    ///
     ///             Ok(())
     ///         }
     ///     })
     /// }).expect("Couldn't create the children group.");
     ///
-    ///     # Bastion::children(|children| {
+    /// Bastion::children(|children| {
+    ///     children.with_exec(move |ctx: BastionContext| {
+    ///         let backend = backend.elems()[0].clone();
+    ///         async move {
+    ///             loop {
+    ///                 let msg: SignedMessage = ctx.recv().await?;
+    ///                 ctx.forward(msg, &backend.addr()).ok();
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn forward(&self, msg: SignedMessage, to: &RefAddr) -> Result<(), SignedMessage> {
+        debug!(
+            "{:?}: Forwarding message: {:?} to: {:?}",
+            self.current().path(),
+            msg,
+            to.path()
+        );
+        let (msg, sign) = msg.extract();
+        let env = Envelope::new_with_sign(BastionMessage::Message(msg), sign);
+        // FIXME: panics?
+        to.sender().unbounded_send(env).map_err(|err| {
+            let env = err.into_inner();
+            match env.msg {
+                BastionMessage::Message(msg) => SignedMessage::new(msg, env.sign),
+                _ => unreachable!(),
+            }
+        })
+    }
+
+    /// Handles a received message with `handler`, isolating the
+    /// panics it throws if the children group of this context was
+    /// configured to (see [`Children::with_panic_isolation`]).
+    ///
+    /// When a panic is isolated, a [`PanickedMessage`] describing
+    /// the message and the panic is sent to the dead letters, and
+    /// this method returns `Err(())` instead of faulting the element,
+    /// which can go on handling the next messages. Otherwise, this
+    /// method returns the output of `handler`.
+    ///
+    /// If the message is sampled (see [`is_sampled`]), `handler` runs
+    /// within a `message` span.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The received message to handle.
+    /// * `handler` - The function handling the message.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_panic_isolation()
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 loop {
+    ///                     let msg = ctx.recv().await?;
+    ///                     // A message making the handler panic doesn't
+    ///                     // prevent the next ones from being handled.
+    ///                     ctx.handle(msg, |msg| async move {
+    ///                         msg! { msg,
+    ///                             divisor: u64 => {
+    ///                                 println!("{}", 100 / divisor);
+    ///                             };
+    ///                             _: _ => ();
+    ///                         }
+    ///                     })
+    ///                     .await
+    ///                     .ok();
+    ///                 }
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_panic_isolation`]: crate::children::Children::with_panic_isolation
+    /// [`is_sampled`]: Self::is_sampled
+    pub async fn handle<F, Fut>(&self, msg: SignedMessage, handler: F) -> Result<Fut::Output, ()>
+    where
+        F: FnOnce(SignedMessage) -> Fut,
+        Fut: Future,
+    {
+        let type_name = msg.msg().type_name();
+        let sampled = self.is_sampled();
+        let span = if sampled {
+            debug_span!("message", child = %self.id, type_name = type_name)
+        } else {
+            Span::none()
+        };
+
+        if !self.state.isolates_panics() {
+            return Ok(in_span(span, handler(msg)).await);
+        }
+
+        let sender = msg.signature().clone();
+        let handled = AssertUnwindSafe(in_span(span, async move { handler(msg).await }))
+            .catch_unwind()
+            .await;
+
+        handled.map_err(|panic| {
+            let panic = panic
+                .downcast_ref::<&'static str>()
+                .map(|panic| panic.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "Box<dyn Any>".to_string());
+            let span = if sampled || self.state.samples_errors() {
+                warn_span!(
+                    "message",
+                    child = %self.id,
+                    type_name = type_name,
+                    panicked = true
+                )
+            } else {
+                Span::none()
+            };
+            let _entered = span.enter();
+            warn!(
+                "Child({}): Isolated a panic while handling a message of type {}: {}",
+                self.id, type_name, panic
+            );
+
+            let msg = PanickedMessage::new(self.id.clone(), type_name, sender, panic);
+            self.tell(&RefAddr::dead_letters(), msg).ok();
+        })
+    }
+
+    /// Handles a received message with `handler` like [`handle`]
+    /// does, except that the message is redelivered later if the
+    /// handler returns a [`Retriable`] error.
+    ///
+    /// The message is redelivered to this element (or to another
+    /// element of the group if this one was restarted meanwhile) after
+    /// the backoff delay of the [`RetryPolicy`] of the group (see
+    /// [`Children::with_redelivery`]) and as many times as the policy
+    /// allows. Once it is exhausted, or if the group doesn't redeliver
+    /// the messages of this type, an [`ExhaustedMessage`] describing
+    /// the message is sent to the dead letters instead.
+    ///
+    /// The redelivered messages are told to the element, which thus
+    /// can't answer them anymore if they were questions.
+    ///
+    /// This method returns the output of `handler`, or `Err(())` if
+    /// it panicked while the panics were isolated.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The received message to handle.
+    /// * `handler` - The function handling the message.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::retry::{Retriable, RetryPolicy};
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// #[derive(Debug, Clone)]
+    /// struct Put(String, u64);
+    ///
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_redelivery::<Put>(RetryPolicy::new().with_max_retries(5))
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 loop {
+    ///                     let msg = ctx.recv().await?;
+    ///                     ctx.handle_retriable(msg, |msg| async move {
+    ///                         msg! { msg,
+    ///                             put: Put => {
+    ///                                 // The store is unavailable...
+    ///                                 println!("Couldn't store {}.", put.0);
+    ///                                 return Err(Retriable::new("store unavailable"));
+    ///                             };
+    ///                             _: _ => ();
+    ///                         }
+    ///                         Ok(())
+    ///                     })
+    ///                     .await
+    ///                     .ok();
+    ///                 }
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`handle`]: Self::handle
+    /// [`Retriable`]: crate::retry::Retriable
+    /// [`RetryPolicy`]: crate::retry::RetryPolicy
+    /// [`Children::with_redelivery`]: crate::children::Children::with_redelivery
+    /// [`ExhaustedMessage`]: crate::message::ExhaustedMessage
+    pub async fn handle_retriable<F, Fut, T>(
+        &self,
+        msg: SignedMessage,
+        handler: F,
+    ) -> Result<Result<T, Retriable>, ()>
+    where
+        F: FnOnce(SignedMessage) -> Fut,
+        Fut: Future<Output = Result<T, Retriable>>,
+    {
+        let type_name = msg.msg().type_name();
+        let sender = msg.signature().clone();
+        let clone = self
+            .state
+            .redelivery()
+            .and_then(|redelivery| redelivery.redelivery().clone_message(&msg));
+
+        let handled = self.handle(msg, handler).await?;
+        if let Err(retriable) = &handled {
+            self.redeliver(clone, type_name, sender, retriable);
+        }
+
+        Ok(handled)
+    }
+
+    // Schedules the redelivery of the message the handler failed to
+    // handle, or sends it to the dead letters if it can't be
+    // redelivered anymore.
+    fn redeliver(
+        &self,
+        msg: Option<SignedMessage>,
+        type_name: &'static str,
+        sender: RefAddr,
+        retriable: &Retriable,
+    ) {
+        let delivered = self.state.deliveries();
+        if let (Some(redelivery), Some(msg)) = (self.state.redelivery(), msg) {
+            let policy = redelivery.redelivery().policy();
+            if delivered <= policy.max_retries() {
+                let backoff = policy.backoff(delivered.saturating_sub(1));
+                debug!(
+                    "BastionContext({}): Redelivering a message of type {} in {:?} (retry {}/{}): {}",
+                    self.id,
+                    type_name,
+                    backoff,
+                    delivered,
+                    policy.max_retries(),
+                    retriable.reason()
+                );
+
+                let ticket = redelivery.schedule(delivered, msg);
+                let redelivery = redelivery.clone();
+                let child = self.current().clone();
+                let children = self.parent().clone();
+                let signature = self.signature();
+                executor::spawn(async move {
+                    Delay::new(backoff).await;
+                    // If the element was restarted in the meantime,
+                    // the first element of the group receiving the
+                    // ticket takes the message.
+                    let ticket = match child.tell_anonymously(ticket) {
+                        Ok(()) => return,
+                        Err(ticket) => ticket,
+                    };
+                    let ticket = match children.broadcast(ticket) {
+                        Ok(()) => return,
+                        Err(ticket) => ticket,
+                    };
+
+                    if let Some((delivered, msg)) = redelivery.take(ticket.0) {
+                        warn!(
+                            "Child({}): Couldn't redeliver a message of type {}.",
+                            child.id(),
+                            type_name
+                        );
+                        let msg = ExhaustedMessage::new(
+                            child.id().clone(),
+                            type_name,
+                            msg.signature().clone(),
+                            delivered,
+                            "the children group was stopped".to_string(),
+                        );
+                        let env = Envelope::new_with_sign(BastionMessage::tell(msg), signature);
+                        RefAddr::dead_letters().sender().unbounded_send(env).ok();
+                    }
+                });
+                return;
+            }
+        }
+
+        warn!(
+            "BastionContext({}): Giving up on a message of type {} after {} deliveries: {}",
+            self.id,
+            type_name,
+            delivered,
+            retriable.reason()
+        );
+        let msg = ExhaustedMessage::new(
+            self.id.clone(),
+            type_name,
+            sender,
+            delivered,
+            retriable.reason().to_string(),
+        );
+        self.tell(&RefAddr::dead_letters(), msg).ok();
+    }
+
+    /// Sends a message from behalf of current context to the addr,
+    /// allowing to addr owner answer.
+    ///
+    /// If the message being handled is a question asked with a
+    /// deadline (see [`BastionContext::deadline`]), the deadline is
+    /// propagated to the question.
+    ///
+    /// This method returns [`Answer`] if it succeeded, or `Err(msg)`
+    /// otherwise.
+    ///
+    /// # Argument
+    ///
+    /// * `msg` - The message to send.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    ///     # Bastion::init();
+    /// // The message that will be "asked"...
+    /// const ASK_MSG: &'static str = "A message containing data (ask).";
+    /// // The message the will be "answered"...
+    /// const ANSWER_MSG: &'static str = "A message containing data (answer).";
+    ///
+    ///     # let children_ref =
+    /// // Create a new child...
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             // ...which will receive the message asked...
+    ///             msg! { ctx.recv().await?,
+    ///                 msg: &'static str =!> {
+    ///                     assert_eq!(msg, ASK_MSG);
+    ///                     // Handle the message...
+    ///
+    ///                     // ...and eventually answer to it...
+    ///                     answer!(ctx, ANSWER_MSG);
+    ///                 };
+    ///                 // This won't happen because this example
+    ///                 // only "asks" a `&'static str`...
+    ///                 _: _ => ();
+    ///             }
+    ///
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    ///
+    ///     # Bastion::children(|children| {
     ///         # children.with_exec(move |ctx: BastionContext| {
     ///             # let child_ref = children_ref.elems()[0].clone();
     ///             # async move {
@@ -724,13 +1606,109 @@ impl BastionContext {
     /// # }
     /// ```
     pub fn ask<M: Message>(&self, to: &RefAddr, msg: M) -> Result<Answer, M> {
+        self.ask_inner(to, msg, self.deadline())
+    }
+
+    /// Sends a message from behalf of current context to the addr,
+    /// allowing the addr to answer it until `deadline`.
+    ///
+    /// The deadline is visible to the element handling the question
+    /// (see [`BastionContext::deadline`]), which can skip the work if
+    /// it was reached, and is propagated to the questions it asks in
+    /// turn. If the message being handled was asked with an earlier
+    /// deadline, the earlier one is used.
+    ///
+    /// This method returns [`Answer`] if it succeeded, or `Err(msg)`
+    /// otherwise. Awaiting the answer doesn't time out by itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The address the message is asked to.
+    /// * `msg` - The message to send.
+    /// * `deadline` - The instant after which the answer won't be
+    ///   waited for anymore.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// # use std::time::{Duration, Instant};
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    ///     # Bastion::init();
+    /// let children_ref = Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| async move {
+    ///         msg! { ctx.recv().await?,
+    ///             _msg: &'static str =!> {
+    ///                 // Only does the work if the asker is still
+    ///                 // waiting for the answer...
+    ///                 let expired = ctx.deadline().map_or(false, |deadline| deadline <= Instant::now());
+    ///                 if !expired {
+    ///                     answer!(ctx, "pong").ok();
+    ///                 }
+    ///             };
+    ///             _: _ => ();
+    ///         }
+    ///         Ok(())
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    ///     # Bastion::children(|children| {
+    ///         # children.with_exec(move |ctx: BastionContext| {
+    ///             # let child_ref = children_ref.elems()[0].clone();
+    ///             # async move {
+    /// let deadline = Instant::now() + Duration::from_millis(500);
+    /// let answer = ctx
+    ///     .ask_with_deadline(&child_ref.addr(), "ping", deadline)
+    ///     .expect("Couldn't send the message.");
+    ///                 #
+    ///                 # Ok(())
+    ///             # }
+    ///         # })
+    ///     # }).unwrap();
+    ///     #
+    ///     # Bastion::start();
+    ///     # Bastion::stop();
+    ///     # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn ask_with_deadline<M: Message>(
+        &self,
+        to: &RefAddr,
+        msg: M,
+        deadline: Instant,
+    ) -> Result<Answer, M> {
+        let deadline = match self.deadline() {
+            Some(current) => current.min(deadline),
+            None => deadline,
+        };
+
+        self.ask_inner(to, msg, Some(deadline))
+    }
+
+    fn ask_inner<M: Message>(
+        &self,
+        to: &RefAddr,
+        msg: M,
+        deadline: Option<Instant>,
+    ) -> Result<Answer, M> {
         debug!(
             "{:?}: Asking message: {:?} to: {:?}",
             self.current().path(),
             msg,
             to
         );
-        let (msg, answer) = BastionMessage::ask(msg, self.signature());
+        let (msg, answer) = BastionMessage::ask_with_deadline(msg, self.signature(), deadline);
         let env = Envelope::new_with_sign(msg, self.signature());
         // FIXME: panics?
         to.sender()
@@ -740,6 +1718,142 @@ impl BastionContext {
         Ok(answer)
     }
 
+    /// Sends a message from behalf of current context to the addr,
+    /// asking it again according to `policy` every time it isn't
+    /// answered on time or can't be delivered.
+    ///
+    /// Each attempt is given [`RetryPolicy::timeout`] to be answered,
+    /// and the retries wait for an exponentially increasing delay
+    /// until the policy's number of retries or time budget is
+    /// exhausted.
+    ///
+    /// This method returns the answer if it succeeded, or the
+    /// [`AskError`] of the last attempt otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The address the message is asked to.
+    /// * `msg` - The message to send, cloned for every attempt.
+    /// * `policy` - Defines how many times and how often the
+    ///   message is asked again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// # use bastion::retry::RetryPolicy;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    ///     # Bastion::init();
+    ///     # let children_ref = Bastion::children(|children| {
+    ///         # children.with_exec(|ctx: BastionContext| async move {
+    ///             # msg! { ctx.recv().await?,
+    ///                 # _msg: &'static str =!> { answer!(ctx, "pong").ok(); };
+    ///                 # _: _ => ();
+    ///             # }
+    ///             # Ok(())
+    ///         # })
+    ///     # }).unwrap();
+    ///     # Bastion::children(|children| {
+    ///         # children.with_exec(move |ctx: BastionContext| {
+    ///             # let child_ref = children_ref.elems()[0].clone();
+    ///             # async move {
+    /// let policy = RetryPolicy::new()
+    ///     .with_timeout(Duration::from_millis(200))
+    ///     .with_max_retries(5);
+    ///
+    /// match ctx.ask_with_retry(&child_ref.addr(), "ping", policy).await {
+    ///     Ok(answer) => {
+    ///         // Handle the answer...
+    ///     }
+    ///     Err(err) => {
+    ///         // The question was never answered...
+    ///     }
+    /// }
+    ///                 #
+    ///                 # Ok(())
+    ///             # }
+    ///         # })
+    ///     # }).unwrap();
+    ///     #
+    ///     # Bastion::start();
+    ///     # Bastion::stop();
+    ///     # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`RetryPolicy::timeout`]: crate::retry::RetryPolicy::timeout
+    pub async fn ask_with_retry<M: Message + Clone>(
+        &self,
+        to: &RefAddr,
+        msg: M,
+        policy: RetryPolicy,
+    ) -> Result<SignedMessage, AskError> {
+        let started = Instant::now();
+        let mut retries = 0;
+
+        loop {
+            // The last attempt is only given the time left.
+            let timeout = match policy.budget() {
+                Some(budget) => policy
+                    .timeout()
+                    .min(budget.checked_sub(started.elapsed()).unwrap_or_default()),
+                None => policy.timeout(),
+            };
+
+            let err = match self.ask(to, msg.clone()) {
+                Ok(answer) => match future::select(answer, Delay::new(timeout)).await {
+                    Either::Left((Ok(reply), _)) => return Ok(reply),
+                    Either::Left((Err(()), _)) => AskError::Unavailable,
+                    Either::Right(_) => AskError::Timeout(timeout),
+                },
+                Err(_) => AskError::Unavailable,
+            };
+
+            if retries >= policy.max_retries() {
+                debug!(
+                    "BastionContext({}): Giving up asking after {} retries: {:?}",
+                    self.id, retries, err
+                );
+                return Err(err);
+            }
+
+            let backoff = policy.backoff(retries);
+            if let Some(budget) = policy.budget() {
+                if started.elapsed() + backoff >= budget {
+                    debug!(
+                        "BastionContext({}): Giving up asking, the budget is exhausted: {:?}",
+                        self.id, err
+                    );
+                    return Err(err);
+                }
+            }
+
+            retries += 1;
+            trace!(
+                "BastionContext({}): Asking again in {:?} (retry {}/{}): {:?}",
+                self.id,
+                backoff,
+                retries,
+                policy.max_retries(),
+                err
+            );
+            Delay::new(backoff).await;
+        }
+    }
+
     /// Sends the notification to each declared dispatcher of the actor.
     ///
     /// # Argument
@@ -769,14 +1883,70 @@ impl BastionContext {
         });
 
         let global_dispatcher = SYSTEM.dispatcher();
+        let target = global_dispatcher.resolve(target, self.state.dispatcher_scope());
         global_dispatcher.broadcast_message(target, &msg);
     }
+
+    /// Sends the broadcasted message to the target group(s), with
+    /// the given priority (see
+    /// [`Dispatcher::broadcast_message_with_priority`]).
+    ///
+    /// # Argument
+    ///
+    /// * `target` - Defines the message receivers in according with
+    /// the [`BroadcastTarget`] value.
+    /// * `message` - The broadcasted message.
+    /// * `priority` - The priority of the message.
+    ///
+    /// [`Dispatcher::broadcast_message_with_priority`]: crate::dispatcher::Dispatcher::broadcast_message_with_priority
+    pub fn broadcast_message_with_priority<M: Message>(
+        &self,
+        target: BroadcastTarget,
+        message: M,
+        priority: Priority,
+    ) {
+        let msg = Arc::new(SignedMessage {
+            msg: Msg::broadcast(message),
+            sign: self.signature(),
+        });
+
+        let global_dispatcher = SYSTEM.dispatcher();
+        let target = global_dispatcher.resolve(target, self.state.dispatcher_scope());
+        global_dispatcher.broadcast_message_with_priority(target, &msg, priority);
+    }
 }
 
 impl ContextState {
     pub(crate) fn new() -> Self {
         ContextState {
             messages: SegQueue::new(),
+            #[cfg(feature = "inspector")]
+            probe: Arc::new(MailboxProbe::new()),
+            poll_site: Arc::new(PollSite::default()),
+            mailbox_depth: Arc::new(MailboxDepth::default()),
+            replay: None,
+            demand: None,
+            quota: None,
+            buffered_bytes: AtomicUsize::new(0),
+            capacity: None,
+            watermarks: None,
+            deadline: Mutex::new(None),
+            activity: None,
+            readiness: None,
+            isolate_panics: false,
+            dispatcher_scope: Vec::new(),
+            sampler: None,
+            sampled: AtomicBool::new(true),
+            redelivery: None,
+            deliveries: AtomicUsize::new(0),
+            resources: Resources::default(),
+            queues: Vec::new(),
+            fairness: None,
+            drain: None,
+            #[cfg(feature = "metrics")]
+            processed: AtomicU64::new(0),
+            #[cfg(feature = "metrics")]
+            processed_by_type: Mutex::new(FxHashMap::default()),
             #[cfg(feature = "scaling")]
             stats: Arc::new(AtomicU64::new(0)),
             #[cfg(feature = "scaling")]
@@ -804,26 +1974,438 @@ impl ContextState {
         self.actor_stats.clone()
     }
 
-    pub(crate) fn push_message(&self, msg: Msg, sign: RefAddr) {
-        self.messages.push(SignedMessage::new(msg, sign))
+    pub(crate) fn with_replay(mut self, replay: Option<Replay>) -> Self {
+        self.replay = replay.map(ReplayBuffer::new);
+        self
+    }
+
+    /// Requests `window` messages from `demand` and then one more
+    /// every time a message is taken out of the mailbox.
+    pub(crate) fn with_demand(mut self, demand: Option<(Demand, usize)>) -> Self {
+        if let Some((demand, window)) = demand {
+            demand.request(window);
+            self.demand = Some(demand);
+        }
+
+        self
+    }
+
+    /// Makes the messages broadcasted with a priority through the
+    /// group's dispatchers be received along with the ones in the
+    /// mailbox.
+    pub(crate) fn with_priority_queues(mut self, queues: Vec<Arc<PriorityQueue>>) -> Self {
+        self.queues = queues;
+        self
+    }
+
+    pub(crate) fn with_quota(mut self, quota: Option<Arc<QuotaState>>) -> Self {
+        self.quota = quota;
+        self
+    }
+
+    pub(crate) fn with_mailbox_capacity(mut self, capacity: Option<usize>) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub(crate) fn with_activity(mut self, activity: Arc<LastSeen>) -> Self {
+        self.activity = Some(activity);
+        self
+    }
+
+    pub(crate) fn with_readiness(mut self, readiness: Option<Arc<Readiness>>) -> Self {
+        self.readiness = readiness;
+        self
+    }
+
+    pub(crate) fn with_panic_isolation(mut self, isolate_panics: bool) -> Self {
+        self.isolate_panics = isolate_panics;
+        self
+    }
+
+    pub(crate) fn with_dispatcher_scope(mut self, dispatcher_scope: Vec<String>) -> Self {
+        self.dispatcher_scope = dispatcher_scope;
+        self
+    }
+
+    pub(crate) fn with_fair_polling(mut self, polling: Option<FairPolling>) -> Self {
+        self.fairness = polling.map(Fairness::new);
+        self
+    }
+
+    pub(crate) fn with_sampler(mut self, sampler: Option<Arc<Sampler>>) -> Self {
+        self.sampler = sampler;
+        self
+    }
+
+    pub(crate) fn with_resources(mut self, resources: Resources) -> Self {
+        self.resources = resources;
+        self
+    }
+
+    pub(crate) fn with_drain(mut self, drain: Option<Arc<Drain>>) -> Self {
+        self.drain = drain;
+        self
+    }
+
+    pub(crate) fn with_redelivery(mut self, redelivery: Option<Arc<RedeliveryQueue>>) -> Self {
+        self.redelivery = redelivery;
+        self
+    }
+
+    pub(crate) fn with_watermarks(mut self, watermarks: Option<WatermarkState>) -> Self {
+        self.watermarks = watermarks;
+        self
+    }
+
+    pub(crate) fn push_message(&self, mut msg: Msg, sign: RefAddr) {
+        if let Some(capacity) = self.capacity {
+            if self.messages.len() >= capacity {
+                warn!("ContextState: Mailbox full: Dropping message: {:?}", msg);
+                return;
+            }
+        }
+
+        if let Some(quota) = &self.quota {
+            if !self.admit(quota, &mut msg) {
+                debug!("ContextState: Quota: Dropping message: {:?}", msg);
+                return;
+            }
+        }
+
+        // Recorded before being pushed, so that it can't be taken
+        // out of the mailbox before being recorded.
+        #[cfg(feature = "inspector")]
+        self.probe.pushed(msg.type_name(), registry::describe(&msg));
+        self.messages.push(SignedMessage::new(msg, sign));
+        self.mailbox_depth.set(self.messages.len());
+        if let Some(watermarks) = &self.watermarks {
+            watermarks.update(self.messages.len());
+        }
+    }
+
+    // Enforces the quota of the group on `msg`, returning whether
+    // it should be pushed to the mailbox.
+    fn admit(&self, quota: &QuotaState, msg: &mut Msg) -> bool {
+        if !quota.admit_ask(msg) {
+            return false;
+        }
+
+        let size = msg.size();
+        while quota.exceeds_bytes(self.buffered_bytes.load(Ordering::SeqCst), size) {
+            match quota.action() {
+                QuotaAction::Reject => return false,
+                QuotaAction::Shed => match self.take_message() {
+                    Some(shed) => debug!("ContextState: Quota: Shedding message: {:?}", shed),
+                    None => break,
+                },
+                QuotaAction::Log => break,
+            }
+        }
+
+        self.buffered_bytes.fetch_add(size, Ordering::SeqCst);
+        true
+    }
+
+    // Takes the next message broadcasted to the group with at least
+    // the `min` priority, delivered like the ones distributed by the
+    // dispatchers' handlers.
+    fn pop_prioritized(&self, min: Priority) -> Option<SignedMessage> {
+        let msg = self.queues.iter().find_map(|queue| queue.pop(min))?;
+        Some(SignedMessage::new(Msg::tell(msg), RefAddr::dead_letters()))
+    }
+
+    // Takes the oldest message out of the mailbox.
+    fn take_message(&self) -> Option<SignedMessage> {
+        let msg = self.messages.pop()?;
+        self.mailbox_depth.set(self.messages.len());
+        #[cfg(feature = "inspector")]
+        self.probe.popped();
+        if let Some(demand) = &self.demand {
+            demand.request(1);
+        }
+        if self.quota.is_some() {
+            self.buffered_bytes
+                .fetch_sub(msg.msg.size(), Ordering::SeqCst);
+        }
+        if let Some(watermarks) = &self.watermarks {
+            watermarks.update(self.messages.len());
+        }
+
+        Some(msg)
+    }
+
+    // Takes the next message out of `source`.
+    fn pop_from(&self, source: MessageSource) -> Option<SignedMessage> {
+        match source {
+            MessageSource::HighPriority => self.pop_prioritized(Priority::High),
+            MessageSource::Mailbox => {
+                let msg = self.take_message()?;
+                #[cfg(feature = "metrics")]
+                {
+                    self.processed.fetch_add(1, Ordering::Relaxed);
+                    // FIXME: panics?
+                    *self
+                        .processed_by_type
+                        .lock()
+                        .unwrap()
+                        .entry(msg.msg.type_name())
+                        .or_insert(0) += 1;
+                }
+
+                Some(msg)
+            }
+            MessageSource::LowPriority => self.pop_prioritized(Priority::Low),
+        }
+    }
+
+    fn pop_next(&self) -> Option<SignedMessage> {
+        let replaying = self.replay.as_ref().and_then(ReplayBuffer::pop_replaying);
+        match (replaying, &self.fairness) {
+            (Some(msg), _) => Some(msg),
+            (None, Some(fairness)) => fairness.next(|source| self.pop_from(source)),
+            (None, None) => self
+                .pop_from(MessageSource::HighPriority)
+                .or_else(|| self.pop_from(MessageSource::Mailbox))
+                .or_else(|| self.pop_from(MessageSource::LowPriority)),
+        }
     }
 
     pub(crate) fn pop_message(&self) -> Option<SignedMessage> {
-        self.messages.pop()
+        // The messages are taken out of the redelivery queue once
+        // their backoff delay elapsed and their tickets were received.
+        // A ticket broadcasted to the group whose message was already
+        // taken by another element is skipped.
+        let (delivered, msg) = loop {
+            let msg = self.pop_next()?;
+            let ticket = msg
+                .msg
+                .as_ref()
+                .downcast_ref::<RedeliveryTicket>()
+                .map(|ticket| ticket.0);
+            match (&self.redelivery, ticket) {
+                (Some(redelivery), Some(ticket)) => {
+                    if let Some(scheduled) = redelivery.take(ticket) {
+                        break scheduled;
+                    }
+                }
+                _ => break (0, msg),
+            }
+        };
+        self.deliveries.store(delivered + 1, Ordering::Relaxed);
+
+        if let Some(replay) = &self.replay {
+            replay.record(&msg);
+        }
+        if let Some(activity) = &self.activity {
+            activity.touch();
+        }
+        self.poll_site.handling(msg.msg.type_name());
+        let sampled = self.sampler.as_ref().is_none_or(|sampler| sampler.sample());
+        self.sampled.store(sampled, Ordering::Relaxed);
+        // FIXME: panics?
+        *self.deadline.lock().unwrap() = msg.msg.deadline();
+
+        Some(msg)
     }
 
-    #[cfg(feature = "scaling")]
+    /// Returns the deadline of the last received message, if any.
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        // FIXME: panics?
+        *self.deadline.lock().unwrap()
+    }
+
+    pub(crate) fn readiness(&self) -> Option<&Arc<Readiness>> {
+        self.readiness.as_ref()
+    }
+
+    pub(crate) fn isolates_panics(&self) -> bool {
+        self.isolate_panics
+    }
+
+    pub(crate) fn dispatcher_scope(&self) -> &[String] {
+        &self.dispatcher_scope
+    }
+
+    pub(crate) fn is_sampled(&self) -> bool {
+        self.sampled.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    pub(crate) fn drain(&self) -> Option<&Arc<Drain>> {
+        self.drain.as_ref()
+    }
+
+    pub(crate) fn redelivery(&self) -> Option<&Arc<RedeliveryQueue>> {
+        self.redelivery.as_ref()
+    }
+
+    /// Returns the number of times the last message taken out of the
+    /// mailbox was delivered.
+    pub(crate) fn deliveries(&self) -> usize {
+        self.deliveries.load(Ordering::Relaxed)
+    }
+
+    /// Returns whether the messages whose handlers panic are traced
+    /// even if they weren't sampled.
+    pub(crate) fn samples_errors(&self) -> bool {
+        self.sampler
+            .as_ref()
+            .is_none_or(|sampler| sampler.samples_errors())
+    }
+
+    #[cfg(feature = "inspector")]
+    pub(crate) fn mailbox_probe(&self) -> &Arc<MailboxProbe> {
+        &self.probe
+    }
+
+    pub(crate) fn poll_site(&self) -> &Arc<PollSite> {
+        &self.poll_site
+    }
+
+    pub(crate) fn mailbox_depth(&self) -> &Arc<MailboxDepth> {
+        &self.mailbox_depth
+    }
+
+    /// Returns whether the mailbox has no more messages waiting to
+    /// be received.
+    pub(crate) fn is_mailbox_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Makes the last processed messages be received again before
+    /// the ones that are still in the mailbox.
+    pub(crate) fn replay_processed(&self) {
+        if let Some(replay) = &self.replay {
+            replay.rewind();
+        }
+    }
+
+    #[cfg(any(feature = "scaling", feature = "metrics"))]
     pub(crate) fn mailbox_size(&self) -> u32 {
         self.messages.len() as _
     }
+
+    /// Returns the number of messages taken out of the mailbox.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn processed(&self) -> u64 {
+        self.processed.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of messages of each type taken out of
+    /// the mailbox.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn processed_by_type(&self) -> FxHashMap<&'static str, u64> {
+        // FIXME: panics?
+        self.processed_by_type.lock().unwrap().clone()
+    }
+
+    /// Returns the number of times each watermark of the mailbox was
+    /// crossed while it was filling up.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn watermark_crossings(&self) -> Vec<(u8, u64)> {
+        self.watermarks
+            .as_ref()
+            .map(|watermarks| watermarks.crossings().collect())
+            .unwrap_or_default()
+    }
+}
+
+impl Replay {
+    pub(crate) fn new<M: Message + Clone>(last: usize) -> Self {
+        let cloner = Arc::new(|msg: &Msg| {
+            let msg: &M = msg.as_ref().downcast_ref()?;
+            Some(Msg::tell(msg.clone()))
+        });
+
+        Replay { last, cloner }
+    }
+}
+
+impl ReplayBuffer {
+    fn new(replay: Replay) -> Self {
+        let processed = Mutex::new(VecDeque::with_capacity(replay.last));
+        let in_flight = Mutex::new(None);
+        let replaying = Mutex::new(VecDeque::new());
+
+        ReplayBuffer {
+            replay,
+            processed,
+            in_flight,
+            replaying,
+        }
+    }
+
+    fn record(&self, msg: &SignedMessage) {
+        if self.replay.last == 0 {
+            return;
+        }
+
+        let clone =
+            (self.replay.cloner)(&msg.msg).map(|clone| SignedMessage::new(clone, msg.sign.clone()));
+        // FIXME: panics?
+        let mut in_flight = self.in_flight.lock().unwrap();
+        // The previous message was processed since the element
+        // received a new one.
+        if let Some(processed_msg) = std::mem::replace(&mut *in_flight, clone) {
+            // FIXME: panics?
+            let mut processed = self.processed.lock().unwrap();
+            if processed.len() == self.replay.last {
+                processed.pop_front();
+            }
+
+            processed.push_back(processed_msg);
+        }
+    }
+
+    fn pop_replaying(&self) -> Option<SignedMessage> {
+        // FIXME: panics?
+        self.replaying.lock().unwrap().pop_front()
+    }
+
+    fn rewind(&self) {
+        // The message that was being processed is dropped, so that
+        // a message making the element fault isn't received again
+        // every time it is restarted.
+        // FIXME: panics?
+        self.in_flight.lock().unwrap().take();
+        // FIXME: panics?
+        let mut processed = self.processed.lock().unwrap();
+        let mut replaying = self.replaying.lock().unwrap();
+        // The messages that weren't replayed yet are more recent
+        // than the ones that were processed.
+        processed.append(&mut replaying);
+        *replaying = processed.split_off(0);
+    }
+}
+
+impl Debug for Replay {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Replay")
+            .field("last", &self.last)
+            .finish()
+    }
 }
 
 impl Display for BastionId {
     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
-        self.0.fmt(fmt)
+        Display::fmt(&self.0, fmt)
     }
 }
 
+// Polls `fut` within `span`, entering it every time `fut` is polled.
+async fn in_span<F: Future>(span: Span, fut: F) -> F::Output {
+    pin_mut!(fut);
+    future::poll_fn(|ctx| {
+        let _entered = span.enter();
+        fut.as_mut().poll(ctx)
+    })
+    .await
+}
+
 #[cfg(test)]
 mod context_tests {
     use super::*;
@@ -0,0 +1,221 @@
+//!
+//! Demand-based backpressure between producers and children groups.
+//!
+//! The mailboxes of the children groups are unbounded, which means that
+//! a producer faster than the group it feeds makes the group's mailboxes
+//! grow without bounds. A [`Demand`] lets the group pull messages
+//! instead: the group advertises how many messages it can take and the
+//! producers only push that many, waiting for the group to advertise
+//! more before pushing again.
+//!
+//! A children group advertises its demand once it was given a [`Demand`]
+//! with [`Children::with_demand`], and producers push according to it
+//! either by acquiring a unit of demand before each message with
+//! [`Demand::acquire`], or by forwarding a stream to the group with
+//! [`forward`].
+//!
+//! [`Children::with_demand`]: crate::children::Children::with_demand
+use crate::children_ref::ChildrenRef;
+use crate::message::Message;
+use futures::prelude::*;
+use pin_utils::pin_mut;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use tracing::{debug, trace};
+
+#[derive(Clone)]
+/// The number of messages the children groups sharing it can take.
+///
+/// Cloning a `Demand` returns a new handle to the same demand.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::demand::{self, Demand};
+/// # use futures::stream;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let demand = Demand::new();
+///
+/// let children_ref = Bastion::children(|children| {
+///     children
+///         // Every element takes up to 16 messages at a time...
+///         .with_demand(demand.clone(), 16)
+///         .with_exec(|ctx| {
+///             async move {
+///                 // ...
+///                 # Ok(())
+///             }
+///         })
+/// }).expect("Couldn't create the children group.");
+///
+/// # Bastion::start();
+/// // ...and the producer only pushes as many messages as they can take.
+/// spawn!(async move {
+///     demand::forward(stream::iter(0..1_000u64), demand, children_ref)
+///         .await
+///         .expect("Couldn't forward the stream.");
+/// });
+/// #
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Demand {
+    inner: Arc<DemandInner>,
+}
+
+struct DemandInner {
+    // The number of messages which can still be pushed.
+    available: AtomicUsize,
+    // The producers waiting for more demand.
+    wakers: Mutex<Vec<Waker>>,
+}
+
+#[derive(Debug)]
+/// Future returned by [`Demand::acquire`].
+pub struct Acquire<'a> {
+    demand: &'a Demand,
+}
+
+impl Demand {
+    /// Creates a new demand, which doesn't allow to push any message
+    /// until some is requested.
+    pub fn new() -> Self {
+        let inner = Arc::new(DemandInner {
+            available: AtomicUsize::new(0),
+            wakers: Mutex::new(Vec::new()),
+        });
+
+        Demand { inner }
+    }
+
+    /// Advertises that `n` more messages can be taken, waking up the
+    /// producers waiting for demand.
+    pub fn request(&self, n: usize) {
+        if n == 0 {
+            return;
+        }
+
+        trace!("Demand: Requesting {} messages.", n);
+        self.inner.available.fetch_add(n, Ordering::SeqCst);
+
+        // FIXME: panics?
+        let wakers = std::mem::take(&mut *self.inner.wakers.lock().unwrap());
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Returns the number of messages which can still be pushed.
+    pub fn available(&self) -> usize {
+        self.inner.available.load(Ordering::SeqCst)
+    }
+
+    /// Takes a unit of demand without waiting, returning `false` if
+    /// there is none left.
+    pub fn try_acquire(&self) -> bool {
+        self.inner
+            .available
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |available| {
+                available.checked_sub(1)
+            })
+            .is_ok()
+    }
+
+    /// Returns a future taking a unit of demand, waiting for some to
+    /// be requested if there is none left.
+    pub fn acquire(&self) -> Acquire<'_> {
+        Acquire { demand: self }
+    }
+}
+
+impl<'a> Future for Acquire<'a> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if self.demand.try_acquire() {
+            return Poll::Ready(());
+        }
+
+        // FIXME: panics?
+        self.demand
+            .inner
+            .wakers
+            .lock()
+            .unwrap()
+            .push(cx.waker().clone());
+
+        // Some demand might have been requested while the waker
+        // was being registered.
+        if self.demand.try_acquire() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Sends the items of `stream` to the elements of `children` in a
+/// round-robin fashion, taking a unit of `demand` before sending
+/// each of them.
+///
+/// This method returns `()` once the stream is exhausted, or the
+/// item that couldn't be sent otherwise.
+pub async fn forward<S>(stream: S, demand: Demand, children: ChildrenRef) -> Result<(), S::Item>
+where
+    S: Stream,
+    S::Item: Message,
+{
+    debug!(
+        "Demand: Forwarding a stream to Children({}).",
+        children.id()
+    );
+    pin_mut!(stream);
+
+    let elems = children.elems();
+    let mut next = 0;
+    while let Some(item) = stream.next().await {
+        if elems.is_empty() {
+            return Err(item);
+        }
+
+        demand.acquire().await;
+        elems[next % elems.len()].tell_anonymously(item)?;
+        next += 1;
+    }
+
+    Ok(())
+}
+
+impl Default for Demand {
+    fn default() -> Self {
+        Demand::new()
+    }
+}
+
+impl Debug for Demand {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Demand")
+            .field("available", &self.available())
+            .finish()
+    }
+}
@@ -3,14 +3,21 @@
 //! group of actors through the dispatchers that holds information about
 //! actors grouped together.
 use crate::child_ref::ChildRef;
-use crate::envelope::SignedMessage;
+use crate::envelope::{Envelope, SignedMessage};
+use crate::message::BastionMessage;
 use anyhow::Result as AnyResult;
 use lever::prelude::*;
+use std::cmp;
+use std::collections::{BTreeSet, BinaryHeap};
 use std::fmt::{self, Debug};
 use std::hash::{Hash, Hasher};
+#[cfg(feature = "chaos")]
+use std::sync::atomic::AtomicBool;
+#[cfg(feature = "distributed")]
+use std::sync::Weak;
 use std::sync::{
     atomic::{AtomicUsize, Ordering},
-    Arc,
+    Arc, Mutex,
 };
 use tracing::{debug, trace, warn};
 
@@ -40,12 +47,31 @@ pub enum BroadcastTarget {
     /// Send the broadcasted message to everyone in the system.
     All,
     /// Send the broadcasted message to each actor in group.
+    ///
+    /// If some segments of the group name are wildcards (eg.
+    /// `"payments.*"`), the message is sent to each actor of every
+    /// group whose name matches it (see [`DispatcherType::matches`]).
+    ///
+    /// When clustering is enabled, the textual messages (`String`s
+    /// and `&'static str`s) are also sent to the groups with the
+    /// same name on the other members of the cluster.
     Group(String),
+    /// Send the broadcasted message to each actor in group, like
+    /// [`BroadcastTarget::Group`], but only on the current node even
+    /// when clustering is enabled.
+    LocalGroup(String),
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 /// Defines the type of the dispatcher.
 ///
+/// The names of the `Named` dispatchers can be made of segments
+/// separated by dots (eg. `"payments.workers.eu"`), which puts them
+/// in a hierarchy: `payments.workers.eu` is a child of
+/// `payments.workers`, itself a child of `payments`. Messages can then
+/// be broadcasted to whole families of groups with wildcards (see
+/// [`DispatcherType::matches`]).
+///
 /// The default type is `Anonymous`.
 pub enum DispatcherType {
     /// The default kind of the dispatcher which is using for
@@ -60,6 +86,25 @@ pub enum DispatcherType {
     Named(String),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Defines the priority of a message broadcasted through a
+/// dispatcher with [`Dispatcher::broadcast_message_with_priority`].
+///
+/// The messages broadcasted without a priority have the `Normal`
+/// one.
+pub enum Priority {
+    /// The message is received by the actors of the group once
+    /// their mailboxes are empty.
+    Low,
+    /// The message is distributed by the dispatcher's handler,
+    /// like the ones broadcasted without a priority.
+    Normal,
+    /// The message is received by the first actor of the group
+    /// looking for a message, before the messages already queued
+    /// in its mailbox.
+    High,
+}
+
 /// The default handler, which does round-robin.
 pub type DefaultDispatcherHandler = RoundRobinHandler;
 
@@ -132,6 +177,13 @@ pub struct Dispatcher {
     /// Special field that stores information about all
     /// registered actors in the group.
     actors: DispatcherMap,
+    /// The messages broadcasted with a priority, shared by all
+    /// the registered actors.
+    queue: Arc<PriorityQueue>,
+    /// Whether the messages broadcasted through the dispatcher
+    /// are dropped, to simulate a network partition.
+    #[cfg(feature = "chaos")]
+    partitioned: AtomicBool,
 }
 
 impl Dispatcher {
@@ -162,6 +214,9 @@ impl Dispatcher {
             dispatcher_type,
             handler: Box::new(DefaultDispatcherHandler::default()),
             actors: Default::default(),
+            queue: Default::default(),
+            #[cfg(feature = "chaos")]
+            partitioned: AtomicBool::new(false),
         }
     }
 
@@ -205,8 +260,84 @@ impl Dispatcher {
     /// The logic of who and how should receive the message relies onto
     /// the handler implementation.
     pub fn broadcast_message(&self, message: &Arc<SignedMessage>) {
+        #[cfg(feature = "chaos")]
+        {
+            if self.partitioned.load(Ordering::SeqCst) {
+                warn!(
+                    "Chaos: Dropping a message broadcasted to the partitioned {:?} dispatcher.",
+                    self.dispatcher_type
+                );
+                return;
+            }
+        }
+
         self.handler.broadcast_message(&self.actors, &message);
     }
+
+    /// Sends the message to the group of actors, with the given
+    /// priority.
+    ///
+    /// Messages with a `Normal` priority are distributed by the
+    /// handler, like with [`broadcast_message`]. The others are put
+    /// into a queue shared by the whole group, from which the first
+    /// actor looking for a message takes the oldest message with the
+    /// highest priority: `High` priority messages are received before
+    /// the messages already queued in the actors' mailboxes, and `Low`
+    /// priority ones once the mailboxes are empty.
+    ///
+    /// [`broadcast_message`]: Self::broadcast_message
+    pub fn broadcast_message_with_priority(
+        &self,
+        message: &Arc<SignedMessage>,
+        priority: Priority,
+    ) {
+        if priority == Priority::Normal {
+            return self.broadcast_message(message);
+        }
+
+        #[cfg(feature = "chaos")]
+        {
+            if self.partitioned.load(Ordering::SeqCst) {
+                warn!(
+                    "Chaos: Dropping a message broadcasted to the partitioned {:?} dispatcher.",
+                    self.dispatcher_type
+                );
+                return;
+            }
+        }
+
+        self.queue.push(priority, message.clone());
+
+        // Wakes every actor up, so that the first one looking
+        // for a message takes it.
+        let mut woken = 0;
+        for entry in self.actors.iter().filter(|entry| entry.0.is_public()) {
+            let env = Envelope::from_dead_letters(BastionMessage::heartbeat());
+            if entry.0.send(env).is_ok() {
+                woken += 1;
+            }
+        }
+
+        if woken == 0 {
+            debug!(
+                "no public children to wake up, queuing message with {:?} priority",
+                priority
+            );
+        }
+    }
+
+    /// Returns the queue of the messages broadcasted with a
+    /// priority.
+    pub(crate) fn queue(&self) -> &Arc<PriorityQueue> {
+        &self.queue
+    }
+
+    /// Starts or stops dropping the messages broadcasted through
+    /// the dispatcher.
+    #[cfg(feature = "chaos")]
+    pub(crate) fn set_partitioned(&self, partitioned: bool) {
+        self.partitioned.store(partitioned, Ordering::SeqCst);
+    }
 }
 
 impl Debug for Dispatcher {
@@ -227,6 +358,109 @@ impl DispatcherType {
             DispatcherType::Named(value) => value.to_owned(),
         }
     }
+
+    /// Returns the parent of this dispatcher in the hierarchy of the
+    /// dispatchers' names (eg. `payments.workers` for
+    /// `payments.workers.eu`), if it has one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// let eu = DispatcherType::Named("payments.workers.eu".to_string());
+    /// let workers = DispatcherType::Named("payments.workers".to_string());
+    ///
+    /// assert_eq!(eu.parent(), Some(workers));
+    /// assert_eq!(DispatcherType::Anonymous.parent(), None);
+    /// ```
+    pub fn parent(&self) -> Option<DispatcherType> {
+        match self {
+            DispatcherType::Anonymous => None,
+            DispatcherType::Named(value) => value
+                .rfind('.')
+                .map(|idx| DispatcherType::Named(value[..idx].to_string())),
+        }
+    }
+
+    /// Returns whether this dispatcher is below `ancestor` in the
+    /// hierarchy of the dispatchers' names.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// let eu = DispatcherType::Named("payments.workers.eu".to_string());
+    /// let payments = DispatcherType::Named("payments".to_string());
+    ///
+    /// assert!(eu.is_descendant_of(&payments));
+    /// assert!(!payments.is_descendant_of(&eu));
+    /// ```
+    pub fn is_descendant_of(&self, ancestor: &DispatcherType) -> bool {
+        match (self, ancestor) {
+            (DispatcherType::Named(value), DispatcherType::Named(ancestor)) => {
+                value.len() > ancestor.len()
+                    && value.starts_with(ancestor.as_str())
+                    && value[ancestor.len()..].starts_with('.')
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns whether the name of this dispatcher matches `pattern`,
+    /// whose segments are either names, which have to be equal to
+    /// the dispatcher's ones, or wildcards (`*`), which match any
+    /// segment. A wildcard at the end of the pattern matches one or
+    /// more segments, so that `"payments.*"` matches every dispatcher
+    /// below `payments`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// let eu = DispatcherType::Named("payments.workers.eu".to_string());
+    ///
+    /// assert!(eu.matches("payments.*"));
+    /// assert!(eu.matches("payments.*.eu"));
+    /// assert!(!eu.matches("payments.*.us"));
+    /// assert!(!eu.matches("billing.*"));
+    /// ```
+    pub fn matches(&self, pattern: &str) -> bool {
+        let value = match self {
+            DispatcherType::Anonymous => return false,
+            DispatcherType::Named(value) => value,
+        };
+
+        let pattern = pattern.split('.').collect::<Vec<_>>();
+        let segments = value.split('.').collect::<Vec<_>>();
+        let family = pattern.last() == Some(&"*");
+        if segments.len() < pattern.len() || (!family && segments.len() > pattern.len()) {
+            return false;
+        }
+
+        pattern
+            .iter()
+            .zip(segments.iter())
+            .all(|(pattern, segment)| *pattern == "*" || pattern == segment)
+    }
+
+    /// Returns the type of the dispatcher named `name` in the
+    /// dispatcher scope `scope` (the names of the nested scopes,
+    /// from the outermost to the innermost one).
+    pub(crate) fn scoped(scope: &[String], name: &str) -> DispatcherType {
+        if scope.is_empty() {
+            return DispatcherType::Named(name.to_string());
+        }
+
+        DispatcherType::Named(format!("{}/{}", scope.join("/"), name))
+    }
+
+    // Returns whether `name` contains wildcards.
+    fn is_pattern(name: &str) -> bool {
+        name.split('.').any(|segment| segment == "*")
+    }
 }
 
 impl Default for Dispatcher {
@@ -235,6 +469,9 @@ impl Default for Dispatcher {
             dispatcher_type: DispatcherType::default(),
             handler: Box::new(DefaultDispatcherHandler::default()),
             actors: LOTable::new(),
+            queue: Default::default(),
+            #[cfg(feature = "chaos")]
+            partitioned: AtomicBool::new(false),
         }
     }
 }
@@ -269,6 +506,23 @@ impl Into<DispatcherType> for String {
 pub(crate) struct GlobalDispatcher {
     /// Storage for all registered group of actors.
     pub dispatchers: LOTable<DispatcherType, Arc<Box<Dispatcher>>>,
+    /// The names of the registered named dispatchers, sorted so
+    /// that the dispatchers below another one in the hierarchy
+    /// follow it.
+    hierarchy: Mutex<BTreeSet<String>>,
+    /// The relay sending the messages broadcasted to groups to
+    /// the other members of the cluster, if any.
+    #[cfg(feature = "distributed")]
+    relay: Mutex<Option<Weak<dyn BroadcastRelay>>>,
+}
+
+#[cfg(feature = "distributed")]
+/// Sends the messages broadcasted to groups on the current node to
+/// the groups with the same name on the other members of the cluster.
+pub(crate) trait BroadcastRelay: Debug + Send + Sync + 'static {
+    /// Sends `text` to the groups matching `group` on the other
+    /// members of the cluster.
+    fn relay(&self, group: &str, text: &str);
 }
 
 impl GlobalDispatcher {
@@ -276,6 +530,40 @@ impl GlobalDispatcher {
     pub(crate) fn new() -> Self {
         GlobalDispatcher {
             dispatchers: LOTable::new(),
+            hierarchy: Mutex::new(BTreeSet::new()),
+            #[cfg(feature = "distributed")]
+            relay: Mutex::new(None),
+        }
+    }
+
+    #[cfg(feature = "distributed")]
+    /// Sets the relay sending the messages broadcasted to groups to
+    /// the other members of the cluster, replacing the previous one.
+    pub(crate) fn set_relay(&self, relay: Weak<dyn BroadcastRelay>) {
+        // FIXME: panics?
+        *self.relay.lock().unwrap() = Some(relay);
+    }
+
+    #[cfg(feature = "distributed")]
+    /// Sends the given message to the groups matching `group` on the
+    /// other members of the cluster, if it is textual and clustering
+    /// is enabled.
+    fn relay(&self, group: &str, message: &SignedMessage) {
+        // FIXME: panics?
+        let relay = match self.relay.lock().unwrap().as_ref().and_then(Weak::upgrade) {
+            Some(relay) => relay,
+            None => return,
+        };
+
+        if let Some(text) = message.msg.downcast_ref::<String>() {
+            relay.relay(group, &text);
+        } else if let Some(text) = message.msg.downcast_ref::<&'static str>() {
+            relay.relay(group, &text);
+        } else {
+            trace!(
+                "The non-textual message can't be relayed to the '{}' groups of the cluster.",
+                group
+            );
         }
     }
 
@@ -332,15 +620,46 @@ impl GlobalDispatcher {
 
     /// Broadcasts the given message in according with the specified target.
     pub(crate) fn broadcast_message(&self, target: BroadcastTarget, message: &Arc<SignedMessage>) {
+        self.broadcast_message_with_priority(target, message, Priority::Normal)
+    }
+
+    /// Broadcasts the given message with the given priority in according
+    /// with the specified target.
+    pub(crate) fn broadcast_message_with_priority(
+        &self,
+        target: BroadcastTarget,
+        message: &Arc<SignedMessage>,
+        priority: Priority,
+    ) {
         let mut acked_dispatchers: Vec<DispatcherType> = Vec::new();
 
+        #[cfg(feature = "distributed")]
+        {
+            if let BroadcastTarget::Group(name) = &target {
+                self.relay(name, message);
+            }
+        }
+
         match target {
             BroadcastTarget::All => self
                 .dispatchers
                 .iter()
                 .map(|pair| pair.0.name().into())
                 .for_each(|group_name| acked_dispatchers.push(group_name)),
-            BroadcastTarget::Group(name) => {
+            BroadcastTarget::Group(name) | BroadcastTarget::LocalGroup(name)
+                if DispatcherType::is_pattern(&name) =>
+            {
+                let matching = self.matching(&name);
+                if matching.is_empty() {
+                    warn!(
+                        "The message can't be delivered to the groups matching the '{}' name.",
+                        name
+                    );
+                }
+
+                acked_dispatchers.extend(matching);
+            }
+            BroadcastTarget::Group(name) | BroadcastTarget::LocalGroup(name) => {
                 let target_dispatcher = name.into();
                 acked_dispatchers.push(target_dispatcher);
             }
@@ -349,7 +668,7 @@ impl GlobalDispatcher {
         for dispatcher_type in acked_dispatchers {
             match self.dispatchers.get(&dispatcher_type) {
                 Some(dispatcher) => {
-                    dispatcher.broadcast_message(&message.clone());
+                    dispatcher.broadcast_message_with_priority(&message.clone(), priority);
                 }
                 // TODO: Put the message into the dead queue
                 None => {
@@ -363,19 +682,97 @@ impl GlobalDispatcher {
         }
     }
 
+    /// Resolves the group named by `target` from the dispatcher
+    /// scope `scope`: the dispatchers registered in the innermost
+    /// scope are looked up first, then the ones registered in the
+    /// scopes enclosing it, and finally the global ones. The targets
+    /// naming every group or groups matching a pattern are returned
+    /// as is.
+    pub(crate) fn resolve(&self, target: BroadcastTarget, scope: &[String]) -> BroadcastTarget {
+        let name = match &target {
+            BroadcastTarget::Group(name) | BroadcastTarget::LocalGroup(name)
+                if !DispatcherType::is_pattern(name) =>
+            {
+                name.clone()
+            }
+            _ => return target,
+        };
+
+        let resolved = (1..=scope.len())
+            .rev()
+            .map(|depth| DispatcherType::scoped(&scope[..depth], &name))
+            .find(|dispatcher_type| self.dispatchers.contains_key(dispatcher_type));
+
+        match (resolved, target) {
+            (Some(dispatcher_type), BroadcastTarget::Group(_)) => {
+                trace!("Resolved the '{}' group to {:?}.", name, dispatcher_type);
+                BroadcastTarget::Group(dispatcher_type.name())
+            }
+            (Some(dispatcher_type), BroadcastTarget::LocalGroup(_)) => {
+                trace!("Resolved the '{}' group to {:?}.", name, dispatcher_type);
+                BroadcastTarget::LocalGroup(dispatcher_type.name())
+            }
+            (_, target) => target,
+        }
+    }
+
+    /// Returns the registered dispatchers whose names match the
+    /// given pattern.
+    pub(crate) fn matching(&self, pattern: &str) -> Vec<DispatcherType> {
+        // Only the dispatchers whose names start with the segments
+        // before the first wildcard can match.
+        let prefix = pattern
+            .split('.')
+            .take_while(|segment| *segment != "*")
+            .map(|segment| format!("{}.", segment))
+            .collect::<String>();
+
+        // FIXME: panics?
+        let hierarchy = self.hierarchy.lock().unwrap();
+        hierarchy
+            .range(prefix.clone()..)
+            .take_while(|name| name.starts_with(&prefix))
+            .map(|name| DispatcherType::Named(name.clone()))
+            .filter(|dispatcher_type| dispatcher_type.matches(pattern))
+            .collect()
+    }
+
+    /// Returns the queues of the messages broadcasted with a priority
+    /// through the given dispatchers.
+    pub(crate) fn queues(&self, dispatchers: &[DispatcherType]) -> Vec<Arc<PriorityQueue>> {
+        dispatchers
+            .iter()
+            .filter_map(|key| self.dispatchers.get(key))
+            .map(|dispatcher| dispatcher.queue().clone())
+            .collect()
+    }
+
     /// Adds dispatcher to the global registry.
     pub(crate) fn register_dispatcher(&self, dispatcher: &Arc<Box<Dispatcher>>) -> AnyResult<()> {
         let dispatcher_type = dispatcher.dispatcher_type();
         let is_registered = self.dispatchers.contains_key(&dispatcher_type);
 
         if is_registered && dispatcher_type != DispatcherType::Anonymous {
-            warn!(
-                "The dispatcher with the '{:?}' name already registered in the cluster.",
-                dispatcher_type
-            );
+            // The dispatchers of a supervisor are registered by each
+            // of the children groups it supervises.
+            let shared = self
+                .dispatchers
+                .get(&dispatcher_type)
+                .is_some_and(|registered| Arc::ptr_eq(&registered, dispatcher));
+            if !shared {
+                warn!(
+                    "The dispatcher with the '{:?}' name already registered in the cluster.",
+                    dispatcher_type
+                );
+            }
             return Ok(());
         }
 
+        if let DispatcherType::Named(name) = &dispatcher_type {
+            // FIXME: panics?
+            self.hierarchy.lock().unwrap().insert(name.clone());
+        }
+
         let instance = dispatcher.clone();
         self.dispatchers.insert(dispatcher_type, instance)?;
         Ok(())
@@ -383,11 +780,74 @@ impl GlobalDispatcher {
 
     /// Removes dispatcher from the global registry.
     pub(crate) fn remove_dispatcher(&self, dispatcher: &Arc<Box<Dispatcher>>) -> AnyResult<()> {
-        self.dispatchers.remove(&dispatcher.dispatcher_type())?;
+        let dispatcher_type = dispatcher.dispatcher_type();
+        if let DispatcherType::Named(name) = &dispatcher_type {
+            // FIXME: panics?
+            self.hierarchy.lock().unwrap().remove(name);
+        }
+
+        self.dispatchers.remove(&dispatcher_type)?;
         Ok(())
     }
 }
 
+#[derive(Debug, Default)]
+/// The messages broadcasted with a priority through a dispatcher,
+/// shared by all the actors registered in it.
+pub(crate) struct PriorityQueue {
+    heap: Mutex<BinaryHeap<Prioritized>>,
+    // The number of messages pushed so far, used to keep the
+    // messages of a same priority in order.
+    pushed: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct Prioritized {
+    priority: Priority,
+    seq: usize,
+    msg: Arc<SignedMessage>,
+}
+
+impl PriorityQueue {
+    pub(crate) fn push(&self, priority: Priority, msg: Arc<SignedMessage>) {
+        let seq = self.pushed.fetch_add(1, Ordering::SeqCst);
+        let msg = Prioritized { priority, seq, msg };
+        self.heap.lock().unwrap().push(msg);
+    }
+
+    /// Pops the oldest of the messages with the highest priority,
+    /// if this priority is at least `min`.
+    pub(crate) fn pop(&self, min: Priority) -> Option<Arc<SignedMessage>> {
+        let mut heap = self.heap.lock().unwrap();
+        match heap.peek() {
+            Some(msg) if msg.priority >= min => heap.pop().map(|msg| msg.msg),
+            _ => None,
+        }
+    }
+}
+
+impl Ord for Prioritized {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Prioritized {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Prioritized {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority && self.seq == other.seq
+    }
+}
+
+impl Eq for Prioritized {}
+
 #[cfg(test)]
 mod tests {
     use crate::child_ref::ChildRef;
@@ -684,4 +1144,105 @@ mod tests {
         let handler_was_called = handler.was_called();
         assert_eq!(handler_was_called, true);
     }
+
+    #[test]
+    fn test_dispatcher_type_hierarchy() {
+        let eu = DispatcherType::Named("payments.workers.eu".to_string());
+        let workers = DispatcherType::Named("payments.workers".to_string());
+        let payments = DispatcherType::Named("payments".to_string());
+
+        assert_eq!(eu.parent(), Some(workers.clone()));
+        assert_eq!(payments.parent(), None);
+        assert!(eu.is_descendant_of(&payments));
+        assert!(!eu.is_descendant_of(&DispatcherType::Named("pay".to_string())));
+
+        assert!(eu.matches("payments.*"));
+        assert!(eu.matches("*.workers.*"));
+        assert!(workers.matches("payments.*"));
+        assert!(!payments.matches("payments.*"));
+        assert!(!eu.matches("payments.*.us"));
+        assert!(!DispatcherType::Anonymous.matches("*"));
+    }
+
+    #[test]
+    fn test_global_dispatcher_broadcast_message_to_family() {
+        let global_dispatcher = GlobalDispatcher::new();
+        let handlers = ["payments.workers.eu", "payments.workers.us", "billing"]
+            .iter()
+            .map(|name| {
+                let handler = Box::new(CustomHandler::new(false));
+                let dispatcher_type = DispatcherType::Named(name.to_string());
+                let local_dispatcher = Arc::new(Box::new(
+                    Dispatcher::with_type(dispatcher_type).with_handler(handler.clone()),
+                ));
+                global_dispatcher
+                    .register_dispatcher(&local_dispatcher)
+                    .unwrap();
+
+                handler
+            })
+            .collect::<Vec<_>>();
+
+        let (sender, _) = mpsc::unbounded();
+        let path = Arc::new(BastionPath::root());
+        let message = Arc::new(SignedMessage::new(
+            Msg::broadcast("data"),
+            RefAddr::new(path, sender),
+        ));
+
+        let target = BroadcastTarget::Group("payments.*".to_string());
+        global_dispatcher.broadcast_message(target, &message);
+        assert!(handlers[0].was_called());
+        assert!(handlers[1].was_called());
+        assert!(!handlers[2].was_called());
+    }
+
+    #[test]
+    fn test_global_dispatcher_resolve_scoped_group() {
+        let global_dispatcher = GlobalDispatcher::new();
+        for name in &["billing/workers", "workers"] {
+            let dispatcher_type = DispatcherType::Named(name.to_string());
+            let local_dispatcher = Arc::new(Box::new(Dispatcher::with_type(dispatcher_type)));
+            global_dispatcher
+                .register_dispatcher(&local_dispatcher)
+                .unwrap();
+        }
+
+        let resolve = |scope: &[&str]| {
+            let scope = scope.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+            let target = BroadcastTarget::Group("workers".to_string());
+            match global_dispatcher.resolve(target, &scope) {
+                BroadcastTarget::Group(name) => name,
+                target => panic!("unexpected target: {:?}", target),
+            }
+        };
+        assert_eq!(resolve(&["billing"]), "billing/workers");
+        assert_eq!(resolve(&["billing", "eu"]), "billing/workers");
+        assert_eq!(resolve(&["payments"]), "workers");
+        assert_eq!(resolve(&[]), "workers");
+    }
+
+    #[test]
+    fn test_local_dispatcher_broadcast_message_with_priority() {
+        let handler = Box::new(CustomHandler::new(false));
+        let instance = Dispatcher::default().with_handler(handler.clone());
+        let (sender, _) = mpsc::unbounded();
+        let path = Arc::new(BastionPath::root());
+        let sign = RefAddr::new(path, sender);
+
+        let low = Arc::new(SignedMessage::new(Msg::broadcast("low"), sign.clone()));
+        let first = Arc::new(SignedMessage::new(Msg::broadcast("first"), sign.clone()));
+        let second = Arc::new(SignedMessage::new(Msg::broadcast("second"), sign));
+
+        instance.broadcast_message_with_priority(&low, Priority::Low);
+        instance.broadcast_message_with_priority(&first, Priority::High);
+        instance.broadcast_message_with_priority(&second, Priority::High);
+        assert!(!handler.was_called());
+
+        let queue = instance.queue();
+        assert!(Arc::ptr_eq(&queue.pop(Priority::High).unwrap(), &first));
+        assert!(Arc::ptr_eq(&queue.pop(Priority::High).unwrap(), &second));
+        assert!(queue.pop(Priority::High).is_none());
+        assert!(Arc::ptr_eq(&queue.pop(Priority::Low).unwrap(), &low));
+    }
 }
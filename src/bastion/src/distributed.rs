@@ -2,7 +2,11 @@
 //! Cluster formation and distributed actor instantiation
 use crate::children_ref::ChildrenRef;
 use crate::context::*;
+use crate::dispatcher::{BroadcastRelay, BroadcastTarget};
+use crate::envelope::{RefAddr, SignedMessage};
 use crate::message::Message;
+use crate::registry;
+use crate::system::SYSTEM;
 use crate::Bastion;
 
 use crate::message::Msg;
@@ -13,12 +17,51 @@ use std::sync::Arc;
 
 use core::future::Future;
 use futures::future;
+use std::convert::TryInto;
+use std::fmt::Debug;
+use std::io::Read;
 use tracing::*;
 
 use lever::table::lotable::*;
 
 use uuid::Uuid;
 
+#[cfg(feature = "metrics")]
+use crate::metrics::{self, Sample};
+#[cfg(feature = "metrics")]
+use std::sync::Mutex;
+#[cfg(feature = "metrics")]
+use std::time::{Duration, Instant};
+
+/// The prefix of the payloads announcing the roles of a node to the
+/// other members of the cluster.
+const ROLES_PREFIX: &str = "\u{0}bastion:roles:";
+/// The prefix of the payloads holding a message of a registered
+/// type, followed by the name of the type, a line feed and the
+/// message as JSON.
+const MESSAGE_PREFIX: &str = "\u{0}bastion:message:";
+/// The prefix of the payloads holding a message broadcasted to a
+/// group, followed by the name of the group and a line feed.
+const BROADCAST_PREFIX: &str = "\u{0}bastion:broadcast:";
+/// The prefix of the payloads announcing the compression algorithms
+/// a node supports to the other members of the cluster.
+const COMPRESSION_PREFIX: &str = "\u{0}bastion:compression:";
+/// The prefix of the compressed payloads, followed by the name of
+/// the algorithm, a colon and the base64 encoded compressed payload.
+const COMPRESSED_PREFIX: &str = "\u{0}bastion:compressed:";
+/// The default size (in bytes) from which the payloads get
+/// compressed.
+const DEFAULT_COMPRESSION_THRESHOLD: usize = 1024;
+/// The default maximum size (in bytes) of the decompressed payloads.
+const DEFAULT_MAX_DECOMPRESSED_SIZE: usize = 16 * 1024 * 1024;
+/// The prefix of the payloads holding the metrics of a node, sent to
+/// the node aggregating them, followed by the samples as JSON.
+const METRICS_PREFIX: &str = "\u{0}bastion:metrics:";
+/// The label added to the metrics imported from the other members of
+/// the cluster, holding the identifier of the member.
+#[cfg(feature = "metrics")]
+const NODE_LABEL: &str = "node";
+
 ///
 /// Cluster message that is sent and delivered among members
 #[derive(Debug)]
@@ -41,6 +84,316 @@ impl ClusterMessage {
     }
 }
 
+///
+/// Hook encoding the payloads before they are sent to the other members
+/// of the cluster and decoding them once they are received.
+///
+/// It is applied to the payloads themselves, independently of the transport,
+/// so that they can be encrypted and/or signed end-to-end, even when they go
+/// through untrusted relays or are stored in outboxes before being delivered.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use uuid::Uuid;
+/// #
+/// #[derive(Debug)]
+/// struct Reversed;
+///
+/// impl PayloadCodec for Reversed {
+///     fn seal(&self, _to: &Uuid, payload: &str) -> Result<String, ()> {
+///         Ok(payload.chars().rev().collect())
+///     }
+///
+///     fn open(&self, _from: &Uuid, payload: &str) -> Result<String, ()> {
+///         Ok(payload.chars().rev().collect())
+///     }
+/// }
+/// ```
+pub trait PayloadCodec: Debug + Send + Sync + 'static {
+    ///
+    /// Encodes a payload before it gets sent to the member `to`.
+    ///
+    /// Returning `Err(())` prevents the payload from being sent.
+    #[allow(clippy::result_unit_err)]
+    fn seal(&self, to: &Uuid, payload: &str) -> Result<String, ()>;
+
+    ///
+    /// Decodes a payload received from the member `from`.
+    ///
+    /// Returning `Err(())` (eg. because the payload couldn't be authenticated)
+    /// drops the payload.
+    #[allow(clippy::result_unit_err)]
+    fn open(&self, from: &Uuid, payload: &str) -> Result<String, ()>;
+}
+
+///
+/// Algorithm compressing the payloads sent to the other members of the
+/// cluster.
+///
+/// The algorithm used with a member is negotiated when it joins the
+/// cluster: both nodes announce the algorithms they support, and each
+/// one uses the first of its own that the other one supports. The
+/// payloads are compressed before being sealed by the [PayloadCodec],
+/// if any.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    ///
+    /// LZ4, which is fast but doesn't compress as much.
+    Lz4,
+    ///
+    /// Zstandard, which compresses more but is slower.
+    Zstd,
+}
+
+impl Compression {
+    fn name(self) -> &'static str {
+        match self {
+            Compression::Lz4 => "lz4",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "lz4" => Some(Compression::Lz4),
+            "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    fn compress(self, payload: &str) -> Result<String, ()> {
+        let compressed = match self {
+            Compression::Lz4 => lz4_flex::compress_prepend_size(payload.as_bytes()),
+            Compression::Zstd => zstd::encode_all(payload.as_bytes(), 0).map_err(|_| ())?,
+        };
+
+        Ok(base64::encode(compressed))
+    }
+
+    // Decompresses `payload`, returning `Err(())` if it couldn't be
+    // or if it would be larger than `max_size` once decompressed.
+    fn decompress(self, payload: &str, max_size: usize) -> Result<String, ()> {
+        let compressed = base64::decode(payload).map_err(|_| ())?;
+        let decompressed = match self {
+            Compression::Lz4 => {
+                // The size prepended by the peer is checked before
+                // anything gets allocated for it.
+                let size = compressed.get(..4).ok_or(())?.try_into().map_err(|_| ())?;
+                let size = u32::from_le_bytes(size) as usize;
+                if size > max_size {
+                    return Err(());
+                }
+
+                let decompressed = lz4_flex::decompress(&compressed[4..], size).map_err(|_| ())?;
+                if decompressed.len() != size {
+                    return Err(());
+                }
+
+                decompressed
+            }
+            Compression::Zstd => {
+                let decoder = zstd::stream::Decoder::new(compressed.as_slice()).map_err(|_| ())?;
+                let mut decompressed = Vec::new();
+                // One more byte is read to know whether the payload
+                // is larger than allowed.
+                decoder
+                    .take(max_size as u64 + 1)
+                    .read_to_end(&mut decompressed)
+                    .map_err(|_| ())?;
+                if decompressed.len() > max_size {
+                    return Err(());
+                }
+
+                decompressed
+            }
+        };
+
+        String::from_utf8(decompressed).map_err(|_| ())
+    }
+}
+
+///
+/// Bastion-specific options of the current node in a cluster, given to
+/// [`Bastion::distributed_with`] alongside its cluster configuration.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// let options = ClusterOptions::new()
+///     .with_role("worker")
+///     .with_role("storage")
+///     .with_compression(Compression::Zstd)
+///     .with_compression(Compression::Lz4)
+///     .with_compression_threshold(512)
+///     .with_max_decompressed_size(1024 * 1024);
+/// ```
+///
+/// [`Bastion::distributed_with`]: crate::Bastion::distributed_with
+#[derive(Debug, Clone)]
+pub struct ClusterOptions {
+    roles: Vec<String>,
+    codec: Option<Arc<dyn PayloadCodec>>,
+    compressions: Vec<Compression>,
+    compression_threshold: usize,
+    max_decompressed_size: usize,
+    #[cfg(feature = "metrics")]
+    metrics_aggregator: Option<(Uuid, Duration)>,
+}
+
+impl ClusterOptions {
+    ///
+    /// Creates options declaring no role and sending the payloads as is.
+    pub fn new() -> Self {
+        ClusterOptions::default()
+    }
+
+    ///
+    /// Declares that the current node has the given role (eg. `"worker"`,
+    /// `"frontend"` or `"storage"`), which gets announced to the other members
+    /// of the cluster when they join it.
+    ///
+    /// Roles shouldn't contain commas.
+    pub fn with_role(mut self, role: impl Into<String>) -> Self {
+        let role = role.into();
+        if !self.roles.contains(&role) {
+            self.roles.push(role);
+        }
+
+        self
+    }
+
+    ///
+    /// Sets the [PayloadCodec] sealing the payloads sent to the other members
+    /// of the cluster and opening the ones received from them.
+    pub fn with_codec<C: PayloadCodec>(mut self, codec: C) -> Self {
+        self.codec = Some(Arc::new(codec));
+        self
+    }
+
+    ///
+    /// Declares that the current node supports compressing the payloads
+    /// with the given [Compression] algorithm, the ones declared first
+    /// being preferred.
+    ///
+    /// The payloads are only compressed for the members which declared
+    /// supporting one of the same algorithms.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        if !self.compressions.contains(&compression) {
+            self.compressions.push(compression);
+        }
+
+        self
+    }
+
+    ///
+    /// Sets the size (in bytes) from which the payloads get compressed,
+    /// which defaults to 1024. Smaller payloads are always sent as is.
+    pub fn with_compression_threshold(mut self, threshold: usize) -> Self {
+        self.compression_threshold = threshold;
+        self
+    }
+
+    ///
+    /// Sets the maximum size (in bytes) of the payloads received compressed
+    /// once they are decompressed, which defaults to 16MiB. Larger payloads
+    /// are dropped without being decompressed entirely.
+    pub fn with_max_decompressed_size(mut self, size: usize) -> Self {
+        self.max_decompressed_size = size;
+        self
+    }
+
+    ///
+    /// Sends the metrics of the current node (see [`metrics::registry`])
+    /// to the member `node` every `interval`, so that a single scrape of
+    /// its registry shows the metrics of the whole cluster.
+    ///
+    /// The aggregating node imports them into its own registry, labeled
+    /// with the identifier of the node they come from (`node`), and
+    /// removes them once the node is down. The metrics are only sent
+    /// while the [`DistributedContext`] is receiving messages.
+    ///
+    /// [`metrics::registry`]: crate::metrics::registry
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics_aggregator(mut self, node: Uuid, interval: Duration) -> Self {
+        self.metrics_aggregator = Some((node, interval));
+        self
+    }
+
+    ///
+    /// Returns the roles declared by the current node.
+    pub fn roles(&self) -> &[String] {
+        &self.roles
+    }
+
+    ///
+    /// Returns the compression algorithms supported by the current node,
+    /// in order of preference.
+    pub fn compressions(&self) -> &[Compression] {
+        &self.compressions
+    }
+
+    // Returns the first of the compression algorithms supported by the
+    // current node that a member announced supporting as `names`.
+    fn negotiate_compression(&self, names: &str) -> Option<Compression> {
+        let supported: Vec<_> = names
+            .split(',')
+            .filter_map(Compression::from_name)
+            .collect();
+        self.compressions
+            .iter()
+            .find(|compression| supported.contains(compression))
+            .copied()
+    }
+
+    // Compresses `payload` with `compression`, returning `Ok(None)` if
+    // it's smaller than the threshold or if compressing it didn't make
+    // it smaller.
+    fn compress(&self, compression: Compression, payload: &str) -> Result<Option<String>, ()> {
+        if payload.len() < self.compression_threshold {
+            return Ok(None);
+        }
+
+        let compressed = compression.compress(payload)?;
+        let compressed = format!("{}{}:{}", COMPRESSED_PREFIX, compression.name(), compressed);
+        if compressed.len() >= payload.len() {
+            return Ok(None);
+        }
+
+        Ok(Some(compressed))
+    }
+
+    // Decompresses `payload` if it was compressed, returning `Err(())`
+    // if it couldn't be or if it would be too large once decompressed.
+    fn decompress(&self, payload: String) -> Result<String, ()> {
+        let compressed = match payload.strip_prefix(COMPRESSED_PREFIX) {
+            Some(compressed) => compressed,
+            None => return Ok(payload),
+        };
+
+        let idx = compressed.find(':').ok_or(())?;
+        let compression = Compression::from_name(&compressed[..idx]).ok_or(())?;
+        compression.decompress(&compressed[idx + 1..], self.max_decompressed_size)
+    }
+}
+
+impl Default for ClusterOptions {
+    fn default() -> Self {
+        ClusterOptions {
+            roles: Vec::new(),
+            codec: None,
+            compressions: Vec::new(),
+            compression_threshold: DEFAULT_COMPRESSION_THRESHOLD,
+            max_decompressed_size: DEFAULT_MAX_DECOMPRESSED_SIZE,
+            #[cfg(feature = "metrics")]
+            metrics_aggregator: None,
+        }
+    }
+}
+
 ///
 /// Distributed context that holds currently formed/forming cluster's context.
 #[derive(Debug)]
@@ -48,18 +401,29 @@ pub struct DistributedContext {
     bctx: BastionContext,
     me: Uuid,
     members: LOTable<Uuid, ArtilleryMember>,
+    member_roles: LOTable<Uuid, Vec<String>>,
+    member_compressions: LOTable<Uuid, Compression>,
     cluster: Arc<Cluster>,
+    options: ClusterOptions,
+    // When the metrics were last sent to the node aggregating them.
+    #[cfg(feature = "metrics")]
+    metrics_published: Mutex<Instant>,
 }
 
 impl DistributedContext {
     ///
     /// Initializes distributed context with underlying actor's local context and cluster handle.
-    fn new(bctx: BastionContext, cluster: Arc<Cluster>, me: Uuid) -> Self {
+    fn new(bctx: BastionContext, cluster: Arc<Cluster>, me: Uuid, options: ClusterOptions) -> Self {
         DistributedContext {
             bctx,
             me,
             members: LOTable::new(),
+            member_roles: LOTable::new(),
+            member_compressions: LOTable::new(),
             cluster,
+            options,
+            #[cfg(feature = "metrics")]
+            metrics_published: Mutex::new(Instant::now()),
         }
     }
 
@@ -90,38 +454,385 @@ impl DistributedContext {
             .collect()
     }
 
+    ///
+    /// Returns the roles declared by the current node.
+    pub fn roles(&self) -> &[String] {
+        self.options.roles()
+    }
+
+    ///
+    /// Returns the roles the member `id` announced, which are empty until
+    /// its announcement has been received.
+    pub fn member_roles(&self, id: &Uuid) -> Vec<String> {
+        self.member_roles.get(id).unwrap_or_default()
+    }
+
+    ///
+    /// Get current members of the cluster which announced having the given role.
+    ///
+    /// This allows to choose on which nodes the work is placed in heterogeneous clusters.
+    pub fn members_with_role(&self, role: &str) -> Vec<ArtilleryMember> {
+        self.members()
+            .into_iter()
+            .filter(|m| self.member_roles(&m.host_key()).iter().any(|r| r == role))
+            .collect()
+    }
+
     ///
     /// Send a fire and forget style message to a destined cluster member.
     /// Message needs to be stringified or apply the rules of bastion's [Message] trait.
+    ///
+    /// If a [PayloadCodec] was given to the cluster, the message is sealed with it
+    /// before being sent, and returned back if it couldn't be.
     pub fn tell<M>(&self, to: &Uuid, msg: M) -> Result<(), M>
     where
         M: Message + AsRef<str>,
     {
         debug!("Sending payload");
-        self.cluster.send_payload(*to, msg);
+        self.send(to, msg.as_ref()).map_err(|()| msg)
+    }
+
+    ///
+    /// Send a fire and forget style message of a type registered with
+    /// [`Bastion::register_message_type`] to a destined cluster member, which
+    /// receives it as a message of the same type (if it registered it too).
+    ///
+    /// The message is returned back if its type wasn't registered or if it
+    /// couldn't be serialized or sent.
+    ///
+    /// [`Bastion::register_message_type`]: crate::Bastion::register_message_type
+    pub fn tell_message<M>(&self, to: &Uuid, msg: M) -> Result<(), M>
+    where
+        M: Message + serde::Serialize,
+    {
+        if !registry::is_registered::<M>() {
+            warn!(
+                "DistributedContext({}): Can't send unregistered message type: {}",
+                self.me,
+                std::any::type_name::<M>()
+            );
+            return Err(msg);
+        }
+
+        let json = match serde_json::to_string(&msg) {
+            Ok(json) => json,
+            Err(_) => return Err(msg),
+        };
+
+        debug!("Sending message");
+        let payload = format!(
+            "{}{}\n{}",
+            MESSAGE_PREFIX,
+            std::any::type_name::<M>(),
+            json
+        );
+        self.send(to, &payload).map_err(|()| msg)
+    }
+
+    ///
+    /// Returns the compression algorithm used for the payloads sent to the
+    /// member `id`, if one was negotiated with it.
+    pub fn member_compression(&self, id: &Uuid) -> Option<Compression> {
+        self.member_compressions.get(id)
+    }
+
+    // Sends `payload` to `to`, after having compressed it if it's large
+    // enough and an algorithm was negotiated with `to`, and sealed it if
+    // a codec was given.
+    fn send(&self, to: &Uuid, payload: &str) -> Result<(), ()> {
+        let compressed = self.compress(to, payload);
+        let payload = compressed.as_deref().unwrap_or(payload);
+
+        match &self.options.codec {
+            Some(codec) => {
+                let payload = codec.seal(to, payload).map_err(|()| {
+                    warn!("DistributedContext({}): Couldn't seal payload.", self.me);
+                })?;
+                self.cluster.send_payload(*to, payload);
+            }
+            None => self.cluster.send_payload(*to, payload),
+        }
+
         Ok(())
     }
 
+    // Compresses `payload` for `to`, unless it's smaller than the
+    // threshold, no algorithm was negotiated or compressing it didn't
+    // make it smaller.
+    fn compress(&self, to: &Uuid, payload: &str) -> Option<String> {
+        let compression = self.member_compression(to)?;
+        match self.options.compress(compression, payload) {
+            Ok(compressed) => compressed,
+            Err(()) => {
+                warn!(
+                    "DistributedContext({}): Couldn't compress payload with {}.",
+                    self.me,
+                    compression.name()
+                );
+                None
+            }
+        }
+    }
+
+    // Announces the compression algorithms supported by the current node
+    // to `to`.
+    fn announce_compressions(&self, to: &Uuid) {
+        if self.options.compressions().is_empty() {
+            return;
+        }
+
+        debug!(
+            "DistributedContext({}): Announcing compressions to {}.",
+            self.me, to
+        );
+        let names: Vec<_> = self
+            .options
+            .compressions()
+            .iter()
+            .map(|compression| compression.name())
+            .collect();
+        let announcement = format!("{}{}", COMPRESSION_PREFIX, names.join(","));
+        self.send(to, &announcement).ok();
+    }
+
+    // Negotiates the compression algorithm used with `from`, which
+    // announced supporting `names`.
+    fn negotiate_compression(&self, from: Uuid, names: &str) {
+        match self.options.negotiate_compression(names) {
+            Some(compression) => {
+                debug!(
+                    "DistributedContext({}): Compressing payloads to {} with {}.",
+                    self.me,
+                    from,
+                    compression.name()
+                );
+                let _ = self.member_compressions.insert(from, compression);
+            }
+            None => {
+                let _ = self.member_compressions.remove(&from);
+            }
+        }
+    }
+
+    // Announces the roles of the current node to `to`.
+    fn announce_roles(&self, to: &Uuid) {
+        if self.roles().is_empty() {
+            return;
+        }
+
+        debug!(
+            "DistributedContext({}): Announcing roles to {}.",
+            self.me, to
+        );
+        let announcement = format!("{}{}", ROLES_PREFIX, self.roles().join(","));
+        self.send(to, &announcement).ok();
+    }
+
+    // Delivers `text`, broadcasted by `from` to the groups matching
+    // `group`, to the groups of the current node only.
+    fn deliver_broadcast(&self, from: &Uuid, group: &str, text: String) {
+        trace!(
+            "DistributedContext({}): Member {} broadcasted to '{}' groups: {}",
+            self.me,
+            from,
+            group,
+            text
+        );
+        let msg = Arc::new(SignedMessage::new(
+            Msg::broadcast(text),
+            RefAddr::dead_letters(),
+        ));
+
+        SYSTEM
+            .dispatcher()
+            .broadcast_message(BroadcastTarget::LocalGroup(group.to_string()), &msg);
+    }
+
+    // Sends the metrics of the current node to the node aggregating
+    // them, if there is one and the interval elapsed since they were
+    // last sent. The metrics imported from other nodes aren't sent.
+    #[cfg(feature = "metrics")]
+    fn publish_metrics(&self) {
+        let (aggregator, interval) = match self.options.metrics_aggregator {
+            Some((aggregator, _)) if aggregator == self.me => return,
+            Some(metrics_aggregator) => metrics_aggregator,
+            None => return,
+        };
+
+        {
+            // FIXME: panics?
+            let mut published = self.metrics_published.lock().unwrap();
+            if published.elapsed() < interval {
+                return;
+            }
+
+            *published = Instant::now();
+        }
+
+        let samples: Vec<Sample> = metrics::registry()
+            .snapshot()
+            .into_iter()
+            .filter(|sample| !sample.labels().iter().any(|(name, _)| name == NODE_LABEL))
+            .collect();
+        match serde_json::to_string(&samples) {
+            Ok(samples) => {
+                trace!(
+                    "DistributedContext({}): Sending metrics to {}.",
+                    self.me,
+                    aggregator
+                );
+                let payload = format!("{}{}", METRICS_PREFIX, samples);
+                self.send(&aggregator, &payload).ok();
+            }
+            Err(err) => warn!(
+                "DistributedContext({}): Couldn't serialize metrics: {}",
+                self.me, err
+            ),
+        }
+    }
+
+    // Imports the metrics sent by `from` into the registry of the
+    // current node, labeled with its identifier.
+    #[cfg(feature = "metrics")]
+    fn import_metrics(&self, from: &Uuid, samples: &str) {
+        match serde_json::from_str::<Vec<Sample>>(samples) {
+            Ok(samples) => {
+                let node = from.to_string();
+                metrics::registry().import(&samples, &[(NODE_LABEL, &node)]);
+            }
+            Err(err) => warn!(
+                "DistributedContext({}): Ignoring invalid metrics from {}: {}",
+                self.me, from, err
+            ),
+        }
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    fn import_metrics(&self, from: &Uuid, samples: &str) {
+        warn!(
+            "DistributedContext({}): Ignoring {} bytes of metrics from {}: the metrics are disabled.",
+            self.me,
+            samples.len(),
+            from
+        );
+    }
+
     ///
     /// Channel that aggregates incoming cluster events to this node.
+    ///
+    /// The messages broadcasted by the other members to the groups of the
+    /// current node are delivered to them while this is being awaited.
     pub async fn recv(&self) -> Result<ClusterMessage, ()> {
         debug!(
             "DistributedContext({}): Waiting to receive message.",
             self.me
         );
         loop {
+            #[cfg(feature = "metrics")]
+            self.publish_metrics();
+
             for (members, event) in self.cluster.events.try_iter() {
                 warn!(event = format!("{:?}", event).as_str(), "Cluster event");
                 if let ArtilleryMemberEvent::Payload(member, msg) = event {
-                    return Ok(ClusterMessage::new(Msg::tell(msg), member.host_key()));
+                    let from = member.host_key();
+                    let msg = match &self.options.codec {
+                        Some(codec) => match codec.open(&from, &msg) {
+                            Ok(msg) => msg,
+                            Err(()) => {
+                                warn!(
+                                    "DistributedContext({}): Dropping payload from {} which couldn't be opened.",
+                                    self.me, from
+                                );
+                                continue;
+                            }
+                        },
+                        None => msg,
+                    };
+                    let msg = match self.options.decompress(msg) {
+                        Ok(msg) => msg,
+                        Err(()) => {
+                            warn!(
+                                "DistributedContext({}): Dropping payload from {} which couldn't be decompressed.",
+                                self.me, from
+                            );
+                            continue;
+                        }
+                    };
+
+                    if let Some(names) = msg.strip_prefix(COMPRESSION_PREFIX) {
+                        debug!(
+                            "DistributedContext({}): Member {} announced compressions: {}",
+                            self.me, from, names
+                        );
+                        self.negotiate_compression(from, names);
+                        continue;
+                    }
+
+                    if let Some(samples) = msg.strip_prefix(METRICS_PREFIX) {
+                        self.import_metrics(&from, samples);
+                        continue;
+                    }
+
+                    if let Some(roles) = msg.strip_prefix(ROLES_PREFIX) {
+                        debug!(
+                            "DistributedContext({}): Member {} announced roles: {}",
+                            self.me, from, roles
+                        );
+                        let roles = roles.split(',').map(str::to_string).collect();
+                        let _ = self.member_roles.insert(from, roles);
+                        continue;
+                    }
+
+                    if let Some(broadcast) = msg.strip_prefix(BROADCAST_PREFIX) {
+                        match broadcast.find('\n') {
+                            Some(idx) => self.deliver_broadcast(
+                                &from,
+                                &broadcast[..idx],
+                                broadcast[idx + 1..].to_string(),
+                            ),
+                            None => warn!(
+                                "DistributedContext({}): Ignoring invalid broadcast from {}.",
+                                self.me, from
+                            ),
+                        }
+                        continue;
+                    }
+
+                    if let Some(message) = msg.strip_prefix(MESSAGE_PREFIX) {
+                        let decoded = message.find('\n').and_then(|idx| {
+                            registry::decode(&message[..idx], &message[idx + 1..])
+                        });
+                        match decoded {
+                            Some(msg) => return Ok(ClusterMessage::new(msg, from)),
+                            None => {
+                                warn!(
+                                    "DistributedContext({}): Dropping message from {} of an unknown type.",
+                                    self.me, from
+                                );
+                                continue;
+                            }
+                        }
+                    }
+
+                    return Ok(ClusterMessage::new(Msg::tell(msg), from));
                 }
 
                 members.iter().for_each(|m| match m.state() {
                     ArtilleryMemberState::Alive => {
-                        let _ = self.members.insert(m.host_key(), m.clone());
+                        let id = m.host_key();
+                        let joined = self.members.get(&id).is_none();
+                        let _ = self.members.insert(id, m.clone());
+                        if joined && id != self.me {
+                            self.announce_compressions(&id);
+                            self.announce_roles(&id);
+                        }
                     }
                     ArtilleryMemberState::Down => {
                         let _ = self.members.remove(&m.host_key());
+                        let _ = self.member_roles.remove(&m.host_key());
+                        let _ = self.member_compressions.remove(&m.host_key());
+                        #[cfg(feature = "metrics")]
+                        metrics::registry().remove_labeled(NODE_LABEL, &m.host_key().to_string());
                     }
                     _ => {}
                 });
@@ -130,10 +841,20 @@ impl DistributedContext {
     }
 }
 
+impl BroadcastRelay for DistributedContext {
+    fn relay(&self, group: &str, text: &str) {
+        let payload = format!("{}{}\n{}", BROADCAST_PREFIX, group, text);
+        for member in self.members() {
+            self.send(&member.host_key(), &payload).ok();
+        }
+    }
+}
+
 ///
 /// Creates distributed cluster actor
 pub(crate) fn cluster_actor<I, F>(
     cluster_config: &'static ArtilleryAPClusterConfig,
+    options: ClusterOptions,
     action: I,
 ) -> Result<ChildrenRef, ()>
 where
@@ -148,7 +869,10 @@ where
             ctx,
             ap_cluster.cluster(),
             cluster_config.node_id,
+            options.clone(),
         ));
+        let relay: Arc<dyn BroadcastRelay> = dctx.clone();
+        SYSTEM.dispatcher().set_relay(Arc::downgrade(&relay));
         let action = action.clone();
 
         let core = async move {
@@ -168,3 +892,105 @@ where
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(compression: Compression) -> ClusterOptions {
+        ClusterOptions::new()
+            .with_compression(compression)
+            .with_compression_threshold(0)
+    }
+
+    fn compressible() -> String {
+        "bastion ".repeat(256)
+    }
+
+    #[test]
+    fn compressed_payloads_round_trip() {
+        for compression in [Compression::Lz4, Compression::Zstd].iter().copied() {
+            let options = options(compression);
+            let payload = compressible();
+            let compressed = options.compress(compression, &payload).unwrap().unwrap();
+            assert!(compressed.len() < payload.len());
+            assert_eq!(options.decompress(compressed).unwrap(), payload);
+        }
+    }
+
+    #[test]
+    fn uncompressed_payloads_are_left_as_is() {
+        let options = options(Compression::Lz4);
+        assert_eq!(options.decompress("payload".to_string()).unwrap(), "payload");
+    }
+
+    #[test]
+    fn payloads_below_the_threshold_are_not_compressed() {
+        let options = options(Compression::Zstd).with_compression_threshold(4096);
+        assert_eq!(options.compress(Compression::Zstd, &compressible()), Ok(None));
+
+        let options = options.with_compression_threshold(2048);
+        assert!(options
+            .compress(Compression::Zstd, &compressible())
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn payloads_not_getting_smaller_are_not_compressed() {
+        let options = options(Compression::Lz4);
+        assert_eq!(options.compress(Compression::Lz4, "abc"), Ok(None));
+    }
+
+    #[test]
+    fn the_first_supported_compression_is_negotiated() {
+        let options = ClusterOptions::new()
+            .with_compression(Compression::Zstd)
+            .with_compression(Compression::Lz4);
+
+        assert_eq!(
+            options.negotiate_compression("lz4,zstd"),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(
+            options.negotiate_compression("gzip,lz4"),
+            Some(Compression::Lz4)
+        );
+        assert_eq!(options.negotiate_compression("gzip"), None);
+        assert_eq!(options.negotiate_compression(""), None);
+        assert_eq!(ClusterOptions::new().negotiate_compression("lz4"), None);
+    }
+
+    #[test]
+    fn payloads_too_large_once_decompressed_are_rejected() {
+        for compression in [Compression::Lz4, Compression::Zstd].iter().copied() {
+            let payload = compressible();
+            let compressed = options(compression)
+                .compress(compression, &payload)
+                .unwrap()
+                .unwrap();
+
+            let options = options(compression).with_max_decompressed_size(payload.len());
+            assert!(options.decompress(compressed.clone()).is_ok());
+
+            let options = options.with_max_decompressed_size(payload.len() - 1);
+            assert_eq!(options.decompress(compressed), Err(()));
+        }
+    }
+
+    #[test]
+    fn forged_lz4_sizes_are_rejected() {
+        let options = options(Compression::Lz4);
+
+        // A size of 2GiB prepended to a few bytes.
+        let forged = base64::encode([0x00, 0x00, 0x00, 0x80, 0x10, b'a']);
+        let forged = format!("{}lz4:{}", COMPRESSED_PREFIX, forged);
+        assert_eq!(options.decompress(forged), Err(()));
+
+        // A size smaller than the decompressed payload.
+        let mut compressed = lz4_flex::compress_prepend_size(compressible().as_bytes());
+        compressed[..4].copy_from_slice(&16u32.to_le_bytes());
+        let forged = format!("{}lz4:{}", COMPRESSED_PREFIX, base64::encode(compressed));
+        assert_eq!(options.decompress(forged), Err(()));
+    }
+}
@@ -0,0 +1,164 @@
+//!
+//! Graceful draining of the work in flight when a children group is
+//! told to stop.
+//!
+//! When a children group is given a drain deadline with
+//! [`Children::with_drain_deadline`], it doesn't kill its elements as
+//! soon as it is told to stop. It first tells them to stop accepting
+//! new work ([`BastionContext::is_draining`] returns `true` and
+//! [`BastionContext::draining`] resolves), then waits for the work
+//! they registered with [`BastionContext::track`] (eg. the connections
+//! or requests of a server) to be done, and only stops them once it
+//! is or once the deadline passed.
+//!
+//! The elements are still killed right away when the group is killed
+//! (eg. to be restarted after a fault).
+//!
+//! [`Children::with_drain_deadline`]: crate::children::Children::with_drain_deadline
+//! [`BastionContext::is_draining`]: crate::context::BastionContext::is_draining
+//! [`BastionContext::draining`]: crate::context::BastionContext::draining
+//! [`BastionContext::track`]: crate::context::BastionContext::track
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use tracing::debug;
+
+#[derive(Default)]
+/// The work in flight of the elements of a children group, and
+/// whether it is being drained.
+pub(crate) struct Drain {
+    in_flight: AtomicUsize,
+    draining: AtomicBool,
+    // The elements waiting for the group to start draining.
+    wakers: Mutex<Vec<Waker>>,
+}
+
+/// A value (eg. a connection or a request) registered as work in
+/// flight with [`BastionContext::track`], until it is dropped.
+///
+/// It derefs to the tracked value.
+///
+/// [`BastionContext::track`]: crate::context::BastionContext::track
+pub struct Tracked<T> {
+    value: T,
+    drain: Option<Arc<Drain>>,
+}
+
+#[derive(Debug)]
+/// Future returned by [`BastionContext::draining`].
+///
+/// [`BastionContext::draining`]: crate::context::BastionContext::draining
+pub struct Draining<'a> {
+    drain: Option<&'a Drain>,
+}
+
+impl Drain {
+    pub(crate) fn new() -> Self {
+        Drain::default()
+    }
+
+    /// Returns the number of values tracked which weren't dropped
+    /// yet.
+    pub(crate) fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Tells the elements to stop accepting new work, waking up the
+    /// ones waiting for it.
+    pub(crate) fn start(&self) {
+        debug!("Drain: Draining {} tracked values.", self.in_flight());
+        self.draining.store(true, Ordering::SeqCst);
+
+        // FIXME: panics?
+        let wakers = std::mem::take(&mut *self.wakers.lock().unwrap());
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    pub(crate) fn track<T>(drain: Option<Arc<Drain>>, value: T) -> Tracked<T> {
+        if let Some(drain) = &drain {
+            drain.in_flight.fetch_add(1, Ordering::SeqCst);
+        }
+
+        Tracked { value, drain }
+    }
+
+    pub(crate) fn draining(drain: Option<&Drain>) -> Draining<'_> {
+        Draining { drain }
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Tracked<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T> Drop for Tracked<T> {
+    fn drop(&mut self) {
+        if let Some(drain) = &self.drain {
+            drain.in_flight.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl<'a> Future for Draining<'a> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // The elements of a group without a drain deadline are killed
+        // right away instead.
+        let drain = match self.drain {
+            Some(drain) => drain,
+            None => return Poll::Pending,
+        };
+        if drain.is_draining() {
+            return Poll::Ready(());
+        }
+
+        // FIXME: panics?
+        drain.wakers.lock().unwrap().push(cx.waker().clone());
+
+        // The group might have started draining while the waker was
+        // being registered.
+        if drain.is_draining() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Debug for Drain {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Drain")
+            .field("in_flight", &self.in_flight())
+            .field("draining", &self.is_draining())
+            .finish()
+    }
+}
+
+impl<T: Debug> Debug for Tracked<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Tracked")
+            .field("value", &self.value)
+            .finish()
+    }
+}
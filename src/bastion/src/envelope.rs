@@ -4,9 +4,11 @@
 
 use crate::broadcast::Sender;
 use crate::message::{BastionMessage, Message, Msg};
-use crate::path::BastionPath;
+use crate::path::{BastionPath, BastionPathElement};
+use crate::registry;
 use crate::system::SYSTEM;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 pub(crate) struct Envelope {
@@ -105,6 +107,107 @@ impl SignedMessage {
     pub fn signature(&self) -> &RefAddr {
         &self.sign
     }
+
+    /// Returns the message, without consuming it (eg. to inspect it
+    /// before deciding where to forward it).
+    pub fn msg(&self) -> &Msg {
+        &self.msg
+    }
+
+    /// Returns the instant after which the sender of this message
+    /// won't wait for its answer anymore, if it is a question which
+    /// was asked with a deadline (see
+    /// [`BastionContext::ask_with_deadline`]).
+    ///
+    /// [`BastionContext::ask_with_deadline`]: crate::context::BastionContext::ask_with_deadline
+    pub fn deadline(&self) -> Option<Instant> {
+        self.msg.deadline()
+    }
+
+    /// Returns how long ago this message was sent, which is how long
+    /// it waited before being received when called right after
+    /// receiving it.
+    ///
+    /// This allows the handlers to shed the messages which are too
+    /// stale to be worth handling, or to track the latency of the
+    /// messages.
+    ///
+    /// Note that the messages broadcasted with a priority through
+    /// the dispatchers are only stamped once they are taken out of
+    /// their priority queue.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             loop {
+    ///                 let msg: SignedMessage = ctx.recv().await?;
+    ///                 if msg.queued_for() > Duration::from_secs(1) {
+    ///                     // Too stale to be worth handling...
+    ///                     continue;
+    ///                 }
+    ///                 // ...
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn queued_for(&self) -> Duration {
+        self.msg.sent_at().elapsed()
+    }
+
+    /// Returns the contents of the message serialized as JSON, if its
+    /// type was registered with [`Bastion::register_message_type`]
+    /// (eg. to log the messages which couldn't be handled).
+    ///
+    /// [`Bastion::register_message_type`]: crate::Bastion::register_message_type
+    pub fn describe(&self) -> Option<String> {
+        registry::describe(&self.msg)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// What kind of sender signed a message, as returned by
+/// [`RefAddr::kind`].
+pub enum SenderKind {
+    /// An element of a children group.
+    Child,
+    /// A children group.
+    Children,
+    /// A supervisor.
+    Supervisor,
+    /// The system itself.
+    System,
+    /// A sender out of the supervision tree (eg. a message sent
+    /// with [`Bastion::broadcast`] or from an [`Inbox`]).
+    ///
+    /// [`Bastion::broadcast`]: crate::Bastion::broadcast
+    /// [`Inbox`]: crate::inbox::Inbox
+    Anonymous,
 }
 
 #[derive(Debug, Clone)]
@@ -269,6 +372,105 @@ impl RefAddr {
         &self.path
     }
 
+    /// Returns what kind of sender signed the message, allowing to
+    /// apply different rules to the messages depending on where they
+    /// come from (eg. to only trust those sent from within the
+    /// supervision tree).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    ///     # Bastion::init();
+    ///     #
+    ///     # let children_ref = Bastion::children(|children| children).unwrap();
+    /// let msg = "A message containing data.";
+    /// children_ref.broadcast(msg).expect("Couldn't send the message.");
+    ///
+    ///     # Bastion::children(|children| {
+    ///         # children.with_exec(|ctx: BastionContext| {
+    ///             # async move {
+    /// msg! { ctx.recv().await?,
+    ///     ref msg: &'static str => {
+    ///         if signature!().kind() == SenderKind::Anonymous {
+    ///             // Validate the message...
+    ///         }
+    ///     };
+    ///     // We are only sending a `&'static str` in this
+    ///     // example, so we know that this won't happen...
+    ///     _: _ => ();
+    /// }
+    ///                 #
+    ///                 # Ok(())
+    ///             # }
+    ///         # })
+    ///     # }).unwrap();
+    ///     #
+    ///     # Bastion::start();
+    ///     # Bastion::stop();
+    ///     # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn kind(&self) -> SenderKind {
+        if self.path.is_dead_letters() {
+            return SenderKind::Anonymous;
+        }
+
+        match self.path.elem() {
+            None => SenderKind::System,
+            // The inboxes are out of the supervision tree.
+            Some(BastionPathElement::Child(_)) if self.path.is_inbox() => SenderKind::Anonymous,
+            Some(BastionPathElement::Child(_)) => SenderKind::Child,
+            Some(BastionPathElement::Children(_)) => SenderKind::Children,
+            Some(BastionPathElement::Supervisor(_)) => SenderKind::Supervisor,
+        }
+    }
+
+    /// Returns whether the message was sent by an element of a
+    /// children group (see [`kind`]).
+    ///
+    /// [`kind`]: Self::kind
+    pub fn is_from_child(&self) -> bool {
+        self.kind() == SenderKind::Child
+    }
+
+    /// Returns whether the message was sent by a supervisor (see
+    /// [`kind`]).
+    ///
+    /// [`kind`]: Self::kind
+    pub fn is_from_supervisor(&self) -> bool {
+        self.kind() == SenderKind::Supervisor
+    }
+
+    /// Returns whether the message was sent by the system itself
+    /// (see [`kind`]).
+    ///
+    /// [`kind`]: Self::kind
+    pub fn is_from_system(&self) -> bool {
+        self.kind() == SenderKind::System
+    }
+
+    /// Returns whether the message was sent from out of the
+    /// supervision tree (see [`kind`]).
+    ///
+    /// [`kind`]: Self::kind
+    pub fn is_anonymous(&self) -> bool {
+        self.kind() == SenderKind::Anonymous
+    }
+
     pub(crate) fn sender(&self) -> &Sender {
         &self.sender
     }
@@ -1,20 +1,202 @@
 //!
 //! Describes the error types that may happen within bastion.
 //! Given Bastion has a let it crash strategy, most error aren't noticeable.
-//! A ReceiveError may however be raised when calling try_recv() or try_recv_timeout()
-//! More errors may happen in the future.
+//! The errors which may however be raised are documented on their
+//! types. More errors may happen in the future.
 
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::sync::Arc;
 use std::time::Duration;
 
 #[derive(Debug)]
 /// These errors happen
-/// when [`try_recv`] or [`try_recv_timeout`] are invoked
+/// when [`try_recv`], [`try_recv_timeout`] or [`Inbox::recv_timeout`]
+/// are invoked
 ///
 /// [`try_recv`]: crate::context::BastionContext::try_recv
 /// [`try_recv_timeout`]: crate::context::BastionContext::try_recv_timeout
+/// [`Inbox::recv_timeout`]: crate::inbox::Inbox::recv_timeout
 pub enum ReceiveError {
     /// We didn't receive a message on time
     Timeout(Duration),
     /// Generic error. Not used yet
     Other,
 }
+
+#[derive(Debug)]
+/// This error happens when the system didn't stop before
+/// the timeout given to [`block_until_stopped_timeout`] elapsed.
+///
+/// [`block_until_stopped_timeout`]: crate::Bastion::block_until_stopped_timeout
+pub struct StillRunning;
+
+#[derive(Debug)]
+/// These errors happen when asking a question through
+/// [`Handle::ask`], [`CircuitBreaker::ask`] or [`Bulkhead::ask`],
+/// or with [`ask_with_retry`] or [`ask_blocking`], or when
+/// submitting a request with [`Pool::submit`].
+///
+/// [`Handle::ask`]: crate::web::Handle::ask
+/// [`CircuitBreaker::ask`]: crate::circuit_breaker::CircuitBreaker::ask
+/// [`Bulkhead::ask`]: crate::bulkhead::Bulkhead::ask
+/// [`ask_with_retry`]: crate::context::BastionContext::ask_with_retry
+/// [`ask_blocking`]: crate::child_ref::ChildRef::ask_blocking
+/// [`Pool::submit`]: crate::pool::Pool::submit
+pub enum AskError {
+    /// No element could be asked the question (eg. because its
+    /// mailbox is closed), or the element dropped it without
+    /// answering
+    Unavailable,
+    /// The element didn't answer on time
+    Timeout(Duration),
+    /// The element answered with a message of another type
+    UnexpectedReply,
+    /// The question wasn't asked because the circuit of the
+    /// [`CircuitBreaker`] is open
+    ///
+    /// [`CircuitBreaker`]: crate::circuit_breaker::CircuitBreaker
+    Open,
+    /// The question wasn't asked because too many questions were
+    /// already queued by the [`Bulkhead`]
+    ///
+    /// [`Bulkhead`]: crate::bulkhead::Bulkhead
+    Rejected,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// These errors happen when a [`Saga`] doesn't complete.
+///
+/// [`Saga`]: crate::saga::Saga
+pub enum SagaError {
+    /// The step with the given index failed and the steps
+    /// which completed before it were compensated
+    Compensated(usize),
+    /// The compensation of the step with the given index failed,
+    /// and will be attempted again the next time the saga is run
+    CompensationFailed(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// These errors happen when a [`Transaction`] isn't committed.
+///
+/// [`Transaction`]: crate::transaction::Transaction
+pub enum TransactionError {
+    /// A participant voted to abort the transaction or didn't vote
+    /// on time, and every participant aborted it
+    Aborted,
+    /// The given number of participants didn't acknowledge the
+    /// decision to commit on time, which will be told again the
+    /// next time the transaction is run
+    CommitInDoubt(usize),
+    /// The given number of participants didn't acknowledge the
+    /// decision to abort on time, which will be told again the
+    /// next time the transaction is run
+    AbortInDoubt(usize),
+}
+
+#[derive(Clone)]
+/// The error returned by the future of an element of a children
+/// group, set with [`Children::with_fallible_exec`], and given to
+/// its supervisor with [`FaultReason::Failed`].
+///
+/// Two `ExecError`s are equal if they are the same error.
+///
+/// [`Children::with_fallible_exec`]: crate::children::Children::with_fallible_exec
+/// [`FaultReason::Failed`]: crate::supervisor::FaultReason::Failed
+pub struct ExecError(Arc<dyn Error + Send + Sync>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// These errors happen when initializing the system with an
+/// invalid [`Config`] through [`Bastion::try_init_with`].
+///
+/// [`Config`]: crate::Config
+/// [`Bastion::try_init_with`]: crate::Bastion::try_init_with
+pub enum ConfigError {
+    /// The executor was configured to start without any thread
+    NoExecutorThreads,
+    /// The executor threads were configured but the executor
+    /// was already started
+    ExecutorStarted,
+    /// The mailboxes were configured to hold no message
+    NoMailboxCapacity,
+    /// The heartbeats were configured to be sent without any
+    /// delay between them
+    NoHeartbeatTick,
+    /// The stalled elements were configured to be restarted
+    /// after no heartbeat tick
+    NoStalledTicks,
+    /// The stalled elements were configured to be restarted
+    /// but the heartbeats were disabled
+    StalledWithoutHeartbeats,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let msg = match self {
+            ConfigError::NoExecutorThreads => "the executor needs at least one thread",
+            ConfigError::ExecutorStarted => {
+                "the executor threads can't be set once the executor started"
+            }
+            ConfigError::NoMailboxCapacity => "the mailboxes need to hold at least one message",
+            ConfigError::NoHeartbeatTick => "the heartbeat tick can't be zero",
+            ConfigError::NoStalledTicks => {
+                "the stalled elements can't be restarted after zero heartbeat ticks"
+            }
+            ConfigError::StalledWithoutHeartbeats => {
+                "the stalled elements can't be restarted without heartbeats"
+            }
+        };
+
+        fmt.write_str(msg)
+    }
+}
+
+impl Error for ConfigError {}
+
+impl ExecError {
+    pub(crate) fn new<E>(error: E) -> Self
+    where
+        E: Error + Send + Sync + 'static,
+    {
+        ExecError(Arc::new(error))
+    }
+
+    /// Returns whether the error is of type `E`.
+    pub fn is<E: Error + 'static>(&self) -> bool {
+        self.0.is::<E>()
+    }
+
+    /// Returns a reference to the error if it is of type `E`.
+    pub fn downcast_ref<E: Error + 'static>(&self) -> Option<&E> {
+        self.0.downcast_ref::<E>()
+    }
+}
+
+impl Debug for ExecError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        Debug::fmt(&self.0, fmt)
+    }
+}
+
+impl Display for ExecError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self.0, fmt)
+    }
+}
+
+impl PartialEq for ExecError {
+    fn eq(&self, other: &Self) -> bool {
+        let this = Arc::as_ptr(&self.0) as *const u8;
+        let other = Arc::as_ptr(&other.0) as *const u8;
+        this == other
+    }
+}
+
+impl Eq for ExecError {}
+
+impl Error for ExecError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
@@ -1,5 +1,6 @@
 //! A module that exposes the functions used under the hoods from `bastion`s macros: `spawn!`, `run!`
 //! and `blocking!`.
+pub use bastion_executor::handle::ExecutorHandle;
 pub use lightproc::proc_stack::ProcStack;
 use lightproc::recoverable_handle::RecoverableHandle;
 use std::future::Future;
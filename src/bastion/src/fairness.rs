@@ -0,0 +1,211 @@
+//!
+//! Fair polling of the sources the elements of a children group take
+//! their messages from.
+//!
+//! The elements of a group take their messages from several sources:
+//! the messages broadcasted to the group with a high priority, their
+//! mailbox (where the messages sent to them, the answers to their
+//! questions and the messages scheduled for redelivery end up), and
+//! the messages broadcasted with a low priority. By default, a source
+//! is only polled once the ones before it are empty, so a firehose of
+//! prioritized broadcasts can starve the mailbox (and the control
+//! messages sent to it).
+//!
+//! [`FairPolling`] (set with [`Children::with_fair_polling`]) polls
+//! the sources in turn instead, taking at most a given number of
+//! messages (the source's quantum) from each of them before moving to
+//! the next one which isn't empty.
+//!
+//! [`Children::with_fair_polling`]: crate::children::Children::with_fair_polling
+use std::sync::Mutex;
+
+/// The default number of messages taken from a source before moving
+/// to the next one.
+const DEFAULT_QUANTUM: usize = 1;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// A source the elements of a children group take their messages
+/// from, in the order they are polled.
+pub enum MessageSource {
+    /// The messages broadcasted to the group with a high priority.
+    HighPriority,
+    /// The mailbox of the element.
+    Mailbox,
+    /// The messages broadcasted to the group with a low priority.
+    LowPriority,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The number of messages (the quantum) the elements of a children
+/// group take from each of their sources in turn.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::fairness::{FairPolling, MessageSource};
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// Bastion::children(|children| {
+///     children
+///         // Takes up to 16 prioritized broadcasts, then up to 4
+///         // messages from the mailbox, and so on.
+///         .with_fair_polling(
+///             FairPolling::new()
+///                 .with_quantum(MessageSource::HighPriority, 16)
+///                 .with_quantum(MessageSource::Mailbox, 4),
+///         )
+///         .with_exec(|ctx: BastionContext| {
+///             async move {
+///                 loop {
+///                     let _msg = ctx.recv().await?;
+///                     // ...
+///                 }
+///             }
+///         })
+/// }).expect("Couldn't create the children group.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct FairPolling {
+    quanta: [usize; 3],
+}
+
+#[derive(Debug)]
+/// The fair polling of the sources of an element, along with the
+/// source being polled and the number of messages taken from it.
+pub(crate) struct Fairness {
+    polling: FairPolling,
+    cursor: Mutex<(usize, usize)>,
+}
+
+impl MessageSource {
+    const ALL: [MessageSource; 3] = [
+        MessageSource::HighPriority,
+        MessageSource::Mailbox,
+        MessageSource::LowPriority,
+    ];
+
+    fn index(self) -> usize {
+        match self {
+            MessageSource::HighPriority => 0,
+            MessageSource::Mailbox => 1,
+            MessageSource::LowPriority => 2,
+        }
+    }
+}
+
+impl FairPolling {
+    /// Creates a fair polling taking one message from each source in
+    /// turn.
+    pub fn new() -> Self {
+        FairPolling {
+            quanta: [DEFAULT_QUANTUM; 3],
+        }
+    }
+
+    /// Sets the number of messages taken from `source` before moving
+    /// to the next source (at least one).
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source whose quantum is set.
+    /// * `quantum` - The number of messages taken from the source in
+    ///   a row.
+    pub fn with_quantum(mut self, source: MessageSource, quantum: usize) -> Self {
+        self.quanta[source.index()] = quantum.max(1);
+        self
+    }
+
+    /// Returns the number of messages taken from `source` before
+    /// moving to the next source.
+    pub fn quantum(&self, source: MessageSource) -> usize {
+        self.quanta[source.index()]
+    }
+}
+
+impl Default for FairPolling {
+    fn default() -> Self {
+        FairPolling::new()
+    }
+}
+
+impl Fairness {
+    pub(crate) fn new(polling: FairPolling) -> Self {
+        Fairness {
+            polling,
+            cursor: Mutex::new((0, 0)),
+        }
+    }
+
+    /// Takes the next message from the sources in turn with `pop`,
+    /// starting with the one being polled if its quantum wasn't used
+    /// up yet.
+    pub(crate) fn next<T, F>(&self, mut pop: F) -> Option<T>
+    where
+        F: FnMut(MessageSource) -> Option<T>,
+    {
+        // FIXME: panics?
+        let mut cursor = self.cursor.lock().unwrap();
+        let (mut current, mut taken) = *cursor;
+        // The source being polled is polled again after the others
+        // if its quantum was used up.
+        for _ in 0..=MessageSource::ALL.len() {
+            let source = MessageSource::ALL[current];
+            if taken < self.polling.quantum(source) {
+                if let Some(msg) = pop(source) {
+                    *cursor = (current, taken + 1);
+                    return Some(msg);
+                }
+            }
+
+            current = (current + 1) % MessageSource::ALL.len();
+            taken = 0;
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_a_quantum_from_each_source_in_turn() {
+        let fairness = Fairness::new(
+            FairPolling::new()
+                .with_quantum(MessageSource::HighPriority, 2)
+                .with_quantum(MessageSource::Mailbox, 1),
+        );
+        let mut high = vec![1, 2, 3, 4, 5];
+        let mut mailbox = vec![10, 11];
+        let mut pop = |source| match source {
+            MessageSource::HighPriority if !high.is_empty() => Some(high.remove(0)),
+            MessageSource::Mailbox if !mailbox.is_empty() => Some(mailbox.remove(0)),
+            _ => None,
+        };
+
+        let mut taken = Vec::new();
+        while let Some(msg) = fairness.next(&mut pop) {
+            taken.push(msg);
+        }
+        assert_eq!(taken, vec![1, 2, 10, 3, 4, 11, 5]);
+    }
+}
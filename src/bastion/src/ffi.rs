@@ -0,0 +1,410 @@
+//!
+//! C API allowing to use bastion as the supervision and actor runtime
+//! of applications which aren't written in Rust.
+//!
+//! The host application initializes and starts the system with
+//! [`bastion_init`] and [`bastion_start`], spawns children groups whose
+//! elements call a C callback for every message they receive with
+//! [`bastion_spawn_group`], and then sends them byte payloads with
+//! [`bastion_tell`] and [`bastion_ask`]. Every payload is passed to
+//! the elements as a [`Payload`].
+//!
+//! The functions returning a status return [`BASTION_OK`] if they
+//! succeeded, or a negative status otherwise. A panic never unwinds
+//! into the host application: the function catches it and returns
+//! [`BASTION_ERROR`] (or null) instead.
+//!
+//! The crate is only built as a Rust library by default, and the
+//! dynamic or static library the host application links with can be
+//! built from the root of the crate with:
+//!
+//! ```text
+//! cargo rustc --release --features ffi --crate-type cdylib
+//! cargo rustc --release --features ffi --crate-type staticlib
+//! ```
+//!
+//! The C header declaring this API is `include/bastion.h`, which can
+//! be regenerated with [cbindgen] from the root of the crate:
+//!
+//! ```text
+//! cbindgen --config cbindgen.toml --crate bastion --output include/bastion.h
+//! ```
+//!
+//! [cbindgen]: https://github.com/eqrion/cbindgen
+//! [`Payload`]: crate::payload::Payload
+#![allow(unsafe_code)]
+use crate::bastion::Bastion;
+use crate::children_ref::ChildrenRef;
+use crate::context::BastionContext;
+use crate::envelope::SignedMessage;
+use crate::errors::AskError;
+use crate::executor;
+use crate::payload::Payload;
+use std::os::raw::{c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tracing::{debug, error, warn};
+
+/// The status returned when a call succeeded.
+pub const BASTION_OK: c_int = 0;
+/// The status returned when a call failed.
+pub const BASTION_ERROR: c_int = -1;
+/// The status returned when a question wasn't answered on time.
+pub const BASTION_TIMEOUT: c_int = -2;
+
+/// The callback called by the elements of a group spawned with
+/// [`bastion_spawn_group`] for every message they receive, with the
+/// `user_data` given to [`bastion_spawn_group`] and the bytes of the
+/// message.
+///
+/// `reply` is null unless the message was asked with [`bastion_ask`],
+/// in which case the callback can answer it with [`bastion_reply`].
+/// The element faults (and is restarted by its supervisor) if the
+/// callback returns anything other than [`BASTION_OK`].
+pub type BastionHandler = extern "C" fn(
+    user_data: *mut c_void,
+    data: *const u8,
+    len: usize,
+    reply: *mut BastionReply,
+) -> c_int;
+
+#[derive(Debug)]
+/// A children group spawned with [`bastion_spawn_group`].
+pub struct BastionGroup {
+    children: ChildrenRef,
+    // The index of the next element to send a message to.
+    next: AtomicUsize,
+}
+
+#[derive(Debug, Default)]
+/// The answer to a question asked with [`bastion_ask`], set by the
+/// callback of the element with [`bastion_reply`].
+pub struct BastionReply {
+    bytes: Option<Vec<u8>>,
+}
+
+// The callback of a group and its user data, which the host
+// application must allow to be used from any thread.
+#[derive(Debug, Clone, Copy)]
+struct Handler {
+    callback: BastionHandler,
+    user_data: *mut c_void,
+}
+
+// SAFETY: `Handler` is only `!Send` and `!Sync` because of the raw
+// `user_data` pointer, which is never dereferenced on the Rust side
+// but only passed back to `callback`. The caller of
+// `bastion_spawn_group` guarantees that both can be used from any
+// thread, concurrently, until the group is stopped.
+unsafe impl Send for Handler {}
+unsafe impl Sync for Handler {}
+
+/// Initializes the system (see [`Bastion::init`]).
+#[no_mangle]
+pub extern "C" fn bastion_init() -> c_int {
+    guard(BASTION_ERROR, || {
+        Bastion::init();
+        BASTION_OK
+    })
+}
+
+/// Starts the system (see [`Bastion::start`]).
+#[no_mangle]
+pub extern "C" fn bastion_start() -> c_int {
+    guard(BASTION_ERROR, || {
+        Bastion::start();
+        BASTION_OK
+    })
+}
+
+/// Stops the system and blocks until it stopped (see
+/// [`Bastion::stop`] and [`Bastion::block_until_stopped`]).
+#[no_mangle]
+pub extern "C" fn bastion_stop() -> c_int {
+    guard(BASTION_ERROR, || {
+        Bastion::stop();
+        Bastion::block_until_stopped();
+        BASTION_OK
+    })
+}
+
+/// Spawns a children group of `redundancy` elements calling
+/// `handler` with `user_data` for every message they receive.
+///
+/// This function returns the group, which must be freed with
+/// [`bastion_group_free`], or null if it couldn't be spawned.
+///
+/// # Safety
+///
+/// `handler` is called with `user_data` by every element of the
+/// group, concurrently and from the threads of the executor, so both
+/// must be safe to use from any thread, and `user_data` must stay
+/// valid until the group is stopped.
+#[no_mangle]
+pub unsafe extern "C" fn bastion_spawn_group(
+    redundancy: usize,
+    handler: BastionHandler,
+    user_data: *mut c_void,
+) -> *mut BastionGroup {
+    let handler = Handler {
+        callback: handler,
+        user_data,
+    };
+
+    guard(ptr::null_mut(), || {
+        let children = Bastion::children(move |children| {
+            children.with_redundancy(redundancy.max(1)).with_exec(
+                move |ctx: BastionContext| async move {
+                    loop {
+                        let msg = ctx.recv().await?;
+                        handle(handler, msg)?;
+                    }
+                },
+            )
+        });
+
+        match children {
+            Ok(children) => {
+                debug!("FFI: Spawned Children({}).", children.id());
+                let group = BastionGroup {
+                    children,
+                    next: AtomicUsize::new(0),
+                };
+
+                Box::into_raw(Box::new(group))
+            }
+            Err(()) => ptr::null_mut(),
+        }
+    })
+}
+
+/// Sends a copy of the `len` bytes at `data` to the next element of
+/// `group`.
+///
+/// # Safety
+///
+/// `group` must have been returned by [`bastion_spawn_group`] and not
+/// freed, and `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bastion_tell(
+    group: *const BastionGroup,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    guard(BASTION_ERROR, || {
+        let group = match group.as_ref() {
+            Some(group) => group,
+            None => return BASTION_ERROR,
+        };
+        let payload = Payload::new(bytes(data, len));
+
+        let elems = group.children.elems();
+        if elems.is_empty() {
+            return BASTION_ERROR;
+        }
+
+        let next = group.next.fetch_add(1, Ordering::Relaxed);
+        match elems[next % elems.len()].tell_anonymously(payload) {
+            Ok(()) => BASTION_OK,
+            Err(_) => BASTION_ERROR,
+        }
+    })
+}
+
+/// Asks a copy of the `len` bytes at `data` to the next element of
+/// `group` and blocks until it answers or `timeout_ms` milliseconds
+/// elapsed.
+///
+/// If it succeeded, the answer is written to `out_data` and
+/// `out_len`, and must be freed with [`bastion_bytes_free`].
+///
+/// # Safety
+///
+/// `group` must have been returned by [`bastion_spawn_group`] and not
+/// freed, `data` must point to `len` readable bytes, and `out_data`
+/// and `out_len` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn bastion_ask(
+    group: *const BastionGroup,
+    data: *const u8,
+    len: usize,
+    timeout_ms: u64,
+    out_data: *mut *mut u8,
+    out_len: *mut usize,
+) -> c_int {
+    guard(BASTION_ERROR, || {
+        let group = match group.as_ref() {
+            Some(group) => group,
+            None => return BASTION_ERROR,
+        };
+        if out_data.is_null() || out_len.is_null() {
+            return BASTION_ERROR;
+        }
+        let payload = Payload::new(bytes(data, len));
+
+        let timeout = Some(Duration::from_millis(timeout_ms));
+        let asked = group
+            .children
+            .ask_round_robin(&group.next, payload, timeout);
+        let reply = match executor::run(asked) {
+            Ok(reply) => reply,
+            Err(AskError::Timeout(_)) => return BASTION_TIMEOUT,
+            Err(_) => return BASTION_ERROR,
+        };
+
+        let (msg, _) = reply.extract();
+        let payload = match msg.downcast::<Payload>() {
+            Ok(payload) => payload,
+            Err(_) => return BASTION_ERROR,
+        };
+        let reply = match payload.bytes() {
+            Ok(bytes) => bytes.into_owned().into_boxed_slice(),
+            Err(_) => return BASTION_ERROR,
+        };
+
+        *out_len = reply.len();
+        *out_data = Box::into_raw(reply) as *mut u8;
+
+        BASTION_OK
+    })
+}
+
+/// Answers the question being handled with a copy of the `len` bytes
+/// at `data`, replacing any previous answer.
+///
+/// # Safety
+///
+/// `reply` must be the one given to the callback being called, and
+/// `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bastion_reply(
+    reply: *mut BastionReply,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    guard(BASTION_ERROR, || match reply.as_mut() {
+        Some(reply) => {
+            reply.bytes = Some(bytes(data, len).to_vec());
+            BASTION_OK
+        }
+        None => BASTION_ERROR,
+    })
+}
+
+/// Stops the elements of `group` (see [`ChildrenRef::stop`]).
+///
+/// # Safety
+///
+/// `group` must have been returned by [`bastion_spawn_group`] and not
+/// freed.
+///
+/// [`ChildrenRef::stop`]: crate::children_ref::ChildrenRef::stop
+#[no_mangle]
+pub unsafe extern "C" fn bastion_group_stop(group: *const BastionGroup) -> c_int {
+    guard(BASTION_ERROR, || {
+        match group.as_ref().map(|group| group.children.stop()) {
+            Some(Ok(())) => BASTION_OK,
+            _ => BASTION_ERROR,
+        }
+    })
+}
+
+/// Frees `group`, without stopping its elements.
+///
+/// # Safety
+///
+/// `group` must have been returned by [`bastion_spawn_group`] and not
+/// freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn bastion_group_free(group: *mut BastionGroup) {
+    guard((), || {
+        if !group.is_null() {
+            drop(Box::from_raw(group));
+        }
+    })
+}
+
+/// Frees the answer written by [`bastion_ask`].
+///
+/// # Safety
+///
+/// `data` and `len` must have been written by [`bastion_ask`] and not
+/// freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn bastion_bytes_free(data: *mut u8, len: usize) {
+    guard((), || {
+        if !data.is_null() {
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)));
+        }
+    })
+}
+
+// Calls `f`, returning `on_panic` instead of unwinding into the
+// host application if it panics.
+fn guard<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        error!("FFI: Caught a panic.");
+        on_panic
+    })
+}
+
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> &'a [u8] {
+    if data.is_null() || len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(data, len)
+    }
+}
+
+// Calls the callback of the group with the bytes of `msg`,
+// answering it if it was asked.
+fn handle(handler: Handler, msg: SignedMessage) -> Result<(), ()> {
+    let (mut msg, _) = msg.extract();
+    let sender = msg.take_sender();
+    let payload = match msg.downcast::<Payload>() {
+        Ok(payload) => payload,
+        Err(msg) => match msg.downcast_ref::<Payload>() {
+            Some(payload) => payload.as_ref().clone(),
+            None => {
+                warn!("FFI: Dropping message: {:?}", msg);
+                return Ok(());
+            }
+        },
+    };
+    let data = payload.bytes().map_err(|_| ())?;
+
+    let mut reply = BastionReply::default();
+    let reply_ptr = if sender.is_some() {
+        &mut reply as *mut BastionReply
+    } else {
+        ptr::null_mut()
+    };
+    let status = (handler.callback)(handler.user_data, data.as_ptr(), data.len(), reply_ptr);
+
+    if let Some(sender) = sender {
+        let bytes = reply.bytes.unwrap_or_default();
+        sender.reply(Payload::new(bytes)).ok();
+    }
+
+    if status == BASTION_OK {
+        Ok(())
+    } else {
+        warn!("FFI: The handler returned {}.", status);
+        Err(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guard_catches_panics() {
+        assert_eq!(guard(BASTION_ERROR, || BASTION_OK), BASTION_OK);
+        assert_eq!(guard(BASTION_ERROR, || panic!("oops")), BASTION_ERROR);
+        assert!(guard(ptr::null_mut::<BastionGroup>(), || panic!("oops")).is_null());
+    }
+}
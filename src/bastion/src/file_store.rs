@@ -0,0 +1,535 @@
+//!
+//! Persistence backends storing the events and the snapshots of the
+//! persistent actors in files.
+//!
+//! The [`FileJournal`] appends the events of every actor to a single
+//! file, and the [`FileSnapshotStore`] saves the last snapshot of every
+//! actor in its own file of a directory. Both frame their records with
+//! their length and a CRC32 checksum of their bytes, so that a record
+//! which was only partially written (eg. because the process crashed)
+//! is detected and ignored when reading them back, while a journal
+//! holding a corrupted record is only opened (and truncated) on
+//! demand, with [`FileJournal::open_truncating`]. The events and the
+//! snapshots are encoded in JSON.
+//!
+//! This module is available only with the `file-store` feature flag.
+use crate::persistence::{EventJournal, SnapshotStore};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, trace, warn};
+
+/// The size of the header of a record: its length and its checksum.
+const HEADER_LEN: usize = 8;
+
+#[derive(Debug, Clone)]
+/// An [`EventJournal`] appending the events to a file.
+///
+/// Cloning a `FileJournal` returns a new handle to the same file.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use bastion::file_store::{FileJournal, FileSnapshotStore};
+/// # use bastion::persistence::{Persistent, PersistentState, SnapshotPolicy};
+/// #
+/// # #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+/// # struct Counter(u64);
+/// #
+/// # impl Persistent for Counter {
+/// #     type Event = u64;
+/// #
+/// #     fn apply(&mut self, event: &u64) {
+/// #         self.0 += event;
+/// #     }
+/// # }
+/// #
+/// let journal = FileJournal::open("data/journal").expect("Couldn't open the journal.");
+/// let snapshots = FileSnapshotStore::open("data/snapshots").expect("Couldn't open the store.");
+///
+/// let counter = PersistentState::recover(
+///     "counter",
+///     Counter::default(),
+///     journal,
+///     snapshots,
+///     SnapshotPolicy::new().with_every_events(1000),
+/// ).expect("Couldn't recover the counter.");
+/// ```
+pub struct FileJournal {
+    path: Arc<PathBuf>,
+    // The file opened in append mode, which is locked while
+    // a record is being written.
+    file: Arc<Mutex<File>>,
+}
+
+#[derive(Debug, Clone)]
+/// A [`SnapshotStore`] saving the last snapshot of every actor in its
+/// own file of a directory.
+///
+/// The snapshots are written to a temporary file which then replaces
+/// the previous one, so that a crash while saving a snapshot leaves the
+/// previous one intact.
+///
+/// Cloning a `FileSnapshotStore` returns a new handle to the same
+/// directory.
+pub struct FileSnapshotStore {
+    dir: Arc<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct EventRecord<'a, E> {
+    id: &'a str,
+    seq: u64,
+    event: &'a E,
+}
+
+#[derive(Deserialize)]
+struct OwnedEventRecord<E> {
+    id: String,
+    seq: u64,
+    event: E,
+}
+
+#[derive(Serialize)]
+struct SnapshotRecord<'a, S> {
+    seq: u64,
+    state: &'a S,
+}
+
+#[derive(Deserialize)]
+struct OwnedSnapshotRecord<S> {
+    seq: u64,
+    state: S,
+}
+
+impl FileJournal {
+    /// Opens the journal stored in the file at `path`, creating it
+    /// (but not its parent directories) if it doesn't exist.
+    ///
+    /// If the file ends with a record which was only partially
+    /// written (eg. because the process crashed while appending it,
+    /// in which case the append never succeeded), it is truncated to
+    /// the end of the last valid record so that the events appended
+    /// afterward can be read back.
+    ///
+    /// This method returns an error of kind
+    /// [`io::ErrorKind::InvalidData`] if a complete record doesn't
+    /// match its checksum, since truncating the journal there would
+    /// lose the events following it. [`open_truncating`] can open
+    /// such a journal anyway.
+    ///
+    /// [`open_truncating`]: Self::open_truncating
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        FileJournal::open_with(path, false)
+    }
+
+    /// Opens the journal stored in the file at `path` like [`open`]
+    /// does, except that it is truncated to the end of the last valid
+    /// record even if a complete record doesn't match its checksum.
+    ///
+    /// All the events following the first corrupted record are lost,
+    /// whether they are valid or not.
+    ///
+    /// [`open`]: Self::open
+    pub fn open_truncating(path: impl AsRef<Path>) -> io::Result<Self> {
+        FileJournal::open_with(path, true)
+    }
+
+    fn open_with(path: impl AsRef<Path>, truncate_corrupted: bool) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        debug!("FileJournal({}): Opening.", path.display());
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(&path)?;
+
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        let valid = read_records(&bytes)
+            .map(|record| HEADER_LEN + record.len())
+            .sum::<usize>();
+        if valid < bytes.len() {
+            if !truncate_corrupted && !is_torn(&bytes[valid..]) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: corrupted record at offset {}", path.display(), valid),
+                ));
+            }
+
+            warn!(
+                "FileJournal({}): Truncating {} bytes after the last valid record.",
+                path.display(),
+                bytes.len() - valid
+            );
+            file.set_len(valid as u64)?;
+            file.sync_all()?;
+        }
+
+        let file = OpenOptions::new().append(true).open(&path)?;
+
+        Ok(FileJournal {
+            path: Arc::new(path),
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    /// Returns the path of the file the events are appended to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl<E> EventJournal<E> for FileJournal
+where
+    E: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    fn append(&self, id: &str, seq: u64, event: &E) -> Result<(), ()> {
+        let record = EventRecord { id, seq, event };
+        let bytes = serde_json::to_vec(&record).map_err(|err| {
+            warn!(
+                "FileJournal: Couldn't encode event #{} of {}: {}",
+                seq, id, err
+            );
+        })?;
+
+        // FIXME: panics?
+        let mut file = self.file.lock().unwrap();
+        write_record(&mut *file, &bytes)
+            .and_then(|()| file.sync_data())
+            .map_err(|err| {
+                warn!(
+                    "FileJournal({}): Couldn't append event #{} of {}: {}",
+                    self.path.display(),
+                    seq,
+                    id,
+                    err
+                );
+            })
+    }
+
+    fn events(&self, id: &str, after: u64) -> Result<Vec<(u64, E)>, ()> {
+        let bytes = fs::read(&*self.path).map_err(|err| {
+            warn!(
+                "FileJournal({}): Couldn't read: {}",
+                self.path.display(),
+                err
+            );
+        })?;
+
+        let mut events = Vec::new();
+        for record in read_records(&bytes) {
+            let record: OwnedEventRecord<E> = serde_json::from_slice(record).map_err(|err| {
+                warn!(
+                    "FileJournal({}): Couldn't decode event: {}",
+                    self.path.display(),
+                    err
+                );
+            })?;
+
+            if record.id == id && record.seq > after {
+                events.push((record.seq, record.event));
+            }
+        }
+
+        trace!(
+            "FileJournal({}): Read {} events of {}.",
+            self.path.display(),
+            events.len(),
+            id
+        );
+        Ok(events)
+    }
+}
+
+impl FileSnapshotStore {
+    /// Opens the store saving the snapshots in the directory at
+    /// `dir`, creating it (and its parent directories) if it
+    /// doesn't exist.
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        debug!("FileSnapshotStore({}): Opening.", dir.display());
+        fs::create_dir_all(&dir)?;
+
+        Ok(FileSnapshotStore { dir: Arc::new(dir) })
+    }
+
+    /// Returns the path of the directory the snapshots are saved in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    // Returns the path of the file the snapshot of the actor with
+    // the given id is saved in, whose name is the id encoded in
+    // hexadecimal so that any id can be used.
+    fn snapshot_path(&self, id: &str) -> PathBuf {
+        let mut name = String::with_capacity(id.len() * 2 + 9);
+        for byte in id.bytes() {
+            write!(name, "{:02x}", byte).unwrap();
+        }
+        name.push_str(".snapshot");
+
+        self.dir.join(name)
+    }
+}
+
+impl<S> SnapshotStore<S> for FileSnapshotStore
+where
+    S: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    fn save(&self, id: &str, seq: u64, state: &S) -> Result<(), ()> {
+        let record = SnapshotRecord { seq, state };
+        let bytes = serde_json::to_vec(&record).map_err(|err| {
+            warn!(
+                "FileSnapshotStore: Couldn't encode snapshot of {}: {}",
+                id, err
+            );
+        })?;
+
+        let path = self.snapshot_path(id);
+        let tmp = path.with_extension("tmp");
+        let res = File::create(&tmp)
+            .and_then(|mut file| {
+                write_record(&mut file, &bytes)?;
+                file.sync_all()
+            })
+            .and_then(|()| fs::rename(&tmp, &path));
+
+        res.map_err(|err| {
+            warn!(
+                "FileSnapshotStore({}): Couldn't save snapshot #{} of {}: {}",
+                self.dir.display(),
+                seq,
+                id,
+                err
+            );
+        })
+    }
+
+    fn load(&self, id: &str) -> Result<Option<(u64, S)>, ()> {
+        let mut bytes = Vec::new();
+        match File::open(self.snapshot_path(id)) {
+            Ok(mut file) => file.read_to_end(&mut bytes).map_err(|err| {
+                warn!(
+                    "FileSnapshotStore({}): Couldn't read snapshot of {}: {}",
+                    self.dir.display(),
+                    id,
+                    err
+                );
+            })?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                warn!(
+                    "FileSnapshotStore({}): Couldn't open snapshot of {}: {}",
+                    self.dir.display(),
+                    id,
+                    err
+                );
+                return Err(());
+            }
+        };
+
+        let record = match read_records(&bytes).next() {
+            Some(record) => record,
+            None => {
+                warn!(
+                    "FileSnapshotStore({}): Ignoring corrupted snapshot of {}.",
+                    self.dir.display(),
+                    id
+                );
+                return Ok(None);
+            }
+        };
+
+        let record: OwnedSnapshotRecord<S> = serde_json::from_slice(record).map_err(|err| {
+            warn!(
+                "FileSnapshotStore({}): Couldn't decode snapshot of {}: {}",
+                self.dir.display(),
+                id,
+                err
+            );
+        })?;
+
+        Ok(Some((record.seq, record.state)))
+    }
+}
+
+// Writes `bytes` preceded by their length and their checksum.
+fn write_record<W: Write>(writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(bytes.len()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("record of {} bytes is too large", bytes.len()),
+        )
+    })?;
+
+    let mut frame = Vec::with_capacity(HEADER_LEN + bytes.len());
+    frame.extend_from_slice(&len.to_le_bytes());
+    frame.extend_from_slice(&crc32fast::hash(bytes).to_le_bytes());
+    frame.extend_from_slice(bytes);
+
+    writer.write_all(&frame)?;
+    writer.flush()
+}
+
+// Returns the records framed in `bytes`, stopping at the first one
+// which is truncated or whose checksum doesn't match.
+fn read_records(mut bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
+    std::iter::from_fn(move || {
+        if bytes.is_empty() {
+            return None;
+        }
+
+        if bytes.len() < HEADER_LEN {
+            warn!("FileStore: Ignoring truncated record header.");
+            return None;
+        }
+
+        let mut len = [0; 4];
+        len.copy_from_slice(&bytes[..4]);
+        let len = u32::from_le_bytes(len) as usize;
+        let mut crc = [0; 4];
+        crc.copy_from_slice(&bytes[4..HEADER_LEN]);
+        let crc = u32::from_le_bytes(crc);
+
+        let record = match bytes.get(HEADER_LEN..HEADER_LEN + len) {
+            Some(record) => record,
+            None => {
+                warn!("FileStore: Ignoring truncated record.");
+                return None;
+            }
+        };
+
+        if crc32fast::hash(record) != crc {
+            warn!("FileStore: Ignoring record with an invalid checksum.");
+            return None;
+        }
+
+        bytes = &bytes[HEADER_LEN + len..];
+        Some(record)
+    })
+}
+
+// Returns whether `bytes` only hold a record which was partially
+// written, rather than a complete but corrupted one.
+fn is_torn(bytes: &[u8]) -> bool {
+    if bytes.len() < HEADER_LEN {
+        return true;
+    }
+
+    let mut len = [0; 4];
+    len.copy_from_slice(&bytes[..4]);
+    HEADER_LEN + u32::from_le_bytes(len) as usize > bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn journal_path() -> PathBuf {
+        std::env::temp_dir().join(format!("bastion-journal-{}", Uuid::new_v4()))
+    }
+
+    fn events(journal: &FileJournal, id: &str) -> Vec<(u64, u64)> {
+        EventJournal::<u64>::events(journal, id, 0).unwrap()
+    }
+
+    #[test]
+    fn journal_round_trip() {
+        let path = journal_path();
+        let journal = FileJournal::open(&path).unwrap();
+        journal.append("a", 1, &10u64).unwrap();
+        journal.append("b", 1, &20u64).unwrap();
+        journal.append("a", 2, &30u64).unwrap();
+
+        assert_eq!(events(&journal, "a"), vec![(1, 10), (2, 30)]);
+        assert_eq!(events(&journal, "b"), vec![(1, 20)]);
+        assert_eq!(
+            EventJournal::<u64>::events(&journal, "a", 1).unwrap(),
+            vec![(2, 30)]
+        );
+
+        let journal = FileJournal::open(&path).unwrap();
+        assert_eq!(events(&journal, "a"), vec![(1, 10), (2, 30)]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn journal_torn_tail() {
+        let path = journal_path();
+        let journal = FileJournal::open(&path).unwrap();
+        journal.append("a", 1, &10u64).unwrap();
+        let valid = fs::metadata(&path).unwrap().len();
+
+        let mut torn = Vec::new();
+        write_record(&mut torn, b"{\"id\":\"a\",\"seq\":2,\"event\":20}").unwrap();
+        torn.truncate(torn.len() - 4);
+        journal.file.lock().unwrap().write_all(&torn).unwrap();
+        assert_eq!(events(&journal, "a"), vec![(1, 10)]);
+
+        let journal = FileJournal::open(&path).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), valid);
+        assert_eq!(events(&journal, "a"), vec![(1, 10)]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn journal_append_after_torn_tail() {
+        let path = journal_path();
+        let journal = FileJournal::open(&path).unwrap();
+        journal.append("a", 1, &10u64).unwrap();
+        journal.file.lock().unwrap().write_all(&[42, 0, 0]).unwrap();
+
+        let journal = FileJournal::open(&path).unwrap();
+        journal.append("a", 2, &20u64).unwrap();
+        assert_eq!(events(&journal, "a"), vec![(1, 10), (2, 20)]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn journal_corrupted_record() {
+        let path = journal_path();
+        let journal = FileJournal::open(&path).unwrap();
+        journal.append("a", 1, &10u64).unwrap();
+        journal.append("a", 2, &20u64).unwrap();
+
+        // Flips a bit of the first event.
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[HEADER_LEN + 1] ^= 1;
+        fs::write(&path, &bytes).unwrap();
+
+        let err = FileJournal::open(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(fs::metadata(&path).unwrap().len(), bytes.len() as u64);
+
+        let journal = FileJournal::open_truncating(&path).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), 0);
+        assert_eq!(events(&journal, "a"), vec![]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let dir = std::env::temp_dir().join(format!("bastion-snapshots-{}", Uuid::new_v4()));
+        let store = FileSnapshotStore::open(&dir).unwrap();
+        assert_eq!(SnapshotStore::<u64>::load(&store, "a/b").unwrap(), None);
+
+        store.save("a/b", 1, &10u64).unwrap();
+        store.save("a/b", 3, &30u64).unwrap();
+        assert_eq!(store.load("a/b").unwrap(), Some((3, 30u64)));
+        assert_eq!(SnapshotStore::<u64>::load(&store, "a").unwrap(), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
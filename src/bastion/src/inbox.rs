@@ -0,0 +1,217 @@
+//!
+//! An inbox allowing code running outside of bastion to receive
+//! messages from its actors.
+//!
+//! The messages sent with [`Bastion::broadcast`] or with the
+//! `*_anonymously` methods are signed by the dead letters, so the
+//! actors can't reply to them. An [`Inbox`] created with
+//! [`Bastion::inbox`] signs the messages sent through it with its own
+//! address instead, which allows the actors to reply to it as they
+//! would to any other actor.
+//!
+//! [`Bastion::broadcast`]: crate::Bastion::broadcast
+//! [`Bastion::inbox`]: crate::Bastion::inbox
+use crate::broadcast::Receiver;
+use crate::context::BastionId;
+use crate::envelope::{Envelope, RefAddr, SignedMessage};
+use crate::errors::ReceiveError;
+use crate::message::{Answer, BastionMessage, Message};
+use crate::path::BastionPath;
+use futures::channel::mpsc;
+use futures::{FutureExt, StreamExt};
+use futures_timer::Delay;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, trace};
+
+#[derive(Debug)]
+/// A handle allowing code running outside of bastion to send
+/// messages to actors and to receive their replies.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let children_ref = Bastion::children(|children| {
+///     children.with_exec(|ctx: BastionContext| {
+///         async move {
+///             loop {
+///                 let msg: SignedMessage = ctx.recv().await?;
+///                 // Reply to the inbox the message was sent from...
+///                 ctx.tell(msg.signature(), "pong").ok();
+///             }
+///         }
+///     })
+/// }).expect("Couldn't create the children group.");
+///
+/// let mut inbox = Bastion::inbox();
+///
+/// # Bastion::start();
+/// let child_ref = &children_ref.elems()[0];
+/// inbox.tell(&child_ref.addr(), "ping").expect("Couldn't send the message.");
+///
+/// # run!(async move {
+/// msg! { inbox.recv().await.expect("Couldn't receive the reply."),
+///     reply: &'static str => {
+///         assert_eq!(reply, "pong");
+///     };
+///     _: _ => ();
+/// }
+/// # });
+/// #
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Inbox {
+    id: BastionId,
+    addr: RefAddr,
+    recver: Receiver,
+}
+
+impl Inbox {
+    pub(crate) fn new() -> Self {
+        let id = BastionId::new();
+        let (sender, recver) = mpsc::unbounded();
+        let path = Arc::new(BastionPath::inbox(id.clone()));
+        let addr = RefAddr::new(path, sender);
+        debug!("Inbox({}): Created.", id);
+
+        Inbox { id, addr, recver }
+    }
+
+    /// Returns the identifier of this inbox.
+    pub fn id(&self) -> &BastionId {
+        &self.id
+    }
+
+    /// Returns the address of this inbox, which can be given to
+    /// actors for them to send messages to it.
+    pub fn addr(&self) -> RefAddr {
+        self.addr.clone()
+    }
+
+    /// Sends a message to `to`, signed with the address of this
+    /// inbox so that it can be replied to.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(msg)`
+    /// otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The address the message is sent to.
+    /// * `msg` - The message to send.
+    pub fn tell<M: Message>(&self, to: &RefAddr, msg: M) -> Result<(), M> {
+        debug!(
+            "Inbox({}): Telling message: {:?} to: {:?}",
+            self.id,
+            msg,
+            to.path()
+        );
+        let msg = BastionMessage::tell(msg);
+        let env = Envelope::new_with_sign(msg, self.addr());
+        // FIXME: panics?
+        to.sender()
+            .unbounded_send(env)
+            .map_err(|err| err.into_inner().into_msg().unwrap())
+    }
+
+    /// Sends a message to `to`, signed with the address of this
+    /// inbox, and allowing it to be answered.
+    ///
+    /// This method returns [`Answer`] if it succeeded, or `Err(msg)`
+    /// otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The address the message is asked to.
+    /// * `msg` - The message to send.
+    pub fn ask<M: Message>(&self, to: &RefAddr, msg: M) -> Result<Answer, M> {
+        debug!(
+            "Inbox({}): Asking message: {:?} to: {:?}",
+            self.id,
+            msg,
+            to.path()
+        );
+        let (msg, answer) = BastionMessage::ask(msg, self.addr());
+        let env = Envelope::new_with_sign(msg, self.addr());
+        // FIXME: panics?
+        to.sender()
+            .unbounded_send(env)
+            .map_err(|err| err.into_inner().into_msg().unwrap())?;
+
+        Ok(answer)
+    }
+
+    /// Retrieves asynchronously a message sent to this inbox, waiting
+    /// for one if none has been received yet.
+    ///
+    /// This method returns [`SignedMessage`] if it succeeded, or
+    /// `Err(())` if no message can be received anymore.
+    pub async fn recv(&mut self) -> Result<SignedMessage, ()> {
+        debug!("Inbox({}): Waiting to receive message.", self.id);
+        while let Some(env) = self.recver.next().await {
+            if let Some(msg) = self.unpack(env) {
+                return Ok(msg);
+            }
+        }
+
+        Err(())
+    }
+
+    /// Retrieves a message sent to this inbox if one has been
+    /// received, without waiting for one otherwise.
+    pub fn try_recv(&mut self) -> Option<SignedMessage> {
+        trace!("Inbox({}): Trying to receive message.", self.id);
+        while let Ok(env) = self.recver.try_recv() {
+            if let Some(msg) = self.unpack(env) {
+                return Some(msg);
+            }
+        }
+
+        trace!("Inbox({}): Received no message.", self.id);
+        None
+    }
+
+    /// Retrieves asynchronously a message sent to this inbox, waiting
+    /// until `timeout` for one if none has been received yet.
+    ///
+    /// This method returns [`SignedMessage`] if it succeeded, or
+    /// `Err(ReceiveError::Timeout(timeout))` if none was received
+    /// on time.
+    pub async fn recv_timeout(&mut self, timeout: Duration) -> Result<SignedMessage, ReceiveError> {
+        futures::select! {
+            msg = self.recv().fuse() => msg.map_err(|()| ReceiveError::Other),
+            _ = Delay::new(timeout).fuse() => Err(ReceiveError::Timeout(timeout)),
+        }
+    }
+
+    // Returns the message held by `env`, if any.
+    fn unpack(&self, env: Envelope) -> Option<SignedMessage> {
+        match env.msg {
+            BastionMessage::Message(msg) => {
+                trace!("Inbox({}): Received message: {:?}", self.id, msg);
+                Some(SignedMessage::new(msg, env.sign))
+            }
+            msg => {
+                trace!("Inbox({}): Ignoring message: {:?}", self.id, msg);
+                None
+            }
+        }
+    }
+}
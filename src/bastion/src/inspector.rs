@@ -0,0 +1,137 @@
+//!
+//! Inspection of the mailboxes of the elements of children groups, to
+//! diagnose what an element is stuck on.
+//!
+//! With the `inspector` feature, the elements keep track of the type
+//! names and the enqueue times of the messages waiting in their
+//! mailboxes, which [`ChildRef::inspect_mailbox`] returns without
+//! taking the messages out of the mailboxes. This adds some work to
+//! every message sent to an element, so the feature is meant to be
+//! enabled in debug builds.
+//!
+//! Only the messages sent to the elements (told, asked, or broadcasted
+//! to their groups) are tracked, not the ones waiting in the priority
+//! queues of the dispatchers.
+//!
+//! The contents of the messages are kept too if their types were
+//! registered with [`Bastion::register_message_type`].
+//!
+//! [`ChildRef::inspect`] returns them along with the poll accounting of
+//! the element, which tells whether it is stuck on a future that is
+//! never woken up or busy polling one.
+//!
+//! [`ChildRef::inspect_mailbox`]: crate::child_ref::ChildRef::inspect_mailbox
+//! [`ChildRef::inspect`]: crate::child_ref::ChildRef::inspect
+//! [`Bastion::register_message_type`]: crate::Bastion::register_message_type
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+/// A message waiting in the mailbox of an element.
+pub struct QueuedMessage {
+    type_name: &'static str,
+    // The message serialized as JSON, if its type was registered.
+    contents: Option<String>,
+    queued_at: Instant,
+}
+
+#[derive(Debug, Clone)]
+/// A snapshot of an element, returned by [`ChildRef::inspect`].
+///
+/// [`ChildRef::inspect`]: crate::child_ref::ChildRef::inspect
+pub struct Inspection {
+    mailbox: Vec<QueuedMessage>,
+    poll_count: u64,
+    poll_time: Duration,
+}
+
+#[derive(Debug, Default)]
+/// The messages waiting in the mailbox of an element, in the order
+/// they were pushed to it.
+pub(crate) struct MailboxProbe {
+    queued: Mutex<VecDeque<QueuedMessage>>,
+}
+
+impl QueuedMessage {
+    /// Returns the type name of the message.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns the contents of the message serialized as JSON, if
+    /// its type was registered with [`Bastion::register_message_type`].
+    ///
+    /// [`Bastion::register_message_type`]: crate::Bastion::register_message_type
+    pub fn contents(&self) -> Option<&str> {
+        self.contents.as_deref()
+    }
+
+    /// Returns the instant the message was pushed to the mailbox.
+    pub fn queued_at(&self) -> Instant {
+        self.queued_at
+    }
+
+    /// Returns how long the message has been waiting in the mailbox.
+    pub fn age(&self) -> Duration {
+        self.queued_at.elapsed()
+    }
+}
+
+impl Inspection {
+    pub(crate) fn new(mailbox: Vec<QueuedMessage>, poll_count: u64, poll_time: Duration) -> Self {
+        Inspection {
+            mailbox,
+            poll_count,
+            poll_time,
+        }
+    }
+
+    /// Returns the messages waiting in the mailbox of the element,
+    /// from the oldest to the newest.
+    pub fn mailbox(&self) -> &[QueuedMessage] {
+        &self.mailbox
+    }
+
+    /// Returns the number of times the executor polled the element
+    /// since it was last (re)started.
+    pub fn poll_count(&self) -> u64 {
+        self.poll_count
+    }
+
+    /// Returns the time the executor spent polling the element since
+    /// it was last (re)started.
+    pub fn poll_time(&self) -> Duration {
+        self.poll_time
+    }
+}
+
+impl MailboxProbe {
+    pub(crate) fn new() -> Self {
+        MailboxProbe::default()
+    }
+
+    /// Records a message of type `type_name` being pushed to the
+    /// mailbox, along with its contents if they are known.
+    pub(crate) fn pushed(&self, type_name: &'static str, contents: Option<String>) {
+        // FIXME: panics?
+        self.queued.lock().unwrap().push_back(QueuedMessage {
+            type_name,
+            contents,
+            queued_at: Instant::now(),
+        });
+    }
+
+    /// Records the oldest message being taken out of the mailbox.
+    pub(crate) fn popped(&self) {
+        // FIXME: panics?
+        self.queued.lock().unwrap().pop_front();
+    }
+
+    /// Returns the messages waiting in the mailbox, from the oldest
+    /// to the newest.
+    pub(crate) fn snapshot(&self) -> Vec<QueuedMessage> {
+        // FIXME: panics?
+        self.queued.lock().unwrap().iter().cloned().collect()
+    }
+}
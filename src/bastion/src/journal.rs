@@ -0,0 +1,257 @@
+//!
+//! Journaling of the messages delivered to a children group, allowing
+//! to reproduce bugs deterministically by replaying them to a fresh
+//! instance of the group.
+//!
+//! A [`Journal`] records, in the order they were delivered, every
+//! message of a given type received by the elements of the children
+//! groups it was attached to with [`Children::with_journal`]. Its
+//! entries can then be inspected or fed again to another children
+//! group with [`Journal::replay`].
+//!
+//! [`Children::with_journal`]: crate::children::Children::with_journal
+use crate::children_ref::ChildrenRef;
+use crate::context::BastionId;
+use crate::envelope::RefAddr;
+use crate::message::{Message, Msg};
+use crate::path::BastionPath;
+use fxhash::FxHashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, trace};
+
+/// Records a message delivered to an element.
+type Record = Arc<dyn Fn(&BastionId, &Msg, &RefAddr) + Send + Sync>;
+
+#[derive(Clone)]
+/// Records the messages delivered to the elements of a children group
+/// in a [`Journal`].
+pub(crate) struct Recorder(Record);
+
+/// A journal of the messages of type `M` delivered to the elements
+/// of the children groups it is attached to.
+///
+/// Cloning a `Journal` returns a new handle to the same journal.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::journal::Journal;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// #[derive(Debug, Clone)]
+/// enum Command {
+///     Put(String, u64),
+///     Remove(String),
+/// }
+///
+/// let journal = Journal::<Command>::new();
+///
+/// let children_ref = Bastion::children(|children| {
+///     children
+///         .with_journal(journal.clone())
+///         .with_exec(|ctx| {
+///             async move {
+///                 // ...
+///                 # Ok(())
+///             }
+///         })
+/// }).expect("Couldn't create the children group.");
+///
+/// // ...once the bug happened, the journal can be replayed to a
+/// // fresh instance of the group...
+/// let fresh_ref = Bastion::children(|children| {
+///     children.with_exec(|ctx| {
+///         async move {
+///             // ...
+///             # Ok(())
+///         }
+///     })
+/// }).expect("Couldn't create the children group.");
+///
+/// journal.replay(&fresh_ref).expect("Couldn't replay the journal.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Journal<M> {
+    entries: Arc<Mutex<Vec<JournalEntry<M>>>>,
+}
+
+#[derive(Debug, Clone)]
+/// A message recorded in a [`Journal`].
+pub struct JournalEntry<M> {
+    seq: usize,
+    child: BastionId,
+    sender: Arc<BastionPath>,
+    message: M,
+}
+
+impl<M: Message + Clone> Journal<M> {
+    /// Creates a new empty journal.
+    pub fn new() -> Self {
+        Journal {
+            entries: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Returns a copy of the entries recorded so far, in the order
+    /// the messages were delivered.
+    pub fn entries(&self) -> Vec<JournalEntry<M>> {
+        // FIXME: panics?
+        self.entries.lock().unwrap().clone()
+    }
+
+    /// Returns the number of entries recorded so far.
+    pub fn len(&self) -> usize {
+        // FIXME: panics?
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Returns `true` if no entry has been recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes every entry recorded so far.
+    pub fn clear(&self) {
+        // FIXME: panics?
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Sends the messages recorded in this journal again, in the
+    /// same order, to the elements of `children`.
+    ///
+    /// The messages delivered to the same element of the journaled
+    /// group are sent to the same element of `children`, the
+    /// elements being matched in the order they first appear in the
+    /// journal. If `children` contains fewer elements than the
+    /// journaled groups did, the elements are reused in a
+    /// round-robin fashion.
+    ///
+    /// This method returns `()` if it succeeded, or the message that
+    /// couldn't be sent otherwise.
+    pub fn replay(&self, children: &ChildrenRef) -> Result<(), M> {
+        let elems = children.elems();
+        let entries = self.entries();
+        debug!(
+            "Journal: Replaying {} entries to Children({}).",
+            entries.len(),
+            children.id()
+        );
+
+        let mut targets = FxHashMap::default();
+        for entry in entries {
+            if elems.is_empty() {
+                return Err(entry.message);
+            }
+
+            let next = targets.len();
+            let target = *targets.entry(entry.child).or_insert(next) % elems.len();
+            trace!("Journal: Replaying entry #{}.", entry.seq);
+            elems[target].tell_anonymously(entry.message)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn recorder(&self) -> Recorder {
+        let entries = self.entries.clone();
+        Recorder(Arc::new(
+            move |child: &BastionId, msg: &Msg, sign: &RefAddr| {
+                let message: &M = match msg.as_ref().downcast_ref() {
+                    Some(message) => message,
+                    None => return,
+                };
+
+                // FIXME: panics?
+                let mut entries = entries.lock().unwrap();
+                let entry = JournalEntry {
+                    seq: entries.len(),
+                    child: child.clone(),
+                    sender: sign.path().clone(),
+                    message: message.clone(),
+                };
+
+                entries.push(entry);
+            },
+        ))
+    }
+}
+
+impl Recorder {
+    pub(crate) fn record(&self, child: &BastionId, msg: &Msg, sign: &RefAddr) {
+        (self.0)(child, msg, sign)
+    }
+}
+
+impl<M> JournalEntry<M> {
+    /// Returns the position of the entry in the journal, which is
+    /// the order in which the message was delivered.
+    pub fn seq(&self) -> usize {
+        self.seq
+    }
+
+    /// Returns the identifier of the element the message was
+    /// delivered to.
+    pub fn child(&self) -> &BastionId {
+        &self.child
+    }
+
+    /// Returns the path of the sender of the message.
+    pub fn sender(&self) -> &Arc<BastionPath> {
+        &self.sender
+    }
+
+    /// Returns the recorded message.
+    pub fn message(&self) -> &M {
+        &self.message
+    }
+
+    /// Returns the recorded message, consuming the entry.
+    pub fn into_message(self) -> M {
+        self.message
+    }
+}
+
+impl<M: Message + Clone> Default for Journal<M> {
+    fn default() -> Self {
+        Journal::new()
+    }
+}
+
+impl<M> Clone for Journal<M> {
+    fn clone(&self) -> Self {
+        Journal {
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+impl<M> Debug for Journal<M> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Journal").finish()
+    }
+}
+
+impl Debug for Recorder {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Recorder").finish()
+    }
+}
@@ -60,7 +60,7 @@
 
 pub use self::bastion::Bastion;
 pub use self::callbacks::Callbacks;
-pub use self::config::Config;
+pub use self::config::{AnonymousNaming, Config};
 
 #[macro_use]
 mod macros;
@@ -70,22 +70,69 @@ mod broadcast;
 mod callbacks;
 mod child;
 mod config;
+mod parking;
 mod system;
 
+pub mod alias;
+pub mod answers;
+#[cfg(feature = "tokio-sync")]
+pub mod bridge;
+pub mod bulkhead;
+#[cfg(feature = "chaos")]
+pub mod chaos;
 pub mod child_ref;
 pub mod children;
 pub mod children_ref;
+pub mod circuit_breaker;
 pub mod context;
+pub mod demand;
 pub mod dispatcher;
+pub mod drain;
 pub mod envelope;
 pub mod executor;
+pub mod fairness;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "file-store")]
+pub mod file_store;
+pub mod inbox;
+#[cfg(feature = "inspector")]
+pub mod inspector;
 #[cfg(not(target_os = "windows"))]
 pub mod io;
+#[cfg(feature = "journal")]
+pub mod journal;
 pub mod message;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub mod path;
+pub mod payload;
+pub mod persistence;
+pub mod pipeline;
+pub mod pool;
+#[cfg(feature = "process")]
+pub mod process;
+pub mod quota;
+pub mod registry;
 #[cfg(feature = "scaling")]
 pub mod resizer;
+pub mod resource;
+pub mod retry;
+pub mod router;
+pub mod saga;
+pub mod sampling;
+pub mod schedule;
+#[cfg(feature = "sled-store")]
+pub mod sled_store;
+pub mod source;
 pub mod supervisor;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod transaction;
+pub mod tree;
+pub mod watermark;
+#[cfg(feature = "web")]
+pub mod web;
 
 pub mod errors;
 
@@ -100,16 +147,17 @@ pub mod prelude {
     pub use crate::bastion::Bastion;
     pub use crate::callbacks::Callbacks;
     pub use crate::child_ref::ChildRef;
-    pub use crate::children::Children;
+    pub use crate::children::{ChildRestart, Children, RestartState};
     pub use crate::children_ref::ChildrenRef;
-    pub use crate::config::Config;
+    pub use crate::config::{AnonymousNaming, Config};
     pub use crate::context::{BastionContext, BastionId, NIL_ID};
     pub use crate::dispatcher::{
         BroadcastTarget, DefaultDispatcherHandler, Dispatcher, DispatcherHandler, DispatcherMap,
-        DispatcherType, NotificationType,
+        DispatcherType, NotificationType, Priority,
     };
-    pub use crate::envelope::{RefAddr, SignedMessage};
+    pub use crate::envelope::{RefAddr, SenderKind, SignedMessage};
     pub use crate::errors::*;
+    pub use crate::executor::ExecutorHandle;
     #[cfg(not(target_os = "windows"))]
     pub use crate::io::*;
     pub use crate::message::{Answer, AnswerSender, Message, Msg};
@@ -118,9 +166,11 @@ pub mod prelude {
     #[cfg(feature = "scaling")]
     pub use crate::resizer::{OptimalSizeExploringResizer, UpperBound, UpscaleStrategy};
     pub use crate::supervisor::{
-        ActorRestartStrategy, RestartPolicy, RestartStrategy, SupervisionStrategy, Supervisor,
-        SupervisorRef,
+        AbortPolicy, ActorRestartStrategy, Directive, Fault, FaultReason, RestartCircuit,
+        RestartPolicy, RestartStrategy, ShutdownOrder, SupervisionDecider, SupervisionStrategy,
+        Supervisor, SupervisorRef,
     };
+    pub use crate::tree::{GroupStats, SupervisorStats};
     pub use crate::{answer, blocking, children, run, spawn, supervisor};
 
     distributed_api! {
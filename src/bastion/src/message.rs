@@ -7,18 +7,26 @@
 //! * Messages are not guaranteed to be ordered, all message's order is causal.
 //!
 use crate::callbacks::CallbackType;
-use crate::children::Children;
+use crate::child::Init;
+use crate::children::{Children, Reconfigure};
 use crate::context::{BastionId, ContextState};
 use crate::envelope::{RefAddr, SignedMessage};
-use crate::supervisor::{SupervisionStrategy, Supervisor};
+use crate::errors::AskError;
+use crate::payload::Payload;
+use crate::quota::InFlight;
+use crate::supervisor::{FaultReason, SupervisionStrategy, Supervisor};
 
 use futures::channel::oneshot::{self, Receiver};
+use futures::future::{self, Either};
+use futures_timer::Delay;
 use std::any::{type_name, Any};
 use std::fmt::Debug;
 use std::future::Future;
+use std::mem;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 use tracing::{debug, trace};
 
 /// A trait that any message sent needs to implement (it is
@@ -40,7 +48,13 @@ impl<T> Message for T where T: Any + Send + Sync + Debug {}
 ///
 /// [`respond`]: #method.respond
 #[derive(Debug)]
-pub struct AnswerSender(oneshot::Sender<SignedMessage>, RefAddr);
+pub struct AnswerSender(
+    oneshot::Sender<SignedMessage>,
+    RefAddr,
+    // Counts the question against the quota of the group
+    // until it is answered.
+    Option<InFlight>,
+);
 
 #[derive(Debug)]
 /// A [`Future`] returned when successfully "asking" a
@@ -198,7 +212,13 @@ pub struct Answer(Receiver<SignedMessage>);
 ///
 /// [`BastionContext::recv`]: crate::context::BastionContext::recv
 /// [`BastionContext::try_recv`]: crate::context::BastionContext::try_recv
-pub struct Msg(MsgInner);
+pub struct Msg(
+    MsgInner,
+    // The type name of the message, captured when it was sent.
+    &'static str,
+    // The instant the message was sent.
+    Instant,
+);
 
 #[derive(Debug)]
 enum MsgInner {
@@ -207,6 +227,9 @@ enum MsgInner {
     Ask {
         msg: Box<dyn Any + Send + Sync + 'static>,
         sender: Option<AnswerSender>,
+        // The instant after which the asker won't wait for the
+        // answer anymore, if any.
+        deadline: Option<Instant>,
     },
 }
 
@@ -230,6 +253,7 @@ pub(crate) enum BastionMessage {
     RestartRequired {
         id: BastionId,
         parent_id: BastionId,
+        reason: FaultReason,
     },
     FinishedChild {
         id: BastionId,
@@ -253,12 +277,51 @@ pub(crate) enum BastionMessage {
         id: BastionId,
     },
     Heartbeat,
+    Reconfigure(Reconfigure),
+    Upgrade(Init),
+    PauseRestarts,
+    ResumeRestarts,
+    CircuitTimeout {
+        group: BastionId,
+    },
+    #[cfg(feature = "chaos")]
+    Fault,
+}
+
+#[derive(Debug, Clone)]
+/// A message whose handler panicked while its children group was
+/// isolating the panics (see [`BastionContext::handle`]), as sent
+/// to the dead letters.
+///
+/// [`BastionContext::handle`]: crate::context::BastionContext::handle
+pub struct PanickedMessage {
+    child: BastionId,
+    type_name: &'static str,
+    sender: RefAddr,
+    panic: String,
+}
+
+#[derive(Debug, Clone)]
+/// A message whose handler kept failing with a [`Retriable`] error
+/// until its children group's redelivery policy was exhausted (see
+/// [`BastionContext::handle_retriable`]), as sent to the dead
+/// letters.
+///
+/// [`Retriable`]: crate::retry::Retriable
+/// [`BastionContext::handle_retriable`]: crate::context::BastionContext::handle_retriable
+pub struct ExhaustedMessage {
+    child: BastionId,
+    type_name: &'static str,
+    sender: RefAddr,
+    deliveries: usize,
+    reason: String,
 }
 
 #[derive(Debug)]
 pub(crate) enum Deployment {
     Supervisor(Supervisor),
     Children(Children),
+    ChildrenBatch(Vec<Children>),
 }
 
 impl AnswerSender {
@@ -271,7 +334,7 @@ impl AnswerSender {
         let msg = Msg::tell(msg);
         trace!("{:?}: Sending message: {:?}", self, msg);
 
-        let AnswerSender(sender, sign) = self;
+        let AnswerSender(sender, sign, _in_flight) = self;
         sender
             .send(SignedMessage::new(msg, sign))
             .map_err(|smsg| smsg.msg.try_unwrap().unwrap())
@@ -281,24 +344,29 @@ impl AnswerSender {
 impl Msg {
     pub(crate) fn broadcast<M: Message>(msg: M) -> Self {
         let inner = MsgInner::Broadcast(Arc::new(msg));
-        Msg(inner)
+        Msg(inner, type_name::<M>(), Instant::now())
     }
 
     pub(crate) fn tell<M: Message>(msg: M) -> Self {
         let inner = MsgInner::Tell(Box::new(msg));
-        Msg(inner)
+        Msg(inner, type_name::<M>(), Instant::now())
     }
 
     pub(crate) fn ask<M: Message>(msg: M, sign: RefAddr) -> (Self, Answer) {
         let msg = Box::new(msg);
         let (sender, recver) = oneshot::channel();
-        let sender = AnswerSender(sender, sign);
+        let sender = AnswerSender(sender, sign, None);
         let answer = Answer(recver);
 
         let sender = Some(sender);
-        let inner = MsgInner::Ask { msg, sender };
+        let deadline = None;
+        let inner = MsgInner::Ask {
+            msg,
+            sender,
+            deadline,
+        };
 
-        (Msg(inner), answer)
+        (Msg(inner, type_name::<M>(), Instant::now()), answer)
     }
 
     #[doc(hidden)]
@@ -338,6 +406,7 @@ impl Msg {
     #[doc(hidden)]
     pub fn downcast<M: Message>(self) -> Result<M, Self> {
         trace!("{:?}: Downcasting to {}.", self, type_name::<M>());
+        let (name, sent_at) = (self.1, self.2);
         match self.0 {
             MsgInner::Tell(msg) => {
                 if msg.is::<M>() {
@@ -345,16 +414,24 @@ impl Msg {
                     Ok(*msg.downcast().unwrap())
                 } else {
                     let inner = MsgInner::Tell(msg);
-                    Err(Msg(inner))
+                    Err(Msg(inner, name, sent_at))
                 }
             }
-            MsgInner::Ask { msg, sender } => {
+            MsgInner::Ask {
+                msg,
+                sender,
+                deadline,
+            } => {
                 if msg.is::<M>() {
                     let msg: Box<dyn Any + 'static> = msg;
                     Ok(*msg.downcast().unwrap())
                 } else {
-                    let inner = MsgInner::Ask { msg, sender };
-                    Err(Msg(inner))
+                    let inner = MsgInner::Ask {
+                        msg,
+                        sender,
+                        deadline,
+                    };
+                    Err(Msg(inner, name, sent_at))
                 }
             }
             _ => Err(self),
@@ -373,11 +450,62 @@ impl Msg {
         None
     }
 
+    /// Returns the type name of the message.
+    pub(crate) fn type_name(&self) -> &'static str {
+        self.1
+    }
+
+    /// Returns the instant the message was sent.
+    pub(crate) fn sent_at(&self) -> Instant {
+        self.2
+    }
+
+    /// Returns the size of the message, which is the length
+    /// of its bytes for a [`Payload`].
+    pub(crate) fn size(&self) -> usize {
+        let msg = self.as_ref();
+        match msg.downcast_ref::<Payload>() {
+            Some(payload) => payload.len(),
+            None => mem::size_of_val(msg),
+        }
+    }
+
+    /// Sets the instant after which the asker won't wait for the
+    /// answer to the question anymore.
+    pub(crate) fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        if let MsgInner::Ask { deadline: d, .. } = &mut self.0 {
+            *d = deadline;
+        }
+
+        self
+    }
+
+    /// Returns the instant after which the asker won't wait for the
+    /// answer to the question anymore, if any.
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        match &self.0 {
+            MsgInner::Ask { deadline, .. } => *deadline,
+            _ => None,
+        }
+    }
+
+    /// Counts the question against the quota of the group until
+    /// it is answered.
+    pub(crate) fn track_in_flight(&mut self, in_flight: InFlight) {
+        if let MsgInner::Ask {
+            sender: Some(sender),
+            ..
+        } = &mut self.0
+        {
+            sender.2 = Some(in_flight);
+        }
+    }
+
     pub(crate) fn try_clone(&self) -> Option<Self> {
         trace!("{:?}: Trying to clone.", self);
         if let MsgInner::Broadcast(msg) = &self.0 {
             let inner = MsgInner::Broadcast(msg.clone());
-            Some(Msg(inner))
+            Some(Msg(inner, self.1, self.2))
         } else {
             None
         }
@@ -385,18 +513,19 @@ impl Msg {
 
     pub(crate) fn try_unwrap<M: Message>(self) -> Result<M, Self> {
         debug!("{:?}: Trying to unwrap.", self);
+        let (name, sent_at) = (self.1, self.2);
         if let MsgInner::Broadcast(msg) = self.0 {
             match msg.downcast() {
                 Ok(msg) => match Arc::try_unwrap(msg) {
                     Ok(msg) => Ok(msg),
                     Err(msg) => {
                         let inner = MsgInner::Broadcast(msg);
-                        Err(Msg(inner))
+                        Err(Msg(inner, name, sent_at))
                     }
                 },
                 Err(msg) => {
                     let inner = MsgInner::Broadcast(msg);
-                    Err(Msg(inner))
+                    Err(Msg(inner, name, sent_at))
                 }
             }
         } else {
@@ -415,6 +544,86 @@ impl AsRef<dyn Any> for Msg {
     }
 }
 
+impl PanickedMessage {
+    pub(crate) fn new(
+        child: BastionId,
+        type_name: &'static str,
+        sender: RefAddr,
+        panic: String,
+    ) -> Self {
+        PanickedMessage {
+            child,
+            type_name,
+            sender,
+            panic,
+        }
+    }
+
+    /// Returns the identifier of the element whose handler panicked.
+    pub fn child(&self) -> &BastionId {
+        &self.child
+    }
+
+    /// Returns the type name of the message.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns the address of the sender of the message.
+    pub fn sender(&self) -> &RefAddr {
+        &self.sender
+    }
+
+    /// Returns the message the handler panicked with.
+    pub fn panic(&self) -> &str {
+        &self.panic
+    }
+}
+
+impl ExhaustedMessage {
+    pub(crate) fn new(
+        child: BastionId,
+        type_name: &'static str,
+        sender: RefAddr,
+        deliveries: usize,
+        reason: String,
+    ) -> Self {
+        ExhaustedMessage {
+            child,
+            type_name,
+            sender,
+            deliveries,
+            reason,
+        }
+    }
+
+    /// Returns the identifier of the element which last failed to
+    /// handle the message.
+    pub fn child(&self) -> &BastionId {
+        &self.child
+    }
+
+    /// Returns the type name of the message.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns the address of the sender of the message.
+    pub fn sender(&self) -> &RefAddr {
+        &self.sender
+    }
+
+    /// Returns how many times the message was delivered.
+    pub fn deliveries(&self) -> usize {
+        self.deliveries
+    }
+
+    /// Returns the reason of the last failure to handle the message.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
 impl BastionMessage {
     pub(crate) fn start() -> Self {
         BastionMessage::Start
@@ -440,6 +649,12 @@ impl BastionMessage {
         BastionMessage::Deploy(deployment.into())
     }
 
+    pub(crate) fn deploy_children_batch(batch: Vec<Children>) -> Self {
+        let deployment = Deployment::ChildrenBatch(batch);
+
+        BastionMessage::Deploy(deployment.into())
+    }
+
     pub(crate) fn prune(id: BastionId) -> Self {
         BastionMessage::Prune { id }
     }
@@ -479,8 +694,25 @@ impl BastionMessage {
         (BastionMessage::Message(msg), answer)
     }
 
-    pub(crate) fn restart_required(id: BastionId, parent_id: BastionId) -> Self {
-        BastionMessage::RestartRequired { id, parent_id }
+    pub(crate) fn ask_with_deadline<M: Message>(
+        msg: M,
+        sign: RefAddr,
+        deadline: Option<Instant>,
+    ) -> (Self, Answer) {
+        let (msg, answer) = Msg::ask(msg, sign);
+        (BastionMessage::Message(msg.with_deadline(deadline)), answer)
+    }
+
+    pub(crate) fn restart_required(
+        id: BastionId,
+        parent_id: BastionId,
+        reason: FaultReason,
+    ) -> Self {
+        BastionMessage::RestartRequired {
+            id,
+            parent_id,
+            reason,
+        }
     }
 
     pub(crate) fn finished_child(id: BastionId, parent_id: BastionId) -> Self {
@@ -515,6 +747,31 @@ impl BastionMessage {
         BastionMessage::Heartbeat
     }
 
+    pub(crate) fn reconfigure(reconfigure: Reconfigure) -> Self {
+        BastionMessage::Reconfigure(reconfigure)
+    }
+
+    pub(crate) fn upgrade(init: Init) -> Self {
+        BastionMessage::Upgrade(init)
+    }
+
+    pub(crate) fn pause_restarts() -> Self {
+        BastionMessage::PauseRestarts
+    }
+
+    pub(crate) fn resume_restarts() -> Self {
+        BastionMessage::ResumeRestarts
+    }
+
+    pub(crate) fn circuit_timeout(group: BastionId) -> Self {
+        BastionMessage::CircuitTimeout { group }
+    }
+
+    #[cfg(feature = "chaos")]
+    pub(crate) fn fault() -> Self {
+        BastionMessage::Fault
+    }
+
     pub(crate) fn try_clone(&self) -> Option<Self> {
         trace!("{:?}: Trying to clone.", self);
         let clone = match self {
@@ -540,9 +797,11 @@ impl BastionMessage {
                 state.clone(),
             ),
             BastionMessage::Message(msg) => BastionMessage::Message(msg.try_clone()?),
-            BastionMessage::RestartRequired { id, parent_id } => {
-                BastionMessage::restart_required(id.clone(), parent_id.clone())
-            }
+            BastionMessage::RestartRequired {
+                id,
+                parent_id,
+                reason,
+            } => BastionMessage::restart_required(id.clone(), parent_id.clone(), reason.clone()),
             BastionMessage::FinishedChild { id, parent_id } => {
                 BastionMessage::finished_child(id.clone(), parent_id.clone())
             }
@@ -555,6 +814,17 @@ impl BastionMessage {
             BastionMessage::Stopped { id } => BastionMessage::stopped(id.clone()),
             BastionMessage::Faulted { id } => BastionMessage::faulted(id.clone()),
             BastionMessage::Heartbeat => BastionMessage::heartbeat(),
+            BastionMessage::Reconfigure(reconfigure) => {
+                BastionMessage::reconfigure(reconfigure.clone())
+            }
+            BastionMessage::Upgrade(_) => return None,
+            BastionMessage::PauseRestarts => BastionMessage::pause_restarts(),
+            BastionMessage::ResumeRestarts => BastionMessage::resume_restarts(),
+            BastionMessage::CircuitTimeout { group } => {
+                BastionMessage::circuit_timeout(group.clone())
+            }
+            #[cfg(feature = "chaos")]
+            BastionMessage::Fault => BastionMessage::fault(),
         };
 
         Some(clone)
@@ -569,6 +839,25 @@ impl BastionMessage {
     }
 }
 
+impl Answer {
+    /// Waits for the answer for up to `timeout` if given, returning
+    /// [`AskError::Timeout`] once it elapsed or
+    /// [`AskError::Unavailable`] if the question was dropped without
+    /// being answered.
+    pub(crate) async fn within(self, timeout: Option<Duration>) -> Result<SignedMessage, AskError> {
+        let timeout = match timeout {
+            Some(timeout) => timeout,
+            None => return self.await.map_err(|()| AskError::Unavailable),
+        };
+
+        match future::select(self, Delay::new(timeout)).await {
+            Either::Left((Ok(answer), _)) => Ok(answer),
+            Either::Left((Err(()), _)) => Err(AskError::Unavailable),
+            Either::Right(_) => Err(AskError::Timeout(timeout)),
+        }
+    }
+}
+
 impl Future for Answer {
     type Output = Result<SignedMessage, ()>;
 
@@ -1084,10 +1373,14 @@ impl<O> MessageHandler<O> {
         match self.state.take_message() {
             Ok(SignedMessage {
                 msg:
-                    Msg(MsgInner::Ask {
-                        msg,
-                        sender: Some(sender),
-                    }),
+                    Msg(
+                        MsgInner::Ask {
+                            msg,
+                            sender: Some(sender),
+                            ..
+                        },
+                        ..,
+                    ),
                 ..
             }) if msg.is::<T>() => {
                 let msg: Box<dyn Any> = msg;
@@ -1104,7 +1397,7 @@ impl<O> MessageHandler<O> {
     ) -> Result<(Arc<T>, RefAddr), MessageHandler<O>> {
         match self.state.take_message() {
             Ok(SignedMessage {
-                msg: Msg(MsgInner::Broadcast(msg)),
+                msg: Msg(MsgInner::Broadcast(msg), ..),
                 sign,
             }) if msg.is::<T>() => {
                 let msg: Arc<dyn Any + Send + Sync + 'static> = msg;
@@ -1119,7 +1412,7 @@ impl<O> MessageHandler<O> {
     fn try_into_tell<T: 'static>(self) -> Result<(T, RefAddr), MessageHandler<O>> {
         match self.state.take_message() {
             Ok(SignedMessage {
-                msg: Msg(MsgInner::Tell(msg)),
+                msg: Msg(MsgInner::Tell(msg), ..),
                 sign,
             }) if msg.is::<T>() => {
                 let msg: Box<dyn Any> = msg;
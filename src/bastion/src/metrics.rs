@@ -0,0 +1,354 @@
+//!
+//! Metrics about the children groups, published to a process-wide
+//! registry.
+//!
+//! The [`Registry`] returned by [`registry`] holds counters and gauges
+//! identified by a name and a set of labels. Children groups publish
+//! their metrics to it once a stats collector was attached to them with
+//! [`Children::with_stats_collector`], and the registry can be read with
+//! [`Registry::snapshot`] to export them (eg. to Prometheus).
+//!
+//! The stats collector publishes, every interval:
+//! - `bastion_mailbox_depth` (gauge, per element): the number of messages
+//!   waiting in the mailbox of the element.
+//! - `bastion_messages_processed_total` (counter): the number of messages
+//!   the elements of the group received.
+//! - `bastion_messages_by_type_total` (counter, per message type): the
+//!   number of messages of each type the elements of the group received,
+//!   labeled with the type name of the messages (`message`) as it was when
+//!   they were sent.
+//! - `bastion_messages_per_second` (gauge): the rate at which the elements
+//!   of the group received messages during the last interval.
+//! - `bastion_restarts_total` (counter): the number of times elements of
+//!   the group were restarted.
+//! - `bastion_polls_total` (counter, per element): the number of times the
+//!   executor polled the element (see [`ChildRef::poll_count`]).
+//! - `bastion_poll_time_microseconds_total` (counter, per element): the
+//!   time the executor spent polling the element (see
+//!   [`ChildRef::poll_time`]).
+//! - `bastion_mailbox_watermark_crossings_total` (counter, per level): the
+//!   number of times the mailboxes of the elements of the group crossed
+//!   each of their watermarks while filling up, labeled with the level of
+//!   the watermark in percents (`level`), if the group has watermarks (see
+//!   [`Children::with_mailbox_watermarks`]).
+//!
+//! [`Children::with_stats_collector`]: crate::children::Children::with_stats_collector
+//! [`ChildRef::poll_count`]: crate::child_ref::ChildRef::poll_count
+//! [`ChildRef::poll_time`]: crate::child_ref::ChildRef::poll_time
+//! [`Children::with_mailbox_watermarks`]: crate::children::Children::with_mailbox_watermarks
+use crate::context::{BastionId, ContextState};
+use fxhash::FxHashMap;
+use lazy_static::lazy_static;
+use lightproc::proc_stack::ProcStats;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+lazy_static! {
+    static ref REGISTRY: Registry = Registry::default();
+}
+
+/// Returns the process-wide metrics registry.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::metrics::{self, Value};
+/// #
+/// for sample in metrics::registry().snapshot() {
+///     match sample.value() {
+///         Value::Counter(value) => println!("{} {:?} {}", sample.name(), sample.labels(), value),
+///         Value::Gauge(value) => println!("{} {:?} {}", sample.name(), sample.labels(), value),
+///     }
+/// }
+/// ```
+pub fn registry() -> &'static Registry {
+    &REGISTRY
+}
+
+#[derive(Debug, Default)]
+/// A registry of counters and gauges, identified by their name and
+/// labels.
+pub struct Registry {
+    metrics: Mutex<BTreeMap<Key, Metric>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Key {
+    name: String,
+    labels: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
+enum Metric {
+    Counter(Counter),
+    Gauge(Gauge),
+}
+
+#[derive(Debug, Clone, Default)]
+/// A metric which only goes up.
+///
+/// Cloning a `Counter` returns a new handle to the same counter.
+pub struct Counter(Arc<AtomicU64>);
+
+#[derive(Debug, Clone, Default)]
+/// A metric which can go up and down.
+///
+/// Cloning a `Gauge` returns a new handle to the same gauge.
+pub struct Gauge(Arc<AtomicU64>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The value of a metric at the time a [`Registry::snapshot`] was
+/// taken.
+pub struct Sample {
+    name: String,
+    labels: Vec<(String, String)>,
+    value: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// The value of a [`Sample`].
+pub enum Value {
+    /// The value of a [`Counter`].
+    Counter(u64),
+    /// The value of a [`Gauge`].
+    Gauge(f64),
+}
+
+impl Registry {
+    /// Returns the counter with the given name and labels, creating
+    /// it if it doesn't exist yet (or if it was a gauge).
+    pub fn counter(&self, name: &str, labels: &[(&str, &str)]) -> Counter {
+        // FIXME: panics?
+        let mut metrics = self.metrics.lock().unwrap();
+        match metrics.get(&Key::new(name, labels)) {
+            Some(Metric::Counter(counter)) => counter.clone(),
+            _ => {
+                let counter = Counter::default();
+                metrics.insert(Key::new(name, labels), Metric::Counter(counter.clone()));
+                counter
+            }
+        }
+    }
+
+    /// Returns the gauge with the given name and labels, creating
+    /// it if it doesn't exist yet (or if it was a counter).
+    pub fn gauge(&self, name: &str, labels: &[(&str, &str)]) -> Gauge {
+        // FIXME: panics?
+        let mut metrics = self.metrics.lock().unwrap();
+        match metrics.get(&Key::new(name, labels)) {
+            Some(Metric::Gauge(gauge)) => gauge.clone(),
+            _ => {
+                let gauge = Gauge::default();
+                metrics.insert(Key::new(name, labels), Metric::Gauge(gauge.clone()));
+                gauge
+            }
+        }
+    }
+
+    /// Removes the metrics whose labels contain the given label.
+    pub fn remove_labeled(&self, label: &str, value: &str) {
+        // FIXME: panics?
+        self.metrics.lock().unwrap().retain(|key, _| {
+            !key.labels
+                .iter()
+                .any(|(name, val)| name == label && val == value)
+        });
+    }
+
+    /// Sets the metrics of the registry to the values of the given
+    /// samples (eg. taken from the registry of another process), with
+    /// `labels` added to the labels of each sample.
+    pub fn import(&self, samples: &[Sample], labels: &[(&str, &str)]) {
+        for sample in samples {
+            let mut sample_labels = sample
+                .labels
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()))
+                .collect::<Vec<_>>();
+            sample_labels.extend_from_slice(labels);
+
+            match sample.value {
+                Value::Counter(value) => self.counter(&sample.name, &sample_labels).store(value),
+                Value::Gauge(value) => self.gauge(&sample.name, &sample_labels).set(value),
+            }
+        }
+    }
+
+    /// Returns the current value of every metric of the registry,
+    /// sorted by name and labels.
+    pub fn snapshot(&self) -> Vec<Sample> {
+        // FIXME: panics?
+        let metrics = self.metrics.lock().unwrap();
+        metrics
+            .iter()
+            .map(|(key, metric)| Sample {
+                name: key.name.clone(),
+                labels: key.labels.clone(),
+                value: match metric {
+                    Metric::Counter(counter) => Value::Counter(counter.get()),
+                    Metric::Gauge(gauge) => Value::Gauge(gauge.get()),
+                },
+            })
+            .collect()
+    }
+}
+
+impl Key {
+    fn new(name: &str, labels: &[(&str, &str)]) -> Self {
+        let mut labels = labels
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<_>>();
+        labels.sort();
+
+        Key {
+            name: name.to_string(),
+            labels,
+        }
+    }
+}
+
+impl Counter {
+    /// Increments the counter by `n`.
+    pub fn increment(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    // Sets the counter to the value of a counter imported from
+    // another registry.
+    fn store(&self, value: u64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    /// Returns the current value of the counter.
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Gauge {
+    /// Sets the value of the gauge.
+    pub fn set(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Returns the current value of the gauge.
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+impl Sample {
+    /// Returns the name of the metric.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the labels of the metric, sorted by name.
+    pub fn labels(&self) -> &[(String, String)] {
+        &self.labels
+    }
+
+    /// Returns the value of the metric.
+    pub fn value(&self) -> Value {
+        self.value
+    }
+}
+
+#[derive(Debug, Default)]
+/// The data the stats collector of a children group samples.
+pub(crate) struct GroupMetrics {
+    // The states of the launched elements, to sample the depth of
+    // their mailboxes and the number of messages they received.
+    states: Mutex<FxHashMap<BastionId, Arc<Pin<Box<ContextState>>>>>,
+    // The poll accounting of the launched elements.
+    stats: Mutex<FxHashMap<BastionId, Arc<ProcStats>>>,
+    restarts: AtomicU64,
+}
+
+/// A sample of the [`GroupMetrics`] of a children group.
+pub(crate) struct GroupSample {
+    pub(crate) mailboxes: Vec<(BastionId, u64)>,
+    pub(crate) polls: Vec<(BastionId, u64, Duration)>,
+    pub(crate) processed: u64,
+    pub(crate) processed_by_type: FxHashMap<&'static str, u64>,
+    pub(crate) watermark_crossings: FxHashMap<u8, u64>,
+    pub(crate) restarts: u64,
+}
+
+impl GroupMetrics {
+    pub(crate) fn register(
+        &self,
+        id: BastionId,
+        state: Arc<Pin<Box<ContextState>>>,
+        stats: Arc<ProcStats>,
+    ) {
+        // FIXME: panics?
+        self.states.lock().unwrap().insert(id.clone(), state);
+        // FIXME: panics?
+        self.stats.lock().unwrap().insert(id, stats);
+    }
+
+    pub(crate) fn unregister(&self, id: &BastionId) {
+        // FIXME: panics?
+        self.states.lock().unwrap().remove(id);
+        // FIXME: panics?
+        self.stats.lock().unwrap().remove(id);
+    }
+
+    pub(crate) fn clear(&self) {
+        // FIXME: panics?
+        self.states.lock().unwrap().clear();
+        // FIXME: panics?
+        self.stats.lock().unwrap().clear();
+        self.restarts.store(0, Ordering::Relaxed);
+    }
+
+    pub(crate) fn restarted(&self) {
+        self.restarts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn sample(&self) -> GroupSample {
+        // FIXME: panics?
+        let states = self.states.lock().unwrap();
+        let mailboxes = states
+            .iter()
+            .map(|(id, state)| (id.clone(), state.mailbox_size() as u64))
+            .collect();
+        let processed = states.values().map(|state| state.processed()).sum();
+        let mut processed_by_type = FxHashMap::default();
+        for state in states.values() {
+            for (name, count) in state.processed_by_type() {
+                *processed_by_type.entry(name).or_insert(0) += count;
+            }
+        }
+        let mut watermark_crossings = FxHashMap::default();
+        for state in states.values() {
+            for (level, count) in state.watermark_crossings() {
+                *watermark_crossings.entry(level).or_insert(0) += count;
+            }
+        }
+
+        // FIXME: panics?
+        let polls = self
+            .stats
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, stats)| (id.clone(), stats.polls(), stats.poll_time()))
+            .collect();
+
+        GroupSample {
+            mailboxes,
+            polls,
+            processed,
+            processed_by_type,
+            watermark_crossings,
+            restarts: self.restarts.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//!
+//! Parking of the idle elements of a children group.
+//!
+//! A children group polls the handles of its elements to be woken
+//! once one of them completes, and to call the panic callbacks of
+//! those which panicked. Instead of polling the handles of all its
+//! elements every time it is woken, a group parking its elements
+//! only polls those of the elements which were just launched or
+//! which woke it, so that its idle elements cost it nothing.
+use crate::context::BastionId;
+use crossbeam_queue::SegQueue;
+use futures::task::{self, ArcWake, AtomicWaker};
+use std::sync::Arc;
+use std::task::Waker;
+
+#[derive(Debug, Default)]
+pub(crate) struct Parking {
+    ready: Arc<Ready>,
+}
+
+#[derive(Debug, Default)]
+struct Ready {
+    // The elements whose handles have to be polled.
+    ids: SegQueue<BastionId>,
+    // The waker of the group's process.
+    group: AtomicWaker,
+}
+
+/// The waker given to the handle of an element, marking it as ready
+/// before waking the group.
+struct ElementWaker {
+    id: BastionId,
+    ready: Arc<Ready>,
+}
+
+impl Parking {
+    /// Makes the handle of the element with the given identifier
+    /// polled the next time the group is woken.
+    pub(crate) fn unpark(&self, id: &BastionId) {
+        self.ready.ids.push(id.clone());
+    }
+
+    /// Registers the waker of the group's process, woken once an
+    /// element is ready.
+    pub(crate) fn register(&self, waker: &Waker) {
+        self.ready.group.register(waker);
+    }
+
+    /// Returns the identifiers of the elements whose handles have to
+    /// be polled, along with the wakers to poll them with.
+    pub(crate) fn ready(&self) -> Vec<(BastionId, Waker)> {
+        let mut ready = Vec::new();
+        while let Some(id) = self.ready.ids.pop() {
+            let waker = task::waker(Arc::new(ElementWaker {
+                id: id.clone(),
+                ready: self.ready.clone(),
+            }));
+
+            ready.push((id, waker));
+        }
+
+        ready
+    }
+}
+
+impl ArcWake for ElementWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.ready.ids.push(arc_self.id.clone());
+        arc_self.ready.group.wake();
+    }
+}
@@ -69,6 +69,19 @@ impl BastionPath {
         }
     }
 
+    // An inbox out of Bastion scope
+    pub(crate) fn inbox(id: BastionId) -> BastionPath {
+        BastionPath {
+            parent_chain: vec![],
+            this: Some(BastionPathElement::Child(id)),
+        }
+    }
+
+    // Whether it's the path of an inbox
+    pub(crate) fn is_inbox(&self) -> bool {
+        self.parent_chain.is_empty() && self.this.as_ref().map(|e| e.is_child()).unwrap_or(false)
+    }
+
     /// iterates over path elements
     pub(crate) fn iter(&self) -> impl Iterator<Item = &BastionId> {
         let parent_iter = self.parent_chain.iter();
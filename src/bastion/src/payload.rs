@@ -0,0 +1,291 @@
+//!
+//! Out-of-line storage for large message payloads.
+//!
+//! A [`Payload`] holds raw bytes and is cheap to pass around: once its
+//! size goes above the offload threshold (64KiB by default, see
+//! [`Config::with_offload_threshold`]), its bytes are stored in a shared
+//! `Arc<[u8]>` and only a handle to them goes through the mailboxes, so
+//! that telling or broadcasting a multi-megabyte payload to many children
+//! doesn't copy it every time. The bytes of a large payload can also be
+//! spilled to a temporary file with [`Payload::spill`], to hand them over
+//! to a remote transport by path instead of by value.
+//!
+//! [`Config::with_offload_threshold`]: crate::config::Config::with_offload_threshold
+use std::borrow::Cow;
+use std::fmt::{self, Debug, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// The size (in bytes) above which payloads are stored out-of-line
+/// by default.
+pub const DEFAULT_OFFLOAD_THRESHOLD: usize = 64 * 1024;
+
+static THRESHOLD: AtomicUsize = AtomicUsize::new(DEFAULT_OFFLOAD_THRESHOLD);
+
+/// Returns the size (in bytes) above which payloads are stored
+/// out-of-line.
+pub fn threshold() -> usize {
+    THRESHOLD.load(Ordering::Relaxed)
+}
+
+pub(crate) fn set_threshold(threshold: usize) {
+    debug!(
+        "Payload: Setting the offload threshold to {} bytes.",
+        threshold
+    );
+    THRESHOLD.store(threshold, Ordering::Relaxed);
+}
+
+#[derive(Clone)]
+/// Raw bytes which can be sent as a message, stored out-of-line once
+/// they are larger than the offload [`threshold`].
+///
+/// Cloning an offloaded `Payload` returns a new handle to the same
+/// bytes.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::payload::Payload;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let children_ref = Bastion::children(|children| {
+///     children
+///         .with_redundancy(8)
+///         .with_exec(|ctx: BastionContext| {
+///             async move {
+///                 msg! { ctx.recv().await?,
+///                     ref payload: Payload => {
+///                         let bytes = payload.bytes().expect("Couldn't read the payload.");
+///                         // ...
+///                     };
+///                     _: _ => ();
+///                 }
+///                 Ok(())
+///             }
+///         })
+/// }).expect("Couldn't create the children group.");
+///
+/// # Bastion::start();
+/// // Only a handle to the 4MiB of the payload is broadcasted.
+/// let payload = Payload::new(vec![0; 4 * 1024 * 1024]);
+/// assert!(payload.is_offloaded());
+/// children_ref.broadcast(payload).expect("Couldn't broadcast the payload.");
+/// #
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Payload {
+    inner: PayloadInner,
+}
+
+#[derive(Clone)]
+enum PayloadInner {
+    Inline(Box<[u8]>),
+    Shared(Arc<[u8]>),
+    Spilled(Arc<SpilledFile>),
+}
+
+// A temporary file holding the bytes of a payload, which is
+// removed once the last handle to it is dropped.
+struct SpilledFile {
+    path: PathBuf,
+    len: usize,
+}
+
+impl Payload {
+    /// Creates a new payload holding `bytes`, storing them
+    /// out-of-line if they are larger than the offload
+    /// [`threshold`].
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        let bytes = bytes.into();
+        let inner = if bytes.len() > threshold() {
+            PayloadInner::Shared(bytes.into())
+        } else {
+            PayloadInner::Inline(bytes.into_boxed_slice())
+        };
+
+        Payload { inner }
+    }
+
+    /// Returns the size (in bytes) of the payload.
+    pub fn len(&self) -> usize {
+        match &self.inner {
+            PayloadInner::Inline(bytes) => bytes.len(),
+            PayloadInner::Shared(bytes) => bytes.len(),
+            PayloadInner::Spilled(file) => file.len,
+        }
+    }
+
+    /// Returns whether the payload is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns whether the bytes of the payload are stored
+    /// out-of-line, either in memory or in a temporary file.
+    pub fn is_offloaded(&self) -> bool {
+        !matches!(self.inner, PayloadInner::Inline(_))
+    }
+
+    /// Returns the path of the temporary file the payload was
+    /// spilled to, if it was.
+    pub fn path(&self) -> Option<&Path> {
+        match &self.inner {
+            PayloadInner::Spilled(file) => Some(&file.path),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes of the payload, reading them from its
+    /// temporary file if it was spilled to one.
+    pub fn bytes(&self) -> io::Result<Cow<'_, [u8]>> {
+        match &self.inner {
+            PayloadInner::Inline(bytes) => Ok(Cow::Borrowed(bytes)),
+            PayloadInner::Shared(bytes) => Ok(Cow::Borrowed(bytes)),
+            PayloadInner::Spilled(file) => fs::read(&file.path).map(Cow::Owned),
+        }
+    }
+
+    /// Writes the bytes of an offloaded payload to a temporary
+    /// file and returns a payload referencing it, which can be
+    /// handed over to a remote transport by [`path`].
+    ///
+    /// Payloads which aren't offloaded are kept in memory and
+    /// payloads already spilled to a file aren't written again.
+    /// The file is removed once every handle to it is dropped.
+    ///
+    /// [`path`]: Self::path
+    pub fn spill(&self) -> io::Result<Self> {
+        let bytes = match &self.inner {
+            PayloadInner::Shared(bytes) => bytes,
+            _ => return Ok(self.clone()),
+        };
+
+        let path = std::env::temp_dir().join(format!("bastion-payload-{}", Uuid::new_v4()));
+        debug!(
+            "Payload: Spilling {} bytes to {}.",
+            bytes.len(),
+            path.display()
+        );
+        fs::write(&path, bytes)?;
+
+        let file = SpilledFile {
+            path,
+            len: bytes.len(),
+        };
+        let inner = PayloadInner::Spilled(Arc::new(file));
+
+        Ok(Payload { inner })
+    }
+}
+
+impl From<Vec<u8>> for Payload {
+    fn from(bytes: Vec<u8>) -> Self {
+        Payload::new(bytes)
+    }
+}
+
+impl From<&[u8]> for Payload {
+    fn from(bytes: &[u8]) -> Self {
+        Payload::new(bytes)
+    }
+}
+
+impl Drop for SpilledFile {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            warn!("Payload: Couldn't remove {}: {}", self.path.display(), err);
+        }
+    }
+}
+
+// The bytes aren't formatted, since payloads are logged every
+// time they are sent.
+impl Debug for Payload {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let storage = match &self.inner {
+            PayloadInner::Inline(_) => "inline",
+            PayloadInner::Shared(_) => "shared",
+            PayloadInner::Spilled(_) => "spilled",
+        };
+
+        fmt.debug_struct("Payload")
+            .field("len", &self.len())
+            .field("storage", &storage)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_payloads_stay_inline() {
+        let payload = Payload::new(vec![1, 2, 3]);
+        assert!(!payload.is_offloaded());
+        assert_eq!(payload.len(), 3);
+        assert_eq!(&*payload.bytes().unwrap(), &[1, 2, 3]);
+
+        let empty = Payload::new(vec![]);
+        assert!(empty.is_empty());
+        assert!(!empty.is_offloaded());
+    }
+
+    #[test]
+    fn large_payloads_are_shared() {
+        let payload = Payload::new(vec![7; threshold() + 1]);
+        assert!(payload.is_offloaded());
+        assert_eq!(payload.len(), threshold() + 1);
+        assert!(payload.path().is_none());
+
+        let clone = payload.clone();
+        let bytes = payload.bytes().unwrap();
+        let cloned = clone.bytes().unwrap();
+        assert_eq!(bytes.as_ptr(), cloned.as_ptr());
+    }
+
+    #[test]
+    fn only_shared_payloads_are_spilled() {
+        let inline = Payload::new(vec![1, 2, 3]);
+        assert!(inline.spill().unwrap().path().is_none());
+
+        let payload = Payload::new(vec![7; threshold() + 1]);
+        let spilled = payload.spill().unwrap();
+        let path = spilled.path().unwrap().to_path_buf();
+        assert!(spilled.is_offloaded());
+        assert_eq!(spilled.len(), payload.len());
+        assert_eq!(spilled.bytes().unwrap(), payload.bytes().unwrap());
+
+        // A spilled payload isn't written again.
+        let respilled = spilled.spill().unwrap();
+        assert_eq!(respilled.path(), Some(path.as_path()));
+
+        // The file is removed with the last handle to it.
+        drop(spilled);
+        assert!(path.exists());
+        drop(respilled);
+        assert!(!path.exists());
+    }
+}
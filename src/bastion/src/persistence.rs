@@ -0,0 +1,573 @@
+//!
+//! Persistent actors, whose state is rebuilt from the events they
+//! persisted when they are restarted.
+//!
+//! A [`PersistentState`] holds the state of an actor implementing
+//! [`Persistent`]. Every event persisted with
+//! [`PersistentState::persist`] is appended to an [`EventJournal`]
+//! before being applied to the state, and the state is saved from time
+//! to time in a [`SnapshotStore`], as decided by its [`SnapshotPolicy`],
+//! so that recovering it with [`PersistentState::recover`] only needs to
+//! apply the events persisted since the last snapshot.
+//!
+//! The [`MemoryJournal`] and the [`MemorySnapshotStore`] keep the events
+//! and the snapshots in memory, which allows to recover the state of the
+//! actors after they were restarted; implementing the traits on top of a
+//! database allows to recover it after the whole process was restarted.
+use fxhash::FxHashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, trace, warn};
+
+/// The state of a persistent actor, changed by the events it
+/// persists.
+pub trait Persistent: Send + Sync + 'static {
+    /// The type of the events changing the state.
+    type Event: Send + Sync + 'static;
+
+    /// Applies `event` to the state.
+    fn apply(&mut self, event: &Self::Event);
+
+    /// Returns the size (in bytes) of `event`, which is used by the
+    /// [`SnapshotPolicy::with_size_threshold`] policy.
+    ///
+    /// This defaults to the size of the event's type, and should be
+    /// overridden if the events hold data on the heap.
+    fn event_size(event: &Self::Event) -> usize {
+        mem::size_of_val(event)
+    }
+}
+
+/// Persists the events of the persistent actors, in order.
+pub trait EventJournal<E>: Send + Sync + 'static {
+    /// Appends `event`, whose sequence number is `seq`, to the
+    /// events of the actor with the given id.
+    #[allow(clippy::result_unit_err)]
+    fn append(&self, id: &str, seq: u64, event: &E) -> Result<(), ()>;
+    /// Returns the events of the actor with the given id whose
+    /// sequence numbers are greater than `after`, along with their
+    /// sequence numbers, in order.
+    #[allow(clippy::result_unit_err)]
+    fn events(&self, id: &str, after: u64) -> Result<Vec<(u64, E)>, ()>;
+}
+
+/// Persists the snapshots of the states of the persistent actors.
+pub trait SnapshotStore<S>: Send + Sync + 'static {
+    /// Saves `state`, which includes the events up to the sequence
+    /// number `seq`, as the last snapshot of the actor with the
+    /// given id.
+    #[allow(clippy::result_unit_err)]
+    fn save(&self, id: &str, seq: u64, state: &S) -> Result<(), ()>;
+    /// Returns the last snapshot of the actor with the given id,
+    /// along with the sequence number of the last event it includes,
+    /// if one was saved.
+    #[allow(clippy::result_unit_err)]
+    fn load(&self, id: &str) -> Result<Option<(u64, S)>, ()>;
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// Defines when the state of a persistent actor is saved in its
+/// [`SnapshotStore`].
+///
+/// A snapshot is saved after an event was persisted if any of the
+/// policy's conditions is met. By default, no snapshot is saved and the
+/// state is recovered by applying all the events persisted so far.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::persistence::SnapshotPolicy;
+/// # use std::time::Duration;
+/// #
+/// let policy = SnapshotPolicy::new()
+///     // Saves a snapshot every 100 events...
+///     .with_every_events(100)
+///     // ...or once the events persisted since the last one take
+///     // more than 1MiB...
+///     .with_size_threshold(1024 * 1024)
+///     // ...or every minute, if an event was persisted since.
+///     .with_every(Duration::from_secs(60));
+/// ```
+pub struct SnapshotPolicy {
+    every_events: Option<u64>,
+    every: Option<Duration>,
+    size_threshold: Option<usize>,
+}
+
+/// Holds the state of a persistent actor, persisting the events
+/// changing it and saving its snapshots.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::persistence::{
+/// #     MemoryJournal, MemorySnapshotStore, Persistent, PersistentState, SnapshotPolicy,
+/// # };
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// #[derive(Debug, Default, Clone)]
+/// struct Counter(u64);
+///
+/// impl Persistent for Counter {
+///     type Event = u64;
+///
+///     fn apply(&mut self, event: &u64) {
+///         self.0 += event;
+///     }
+/// }
+///
+/// // Kept when the element is restarted.
+/// let journal = MemoryJournal::new();
+/// let snapshots = MemorySnapshotStore::new();
+///
+/// Bastion::children(|children| {
+///     children.with_exec(move |ctx: BastionContext| {
+///         let journal = journal.clone();
+///         let snapshots = snapshots.clone();
+///         async move {
+///             let policy = SnapshotPolicy::new().with_every_events(100);
+///             let mut counter = PersistentState::recover(
+///                 "counter",
+///                 Counter::default(),
+///                 journal,
+///                 snapshots,
+///                 policy,
+///             )?;
+///
+///             loop {
+///                 msg! { ctx.recv().await?,
+///                     n: u64 => {
+///                         counter.persist(n)?;
+///                     };
+///                     _: _ => ();
+///                 }
+///             }
+///         }
+///     })
+/// }).expect("Couldn't create the children group.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct PersistentState<A: Persistent> {
+    id: String,
+    state: A,
+    // The sequence number of the last persisted event.
+    seq: u64,
+    journal: Arc<dyn EventJournal<A::Event>>,
+    snapshots: Arc<dyn SnapshotStore<A>>,
+    policy: SnapshotPolicy,
+    // The number and the size of the events persisted since
+    // the last snapshot, and when it was saved.
+    events: u64,
+    bytes: usize,
+    snapshotted_at: Instant,
+}
+
+/// The events of the actors, along with their sequence numbers, by id.
+type Events<E> = Arc<Mutex<FxHashMap<String, Vec<(u64, E)>>>>;
+
+/// An [`EventJournal`] keeping the events in memory.
+///
+/// Cloning a `MemoryJournal` returns a new handle to the same journal.
+pub struct MemoryJournal<E> {
+    events: Events<E>,
+}
+
+/// A [`SnapshotStore`] keeping the snapshots in memory.
+///
+/// Cloning a `MemorySnapshotStore` returns a new handle to the same
+/// store.
+pub struct MemorySnapshotStore<S> {
+    snapshots: Arc<Mutex<FxHashMap<String, (u64, S)>>>,
+}
+
+impl SnapshotPolicy {
+    /// Creates a new policy saving no snapshot.
+    pub fn new() -> Self {
+        SnapshotPolicy::default()
+    }
+
+    /// Makes a snapshot be saved every `events` events.
+    pub fn with_every_events(mut self, events: u64) -> Self {
+        self.every_events = Some(events.max(1));
+        self
+    }
+
+    /// Makes a snapshot be saved once an event is persisted at
+    /// least `interval` after the last snapshot was saved.
+    pub fn with_every(mut self, interval: Duration) -> Self {
+        self.every = Some(interval);
+        self
+    }
+
+    /// Makes a snapshot be saved once the events persisted since
+    /// the last snapshot take at least `bytes` bytes (see
+    /// [`Persistent::event_size`]).
+    pub fn with_size_threshold(mut self, bytes: usize) -> Self {
+        self.size_threshold = Some(bytes);
+        self
+    }
+
+    // Returns whether a snapshot should be saved after `events`
+    // events of `bytes` bytes were persisted in `elapsed` since
+    // the last one.
+    fn should_snapshot(&self, events: u64, bytes: usize, elapsed: Duration) -> bool {
+        self.every_events.is_some_and(|every| events >= every)
+            || self
+                .size_threshold
+                .is_some_and(|threshold| bytes >= threshold)
+            || self.every.is_some_and(|every| elapsed >= every)
+    }
+}
+
+impl<A: Persistent> PersistentState<A> {
+    /// Recovers the state of the persistent actor with the given id,
+    /// by applying to its last snapshot (or to `initial` if it has
+    /// none) the events persisted since.
+    ///
+    /// This method returns the recovered state if it succeeded, or
+    /// `Err(())` if the snapshot or the events couldn't be loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The identifier of the actor in the journal and the
+    ///   snapshot store.
+    /// * `initial` - The state of the actor before it persisted any
+    ///   event.
+    /// * `journal` - Where the events are persisted.
+    /// * `snapshots` - Where the snapshots are saved.
+    /// * `policy` - Defines when the snapshots are saved.
+    #[allow(clippy::result_unit_err)]
+    pub fn recover<J, S>(
+        id: impl Into<String>,
+        initial: A,
+        journal: J,
+        snapshots: S,
+        policy: SnapshotPolicy,
+    ) -> Result<Self, ()>
+    where
+        J: EventJournal<A::Event>,
+        S: SnapshotStore<A>,
+    {
+        let id = id.into();
+        debug!("PersistentState({}): Recovering.", id);
+        let (seq, mut state) = match snapshots.load(&id)? {
+            Some((seq, state)) => {
+                trace!("PersistentState({}): Loaded snapshot #{}.", id, seq);
+                (seq, state)
+            }
+            None => (0, initial),
+        };
+
+        let mut last = seq;
+        let mut bytes = 0;
+        let events = journal.events(&id, seq)?;
+        for (seq, event) in events.iter() {
+            state.apply(event);
+            bytes += A::event_size(event);
+            last = *seq;
+        }
+        debug!(
+            "PersistentState({}): Recovered with {} events since the last snapshot.",
+            id,
+            events.len()
+        );
+
+        Ok(PersistentState {
+            id,
+            state,
+            seq: last,
+            journal: Arc::new(journal),
+            snapshots: Arc::new(snapshots),
+            policy,
+            events: events.len() as u64,
+            bytes,
+            snapshotted_at: Instant::now(),
+        })
+    }
+
+    /// Returns the identifier of the actor.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the current state.
+    pub fn state(&self) -> &A {
+        &self.state
+    }
+
+    /// Returns the sequence number of the last persisted event.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Appends `event` to the journal and applies it to the state,
+    /// saving a snapshot afterwards if the policy says so.
+    ///
+    /// This method returns `Ok(())` if the event was persisted (even
+    /// if the snapshot couldn't be saved), or `Err(())` otherwise, in
+    /// which case the state isn't changed.
+    #[allow(clippy::result_unit_err)]
+    pub fn persist(&mut self, event: A::Event) -> Result<(), ()> {
+        let seq = self.seq + 1;
+        trace!("PersistentState({}): Persisting event #{}.", self.id, seq);
+        self.journal.append(&self.id, seq, &event)?;
+
+        self.state.apply(&event);
+        self.seq = seq;
+        self.events += 1;
+        self.bytes += A::event_size(&event);
+
+        let elapsed = self.snapshotted_at.elapsed();
+        if self
+            .policy
+            .should_snapshot(self.events, self.bytes, elapsed)
+            && self.snapshot().is_err()
+        {
+            warn!(
+                "PersistentState({}): Couldn't save snapshot #{}.",
+                self.id, self.seq
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Saves a snapshot of the current state, regardless of the
+    /// policy.
+    ///
+    /// This method returns `Ok(())` if it succeeded, or `Err(())`
+    /// otherwise.
+    #[allow(clippy::result_unit_err)]
+    pub fn snapshot(&mut self) -> Result<(), ()> {
+        debug!(
+            "PersistentState({}): Saving snapshot #{}.",
+            self.id, self.seq
+        );
+        self.snapshots.save(&self.id, self.seq, &self.state)?;
+        self.events = 0;
+        self.bytes = 0;
+        self.snapshotted_at = Instant::now();
+
+        Ok(())
+    }
+}
+
+impl<E: Send + Sync + 'static> MemoryJournal<E> {
+    /// Creates a new empty journal.
+    pub fn new() -> Self {
+        MemoryJournal::default()
+    }
+}
+
+impl<E: Clone + Send + Sync + 'static> EventJournal<E> for MemoryJournal<E> {
+    fn append(&self, id: &str, seq: u64, event: &E) -> Result<(), ()> {
+        // FIXME: panics?
+        let mut events = self.events.lock().unwrap();
+        events
+            .entry(id.to_string())
+            .or_default()
+            .push((seq, event.clone()));
+        Ok(())
+    }
+
+    fn events(&self, id: &str, after: u64) -> Result<Vec<(u64, E)>, ()> {
+        // FIXME: panics?
+        let events = self.events.lock().unwrap();
+        let events = events
+            .get(id)
+            .map(|events| {
+                events
+                    .iter()
+                    .filter(|(seq, _)| *seq > after)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(events)
+    }
+}
+
+impl<S: Send + Sync + 'static> MemorySnapshotStore<S> {
+    /// Creates a new empty store.
+    pub fn new() -> Self {
+        MemorySnapshotStore::default()
+    }
+}
+
+impl<S: Clone + Send + Sync + 'static> SnapshotStore<S> for MemorySnapshotStore<S> {
+    fn save(&self, id: &str, seq: u64, state: &S) -> Result<(), ()> {
+        // FIXME: panics?
+        let mut snapshots = self.snapshots.lock().unwrap();
+        snapshots.insert(id.to_string(), (seq, state.clone()));
+        Ok(())
+    }
+
+    fn load(&self, id: &str) -> Result<Option<(u64, S)>, ()> {
+        // FIXME: panics?
+        let snapshots = self.snapshots.lock().unwrap();
+        Ok(snapshots.get(id).cloned())
+    }
+}
+
+impl<E> Default for MemoryJournal<E> {
+    fn default() -> Self {
+        MemoryJournal {
+            events: Arc::new(Mutex::new(FxHashMap::default())),
+        }
+    }
+}
+
+impl<E> Clone for MemoryJournal<E> {
+    fn clone(&self) -> Self {
+        MemoryJournal {
+            events: self.events.clone(),
+        }
+    }
+}
+
+impl<E> Debug for MemoryJournal<E> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("MemoryJournal").finish()
+    }
+}
+
+impl<S> Default for MemorySnapshotStore<S> {
+    fn default() -> Self {
+        MemorySnapshotStore {
+            snapshots: Arc::new(Mutex::new(FxHashMap::default())),
+        }
+    }
+}
+
+impl<S> Clone for MemorySnapshotStore<S> {
+    fn clone(&self) -> Self {
+        MemorySnapshotStore {
+            snapshots: self.snapshots.clone(),
+        }
+    }
+}
+
+impl<S> Debug for MemorySnapshotStore<S> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("MemorySnapshotStore").finish()
+    }
+}
+
+impl<A: Persistent + Debug> Debug for PersistentState<A> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("PersistentState")
+            .field("id", &self.id)
+            .field("state", &self.state)
+            .field("seq", &self.seq)
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Default, PartialEq)]
+    struct Counter(u64);
+
+    impl Persistent for Counter {
+        type Event = u64;
+
+        fn apply(&mut self, event: &u64) {
+            self.0 += event;
+        }
+    }
+
+    fn recover(
+        journal: &MemoryJournal<u64>,
+        snapshots: &MemorySnapshotStore<Counter>,
+        policy: SnapshotPolicy,
+    ) -> PersistentState<Counter> {
+        PersistentState::recover(
+            "counter",
+            Counter::default(),
+            journal.clone(),
+            snapshots.clone(),
+            policy,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn policy_triggers_on_any_bound() {
+        let policy = SnapshotPolicy::new();
+        assert!(!policy.should_snapshot(u64::MAX, usize::MAX, Duration::from_secs(3600)));
+
+        let policy = SnapshotPolicy::new()
+            .with_every_events(10)
+            .with_size_threshold(100)
+            .with_every(Duration::from_secs(1));
+        assert!(!policy.should_snapshot(9, 99, Duration::from_millis(999)));
+        assert!(policy.should_snapshot(10, 0, Duration::from_secs(0)));
+        assert!(policy.should_snapshot(0, 100, Duration::from_secs(0)));
+        assert!(policy.should_snapshot(0, 0, Duration::from_secs(1)));
+
+        // Snapshotting after every zero event means after every event.
+        let policy = SnapshotPolicy::new().with_every_events(0);
+        assert!(!policy.should_snapshot(0, 0, Duration::from_secs(0)));
+        assert!(policy.should_snapshot(1, 0, Duration::from_secs(0)));
+    }
+
+    #[test]
+    fn recovers_from_the_journal() {
+        let journal = MemoryJournal::new();
+        let snapshots = MemorySnapshotStore::new();
+
+        let mut state = recover(&journal, &snapshots, SnapshotPolicy::new());
+        for event in 1..=4 {
+            state.persist(event).unwrap();
+        }
+        assert_eq!(state.state(), &Counter(10));
+        assert_eq!(state.seq(), 4);
+        assert!(snapshots.load("counter").unwrap().is_none());
+
+        let state = recover(&journal, &snapshots, SnapshotPolicy::new());
+        assert_eq!(state.state(), &Counter(10));
+        assert_eq!(state.seq(), 4);
+    }
+
+    #[test]
+    fn recovers_from_the_last_snapshot() {
+        let journal = MemoryJournal::new();
+        let snapshots = MemorySnapshotStore::new();
+        let policy = SnapshotPolicy::new().with_every_events(3);
+
+        let mut state = recover(&journal, &snapshots, policy.clone());
+        for event in 1..=4 {
+            state.persist(event).unwrap();
+        }
+        assert_eq!(snapshots.load("counter").unwrap(), Some((3, Counter(6))));
+
+        // Only the events after the snapshot are applied again.
+        journal.append("counter", 2, &100).unwrap();
+        let state = recover(&journal, &snapshots, policy);
+        assert_eq!(state.state(), &Counter(10));
+        assert_eq!(state.seq(), 4);
+    }
+}
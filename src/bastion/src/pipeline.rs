@@ -0,0 +1,635 @@
+//!
+//! Pipelines composing children groups into stages.
+//!
+//! A [`Pipeline`] starts with a source stage emitting the items of a
+//! stream, goes through any number of stages transforming them, and
+//! ends with a sink stage consuming them. Every stage is a children
+//! group receiving the items of the previous stage and sending its own
+//! to the next one, only pushing as many items as the next stage
+//! advertised it can take (see the [`demand`] module).
+//!
+//! All the stages of a pipeline are supervised by the same supervisor,
+//! which restarts them according to the pipeline's supervision
+//! strategy.
+//!
+//! Stages can also aggregate the items of the previous stage over a
+//! [`Window`] (of a given number of items or a given duration) before
+//! sending the result to the next one, with [`Pipeline::window`].
+//!
+//! [`demand`]: crate::demand
+use crate::bastion::Bastion;
+use crate::child_ref::ChildRef;
+use crate::context::{BastionContext, BastionId};
+use crate::demand::Demand;
+use crate::errors::ReceiveError;
+use crate::message::Message;
+use crate::supervisor::{SupervisionStrategy, Supervisor, SupervisorRef};
+use futures::prelude::*;
+use pin_utils::pin_mut;
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Formatter};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, trace, warn};
+
+/// The default number of items every element of a stage can take
+/// at a time.
+const DEFAULT_WINDOW: usize = 16;
+
+/// Creates stages given the supervisor of a pipeline and the inlet
+/// of the stage they emit their items to.
+type Build<T> = Box<dyn FnOnce(&Supervisor, &Settings, Inlet<T>)>;
+
+/// A pipeline of stages whose last one emits items of type `T`.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::pipeline::Pipeline;
+/// # use futures::stream;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let supervisor_ref = Pipeline::source(|| stream::iter(0..1_000u64))
+///     .map(|n| n * n)
+///     .map(|n| format!("{}", n))
+///     .with_redundancy(4)
+///     .sink(|square| println!("{}", square))
+///     .expect("Couldn't create the pipeline.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Pipeline<T> {
+    // Creates the stages built so far, given the supervisor of the
+    // pipeline and the inlet of the stage they emit their items to.
+    build: Build<T>,
+    settings: Settings,
+}
+
+#[derive(Debug, Clone, Copy)]
+/// The items a stage aggregates before sending the result to the
+/// next stage.
+///
+/// Windows are either tumbling, when every item is part of a single
+/// window, or sliding, when a new window starts every `step` (items
+/// or duration) and overlaps with the previous ones.
+pub enum Window {
+    /// Windows of `size` items, starting every `step` items.
+    Count {
+        /// The number of items in a window.
+        size: usize,
+        /// The number of items between the start of two windows.
+        step: usize,
+    },
+    /// Windows of the items received during `size`, ending
+    /// every `step`.
+    Time {
+        /// The duration of a window.
+        size: Duration,
+        /// The duration between the end of two windows.
+        step: Duration,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Settings {
+    window: usize,
+    redundancy: usize,
+    strategy: SupervisionStrategy,
+}
+
+/// The elements of a stage, to which the previous stage sends its
+/// items.
+struct Inlet<T> {
+    inner: Arc<InletInner>,
+    _marker: PhantomData<fn(T)>,
+}
+
+struct InletInner {
+    elems: Mutex<Vec<ChildRef>>,
+    next: AtomicUsize,
+    demand: Demand,
+}
+
+/// An element of a stage, receiving the items of type `T` of the
+/// previous stage and emitting items of type `U` to the next one.
+pub(crate) struct Stage<T, U> {
+    ctx: BastionContext,
+    out: Option<Inlet<U>>,
+    _marker: PhantomData<fn(T)>,
+}
+
+impl<T: Message> Pipeline<T> {
+    /// Creates a new pipeline whose source stage emits the items of
+    /// the stream returned by `init`.
+    ///
+    /// The source stage has a single element, which calls `init`
+    /// again every time it is restarted.
+    pub fn source<I, S>(init: I) -> Self
+    where
+        I: Fn() -> S + Send + Sync + 'static,
+        S: Stream<Item = T> + Send + 'static,
+    {
+        let init = Arc::new(init);
+        let build = move |sp: &Supervisor, settings: &Settings, out: Inlet<T>| {
+            let settings = Settings {
+                redundancy: 1,
+                ..settings.clone()
+            };
+
+            launch_stage(
+                sp,
+                &settings,
+                &Inlet::<()>::new(),
+                Some(out),
+                move |stage| {
+                    let stream = init();
+                    async move {
+                        pin_mut!(stream);
+                        while let Some(item) = stream.next().await {
+                            stage.send(item).await;
+                        }
+
+                        Ok(())
+                    }
+                },
+            );
+        };
+
+        Pipeline {
+            build: Box::new(build),
+            settings: Settings {
+                window: DEFAULT_WINDOW,
+                redundancy: 1,
+                strategy: SupervisionStrategy::default(),
+            },
+        }
+    }
+
+    /// Adds a stage sending the result of `f` applied to every
+    /// item of the previous stage to the next one.
+    pub fn map<U, F>(self, f: F) -> Pipeline<U>
+    where
+        U: Message,
+        F: Fn(T) -> U + Send + Sync + 'static,
+    {
+        let f = Arc::new(f);
+        self.stage(move |stage| {
+            let f = f.clone();
+            async move {
+                loop {
+                    let item = stage.recv().await?;
+                    stage.send(f(item)).await;
+                }
+            }
+        })
+    }
+
+    /// Sets the number of items every element of the stages can
+    /// take at a time (`16` by default).
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.settings.window = window;
+        self
+    }
+
+    /// Sets the number of elements of every stage but the source
+    /// (`1` by default).
+    pub fn with_redundancy(mut self, redundancy: usize) -> Self {
+        self.settings.redundancy = redundancy;
+        self
+    }
+
+    /// Sets the strategy the supervisor of the pipeline uses when
+    /// one of its stages dies (`OneForOne` by default).
+    pub fn with_strategy(mut self, strategy: SupervisionStrategy) -> Self {
+        self.settings.strategy = strategy;
+        self
+    }
+
+    /// Ends the pipeline with a stage calling `f` with every item of
+    /// the previous stage, and creates all of its stages.
+    ///
+    /// This method returns a [`SupervisorRef`] referencing the
+    /// supervisor of the pipeline if it succeeded, or `Err(())`
+    /// otherwise.
+    #[allow(clippy::result_unit_err)]
+    pub fn sink<F>(self, f: F) -> Result<SupervisorRef, ()>
+    where
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        let Pipeline { build, settings } = self;
+        let f = Arc::new(f);
+
+        Bastion::supervisor(move |sp| {
+            let sp = sp.with_strategy(settings.strategy.clone());
+            debug!("Pipeline: Creating stages under Supervisor({}).", sp.id());
+
+            let inlet = Inlet::new();
+            launch_stage::<T, (), _, _>(&sp, &settings, &inlet, None, move |stage| {
+                let f = f.clone();
+                async move {
+                    loop {
+                        f(stage.recv().await?);
+                    }
+                }
+            });
+            build(&sp, &settings, inlet);
+
+            sp
+        })
+    }
+
+    /// Adds a stage sending the result of `aggregate` applied to the
+    /// items of the previous stage in every `window` to the next one.
+    ///
+    /// The stage has a single element, so that its windows hold all
+    /// the items of the previous stage. Time windows which didn't
+    /// receive any item are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::pipeline::{Pipeline, Window};
+    /// # use futures::stream;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Pipeline::source(|| stream::iter(0..1_000u64))
+    ///     // The sum of every 10 items...
+    ///     .window(Window::tumbling_count(10), |items| items.iter().sum::<u64>())
+    ///     // ...and the maximum of those sums over the last second,
+    ///     // every 100ms.
+    ///     .window(
+    ///         Window::sliding_time(Duration::from_secs(1), Duration::from_millis(100)),
+    ///         |sums| sums.iter().max().copied().unwrap_or_default(),
+    ///     )
+    ///     .sink(|max| println!("{}", max))
+    ///     .expect("Couldn't create the pipeline.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn window<U, F>(self, window: Window, aggregate: F) -> Pipeline<U>
+    where
+        U: Message,
+        F: Fn(&[T]) -> U + Send + Sync + 'static,
+    {
+        let aggregate = Arc::new(aggregate);
+        self.stage_with(Some(1), move |stage| {
+            let aggregate = aggregate.clone();
+            async move {
+                match window {
+                    Window::Count { size, step } => {
+                        aggregate_count(&stage, size, step, &*aggregate).await
+                    }
+                    Window::Time { size, step } => {
+                        aggregate_time(&stage, size, step, &*aggregate).await
+                    }
+                }
+            }
+        })
+    }
+
+    /// Adds a stage whose elements run the future returned by `init`.
+    pub(crate) fn stage<U, I, F>(self, init: I) -> Pipeline<U>
+    where
+        U: Message,
+        I: Fn(Stage<T, U>) -> F + Send + Sync + 'static,
+        F: Future<Output = Result<(), ()>> + Send + 'static,
+    {
+        self.stage_with(None, init)
+    }
+
+    /// Adds a stage whose elements run the future returned by `init`,
+    /// overriding the redundancy of the pipeline if `redundancy` is
+    /// set.
+    fn stage_with<U, I, F>(self, redundancy: Option<usize>, init: I) -> Pipeline<U>
+    where
+        U: Message,
+        I: Fn(Stage<T, U>) -> F + Send + Sync + 'static,
+        F: Future<Output = Result<(), ()>> + Send + 'static,
+    {
+        let Pipeline {
+            build: upstream,
+            settings,
+        } = self;
+        let build = move |sp: &Supervisor, settings: &Settings, out: Inlet<U>| {
+            let inlet = Inlet::new();
+            let stage_settings = Settings {
+                redundancy: redundancy.unwrap_or(settings.redundancy),
+                ..settings.clone()
+            };
+
+            launch_stage(sp, &stage_settings, &inlet, Some(out), init);
+            upstream(sp, settings, inlet);
+        };
+
+        Pipeline {
+            build: Box::new(build),
+            settings,
+        }
+    }
+}
+
+/// Sends the result of `aggregate` applied to every window of `size`
+/// items received by `stage`, starting every `step` items.
+async fn aggregate_count<T, U, F>(
+    stage: &Stage<T, U>,
+    size: usize,
+    step: usize,
+    aggregate: &F,
+) -> Result<(), ()>
+where
+    T: Message,
+    U: Message,
+    F: Fn(&[T]) -> U,
+{
+    let mut items = VecDeque::with_capacity(size);
+    // The number of items to ignore before the next window starts,
+    // when windows don't overlap.
+    let mut skip = 0;
+
+    loop {
+        let item = stage.recv().await?;
+        if skip > 0 {
+            skip -= 1;
+            continue;
+        }
+
+        items.push_back(item);
+        if items.len() == size {
+            stage.send(aggregate(items.make_contiguous())).await;
+
+            let dropped = step.min(items.len());
+            items.drain(..dropped);
+            skip = step - dropped;
+        }
+    }
+}
+
+/// Sends the result of `aggregate` applied to the items received by
+/// `stage` during every window of `size`, ending every `step`.
+async fn aggregate_time<T, U, F>(
+    stage: &Stage<T, U>,
+    size: Duration,
+    step: Duration,
+    aggregate: &F,
+) -> Result<(), ()>
+where
+    T: Message,
+    U: Message,
+    F: Fn(&[T]) -> U,
+{
+    // When the items were received, in the same order as `items`.
+    let mut received = VecDeque::new();
+    let mut items = VecDeque::new();
+    let mut end = Instant::now() + step;
+
+    loop {
+        if let Some(item) = stage.recv_until(end).await? {
+            received.push_back(Instant::now());
+            items.push_back(item);
+            continue;
+        }
+
+        // Forgets the items which were received before the window.
+        while received.front().is_some_and(|at| *at + size <= end) {
+            received.pop_front();
+            items.pop_front();
+        }
+
+        if !items.is_empty() {
+            stage.send(aggregate(items.make_contiguous())).await;
+        }
+
+        end += step;
+    }
+}
+
+/// Creates the children group of a stage under `sp`, whose elements
+/// receive their items through `inlet` and send theirs to `out`.
+fn launch_stage<T, U, I, F>(
+    sp: &Supervisor,
+    settings: &Settings,
+    inlet: &Inlet<T>,
+    out: Option<Inlet<U>>,
+    init: I,
+) where
+    T: Message,
+    U: Message,
+    I: Fn(Stage<T, U>) -> F + Send + Sync + 'static,
+    F: Future<Output = Result<(), ()>> + Send + 'static,
+{
+    let registry = inlet.clone();
+    let children_ref = sp.children_ref(|children| {
+        children
+            .with_redundancy(settings.redundancy)
+            .with_demand(inlet.inner.demand.clone(), settings.window)
+            .with_exec(move |ctx: BastionContext| {
+                // The elements are registered every time they are
+                // (re)started, since their previous references are
+                // useless once they faulted.
+                registry.register(ctx.current().clone());
+                init(Stage {
+                    ctx,
+                    out: out.clone(),
+                    _marker: PhantomData,
+                })
+            })
+    });
+
+    trace!("Pipeline: Created stage Children({}).", children_ref.id());
+}
+
+impl<T: Message> Inlet<T> {
+    fn new() -> Self {
+        let inner = Arc::new(InletInner {
+            elems: Mutex::new(Vec::new()),
+            next: AtomicUsize::new(0),
+            demand: Demand::new(),
+        });
+
+        Inlet {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+
+    fn register(&self, child_ref: ChildRef) {
+        // FIXME: panics?
+        let mut elems = self.inner.elems.lock().unwrap();
+        match elems.iter_mut().find(|elem| elem.id() == child_ref.id()) {
+            Some(elem) => *elem = child_ref,
+            None => elems.push(child_ref),
+        }
+    }
+
+    fn remove(&self, id: &BastionId) {
+        // FIXME: panics?
+        self.inner
+            .elems
+            .lock()
+            .unwrap()
+            .retain(|elem| elem.id() != id);
+    }
+
+    /// Sends `item` to one of the elements of the stage once it
+    /// can take it, returning it back if the stage has no element.
+    async fn send(&self, mut item: T) -> Result<(), T> {
+        self.inner.demand.acquire().await;
+
+        loop {
+            let child_ref = {
+                // FIXME: panics?
+                let elems = self.inner.elems.lock().unwrap();
+                if elems.is_empty() {
+                    // The item isn't going to be taken.
+                    self.inner.demand.request(1);
+                    return Err(item);
+                }
+
+                let next = self.inner.next.fetch_add(1, Ordering::Relaxed);
+                elems[next % elems.len()].clone()
+            };
+
+            match child_ref.tell_anonymously(item) {
+                Ok(()) => return Ok(()),
+                // The element stopped or faulted, and will register
+                // itself again if it is restarted.
+                Err(msg) => {
+                    item = msg;
+                    self.remove(child_ref.id());
+                }
+            }
+        }
+    }
+}
+
+impl<T: Message, U: Message> Stage<T, U> {
+    /// Waits for the next item of the previous stage, ignoring the
+    /// messages of other types.
+    pub(crate) async fn recv(&self) -> Result<T, ()> {
+        loop {
+            let (msg, _) = self.ctx.recv().await?.extract();
+            if let Ok(item) = msg.downcast() {
+                return Ok(item);
+            }
+        }
+    }
+
+    /// Waits for the next item of the previous stage until `deadline`,
+    /// returning `None` if none was received before it.
+    pub(crate) async fn recv_until(&self, deadline: Instant) -> Result<Option<T>, ()> {
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+
+            match self.ctx.try_recv_timeout(deadline - now).await {
+                Ok(msg) => {
+                    let (msg, _) = msg.extract();
+                    if let Ok(item) = msg.downcast() {
+                        return Ok(Some(item));
+                    }
+                }
+                Err(ReceiveError::Timeout(_)) => return Ok(None),
+                Err(ReceiveError::Other) => return Err(()),
+            }
+        }
+    }
+
+    /// Sends `item` to the next stage, once it can take it.
+    pub(crate) async fn send(&self, item: U) {
+        if let Some(out) = &self.out {
+            if let Err(item) = out.send(item).await {
+                warn!(
+                    "Pipeline: Dropping {:?}, the next stage has no element.",
+                    item
+                );
+            }
+        }
+    }
+}
+
+impl Window {
+    /// Creates windows of `size` items which don't overlap.
+    pub fn tumbling_count(size: usize) -> Self {
+        Window::sliding_count(size, size)
+    }
+
+    /// Creates windows of `size` items, a new one starting every
+    /// `step` items.
+    pub fn sliding_count(size: usize, step: usize) -> Self {
+        Window::Count {
+            size: size.max(1),
+            step: step.max(1),
+        }
+    }
+
+    /// Creates windows of the items received during `size` which
+    /// don't overlap.
+    pub fn tumbling_time(size: Duration) -> Self {
+        Window::sliding_time(size, size)
+    }
+
+    /// Creates windows of the items received during `size`, one
+    /// ending every `step`.
+    pub fn sliding_time(size: Duration, step: Duration) -> Self {
+        Window::Time {
+            size,
+            step: step.max(Duration::from_millis(1)),
+        }
+    }
+}
+
+impl<T> Clone for Inlet<T> {
+    fn clone(&self) -> Self {
+        Inlet {
+            inner: self.inner.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Debug for Pipeline<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Pipeline")
+            .field("settings", &self.settings)
+            .finish()
+    }
+}
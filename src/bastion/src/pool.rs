@@ -0,0 +1,171 @@
+//!
+//! A typed pool of workers handling requests.
+//!
+//! A [`Pool`] is created with [`Bastion::pool`], which starts a
+//! children group whose elements all run the same handler, and is
+//! then used to submit requests to them, in a round-robin fashion,
+//! without having to match the messages they receive nor to
+//! downcast their answers.
+//!
+//! The elements of a pool have a bounded mailbox, and are restarted
+//! after a delay if they crash (eg. because the handler panicked).
+//!
+//! [`Bastion::pool`]: crate::Bastion::pool
+use crate::children::Children;
+use crate::children_ref::ChildrenRef;
+use crate::context::BastionContext;
+use crate::errors::AskError;
+use crate::message::Message;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::debug;
+
+/// The number of requests which can be waiting in the mailbox of
+/// every element of a pool.
+const MAILBOX_CAPACITY: usize = 64;
+/// The time after which a crashed element of a pool is restarted.
+const RESTART_DELAY: Duration = Duration::from_millis(100);
+
+/// A cloneable handle to a pool of workers handling requests of
+/// type `Req` and answering them with responses of type `Resp`.
+///
+/// Cloning a `Pool` returns a new handle to the same workers.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::pool::Pool;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let pool: Pool<u64, u64> = Bastion::pool(4, |n: u64| async move { n * 2 })
+///     .expect("Couldn't create the pool.");
+///
+/// # Bastion::start();
+/// # run!(async move {
+/// match pool.submit(21).await {
+///     Ok(doubled) => assert_eq!(doubled, 42),
+///     Err(err) => {
+///         // The request wasn't handled...
+///     }
+/// }
+/// # });
+/// #
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Pool<Req, Resp> {
+    children: ChildrenRef,
+    // The index of the next element to submit a request to.
+    next: Arc<AtomicUsize>,
+    _types: PhantomData<fn(Req) -> Resp>,
+}
+
+impl<Req, Resp> Pool<Req, Resp>
+where
+    Req: Message,
+    Resp: Message,
+{
+    pub(crate) fn new(children: ChildrenRef) -> Self {
+        Pool {
+            children,
+            next: Arc::new(AtomicUsize::new(0)),
+            _types: PhantomData,
+        }
+    }
+
+    // Configures the children group running the pool's workers.
+    pub(crate) fn init<H, F>(children: Children, size: usize, handler: H) -> Children
+    where
+        H: Fn(Req) -> F + Send + Sync + 'static,
+        F: Future<Output = Resp> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+
+        children
+            .with_redundancy(size.max(1))
+            .with_mailbox_capacity(MAILBOX_CAPACITY)
+            .with_restart_delay(RESTART_DELAY)
+            .with_exec(move |ctx: BastionContext| {
+                let handler = handler.clone();
+
+                async move {
+                    loop {
+                        let (mut msg, _) = ctx.recv().await?.extract();
+                        let sender = msg.take_sender();
+                        let req = match msg.downcast::<Req>() {
+                            Ok(req) => req,
+                            Err(msg) => {
+                                debug!("Pool: Dropping message: {:?}", msg);
+                                continue;
+                            }
+                        };
+
+                        let resp = handler(req).await;
+                        if let Some(sender) = sender {
+                            sender.reply(resp).ok();
+                        }
+                    }
+                }
+            })
+    }
+
+    /// Returns the children group running the pool's workers.
+    pub fn children(&self) -> &ChildrenRef {
+        &self.children
+    }
+
+    /// Submits `req` to the next worker of the pool able to receive
+    /// it, and waits for its response.
+    ///
+    /// This method returns the response if it succeeded, or an
+    /// [`AskError`] otherwise: [`AskError::Unavailable`] if no worker
+    /// could receive the request or if the worker handling it crashed
+    /// before answering.
+    pub async fn submit(&self, req: Req) -> Result<Resp, AskError> {
+        let (msg, _) = self
+            .children
+            .ask_round_robin(&self.next, req, None)
+            .await?
+            .extract();
+        msg.downcast::<Resp>()
+            .map_err(|_| AskError::UnexpectedReply)
+    }
+}
+
+impl<Req, Resp> Clone for Pool<Req, Resp> {
+    fn clone(&self) -> Self {
+        Pool {
+            children: self.children.clone(),
+            next: self.next.clone(),
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<Req, Resp> Debug for Pool<Req, Resp> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Pool")
+            .field("children", &self.children)
+            .field("next", &self.next)
+            .finish()
+    }
+}
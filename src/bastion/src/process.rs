@@ -0,0 +1,211 @@
+//!
+//! Children groups whose elements supervise external OS processes.
+//!
+//! A [`Process`] describes a command to run (eg. a sidecar binary).
+//! Given to a children group with [`Children::with_process`], each
+//! element of the group spawns the process, pipes its standard output
+//! and error into `tracing`, and waits for it to exit. When it exits
+//! successfully, the element stops; otherwise (or if the process
+//! couldn't be spawned) the element faults, and is restarted along
+//! with the process according to the restart strategy of its
+//! supervisor. The process is killed when its element is stopped or
+//! killed.
+//!
+//! This module is available only with the `process` feature flag.
+//!
+//! [`Children::with_process`]: crate::children::Children::with_process
+use crate::context::BastionContext;
+use crate::executor::blocking;
+use futures_timer::Delay;
+use std::ffi::{OsStr, OsString};
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+/// How often the elements check whether their process exited.
+const EXIT_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone)]
+/// The command an element of a children group runs as an external
+/// OS process.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::process::Process;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let proxy = Process::new("envoy")
+///     .with_args(&["--config-path", "envoy.yaml"])
+///     .with_env("ENVOY_LOG_LEVEL", "info");
+///
+/// Bastion::supervisor(|sp| {
+///     sp.with_strategy(SupervisionStrategy::OneForOne)
+///         .children(|children| children.with_process(proxy))
+/// }).expect("Couldn't create the supervisor.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Process {
+    program: OsString,
+    args: Vec<OsString>,
+    envs: Vec<(OsString, OsString)>,
+    current_dir: Option<PathBuf>,
+}
+
+// The running process of an element, killed when the element stops
+// (and drops it) before the process exited.
+struct Running(Child);
+
+impl Process {
+    /// Creates the description of a process running `program`,
+    /// without any argument.
+    pub fn new(program: impl AsRef<OsStr>) -> Self {
+        Process {
+            program: program.as_ref().to_owned(),
+            args: Vec::new(),
+            envs: Vec::new(),
+            current_dir: None,
+        }
+    }
+
+    /// Adds an argument to pass to the program.
+    pub fn with_arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.args.push(arg.as_ref().to_owned());
+        self
+    }
+
+    /// Adds arguments to pass to the program.
+    pub fn with_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_owned()));
+        self
+    }
+
+    /// Sets an environment variable of the process, in addition to
+    /// the ones it inherits.
+    pub fn with_env(mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Self {
+        self.envs
+            .push((key.as_ref().to_owned(), value.as_ref().to_owned()));
+        self
+    }
+
+    /// Sets the working directory of the process.
+    pub fn with_current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    /// Returns the program the process runs.
+    pub fn program(&self) -> &OsStr {
+        &self.program
+    }
+
+    /// Spawns the process and waits for it to exit, returning `Ok(())`
+    /// if it exited successfully or `Err(())` otherwise.
+    pub(crate) async fn run(&self, ctx: BastionContext) -> Result<(), ()> {
+        let id = ctx.current().id().clone();
+        let mut child = self.command().spawn().map_err(|err| {
+            error!(
+                "Child({}): Couldn't spawn the process {:?}: {}",
+                id, self.program, err
+            );
+        })?;
+
+        let pid = child.id();
+        debug!(
+            "Child({}): Spawned the process {:?} ({}).",
+            id, self.program, pid
+        );
+        if let Some(stdout) = child.stdout.take() {
+            pipe(stdout, pid, false);
+        }
+        if let Some(stderr) = child.stderr.take() {
+            pipe(stderr, pid, true);
+        }
+
+        let mut running = Running(child);
+        loop {
+            match running.0.try_wait() {
+                Ok(Some(status)) if status.success() => {
+                    debug!("Child({}): The process ({}) exited.", id, pid);
+                    return Ok(());
+                }
+                Ok(Some(status)) => {
+                    warn!("Child({}): The process ({}) failed: {}", id, pid, status);
+                    return Err(());
+                }
+                Ok(None) => Delay::new(EXIT_CHECK_INTERVAL).await,
+                Err(err) => {
+                    error!(
+                        "Child({}): Couldn't check whether the process ({}) exited: {}",
+                        id, pid, err
+                    );
+                    return Err(());
+                }
+            }
+        }
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .envs(self.envs.iter().map(|(key, value)| (key, value)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(dir) = &self.current_dir {
+            command.current_dir(dir);
+        }
+
+        command
+    }
+}
+
+// Logs the lines written by the process with the given pid to one of
+// its outputs, until it closes it.
+fn pipe<R: Read + Send + 'static>(output: R, pid: u32, stderr: bool) {
+    blocking(async move {
+        for line in BufReader::new(output).lines() {
+            match line {
+                Ok(line) if stderr => warn!("Process({}): {}", pid, line),
+                Ok(line) => info!("Process({}): {}", pid, line),
+                Err(_) => break,
+            }
+        }
+    });
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        if let Ok(None) = self.0.try_wait() {
+            debug!("Process({}): Killing.", self.0.id());
+            self.0.kill().ok();
+            self.0.wait().ok();
+        }
+    }
+}
@@ -0,0 +1,284 @@
+//!
+//! Soft resource quotas for children groups, so that a misbehaving
+//! group can't exhaust the whole process.
+//!
+//! A [`Quota`] attached to a children group with
+//! [`Children::with_quota`] bounds the number of questions its elements
+//! were asked and didn't answer yet, the number of bytes waiting in
+//! their mailboxes and the number of helper actors the group spawns.
+//! What happens when one of those bounds is reached is decided by the
+//! [`QuotaAction`] of the quota.
+//!
+//! The size of a message is the size of its value, or the length of its
+//! bytes for a [`Payload`].
+//!
+//! [`Children::with_quota`]: crate::children::Children::with_quota
+//! [`Payload`]: crate::payload::Payload
+use crate::context::BastionId;
+use crate::message::Msg;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tracing::warn;
+
+#[derive(Debug, Clone, Default)]
+/// The bounds of the resources a children group can use.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::quota::{Quota, QuotaAction};
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let quota = Quota::new()
+///     // Up to 1000 questions waiting for an answer...
+///     .with_max_in_flight_asks(1000)
+///     // ...and 16MiB of messages in the mailboxes, dropping the
+///     // oldest messages to make room for the new ones.
+///     .with_max_buffered_bytes(16 * 1024 * 1024)
+///     .with_action(QuotaAction::Shed);
+///
+/// Bastion::children(|children| {
+///     children
+///         .with_quota(quota)
+///         .with_exec(|ctx| {
+///             async move {
+///                 // ...
+///                 # Ok(())
+///             }
+///         })
+/// }).expect("Couldn't create the children group.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Quota {
+    max_in_flight_asks: Option<usize>,
+    max_buffered_bytes: Option<usize>,
+    max_helpers: Option<usize>,
+    action: QuotaAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// What happens when a children group reaches one of the bounds
+/// of its [`Quota`].
+pub enum QuotaAction {
+    /// Drops what goes over the bound: the new message (in which
+    /// case a question is never answered) or the new helper actor.
+    #[default]
+    Reject,
+    /// Drops the oldest messages waiting in the mailbox to make
+    /// room for the new one. The questions and helper actors going
+    /// over their bounds are rejected.
+    Shed,
+    /// Only logs a warning, letting the group go over the bound.
+    Log,
+}
+
+impl Quota {
+    /// Creates a new quota without any bound, which rejects what
+    /// goes over the bounds it is given.
+    pub fn new() -> Self {
+        Quota::default()
+    }
+
+    /// Bounds the number of questions the elements of the group
+    /// were asked and didn't answer yet.
+    pub fn with_max_in_flight_asks(mut self, max: usize) -> Self {
+        self.max_in_flight_asks = Some(max);
+        self
+    }
+
+    /// Bounds the number of bytes of the messages waiting in the
+    /// mailbox of each element of the group.
+    pub fn with_max_buffered_bytes(mut self, max: usize) -> Self {
+        self.max_buffered_bytes = Some(max);
+        self
+    }
+
+    /// Bounds the number of helper actors (see
+    /// [`Children::with_helper`]) the group spawns.
+    ///
+    /// [`Children::with_helper`]: crate::children::Children::with_helper
+    pub fn with_max_helpers(mut self, max: usize) -> Self {
+        self.max_helpers = Some(max);
+        self
+    }
+
+    /// Sets what happens when the group reaches one of the bounds
+    /// of the quota ([`QuotaAction::Reject`] by default).
+    pub fn with_action(mut self, action: QuotaAction) -> Self {
+        self.action = action;
+        self
+    }
+}
+
+#[derive(Debug)]
+/// The usage of the quota of a children group, shared by its
+/// elements.
+pub(crate) struct QuotaState {
+    quota: Quota,
+    // The children group the quota is attached to.
+    id: BastionId,
+    in_flight: Arc<AtomicUsize>,
+}
+
+#[derive(Debug)]
+/// A question which wasn't answered yet, which stops being
+/// counted once dropped along with its sender.
+pub(crate) struct InFlight(Arc<AtomicUsize>);
+
+impl QuotaState {
+    pub(crate) fn new(quota: Quota, id: BastionId) -> Self {
+        QuotaState {
+            quota,
+            id,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub(crate) fn action(&self) -> QuotaAction {
+        self.quota.action
+    }
+
+    /// Counts `msg` as in flight if it is a question, returning
+    /// `false` if it should be rejected.
+    pub(crate) fn admit_ask(&self, msg: &mut Msg) -> bool {
+        if !msg.is_ask() {
+            return true;
+        }
+
+        if let Some(max) = self.quota.max_in_flight_asks {
+            if self.in_flight.load(Ordering::SeqCst) >= max {
+                warn!(
+                    "Children({}): Quota: Reached {} questions in flight.",
+                    self.id, max
+                );
+                if self.action() != QuotaAction::Log {
+                    return false;
+                }
+            }
+        }
+
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        msg.track_in_flight(InFlight(self.in_flight.clone()));
+
+        true
+    }
+
+    /// Returns whether adding `size` bytes to the `buffered` ones
+    /// goes over the bound, logging a warning if it does.
+    pub(crate) fn exceeds_bytes(&self, buffered: usize, size: usize) -> bool {
+        match self.quota.max_buffered_bytes {
+            Some(max) if buffered + size > max => {
+                warn!(
+                    "Children({}): Quota: Reached {} buffered bytes.",
+                    self.id, max
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns whether the helper actor at index `idx` can be
+    /// spawned.
+    pub(crate) fn admit_helper(&self, idx: usize) -> bool {
+        match self.quota.max_helpers {
+            Some(max) if idx >= max => {
+                warn!(
+                    "Children({}): Quota: Reached {} helper actors.",
+                    self.id, max
+                );
+                self.action() == QuotaAction::Log
+            }
+            _ => true,
+        }
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::RefAddr;
+    use crate::path::BastionPath;
+    use futures::channel::mpsc;
+
+    fn ask() -> Msg {
+        let (sender, _) = mpsc::unbounded();
+        let sign = RefAddr::new(Arc::new(BastionPath::root()), sender);
+        Msg::ask("question", sign).0
+    }
+
+    #[test]
+    fn counts_the_questions_until_answered() {
+        let state = QuotaState::new(Quota::new().with_max_in_flight_asks(2), BastionId::new());
+
+        let mut tell = Msg::tell("tell");
+        assert!(state.admit_ask(&mut tell));
+        let (mut first, mut second, mut third) = (ask(), ask(), ask());
+        assert!(state.admit_ask(&mut first));
+        assert!(state.admit_ask(&mut second));
+        assert!(!state.admit_ask(&mut third));
+
+        // Dropping a question drops its sender.
+        drop(first);
+        assert!(state.admit_ask(&mut third));
+    }
+
+    #[test]
+    fn only_logs_the_questions_going_over_the_bound() {
+        let quota = Quota::new()
+            .with_max_in_flight_asks(1)
+            .with_action(QuotaAction::Log);
+        let state = QuotaState::new(quota, BastionId::new());
+
+        let (mut first, mut second) = (ask(), ask());
+        assert!(state.admit_ask(&mut first));
+        assert!(state.admit_ask(&mut second));
+    }
+
+    #[test]
+    fn bounds_the_buffered_bytes() {
+        let state = QuotaState::new(Quota::new().with_max_buffered_bytes(10), BastionId::new());
+        assert!(!state.exceeds_bytes(6, 4));
+        assert!(state.exceeds_bytes(6, 5));
+
+        let state = QuotaState::new(Quota::new(), BastionId::new());
+        assert!(!state.exceeds_bytes(usize::MAX / 2, usize::MAX / 2));
+    }
+
+    #[test]
+    fn bounds_the_helpers_unless_logging() {
+        let state = QuotaState::new(Quota::new().with_max_helpers(2), BastionId::new());
+        assert!(state.admit_helper(1));
+        assert!(!state.admit_helper(2));
+
+        let quota = Quota::new()
+            .with_max_helpers(2)
+            .with_action(QuotaAction::Log);
+        let state = QuotaState::new(quota, BastionId::new());
+        assert!(state.admit_helper(2));
+    }
+}
@@ -0,0 +1,164 @@
+//!
+//! A runtime registry of the message types which can be serialized.
+//!
+//! The message types registered with [`Bastion::register_message_type`]
+//! can be sent to the other members of a cluster with
+//! [`DistributedContext::tell_message`], and the contents of their
+//! messages are shown (as JSON) by [`SignedMessage::describe`], in the
+//! logs of the dead letters and, with the `inspector` feature, by
+//! [`QueuedMessage::contents`].
+//!
+//! The types are registered under their type name, which is only
+//! guaranteed to be the same for the binaries built with the same
+//! compiler, so the members of a cluster exchanging them should run
+//! the same build.
+//!
+//! [`Bastion::register_message_type`]: crate::Bastion::register_message_type
+//! [`DistributedContext::tell_message`]: crate::distributed::DistributedContext::tell_message
+//! [`SignedMessage::describe`]: crate::envelope::SignedMessage::describe
+//! [`QueuedMessage::contents`]: crate::inspector::QueuedMessage::contents
+use crate::message::{Message, Msg};
+use fxhash::FxHashMap;
+use lazy_static::lazy_static;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::any::{type_name, Any};
+use std::sync::RwLock;
+use tracing::{debug, trace};
+
+lazy_static! {
+    static ref REGISTRY: RwLock<FxHashMap<&'static str, MessageType>> =
+        RwLock::new(FxHashMap::default());
+}
+
+#[derive(Clone, Copy)]
+struct MessageType {
+    serialize: fn(&dyn Any) -> Option<String>,
+    // Only used to decode the messages received from remote nodes.
+    #[cfg_attr(not(feature = "distributed"), allow(dead_code))]
+    deserialize: fn(&str) -> Option<Msg>,
+}
+
+/// Returns whether the message type `T` was registered.
+pub fn is_registered<T: Message>() -> bool {
+    // FIXME: panics?
+    REGISTRY.read().unwrap().contains_key(type_name::<T>())
+}
+
+/// Returns the names of the registered message types.
+pub fn registered() -> Vec<&'static str> {
+    // FIXME: panics?
+    REGISTRY.read().unwrap().keys().copied().collect()
+}
+
+/// Registers the message type `T`, returning whether it already
+/// was.
+pub(crate) fn register<T>() -> bool
+where
+    T: Message + Serialize + DeserializeOwned,
+{
+    debug!("Registry: Registering message type: {}", type_name::<T>());
+    let message_type = MessageType {
+        serialize: serialize::<T>,
+        deserialize: deserialize::<T>,
+    };
+
+    // FIXME: panics?
+    let mut registry = REGISTRY.write().unwrap();
+    registry.insert(type_name::<T>(), message_type).is_some()
+}
+
+/// Returns the contents of `msg` serialized as JSON, if its type
+/// was registered.
+pub(crate) fn describe(msg: &Msg) -> Option<String> {
+    // FIXME: panics?
+    let message_type = *REGISTRY.read().unwrap().get(msg.type_name())?;
+    (message_type.serialize)(msg.as_ref())
+}
+
+/// Deserializes a message of the registered type named `name` from
+/// `json`, returning `None` if the type wasn't registered or if the
+/// message couldn't be deserialized.
+#[cfg_attr(not(feature = "distributed"), allow(dead_code))]
+pub(crate) fn decode(name: &str, json: &str) -> Option<Msg> {
+    // FIXME: panics?
+    let message_type = match REGISTRY.read().unwrap().get(name) {
+        Some(message_type) => *message_type,
+        None => {
+            trace!("Registry: Unknown message type: {}", name);
+            return None;
+        }
+    };
+
+    (message_type.deserialize)(json)
+}
+
+fn serialize<T: Message + Serialize>(msg: &dyn Any) -> Option<String> {
+    let msg = msg.downcast_ref::<T>()?;
+    serde_json::to_string(msg).ok()
+}
+
+fn deserialize<T: Message + DeserializeOwned>(json: &str) -> Option<Msg> {
+    match serde_json::from_str::<T>(json) {
+        Ok(msg) => Some(Msg::tell(msg)),
+        Err(err) => {
+            debug!(
+                "Registry: Couldn't deserialize {}: {}",
+                type_name::<T>(),
+                err
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Registered {
+        key: String,
+    }
+
+    // Registered by a single test, since the registry is shared
+    // by the tests.
+    #[derive(Debug, Serialize, Deserialize)]
+    struct RegisteredOnce;
+
+    #[derive(Debug)]
+    struct Unregistered;
+
+    #[test]
+    fn registers_the_types_once() {
+        assert!(!is_registered::<RegisteredOnce>());
+        assert!(!register::<RegisteredOnce>());
+        assert!(register::<RegisteredOnce>());
+        assert!(is_registered::<RegisteredOnce>());
+        assert!(registered().contains(&type_name::<RegisteredOnce>()));
+        assert!(!is_registered::<Unregistered>());
+    }
+
+    #[test]
+    fn describes_and_decodes_the_registered_types() {
+        register::<Registered>();
+        let msg = Msg::tell(Registered {
+            key: "value".to_string(),
+        });
+        let json = describe(&msg).unwrap();
+        assert_eq!(json, r#"{"key":"value"}"#);
+
+        let decoded = decode(type_name::<Registered>(), &json).unwrap();
+        assert_eq!(
+            decoded.as_ref().downcast_ref(),
+            Some(&Registered {
+                key: "value".to_string()
+            })
+        );
+        assert!(decode(type_name::<Registered>(), "{}").is_none());
+
+        assert!(describe(&Msg::tell(Unregistered)).is_none());
+        assert!(decode(type_name::<Unregistered>(), "null").is_none());
+    }
+}
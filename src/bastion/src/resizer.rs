@@ -122,6 +122,11 @@ impl OptimalSizeExploringResizer {
         self.lower_bound = lower_bound;
     }
 
+    /// Set upper bound of the autoscaling group.
+    pub(crate) fn set_upper_bound(&mut self, upper_bound: UpperBound) {
+        self.upper_bound = upper_bound;
+    }
+
     /// Overrides the minimal amount of actors available to use.
     pub fn with_lower_bound(mut self, lower_bound: u64) -> Self {
         if lower_bound == u64::MIN {
@@ -0,0 +1,214 @@
+//!
+//! Resources shared by the elements of a children group, like
+//! connection pools or clients.
+//!
+//! A [`Resource`] attached to a children group with
+//! [`Children::with_resource`] is owned by the group: it is lazily
+//! initialized the first time one of the elements asks for it with
+//! [`BastionContext::resource`], and then shared by all of them. It
+//! is torn down and initialized again once the elements restart after
+//! a fault, and torn down when the group stops, so that it neither
+//! gets recreated by every element nor leaks across restarts.
+//!
+//! [`Children::with_resource`]: crate::children::Children::with_resource
+//! [`BastionContext::resource`]: crate::context::BastionContext::resource
+use async_mutex::Mutex as AsyncMutex;
+use fxhash::FxHashMap;
+use std::any::{Any, TypeId};
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tracing::debug;
+
+type Init<T> = Arc<dyn Fn() -> Pin<Box<dyn Future<Output = T> + Send>> + Send + Sync>;
+type Teardown<T> = Arc<dyn Fn(Arc<T>) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// A value shared by the elements of a children group, initialized
+/// once by an async closure and optionally torn down by another one.
+///
+/// There is at most one resource of each type per group.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::resource::Resource;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// #[derive(Debug)]
+/// struct Pool {
+///     // ...
+/// }
+///
+/// let pool = Resource::new(|| async move {
+///     // Connecting...
+///     Pool {}
+/// })
+/// .with_teardown(|pool| async move {
+///     // Disconnecting...
+/// });
+///
+/// Bastion::children(|children| {
+///     children
+///         .with_resource(pool)
+///         .with_exec(|ctx: BastionContext| {
+///             async move {
+///                 let pool = ctx.resource::<Pool>().await.expect("No pool.");
+///                 // ...
+///                 # Ok(())
+///             }
+///         })
+/// }).expect("Couldn't create the children group.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Resource<T> {
+    init: Init<T>,
+    teardown: Option<Teardown<T>>,
+}
+
+#[derive(Clone, Default)]
+/// The resources owned by a children group, shared by its elements.
+pub(crate) struct Resources {
+    resources: FxHashMap<TypeId, Arc<dyn GroupResource>>,
+}
+
+// A resource owned by a group, along with its value once it was
+// initialized.
+struct Shared<T> {
+    resource: Resource<T>,
+    value: AsyncMutex<Option<Arc<T>>>,
+}
+
+trait GroupResource: Send + Sync {
+    fn as_any(&self) -> &(dyn Any + Send + Sync);
+
+    // Tears the value of the resource down, if it was initialized,
+    // so that it is initialized again the next time it is used.
+    fn release(self: Arc<Self>) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
+
+impl<T: Send + Sync + 'static> Resource<T> {
+    /// Creates a new resource initialized by `init`.
+    ///
+    /// # Arguments
+    ///
+    /// * `init` - The closure returning the future initializing the
+    ///   resource.
+    pub fn new<I, F>(init: I) -> Self
+    where
+        I: Fn() -> F + Send + Sync + 'static,
+        F: Future<Output = T> + Send + 'static,
+    {
+        let init = Arc::new(move || Box::pin(init()) as Pin<Box<dyn Future<Output = T> + Send>>);
+        Resource {
+            init,
+            teardown: None,
+        }
+    }
+
+    /// Sets the closure tearing the resource down when the children
+    /// group stops or before it is initialized again.
+    ///
+    /// # Arguments
+    ///
+    /// * `teardown` - The closure taking the resource and returning
+    ///   the future tearing it down.
+    pub fn with_teardown<D, F>(mut self, teardown: D) -> Self
+    where
+        D: Fn(Arc<T>) -> F + Send + Sync + 'static,
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let teardown = Arc::new(move |value| {
+            Box::pin(teardown(value)) as Pin<Box<dyn Future<Output = ()> + Send>>
+        });
+        self.teardown = Some(teardown);
+        self
+    }
+}
+
+impl Resources {
+    pub(crate) fn insert<T: Send + Sync + 'static>(&mut self, resource: Resource<T>) {
+        let shared = Shared {
+            resource,
+            value: AsyncMutex::new(None),
+        };
+        self.resources.insert(TypeId::of::<T>(), Arc::new(shared));
+    }
+
+    /// Returns the value of the resource of type `T`, initializing
+    /// it if it wasn't yet, if the group owns one.
+    pub(crate) async fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        let shared = self.resources.get(&TypeId::of::<T>())?;
+        let shared: &Shared<T> = shared.as_any().downcast_ref()?;
+
+        let mut value = shared.value.lock().await;
+        if let Some(value) = &*value {
+            return Some(value.clone());
+        }
+
+        debug!("Resources: Initializing {}.", std::any::type_name::<T>());
+        let initialized = Arc::new((shared.resource.init)().await);
+        *value = Some(initialized.clone());
+
+        Some(initialized)
+    }
+
+    /// Tears down the values of the resources which were initialized.
+    pub(crate) async fn release(&self) {
+        for resource in self.resources.values() {
+            resource.clone().release().await;
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> GroupResource for Shared<T> {
+    fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        self
+    }
+
+    fn release(self: Arc<Self>) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(async move {
+            let value = self.value.lock().await.take();
+            if let Some(value) = value {
+                debug!("Resources: Tearing {} down.", std::any::type_name::<T>());
+                if let Some(teardown) = &self.resource.teardown {
+                    teardown(value).await;
+                }
+            }
+        })
+    }
+}
+
+impl<T> Debug for Resource<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Resource")
+            .field("teardown", &self.teardown.is_some())
+            .finish()
+    }
+}
+
+impl Debug for Resources {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Resources")
+            .field("len", &self.resources.len())
+            .finish()
+    }
+}
@@ -0,0 +1,338 @@
+//!
+//! Policies used to ask questions again when they weren't answered.
+//!
+//! A [`RetryPolicy`] is given to [`BastionContext::ask_with_retry`],
+//! which asks a question again, after an exponentially increasing
+//! delay, every time it times out or can't be delivered, until the
+//! policy's number of retries or time budget is exhausted.
+//!
+//! The same policies define how the messages handled with
+//! [`BastionContext::handle_retriable`] by the elements of a children
+//! group created with [`Children::with_redelivery`] are redelivered
+//! when their handlers return a [`Retriable`] error.
+//!
+//! [`BastionContext::ask_with_retry`]: crate::context::BastionContext::ask_with_retry
+//! [`BastionContext::handle_retriable`]: crate::context::BastionContext::handle_retriable
+//! [`Children::with_redelivery`]: crate::children::Children::with_redelivery
+use crate::envelope::SignedMessage;
+use crate::message::{Message, Msg};
+use fxhash::FxHashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The default time given to the target to answer each attempt.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+/// The default number of times a question is asked again.
+const DEFAULT_MAX_RETRIES: usize = 3;
+/// The default delay before the first retry.
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(100);
+/// The default factor the delay is multiplied by after every retry.
+const DEFAULT_MULTIPLIER: f64 = 2.0;
+/// The default upper bound of the delay between two retries.
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq)]
+/// Defines how many times and how often a question is asked again
+/// by [`BastionContext::ask_with_retry`].
+///
+/// By default, a question is given `5s` to be answered and is asked
+/// up to `3` more times, waiting `100ms` before the first retry and
+/// twice as long before every following one, up to `10s`.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::retry::RetryPolicy;
+/// # use std::time::Duration;
+/// #
+/// let policy = RetryPolicy::new()
+///     .with_timeout(Duration::from_millis(500))
+///     .with_max_retries(5)
+///     .with_backoff(Duration::from_millis(50), 3.0)
+///     // Gives up once the question was asked for 10s.
+///     .with_budget(Duration::from_secs(10));
+///
+/// assert_eq!(policy.backoff(0), Duration::from_millis(50));
+/// assert_eq!(policy.backoff(1), Duration::from_millis(150));
+/// ```
+///
+/// [`BastionContext::ask_with_retry`]: crate::context::BastionContext::ask_with_retry
+pub struct RetryPolicy {
+    timeout: Duration,
+    max_retries: usize,
+    backoff: Duration,
+    multiplier: f64,
+    max_backoff: Duration,
+    budget: Option<Duration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The error returned by the handlers given to
+/// [`BastionContext::handle_retriable`] to have the message they
+/// failed to handle redelivered later.
+///
+/// [`BastionContext::handle_retriable`]: crate::context::BastionContext::handle_retriable
+pub struct Retriable {
+    reason: String,
+}
+
+/// Clones a message if it is of a given type.
+type Cloner = Arc<dyn Fn(&Msg) -> Option<Msg> + Send + Sync>;
+
+#[derive(Clone)]
+/// The policy redelivering the messages of a type to the elements of
+/// a children group, along with how to clone them.
+pub(crate) struct Redelivery {
+    policy: RetryPolicy,
+    // Clones the messages of the redelivered type.
+    cloner: Cloner,
+}
+
+#[derive(Debug)]
+/// The messages waiting to be redelivered to the elements of a
+/// children group, which outlives its elements' restarts.
+pub(crate) struct RedeliveryQueue {
+    redelivery: Redelivery,
+    // The next ticket, which is never reused during the group's
+    // lifetime so that a stale ticket can't take another message.
+    next: AtomicU64,
+    // The messages waiting for their backoff delay to elapse, along
+    // with the number of times they were already delivered.
+    scheduled: Mutex<FxHashMap<u64, (usize, SignedMessage)>>,
+}
+
+#[derive(Debug)]
+/// Sent to an element once the backoff delay of one of its messages
+/// elapsed, to take it out of its group's redelivery queue.
+pub(crate) struct RedeliveryTicket(pub(crate) u64);
+
+impl RetryPolicy {
+    /// Creates a new policy with the default settings.
+    pub fn new() -> Self {
+        RetryPolicy::default()
+    }
+
+    /// Sets how long the target is given to answer each attempt.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets how many times the question is asked again after the
+    /// first attempt failed.
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the delay before the first retry and the factor it is
+    /// multiplied by after every retry.
+    ///
+    /// A `multiplier` of `1.0` makes every retry wait for the same
+    /// delay.
+    pub fn with_backoff(mut self, backoff: Duration, multiplier: f64) -> Self {
+        self.backoff = backoff;
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Sets the upper bound of the delay between two retries.
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Sets the total time the question can be asked for, including
+    /// the delays between the retries. No retry is attempted if it
+    /// wouldn't start before the budget is exhausted, and the last
+    /// attempt is given the time left as its timeout.
+    pub fn with_budget(mut self, budget: Duration) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Returns how long the target is given to answer each attempt.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Returns how many times the question is asked again.
+    pub fn max_retries(&self) -> usize {
+        self.max_retries
+    }
+
+    /// Returns the total time the question can be asked for, if
+    /// it is bounded.
+    pub fn budget(&self) -> Option<Duration> {
+        self.budget
+    }
+
+    /// Returns the delay before the retry following the given
+    /// number of retries.
+    pub fn backoff(&self, retries: usize) -> Duration {
+        let factor = self.multiplier.powi(retries.min(i32::MAX as usize) as i32);
+        let backoff = self.backoff.as_secs_f64() * factor;
+        if backoff.is_finite() && backoff < self.max_backoff.as_secs_f64() {
+            Duration::from_secs_f64(backoff)
+        } else {
+            self.max_backoff
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            timeout: DEFAULT_TIMEOUT,
+            max_retries: DEFAULT_MAX_RETRIES,
+            backoff: DEFAULT_BACKOFF,
+            multiplier: DEFAULT_MULTIPLIER,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            budget: None,
+        }
+    }
+}
+
+impl Retriable {
+    /// Creates a new error making the message be redelivered.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - Why the message couldn't be handled.
+    pub fn new(reason: impl Into<String>) -> Self {
+        Retriable {
+            reason: reason.into(),
+        }
+    }
+
+    /// Returns why the message couldn't be handled.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl Redelivery {
+    pub(crate) fn new<M: Message + Clone>(policy: RetryPolicy) -> Self {
+        let cloner = Arc::new(|msg: &Msg| {
+            let msg: &M = msg.as_ref().downcast_ref()?;
+            Some(Msg::tell(msg.clone()))
+        });
+
+        Redelivery { policy, cloner }
+    }
+
+    pub(crate) fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Clones `msg` if it is of the redelivered type.
+    pub(crate) fn clone_message(&self, msg: &SignedMessage) -> Option<SignedMessage> {
+        let clone = (self.cloner)(&msg.msg)?;
+        Some(SignedMessage::new(clone, msg.sign.clone()))
+    }
+}
+
+impl RedeliveryQueue {
+    pub(crate) fn new(redelivery: Redelivery) -> Self {
+        RedeliveryQueue {
+            redelivery,
+            next: AtomicU64::new(0),
+            scheduled: Mutex::new(FxHashMap::default()),
+        }
+    }
+
+    pub(crate) fn redelivery(&self) -> &Redelivery {
+        &self.redelivery
+    }
+
+    /// Keeps `msg` until it is redelivered, returning the ticket to
+    /// take it back with.
+    pub(crate) fn schedule(&self, delivered: usize, msg: SignedMessage) -> RedeliveryTicket {
+        let ticket = self.next.fetch_add(1, Ordering::Relaxed);
+        // FIXME: panics?
+        self.scheduled
+            .lock()
+            .unwrap()
+            .insert(ticket, (delivered, msg));
+
+        RedeliveryTicket(ticket)
+    }
+
+    /// Takes the message kept for `ticket` back, along with the
+    /// number of times it was already delivered.
+    pub(crate) fn take(&self, ticket: u64) -> Option<(usize, SignedMessage)> {
+        // FIXME: panics?
+        self.scheduled.lock().unwrap().remove(&ticket)
+    }
+}
+
+impl Debug for Redelivery {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Redelivery")
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::RefAddr;
+    use crate::path::BastionPath;
+    use futures::channel::mpsc;
+
+    fn signed(msg: &'static str) -> SignedMessage {
+        let (sender, _) = mpsc::unbounded();
+        let sign = RefAddr::new(Arc::new(BastionPath::root()), sender);
+        SignedMessage::new(Msg::tell(msg), sign)
+    }
+
+    #[test]
+    fn backoff_is_bounded() {
+        let policy = RetryPolicy::new()
+            .with_backoff(Duration::from_millis(100), 2.0)
+            .with_max_backoff(Duration::from_millis(500));
+
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff(3), Duration::from_millis(500));
+        assert_eq!(policy.backoff(usize::MAX), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn clones_only_the_redelivered_type() {
+        let redelivery = Redelivery::new::<&'static str>(RetryPolicy::new());
+        let clone = redelivery.clone_message(&signed("put")).unwrap();
+        assert_eq!(clone.msg.as_ref().downcast_ref(), Some(&"put"));
+
+        let (sender, _) = mpsc::unbounded();
+        let sign = RefAddr::new(Arc::new(BastionPath::root()), sender);
+        let other = SignedMessage::new(Msg::tell(42u64), sign);
+        assert!(redelivery.clone_message(&other).is_none());
+    }
+
+    #[test]
+    fn tickets_take_their_own_message_once() {
+        let queue = RedeliveryQueue::new(Redelivery::new::<&'static str>(RetryPolicy::new()));
+        let first = queue.schedule(1, signed("first"));
+        let second = queue.schedule(2, signed("second"));
+        assert_ne!(first.0, second.0);
+
+        let (delivered, msg) = queue.take(first.0).unwrap();
+        assert_eq!(delivered, 1);
+        assert_eq!(msg.msg.as_ref().downcast_ref(), Some(&"first"));
+        assert!(queue.take(first.0).is_none());
+
+        // A new message never gets the ticket of a taken one.
+        let third = queue.schedule(3, signed("third"));
+        assert_ne!(third.0, first.0);
+        assert!(queue.take(first.0).is_none());
+
+        let (delivered, msg) = queue.take(second.0).unwrap();
+        assert_eq!(delivered, 2);
+        assert_eq!(msg.msg.as_ref().downcast_ref(), Some(&"second"));
+    }
+}
@@ -0,0 +1,206 @@
+//!
+//! A router fronting a list of arbitrary actors behind a single address.
+//!
+//! Unlike the dispatchers, which route the messages to the elements of
+//! the children groups registered to them, a [`Router`] is built from any
+//! [`ChildRef`]s (eg. elements of different groups doing the same job
+//! differently) and decides where each message goes with its
+//! [`RoutingLogic`]. The messages are forwarded with the signature of
+//! their senders, so the routees reply to them directly.
+use crate::child_ref::ChildRef;
+use crate::context::BastionContext;
+use crate::envelope::SignedMessage;
+use crate::Bastion;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tracing::{debug, trace, warn};
+
+/// Decides to which of the routees of a [`Router`] each message is
+/// forwarded.
+///
+/// It is implemented for the closures taking the message and the
+/// routees, and returning the index of the routee to forward the
+/// message to, or `None` to drop it.
+pub trait RoutingLogic: Send + Sync + 'static {
+    /// Returns the index in `routees` of the routee `msg` should be
+    /// forwarded to, or `None` if it should be dropped.
+    fn route(&self, msg: &SignedMessage, routees: &[ChildRef]) -> Option<usize>;
+}
+
+#[derive(Debug, Default)]
+/// A [`RoutingLogic`] forwarding the messages to each routee in turn.
+pub struct RoundRobin {
+    index: AtomicUsize,
+}
+
+/// A builder of an actor forwarding the messages it receives to a
+/// list of routees, according to its [`RoutingLogic`].
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::router::Router;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// # let backend = || Bastion::children(|children| {
+/// #     children.with_exec(|ctx: BastionContext| async move {
+/// #         loop {
+/// #             ctx.recv().await?;
+/// #         }
+/// #     })
+/// # }).unwrap();
+/// let postgres = backend();
+/// let sqlite = backend();
+///
+/// // Sends the messages containing "local" to the SQLite backend, and
+/// // the other ones to the PostgreSQL backend...
+/// let router = Router::new(vec![
+///     postgres.elems()[0].clone(),
+///     sqlite.elems()[0].clone(),
+/// ])
+/// .with_logic(|msg: &SignedMessage, _routees: &[ChildRef]| {
+///     match msg.msg().as_ref().downcast_ref::<&'static str>() {
+///         Some(query) if query.contains("local") => Some(1),
+///         _ => Some(0),
+///     }
+/// })
+/// .spawn()
+/// .expect("Couldn't spawn the router.");
+///
+/// // ...behind the address of the router.
+/// router.tell_anonymously("SELECT local").expect("Couldn't send the message.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Router {
+    routees: Vec<ChildRef>,
+    logic: Arc<dyn RoutingLogic>,
+}
+
+impl Router {
+    /// Creates a router forwarding the messages to the given routees
+    /// in turn (see [`RoundRobin`]).
+    pub fn new(routees: Vec<ChildRef>) -> Self {
+        Router {
+            routees,
+            logic: Arc::new(RoundRobin::default()),
+        }
+    }
+
+    /// Adds a routee to the router.
+    pub fn with_routee(mut self, routee: ChildRef) -> Self {
+        self.routees.push(routee);
+        self
+    }
+
+    /// Sets the logic deciding to which routee each message is
+    /// forwarded, replacing the round-robin one.
+    pub fn with_logic<L: RoutingLogic>(mut self, logic: L) -> Self {
+        self.logic = Arc::new(logic);
+        self
+    }
+
+    /// Returns the routees of the router.
+    pub fn routees(&self) -> &[ChildRef] {
+        &self.routees
+    }
+
+    /// Spawns the router as a children group of a single element,
+    /// returning the reference of this element, whose address is
+    /// the one the messages should be sent to.
+    ///
+    /// This method returns the [`ChildRef`] of the router if it
+    /// succeeded, or `Err(())` otherwise.
+    #[allow(clippy::result_unit_err)]
+    pub fn spawn(self) -> Result<ChildRef, ()> {
+        let Router { routees, logic } = self;
+        let routees: Arc<[ChildRef]> = routees.into();
+
+        let children = Bastion::spawn(move |ctx: BastionContext| {
+            let routees = routees.clone();
+            let logic = logic.clone();
+            async move {
+                debug!(
+                    "Router({}): Routing to {} routees.",
+                    ctx.current().id(),
+                    routees.len()
+                );
+                loop {
+                    let msg = ctx.recv().await?;
+                    let routee = match logic.route(&msg, &routees) {
+                        Some(index) if index < routees.len() => &routees[index],
+                        _ => {
+                            warn!(
+                                "Router({}): Dropping unrouted message: {:?}",
+                                ctx.current().id(),
+                                msg
+                            );
+                            continue;
+                        }
+                    };
+
+                    trace!(
+                        "Router({}): Forwarding message to: {:?}",
+                        ctx.current().id(),
+                        routee.path()
+                    );
+                    if let Err(msg) = ctx.forward(msg, &routee.addr()) {
+                        warn!(
+                            "Router({}): Couldn't forward message to: {:?}: {:?}",
+                            ctx.current().id(),
+                            routee.path(),
+                            msg
+                        );
+                    }
+                }
+            }
+        })?;
+
+        children.elems().first().cloned().ok_or(())
+    }
+}
+
+impl<F> RoutingLogic for F
+where
+    F: Fn(&SignedMessage, &[ChildRef]) -> Option<usize> + Send + Sync + 'static,
+{
+    fn route(&self, msg: &SignedMessage, routees: &[ChildRef]) -> Option<usize> {
+        self(msg, routees)
+    }
+}
+
+impl RoutingLogic for RoundRobin {
+    fn route(&self, _msg: &SignedMessage, routees: &[ChildRef]) -> Option<usize> {
+        if routees.is_empty() {
+            return None;
+        }
+
+        Some(self.index.fetch_add(1, Ordering::Relaxed) % routees.len())
+    }
+}
+
+impl Debug for Router {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Router")
+            .field("routees", &self.routees)
+            .finish()
+    }
+}
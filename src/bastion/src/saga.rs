@@ -0,0 +1,564 @@
+//!
+//! Long-running workflows made of steps which are compensated when
+//! one of them fails.
+//!
+//! A [`Saga`] runs its steps one after the other on a state, and saves
+//! its progress in a [`SagaStore`] after every step. If a step fails,
+//! the steps which completed are compensated in the reverse order. If
+//! the element running a saga crashes (or is restarted), the saga is
+//! resumed from the last completed step (or compensation) the next time
+//! it is run with the same id, which the element returned by
+//! [`Saga::exec`] does for every unfinished saga when it starts.
+//!
+//! Steps can be run more than once if the element crashed while
+//! running them, and should thus be idempotent.
+use crate::context::BastionContext;
+use crate::errors::SagaError;
+use crate::message::Message;
+use fxhash::FxHashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, trace, warn};
+
+/// The future returned by the steps of a saga and their
+/// compensations.
+pub type StepFuture<S> = Pin<Box<dyn Future<Output = Result<S, ()>> + Send>>;
+
+type Step<S> = Arc<dyn Fn(S) -> StepFuture<S> + Send + Sync>;
+
+/// Persists the progress of the sagas, so that they can be resumed
+/// after a crash.
+///
+/// The [`MemoryStore`] keeps the progress of the sagas in memory, which
+/// allows to resume them after the elements running them were
+/// restarted; implementing this trait on top of a database allows to
+/// resume them after the whole process was restarted.
+pub trait SagaStore<S>: Send + Sync + 'static {
+    /// Returns the progress of the saga with the given id, if it was
+    /// saved and isn't finished.
+    fn load(&self, id: &str) -> Option<SagaProgress<S>>;
+    /// Saves the progress of the saga with the given id.
+    fn save(&self, id: &str, progress: &SagaProgress<S>);
+    /// Forgets the saga with the given id, once it is finished.
+    fn remove(&self, id: &str);
+    /// Returns the ids of the sagas which aren't finished.
+    fn pending(&self) -> Vec<String>;
+}
+
+#[derive(Debug, Clone)]
+/// The progress of a saga, saved in a [`SagaStore`] after every
+/// step or compensation.
+pub struct SagaProgress<S> {
+    state: S,
+    // The number of steps which completed and weren't
+    // compensated.
+    completed: usize,
+    // The step which failed, if the saga is being compensated.
+    failed: Option<usize>,
+}
+
+/// A [`SagaStore`] keeping the progress of the sagas in memory.
+///
+/// Cloning a `MemoryStore` returns a new handle to the same store.
+pub struct MemoryStore<S> {
+    sagas: Arc<Mutex<FxHashMap<String, SagaProgress<S>>>>,
+}
+
+/// A workflow made of steps run one after the other on a state of
+/// type `S`, each of them having a compensation undoing it.
+///
+/// Cloning a `Saga` returns a new handle to the same steps.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::saga::{MemoryStore, Saga, SagaStart};
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// #[derive(Debug, Clone)]
+/// struct Trip {
+///     flight: Option<u64>,
+///     hotel: Option<u64>,
+/// }
+///
+/// let saga = Saga::new()
+///     .with_step(
+///         |mut trip: Trip| async move {
+///             // Books the flight...
+///             trip.flight = Some(42);
+///             Ok(trip)
+///         },
+///         |mut trip: Trip| async move {
+///             // ...or cancels it.
+///             trip.flight = None;
+///             Ok(trip)
+///         },
+///     )
+///     .with_step(
+///         |mut trip: Trip| async move {
+///             trip.hotel = Some(7);
+///             Ok(trip)
+///         },
+///         |mut trip: Trip| async move {
+///             trip.hotel = None;
+///             Ok(trip)
+///         },
+///     );
+///
+/// // Keeps the progress of the sagas when the element is restarted.
+/// let store = MemoryStore::new();
+///
+/// let trips = Bastion::children(|children| {
+///     children.with_exec(saga.exec(store))
+/// }).expect("Couldn't create the children group.");
+///
+/// # Bastion::start();
+/// let trip = Trip { flight: None, hotel: None };
+/// trips.elems()[0]
+///     .tell_anonymously(SagaStart::new("trip-1", trip))
+///     .expect("Couldn't start the saga.");
+/// #
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Saga<S> {
+    // The steps and their compensations.
+    steps: Arc<Vec<(Step<S>, Step<S>)>>,
+}
+
+#[derive(Debug, Clone)]
+/// The message starting a saga with the given id and initial state
+/// when sent to an element running [`Saga::exec`].
+///
+/// If it was asked, the element answers it with the saga's
+/// `Result<S, SagaError>` once it finished.
+pub struct SagaStart<S> {
+    id: String,
+    state: S,
+}
+
+impl<S> Saga<S>
+where
+    S: Message + Clone,
+{
+    /// Creates a new saga without any step.
+    pub fn new() -> Self {
+        Saga {
+            steps: Arc::new(Vec::new()),
+        }
+    }
+
+    /// Appends a step to the saga, along with the compensation undoing
+    /// it if one of the following steps fails.
+    ///
+    /// Both are given the current state of the saga and return its new
+    /// state, or `Err(())` if they failed.
+    pub fn with_step<A, AF, C, CF>(mut self, action: A, compensation: C) -> Self
+    where
+        A: Fn(S) -> AF + Send + Sync + 'static,
+        AF: Future<Output = Result<S, ()>> + Send + 'static,
+        C: Fn(S) -> CF + Send + Sync + 'static,
+        CF: Future<Output = Result<S, ()>> + Send + 'static,
+    {
+        let action: Step<S> = Arc::new(move |state| Box::pin(action(state)));
+        let compensation: Step<S> = Arc::new(move |state| Box::pin(compensation(state)));
+
+        let mut steps = self.steps.as_ref().clone();
+        steps.push((action, compensation));
+        self.steps = Arc::new(steps);
+        self
+    }
+
+    /// Returns the number of steps of the saga.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns whether the saga doesn't have any step.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Runs the saga with the given id, resuming it from the progress
+    /// saved in `store` if it wasn't finished, or starting it with the
+    /// `state` given otherwise.
+    ///
+    /// This method returns the final state of the saga if all of its
+    /// steps succeeded, or a [`SagaError`] otherwise: if one of its
+    /// steps failed and it was compensated, or if one of the
+    /// compensations failed, in which case the saga is compensated
+    /// again the next time it is run.
+    pub async fn run<T>(&self, id: &str, state: S, store: &T) -> Result<S, SagaError>
+    where
+        T: SagaStore<S> + ?Sized,
+    {
+        let mut progress = match store.load(id) {
+            Some(progress) => {
+                debug!(
+                    "Saga({}): Resuming after {} completed steps.",
+                    id, progress.completed
+                );
+                progress
+            }
+            None => {
+                let progress = SagaProgress {
+                    state,
+                    completed: 0,
+                    failed: None,
+                };
+                store.save(id, &progress);
+                progress
+            }
+        };
+
+        while progress.failed.is_none() && progress.completed < self.steps.len() {
+            let (action, _) = &self.steps[progress.completed];
+            trace!("Saga({}): Running step {}.", id, progress.completed);
+            match action(progress.state.clone()).await {
+                Ok(state) => {
+                    progress.state = state;
+                    progress.completed += 1;
+                }
+                Err(()) => {
+                    warn!("Saga({}): Step {} failed.", id, progress.completed);
+                    progress.failed = Some(progress.completed);
+                }
+            }
+
+            store.save(id, &progress);
+        }
+
+        let failed = match progress.failed {
+            Some(failed) => failed,
+            None => {
+                debug!("Saga({}): Completed.", id);
+                store.remove(id);
+                return Ok(progress.state);
+            }
+        };
+
+        while progress.completed > 0 {
+            let (_, compensation) = &self.steps[progress.completed - 1];
+            trace!(
+                "Saga({}): Compensating step {}.",
+                id,
+                progress.completed - 1
+            );
+            match compensation(progress.state.clone()).await {
+                Ok(state) => {
+                    progress.state = state;
+                    progress.completed -= 1;
+                    store.save(id, &progress);
+                }
+                Err(()) => {
+                    warn!(
+                        "Saga({}): Compensation of step {} failed.",
+                        id,
+                        progress.completed - 1
+                    );
+                    return Err(SagaError::CompensationFailed(progress.completed - 1));
+                }
+            }
+        }
+
+        debug!("Saga({}): Compensated.", id);
+        store.remove(id);
+        Err(SagaError::Compensated(failed))
+    }
+
+    /// Returns the closure to give to [`Children::with_exec`] to make
+    /// the elements of a group run this saga.
+    ///
+    /// When they start, the elements resume the unfinished sagas
+    /// saved in `store`, and then run a saga every time they receive
+    /// a [`SagaStart`]. They fault (and are restarted by their
+    /// supervisor, resuming the saga) if a compensation fails.
+    ///
+    /// Every element resumes the unfinished sagas, so the group
+    /// should have a single element (which is the default).
+    ///
+    /// [`Children::with_exec`]: crate::children::Children::with_exec
+    pub fn exec<T>(
+        &self,
+        store: T,
+    ) -> impl Fn(BastionContext) -> Pin<Box<dyn Future<Output = Result<(), ()>> + Send>>
+           + Send
+           + Sync
+           + 'static
+    where
+        T: SagaStore<S>,
+    {
+        let saga = self.clone();
+        let store = Arc::new(store);
+
+        move |ctx: BastionContext| {
+            let saga = saga.clone();
+            let store = store.clone();
+
+            Box::pin(async move {
+                for id in store.pending() {
+                    if let Some(progress) = store.load(&id) {
+                        if let Err(SagaError::CompensationFailed(_)) =
+                            saga.run(&id, progress.state, &*store).await
+                        {
+                            return Err(());
+                        }
+                    }
+                }
+
+                loop {
+                    let (mut msg, _) = ctx.recv().await?.extract();
+                    let sender = msg.take_sender();
+                    let start = match msg.downcast::<SagaStart<S>>() {
+                        Ok(start) => start,
+                        Err(msg) => {
+                            debug!("Saga: Dropping message: {:?}", msg);
+                            continue;
+                        }
+                    };
+
+                    let res = saga.run(&start.id, start.state, &*store).await;
+                    let faulted = matches!(res, Err(SagaError::CompensationFailed(_)));
+                    if let Some(sender) = sender {
+                        sender.reply(res).ok();
+                    }
+
+                    if faulted {
+                        return Err(());
+                    }
+                }
+            })
+        }
+    }
+}
+
+impl<S> SagaProgress<S> {
+    /// Returns the state of the saga after the last completed step
+    /// or compensation.
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Returns the number of steps which completed and weren't
+    /// compensated.
+    pub fn completed(&self) -> usize {
+        self.completed
+    }
+
+    /// Returns the step which failed, if the saga is being
+    /// compensated.
+    pub fn failed(&self) -> Option<usize> {
+        self.failed
+    }
+}
+
+impl<S> SagaStart<S> {
+    /// Creates a new message starting the saga with the given id
+    /// and initial state.
+    pub fn new(id: impl Into<String>, state: S) -> Self {
+        SagaStart {
+            id: id.into(),
+            state,
+        }
+    }
+
+    /// Returns the id of the saga.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl<S> MemoryStore<S> {
+    /// Creates a new empty store.
+    pub fn new() -> Self {
+        MemoryStore {
+            sagas: Arc::new(Mutex::new(FxHashMap::default())),
+        }
+    }
+}
+
+impl<S> SagaStore<S> for MemoryStore<S>
+where
+    S: Clone + Send + 'static,
+{
+    fn load(&self, id: &str) -> Option<SagaProgress<S>> {
+        self.sagas.lock().unwrap().get(id).cloned()
+    }
+
+    fn save(&self, id: &str, progress: &SagaProgress<S>) {
+        self.sagas
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), progress.clone());
+    }
+
+    fn remove(&self, id: &str) {
+        self.sagas.lock().unwrap().remove(id);
+    }
+
+    fn pending(&self) -> Vec<String> {
+        self.sagas.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+impl<S> Clone for Saga<S> {
+    fn clone(&self) -> Self {
+        Saga {
+            steps: self.steps.clone(),
+        }
+    }
+}
+
+impl<S> Default for Saga<S>
+where
+    S: Message + Clone,
+{
+    fn default() -> Self {
+        Saga::new()
+    }
+}
+
+impl<S> Debug for Saga<S> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Saga")
+            .field("steps", &self.steps.len())
+            .finish()
+    }
+}
+
+impl<S> Clone for MemoryStore<S> {
+    fn clone(&self) -> Self {
+        MemoryStore {
+            sagas: self.sagas.clone(),
+        }
+    }
+}
+
+impl<S> Default for MemoryStore<S> {
+    fn default() -> Self {
+        MemoryStore::new()
+    }
+}
+
+impl<S> Debug for MemoryStore<S> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("MemoryStore")
+            .field("sagas", &self.sagas.lock().unwrap().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    type Log = Vec<String>;
+
+    // Returns a step logging `name` to the state, which fails while
+    // `fails` is set.
+    fn step(name: &'static str, fails: Arc<AtomicBool>) -> impl Fn(Log) -> StepFuture<Log> {
+        move |mut log: Log| {
+            let fails = fails.load(Ordering::SeqCst);
+            Box::pin(async move {
+                if fails {
+                    return Err(());
+                }
+
+                log.push(name.to_string());
+                Ok(log)
+            })
+        }
+    }
+
+    fn saga(failing: &[Arc<AtomicBool>; 4]) -> Saga<Log> {
+        Saga::new()
+            .with_step(
+                step("a", failing[0].clone()),
+                step("undo a", failing[1].clone()),
+            )
+            .with_step(
+                step("b", failing[2].clone()),
+                step("undo b", failing[3].clone()),
+            )
+            .with_step(
+                step("c", failing[2].clone()),
+                step("undo c", failing[3].clone()),
+            )
+    }
+
+    fn flags() -> [Arc<AtomicBool>; 4] {
+        [
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicBool::new(false)),
+        ]
+    }
+
+    #[test]
+    fn runs_every_step() {
+        let store = MemoryStore::new();
+        let saga = saga(&flags());
+        assert_eq!(saga.len(), 3);
+
+        let log = executor::run(saga.run("saga", vec![], &store)).unwrap();
+        assert_eq!(log, vec!["a", "b", "c"]);
+        assert!(store.pending().is_empty());
+    }
+
+    #[test]
+    fn compensates_the_completed_steps() {
+        let store = MemoryStore::new();
+        let failing = flags();
+        let saga = saga(&failing);
+
+        // "b" fails once "a" completed.
+        failing[2].store(true, Ordering::SeqCst);
+        let res = executor::run(saga.run("saga", vec![], &store));
+        assert_eq!(res, Err(SagaError::Compensated(1)));
+        assert!(store.pending().is_empty());
+    }
+
+    #[test]
+    fn resumes_the_failed_compensations() {
+        let store = MemoryStore::new();
+        let failing = flags();
+        let saga = saga(&failing);
+
+        // "b" fails and "undo a" too.
+        failing[1].store(true, Ordering::SeqCst);
+        failing[2].store(true, Ordering::SeqCst);
+        let res = executor::run(saga.run("saga", vec![], &store));
+        assert_eq!(res, Err(SagaError::CompensationFailed(0)));
+
+        let progress = store.load("saga").unwrap();
+        assert_eq!(progress.state(), &vec!["a".to_string()]);
+        assert_eq!(progress.completed(), 1);
+        assert_eq!(progress.failed(), Some(1));
+
+        // The state given to a resumed saga is ignored.
+        failing[1].store(false, Ordering::SeqCst);
+        let res = executor::run(saga.run("saga", vec!["ignored".to_string()], &store));
+        assert_eq!(res, Err(SagaError::Compensated(1)));
+        assert!(store.load("saga").is_none());
+    }
+}
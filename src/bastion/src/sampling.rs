@@ -0,0 +1,149 @@
+//!
+//! Sampling of the tracing spans of the messages handled by the
+//! elements of children groups.
+//!
+//! The elements of a group handling messages with
+//! [`BastionContext::handle`] enter a `message` span while handling
+//! each of them. For the high-throughput groups, [`TraceSampling`]
+//! (set with [`Children::with_trace_sampling`]) limits the spans to
+//! a fraction of the messages, so that they don't drown the tracing
+//! backend. The messages are sampled evenly across the elements of
+//! the group (eg. every hundredth message with a rate of `0.01`),
+//! which keeps the spans representative of the whole traffic, and the
+//! messages whose handlers panic are traced whether they were sampled
+//! or not, unless configured otherwise.
+//!
+//! Custom instrumentation can follow the same decision with
+//! [`BastionContext::is_sampled`].
+//!
+//! [`BastionContext::handle`]: crate::context::BastionContext::handle
+//! [`BastionContext::is_sampled`]: crate::context::BastionContext::is_sampled
+//! [`Children::with_trace_sampling`]: crate::children::Children::with_trace_sampling
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Debug, Clone, PartialEq)]
+/// The fraction of the messages handled by the elements of a
+/// children group which are traced.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::sampling::TraceSampling;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// Bastion::children(|children| {
+///     children
+///         // Traces one message out of a thousand, and the ones
+///         // whose handlers panic.
+///         .with_trace_sampling(TraceSampling::new(0.001))
+///         .with_exec(|ctx: BastionContext| {
+///             async move {
+///                 loop {
+///                     let msg = ctx.recv().await?;
+///                     ctx.handle(msg, |msg| async move {
+///                         // ...
+///                     })
+///                     .await
+///                     .ok();
+///                 }
+///             }
+///         })
+/// }).expect("Couldn't create the children group.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct TraceSampling {
+    rate: f64,
+    errors: bool,
+}
+
+#[derive(Debug)]
+/// Decides which of the messages handled by the elements of a
+/// group are traced, shared by the elements.
+pub(crate) struct Sampler {
+    sampling: TraceSampling,
+    // The number of messages the elements took out of their
+    // mailboxes so far.
+    taken: AtomicU64,
+}
+
+impl TraceSampling {
+    /// Creates a sampling tracing the given fraction of the messages
+    /// (clamped between `0.0`, tracing none of them, and `1.0`,
+    /// tracing all of them), along with the ones whose handlers
+    /// panic.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate` - The fraction of the messages to trace.
+    pub fn new(rate: f64) -> Self {
+        TraceSampling {
+            rate: rate.clamp(0.0, 1.0),
+            errors: true,
+        }
+    }
+
+    /// Sets whether the messages whose handlers panic are traced
+    /// even if they weren't sampled (which they are by default).
+    pub fn with_errors_sampled(mut self, errors: bool) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// Returns the fraction of the messages which are traced.
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Returns whether the messages whose handlers panic are traced
+    /// even if they weren't sampled.
+    pub fn samples_errors(&self) -> bool {
+        self.errors
+    }
+}
+
+impl Default for TraceSampling {
+    fn default() -> Self {
+        TraceSampling::new(1.0)
+    }
+}
+
+impl Sampler {
+    pub(crate) fn new(sampling: TraceSampling) -> Self {
+        Sampler {
+            sampling,
+            taken: AtomicU64::new(0),
+        }
+    }
+
+    /// Records that an element took a message out of its mailbox,
+    /// returning whether it is sampled.
+    pub(crate) fn sample(&self) -> bool {
+        let taken = self.taken.fetch_add(1, Ordering::Relaxed);
+        // A message is sampled whenever the number of messages which
+        // should have been sampled so far grows.
+        let rate = self.sampling.rate;
+        (taken as f64 * rate).floor() < ((taken + 1) as f64 * rate).floor()
+    }
+
+    pub(crate) fn samples_errors(&self) -> bool {
+        self.sampling.errors
+    }
+}
@@ -0,0 +1,385 @@
+//!
+//! Messages sent after a delay, which can survive the restart of the
+//! process.
+//!
+//! A [`Scheduler`] sends a message to the target an alias (see
+//! [`Bastion::alias`]) points to once a delay elapsed, the alias being
+//! resolved when the message is sent so that it reaches the target even
+//! if it was restarted (or repointed) meanwhile.
+//!
+//! When given a [`SnapshotStore`] with [`Scheduler::with_store`], the
+//! scheduler saves the messages which weren't sent yet in it, and
+//! [`Scheduler::recover`] schedules them again after the process was
+//! restarted. The messages which should have been sent while it wasn't
+//! running are sent right away, unless a hook set with
+//! [`Scheduler::with_missed_hook`] decides otherwise.
+//!
+//! [`Bastion::alias`]: crate::Bastion::alias
+//! [`SnapshotStore`]: crate::persistence::SnapshotStore
+use crate::executor;
+use crate::persistence::SnapshotStore;
+use crate::Bastion;
+use futures_timer::Delay;
+use fxhash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, trace, warn};
+use uuid::Uuid;
+
+/// The id under which the scheduled messages are saved in the
+/// scheduler's store.
+const SNAPSHOT_ID: &str = "bastion_schedules";
+/// How late a message can be sent before being considered missed.
+const DEFAULT_GRACE: Duration = Duration::from_secs(1);
+
+type Store = Arc<dyn SnapshotStore<Vec<Scheduled>>>;
+type MissedHook = Arc<dyn Fn(&Scheduled) -> bool + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A message scheduled by a [`Scheduler`], which wasn't sent yet.
+pub struct Scheduled {
+    id: String,
+    target: String,
+    message: String,
+    at: SystemTime,
+}
+
+#[derive(Clone)]
+/// Sends messages to the targets of aliases after a delay, optionally
+/// saving them so that they are sent even if the process restarts
+/// meanwhile.
+///
+/// Cloning a `Scheduler` returns a new handle to the same scheduler.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::persistence::MemorySnapshotStore;
+/// # use bastion::schedule::Scheduler;
+/// # use std::time::Duration;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let reminders = Bastion::children(|children| children).unwrap();
+/// Bastion::alias("reminders", reminders);
+///
+/// let scheduler = Scheduler::new()
+///     // Usually backed by a database (eg. a `SledSnapshotStore`).
+///     .with_store(MemorySnapshotStore::new())
+///     .with_missed_hook(|scheduled| {
+///         // Drops the reminders missed by more than an hour.
+///         scheduled.at().elapsed().unwrap_or_default() < Duration::from_secs(3600)
+///     });
+///
+/// // Sends the messages which weren't sent before the process
+/// // restarted...
+/// scheduler.recover().expect("Couldn't recover the schedules.");
+///
+/// // ...and schedules new ones.
+/// scheduler
+///     .send_after("reminders", "Water the plants.", Duration::from_secs(60))
+///     .expect("Couldn't schedule the reminder.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Scheduler {
+    store: Option<Store>,
+    missed: Option<MissedHook>,
+    grace: Duration,
+    // The messages which weren't sent yet, by id, along with the
+    // sequence number of the last snapshot saved.
+    pending: Arc<Mutex<(FxHashMap<String, Scheduled>, u64)>>,
+}
+
+impl Scheduled {
+    /// Returns the id of the scheduled message, as returned by
+    /// [`Scheduler::send_after`].
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the name of the alias the message is sent to.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Returns the message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns when the message should be sent.
+    pub fn at(&self) -> SystemTime {
+        self.at
+    }
+}
+
+impl Scheduler {
+    /// Creates a new scheduler, which doesn't save its messages.
+    pub fn new() -> Self {
+        Scheduler {
+            store: None,
+            missed: None,
+            grace: DEFAULT_GRACE,
+            pending: Arc::new(Mutex::new((FxHashMap::default(), 0))),
+        }
+    }
+
+    /// Sets the store the messages which weren't sent yet are saved
+    /// in, so that they can be scheduled again with [`recover`] after
+    /// the process restarted.
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The store saving the scheduled messages.
+    ///
+    /// [`recover`]: Self::recover
+    pub fn with_store<S>(mut self, store: S) -> Self
+    where
+        S: SnapshotStore<Vec<Scheduled>>,
+    {
+        self.store = Some(Arc::new(store));
+        self
+    }
+
+    /// Sets the hook called with the recovered messages which should
+    /// have been sent while the process wasn't running, returning
+    /// whether they should still be sent (which they are if no hook
+    /// was set).
+    ///
+    /// # Arguments
+    ///
+    /// * `missed` - The closure deciding whether a missed message
+    ///   should still be sent.
+    pub fn with_missed_hook<F>(mut self, missed: F) -> Self
+    where
+        F: Fn(&Scheduled) -> bool + Send + Sync + 'static,
+    {
+        self.missed = Some(Arc::new(missed));
+        self
+    }
+
+    /// Sets how late a recovered message can be sent before being
+    /// considered missed (one second by default).
+    pub fn with_grace(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    /// Sends `message` to the target of the alias named `target` once
+    /// `delay` elapsed, returning the id of the scheduled message.
+    ///
+    /// This method returns `Err(())` if the message couldn't be saved
+    /// in the scheduler's store.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The name of the alias the message is sent to.
+    /// * `message` - The message to send.
+    /// * `delay` - How long to wait before sending the message.
+    #[allow(clippy::result_unit_err)]
+    pub fn send_after(
+        &self,
+        target: &str,
+        message: impl Into<String>,
+        delay: Duration,
+    ) -> Result<String, ()> {
+        let scheduled = Scheduled {
+            id: Uuid::new_v4().to_string(),
+            target: target.to_string(),
+            message: message.into(),
+            at: SystemTime::now() + delay,
+        };
+        let id = scheduled.id.clone();
+
+        trace!(
+            "Scheduler: Scheduling Scheduled({}) to '{}' in {:?}.",
+            id,
+            target,
+            delay
+        );
+        let saved = self.update(|pending| {
+            pending.insert(id.clone(), scheduled.clone());
+        });
+        if saved.is_err() {
+            warn!("Scheduler: Couldn't save Scheduled({}).", id);
+            // FIXME: panics?
+            self.pending.lock().unwrap().0.remove(&id);
+            return Err(());
+        }
+
+        self.arm(scheduled);
+
+        Ok(id)
+    }
+
+    /// Cancels the scheduled message with the given id, returning
+    /// whether it wasn't sent yet.
+    pub fn cancel(&self, id: &str) -> bool {
+        let mut cancelled = false;
+        let saved = self.update(|pending| {
+            cancelled = pending.remove(id).is_some();
+        });
+        if saved.is_err() {
+            warn!(
+                "Scheduler: Couldn't save the cancellation of Scheduled({}).",
+                id
+            );
+        }
+
+        cancelled
+    }
+
+    /// Schedules again the messages saved in the scheduler's store
+    /// which weren't sent before the process restarted, returning how
+    /// many of them were.
+    ///
+    /// The ones which should have been sent while the process wasn't
+    /// running are sent right away if the missed hook allows it, and
+    /// dropped otherwise.
+    #[allow(clippy::result_unit_err)]
+    pub fn recover(&self) -> Result<usize, ()> {
+        let store = match &self.store {
+            Some(store) => store,
+            None => return Ok(0),
+        };
+
+        let (seq, saved) = store.load(SNAPSHOT_ID)?.unwrap_or_default();
+        debug!("Scheduler: Recovering {} scheduled messages.", saved.len());
+        {
+            // FIXME: panics?
+            let mut pending = self.pending.lock().unwrap();
+            pending.1 = pending.1.max(seq);
+        }
+
+        let now = SystemTime::now();
+        let saved = saved
+            .into_iter()
+            .filter(|scheduled| {
+                let missed = scheduled.at + self.grace < now;
+                let send = !missed || self.missed.as_ref().is_none_or(|hook| hook(scheduled));
+                if !send {
+                    debug!("Scheduler: Dropping missed Scheduled({}).", scheduled.id);
+                }
+
+                send
+            })
+            .collect::<Vec<_>>();
+
+        let mut recovered = Vec::new();
+        self.update(|pending| {
+            for scheduled in saved {
+                if !pending.contains_key(&scheduled.id) {
+                    pending.insert(scheduled.id.clone(), scheduled.clone());
+                    recovered.push(scheduled);
+                }
+            }
+        })?;
+
+        let count = recovered.len();
+        for scheduled in recovered {
+            self.arm(scheduled);
+        }
+
+        Ok(count)
+    }
+
+    /// Returns the messages which weren't sent yet, sorted by when
+    /// they should be.
+    pub fn pending(&self) -> Vec<Scheduled> {
+        // FIXME: panics?
+        let pending = self.pending.lock().unwrap();
+        let mut pending = pending.0.values().cloned().collect::<Vec<_>>();
+        pending.sort_by_key(|scheduled| scheduled.at);
+
+        pending
+    }
+
+    // Changes the pending messages with `change`, and saves them in
+    // the store if there is one.
+    fn update<F>(&self, change: F) -> Result<(), ()>
+    where
+        F: FnOnce(&mut FxHashMap<String, Scheduled>),
+    {
+        // FIXME: panics?
+        let mut pending = self.pending.lock().unwrap();
+        change(&mut pending.0);
+
+        if let Some(store) = &self.store {
+            pending.1 += 1;
+            let saved = pending.0.values().cloned().collect();
+            store.save(SNAPSHOT_ID, pending.1, &saved)?;
+        }
+
+        Ok(())
+    }
+
+    // Sends the message once it is due, unless it was cancelled.
+    fn arm(&self, scheduled: Scheduled) {
+        let scheduler = self.clone();
+        executor::spawn(async move {
+            let delay = scheduled
+                .at
+                .duration_since(SystemTime::now())
+                .unwrap_or_default();
+            Delay::new(delay).await;
+
+            if !scheduler.cancel(&scheduled.id) {
+                return;
+            }
+
+            match Bastion::resolve(&scheduled.target) {
+                Some(target) => {
+                    trace!(
+                        "Scheduler: Sending Scheduled({}) to '{}'.",
+                        scheduled.id,
+                        scheduled.target
+                    );
+                    if target.tell_anonymously(scheduled.message).is_err() {
+                        warn!(
+                            "Scheduler: Couldn't send Scheduled({}) to '{}'.",
+                            scheduled.id, scheduled.target
+                        );
+                    }
+                }
+                None => warn!(
+                    "Scheduler: Dropping Scheduled({}): '{}' isn't an alias.",
+                    scheduled.id, scheduled.target
+                ),
+            }
+        });
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Scheduler::new()
+    }
+}
+
+impl Debug for Scheduler {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Scheduler")
+            .field("store", &self.store.is_some())
+            .field("grace", &self.grace)
+            .finish()
+    }
+}
@@ -0,0 +1,257 @@
+//!
+//! Persistence backends storing the events and the snapshots of the
+//! persistent actors in a [sled] database.
+//!
+//! The [`SledJournal`] and the [`SledSnapshotStore`] each store their
+//! records in their own tree of the database, the events being keyed
+//! by the id of their actor and their sequence number so that the
+//! events of an actor can be read back in order without scanning the
+//! others. The events and the snapshots are encoded in JSON.
+//!
+//! This module is available only with the `sled-store` feature flag.
+//!
+//! [sled]: https://docs.rs/sled
+use crate::persistence::{EventJournal, SnapshotStore};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::convert::TryInto;
+use tracing::{debug, trace, warn};
+
+/// The name of the tree the events are stored in.
+const JOURNAL_TREE: &str = "bastion_journal";
+/// The name of the tree the snapshots are stored in.
+const SNAPSHOTS_TREE: &str = "bastion_snapshots";
+
+#[derive(Debug, Clone)]
+/// An [`EventJournal`] storing the events in a tree of a sled database.
+///
+/// Cloning a `SledJournal` returns a new handle to the same tree.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use bastion::persistence::{Persistent, PersistentState, SnapshotPolicy};
+/// # use bastion::sled_store::{SledJournal, SledSnapshotStore};
+/// #
+/// # #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+/// # struct Counter(u64);
+/// #
+/// # impl Persistent for Counter {
+/// #     type Event = u64;
+/// #
+/// #     fn apply(&mut self, event: &u64) {
+/// #         self.0 += event;
+/// #     }
+/// # }
+/// #
+/// let db = sled::open("data/db").expect("Couldn't open the database.");
+/// let journal = SledJournal::new(&db).expect("Couldn't open the journal.");
+/// let snapshots = SledSnapshotStore::new(&db).expect("Couldn't open the store.");
+///
+/// let counter = PersistentState::recover(
+///     "counter",
+///     Counter::default(),
+///     journal,
+///     snapshots,
+///     SnapshotPolicy::new().with_every_events(1000),
+/// ).expect("Couldn't recover the counter.");
+/// ```
+pub struct SledJournal {
+    tree: sled::Tree,
+}
+
+#[derive(Debug, Clone)]
+/// A [`SnapshotStore`] storing the last snapshot of every actor in a
+/// tree of a sled database.
+///
+/// Cloning a `SledSnapshotStore` returns a new handle to the same
+/// tree.
+pub struct SledSnapshotStore {
+    tree: sled::Tree,
+}
+
+impl SledJournal {
+    /// Opens the journal stored in `db`, creating it if it doesn't
+    /// exist.
+    pub fn new(db: &sled::Db) -> sled::Result<Self> {
+        debug!("SledJournal: Opening.");
+        let tree = db.open_tree(JOURNAL_TREE)?;
+        Ok(SledJournal { tree })
+    }
+
+    // Returns the key of the `seq`-th event of the actor with the
+    // given id: the length of the id, the id, and the sequence
+    // number in big endian so that the keys are sorted by it.
+    fn key(id: &str, seq: u64) -> Vec<u8> {
+        let mut key = SledJournal::prefix(id);
+        key.extend_from_slice(&seq.to_be_bytes());
+        key
+    }
+
+    fn prefix(id: &str) -> Vec<u8> {
+        let mut prefix = Vec::with_capacity(8 + id.len() + 8);
+        prefix.extend_from_slice(&(id.len() as u64).to_be_bytes());
+        prefix.extend_from_slice(id.as_bytes());
+        prefix
+    }
+}
+
+impl<E> EventJournal<E> for SledJournal
+where
+    E: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    fn append(&self, id: &str, seq: u64, event: &E) -> Result<(), ()> {
+        let bytes = serde_json::to_vec(event).map_err(|err| {
+            warn!(
+                "SledJournal: Couldn't encode event #{} of {}: {}",
+                seq, id, err
+            );
+        })?;
+
+        self.tree
+            .insert(SledJournal::key(id, seq), bytes)
+            .and_then(|_| self.tree.flush())
+            .map(|_| ())
+            .map_err(|err| {
+                warn!(
+                    "SledJournal: Couldn't append event #{} of {}: {}",
+                    seq, id, err
+                );
+            })
+    }
+
+    fn events(&self, id: &str, after: u64) -> Result<Vec<(u64, E)>, ()> {
+        let prefix = SledJournal::prefix(id);
+        let mut events = Vec::new();
+        for entry in self
+            .tree
+            .range(SledJournal::key(id, after.saturating_add(1))..)
+        {
+            let (key, value) = entry.map_err(|err| {
+                warn!("SledJournal: Couldn't read the events of {}: {}", id, err);
+            })?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+
+            let seq = key[prefix.len()..]
+                .try_into()
+                .map(u64::from_be_bytes)
+                .map_err(|_| {
+                    warn!("SledJournal: Ignoring invalid key of {}.", id);
+                })?;
+            let event = serde_json::from_slice(&value).map_err(|err| {
+                warn!(
+                    "SledJournal: Couldn't decode event #{} of {}: {}",
+                    seq, id, err
+                );
+            })?;
+
+            events.push((seq, event));
+        }
+
+        trace!("SledJournal: Read {} events of {}.", events.len(), id);
+        Ok(events)
+    }
+}
+
+impl SledSnapshotStore {
+    /// Opens the store saving the snapshots in `db`, creating it if
+    /// it doesn't exist.
+    pub fn new(db: &sled::Db) -> sled::Result<Self> {
+        debug!("SledSnapshotStore: Opening.");
+        let tree = db.open_tree(SNAPSHOTS_TREE)?;
+        Ok(SledSnapshotStore { tree })
+    }
+}
+
+impl<S> SnapshotStore<S> for SledSnapshotStore
+where
+    S: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    fn save(&self, id: &str, seq: u64, state: &S) -> Result<(), ()> {
+        let mut bytes = seq.to_be_bytes().to_vec();
+        serde_json::to_writer(&mut bytes, state).map_err(|err| {
+            warn!(
+                "SledSnapshotStore: Couldn't encode snapshot of {}: {}",
+                id, err
+            );
+        })?;
+
+        self.tree
+            .insert(id.as_bytes(), bytes)
+            .and_then(|_| self.tree.flush())
+            .map(|_| ())
+            .map_err(|err| {
+                warn!(
+                    "SledSnapshotStore: Couldn't save snapshot #{} of {}: {}",
+                    seq, id, err
+                );
+            })
+    }
+
+    fn load(&self, id: &str) -> Result<Option<(u64, S)>, ()> {
+        let value = match self.tree.get(id.as_bytes()) {
+            Ok(Some(value)) => value,
+            Ok(None) => return Ok(None),
+            Err(err) => {
+                warn!(
+                    "SledSnapshotStore: Couldn't read snapshot of {}: {}",
+                    id, err
+                );
+                return Err(());
+            }
+        };
+
+        if value.len() < 8 {
+            warn!("SledSnapshotStore: Ignoring invalid snapshot of {}.", id);
+            return Ok(None);
+        }
+
+        let (seq, state) = value.split_at(8);
+        let seq = u64::from_be_bytes(seq.try_into().unwrap());
+        let state = serde_json::from_slice(state).map_err(|err| {
+            warn!(
+                "SledSnapshotStore: Couldn't decode snapshot of {}: {}",
+                id, err
+            );
+        })?;
+
+        Ok(Some((seq, state)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db() -> sled::Db {
+        sled::Config::new().temporary(true).open().unwrap()
+    }
+
+    #[test]
+    fn journal_round_trip() {
+        let journal = SledJournal::new(&db()).unwrap();
+        journal.append("a", 1, &10u64).unwrap();
+        journal.append("ab", 1, &20u64).unwrap();
+        journal.append("a", 2, &30u64).unwrap();
+
+        let events: Vec<(u64, u64)> = journal.events("a", 0).unwrap();
+        assert_eq!(events, vec![(1, 10), (2, 30)]);
+        let events: Vec<(u64, u64)> = journal.events("a", 1).unwrap();
+        assert_eq!(events, vec![(2, 30)]);
+        let events: Vec<(u64, u64)> = journal.events("ab", 0).unwrap();
+        assert_eq!(events, vec![(1, 20)]);
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let store = SledSnapshotStore::new(&db()).unwrap();
+        assert_eq!(SnapshotStore::<u64>::load(&store, "a").unwrap(), None);
+
+        store.save("a", 1, &10u64).unwrap();
+        store.save("a", 3, &30u64).unwrap();
+        assert_eq!(store.load("a").unwrap(), Some((3, 30u64)));
+        assert_eq!(SnapshotStore::<u64>::load(&store, "b").unwrap(), None);
+    }
+}
@@ -0,0 +1,533 @@
+//!
+//! Source children groups, reading the messages of a target children
+//! group from outside of the system.
+//!
+//! A [`LineSource`] reads the lines (or the frames ending with another
+//! delimiter) written to the standard input or to a named pipe, and
+//! routes them to the elements of a target group in turn. It runs as a
+//! children group of its own, so an error while reading its input
+//! faults it and it is restarted (reopening its input) like any other
+//! group, which makes it handy for CLI tools and log processors.
+//!
+//! Connectors to message brokers (eg. Kafka, AMQP or SQS) implement
+//! the [`MessageSource`] trait instead, and are run by a
+//! [`SourceGroup`], which polls them for batches of messages, routes
+//! the messages to the target group (only as fast as it can take them
+//! if it advertises its [`Demand`]), and commits their offsets once
+//! they were routed. A connector failing to poll or commit faults its
+//! group, which is restarted (reconnecting the connector) according
+//! to the restart strategy of its supervisor, and the messages whose
+//! offsets weren't committed are redelivered by the broker.
+//!
+//! [`Demand`]: crate::demand::Demand
+use crate::child_ref::ChildRef;
+use crate::children::Children;
+use crate::children_ref::ChildrenRef;
+use crate::context::{BastionContext, BastionId};
+use crate::demand::Demand;
+use crate::executor::blocking;
+use crate::message::Message;
+use crate::payload::Payload;
+use crate::supervisor::SupervisorRef;
+use crate::Bastion;
+use std::fmt::{self, Debug, Formatter};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{debug, trace, warn};
+
+/// The default maximum number of messages polled at once by a
+/// [`SourceGroup`].
+const DEFAULT_BATCH_SIZE: usize = 64;
+
+type Connect<S> = Arc<dyn Fn() -> Result<S, ()> + Send + Sync>;
+
+#[derive(Debug, Clone)]
+// Where a source reads its messages from.
+enum Input {
+    Stdin,
+    Pipe(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+/// A builder of a children group reading the lines or frames written
+/// to the standard input or to a named pipe, and routing them to the
+/// elements of a target group in turn.
+///
+/// The lines are sent as `String`s (without their line ending), and
+/// the frames (see [`with_delimiter`]) as [`Payload`]s (without their
+/// delimiter).
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::source::LineSource;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let parsers = Bastion::children(|children| {
+///     children.with_redundancy(4).with_exec(|ctx: BastionContext| {
+///         async move {
+///             loop {
+///                 msg! { ctx.recv().await?,
+///                     line: String => {
+///                         // Parses the log line...
+///                     };
+///                     _: _ => ();
+///                 }
+///             }
+///         }
+///     })
+/// }).expect("Couldn't create the children group.");
+///
+/// LineSource::pipe("/var/run/app.log.fifo")
+///     .spawn(&parsers)
+///     .expect("Couldn't spawn the source.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`with_delimiter`]: Self::with_delimiter
+pub struct LineSource {
+    input: Input,
+    // The byte ending the frames, if the input isn't read line by
+    // line.
+    delimiter: Option<u8>,
+}
+
+impl LineSource {
+    /// Creates a source reading the lines written to the standard
+    /// input, which stops once the standard input is closed.
+    ///
+    /// Note that the standard input can't be read again once it is
+    /// closed, and that it shouldn't be read by anything else.
+    pub fn stdin() -> Self {
+        LineSource {
+            input: Input::Stdin,
+            delimiter: None,
+        }
+    }
+
+    /// Creates a source reading the lines written to the named pipe
+    /// at `path`, which is reopened every time its writer closes it.
+    pub fn pipe(path: impl Into<PathBuf>) -> Self {
+        LineSource {
+            input: Input::Pipe(path.into()),
+            delimiter: None,
+        }
+    }
+
+    /// Makes the source read frames ending with `delimiter` and send
+    /// them as [`Payload`]s, instead of reading lines.
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = Some(delimiter);
+        self
+    }
+
+    /// Spawns the source as a children group of a single element,
+    /// routing the messages it reads to the elements of `target` in
+    /// turn.
+    ///
+    /// This method returns the [`ChildrenRef`] of the source if it
+    /// succeeded, or `Err(())` otherwise.
+    #[allow(clippy::result_unit_err)]
+    pub fn spawn(self, target: &ChildrenRef) -> Result<ChildrenRef, ()> {
+        let targets: Arc<[ChildRef]> = target.elems().into();
+
+        Bastion::spawn(move |ctx: BastionContext| {
+            let source = self.clone();
+            let targets = targets.clone();
+            async move {
+                let id = ctx.current().id().clone();
+                debug!("LineSource({}): Reading {:?}.", id, source.input);
+                blocking(async move { source.read(&id, &targets) })
+                    .await
+                    .unwrap_or(Err(()))
+            }
+        })
+    }
+
+    // Reads the input until it is closed (for the standard input)
+    // or until reading it fails, routing what it reads to `targets`.
+    fn read(&self, id: &BastionId, targets: &[ChildRef]) -> Result<(), ()> {
+        if targets.is_empty() {
+            warn!("LineSource({}): No element to route the messages to.", id);
+            return Err(());
+        }
+
+        let mut targets = Targets {
+            id,
+            targets,
+            next: 0,
+        };
+        let read = match &self.input {
+            Input::Stdin => {
+                let stdin = io::stdin();
+                let read = self.read_from(stdin.lock(), &mut targets);
+                debug!("LineSource({}): The standard input was closed.", id);
+                read
+            }
+            Input::Pipe(path) => loop {
+                // Blocks until a writer opens the pipe.
+                let pipe = match File::open(path) {
+                    Ok(pipe) => pipe,
+                    Err(err) => break Err(err),
+                };
+                if let Err(err) = self.read_from(BufReader::new(pipe), &mut targets) {
+                    break Err(err);
+                }
+
+                debug!(
+                    "LineSource({}): The writer closed {:?}, reopening.",
+                    id, path
+                );
+            },
+        };
+
+        read.map_err(|err| {
+            warn!(
+                "LineSource({}): Couldn't read {:?}: {}",
+                id, self.input, err
+            );
+        })
+    }
+
+    fn read_from(&self, mut input: impl BufRead, targets: &mut Targets) -> io::Result<()> {
+        let delimiter = self.delimiter.unwrap_or(b'\n');
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            if input.read_until(delimiter, &mut buf)? == 0 {
+                return Ok(());
+            }
+
+            if buf.last() == Some(&delimiter) {
+                buf.pop();
+            }
+            match self.delimiter {
+                Some(_) => targets.route(Payload::new(buf.as_slice())),
+                None => {
+                    if buf.last() == Some(&b'\r') {
+                        buf.pop();
+                    }
+                    targets.route(String::from_utf8_lossy(&buf).into_owned());
+                }
+            }
+        }
+    }
+}
+
+/// A connector to an external system (eg. a Kafka topic, an AMQP queue
+/// or an SQS queue) from which a [`SourceGroup`] polls messages.
+///
+/// The methods of a connector are called from the blocking thread pool,
+/// so they can use the blocking clients of the external systems.
+pub trait MessageSource: Send + 'static {
+    /// The type of the messages routed to the target group.
+    type Message: Message;
+
+    /// Polls the next batch of at most `max` messages, waiting for
+    /// some for a while (eg. up to a timeout) if none is available.
+    ///
+    /// An empty batch can be returned if no message became available
+    /// in the meantime, and an error makes the source group fault.
+    #[allow(clippy::result_unit_err)]
+    fn poll_batch(&mut self, max: usize) -> Result<Vec<Self::Message>, ()>;
+
+    /// Commits the offsets of the messages polled so far, which were
+    /// all routed to the target group.
+    ///
+    /// An error makes the source group fault, and the messages whose
+    /// offsets weren't committed are expected to be redelivered once
+    /// the connector reconnected.
+    #[allow(clippy::result_unit_err)]
+    fn commit(&mut self) -> Result<(), ()>;
+}
+
+/// A builder of a children group running a [`MessageSource`] and
+/// routing the messages it polls to the elements of a target group
+/// in turn.
+///
+/// The connector is created by the closure given to [`new`] every
+/// time the group is (re)started, so that it reconnects after a
+/// failure.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::demand::Demand;
+/// # use bastion::source::{MessageSource, SourceGroup};
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// struct Orders {
+///     // The client of the broker...
+/// }
+///
+/// impl MessageSource for Orders {
+///     type Message = String;
+///
+///     fn poll_batch(&mut self, max: usize) -> Result<Vec<String>, ()> {
+///         // Polls up to `max` orders from the broker...
+///         # Ok(vec![])
+///     }
+///
+///     fn commit(&mut self) -> Result<(), ()> {
+///         // Commits the offsets of the polled orders...
+///         # Ok(())
+///     }
+/// }
+///
+/// let demand = Demand::new();
+/// let handlers = Bastion::children(|children| {
+///     children
+///         .with_demand(demand.clone(), 16)
+///         .with_exec(|ctx| {
+///             async move {
+///                 // ...
+///                 # Ok(())
+///             }
+///         })
+/// }).expect("Couldn't create the children group.");
+///
+/// SourceGroup::new(|| {
+///     // Connects to the broker...
+///     Ok(Orders {})
+/// })
+/// .with_batch_size(128)
+/// .with_demand(demand)
+/// .spawn(&handlers)
+/// .expect("Couldn't spawn the source group.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`new`]: Self::new
+pub struct SourceGroup<S: MessageSource> {
+    connect: Connect<S>,
+    batch_size: usize,
+    demand: Option<Demand>,
+}
+
+impl<S: MessageSource> SourceGroup<S> {
+    /// Creates a source group whose connector is created by `connect`
+    /// every time the group is (re)started.
+    pub fn new<C>(connect: C) -> Self
+    where
+        C: Fn() -> Result<S, ()> + Send + Sync + 'static,
+    {
+        SourceGroup {
+            connect: Arc::new(connect),
+            batch_size: DEFAULT_BATCH_SIZE,
+            demand: None,
+        }
+    }
+
+    /// Sets the maximum number of messages polled at once (64 by
+    /// default).
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Makes the source group only poll as many messages as the target
+    /// group advertised it can take with `demand` (see
+    /// [`Children::with_demand`]), which should be the demand of the
+    /// target group.
+    pub fn with_demand(mut self, demand: Demand) -> Self {
+        self.demand = Some(demand);
+        self
+    }
+
+    /// Spawns the source group as a children group of a single element
+    /// supervised by the system supervisor, routing the messages to
+    /// the elements of `target`.
+    ///
+    /// This method returns the [`ChildrenRef`] of the source group if
+    /// it succeeded, or `Err(())` otherwise.
+    #[allow(clippy::result_unit_err)]
+    pub fn spawn(self, target: &ChildrenRef) -> Result<ChildrenRef, ()> {
+        let init = self.into_init(target);
+        Bastion::children(init)
+    }
+
+    /// Spawns the source group like [`spawn`] does, except that the
+    /// group is supervised by `supervisor` (eg. to restart it with a
+    /// back-off).
+    ///
+    /// [`spawn`]: Self::spawn
+    #[allow(clippy::result_unit_err)]
+    pub fn spawn_in(
+        self,
+        supervisor: &SupervisorRef,
+        target: &ChildrenRef,
+    ) -> Result<ChildrenRef, ()> {
+        let init = self.into_init(target);
+        supervisor.children(init)
+    }
+
+    fn into_init(self, target: &ChildrenRef) -> impl FnOnce(Children) -> Children {
+        let targets: Arc<[ChildRef]> = target.elems().into();
+        let SourceGroup {
+            connect,
+            batch_size,
+            demand,
+        } = self;
+
+        move |children: Children| {
+            children
+                .with_redundancy(1)
+                .with_exec(move |ctx: BastionContext| {
+                    let connect = connect.clone();
+                    let demand = demand.clone();
+                    let targets = targets.clone();
+                    async move {
+                        let id = ctx.current().id().clone();
+                        run(&id, connect, batch_size, demand, &targets).await
+                    }
+                })
+        }
+    }
+}
+
+// Connects the connector, then polls it, routes the messages it polls
+// and commits their offsets until it fails.
+async fn run<S: MessageSource>(
+    id: &BastionId,
+    connect: Connect<S>,
+    batch_size: usize,
+    demand: Option<Demand>,
+    targets: &[ChildRef],
+) -> Result<(), ()> {
+    if targets.is_empty() {
+        warn!("SourceGroup({}): No element to route the messages to.", id);
+        return Err(());
+    }
+
+    debug!("SourceGroup({}): Connecting.", id);
+    let mut source = blocking(async move { connect() })
+        .await
+        .unwrap_or(Err(()))
+        .map_err(|_| warn!("SourceGroup({}): Couldn't connect.", id))?;
+
+    let mut targets = Targets {
+        id,
+        targets,
+        next: 0,
+    };
+    loop {
+        let max = match &demand {
+            Some(demand) => {
+                demand.acquire().await;
+                let mut max = 1;
+                while max < batch_size && demand.try_acquire() {
+                    max += 1;
+                }
+                max
+            }
+            None => batch_size,
+        };
+
+        let (polled, batch) = blocking(async move {
+            let batch = source.poll_batch(max);
+            (source, batch)
+        })
+        .await
+        .ok_or(())?;
+        source = polled;
+
+        let batch = batch.map_err(|_| warn!("SourceGroup({}): Couldn't poll.", id))?;
+        trace!("SourceGroup({}): Polled {} messages.", id, batch.len());
+        if let Some(demand) = &demand {
+            // Gives back the demand which wasn't used.
+            demand.request(max.saturating_sub(batch.len()));
+        }
+        if batch.is_empty() {
+            continue;
+        }
+
+        for msg in batch {
+            targets.route(msg);
+        }
+
+        let (committed, commit) = blocking(async move {
+            let commit = source.commit();
+            (source, commit)
+        })
+        .await
+        .ok_or(())?;
+        source = committed;
+
+        commit.map_err(|_| warn!("SourceGroup({}): Couldn't commit.", id))?;
+    }
+}
+
+impl<S: MessageSource> Debug for SourceGroup<S> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("SourceGroup")
+            .field("batch_size", &self.batch_size)
+            .field("demand", &self.demand)
+            .finish()
+    }
+}
+
+// Sends the messages to the elements of the target group in turn.
+struct Targets<'a> {
+    id: &'a BastionId,
+    targets: &'a [ChildRef],
+    next: usize,
+}
+
+impl Targets<'_> {
+    fn route<M: Message>(&mut self, msg: M) {
+        let target = &self.targets[self.next % self.targets.len()];
+        self.next = self.next.wrapping_add(1);
+
+        trace!(
+            "Source({}): Routing message to: {:?}",
+            self.id,
+            target.path()
+        );
+        if let Err(msg) = target.tell_anonymously(msg) {
+            warn!(
+                "Source({}): Couldn't route message to: {:?}: {:?}",
+                self.id,
+                target.path(),
+                msg
+            );
+        }
+    }
+}
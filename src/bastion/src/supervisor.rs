@@ -3,27 +3,38 @@
 //! or other supervisor trees under themselves.
 use crate::broadcast::{Broadcast, Parent, Sender};
 use crate::callbacks::Callbacks;
-use crate::children::Children;
+use crate::children::{Children, RestartState};
 use crate::children_ref::ChildrenRef;
+use crate::circuit_breaker::CircuitState;
 use crate::context::{BastionId, ContextState};
+use crate::dispatcher::{Dispatcher, DispatcherType};
 use crate::envelope::Envelope;
+use crate::errors::ExecError;
 use crate::message::{BastionMessage, Deployment, Message};
 use crate::path::{BastionPath, BastionPathElement};
+use crate::system::SYSTEM;
+use crate::tree::{GroupNode, SupervisorNode, SupervisorStats};
 
 use bastion_executor::pool;
+use futures::future::{self, Either};
 use futures::prelude::*;
 use futures::stream::FuturesOrdered;
 use futures::{pending, poll};
 use futures_timer::Delay;
 use fxhash::FxHashMap;
 use lightproc::prelude::*;
+use pin_utils::pin_mut;
+use std::any::Any;
 use std::cmp::{Eq, PartialEq};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
 use std::ops::Range;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::task::Poll;
-use std::time::Duration;
-use tracing::{debug, trace, warn};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, trace, warn};
 
 #[derive(Debug)]
 /// A supervisor that can supervise both [`Children`] and other
@@ -96,6 +107,17 @@ pub struct Supervisor {
     killed: FxHashMap<BastionId, Supervised>,
     strategy: SupervisionStrategy,
     restart_strategy: RestartStrategy,
+    shutdown_order: ShutdownOrder,
+    // How long the supervised elements are given to stop once
+    // told to stop or to be killed, after which they are killed
+    // and given up on, if they are.
+    stop_timeout: Option<Duration>,
+    // The decider choosing what to do about the faults of
+    // the supervised elements, if any.
+    decider: Option<Arc<dyn SupervisionDecider>>,
+    // The directives mapped from the errors returned by the
+    // supervised elements, tried before the decider.
+    error_directives: ErrorDirectives,
     // The callbacks called at the supervisor's different
     // lifecycle events.
     callbacks: Callbacks,
@@ -112,8 +134,72 @@ pub struct Supervisor {
     subtree_restarts: usize,
     // Store the maximum acceptable restarts for the supervisor.
     subtree_restarts_limit: usize,
+    // Whether the restarts are paused, in which case the faults
+    // of the supervised elements are queued until they are resumed.
+    restarts_paused: bool,
+    // The identifiers of the faulted elements and of their parents,
+    // in the order they faulted while the restarts were paused.
+    queued_restarts: Vec<(BastionId, BastionId, FaultReason)>,
+    // The times the supervisor recovered from the faults of the
+    // supervised elements, within the window of its restart
+    // intensity.
+    recoveries: VecDeque<Instant>,
+    // The states handed over by the supervised elements to their
+    // successors.
+    handovers: Arc<Handovers>,
+    // The names of the dispatcher scopes the supervisor is in, from
+    // the outermost to the innermost one.
+    dispatcher_scope: Vec<String>,
+    // The dispatchers every children group of the subtree is
+    // registered with, set on the enclosing supervisors.
+    #[allow(clippy::redundant_allocation)]
+    inherited_dispatchers: Vec<Arc<Box<Dispatcher>>>,
+    // The dispatchers every children group of the subtree is
+    // registered with, set on this supervisor.
+    #[allow(clippy::redundant_allocation)]
+    dispatchers: Vec<Arc<Box<Dispatcher>>>,
+    // The supervised children groups which only start once other
+    // groups started, along with those groups.
+    start_after: FxHashMap<BastionId, (ChildrenRef, Vec<ChildrenRef>)>,
+    // The faults of the subtree the supervisor is in, if it aborts
+    // the process once they are repeated too often.
+    abort: Option<Arc<AbortState>>,
+    // The live counterpart of the supervisor's snapshot in the
+    // supervision tree.
+    tree: Arc<SupervisorNode>,
+    // The policy breaking the restarts of the supervised children
+    // groups which keep faulting, if any.
+    circuit: Option<RestartCircuit>,
+    // The circuits of the supervised children groups which faulted.
+    circuits: FxHashMap<BastionId, GroupCircuit>,
+    // Whether the restarted elements of the supervised children
+    // groups keep the state of the ones they replace, unless the
+    // groups set it themselves.
+    restart_state: RestartState,
+    // The name the supervisor can be looked up by, if any.
+    name: Option<String>,
 }
 
+/// Maps an error returned by a supervised element to a directive.
+type ErrorDirective = Arc<dyn Fn(&ExecError) -> Option<Directive> + Send + Sync>;
+
+#[derive(Default)]
+/// The closures mapping the errors returned by the supervised
+/// elements to directives, in the order they were added.
+struct ErrorDirectives(Vec<ErrorDirective>);
+
+#[derive(Default)]
+/// The states handed over by the elements supervised by a supervisor
+/// to their successors, by key.
+pub(crate) struct Handovers(Mutex<FxHashMap<String, Box<dyn Any + Send>>>);
+
+#[derive(Debug, Default)]
+/// The supervisors given a name, which can be looked up with
+/// [`Bastion::find_supervisor`].
+///
+/// [`Bastion::find_supervisor`]: crate::Bastion::find_supervisor
+pub(crate) struct NamedSupervisors(Mutex<FxHashMap<String, SupervisorRef>>);
+
 #[derive(Debug, Clone)]
 struct TrackedChildState {
     id: BastionId,
@@ -121,6 +207,20 @@ struct TrackedChildState {
     restarts_counts: usize,
 }
 
+#[derive(Debug)]
+struct GroupCircuit {
+    state: CircuitState,
+    // The number of consecutive faults.
+    failures: usize,
+    last_fault: Option<Instant>,
+    // The instant before which the timeouts scheduled for the
+    // circuit are stale.
+    deadline: Instant,
+    // The faulted elements waiting for the circuit to be half-open
+    // to be restarted.
+    parked: Vec<(BastionId, FaultReason)>,
+}
+
 #[derive(Debug)]
 enum RestartedElement {
     Supervisor(BastionId),
@@ -130,7 +230,7 @@ enum RestartedElement {
 #[derive(Debug)]
 enum ActorSearchMethod {
     OneActor { id: BastionId, parent_id: BastionId },
-    FromActor { id: BastionId, parent_id: BastionId },
+    FromActor { parent_id: BastionId },
     All,
 }
 
@@ -143,6 +243,12 @@ pub struct SupervisorRef {
     id: BastionId,
     sender: Sender,
     path: Arc<BastionPath>,
+    handovers: Arc<Handovers>,
+    dispatcher_scope: Vec<String>,
+    #[allow(clippy::redundant_allocation)]
+    dispatchers: Vec<Arc<Box<Dispatcher>>>,
+    abort: Option<Arc<AbortState>>,
+    tree: Arc<SupervisorNode>,
 }
 
 #[derive(Debug, Clone)]
@@ -168,10 +274,320 @@ pub enum SupervisionStrategy {
     /// group and all the ones that were added to the
     /// supervisor after it are restarted (even those which
     /// were stopped) in the same order they were added to
-    /// the supervisor.
+    /// the supervisor. All the elements of the group are
+    /// restarted, and the supervisors added after it restart
+    /// their whole subtree.
     RestForOne,
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+/// The order in which a supervisor stops its supervised
+/// children groups and supervisors when it stops.
+///
+/// The default order is `Concurrent`.
+pub enum ShutdownOrder {
+    /// All the supervised children groups and supervisors
+    /// are told to stop at once, and stop concurrently.
+    #[default]
+    Concurrent,
+    /// The supervised children groups and supervisors are
+    /// stopped one after the other, in the reverse order
+    /// they were added to the supervisor, each one being
+    /// told to stop once the previous one stopped (eg. so
+    /// that consumers stop before their producers).
+    ReverseStart,
+}
+
+#[derive(Debug, Clone)]
+/// The information about the fault of a supervised element,
+/// given to a [`SupervisionDecider`].
+pub struct Fault {
+    // The faulted element.
+    child: BastionId,
+    // The children group of the faulted element.
+    group: BastionId,
+    // How many times the faulted element was restarted.
+    restarts: usize,
+    // Why the element faulted.
+    reason: FaultReason,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+/// Why an element of a children group faulted, as told to its
+/// supervisor.
+pub enum FaultReason {
+    /// The element panicked.
+    Panicked {
+        /// The message the element panicked with, if its payload
+        /// was a string.
+        message: Option<String>,
+        /// Where the element panicked (`file:line:column`), if known.
+        location: Option<String>,
+    },
+    /// The future of the element returned `Err(())`.
+    Errored,
+    /// The future of the element returned the given error (see
+    /// [`Children::with_fallible_exec`]).
+    ///
+    /// [`Children::with_fallible_exec`]: crate::children::Children::with_fallible_exec
+    Failed(ExecError),
+    /// The element didn't handle any message for too long (see
+    /// [`Children::with_stalled_restart`]).
+    ///
+    /// [`Children::with_stalled_restart`]: crate::children::Children::with_stalled_restart
+    Stalled,
+    /// The element stopped while it is restarted whenever it does
+    /// (see [`ChildRestart::Permanent`]).
+    ///
+    /// [`ChildRestart::Permanent`]: crate::children::ChildRestart::Permanent
+    Stopped,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// What a supervisor should do about the fault of one of
+/// its supervised elements, as decided by a
+/// [`SupervisionDecider`].
+pub enum Directive {
+    /// Restarts the faulted element as the supervisor's
+    /// [`SupervisionStrategy`] and [`RestartStrategy`] would.
+    Restart,
+    /// Stops the whole children group of the faulted element.
+    Stop,
+    /// Stops the supervisor and its whole subtree, and lets
+    /// its own supervisor decide what to do about it.
+    Escalate,
+    /// Drops the faulted element without restarting it,
+    /// leaving the other elements of its group running.
+    Ignore,
+}
+
+/// Decides what a supervisor should do when one of its
+/// supervised elements faults, allowing to implement
+/// supervision logic that the [`SupervisionStrategy`]
+/// variants can't express.
+///
+/// A decider is attached to a supervisor with
+/// [`Supervisor::with_decider`].
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// #[derive(Debug)]
+/// struct GiveUpAfter(usize);
+///
+/// impl SupervisionDecider for GiveUpAfter {
+///     fn decide(&self, fault: &Fault) -> Directive {
+///         if fault.restarts() < self.0 {
+///             Directive::Restart
+///         } else {
+///             Directive::Escalate
+///         }
+///     }
+/// }
+/// ```
+pub trait SupervisionDecider: Debug + Send + Sync + 'static {
+    /// Returns the [`Directive`] the supervisor should follow
+    /// about the given fault.
+    fn decide(&self, fault: &Fault) -> Directive;
+}
+
+impl Fault {
+    /// Returns the identifier of the faulted element.
+    pub fn child(&self) -> &BastionId {
+        &self.child
+    }
+
+    /// Returns the identifier of the children group of the
+    /// faulted element.
+    pub fn group(&self) -> &BastionId {
+        &self.group
+    }
+
+    /// Returns how many times the faulted element was already
+    /// restarted.
+    pub fn restarts(&self) -> usize {
+        self.restarts
+    }
+
+    /// Returns why the element faulted.
+    pub fn reason(&self) -> &FaultReason {
+        &self.reason
+    }
+}
+
+impl Display for FaultReason {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            FaultReason::Panicked { message, location } => {
+                write!(fmt, "Panicked")?;
+                if let Some(message) = message {
+                    write!(fmt, " with '{}'", message)?;
+                }
+                if let Some(location) = location {
+                    write!(fmt, " at {}", location)?;
+                }
+
+                Ok(())
+            }
+            FaultReason::Errored => write!(fmt, "Returned an error"),
+            FaultReason::Failed(error) => write!(fmt, "Failed with '{}'", error),
+            FaultReason::Stalled => write!(fmt, "Stalled"),
+            FaultReason::Stopped => write!(fmt, "Stopped"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// How many faults the elements of a supervision subtree can have
+/// within a window of time before the whole process is aborted,
+/// instead of being supervised again.
+///
+/// This is meant for the deployments where an orchestrator (eg. a
+/// container scheduler) should take over the restarts once the
+/// supervisors can't keep the subtree running.
+pub struct AbortPolicy {
+    max_faults: usize,
+    window: Duration,
+}
+
+#[derive(Clone)]
+/// A circuit breaking the restarts of the elements of a children
+/// group which keeps faulting, set with
+/// [`Supervisor::with_restart_circuit`].
+///
+/// Once the elements of a group faulted `failure_threshold` times in
+/// a row (each fault happening less than `cooldown` after the
+/// previous one), the circuit of the group *opens*: its faulted
+/// elements are parked instead of being restarted, and the messages
+/// sent to them are dropped. After `cooldown`, the circuit becomes
+/// *half-open* and the parked elements are restarted: the circuit
+/// *closes* again if none of them faults for another `cooldown`, or
+/// opens again otherwise.
+pub struct RestartCircuit {
+    failure_threshold: usize,
+    cooldown: Duration,
+    on_transition: Option<TransitionCallback>,
+}
+
+type TransitionCallback = Arc<dyn Fn(&BastionId, CircuitState, CircuitState) + Send + Sync>;
+
+#[derive(Debug)]
+/// The faults of a supervision subtree aborting the process, shared
+/// by its supervisors.
+pub(crate) struct AbortState {
+    policy: AbortPolicy,
+    // The times of the faults within the window of the policy.
+    faults: Mutex<VecDeque<Instant>>,
+}
+
+impl AbortPolicy {
+    /// Creates a policy aborting the process once the subtree had
+    /// more than `max_faults` faults within `window`.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_faults` - The number of faults the subtree can have
+    ///   within the window.
+    /// * `window` - The duration the faults are counted over.
+    pub fn new(max_faults: usize, window: Duration) -> Self {
+        AbortPolicy { max_faults, window }
+    }
+
+    /// Returns the number of faults the subtree can have within the
+    /// window.
+    pub fn max_faults(&self) -> usize {
+        self.max_faults
+    }
+
+    /// Returns the duration the faults are counted over.
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+}
+
+impl RestartCircuit {
+    /// Creates a circuit opening once the elements of a group
+    /// faulted `failure_threshold` times in a row, and staying open
+    /// for `cooldown`.
+    ///
+    /// # Arguments
+    ///
+    /// * `failure_threshold` - The number of consecutive faults
+    ///   opening the circuit.
+    /// * `cooldown` - How long the circuit stays open before the
+    ///   parked elements are restarted.
+    pub fn new(failure_threshold: usize, cooldown: Duration) -> Self {
+        RestartCircuit {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            on_transition: None,
+        }
+    }
+
+    /// Sets the callback called with the identifier of a group, and
+    /// the previous and the new states of its circuit every time it
+    /// changes.
+    pub fn with_on_transition<C>(mut self, callback: C) -> Self
+    where
+        C: Fn(&BastionId, CircuitState, CircuitState) + Send + Sync + 'static,
+    {
+        self.on_transition = Some(Arc::new(callback));
+        self
+    }
+
+    /// Returns the number of consecutive faults opening the circuit.
+    pub fn failure_threshold(&self) -> usize {
+        self.failure_threshold
+    }
+
+    /// Returns how long the circuit stays open before the parked
+    /// elements are restarted.
+    pub fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+}
+
+impl GroupCircuit {
+    fn new() -> Self {
+        GroupCircuit {
+            state: CircuitState::Closed,
+            failures: 0,
+            last_fault: None,
+            deadline: Instant::now(),
+            parked: Vec::new(),
+        }
+    }
+}
+
+impl AbortState {
+    fn new(policy: AbortPolicy) -> Self {
+        AbortState {
+            policy,
+            faults: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Records a fault, returning whether the subtree had too many
+    /// of them within the window.
+    fn fault(&self) -> bool {
+        let now = Instant::now();
+        // FIXME: panics?
+        let mut faults = self.faults.lock().unwrap();
+        while let Some(fault) = faults.front() {
+            if now.duration_since(*fault) < self.policy.window {
+                break;
+            }
+
+            faults.pop_front();
+        }
+
+        faults.push_back(now);
+        faults.len() > self.policy.max_faults
+    }
+}
+
 #[derive(Debug)]
 enum Supervised {
     Supervisor(Supervisor),
@@ -204,6 +620,9 @@ pub enum RestartPolicy {
 pub struct RestartStrategy {
     restart_policy: RestartPolicy,
     strategy: ActorRestartStrategy,
+    // The maximum number of recoveries within a window of time,
+    // if bounded.
+    intensity: Option<(usize, Duration)>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -234,6 +653,22 @@ pub enum ActorRestartStrategy {
         /// Defines a multiplier how fast the timeout will be increasing.
         multiplier: f64,
     },
+    /// Restart an actor after a delay doubling after every restart,
+    /// up to `max`, and randomly shortened by up to the `jitter`
+    /// fraction of it so that the actors faulting together (eg.
+    /// because a downstream service is down) don't restart in
+    /// lockstep.
+    /// A `jitter` of `0.0` makes the delays deterministic, and one
+    /// of `1.0` makes them range from zero to the full delay.
+    ExponentialBackOffWithJitter {
+        /// An initial delay before the restarting an actor.
+        timeout: Duration,
+        /// The upper bound of the delay between two restarts.
+        max: Duration,
+        /// The fraction of the delay which is randomly removed from
+        /// it, between `0.0` and `1.0`.
+        jitter: f64,
+    },
 }
 
 impl ActorRestartStrategy {
@@ -252,6 +687,27 @@ impl ActorRestartStrategy {
                 let delay = timeout.mul_f64(factor);
                 Some(timeout + delay)
             }
+            ActorRestartStrategy::ExponentialBackOffWithJitter {
+                timeout,
+                max,
+                jitter,
+            } => {
+                let factor = 2f64.powi(restarts_count.min(i32::MAX as usize) as i32);
+                let delay = timeout.as_secs_f64() * factor;
+                let delay = if delay.is_finite() && delay < max.as_secs_f64() {
+                    Duration::from_secs_f64(delay)
+                } else {
+                    max
+                };
+
+                let jitter = jitter.clamp(0.0, 1.0);
+                if jitter == 0.0 {
+                    return Some(delay);
+                }
+
+                let random: f64 = rand::random();
+                Some(delay.mul_f64(1.0 - jitter * random))
+            }
             _ => None,
         }
     }
@@ -268,12 +724,33 @@ impl Supervisor {
         let killed = FxHashMap::default();
         let strategy = SupervisionStrategy::default();
         let restart_strategy = RestartStrategy::default();
+        let shutdown_order = ShutdownOrder::default();
+        let stop_timeout = None;
+        let decider = None;
+        let error_directives = ErrorDirectives::default();
         let callbacks = Callbacks::new();
         let is_system_supervisor = false;
         let pre_start_msgs = Vec::new();
         let started = false;
         let subtree_restarts = 0;
         let subtree_restarts_limit = 3;
+        let restarts_paused = false;
+        let queued_restarts = Vec::new();
+        let recoveries = VecDeque::new();
+        let handovers = Arc::new(Handovers::default());
+        let dispatcher_scope = Vec::new();
+        let inherited_dispatchers = Vec::new();
+        let dispatchers = Vec::new();
+        let start_after = FxHashMap::default();
+        let abort = None;
+        let tree = Arc::new(SupervisorNode::new(
+            bcast.id().clone(),
+            bcast.path().clone(),
+        ));
+        let circuit = None;
+        let restart_state = RestartState::default();
+        let circuits = FxHashMap::default();
+        let name = None;
 
         Supervisor {
             bcast,
@@ -285,12 +762,30 @@ impl Supervisor {
             killed,
             strategy,
             restart_strategy,
+            shutdown_order,
+            stop_timeout,
+            decider,
+            error_directives,
             callbacks,
             is_system_supervisor,
             pre_start_msgs,
             started,
             subtree_restarts,
             subtree_restarts_limit,
+            restarts_paused,
+            queued_restarts,
+            recoveries,
+            handovers,
+            dispatcher_scope,
+            inherited_dispatchers,
+            dispatchers,
+            start_after,
+            abort,
+            tree,
+            circuit,
+            restart_state,
+            circuits,
+            name,
         }
     }
 
@@ -327,6 +822,10 @@ impl Supervisor {
 
         if let Some(bcast) = bcast {
             self.bcast = bcast;
+            self.tree
+                .reset(self.bcast.id().clone(), self.bcast.path().clone());
+            // The name now has to be resolved to the new identifier.
+            self.register_name();
         } else {
             self.bcast.clear_children();
         }
@@ -339,6 +838,10 @@ impl Supervisor {
         self.pre_start_msgs.clear();
         self.pre_start_msgs.shrink_to_fit();
 
+        // Every element is going to be restarted anyway.
+        self.queued_restarts.clear();
+        self.circuits.clear();
+
         let restarted_objects = self.search_restarted_objects(ActorSearchMethod::All);
         self.restart(restarted_objects).await;
 
@@ -409,6 +912,10 @@ impl Supervisor {
         &self.callbacks
     }
 
+    pub(crate) fn tree(&self) -> &Arc<SupervisorNode> {
+        &self.tree
+    }
+
     pub(crate) fn as_ref(&self) -> SupervisorRef {
         trace!(
             "Supervisor({}): Creating new SupervisorRef({}).",
@@ -419,8 +926,22 @@ impl Supervisor {
         let id = self.bcast.id().clone();
         let sender = self.bcast.sender().clone();
         let path = self.bcast.path().clone();
+        let handovers = self.handovers.clone();
+        let dispatcher_scope = self.dispatcher_scope.clone();
+        let dispatchers = self.subtree_dispatchers();
+        let abort = self.abort.clone();
+        let tree = self.tree.clone();
 
-        SupervisorRef::new(id, sender, path)
+        SupervisorRef::new(
+            id,
+            sender,
+            path,
+            handovers,
+            dispatcher_scope,
+            dispatchers,
+            abort,
+            tree,
+        )
     }
 
     /// Creates a new supervisor, passes it through the specified
@@ -482,9 +1003,13 @@ impl Supervisor {
             self.id(),
             bcast.id()
         );
-        let supervisor = Supervisor::new(bcast);
+        let supervisor = Supervisor::new(bcast)
+            .in_dispatcher_scope(self.dispatcher_scope.clone())
+            .in_supervisor_dispatchers(self.subtree_dispatchers())
+            .in_abort_scope(self.abort.clone());
         let supervisor = init(supervisor);
         debug!("Supervisor({}): Initialized.", supervisor.id());
+        supervisor.register_name();
 
         debug!(
             "Supervisor({}): Deploying Supervisor({}).",
@@ -558,9 +1083,13 @@ impl Supervisor {
             self.id(),
             bcast.id()
         );
-        let supervisor = Supervisor::new(bcast);
+        let supervisor = Supervisor::new(bcast)
+            .in_dispatcher_scope(self.dispatcher_scope.clone())
+            .in_supervisor_dispatchers(self.subtree_dispatchers())
+            .in_abort_scope(self.abort.clone());
         let supervisor = init(supervisor);
         debug!("Supervisor({}): Initialized.", supervisor.id());
+        supervisor.register_name();
         let supervisor_ref = supervisor.as_ref();
 
         debug!(
@@ -644,7 +1173,9 @@ impl Supervisor {
             self.id(),
             bcast.id()
         );
-        let children = Children::new(bcast);
+        let children = Children::new(bcast)
+            .in_dispatcher_scope(self.dispatcher_scope.clone())
+            .in_supervisor_dispatchers(self.subtree_dispatchers());
         let mut children = init(children);
         debug!("Children({}): Initialized.", children.id());
         // FIXME: children group elems launched without the group itself being launched
@@ -733,10 +1264,15 @@ impl Supervisor {
             self.id(),
             bcast.id()
         );
-        let children = Children::new(bcast);
+        let children = Children::new(bcast)
+            .in_dispatcher_scope(self.dispatcher_scope.clone())
+            .in_supervisor_dispatchers(self.subtree_dispatchers());
         let mut children = init(children);
         debug!("Children({}): Initialized.", children.id());
         // FIXME: children group elems launched without the group itself being launched
+        if let Err(e) = children.register_dispatchers() {
+            warn!("couldn't register all dispatchers into the registry: {}", e);
+        };
         children.launch_elems();
 
         let children_ref = children.as_ref();
@@ -816,6 +1352,119 @@ impl Supervisor {
         self
     }
 
+    /// Sets the order in which the supervisor should stop its
+    /// supervised children groups and supervisors when it stops
+    /// (see [`ShutdownOrder`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `shutdown_order` - The order in which the supervised
+    ///   children groups and supervisors should be stopped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::supervisor(|sp| {
+    ///     sp.with_shutdown_order(ShutdownOrder::ReverseStart)
+    ///         // The producers are started first and stopped last...
+    ///         .children(|children| children)
+    ///         // ...and the consumers the other way around.
+    ///         .children(|children| children)
+    /// }).expect("Couldn't create the supervisor.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_shutdown_order(mut self, shutdown_order: ShutdownOrder) -> Self {
+        trace!(
+            "Supervisor({}): Setting shutdown order: {:?}",
+            self.id(),
+            shutdown_order
+        );
+        self.shutdown_order = shutdown_order;
+        self
+    }
+
+    /// Sets how long the children groups and supervisors supervised
+    /// by this supervisor are given to stop once told to stop or to
+    /// be killed, instead of waiting for them as long as it takes.
+    ///
+    /// Those still running after `timeout` (eg. because the future
+    /// of one of their elements is stuck in blocking code) are
+    /// logged, killed and given up on, so that they can't stall the
+    /// teardown of the whole supervision tree. They are then not
+    /// restarted when the supervisor recovers from a fault.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long the supervised elements are given to
+    ///   stop.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::supervisor(|sp| {
+    ///     sp.with_stop_timeout(Duration::from_secs(10))
+    ///         .children(|children| {
+    ///             children.with_exec(|ctx: BastionContext| {
+    ///                 async move {
+    ///                     // Might never return...
+    ///                     Ok(())
+    ///                 }
+    ///             })
+    ///         })
+    /// }).expect("Couldn't create the supervisor.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_stop_timeout(mut self, timeout: Duration) -> Self {
+        trace!(
+            "Supervisor({}): Setting the stop timeout to {:?}.",
+            self.id(),
+            timeout
+        );
+        self.stop_timeout = Some(timeout);
+        self
+    }
+
     /// Sets the actor restart strategy the supervisor should use
     /// of its supervised children groups or supervisors dies to
     /// restore in the correct state.
@@ -870,16 +1519,17 @@ impl Supervisor {
         self
     }
 
-    /// Sets the callbacks that will get called at this supervisor's
-    /// different lifecycle events.
+    /// Sets the [`SupervisionDecider`] deciding what this
+    /// supervisor should do when one of its supervised elements
+    /// faults, instead of always restarting it.
     ///
-    /// See [`Callbacks`]'s documentation for more information about the
-    /// different callbacks available.
+    /// When the decider returns [`Directive::Restart`], the
+    /// elements are restarted according to the supervisor's
+    /// [`SupervisionStrategy`] and [`RestartStrategy`].
     ///
     /// # Arguments
     ///
-    /// * `callbacks` - The callbacks that will get called for this
-    ///     supervisor.
+    /// * `decider` - The decider the supervisor should use.
     ///
     /// # Example
     ///
@@ -900,67 +1550,659 @@ impl Supervisor {
     /// # fn run() {
     /// # Bastion::init();
     /// #
-    /// Bastion::supervisor(|sp| {
-    ///     let callbacks = Callbacks::new()
-    ///         .with_before_start(|| println!("Supervisor started."))
-    ///         .with_after_stop(|| println!("Supervisor stopped."));
+    /// #[derive(Debug)]
+    /// struct IgnoreFlaky;
     ///
-    ///     sp.with_callbacks(callbacks)
-    /// }).expect("Couldn't create the supervisor.");
+    /// impl SupervisionDecider for IgnoreFlaky {
+    ///     fn decide(&self, fault: &Fault) -> Directive {
+    ///         match fault.restarts() {
+    ///             0..=2 => Directive::Restart,
+    ///             _ => Directive::Ignore,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// Bastion::supervisor(|sp| {
+    ///     sp.with_decider(IgnoreFlaky)
+    /// }).expect("Couldn't create the supervisor");
     /// #
     /// # Bastion::start();
     /// # Bastion::stop();
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
-    pub fn with_callbacks(mut self, callbacks: Callbacks) -> Self {
+    pub fn with_decider<D: SupervisionDecider>(mut self, decider: D) -> Self {
         trace!(
-            "Supervisor({}): Setting callbacks: {:?}",
+            "Supervisor({}): Setting supervision decider: {:?}",
             self.id(),
-            callbacks
+            decider
         );
-        self.callbacks = callbacks;
+        self.decider = Some(Arc::new(decider));
         self
     }
 
-    async fn restart(&mut self, objects: Vec<RestartedElement>) {
-        debug!(
-            "Supervisor({}): Restarting {:?} elements",
-            self.id(),
-            objects.len()
-        );
-        let mut restart_futures = FuturesOrdered::new();
-
-        for object in objects {
-            match object {
-                RestartedElement::Supervisor(supervisor_id) => {
-                    let msg = BastionMessage::restart_subtree();
-                    let env =
-                        Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
-                    self.bcast.send_child(&supervisor_id, env);
-                }
-                RestartedElement::Child { id, parent_id } => {
-                    let index = match self.tracked_groups_order.get(&id) {
-                        Some(index) => *index,
-                        None => continue,
-                    };
-                    let childs = match self.tracked_groups.get_mut(&parent_id) {
-                        Some(childs) => childs,
-                        None => continue,
-                    };
-                    let tracked_state = match childs.get_mut(index) {
-                        Some(tracked_state) => tracked_state,
-                        None => continue,
-                    };
-                    let restarts_count = tracked_state.restarts_count();
-
-                    let restart_required = match self.restart_strategy.restart_policy() {
-                        RestartPolicy::Always => true,
-                        RestartPolicy::Never => false,
-                        RestartPolicy::Tries(max_retries) => restarts_count < max_retries,
-                    };
-
-                    let msg = match restart_required {
+    /// Maps the errors of type `E` returned by the supervised
+    /// elements (see [`Children::with_fallible_exec`]) to the
+    /// [`Directive`] this supervisor should follow, allowing to
+    /// tell the fatal errors from the ones worth a restart.
+    ///
+    /// The mappings are tried in the order they were added, before
+    /// the supervisor's [`SupervisionDecider`] (if any), which
+    /// decides about the faults no mapping matched.
+    ///
+    /// # Arguments
+    ///
+    /// * `directive_fn` - The closure returning the directive to
+    ///   follow about an error of type `E`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::fmt::{self, Display, Formatter};
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// #[derive(Debug)]
+    /// enum DbError {
+    ///     Unreachable,
+    ///     BadCredentials,
+    /// }
+    /// #
+    /// # impl Display for DbError {
+    /// #     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+    /// #         write!(fmt, "{:?}", self)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl std::error::Error for DbError {}
+    ///
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::supervisor(|sp| {
+    ///     sp.with_directive_fn(|err: &DbError| match err {
+    ///         // The database might be back after a restart...
+    ///         DbError::Unreachable => Directive::Restart,
+    ///         // ...but restarting won't fix the credentials.
+    ///         DbError::BadCredentials => Directive::Escalate,
+    ///     })
+    ///     .children(|children| {
+    ///         children.with_fallible_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // Connects to the database...
+    ///                 Err(DbError::Unreachable)
+    ///             }
+    ///         })
+    ///     })
+    /// }).expect("Couldn't create the supervisor.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_fallible_exec`]: crate::children::Children::with_fallible_exec
+    pub fn with_directive_fn<E, F>(mut self, directive_fn: F) -> Self
+    where
+        E: Error + 'static,
+        F: Fn(&E) -> Directive + Send + Sync + 'static,
+    {
+        trace!(
+            "Supervisor({}): Mapping the errors of type {} to directives.",
+            self.id(),
+            std::any::type_name::<E>()
+        );
+        let directive_fn = move |error: &ExecError| error.downcast_ref::<E>().map(&directive_fn);
+        self.error_directives.0.push(Arc::new(directive_fn));
+        self
+    }
+
+    /// Makes the process abort once the elements of the subtree of
+    /// this supervisor (those it supervises directly or through the
+    /// supervisors added to it afterwards) fault too often, as set
+    /// by `policy`, instead of being supervised again.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - How many faults the subtree can have within a
+    ///   window of time.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::supervisor(|sp| {
+    ///     sp.with_abort_policy(AbortPolicy::new(10, Duration::from_secs(60)))
+    ///         // The faults of the supervisors added afterwards count too.
+    ///         .supervisor(|sp| sp)
+    /// }).expect("Couldn't create the supervisor.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_abort_policy(mut self, policy: AbortPolicy) -> Self {
+        trace!(
+            "Supervisor({}): Setting the abort policy: {:?}",
+            self.id(),
+            policy
+        );
+        self.abort = Some(Arc::new(AbortState::new(policy)));
+        self
+    }
+
+    /// Breaks the restarts of the elements of the children groups
+    /// supervised by this supervisor which keep faulting, parking
+    /// them for a cooldown before attempting to restart them again,
+    /// as set by `circuit`.
+    ///
+    /// The elements which were stopped instead of faulting don't
+    /// count (see [`ChildRestart::Permanent`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - How many consecutive faults open the circuit of
+    ///   a group, and for how long.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// let circuit = RestartCircuit::new(5, Duration::from_secs(30))
+    ///     .with_on_transition(|group, from, to| {
+    ///         println!("Children({}): {:?} -> {:?}", group, from, to);
+    ///     });
+    ///
+    /// Bastion::supervisor(|sp| {
+    ///     sp.with_restart_circuit(circuit)
+    ///         .children(|children| {
+    ///             children.with_exec(|ctx: BastionContext| {
+    ///                 async move {
+    ///                     // Connects to a database which might be down...
+    ///                     Err(())
+    ///                 }
+    ///             })
+    ///         })
+    /// }).expect("Couldn't create the supervisor.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`ChildRestart::Permanent`]: crate::children::ChildRestart::Permanent
+    pub fn with_restart_circuit(mut self, circuit: RestartCircuit) -> Self {
+        trace!(
+            "Supervisor({}): Setting the restart circuit: {:?}",
+            self.id(),
+            circuit
+        );
+        self.circuit = Some(circuit);
+        self
+    }
+
+    /// Sets whether the restarted elements of the children groups
+    /// added to this supervisor afterwards keep the state of the
+    /// elements they replace (see [`RestartState`]), unless the groups
+    /// set it themselves with [`Children::with_restart_state`].
+    ///
+    /// # Arguments
+    ///
+    /// * `restart_state` - Whether the restarted elements keep the
+    ///   state of the elements they replace.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::supervisor(|sp| {
+    ///     sp.with_restart_state(RestartState::Fresh)
+    ///         .children(|children| {
+    ///             children.with_exec(|ctx: BastionContext| {
+    ///                 async move {
+    ///                     // Handle the messages...
+    ///                     Ok(())
+    ///                 }
+    ///             })
+    ///         })
+    /// }).expect("Couldn't create the supervisor.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_restart_state`]: crate::children::Children::with_restart_state
+    pub fn with_restart_state(mut self, restart_state: RestartState) -> Self {
+        trace!(
+            "Supervisor({}): Setting the state of the restarted elements: {:?}",
+            self.id(),
+            restart_state
+        );
+        self.restart_state = restart_state;
+        self
+    }
+
+    /// Makes the subtree of this supervisor a dispatcher scope named
+    /// `scope`, so that the named dispatchers of the children groups
+    /// it supervises (directly or through its supervised supervisors)
+    /// don't collide with the ones of the same name in the other
+    /// subtrees.
+    ///
+    /// When an element of a group in the subtree broadcasts a message
+    /// to a group (with [`BastionContext::broadcast_message`]), the
+    /// group's name is first looked up in the innermost scope, then in
+    /// the scopes enclosing it, and finally among the global
+    /// dispatchers. The messages broadcasted from outside of the
+    /// subtree can reach its dispatchers by prefixing their names
+    /// with the ones of their scopes, separated with slashes (eg.
+    /// `"billing/workers"`).
+    ///
+    /// Note that the scope only applies to the children groups and
+    /// supervisors created after this method is called.
+    ///
+    /// # Arguments
+    ///
+    /// * `scope` - The name of the scope, which should be unique
+    ///   among the scopes enclosed in the same one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// // Both subsystems have their own "workers" group...
+    /// for subsystem in &["billing", "shipping"] {
+    ///     Bastion::supervisor(|sp| {
+    ///         sp.with_dispatcher_scope(*subsystem)
+    ///             .children(|children| {
+    ///                 children.with_dispatcher(Dispatcher::with_type(DispatcherType::Named(
+    ///                     "workers".to_string(),
+    ///                 )))
+    ///             })
+    ///             .children(|children| {
+    ///                 children.with_exec(|ctx: BastionContext| {
+    ///                     async move {
+    ///                         // ...which is the one reached from within
+    ///                         // the subsystem.
+    ///                         let target = BroadcastTarget::Group("workers".to_string());
+    ///                         ctx.broadcast_message(target, "job");
+    ///                         Ok(())
+    ///                     }
+    ///                 })
+    ///             })
+    ///     })
+    ///     .expect("Couldn't create the supervisor.");
+    /// }
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`BastionContext::broadcast_message`]: crate::context::BastionContext::broadcast_message
+    pub fn with_dispatcher_scope(mut self, scope: impl Into<String>) -> Self {
+        let scope = scope.into();
+        trace!(
+            "Supervisor({}): Setting dispatcher scope: {}",
+            self.id(),
+            scope
+        );
+        self.dispatcher_scope.push(scope);
+        self
+    }
+
+    pub(crate) fn in_dispatcher_scope(mut self, dispatcher_scope: Vec<String>) -> Self {
+        self.dispatcher_scope = dispatcher_scope;
+        self
+    }
+
+    /// Registers every children group added to this supervisor or to
+    /// the supervisors added to it afterwards with the given
+    /// dispatcher, as if each one was added to it with
+    /// [`Children::with_dispatcher`].
+    ///
+    /// The dispatcher is shared by all the groups, so that a message
+    /// broadcasted to it reaches the elements of all of them. If the
+    /// supervisor is in a dispatcher scope (see
+    /// [`with_dispatcher_scope`]), the name of a `Named` dispatcher is
+    /// prefixed with the ones of the scopes.
+    ///
+    /// # Arguments
+    ///
+    /// * `dispatcher` - The dispatcher the children groups of the
+    ///   subtree are registered with.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::supervisor(|sp| {
+    ///     sp.with_dispatcher(Dispatcher::with_type(DispatcherType::Named(
+    ///         "workers".to_string(),
+    ///     )))
+    ///     // Both groups are registered with the "workers" dispatcher.
+    ///     .children(|children| children.with_redundancy(2))
+    ///     .children(|children| children.with_redundancy(3))
+    /// }).expect("Couldn't create the supervisor.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_dispatcher`]: crate::children::Children::with_dispatcher
+    /// [`with_dispatcher_scope`]: Self::with_dispatcher_scope
+    pub fn with_dispatcher(mut self, dispatcher: Dispatcher) -> Self {
+        let dispatcher = match dispatcher.dispatcher_type() {
+            DispatcherType::Named(name) if !self.dispatcher_scope.is_empty() => {
+                let dispatcher_type = DispatcherType::scoped(&self.dispatcher_scope, &name);
+                dispatcher.with_dispatcher_type(dispatcher_type)
+            }
+            _ => dispatcher,
+        };
+
+        trace!(
+            "Supervisor({}): Setting dispatcher: {:?}",
+            self.id(),
+            dispatcher.dispatcher_type()
+        );
+        self.dispatchers.push(Arc::new(Box::new(dispatcher)));
+        self
+    }
+
+    #[allow(clippy::redundant_allocation)]
+    pub(crate) fn in_supervisor_dispatchers(
+        mut self,
+        dispatchers: Vec<Arc<Box<Dispatcher>>>,
+    ) -> Self {
+        self.inherited_dispatchers = dispatchers;
+        self
+    }
+
+    /// Returns the dispatchers the children groups of the subtree
+    /// are registered with.
+    #[allow(clippy::redundant_allocation)]
+    fn subtree_dispatchers(&self) -> Vec<Arc<Box<Dispatcher>>> {
+        self.inherited_dispatchers
+            .iter()
+            .chain(self.dispatchers.iter())
+            .cloned()
+            .collect()
+    }
+
+    /// Removes the dispatchers set on this supervisor from the
+    /// global registry, once the subtree doesn't run anymore.
+    fn remove_dispatchers(&self) {
+        let global_dispatcher = SYSTEM.dispatcher();
+        for dispatcher in self.dispatchers.iter() {
+            if let Err(e) = global_dispatcher.remove_dispatcher(dispatcher) {
+                warn!("couldn't remove all dispatchers from the registry: {}", e);
+            }
+        }
+    }
+
+    /// Sets the name of this supervisor, which allows to look it up
+    /// with [`Bastion::find_supervisor`] (eg. to supervise new
+    /// children groups from other modules) while it runs.
+    ///
+    /// If another running supervisor was given the same name, the
+    /// name resolves to this supervisor from then on.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the supervisor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::supervisor(|sp| sp.with_name("workers"))
+    ///     .expect("Couldn't create the supervisor.");
+    ///
+    /// // ...and then, elsewhere...
+    /// let workers = Bastion::find_supervisor("workers")
+    ///     .expect("Couldn't find the supervisor.");
+    /// workers.children(|children| {
+    ///     // ...
+    ///     # children
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Bastion::find_supervisor`]: crate::Bastion::find_supervisor
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        trace!("Supervisor({}): Setting name: {}", self.id(), name);
+        self.name = Some(name);
+        self
+    }
+
+    /// Makes the name of this supervisor, if it has one, resolve to
+    /// it.
+    pub(crate) fn register_name(&self) {
+        if let Some(name) = &self.name {
+            SYSTEM
+                .named_supervisors()
+                .register(name.clone(), self.as_ref());
+        }
+    }
+
+    /// Stops the name of this supervisor from resolving to it, once
+    /// it doesn't run anymore.
+    fn unregister_name(&self) {
+        if let Some(name) = &self.name {
+            SYSTEM.named_supervisors().unregister(name, self.id());
+        }
+    }
+
+    pub(crate) fn in_abort_scope(mut self, abort: Option<Arc<AbortState>>) -> Self {
+        self.abort = abort;
+        self
+    }
+
+    /// Sets the callbacks that will get called at this supervisor's
+    /// different lifecycle events.
+    ///
+    /// See [`Callbacks`]'s documentation for more information about the
+    /// different callbacks available.
+    ///
+    /// # Arguments
+    ///
+    /// * `callbacks` - The callbacks that will get called for this
+    ///     supervisor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::supervisor(|sp| {
+    ///     let callbacks = Callbacks::new()
+    ///         .with_before_start(|| println!("Supervisor started."))
+    ///         .with_after_stop(|| println!("Supervisor stopped."));
+    ///
+    ///     sp.with_callbacks(callbacks)
+    /// }).expect("Couldn't create the supervisor.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_callbacks(mut self, callbacks: Callbacks) -> Self {
+        trace!(
+            "Supervisor({}): Setting callbacks: {:?}",
+            self.id(),
+            callbacks
+        );
+        self.callbacks = callbacks;
+        self
+    }
+
+    async fn restart(&mut self, objects: Vec<RestartedElement>) {
+        debug!(
+            "Supervisor({}): Restarting {:?} elements",
+            self.id(),
+            objects.len()
+        );
+        let mut restart_futures = FuturesOrdered::new();
+
+        for object in objects {
+            match object {
+                RestartedElement::Supervisor(supervisor_id) => {
+                    let msg = BastionMessage::restart_subtree();
+                    let env =
+                        Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
+                    self.bcast.send_child(&supervisor_id, env);
+                }
+                RestartedElement::Child { id, parent_id } => {
+                    let index = match self.tracked_groups_order.get(&id) {
+                        Some(index) => *index,
+                        None => continue,
+                    };
+                    let childs = match self.tracked_groups.get_mut(&parent_id) {
+                        Some(childs) => childs,
+                        None => continue,
+                    };
+                    let tracked_state = match childs.get_mut(index) {
+                        Some(tracked_state) => tracked_state,
+                        None => continue,
+                    };
+                    let restarts_count = tracked_state.restarts_count();
+
+                    let restart_required = match self.restart_strategy.restart_policy() {
+                        RestartPolicy::Always => true,
+                        RestartPolicy::Never => false,
+                        RestartPolicy::Tries(max_retries) => restarts_count < max_retries,
+                    };
+
+                    let msg = match restart_required {
                         true => {
                             tracked_state.increase_restarts_counter();
                             let state = tracked_state.state();
@@ -990,6 +2232,14 @@ impl Supervisor {
         }
     }
 
+    fn restarts_count(&self, id: &BastionId, parent_id: &BastionId) -> usize {
+        self.tracked_groups_order
+            .get(id)
+            .and_then(|index| self.tracked_groups.get(parent_id)?.get(*index))
+            .map(|tracked_state| tracked_state.restarts_count())
+            .unwrap_or_default()
+    }
+
     fn remove_child(&mut self, id: &BastionId, parent_id: &BastionId) {
         let index = match self.tracked_groups_order.get(id) {
             Some(index) => *index,
@@ -1009,6 +2259,10 @@ impl Supervisor {
 
     async fn stop(&mut self, range: Range<usize>) {
         debug!("Supervisor({}): Stopping range: {:?}", self.id(), range);
+        if self.shutdown_order == ShutdownOrder::ReverseStart {
+            return self.stop_in_reverse(range).await;
+        }
+
         if range.start == 0 {
             self.bcast.stop_children();
         } else {
@@ -1019,18 +2273,46 @@ impl Supervisor {
             }
         }
 
-        let mut supervised = FuturesOrdered::new();
+        let mut deadline = self.stop_timeout.map(Delay::new);
         // FIXME: panics?
-        for id in self.order.get(range.clone()).unwrap() {
+        let ids = self.order.get(range).unwrap().to_vec();
+        for id in ids {
             // TODO: Err if None?
-            if let Some((_, launched)) = self.launched.remove(&id) {
-                // TODO: add a "stopped" list and poll from it instead of awaiting
-                supervised.push(launched);
+            let launched = match self.launched.remove(&id) {
+                Some((_, launched)) => launched,
+                None => continue,
+            };
+
+            // The stragglers were already logged and killed.
+            if let Some(supervised) = self.wait_stopped(&id, launched, &mut deadline).await {
+                trace!(
+                    "Supervisor({}): Supervised({}) stopped.",
+                    self.id(),
+                    supervised.id()
+                );
+                supervised.callbacks().after_stop();
+
+                self.stopped.insert(id, supervised);
             }
         }
+    }
+
+    async fn stop_in_reverse(&mut self, range: Range<usize>) {
+        // FIXME: panics?
+        let ids = self.order.get(range.clone()).unwrap().to_vec();
+        for id in ids.iter().rev() {
+            // TODO: Err if None?
+            let launched = match self.launched.remove(id) {
+                Some((_, launched)) => launched,
+                None => continue,
+            };
 
-        while let Some(supervised) = supervised.next().await {
-            match supervised {
+            trace!("Supervisor({}): Stopping Supervised({}).", self.id(), id);
+            self.bcast.stop_child(id);
+
+            // Each supervised element is given the whole timeout.
+            let mut deadline = self.stop_timeout.map(Delay::new);
+            match self.wait_stopped(id, launched, &mut deadline).await {
                 Some(supervised) => {
                     trace!(
                         "Supervisor({}): Supervised({}) stopped.",
@@ -1039,13 +2321,21 @@ impl Supervisor {
                     );
                     supervised.callbacks().after_stop();
 
-                    let id = supervised.id().clone();
-                    self.stopped.insert(id, supervised);
+                    self.stopped.insert(id.clone(), supervised);
                 }
-                // FIXME
-                None => unimplemented!(),
+                None => warn!(
+                    "Supervisor({}): Supervised({}) cancelled or killed instead of stopped.",
+                    self.id(),
+                    id
+                ),
             }
         }
+
+        if range.start == 0 {
+            // Stops the remaining supervised elements which weren't
+            // launched anymore.
+            self.bcast.stop_children();
+        }
     }
 
     async fn kill(&mut self, range: Range<usize>) {
@@ -1060,40 +2350,73 @@ impl Supervisor {
             }
         }
 
-        let mut supervised = FuturesOrdered::new();
+        let mut deadline = self.stop_timeout.map(Delay::new);
         // FIXME: panics?
-        for id in self.order.get(range.clone()).unwrap() {
+        let ids = self.order.get(range).unwrap().to_vec();
+        for id in ids {
             // TODO: Err if None?
-            if let Some((_, launched)) = self.launched.remove(&id) {
-                // TODO: add a "stopped" list and poll from it instead of awaiting
-                supervised.push(launched);
+            let launched = match self.launched.remove(&id) {
+                Some((_, launched)) => launched,
+                None => continue,
+            };
+
+            // The stragglers were already logged and cancelled.
+            if let Some(supervised) = self.wait_stopped(&id, launched, &mut deadline).await {
+                trace!(
+                    "Supervisor({}): Supervised({}) stopped.",
+                    self.id(),
+                    supervised.id()
+                );
+                self.killed.insert(id, supervised);
             }
         }
+    }
 
-        while let Some(supervised) = supervised.next().await {
-            match supervised {
-                Some(supervised) => {
-                    trace!(
-                        "Supervisor({}): Supervised({}) stopped.",
-                        self.id(),
-                        supervised.id()
-                    );
-                    let id = supervised.id().clone();
-                    self.killed.insert(id, supervised);
-                }
-                // FIXME
-                None => unimplemented!(),
+    /// Waits for the supervised element with the given identifier to
+    /// stop, until `deadline` (if any) elapsed, after which it is
+    /// killed and given up on.
+    async fn wait_stopped(
+        &mut self,
+        id: &BastionId,
+        launched: RecoverableHandle<Supervised>,
+        deadline: &mut Option<Delay>,
+    ) -> Option<Supervised> {
+        let deadline = match deadline {
+            Some(deadline) => deadline,
+            None => return launched.await,
+        };
+
+        pin_mut!(launched);
+        match future::select(launched.as_mut(), deadline).await {
+            Either::Left((supervised, _)) => supervised,
+            Either::Right(_) => {
+                warn!(
+                    "Supervisor({}): Supervised({}) didn't stop within {:?}, killing it.",
+                    self.id(),
+                    id,
+                    self.stop_timeout.unwrap_or_default()
+                );
+                self.bcast.kill_child(id);
+                // A stuck element might never reach an await point to
+                // be cancelled at, so it isn't waited for anymore.
+                launched.cancel();
+
+                None
             }
         }
     }
 
     fn stopped(&mut self) {
         debug!("Supervisor({}): Stopped.", self.id());
+        self.remove_dispatchers();
+        self.unregister_name();
         self.bcast.stopped();
     }
 
     fn faulted(&mut self) {
         debug!("Supervisor({}): Faulted.", self.id());
+        self.remove_dispatchers();
+        self.unregister_name();
         self.bcast.faulted();
     }
 
@@ -1104,6 +2427,29 @@ impl Supervisor {
             self.strategy
         );
 
+        if let Some((max_restarts, window)) = self.restart_strategy.restart_intensity() {
+            let now = Instant::now();
+            while let Some(recovered) = self.recoveries.front() {
+                if now.duration_since(*recovered) < window {
+                    break;
+                }
+
+                self.recoveries.pop_front();
+            }
+
+            if self.recoveries.len() >= max_restarts {
+                warn!(
+                    "Supervisor({}): Escalating: more than {} restarts within {:?}.",
+                    self.id(),
+                    max_restarts,
+                    window
+                );
+                return Err(());
+            }
+
+            self.recoveries.push_back(now);
+        }
+
         match self.strategy {
             SupervisionStrategy::OneForOne => {
                 let search_method = ActorSearchMethod::OneActor { id, parent_id };
@@ -1120,7 +2466,7 @@ impl Supervisor {
                 self.killed.shrink_to_fit();
             }
             SupervisionStrategy::RestForOne => {
-                let search_method = ActorSearchMethod::FromActor { id, parent_id };
+                let search_method = ActorSearchMethod::FromActor { parent_id };
                 let objects = self.search_restarted_objects(search_method);
                 self.restart(objects).await;
             }
@@ -1140,25 +2486,14 @@ impl Supervisor {
                 };
                 objects.push(element)
             }
-            ActorSearchMethod::FromActor { id, parent_id } => {
-                let childs = self.tracked_groups.get(&parent_id).unwrap();
-                let start_index = *self.tracked_groups_order.get(&id).unwrap();
-
-                // Adding all elements in the group from the given actor
-                childs.iter().skip(start_index).for_each(|tracked_state| {
-                    let id = tracked_state.id();
-                    let element = RestartedElement::Child {
-                        id,
-                        parent_id: parent_id.clone(),
-                    };
-                    objects.push(element)
-                });
-
-                // And then a rest after the failed group
-                let (rest_index, _) = self.launched.get(&parent_id).unwrap();
-                for index in *rest_index + 1..self.order.len() {
-                    let element_id = &self.order[index];
-
+            ActorSearchMethod::FromActor { parent_id } => {
+                // The failed group and then the rest after it, in the
+                // order they were started.
+                let rest_index = match self.launched.get(&parent_id) {
+                    Some((rest_index, _)) => *rest_index,
+                    None => return objects,
+                };
+                for element_id in self.order.iter().skip(rest_index) {
                     match self.tracked_groups.get(element_id) {
                         Some(childs) => {
                             for tracked_state in childs {
@@ -1170,7 +2505,8 @@ impl Supervisor {
                             }
                         }
                         None => {
-                            let restarted_element = RestartedElement::Supervisor(id.clone());
+                            let restarted_element =
+                                RestartedElement::Supervisor(element_id.clone());
                             objects.push(restarted_element);
                         }
                     }
@@ -1219,7 +2555,7 @@ impl Supervisor {
     }
 
     async fn deploy_supervised_object(&mut self, deployment: Box<Deployment>) {
-        let supervised = match *deployment {
+        match *deployment {
             Deployment::Supervisor(supervisor) => {
                 debug!(
                     "Supervisor({}): Deploying Supervisor({}).",
@@ -1227,24 +2563,50 @@ impl Supervisor {
                     supervisor.id()
                 );
                 supervisor.callbacks().before_start();
-                Supervised::supervisor(supervisor)
+                self.tree.add_supervisor(supervisor.tree().clone());
+                self.launch_supervised(Supervised::supervisor(supervisor));
             }
-            Deployment::Children(children) => {
+            Deployment::Children(children) => self.deploy_children(children),
+            Deployment::ChildrenBatch(batch) => {
                 debug!(
-                    "Supervisor({}): Deploying Children({}).",
+                    "Supervisor({}): Deploying {} children groups.",
                     self.id(),
-                    children.id()
+                    batch.len()
                 );
-                children.callbacks().before_start();
-                Supervised::children(children)
+                for children in batch {
+                    self.deploy_children(children);
+                }
             }
-        };
+        }
+    }
+
+    fn deploy_children(&mut self, children: Children) {
+        debug!(
+            "Supervisor({}): Deploying Children({}).",
+            self.id(),
+            children.id()
+        );
+        children.callbacks().before_start();
+        if !children.start_after().is_empty() {
+            let dependencies = children.start_after().to_vec();
+            self.start_after
+                .insert(children.id().clone(), (children.as_ref(), dependencies));
+        }
+
+        let path = children.bcast().path().clone();
+        let group = Arc::new(GroupNode::new(children.id().clone(), children.name(), path));
+        self.tree.add_group(group.clone());
+        let children = children
+            .in_tree(group)
+            .inherit_restart_state(self.restart_state);
+
+        self.launch_supervised(Supervised::children(children));
+    }
 
+    fn launch_supervised(&mut self, supervised: Supervised) {
         self.bcast.register(supervised.bcast());
         if self.started {
-            let msg = BastionMessage::start();
-            let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
-            self.bcast.send_child(supervised.id(), env);
+            self.start_supervised(supervised.id());
         }
 
         debug!(
@@ -1259,6 +2621,37 @@ impl Supervisor {
         self.order.push(id);
     }
 
+    // Sends a start message to the supervised element with the given
+    // identifier, once the groups it has to start after started.
+    fn start_supervised(&self, id: &BastionId) {
+        let msg = BastionMessage::start();
+        let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
+        let (children, dependencies) = match self.start_after.get(id) {
+            Some(start_after) => start_after.clone(),
+            None => return self.bcast.send_child(id, env),
+        };
+
+        debug!(
+            "Supervisor({}): Starting Children({}) after {} groups.",
+            self.id(),
+            id,
+            dependencies.len()
+        );
+        let id = self.id().clone();
+        pool::spawn(
+            async move {
+                for dependency in dependencies.iter() {
+                    dependency.wait_started().await;
+                }
+
+                debug!("Supervisor({}): Starting Children({}).", id, children.id());
+                // FIXME: handle errors
+                children.send(env).ok();
+            },
+            ProcStack::default(),
+        );
+    }
+
     async fn cleanup_supervised_object(&mut self, id: BastionId) {
         // FIXME: Err if None?
         if let Some((_, launched)) = self.launched.remove(&id) {
@@ -1269,6 +2662,8 @@ impl Supervisor {
             supervised.callbacks().after_stop();
 
             self.bcast.unregister(&id);
+            self.tree.remove(&id);
+            self.circuits.remove(&id);
             self.stopped.insert(id.clone(), supervised);
         }
     }
@@ -1277,12 +2672,106 @@ impl Supervisor {
         &mut self,
         id: BastionId,
         parent_id: BastionId,
+        reason: FaultReason,
     ) -> Result<(), ()> {
-        if self.launched.contains_key(&id) {
-            warn!("Supervisor({}): Supervised({}) faulted.", self.id(), id);
+        self.tree.fault();
+        warn!(
+            "Supervisor({}): Child({}) of Children({}) faulted: {}.",
+            self.id(),
+            id,
+            parent_id,
+            reason
+        );
+
+        if let Some(abort) = &self.abort {
+            if abort.fault() {
+                error!(
+                    "Supervisor({}): Aborting: more than {} faults within {:?}.",
+                    self.id(),
+                    abort.policy.max_faults,
+                    abort.policy.window
+                );
+                std::process::abort();
+            }
+        }
+
+        if self.park_fault(&id, &parent_id, &reason) {
+            return Ok(());
         }
 
-        if self.recover(id, parent_id).await.is_err() {
+        self.decide(id, parent_id, reason).await
+    }
+
+    // Decides what to do about the fault of an element, and does it.
+    async fn decide(
+        &mut self,
+        id: BastionId,
+        parent_id: BastionId,
+        reason: FaultReason,
+    ) -> Result<(), ()> {
+        let mapped = match &reason {
+            FaultReason::Failed(error) => self.error_directives.directive(error),
+            _ => None,
+        };
+
+        let directive = match (mapped, &self.decider) {
+            (Some(directive), _) => {
+                debug!(
+                    "Supervisor({}): Mapped the error of Child({}) of Children({}) to {:?}.",
+                    self.id(),
+                    id,
+                    parent_id,
+                    directive
+                );
+
+                directive
+            }
+            (None, Some(decider)) => {
+                let fault = Fault {
+                    restarts: self.restarts_count(&id, &parent_id),
+                    child: id.clone(),
+                    group: parent_id.clone(),
+                    reason,
+                };
+                let directive = decider.decide(&fault);
+                debug!(
+                    "Supervisor({}): Decided to {:?} Child({}) of Children({}).",
+                    self.id(),
+                    directive,
+                    id,
+                    parent_id
+                );
+
+                directive
+            }
+            (None, None) => Directive::Restart,
+        };
+
+        let recovered = match directive {
+            Directive::Restart => self.recover(id, parent_id).await,
+            Directive::Stop => {
+                let env = Envelope::new(
+                    BastionMessage::stop(),
+                    self.bcast.path().clone(),
+                    self.bcast.sender().clone(),
+                );
+                self.bcast.send_child(&parent_id, env);
+                Ok(())
+            }
+            Directive::Escalate => Err(()),
+            Directive::Ignore => {
+                self.remove_child(&id, &parent_id);
+                let env = Envelope::new(
+                    BastionMessage::drop_child(id),
+                    self.bcast.path().clone(),
+                    self.bcast.sender().clone(),
+                );
+                self.bcast.send_child(&parent_id, env);
+                Ok(())
+            }
+        };
+
+        if recovered.is_err() {
             // TODO: stop or kill?
             self.kill(0..self.order.len()).await;
             self.faulted();
@@ -1293,6 +2782,147 @@ impl Supervisor {
         Ok(())
     }
 
+    // Records the fault of an element if the supervisor breaks the
+    // restarts of its groups, returning whether the element was
+    // parked instead of being restarted.
+    fn park_fault(&mut self, id: &BastionId, parent_id: &BastionId, reason: &FaultReason) -> bool {
+        let (failure_threshold, cooldown) = match &self.circuit {
+            Some(circuit) => (circuit.failure_threshold, circuit.cooldown),
+            None => return false,
+        };
+        if let FaultReason::Stopped = reason {
+            return false;
+        }
+
+        let now = Instant::now();
+        let circuit = self
+            .circuits
+            .entry(parent_id.clone())
+            .or_insert_with(GroupCircuit::new);
+        let previous = circuit.state;
+        if previous == CircuitState::Closed {
+            // The faults aren't consecutive anymore once the group
+            // ran for a whole cooldown without faulting.
+            let consecutive = circuit
+                .last_fault
+                .map(|last_fault| now.duration_since(last_fault) < cooldown)
+                .unwrap_or(false);
+            circuit.failures = if consecutive { circuit.failures + 1 } else { 1 };
+            circuit.last_fault = Some(now);
+            if circuit.failures < failure_threshold {
+                return false;
+            }
+        }
+
+        circuit.state = CircuitState::Open;
+        circuit.last_fault = Some(now);
+        circuit.parked.push((id.clone(), reason.clone()));
+        if previous != CircuitState::Open {
+            circuit.deadline = now + cooldown;
+        }
+
+        debug!(
+            "Supervisor({}): Parking Child({}) of Children({}).",
+            self.id(),
+            id,
+            parent_id
+        );
+        if previous != CircuitState::Open {
+            self.schedule_circuit_timeout(parent_id, cooldown);
+            self.circuit_changed(parent_id, previous, CircuitState::Open);
+        }
+
+        true
+    }
+
+    // Makes the circuit of the group with the given identifier
+    // half-open once it was open for the cooldown, restarting its
+    // parked elements, or closes it once none of them faulted for
+    // another cooldown.
+    async fn circuit_timeout(&mut self, group: BastionId) -> Result<(), ()> {
+        let cooldown = match &self.circuit {
+            Some(circuit) => circuit.cooldown,
+            None => return Ok(()),
+        };
+        let circuit = match self.circuits.get_mut(&group) {
+            Some(circuit) => circuit,
+            None => return Ok(()),
+        };
+        // The circuit changed since the timeout was scheduled.
+        if Instant::now() < circuit.deadline {
+            return Ok(());
+        }
+
+        match circuit.state {
+            CircuitState::Open => {
+                circuit.state = CircuitState::HalfOpen;
+                circuit.deadline = Instant::now() + cooldown;
+                let parked = std::mem::take(&mut circuit.parked);
+                self.schedule_circuit_timeout(&group, cooldown);
+                self.circuit_changed(&group, CircuitState::Open, CircuitState::HalfOpen);
+
+                for (id, reason) in parked {
+                    self.decide(id, group.clone(), reason).await?;
+                }
+            }
+            CircuitState::HalfOpen => {
+                circuit.state = CircuitState::Closed;
+                circuit.failures = 0;
+                self.circuit_changed(&group, CircuitState::HalfOpen, CircuitState::Closed);
+            }
+            CircuitState::Closed => (),
+        }
+
+        Ok(())
+    }
+
+    fn schedule_circuit_timeout(&self, group: &BastionId, cooldown: Duration) {
+        let msg = BastionMessage::circuit_timeout(group.clone());
+        let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
+        let sender = self.bcast.sender().clone();
+        pool::spawn(
+            async move {
+                Delay::new(cooldown).await;
+                // FIXME: handle errors
+                sender.unbounded_send(env).ok();
+            },
+            ProcStack::default(),
+        );
+    }
+
+    fn circuit_changed(&self, group: &BastionId, from: CircuitState, to: CircuitState) {
+        info!(
+            "Supervisor({}): Circuit of Children({}) changed from {:?} to {:?}.",
+            self.id(),
+            group,
+            from,
+            to
+        );
+        if let Some(RestartCircuit {
+            on_transition: Some(callback),
+            ..
+        }) = &self.circuit
+        {
+            callback(group, from, to);
+        }
+    }
+
+    async fn resume_restarts(&mut self) -> Result<(), ()> {
+        debug!(
+            "Supervisor({}): Resuming restarts with {} queued faults.",
+            self.id(),
+            self.queued_restarts.len()
+        );
+        self.restarts_paused = false;
+
+        for (id, parent_id, reason) in std::mem::take(&mut self.queued_restarts) {
+            self.recover_supervised_object(id, parent_id, reason)
+                .await?;
+        }
+
+        Ok(())
+    }
+
     async fn handle(&mut self, env: Envelope) -> Result<(), ()> {
         match env {
             Envelope {
@@ -1346,6 +2976,16 @@ impl Supervisor {
                     },
                 ..
             } => {
+                // An element restarted with a fresh state keeps its
+                // restarts count.
+                let index = self.tracked_groups_order.get(&child_id).copied();
+                let tracked_state =
+                    index.and_then(|index| self.tracked_groups.get_mut(&parent_id)?.get_mut(index));
+                if let Some(tracked_state) = tracked_state {
+                    tracked_state.state = state;
+                    return Ok(());
+                }
+
                 let child_state = TrackedChildState::new(child_id.clone(), state);
                 match self.tracked_groups.get_mut(&parent_id) {
                     Some(childs) => {
@@ -1370,10 +3010,41 @@ impl Supervisor {
                 self.bcast.send_children(env);
             }
             Envelope {
-                msg: BastionMessage::RestartRequired { id, parent_id },
+                msg:
+                    BastionMessage::RestartRequired {
+                        id,
+                        parent_id,
+                        reason,
+                    },
+                ..
+            } if self.restarts_paused => {
+                debug!(
+                    "Supervisor({}): Restarts paused: Queuing fault of: {}",
+                    self.id(),
+                    id
+                );
+                if !self
+                    .queued_restarts
+                    .iter()
+                    .any(|(queued, _, _)| *queued == id)
+                {
+                    self.queued_restarts.push((id, parent_id, reason));
+                }
+            }
+            Envelope {
+                msg:
+                    BastionMessage::RestartRequired {
+                        id,
+                        parent_id,
+                        reason,
+                    },
                 ..
             } => {
-                if self.recover_supervised_object(id, parent_id).await.is_err() {
+                if self
+                    .recover_supervised_object(id, parent_id, reason)
+                    .await
+                    .is_err()
+                {
                     return Err(());
                 }
             }
@@ -1409,6 +3080,34 @@ impl Supervisor {
                 msg: BastionMessage::Heartbeat,
                 ..
             } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::Reconfigure(_),
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::Upgrade(_),
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::PauseRestarts,
+                ..
+            } => {
+                debug!("Supervisor({}): Pausing restarts.", self.id());
+                self.restarts_paused = true;
+            }
+            Envelope {
+                msg: BastionMessage::ResumeRestarts,
+                ..
+            } => self.resume_restarts().await?,
+            Envelope {
+                msg: BastionMessage::CircuitTimeout { group },
+                ..
+            } => self.circuit_timeout(group).await?,
+            #[cfg(feature = "chaos")]
+            Envelope {
+                msg: BastionMessage::Fault,
+                ..
+            } => unreachable!(),
         }
 
         Ok(())
@@ -1423,9 +3122,9 @@ impl Supervisor {
         debug!("Supervisor({}): Starting.", self.id());
         self.started = true;
 
-        let msg = BastionMessage::start();
-        let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
-        self.bcast.send_children(env);
+        for id in self.order.iter() {
+            self.start_supervised(id);
+        }
 
         let msgs = self.pre_start_msgs.drain(..).collect::<Vec<_>>();
         self.pre_start_msgs.shrink_to_fit();
@@ -1492,8 +3191,34 @@ impl Supervisor {
 }
 
 impl SupervisorRef {
-    pub(crate) fn new(id: BastionId, sender: Sender, path: Arc<BastionPath>) -> Self {
-        SupervisorRef { id, sender, path }
+    #[allow(clippy::too_many_arguments, clippy::redundant_allocation)]
+    pub(crate) fn new(
+        id: BastionId,
+        sender: Sender,
+        path: Arc<BastionPath>,
+        handovers: Arc<Handovers>,
+        dispatcher_scope: Vec<String>,
+        dispatchers: Vec<Arc<Box<Dispatcher>>>,
+        abort: Option<Arc<AbortState>>,
+        tree: Arc<SupervisorNode>,
+    ) -> Self {
+        SupervisorRef {
+            id,
+            sender,
+            path,
+            handovers,
+            dispatcher_scope,
+            dispatchers,
+            abort,
+            tree,
+        }
+    }
+
+    /// Returns the dispatchers the children groups of the subtree
+    /// are registered with.
+    #[allow(clippy::redundant_allocation)]
+    fn subtree_dispatchers(&self) -> Vec<Arc<Box<Dispatcher>>> {
+        self.dispatchers.clone()
     }
 
     /// Returns the identifier of the supervisor this `SupervisorRef`
@@ -1594,8 +3319,12 @@ impl SupervisorRef {
             self.id(),
             bcast.id()
         );
-        let supervisor = Supervisor::new(bcast);
+        let supervisor = Supervisor::new(bcast)
+            .in_dispatcher_scope(self.dispatcher_scope.clone())
+            .in_supervisor_dispatchers(self.subtree_dispatchers())
+            .in_abort_scope(self.abort.clone());
         let supervisor = init(supervisor);
+        supervisor.register_name();
         let supervisor_ref = supervisor.as_ref();
         debug!("Supervisor({}): Initialized.", supervisor.id());
 
@@ -1670,6 +3399,108 @@ impl SupervisorRef {
         self.children_with_id(BastionId::new(), init)
     }
 
+    /// Creates many [`Children`] at once, passes each of them
+    /// through `defaults` and then through its own closure from
+    /// `specs`, and then starts supervising all of them.
+    ///
+    /// The groups are deployed to the supervisor together, so that
+    /// either all of them or none are, which is cheaper than
+    /// creating them one by one when bootstrapping many groups.
+    ///
+    /// This method returns the [`ChildrenRef`]s referencing the
+    /// newly created children groups, in the order of `specs`, if
+    /// they were deployed, otherwise returns an `Err(())`.
+    ///
+    /// # Arguments
+    ///
+    /// * `defaults` - The closure configuring every new `Children`
+    ///   before its own closure from `specs` does.
+    /// * `specs` - The closures taking a new `Children` as an
+    ///     argument and returning it once configured.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let sp_ref = Bastion::supervisor(|sp| sp).unwrap();
+    /// let shards: Vec<ChildrenRef> = sp_ref
+    ///     .children_batch(
+    ///         |children| children.with_redundancy(2),
+    ///         (0..16).map(|shard| {
+    ///             move |children: Children| {
+    ///                 children
+    ///                     .with_name(format!("shard-{}", shard))
+    ///                     .with_exec(|ctx: BastionContext| {
+    ///                         async move {
+    ///                             // ...
+    ///                             Ok(())
+    ///                         }
+    ///                     })
+    ///             }
+    ///         }),
+    ///     )
+    ///     .expect("Couldn't create the children groups.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    #[allow(clippy::result_unit_err)]
+    pub fn children_batch<D, I, C>(&self, defaults: D, specs: I) -> Result<Vec<ChildrenRef>, ()>
+    where
+        D: Fn(Children) -> Children,
+        I: IntoIterator<Item = C>,
+        C: FnOnce(Children) -> Children,
+    {
+        debug!("SupervisorRef({}): Creating children groups.", self.id());
+        let mut batch = Vec::new();
+        for init in specs {
+            let parent = Parent::supervisor(self.clone());
+            let bcast = Broadcast::new(parent, BastionPathElement::Children(BastionId::new()));
+
+            let children = Children::new(bcast)
+                .in_dispatcher_scope(self.dispatcher_scope.clone())
+                .in_supervisor_dispatchers(self.subtree_dispatchers());
+            let mut children = init(defaults(children));
+            debug!("Children({}): Initialized.", children.id());
+            // FIXME: children group elems launched without the group itself being launched
+            if let Err(e) = children.register_dispatchers() {
+                warn!("couldn't register all dispatchers into the registry: {}", e);
+            };
+            children.launch_elems();
+
+            batch.push(children);
+        }
+
+        let children_refs = batch.iter().map(Children::as_ref).collect();
+        debug!(
+            "SupervisorRef({}): Deploying {} children groups.",
+            self.id(),
+            batch.len()
+        );
+        let msg = BastionMessage::deploy_children_batch(batch);
+        let env = Envelope::new(msg, self.path.clone(), self.sender.clone());
+        self.send(env).map_err(|_| ())?;
+
+        Ok(children_refs)
+    }
+
     pub(crate) fn children_with_id<C>(&self, id: BastionId, init: C) -> Result<ChildrenRef, ()>
     where
         C: FnOnce(Children) -> Children,
@@ -1683,10 +3514,15 @@ impl SupervisorRef {
             self.id(),
             bcast.id()
         );
-        let children = Children::new(bcast);
+        let children = Children::new(bcast)
+            .in_dispatcher_scope(self.dispatcher_scope.clone())
+            .in_supervisor_dispatchers(self.subtree_dispatchers());
         let mut children = init(children);
         debug!("Children({}): Initialized.", children.id());
         // FIXME: children group elems launched without the group itself being launched
+        if let Err(e) = children.register_dispatchers() {
+            warn!("couldn't register all dispatchers into the registry: {}", e);
+        };
         children.launch_elems();
 
         let children_ref = children.as_ref();
@@ -1924,6 +3760,232 @@ impl SupervisorRef {
         self.send(env).map_err(|_| ())
     }
 
+    /// Sends a message to the supervisor this `SupervisorRef`
+    /// is referencing to tell it to stop restarting the elements
+    /// that fault, until [`resume_restarts`] is called.
+    ///
+    /// The faults are queued meanwhile, and the faulted elements
+    /// are restarted (according to the supervision strategy) once
+    /// the restarts are resumed. This avoids restarting them into
+    /// the same failure during a known outage of a dependency.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(())`
+    /// otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let sp_ref = Bastion::supervisor(|sp| sp).unwrap();
+    /// // The database is down for maintenance...
+    /// sp_ref.pause_restarts().expect("Couldn't send the message.");
+    ///
+    /// // ...and is back up.
+    /// sp_ref.resume_restarts().expect("Couldn't send the message.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`resume_restarts`]: Self::resume_restarts
+    #[allow(clippy::result_unit_err)]
+    pub fn pause_restarts(&self) -> Result<(), ()> {
+        debug!("SupervisorRef({}): Pausing restarts.", self.id());
+        let msg = BastionMessage::pause_restarts();
+        let env = Envelope::from_dead_letters(msg);
+        self.send(env).map_err(|_| ())
+    }
+
+    /// Sends a message to the supervisor this `SupervisorRef`
+    /// is referencing to tell it to restart the elements that
+    /// faulted since [`pause_restarts`] was called, and the ones
+    /// that fault from now on.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(())`
+    /// otherwise.
+    ///
+    /// [`pause_restarts`]: Self::pause_restarts
+    #[allow(clippy::result_unit_err)]
+    pub fn resume_restarts(&self) -> Result<(), ()> {
+        debug!("SupervisorRef({}): Resuming restarts.", self.id());
+        let msg = BastionMessage::resume_restarts();
+        let env = Envelope::from_dead_letters(msg);
+        self.send(env).map_err(|_| ())
+    }
+
+    /// Hands `state` over to the successor of an element supervised by
+    /// the supervisor this `SupervisorRef` is referencing, which can
+    /// take it over with [`take_over`] using the same `key`.
+    ///
+    /// This allows stateful roles (eg. a singleton-like group being
+    /// scaled down, shut down or moved) to pass their in-memory state
+    /// to the group replacing them before they stop. A state which was
+    /// already handed over with the same key and wasn't taken over yet
+    /// is replaced. The states are kept by the supervisor, even when
+    /// it is restarted.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key identifying the handed over role.
+    /// * `state` - The state to hand over.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// #[derive(Debug)]
+    /// struct HandOver;
+    ///
+    /// let sp = Bastion::supervisor(|sp| sp).expect("Couldn't create the supervisor.");
+    ///
+    /// let leader = |sp: &SupervisorRef| sp.children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             let sp = ctx.supervisor().unwrap();
+    ///             // Takes the state of the previous leader over...
+    ///             let mut counter = sp.take_over::<u64>("leader").unwrap_or(0);
+    ///             let mut handing_over = false;
+    ///             while !handing_over {
+    ///                 msg! { ctx.recv().await?,
+    ///                     ref _msg: HandOver => handing_over = true;
+    ///                     _: _ => counter += 1;
+    ///                 }
+    ///             }
+    ///
+    ///             // ...and hands it over to the next one.
+    ///             sp.hand_over("leader", counter);
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// });
+    ///
+    /// let old = leader(&sp).expect("Couldn't create the children group.");
+    /// // ...
+    /// old.broadcast(HandOver).expect("Couldn't send the message.");
+    /// let new = leader(&sp).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`take_over`]: Self::take_over
+    pub fn hand_over<T: Send + 'static>(&self, key: impl Into<String>, state: T) {
+        let key = key.into();
+        debug!("SupervisorRef({}): Handing over: {}", self.id(), key);
+        // FIXME: panics?
+        let mut handovers = self.handovers.0.lock().unwrap();
+        handovers.insert(key, Box::new(state));
+    }
+
+    /// Takes over the state handed over with [`hand_over`] using the
+    /// same `key`, if any and if it is of type `T`.
+    ///
+    /// See [`hand_over`] for an example.
+    ///
+    /// [`hand_over`]: Self::hand_over
+    pub fn take_over<T: Send + 'static>(&self, key: &str) -> Option<T> {
+        // FIXME: panics?
+        let mut handovers = self.handovers.0.lock().unwrap();
+        if !handovers.get(key)?.is::<T>() {
+            warn!(
+                "SupervisorRef({}): The state handed over as {} isn't of the expected type.",
+                self.id(),
+                key
+            );
+            return None;
+        }
+
+        debug!("SupervisorRef({}): Taking over: {}", self.id(), key);
+        let state = handovers.remove(key)?;
+        state.downcast().ok().map(|state| *state)
+    }
+
+    /// Returns a snapshot of the supervision subtree of the
+    /// supervisor this `SupervisorRef` is referencing: the children
+    /// groups and supervisors it supervises, their number of running
+    /// elements, restarts and last faults.
+    ///
+    /// See [`Bastion::tree`] to get a snapshot of the whole tree.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// let sp = Bastion::supervisor(|sp| sp).expect("Couldn't create the supervisor.");
+    /// sp.children(|children| children.with_name("workers"))
+    ///     .expect("Couldn't create the children group.");
+    /// # Bastion::start();
+    ///
+    /// // ...
+    /// let stats: SupervisorStats = sp.stats();
+    /// for group in stats.groups() {
+    ///     println!(
+    ///         "{}: {} running, {} restarts",
+    ///         group.name(),
+    ///         group.children(),
+    ///         group.restarts()
+    ///     );
+    /// }
+    /// #
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Bastion::tree`]: crate::Bastion::tree
+    pub fn stats(&self) -> SupervisorStats {
+        self.tree.snapshot()
+    }
+
     pub(crate) fn send(&self, env: Envelope) -> Result<(), Envelope> {
         trace!("SupervisorRef({}): Sending message: {:?}", self.id(), env);
         self.sender
@@ -2047,6 +4109,9 @@ impl RestartStrategy {
     ///         failed actor with the delay increasing linearly.
     ///     - [`ActorRestartStrategy::ExponentialBackOff`] would restart the
     ///         failed actor with the delay, multiplied by given coefficient.
+    ///     - [`ActorRestartStrategy::ExponentialBackOffWithJitter`] would
+    ///       restart the failed actor with the delay doubling up to a
+    ///       maximum, randomly shortened.
     ///
     /// # Example
     ///
@@ -2064,6 +4129,7 @@ impl RestartStrategy {
         RestartStrategy {
             restart_policy,
             strategy,
+            intensity: None,
         }
     }
 
@@ -2092,6 +4158,42 @@ impl RestartStrategy {
         self
     }
 
+    /// Returns the maximum number of times the supervisor recovers
+    /// from the faults of its supervised elements within a window
+    /// of time, if it is bounded.
+    pub fn restart_intensity(&self) -> Option<(usize, Duration)> {
+        self.intensity
+    }
+
+    /// Sets the maximum number of times the supervisor recovers from
+    /// the faults of its supervised elements within `window`.
+    ///
+    /// Once a fault would exceed it, the supervisor stops restarting
+    /// the supervised elements and escalates the fault to its own
+    /// parent instead, by faulting itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_restarts` - The number of recoveries allowed within
+    ///   the window.
+    /// * `window` - The duration of the window.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::time::Duration;
+    /// # use bastion::prelude::*;
+    /// #
+    /// // Escalates the fault once its elements faulted more than 5
+    /// // times within 30s.
+    /// let restart_strategy = RestartStrategy::default()
+    ///     .with_restart_intensity(5, Duration::from_secs(30));
+    /// ```
+    pub fn with_restart_intensity(mut self, max_restarts: usize, window: Duration) -> Self {
+        self.intensity = Some((max_restarts, window));
+        self
+    }
+
     pub(crate) async fn apply_strategy(&self, restarts_count: usize) {
         if let Some(dur) = self.strategy.calculate(restarts_count) {
             Delay::new(dur).await;
@@ -2110,6 +4212,7 @@ impl Default for RestartStrategy {
         RestartStrategy {
             restart_policy: RestartPolicy::Always,
             strategy: ActorRestartStrategy::default(),
+            intensity: None,
         }
     }
 }
@@ -2120,6 +4223,80 @@ impl Default for ActorRestartStrategy {
     }
 }
 
+impl Debug for RestartCircuit {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("RestartCircuit")
+            .field("failure_threshold", &self.failure_threshold)
+            .field("cooldown", &self.cooldown)
+            .finish()
+    }
+}
+
+impl ErrorDirectives {
+    /// Returns the directive the first matching closure mapped the
+    /// error to, if any.
+    fn directive(&self, error: &ExecError) -> Option<Directive> {
+        self.0.iter().find_map(|directive_fn| directive_fn(error))
+    }
+}
+
+impl NamedSupervisors {
+    /// Makes `name` resolve to `supervisor`.
+    pub(crate) fn register(&self, name: String, supervisor: SupervisorRef) {
+        debug!(
+            "NamedSupervisors: Naming Supervisor({}) '{}'.",
+            supervisor.id(),
+            name
+        );
+        // FIXME: panics?
+        let mut named = self.0.lock().unwrap();
+        if let Some(previous) = named.get(&name) {
+            if previous != &supervisor {
+                warn!(
+                    "NamedSupervisors: '{}' doesn't name Supervisor({}) anymore.",
+                    name,
+                    previous.id()
+                );
+            }
+        }
+
+        named.insert(name, supervisor);
+    }
+
+    /// Returns the supervisor named `name`, if it is running.
+    pub(crate) fn get(&self, name: &str) -> Option<SupervisorRef> {
+        // FIXME: panics?
+        self.0.lock().unwrap().get(name).cloned()
+    }
+
+    /// Stops `name` from resolving to the supervisor with the given
+    /// identifier, if it still does.
+    pub(crate) fn unregister(&self, name: &str, id: &BastionId) {
+        // FIXME: panics?
+        let mut named = self.0.lock().unwrap();
+        if named.get(name).map(SupervisorRef::id) == Some(id) {
+            debug!("NamedSupervisors: Removing '{}'.", name);
+            named.remove(name);
+        }
+    }
+}
+
+impl Debug for ErrorDirectives {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("ErrorDirectives")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+impl Debug for Handovers {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        // FIXME: panics?
+        let handovers = self.0.lock().unwrap();
+        fmt.debug_set().entries(handovers.keys()).finish()
+    }
+}
+
 impl PartialEq for SupervisorRef {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
@@ -1,3 +1,4 @@
+use crate::alias::Aliases;
 use crate::broadcast::{Broadcast, Parent, Sender};
 use crate::children_ref::ChildrenRef;
 use crate::context::{BastionContext, BastionId, NIL_ID};
@@ -5,7 +6,8 @@ use crate::dispatcher::GlobalDispatcher;
 use crate::envelope::Envelope;
 use crate::message::{BastionMessage, Deployment};
 use crate::path::{BastionPath, BastionPathElement};
-use crate::supervisor::{Supervisor, SupervisorRef};
+use crate::supervisor::{NamedSupervisors, Supervisor, SupervisorRef};
+use crate::tree::{SupervisorStats, TreeRoots};
 use async_mutex::Mutex as AsyncMutex;
 use bastion_executor::pool;
 use futures::prelude::*;
@@ -15,13 +17,17 @@ use fxhash::{FxHashMap, FxHashSet};
 use lazy_static::lazy_static;
 use lightproc::prelude::*;
 use std::sync::{Arc, Condvar, Mutex};
-use std::task::Poll;
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, trace, warn};
 
 lazy_static! {
     pub(crate) static ref SYSTEM: GlobalSystem = System::init();
 }
 
+// The handler called when a fault escalates to the system.
+type FailureHandler = Arc<dyn Fn(&BastionId) + Send + Sync>;
+
 pub(crate) struct GlobalSystem {
     sender: Sender,
     supervisor: SupervisorRef,
@@ -30,7 +36,15 @@ pub(crate) struct GlobalSystem {
     handle: Arc<AsyncMutex<Option<RecoverableHandle<()>>>>,
     running: Mutex<bool>,
     stopping_cvar: Condvar,
+    // Tasks waiting for the system to stop (see `poll_stopped`).
+    stopping_wakers: Mutex<Vec<Waker>>,
     dispatcher: GlobalDispatcher,
+    aliases: Aliases,
+    named_supervisors: NamedSupervisors,
+    // The handler set with `Bastion::on_unhandled_failure`, if any.
+    failure_handler: Mutex<Option<FailureHandler>>,
+    // The top-level supervisors, shared with the system.
+    tree: Arc<TreeRoots>,
 }
 
 #[derive(Debug)]
@@ -42,6 +56,7 @@ struct System {
     waiting: FuturesUnordered<RecoverableHandle<Supervisor>>,
     pre_start_msgs: Vec<Envelope>,
     started: bool,
+    tree: Arc<TreeRoots>,
 }
 
 #[allow(clippy::mutex_atomic)]
@@ -51,13 +66,18 @@ impl GlobalSystem {
         supervisor: SupervisorRef,
         dead_letters: ChildrenRef,
         handle: RecoverableHandle<()>,
+        tree: Arc<TreeRoots>,
     ) -> Self {
         let handle = Some(handle);
         let handle = Arc::new(AsyncMutex::new(handle));
         let path = Arc::new(BastionPath::root());
         let running = Mutex::new(true);
         let stopping_cvar = Condvar::new();
+        let stopping_wakers = Mutex::new(Vec::new());
         let dispatcher = GlobalDispatcher::new();
+        let aliases = Aliases::new();
+        let named_supervisors = NamedSupervisors::default();
+        let failure_handler = Mutex::new(None);
 
         GlobalSystem {
             sender,
@@ -67,7 +87,12 @@ impl GlobalSystem {
             handle,
             running,
             stopping_cvar,
+            stopping_wakers,
             dispatcher,
+            aliases,
+            named_supervisors,
+            failure_handler,
+            tree,
         }
     }
 
@@ -95,10 +120,46 @@ impl GlobalSystem {
         &self.dispatcher
     }
 
+    pub(crate) fn aliases(&self) -> &Aliases {
+        &self.aliases
+    }
+
+    pub(crate) fn named_supervisors(&self) -> &NamedSupervisors {
+        &self.named_supervisors
+    }
+
+    pub(crate) fn tree(&self) -> Vec<SupervisorStats> {
+        self.tree.snapshot()
+    }
+
+    pub(crate) fn set_failure_handler<F>(&self, handler: F)
+    where
+        F: Fn(&BastionId) + Send + Sync + 'static,
+    {
+        // FIXME: panics?
+        *self.failure_handler.lock().unwrap() = Some(Arc::new(handler));
+    }
+
+    // Calls the failure handler, if one was set, about the fault of
+    // the top-level supervisor `id`.
+    fn notify_failure(&self, id: &BastionId) {
+        // FIXME: panics?
+        let handler = self.failure_handler.lock().unwrap().clone();
+        if let Some(handler) = handler {
+            debug!("System: Calling the unhandled failure handler.");
+            handler(id);
+        }
+    }
+
     pub(crate) fn notify_stopped(&self) {
         // FIXME: panics
         *self.running.lock().unwrap() = false;
         self.stopping_cvar.notify_all();
+
+        // FIXME: panics
+        for waker in self.stopping_wakers.lock().unwrap().drain(..) {
+            waker.wake();
+        }
     }
 
     pub(crate) fn wait_until_stopped(&self) {
@@ -108,6 +169,54 @@ impl GlobalSystem {
             running = self.stopping_cvar.wait(running).unwrap();
         }
     }
+
+    /// Returns `true` if the system stopped before `timeout` elapsed.
+    pub(crate) fn wait_until_stopped_timeout(&self, timeout: Duration) -> bool {
+        // A timeout too large to be represented is as good as none.
+        let deadline = Instant::now().checked_add(timeout);
+        // FIXME: panics
+        let mut running = self.running.lock().unwrap();
+        while *running {
+            let deadline = match deadline {
+                Some(deadline) => deadline,
+                None => {
+                    running = self.stopping_cvar.wait(running).unwrap();
+                    continue;
+                }
+            };
+
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+
+            running = self
+                .stopping_cvar
+                .wait_timeout(running, deadline - now)
+                .unwrap()
+                .0;
+        }
+
+        true
+    }
+
+    pub(crate) fn poll_stopped(&self, ctx: &mut Context) -> Poll<()> {
+        // NOTE: the lock on `running` is held while registering the
+        //      waker so that `notify_stopped` can't drain the wakers
+        //      in between.
+        // FIXME: panics
+        let running = self.running.lock().unwrap();
+        if !*running {
+            return Poll::Ready(());
+        }
+
+        let mut wakers = self.stopping_wakers.lock().unwrap();
+        if !wakers.iter().any(|waker| waker.will_wake(ctx.waker())) {
+            wakers.push(ctx.waker().clone());
+        }
+
+        Poll::Pending
+    }
 }
 
 impl System {
@@ -120,6 +229,7 @@ impl System {
         let waiting = FuturesUnordered::new();
         let pre_start_msgs = Vec::new();
         let started = false;
+        let tree = Arc::new(TreeRoots::default());
 
         let sender = bcast.sender().clone();
 
@@ -130,6 +240,7 @@ impl System {
             waiting,
             pre_start_msgs,
             started,
+            tree: tree.clone(),
         };
 
         debug!("System: Creating the system supervisor.");
@@ -154,7 +265,7 @@ impl System {
         let dead_letters_ref =
             Self::spawn_dead_letters(&supervisor_ref).expect("Can't spawn dead letters");
 
-        GlobalSystem::new(sender, supervisor_ref, dead_letters_ref, handle)
+        GlobalSystem::new(sender, supervisor_ref, dead_letters_ref, handle, tree)
     }
 
     fn stack(&self) -> ProcStack {
@@ -167,7 +278,14 @@ impl System {
             children.with_exec(|ctx: BastionContext| async move {
                 loop {
                     let smsg = ctx.recv().await?;
-                    debug!("Received dead letter: {:?}", smsg);
+                    match smsg.describe() {
+                        Some(contents) => debug!(
+                            "Received dead letter: {}: {}",
+                            smsg.msg().type_name(),
+                            contents
+                        ),
+                        None => debug!("Received dead letter: {:?}", smsg),
+                    }
                 }
             })
         })
@@ -249,13 +367,14 @@ impl System {
         }
     }
 
-    async fn deploy(&mut self, deployment: Box<Deployment>) {
+    async fn deploy(&mut self, deployment: Box<Deployment>) -> Result<(), ()> {
         match *deployment {
             Deployment::Supervisor(supervisor) => {
                 debug!("System: Deploying Supervisor({}).", supervisor.id());
                 supervisor.callbacks().before_start();
 
                 self.bcast.register(supervisor.bcast());
+                self.tree.add(supervisor.tree().clone());
                 if self.started {
                     let msg = BastionMessage::start();
                     let envelope =
@@ -267,9 +386,24 @@ impl System {
                 let id = supervisor.id().clone();
                 let launched = supervisor.launch();
                 self.launched.insert(id, launched);
+
+                Ok(())
+            }
+            // The children groups are deployed by the supervisors.
+            Deployment::Children(children) => {
+                error!(
+                    "System: Can't deploy Children({}) without a supervisor.",
+                    children.id()
+                );
+                Err(())
+            }
+            Deployment::ChildrenBatch(batch) => {
+                error!(
+                    "System: Can't deploy a batch of {} Children without a supervisor.",
+                    batch.len()
+                );
+                Err(())
             }
-            // FIXME
-            Deployment::Children(_) => unimplemented!(),
         }
     }
 
@@ -278,6 +412,7 @@ impl System {
         if let Some(launched) = self.launched.remove(&id) {
             // TODO: stop or kill?
             self.bcast.kill_child(&id);
+            self.tree.remove(&id);
             self.waiting.push(launched);
         }
     }
@@ -320,7 +455,11 @@ impl System {
             Envelope {
                 msg: BastionMessage::Deploy(deployment),
                 ..
-            } => self.deploy(deployment).await,
+            } => {
+                // The deployments which failed were dropped, which
+                // mustn't stop the system.
+                self.deploy(deployment).await.ok();
+            }
             Envelope {
                 msg: BastionMessage::Prune { id },
                 ..
@@ -376,11 +515,38 @@ impl System {
             Envelope {
                 msg: BastionMessage::Faulted { id, .. },
                 ..
-            } => self.restart_supervised_object(id),
+            } => {
+                if self.launched.contains_key(&id) {
+                    SYSTEM.notify_failure(&id);
+                }
+
+                self.restart_supervised_object(id)
+            }
             Envelope {
                 msg: BastionMessage::Heartbeat,
                 ..
             } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::Reconfigure(_),
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::Upgrade(_),
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::PauseRestarts | BastionMessage::ResumeRestarts,
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::CircuitTimeout { .. },
+                ..
+            } => unreachable!(),
+            #[cfg(feature = "chaos")]
+            Envelope {
+                msg: BastionMessage::Fault,
+                ..
+            } => unreachable!(),
         }
 
         Ok(())
@@ -0,0 +1,283 @@
+//!
+//! Property-testing utilities for the protocols of actors.
+//!
+//! A [`Protocol`] describes an actor answering the questions of type
+//! `M` with replies of type `R`, along with invariants that must hold
+//! for every sequence of questions. [`messages`] builds a [proptest]
+//! strategy generating such sequences and [`Protocol::check`] runs one
+//! of them against a fresh instance of the actor, checking the
+//! invariants on its replies.
+//!
+//! The questions are asked one at a time, each of them only being
+//! sent once the previous one was answered (or timed out), so that a
+//! given sequence always reaches the actor in the same order and
+//! failing cases can be shrunk and reproduced.
+//!
+//! [proptest]: https://docs.rs/proptest
+use crate::bastion::Bastion;
+use crate::child_ref::ChildRef;
+use crate::children::Children;
+use crate::executor;
+use crate::message::Message;
+use futures::future::{self, Either};
+use futures_timer::Delay;
+use proptest::collection::{self, SizeRange, VecStrategy};
+use proptest::strategy::Strategy;
+use proptest::test_runner::{TestCaseError, TestError, TestRunner};
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, trace};
+
+/// The default time given to the actor to answer a question before
+/// its reply is considered missing.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Returns a strategy generating sequences of questions built by
+/// `message`, whose length is in `len`.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::testing;
+/// # use proptest::prelude::*;
+/// #
+/// #[derive(Debug, Clone)]
+/// enum Command {
+///     Push(u64),
+///     Pop,
+/// }
+///
+/// let commands = testing::messages(
+///     prop_oneof![any::<u64>().prop_map(Command::Push), Just(Command::Pop)],
+///     0..32,
+/// );
+/// ```
+pub fn messages<S>(message: S, len: impl Into<SizeRange>) -> VecStrategy<S>
+where
+    S: Strategy,
+    S::Value: Message,
+{
+    collection::vec(message, len)
+}
+
+/// A question asked to the actor under test along with its reply.
+#[derive(Debug, Clone)]
+pub struct Exchange<M, R> {
+    message: M,
+    reply: Option<R>,
+}
+
+/// Checks an invariant over the exchanges of a run.
+type Invariant<M, R> = Arc<dyn Fn(&[Exchange<M, R>]) -> bool + Send + Sync>;
+
+/// The protocol of an actor answering questions of type `M` with
+/// replies of type `R`, along with the invariants it must uphold.
+///
+/// The system must have been initialized and started before checking
+/// the protocol.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::testing::{self, Exchange, Protocol};
+/// # use proptest::prelude::*;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// Bastion::init();
+/// Bastion::start();
+///
+/// #[derive(Debug, Clone)]
+/// enum Command {
+///     Push(u64),
+///     Pop,
+/// }
+///
+/// let protocol = Protocol::<Command, usize>::new(|children| {
+///     children.with_exec(|ctx: BastionContext| async move {
+///         let mut stack = Vec::new();
+///         loop {
+///             msg! { ctx.recv().await?,
+///                 command: Command =!> {
+///                     match command {
+///                         Command::Push(value) => stack.push(value),
+///                         Command::Pop => { stack.pop(); }
+///                     }
+///                     answer!(ctx, stack.len()).expect("Couldn't answer.");
+///                 };
+///                 _: _ => ();
+///             }
+///         }
+///     })
+/// })
+/// // The actor answers every question...
+/// .with_invariant(|exchanges| exchanges.iter().all(|exchange| exchange.reply().is_some()))
+/// // ...and never reports more elements than it was given.
+/// .with_invariant(|exchanges| {
+///     let pushed = exchanges
+///         .iter()
+///         .filter(|exchange| matches!(exchange.message(), Command::Push(_)))
+///         .count();
+///     exchanges.last().and_then(Exchange::reply).map_or(true, |len| *len <= pushed)
+/// });
+///
+/// let commands = testing::messages(
+///     prop_oneof![any::<u64>().prop_map(Command::Push), Just(Command::Pop)],
+///     0..16,
+/// );
+///
+/// protocol.run(commands).expect("The protocol doesn't hold.");
+/// #
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Protocol<M, R> {
+    init: Arc<dyn Fn(Children) -> Children + Send + Sync>,
+    invariants: Vec<Invariant<M, R>>,
+    timeout: Duration,
+}
+
+impl<M, R> Protocol<M, R>
+where
+    M: Message + Clone,
+    R: Message + Clone,
+{
+    /// Creates a new protocol whose actor is defined by `init`, which
+    /// is given the children group every instance of the actor will
+    /// be the only element of.
+    pub fn new<I>(init: I) -> Self
+    where
+        I: Fn(Children) -> Children + Send + Sync + 'static,
+    {
+        Protocol {
+            init: Arc::new(init),
+            invariants: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Adds an invariant which must hold after every reply of the
+    /// actor, given all the questions asked so far and their replies.
+    pub fn with_invariant<I>(mut self, invariant: I) -> Self
+    where
+        I: Fn(&[Exchange<M, R>]) -> bool + Send + Sync + 'static,
+    {
+        self.invariants.push(Arc::new(invariant));
+        self
+    }
+
+    /// Sets how long the actor is given to answer a question before
+    /// its reply is considered missing (`1s` by default).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Asks `messages` one at a time to a fresh instance of the actor,
+    /// checking the invariants after each reply.
+    ///
+    /// This method returns the exchanges with the actor if the
+    /// invariants held, or a [`TestCaseError`] (allowing it to be
+    /// used with `?` in a `proptest!` block) otherwise.
+    pub fn check(&self, messages: Vec<M>) -> Result<Vec<Exchange<M, R>>, TestCaseError> {
+        let init = self.init.clone();
+        let children_ref = Bastion::children(move |children| init(children.with_redundancy(1)))
+            .map_err(|_| TestCaseError::fail("Couldn't create the children group."))?;
+        debug!(
+            "Protocol: Checking {} messages against Children({}).",
+            messages.len(),
+            children_ref.id()
+        );
+
+        let res = match children_ref.elems().first() {
+            Some(child_ref) => self.exchange(child_ref, messages),
+            None => Err(TestCaseError::fail("The children group is empty.")),
+        };
+        // TODO: handle errors
+        children_ref.kill().ok();
+
+        res
+    }
+
+    /// Runs [`check`] against the sequences of questions generated by
+    /// `strategy` with a default [`TestRunner`], shrinking the first
+    /// sequence for which the invariants didn't hold.
+    ///
+    /// [`check`]: Self::check
+    pub fn run<S>(&self, strategy: S) -> Result<(), TestError<Vec<M>>>
+    where
+        S: Strategy<Value = Vec<M>>,
+    {
+        TestRunner::default().run(&strategy, |messages| self.check(messages).map(|_| ()))
+    }
+
+    fn exchange(
+        &self,
+        child_ref: &ChildRef,
+        messages: Vec<M>,
+    ) -> Result<Vec<Exchange<M, R>>, TestCaseError> {
+        let mut exchanges = Vec::with_capacity(messages.len());
+        for (seq, message) in messages.into_iter().enumerate() {
+            trace!("Protocol: Asking message #{}: {:?}", seq, message);
+            let answer = child_ref
+                .ask_anonymously(message.clone())
+                .map_err(|_| TestCaseError::fail(format!("Couldn't ask message #{}.", seq)))?;
+
+            let timeout = Delay::new(self.timeout);
+            let reply = match executor::run(future::select(answer, timeout)) {
+                Either::Left((Ok(reply), _)) => {
+                    let (msg, _) = reply.extract();
+                    msg.downcast().ok()
+                }
+                Either::Left((Err(()), _)) | Either::Right(_) => None,
+            };
+
+            exchanges.push(Exchange { message, reply });
+            for (idx, invariant) in self.invariants.iter().enumerate() {
+                if !invariant(&exchanges) {
+                    return Err(TestCaseError::fail(format!(
+                        "Invariant #{} didn't hold after message #{}.",
+                        idx, seq
+                    )));
+                }
+            }
+        }
+
+        Ok(exchanges)
+    }
+}
+
+impl<M, R> Exchange<M, R> {
+    /// Returns the question asked to the actor.
+    pub fn message(&self) -> &M {
+        &self.message
+    }
+
+    /// Returns the reply of the actor, if it answered in time with a
+    /// message of the expected type.
+    pub fn reply(&self) -> Option<&R> {
+        self.reply.as_ref()
+    }
+}
+
+impl<M, R> Debug for Protocol<M, R> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Protocol")
+            .field("invariants", &self.invariants.len())
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
@@ -0,0 +1,437 @@
+//!
+//! Changes applied atomically to the states of several elements,
+//! with a two-phase commit.
+//!
+//! A [`Coordinator`] runs a [`Transaction`] in two phases. It first
+//! asks every participant to [`Prepare`] its change, which they
+//! answer with a [`Vote`]. If all of them vote [`Vote::Yes`] on time,
+//! the coordinator decides to commit the transaction, otherwise to
+//! abort it, and saves its [`Decision`] in a [`DecisionStore`] before
+//! telling it to the participants with a [`Commit`] or an [`Abort`]
+//! message, which they answer (with any message) once they applied
+//! it.
+//!
+//! If a participant doesn't acknowledge the decision on time (eg.
+//! because it crashed), the decision stays in the store: running the
+//! transaction again with the same id tells the saved decision to the
+//! participants instead of preparing them again, and the participants
+//! which prepared a change can look the decision up with
+//! [`Coordinator::decision`]. Applying a decision can thus happen more
+//! than once, and should be idempotent.
+use crate::child_ref::ChildRef;
+use crate::envelope::SignedMessage;
+use crate::errors::{AskError, TransactionError};
+use crate::message::Message;
+use futures::future::{self, Either};
+use fxhash::FxHashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, trace, warn};
+
+/// The default time given to the participants to answer.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Persists the decisions of the transactions until every
+/// participant acknowledged them.
+///
+/// The [`MemoryDecisions`] keep the decisions in memory, which allows
+/// to finish the transactions after the elements coordinating them
+/// were restarted; implementing this trait on top of a database
+/// allows to finish them after the whole process was restarted.
+pub trait DecisionStore: Send + Sync + 'static {
+    /// Returns the decision of the transaction with the given id, if
+    /// it was saved and not every participant acknowledged it yet.
+    fn load(&self, transaction: &str) -> Option<Decision>;
+    /// Saves the decision of the transaction with the given id.
+    fn save(&self, transaction: &str, decision: Decision);
+    /// Forgets the transaction with the given id, once every
+    /// participant acknowledged its decision.
+    fn remove(&self, transaction: &str);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Whether a transaction is committed or aborted.
+pub enum Decision {
+    /// Every participant voted to commit the transaction.
+    Commit,
+    /// A participant voted to abort the transaction or didn't vote
+    /// on time.
+    Abort,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The answer of a participant to a [`Prepare`] message.
+pub enum Vote {
+    /// The participant prepared its change and is able to commit it.
+    Yes,
+    /// The participant can't commit its change.
+    No,
+}
+
+#[derive(Debug, Clone)]
+/// The message asking a participant to prepare its change, which it
+/// answers with a [`Vote`].
+pub struct Prepare<T> {
+    transaction: String,
+    change: T,
+}
+
+#[derive(Debug, Clone)]
+/// The message telling a participant to commit the change it
+/// prepared, which it answers once it did.
+pub struct Commit {
+    transaction: String,
+}
+
+#[derive(Debug, Clone)]
+/// The message telling a participant to abort the change it
+/// prepared (if it did), which it answers once it did.
+pub struct Abort {
+    transaction: String,
+}
+
+#[derive(Debug, Clone)]
+/// A change of type `T` to apply to the states of several elements.
+pub struct Transaction<T> {
+    id: String,
+    // The participants along with their change.
+    participants: Vec<(ChildRef, T)>,
+}
+
+/// A [`DecisionStore`] keeping the decisions in memory.
+///
+/// Cloning a `MemoryDecisions` returns a new handle to the same
+/// store.
+#[derive(Clone, Default)]
+pub struct MemoryDecisions {
+    decisions: Arc<Mutex<FxHashMap<String, Decision>>>,
+}
+
+/// Runs transactions with a two-phase commit, saving their
+/// decisions in a [`DecisionStore`].
+///
+/// Cloning a `Coordinator` returns a new handle to the same store.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::transaction::{
+/// #     Abort, Commit, Coordinator, MemoryDecisions, Prepare, Transaction, Vote,
+/// # };
+/// # use std::time::Duration;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let account = |balance: i64| {
+///     Bastion::children(move |children| {
+///         children.with_exec(move |ctx: BastionContext| async move {
+///             let mut balance = balance;
+///             let mut prepared = None;
+///             loop {
+///                 msg! { ctx.recv().await?,
+///                     prepare: Prepare<i64> =!> {
+///                         let vote = if balance + prepare.change() >= 0 {
+///                             prepared = Some(*prepare.change());
+///                             Vote::Yes
+///                         } else {
+///                             Vote::No
+///                         };
+///                         answer!(ctx, vote).ok();
+///                     };
+///                     _commit: Commit =!> {
+///                         balance += prepared.take().unwrap_or(0);
+///                         answer!(ctx, ()).ok();
+///                     };
+///                     _abort: Abort =!> {
+///                         prepared = None;
+///                         answer!(ctx, ()).ok();
+///                     };
+///                     _: _ => ();
+///                 }
+///             }
+///         })
+///     })
+///     .expect("Couldn't create the children group.")
+/// };
+///
+/// let alice = account(100);
+/// let bob = account(0);
+/// let coordinator = Coordinator::new(MemoryDecisions::new())
+///     .with_timeout(Duration::from_secs(1));
+///
+/// # Bastion::start();
+/// # run!(async move {
+/// let transfer = Transaction::new("transfer-1")
+///     .with_participant(alice.elems()[0].clone(), -50)
+///     .with_participant(bob.elems()[0].clone(), 50);
+/// match coordinator.run(transfer).await {
+///     Ok(()) => {
+///         // Both balances were updated...
+///     }
+///     Err(_) => {
+///         // Neither was, or a participant didn't acknowledge the
+///         // decision yet...
+///     }
+/// }
+/// # });
+/// #
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Coordinator {
+    store: Arc<dyn DecisionStore>,
+    timeout: Duration,
+}
+
+impl Coordinator {
+    /// Creates a new coordinator saving the decisions of the
+    /// transactions in `store`.
+    pub fn new<D: DecisionStore>(store: D) -> Self {
+        Coordinator {
+            store: Arc::new(store),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Sets how long the participants are given to answer each
+    /// message, `5s` by default.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns the decision of the transaction with the given id, if
+    /// not every participant acknowledged it yet.
+    ///
+    /// The participants which prepared a change without having been
+    /// told the decision (eg. because they were restarted) can use
+    /// it to know whether to commit or abort it.
+    pub fn decision(&self, transaction: &str) -> Option<Decision> {
+        self.store.load(transaction)
+    }
+
+    /// Runs `transaction`, preparing its participants and telling
+    /// them the decision, or only telling them the decision if it was
+    /// saved by a previous run which didn't finish.
+    ///
+    /// This method returns `Ok(())` if the transaction was committed
+    /// and every participant acknowledged it, or a
+    /// [`TransactionError`] otherwise: if it was aborted, or if a
+    /// participant didn't acknowledge the decision, in which case
+    /// it is told again the next time the transaction is run.
+    pub async fn run<T: Message>(
+        &self,
+        transaction: Transaction<T>,
+    ) -> Result<(), TransactionError> {
+        let id = transaction.id;
+        if let Some(decision) = self.store.load(&id) {
+            debug!(
+                "Transaction({}): Resuming with decision: {:?}",
+                id, decision
+            );
+            let participants = transaction
+                .participants
+                .into_iter()
+                .map(|(participant, _)| participant)
+                .collect();
+            return self.decide(&id, participants, decision).await;
+        }
+
+        let (participants, changes): (Vec<_>, Vec<_>) =
+            transaction.participants.into_iter().unzip();
+        let mut votes = Vec::with_capacity(participants.len());
+        for (participant, change) in participants.iter().zip(changes) {
+            trace!(
+                "Transaction({}): Preparing Child({}).",
+                id,
+                participant.id()
+            );
+            let prepare = Prepare {
+                transaction: id.clone(),
+                change,
+            };
+            votes.push(self.ask(participant, prepare));
+        }
+
+        // The participants which didn't vote on time count as
+        // voting no.
+        let committed = future::join_all(votes).await.into_iter().all(|answer| {
+            answer
+                .and_then(|answer| answer.extract().0.downcast::<Vote>().ok())
+                .map(|vote| vote == Vote::Yes)
+                .unwrap_or(false)
+        });
+        let decision = if committed {
+            Decision::Commit
+        } else {
+            Decision::Abort
+        };
+
+        debug!("Transaction({}): Decided to {:?}.", id, decision);
+        self.store.save(&id, decision);
+        self.decide(&id, participants, decision).await
+    }
+
+    // Tells `decision` to the participants, forgetting it once all
+    // of them acknowledged it.
+    async fn decide(
+        &self,
+        id: &str,
+        participants: Vec<ChildRef>,
+        decision: Decision,
+    ) -> Result<(), TransactionError> {
+        let acks = participants.iter().map(|participant| {
+            let transaction = id.to_string();
+            match decision {
+                Decision::Commit => Either::Left(self.ask(participant, Commit { transaction })),
+                Decision::Abort => Either::Right(self.ask(participant, Abort { transaction })),
+            }
+        });
+
+        let unacknowledged = future::join_all(acks)
+            .await
+            .into_iter()
+            .filter(Option::is_none)
+            .count();
+        if unacknowledged > 0 {
+            warn!(
+                "Transaction({}): {} participants didn't acknowledge the decision.",
+                id, unacknowledged
+            );
+            return match decision {
+                Decision::Commit => Err(TransactionError::CommitInDoubt(unacknowledged)),
+                Decision::Abort => Err(TransactionError::AbortInDoubt(unacknowledged)),
+            };
+        }
+
+        debug!("Transaction({}): Finished.", id);
+        self.store.remove(id);
+        match decision {
+            Decision::Commit => Ok(()),
+            Decision::Abort => Err(TransactionError::Aborted),
+        }
+    }
+
+    // Asks `msg` to `participant`, returning its answer if it
+    // answered on time.
+    async fn ask<M: Message>(&self, participant: &ChildRef, msg: M) -> Option<SignedMessage> {
+        let answer = participant.ask_anonymously(msg).ok()?;
+        match answer.within(Some(self.timeout)).await {
+            Ok(answer) => Some(answer),
+            Err(AskError::Timeout(_)) => {
+                debug!(
+                    "Coordinator: Child({}) didn't answer within {:?}.",
+                    participant.id(),
+                    self.timeout
+                );
+                None
+            }
+            Err(_) => None,
+        }
+    }
+}
+
+impl<T> Prepare<T> {
+    /// Returns the id of the transaction.
+    pub fn transaction(&self) -> &str {
+        &self.transaction
+    }
+
+    /// Returns the change the participant has to prepare.
+    pub fn change(&self) -> &T {
+        &self.change
+    }
+}
+
+impl Commit {
+    /// Returns the id of the transaction.
+    pub fn transaction(&self) -> &str {
+        &self.transaction
+    }
+}
+
+impl Abort {
+    /// Returns the id of the transaction.
+    pub fn transaction(&self) -> &str {
+        &self.transaction
+    }
+}
+
+impl<T> Transaction<T> {
+    /// Creates a new transaction with the given id and without
+    /// any participant.
+    pub fn new(id: impl Into<String>) -> Self {
+        Transaction {
+            id: id.into(),
+            participants: Vec::new(),
+        }
+    }
+
+    /// Adds a participant to the transaction, along with the change
+    /// it has to apply.
+    pub fn with_participant(mut self, participant: ChildRef, change: T) -> Self {
+        self.participants.push((participant, change));
+        self
+    }
+
+    /// Returns the id of the transaction.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl MemoryDecisions {
+    /// Creates a new empty store.
+    pub fn new() -> Self {
+        MemoryDecisions::default()
+    }
+}
+
+impl DecisionStore for MemoryDecisions {
+    fn load(&self, transaction: &str) -> Option<Decision> {
+        self.decisions.lock().unwrap().get(transaction).copied()
+    }
+
+    fn save(&self, transaction: &str, decision: Decision) {
+        self.decisions
+            .lock()
+            .unwrap()
+            .insert(transaction.to_string(), decision);
+    }
+
+    fn remove(&self, transaction: &str) {
+        self.decisions.lock().unwrap().remove(transaction);
+    }
+}
+
+impl Debug for MemoryDecisions {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("MemoryDecisions")
+            .field("decisions", &self.decisions.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl Debug for Coordinator {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Coordinator")
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
@@ -0,0 +1,416 @@
+//!
+//! Snapshots of the supervision tree, for introspection.
+//!
+//! [`Bastion::tree`] and [`SupervisorRef::stats`] return a snapshot of
+//! the supervisors and children groups of the whole tree or of a
+//! subtree: their paths, the names and the number of running elements
+//! of the groups, how many times their elements were restarted, when
+//! they last faulted and how much the executor polled them.
+//!
+//! [`Bastion::tree`]: crate::Bastion::tree
+//! [`SupervisorRef::stats`]: crate::supervisor::SupervisorRef::stats
+use crate::context::BastionId;
+use crate::path::BastionPath;
+use fxhash::FxHashMap;
+use lightproc::proc_stack::ProcStats;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Clone)]
+/// A snapshot of a supervisor and of the subtree it supervises.
+pub struct SupervisorStats {
+    id: BastionId,
+    path: Arc<BastionPath>,
+    faults: usize,
+    last_fault: Option<SystemTime>,
+    groups: Vec<GroupStats>,
+    supervisors: Vec<SupervisorStats>,
+}
+
+#[derive(Debug, Clone)]
+/// A snapshot of a children group.
+pub struct GroupStats {
+    id: BastionId,
+    name: String,
+    path: Arc<BastionPath>,
+    children: usize,
+    restarts: usize,
+    last_fault: Option<SystemTime>,
+    poll_count: u64,
+    poll_time: Duration,
+}
+
+#[derive(Debug)]
+/// The live counterpart of a [`SupervisorStats`], shared by the
+/// supervisor, its references and its parent.
+pub(crate) struct SupervisorNode {
+    // The identifier and the path of the supervisor, which change
+    // when the system restarts it.
+    identity: Mutex<(BastionId, Arc<BastionPath>)>,
+    faults: AtomicUsize,
+    last_fault: Mutex<Option<SystemTime>>,
+    // The supervised groups and supervisors, in the order they were
+    // added.
+    supervised: Mutex<Vec<SupervisedNode>>,
+}
+
+#[derive(Debug)]
+/// The live counterpart of a [`GroupStats`], shared by the children
+/// group and its supervisor.
+pub(crate) struct GroupNode {
+    id: BastionId,
+    name: String,
+    path: Arc<BastionPath>,
+    children: AtomicUsize,
+    restarts: AtomicUsize,
+    last_fault: Mutex<Option<SystemTime>>,
+    // The poll accounting of the running elements.
+    stats: Mutex<FxHashMap<BastionId, Arc<ProcStats>>>,
+}
+
+#[derive(Debug, Default)]
+/// The top-level supervisors, supervised by the system.
+pub(crate) struct TreeRoots {
+    supervisors: Mutex<Vec<Arc<SupervisorNode>>>,
+}
+
+#[derive(Debug, Clone)]
+enum SupervisedNode {
+    Supervisor(Arc<SupervisorNode>),
+    Group(Arc<GroupNode>),
+}
+
+impl SupervisorStats {
+    /// Returns the identifier of the supervisor.
+    pub fn id(&self) -> &BastionId {
+        &self.id
+    }
+
+    /// Returns the path of the supervisor.
+    pub fn path(&self) -> &BastionPath {
+        &self.path
+    }
+
+    /// Returns how many faults of the elements it supervises the
+    /// supervisor recovered from.
+    pub fn faults(&self) -> usize {
+        self.faults
+    }
+
+    /// Returns when the supervisor last recovered from a fault, if
+    /// it ever did.
+    pub fn last_fault(&self) -> Option<SystemTime> {
+        self.last_fault
+    }
+
+    /// Returns the children groups the supervisor supervises, in the
+    /// order they were added.
+    pub fn groups(&self) -> &[GroupStats] {
+        &self.groups
+    }
+
+    /// Returns the supervisors the supervisor supervises, in the
+    /// order they were added.
+    pub fn supervisors(&self) -> &[SupervisorStats] {
+        &self.supervisors
+    }
+}
+
+impl GroupStats {
+    /// Returns the identifier of the children group.
+    pub fn id(&self) -> &BastionId {
+        &self.id
+    }
+
+    /// Returns the name of the children group.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the path of the children group.
+    pub fn path(&self) -> &BastionPath {
+        &self.path
+    }
+
+    /// Returns the number of elements of the group which are
+    /// running.
+    pub fn children(&self) -> usize {
+        self.children
+    }
+
+    /// Returns how many times the elements of the group were
+    /// restarted.
+    pub fn restarts(&self) -> usize {
+        self.restarts
+    }
+
+    /// Returns when an element of the group last faulted, if one
+    /// ever did.
+    pub fn last_fault(&self) -> Option<SystemTime> {
+        self.last_fault
+    }
+
+    /// Returns the number of times the executor polled the running
+    /// elements of the group since they were last (re)started.
+    pub fn poll_count(&self) -> u64 {
+        self.poll_count
+    }
+
+    /// Returns the time the executor spent polling the running
+    /// elements of the group since they were last (re)started.
+    pub fn poll_time(&self) -> Duration {
+        self.poll_time
+    }
+}
+
+impl SupervisorNode {
+    pub(crate) fn new(id: BastionId, path: Arc<BastionPath>) -> Self {
+        SupervisorNode {
+            identity: Mutex::new((id, path)),
+            faults: AtomicUsize::new(0),
+            last_fault: Mutex::new(None),
+            supervised: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn id(&self) -> BastionId {
+        // FIXME: panics?
+        self.identity.lock().unwrap().0.clone()
+    }
+
+    /// Updates the identifier and the path of the supervisor, once it
+    /// was restarted.
+    pub(crate) fn reset(&self, id: BastionId, path: Arc<BastionPath>) {
+        // FIXME: panics?
+        *self.identity.lock().unwrap() = (id, path);
+    }
+
+    pub(crate) fn fault(&self) {
+        self.faults.fetch_add(1, Ordering::SeqCst);
+        // FIXME: panics?
+        *self.last_fault.lock().unwrap() = Some(SystemTime::now());
+    }
+
+    pub(crate) fn add_supervisor(&self, supervisor: Arc<SupervisorNode>) {
+        // FIXME: panics?
+        let mut supervised = self.supervised.lock().unwrap();
+        supervised.push(SupervisedNode::Supervisor(supervisor));
+    }
+
+    pub(crate) fn add_group(&self, group: Arc<GroupNode>) {
+        // FIXME: panics?
+        let mut supervised = self.supervised.lock().unwrap();
+        supervised.push(SupervisedNode::Group(group));
+    }
+
+    /// Removes the supervised group or supervisor with the given
+    /// identifier.
+    pub(crate) fn remove(&self, id: &BastionId) {
+        // FIXME: panics?
+        let mut supervised = self.supervised.lock().unwrap();
+        supervised.retain(|node| match node {
+            SupervisedNode::Supervisor(supervisor) => supervisor.id() != *id,
+            SupervisedNode::Group(group) => group.id != *id,
+        });
+    }
+
+    pub(crate) fn snapshot(&self) -> SupervisorStats {
+        // FIXME: panics?
+        let (id, path) = self.identity.lock().unwrap().clone();
+        let supervised = self.supervised.lock().unwrap().clone();
+
+        let mut groups = Vec::new();
+        let mut supervisors = Vec::new();
+        for node in supervised {
+            match node {
+                SupervisedNode::Supervisor(supervisor) => supervisors.push(supervisor.snapshot()),
+                SupervisedNode::Group(group) => groups.push(group.snapshot()),
+            }
+        }
+
+        SupervisorStats {
+            id,
+            path,
+            faults: self.faults.load(Ordering::SeqCst),
+            // FIXME: panics?
+            last_fault: *self.last_fault.lock().unwrap(),
+            groups,
+            supervisors,
+        }
+    }
+}
+
+impl GroupNode {
+    pub(crate) fn new(id: BastionId, name: String, path: Arc<BastionPath>) -> Self {
+        GroupNode {
+            id,
+            name,
+            path,
+            children: AtomicUsize::new(0),
+            restarts: AtomicUsize::new(0),
+            last_fault: Mutex::new(None),
+            stats: Mutex::new(FxHashMap::default()),
+        }
+    }
+
+    pub(crate) fn set_children(&self, children: usize) {
+        self.children.store(children, Ordering::SeqCst);
+    }
+
+    pub(crate) fn fault(&self) {
+        // FIXME: panics?
+        *self.last_fault.lock().unwrap() = Some(SystemTime::now());
+    }
+
+    pub(crate) fn restarted(&self) {
+        self.restarts.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Tracks the poll accounting of the element with the given
+    /// identifier, once it was (re)launched.
+    pub(crate) fn launched(&self, id: BastionId, stats: Arc<ProcStats>) {
+        // FIXME: panics?
+        self.stats.lock().unwrap().insert(id, stats);
+    }
+
+    /// Stops tracking the poll accounting of the element with the
+    /// given identifier, or of all of them.
+    pub(crate) fn dropped(&self, id: Option<&BastionId>) {
+        // FIXME: panics?
+        let mut stats = self.stats.lock().unwrap();
+        match id {
+            Some(id) => {
+                stats.remove(id);
+            }
+            None => stats.clear(),
+        }
+    }
+
+    fn snapshot(&self) -> GroupStats {
+        // FIXME: panics?
+        let stats = self.stats.lock().unwrap();
+        let poll_count = stats.values().map(|stats| stats.polls()).sum();
+        let poll_time = stats.values().map(|stats| stats.poll_time()).sum();
+
+        GroupStats {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            path: self.path.clone(),
+            children: self.children.load(Ordering::SeqCst),
+            restarts: self.restarts.load(Ordering::SeqCst),
+            // FIXME: panics?
+            last_fault: *self.last_fault.lock().unwrap(),
+            poll_count,
+            poll_time,
+        }
+    }
+}
+
+impl TreeRoots {
+    pub(crate) fn add(&self, supervisor: Arc<SupervisorNode>) {
+        // FIXME: panics?
+        self.supervisors.lock().unwrap().push(supervisor);
+    }
+
+    pub(crate) fn remove(&self, id: &BastionId) {
+        // FIXME: panics?
+        let mut supervisors = self.supervisors.lock().unwrap();
+        supervisors.retain(|supervisor| supervisor.id() != *id);
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<SupervisorStats> {
+        // FIXME: panics?
+        let supervisors = self.supervisors.lock().unwrap().clone();
+        supervisors
+            .iter()
+            .map(|supervisor| supervisor.snapshot())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path() -> Arc<BastionPath> {
+        Arc::new(BastionPath::root())
+    }
+
+    #[test]
+    fn snapshots_the_supervised_nodes_in_order() {
+        let roots = TreeRoots::default();
+        let root = Arc::new(SupervisorNode::new(BastionId::new(), path()));
+        let nested = Arc::new(SupervisorNode::new(BastionId::new(), path()));
+        let first = Arc::new(GroupNode::new(BastionId::new(), "first".into(), path()));
+        let second = Arc::new(GroupNode::new(BastionId::new(), "second".into(), path()));
+        root.add_group(first.clone());
+        root.add_supervisor(nested.clone());
+        nested.add_group(second.clone());
+        roots.add(root.clone());
+
+        let tree = roots.snapshot();
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].id(), &root.id());
+        let names = tree[0].groups().iter().map(GroupStats::name);
+        assert_eq!(names.collect::<Vec<_>>(), vec!["first"]);
+        assert_eq!(tree[0].supervisors().len(), 1);
+        assert_eq!(tree[0].supervisors()[0].groups()[0].id(), &second.id);
+
+        root.remove(&nested.id());
+        root.remove(&first.id);
+        let tree = roots.snapshot();
+        assert!(tree[0].groups().is_empty());
+        assert!(tree[0].supervisors().is_empty());
+
+        roots.remove(&root.id());
+        assert!(roots.snapshot().is_empty());
+    }
+
+    #[test]
+    fn tracks_the_faults_and_the_restarts() {
+        let supervisor = SupervisorNode::new(BastionId::new(), path());
+        let group = Arc::new(GroupNode::new(BastionId::new(), "group".into(), path()));
+        supervisor.add_group(group.clone());
+
+        let stats = supervisor.snapshot();
+        assert_eq!(stats.faults(), 0);
+        assert!(stats.last_fault().is_none());
+        assert!(stats.groups()[0].last_fault().is_none());
+
+        group.set_children(3);
+        group.fault();
+        group.restarted();
+        supervisor.fault();
+        let stats = supervisor.snapshot();
+        assert_eq!(stats.faults(), 1);
+        assert!(stats.last_fault().is_some());
+        assert_eq!(stats.groups()[0].children(), 3);
+        assert_eq!(stats.groups()[0].restarts(), 1);
+        assert!(stats.groups()[0].last_fault().is_some());
+
+        // A restarted supervisor keeps its node.
+        let id = BastionId::new();
+        supervisor.reset(id.clone(), path());
+        assert_eq!(supervisor.snapshot().id(), &id);
+        assert_eq!(supervisor.snapshot().groups().len(), 1);
+    }
+
+    #[test]
+    fn sums_the_poll_accounting_of_the_running_elements() {
+        let group = GroupNode::new(BastionId::new(), "group".into(), path());
+        let (first, second) = (BastionId::new(), BastionId::new());
+        group.launched(first.clone(), Arc::new(ProcStats::default()));
+        group.launched(second, Arc::new(ProcStats::default()));
+        assert_eq!(group.stats.lock().unwrap().len(), 2);
+
+        group.dropped(Some(&first));
+        assert_eq!(group.stats.lock().unwrap().len(), 1);
+        group.dropped(None);
+        assert!(group.stats.lock().unwrap().is_empty());
+
+        let stats = group.snapshot();
+        assert_eq!(stats.poll_count(), 0);
+        assert_eq!(stats.poll_time(), Duration::from_secs(0));
+    }
+}
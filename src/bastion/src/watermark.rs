@@ -0,0 +1,320 @@
+//!
+//! Watermarks on the bounded mailboxes of children groups, reporting
+//! when they fill up before they overflow.
+//!
+//! [`Watermarks`] attached to a children group with
+//! [`Children::with_mailbox_watermarks`] are levels, in percents of the
+//! capacity of the mailboxes (see [`Children::with_mailbox_capacity`]),
+//! which are crossed when the mailbox of an element of the group fills
+//! up or drains. Every crossing is logged and reported to the callback
+//! of the watermarks, if any, so that the load can be shed or the group
+//! scaled before messages get dropped. With the `metrics` feature, the
+//! stats collector of the group also counts the crossings.
+//!
+//! [`Children::with_mailbox_watermarks`]: crate::children::Children::with_mailbox_watermarks
+//! [`Children::with_mailbox_capacity`]: crate::children::Children::with_mailbox_capacity
+use crate::context::BastionId;
+use std::fmt::{self, Debug, Formatter};
+#[cfg(feature = "metrics")]
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// The levels the watermarks are at by default, in percents of the
+/// capacity of the mailboxes.
+const DEFAULT_LEVELS: [u8; 3] = [50, 80, 100];
+
+type Callback = Arc<dyn Fn(&WatermarkEvent) + Send + Sync>;
+
+#[derive(Clone)]
+/// The levels, in percents of the capacity of the mailboxes, whose
+/// crossings are reported.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::watermark::{WatermarkEvent, Watermarks};
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let watermarks = Watermarks::new().with_callback(|event: &WatermarkEvent| {
+///     if event.is_rising() && event.level() >= 80 {
+///         // Start shedding load or scaling the group up...
+///     }
+/// });
+///
+/// Bastion::children(|children| {
+///     children
+///         .with_mailbox_capacity(1024)
+///         .with_mailbox_watermarks(watermarks)
+///         .with_exec(|ctx| {
+///             async move {
+///                 // ...
+///                 # Ok(())
+///             }
+///         })
+/// }).expect("Couldn't create the children group.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Watermarks {
+    // Sorted and without duplicates.
+    levels: Vec<u8>,
+    callback: Option<Callback>,
+}
+
+#[derive(Debug, Clone)]
+/// The crossing of a watermark by the mailbox of an element of a
+/// children group.
+pub struct WatermarkEvent {
+    child: BastionId,
+    level: u8,
+    depth: usize,
+    capacity: usize,
+    rising: bool,
+}
+
+#[derive(Debug)]
+/// The watermarks of the mailbox of an element, and the highest one
+/// it reached.
+pub(crate) struct WatermarkState {
+    watermarks: Watermarks,
+    child: BastionId,
+    capacity: usize,
+    // The number of levels the mailbox reached.
+    reached: AtomicUsize,
+    // The number of times each level was crossed while rising.
+    #[cfg(feature = "metrics")]
+    crossings: Vec<AtomicU64>,
+}
+
+impl Watermarks {
+    /// Creates watermarks at 50%, 80% and 100% of the capacity of
+    /// the mailboxes, which are only logged when crossed.
+    pub fn new() -> Self {
+        Watermarks {
+            levels: DEFAULT_LEVELS.to_vec(),
+            callback: None,
+        }
+    }
+
+    /// Replaces the levels of the watermarks, in percents of the
+    /// capacity of the mailboxes (between 1 and 100).
+    pub fn with_levels(mut self, levels: &[u8]) -> Self {
+        let mut levels = levels
+            .iter()
+            .map(|level| (*level).clamp(1, 100))
+            .collect::<Vec<_>>();
+        levels.sort_unstable();
+        levels.dedup();
+
+        self.levels = levels;
+        self
+    }
+
+    /// Sets the callback which is called every time the mailbox of an
+    /// element crosses one of the watermarks, whether it fills up or
+    /// drains.
+    ///
+    /// The callback is called by the element receiving or sending the
+    /// message, so it should return quickly.
+    pub fn with_callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&WatermarkEvent) + Send + Sync + 'static,
+    {
+        self.callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Returns the levels of the watermarks, in percents of the
+    /// capacity of the mailboxes.
+    pub fn levels(&self) -> &[u8] {
+        &self.levels
+    }
+}
+
+impl WatermarkEvent {
+    /// Returns the identifier of the element whose mailbox crossed
+    /// the watermark.
+    pub fn child(&self) -> &BastionId {
+        &self.child
+    }
+
+    /// Returns the level of the crossed watermark, in percents of the
+    /// capacity of the mailbox.
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Returns the number of messages in the mailbox when it crossed
+    /// the watermark.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the number of messages the mailbox can hold.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns whether the mailbox crossed the watermark while filling
+    /// up, or while draining otherwise.
+    pub fn is_rising(&self) -> bool {
+        self.rising
+    }
+}
+
+impl WatermarkState {
+    pub(crate) fn new(watermarks: Watermarks, child: BastionId, capacity: usize) -> Self {
+        WatermarkState {
+            #[cfg(feature = "metrics")]
+            crossings: watermarks
+                .levels
+                .iter()
+                .map(|_| AtomicU64::new(0))
+                .collect(),
+            watermarks,
+            child,
+            capacity,
+            reached: AtomicUsize::new(0),
+        }
+    }
+
+    /// Reports the watermarks the mailbox crossed to reach `depth`
+    /// messages, if any.
+    pub(crate) fn update(&self, depth: usize) {
+        let levels = &self.watermarks.levels;
+        let reached = levels
+            .iter()
+            .take_while(|level| depth * 100 >= self.capacity * **level as usize)
+            .count();
+        let previous = self.reached.swap(reached, Ordering::SeqCst);
+        if reached == previous {
+            return;
+        }
+
+        let rising = reached > previous;
+        let level = levels[reached.max(previous) - 1];
+        if rising {
+            warn!(
+                "Child({}): Mailbox reached {}% of its capacity ({} messages).",
+                self.child, level, depth
+            );
+            #[cfg(feature = "metrics")]
+            for crossings in &self.crossings[previous..reached] {
+                crossings.fetch_add(1, Ordering::Relaxed);
+            }
+        } else {
+            debug!(
+                "Child({}): Mailbox went below {}% of its capacity ({} messages).",
+                self.child, level, depth
+            );
+        }
+
+        if let Some(callback) = &self.watermarks.callback {
+            callback(&WatermarkEvent {
+                child: self.child.clone(),
+                level,
+                depth,
+                capacity: self.capacity,
+                rising,
+            });
+        }
+    }
+
+    /// Returns the number of times each level was crossed while the
+    /// mailbox was filling up.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn crossings(&self) -> impl Iterator<Item = (u8, u64)> + '_ {
+        self.watermarks
+            .levels
+            .iter()
+            .copied()
+            .zip(self.crossings.iter().map(|c| c.load(Ordering::Relaxed)))
+    }
+}
+
+impl Default for Watermarks {
+    fn default() -> Self {
+        Watermarks::new()
+    }
+}
+
+impl Debug for Watermarks {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Watermarks")
+            .field("levels", &self.levels)
+            .field("callback", &self.callback.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn levels_are_bounded_sorted_and_unique() {
+        let watermarks = Watermarks::new().with_levels(&[120, 0, 50, 10, 50]);
+        assert_eq!(watermarks.levels(), &[1, 10, 50, 100]);
+    }
+
+    #[test]
+    fn reports_the_crossings() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let reported = events.clone();
+        let watermarks = Watermarks::new().with_levels(&[50, 100]).with_callback(
+            move |event: &WatermarkEvent| {
+                let event = (event.level(), event.depth(), event.is_rising());
+                reported.lock().unwrap().push(event);
+            },
+        );
+        let state = WatermarkState::new(watermarks, BastionId::new(), 10);
+
+        for depth in &[1, 4, 5, 6, 10, 10, 9, 4, 0] {
+            state.update(*depth);
+        }
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                (50, 5, true),
+                (100, 10, true),
+                (100, 9, false),
+                (50, 4, false)
+            ]
+        );
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn counts_the_rising_crossings() {
+        let watermarks = Watermarks::new().with_levels(&[50, 100]);
+        let state = WatermarkState::new(watermarks, BastionId::new(), 10);
+
+        for depth in &[5, 0, 10, 4, 6] {
+            state.update(*depth);
+        }
+        assert_eq!(
+            state.crossings().collect::<Vec<_>>(),
+            vec![(50, 3), (100, 1)]
+        );
+    }
+}
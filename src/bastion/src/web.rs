@@ -0,0 +1,196 @@
+//!
+//! Helpers to call actors from the handlers of web frameworks.
+//!
+//! A [`Handle`] is a cloneable and typed way to ask questions to the
+//! elements of a children group, designed to be put into the state of
+//! an axum or actix-web application: every question is given a timeout
+//! and the number of questions waiting for a reply at once is bounded,
+//! so that a slow group makes the handlers fail fast instead of piling
+//! up requests.
+use crate::children_ref::ChildrenRef;
+use crate::demand::Demand;
+use crate::errors::AskError;
+use crate::message::Message;
+use std::fmt::{self, Debug, Formatter};
+use std::marker::PhantomData;
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The default time given to the elements to answer a question.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+/// The default number of questions waiting for a reply at once.
+const DEFAULT_CONCURRENCY: usize = 64;
+
+/// A cloneable handle asking questions of type `M` to the elements
+/// of a children group, in a round-robin fashion, and waiting for
+/// replies of type `R`.
+///
+/// Cloning a `Handle` returns a new handle sharing the same
+/// concurrency limit.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use bastion::web::Handle;
+/// # use std::time::Duration;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// #[derive(Debug)]
+/// struct GetUser(u64);
+///
+/// let users = Bastion::children(|children| {
+///     children
+///         .with_redundancy(4)
+///         .with_exec(|ctx: BastionContext| async move {
+///             loop {
+///                 msg! { ctx.recv().await?,
+///                     get: GetUser =!> {
+///                         answer!(ctx, format!("user #{}", get.0)).ok();
+///                     };
+///                     _: _ => ();
+///                 }
+///             }
+///         })
+/// }).expect("Couldn't create the children group.");
+///
+/// // Put into the state of the application...
+/// let users = Handle::<GetUser, String>::new(users)
+///     .with_timeout(Duration::from_millis(500))
+///     .with_concurrency(128);
+///
+/// # Bastion::start();
+/// // ...and then, in a handler.
+/// # run!(async move {
+/// let user = users.ask(GetUser(42)).await;
+/// # });
+/// #
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Handle<M, R> {
+    children: ChildrenRef,
+    // The index of the next element to ask.
+    next: Arc<AtomicUsize>,
+    // The number of questions which can still be asked
+    // without waiting.
+    permits: Demand,
+    concurrency: usize,
+    timeout: Duration,
+    _marker: PhantomData<fn(M) -> R>,
+}
+
+// Gives its permit back once the question was answered (or
+// timed out), even if the handler was cancelled.
+struct Permit<'a> {
+    permits: &'a Demand,
+}
+
+impl<M, R> Handle<M, R>
+where
+    M: Message,
+    R: Message,
+{
+    /// Creates a new handle asking questions to the elements of
+    /// `children`, with a timeout of `5s` and up to `64` questions
+    /// waiting for a reply at once.
+    pub fn new(children: ChildrenRef) -> Self {
+        let permits = Demand::new();
+        permits.request(DEFAULT_CONCURRENCY);
+
+        Handle {
+            children,
+            next: Arc::new(AtomicUsize::new(0)),
+            permits,
+            concurrency: DEFAULT_CONCURRENCY,
+            timeout: DEFAULT_TIMEOUT,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets how long the elements are given to answer a question.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets how many questions can wait for a reply at once, the
+    /// others waiting for one of them to be answered before being
+    /// asked.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        let concurrency = concurrency.max(1);
+        self.permits = Demand::new();
+        self.permits.request(concurrency);
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Returns the children group the questions are asked to.
+    pub fn children(&self) -> &ChildrenRef {
+        &self.children
+    }
+
+    /// Asks `msg` to the next element of the group and waits for
+    /// its reply.
+    ///
+    /// The elements which can't be asked (eg. because they were
+    /// stopped) are skipped. This method returns the reply if it
+    /// succeeded, or an [`AskError`] otherwise.
+    pub async fn ask(&self, msg: M) -> Result<R, AskError> {
+        self.permits.acquire().await;
+        let _permit = Permit {
+            permits: &self.permits,
+        };
+
+        let (msg, _) = self
+            .children
+            .ask_round_robin(&self.next, msg, Some(self.timeout))
+            .await?
+            .extract();
+        msg.downcast().map_err(|_| AskError::UnexpectedReply)
+    }
+}
+
+impl<'a> Drop for Permit<'a> {
+    fn drop(&mut self) {
+        self.permits.request(1);
+    }
+}
+
+impl<M, R> Clone for Handle<M, R> {
+    fn clone(&self) -> Self {
+        Handle {
+            children: self.children.clone(),
+            next: self.next.clone(),
+            permits: self.permits.clone(),
+            concurrency: self.concurrency,
+            timeout: self.timeout,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<M, R> Debug for Handle<M, R> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Handle")
+            .field("children", self.children.id())
+            .field("concurrency", &self.concurrency)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
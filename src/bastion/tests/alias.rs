@@ -0,0 +1,122 @@
+mod common;
+
+use bastion::prelude::*;
+use common::wait_for;
+use std::sync::{Arc, Mutex};
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+// Spawns a group whose elements record the messages they receive.
+fn spawn_group(redundancy: usize, received: &Arc<Mutex<Vec<&'static str>>>) -> ChildrenRef {
+    let received = received.clone();
+    Bastion::children(move |children| {
+        let received = received.clone();
+        children
+            .with_redundancy(redundancy)
+            .with_exec(move |ctx: BastionContext| {
+                let received = received.clone();
+                async move {
+                    loop {
+                        msg! { ctx.recv().await?,
+                            msg: &'static str => {
+                                received.lock().unwrap().push(msg);
+                            };
+                            ref msg: &'static str => {
+                                received.lock().unwrap().push(*msg);
+                            };
+                            _: _ => ();
+                        }
+                    }
+                }
+            })
+    })
+    .expect("Couldn't create the children group.")
+}
+
+fn resolves_the_elements() {
+    let received = Arc::new(Mutex::new(vec![]));
+    let children = spawn_group(1, &received);
+    let child = children.elems()[0].clone();
+
+    assert!(Bastion::alias("element", child.clone()).is_none());
+    let target = Bastion::resolve("element").expect("Couldn't resolve the alias.");
+    assert_eq!(target.as_child().map(ChildRef::id), Some(child.id()));
+    assert!(target.as_children().is_none());
+    assert!(Arc::ptr_eq(target.path(), child.path()));
+
+    target.tell_anonymously("hello").unwrap();
+    wait_for(&received, 1);
+    assert_eq!(*received.lock().unwrap(), vec!["hello"]);
+}
+
+fn broadcasts_to_the_groups() {
+    let received = Arc::new(Mutex::new(vec![]));
+    let children = spawn_group(3, &received);
+
+    Bastion::alias("group", children.clone());
+    let target = Bastion::resolve("group").expect("Couldn't resolve the alias.");
+    assert_eq!(
+        target.as_children().map(ChildrenRef::id),
+        Some(children.id())
+    );
+    assert!(target.as_child().is_none());
+
+    target.tell_anonymously("hello").unwrap();
+    wait_for(&received, 3);
+    assert_eq!(*received.lock().unwrap(), vec!["hello"; 3]);
+}
+
+fn replaces_and_removes_the_aliases() {
+    let received = Arc::new(Mutex::new(vec![]));
+    let first = spawn_group(1, &received);
+    let second = spawn_group(1, &received);
+
+    Bastion::alias("replaced", first.clone());
+    let previous = Bastion::alias("replaced", second.clone()).expect("The alias wasn't set.");
+    assert_eq!(
+        previous.as_children().map(ChildrenRef::id),
+        Some(first.id())
+    );
+    let target = Bastion::resolve("replaced").expect("Couldn't resolve the alias.");
+    assert_eq!(target.as_children().map(ChildrenRef::id), Some(second.id()));
+
+    let removed = Bastion::unalias("replaced").expect("The alias wasn't set.");
+    assert_eq!(
+        removed.as_children().map(ChildrenRef::id),
+        Some(second.id())
+    );
+    assert!(Bastion::resolve("replaced").is_none());
+    assert!(Bastion::unalias("replaced").is_none());
+}
+
+fn run() {
+    setup();
+    resolves_the_elements();
+    broadcasts_to_the_groups();
+    replaces_and_removes_the_aliases();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn alias() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn alias() {
+        super::run();
+    }
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use bastion::prelude::*;
+use common::wait_for;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// The deadlines seen by the handlers, by handler.
+type Deadlines = Arc<Mutex<Vec<(&'static str, Option<Instant>)>>>;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+// Spawns an element recording the deadline of the questions it is
+// asked, asking them again to `next` (if any) before answering them.
+fn handler(name: &'static str, deadlines: Deadlines, next: Option<ChildRef>) -> ChildRef {
+    let children = Bastion::children(move |children| {
+        let (deadlines, next) = (deadlines.clone(), next.clone());
+        children.with_exec(move |ctx: BastionContext| {
+            let (deadlines, next) = (deadlines.clone(), next.clone());
+            async move {
+                loop {
+                    msg! { ctx.recv().await?,
+                        question: &'static str =!> {
+                            deadlines.lock().unwrap().push((name, ctx.deadline()));
+                            if let Some(next) = &next {
+                                ctx.ask(&next.addr(), question).unwrap().await.unwrap();
+                            }
+                            answer!(ctx, name).unwrap();
+                        };
+                        _: _ => ();
+                    }
+                }
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    children.elems()[0].clone()
+}
+
+fn propagates_the_deadlines() {
+    let deadlines = Deadlines::default();
+    let back = handler("back", deadlines.clone(), None);
+    let front = handler("front", deadlines.clone(), Some(back));
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    Bastion::children(move |children| {
+        let front = front.clone();
+        children.with_exec(move |ctx: BastionContext| {
+            let front = front.clone();
+            async move {
+                ctx.ask_with_deadline(&front.addr(), "with deadline", deadline)
+                    .unwrap()
+                    .await
+                    .unwrap();
+                ctx.ask(&front.addr(), "without deadline")
+                    .unwrap()
+                    .await
+                    .unwrap();
+                // Waits instead of stopping, so that it isn't restarted.
+                loop {
+                    ctx.recv().await?;
+                }
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    wait_for(&deadlines, 4);
+    assert_eq!(
+        *deadlines.lock().unwrap(),
+        [
+            ("front", Some(deadline)),
+            ("back", Some(deadline)),
+            ("front", None),
+            ("back", None),
+        ]
+    );
+}
+
+fn run() {
+    setup();
+    propagates_the_deadlines();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn ask_deadline() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn ask_deadline() {
+        super::run();
+    }
+}
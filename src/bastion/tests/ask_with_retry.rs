@@ -0,0 +1,129 @@
+mod common;
+
+use bastion::prelude::*;
+use bastion::retry::RetryPolicy;
+use common::wait_for;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+struct Ping;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+fn policy(max_retries: usize) -> RetryPolicy {
+    RetryPolicy::new()
+        .with_timeout(Duration::from_millis(50))
+        .with_max_retries(max_retries)
+        .with_backoff(Duration::from_millis(10), 1.0)
+}
+
+// Spawns an element which lets the first `unanswered` questions it
+// receives time out and answers the next ones, returning it along
+// with the number of questions it received.
+fn spawn_target(unanswered: usize) -> (ChildRef, Arc<AtomicUsize>) {
+    let asked = Arc::new(AtomicUsize::new(0));
+
+    let counted = asked.clone();
+    let children = Bastion::children(move |children| {
+        let asked = counted.clone();
+        children.with_exec(move |ctx: BastionContext| {
+            let asked = asked.clone();
+            async move {
+                // The questions are kept instead of being dropped,
+                // which would make them fail right away.
+                let mut pending = vec![];
+                loop {
+                    let msg = ctx.recv().await?;
+                    if asked.fetch_add(1, Ordering::SeqCst) < unanswered {
+                        pending.push(msg);
+                    } else {
+                        answer!(msg, "pong").ok();
+                    }
+                }
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    (children.elems()[0].clone(), asked)
+}
+
+// Asks `target` with `policy` from an element, returning the outcome.
+fn ask_with_retry(target: ChildRef, policy: RetryPolicy) -> Result<(), AskError> {
+    let outcomes = Arc::new(Mutex::new(vec![]));
+
+    let recorded = outcomes.clone();
+    Bastion::children(move |children| {
+        children.with_exec(move |ctx: BastionContext| {
+            let target = target.clone();
+            let policy = policy.clone();
+            let recorded = recorded.clone();
+            async move {
+                let outcome = ctx.ask_with_retry(&target.addr(), Ping, policy).await;
+                recorded.lock().unwrap().push(outcome.map(|_| ()));
+
+                // Waits instead of stopping, so that it isn't restarted.
+                loop {
+                    ctx.recv().await?;
+                }
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    wait_for(&outcomes, 1);
+    let outcome = outcomes.lock().unwrap().pop();
+    outcome.expect("The question wasn't asked.")
+}
+
+fn succeeds_after_timeouts() {
+    let (target, asked) = spawn_target(2);
+
+    assert!(ask_with_retry(target, policy(3)).is_ok());
+    assert_eq!(asked.load(Ordering::SeqCst), 3);
+}
+
+fn gives_up_after_max_retries() {
+    let (target, asked) = spawn_target(usize::MAX);
+
+    assert!(matches!(
+        ask_with_retry(target, policy(2)),
+        Err(AskError::Timeout(_))
+    ));
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(asked.load(Ordering::SeqCst), 3);
+}
+
+fn run() {
+    setup();
+    succeeds_after_timeouts();
+    gives_up_after_max_retries();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn ask_with_retry() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn ask_with_retry() {
+        super::run();
+    }
+}
@@ -0,0 +1,38 @@
+use bastion::prelude::*;
+use std::time::Duration;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn times_out_while_running() {
+    assert!(Bastion::block_until_stopped_timeout(Duration::from_millis(10)).is_err());
+}
+
+fn waits_without_a_deadline_for_huge_timeouts() {
+    Bastion::stop();
+    assert!(Bastion::block_until_stopped_timeout(Duration::MAX).is_ok());
+}
+
+fn run() {
+    setup();
+    times_out_while_running();
+    waits_without_a_deadline_for_huge_timeouts();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn block_until_stopped_timeout() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn block_until_stopped_timeout() {
+        super::run();
+    }
+}
@@ -0,0 +1,103 @@
+use bastion::bulkhead::Bulkhead;
+use bastion::prelude::*;
+use std::thread;
+use std::time::Duration;
+
+// Makes the element answer after the given number of milliseconds.
+#[derive(Debug, Clone)]
+struct Slow(u64);
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+fn spawn_group() -> ChildrenRef {
+    Bastion::children(|children| {
+        children.with_exec(|ctx: BastionContext| async move {
+            loop {
+                msg! { ctx.recv().await?,
+                    slow: Slow =!> {
+                        thread::sleep(Duration::from_millis(slow.0));
+                        answer!(ctx, "done").ok();
+                    };
+                    _: _ => ();
+                }
+            }
+        })
+    })
+    .expect("Couldn't create the children group.")
+}
+
+fn queues_and_rejects_the_questions_over_the_bounds() {
+    let bulkhead = Bulkhead::new(spawn_group(), 1).with_max_queued(1);
+
+    let asked = {
+        let bulkhead = bulkhead.clone();
+        thread::spawn(move || run!(bulkhead.ask(Slow(300))))
+    };
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(bulkhead.in_flight(), 1);
+    assert_eq!(bulkhead.queued(), 0);
+
+    let queued = {
+        let bulkhead = bulkhead.clone();
+        thread::spawn(move || run!(bulkhead.ask(Slow(0))))
+    };
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(bulkhead.in_flight(), 1);
+    assert_eq!(bulkhead.queued(), 1);
+
+    assert!(matches!(
+        run!(bulkhead.ask(Slow(0))),
+        Err(AskError::Rejected)
+    ));
+
+    assert!(asked.join().unwrap().is_ok());
+    assert!(queued.join().unwrap().is_ok());
+    assert_eq!(bulkhead.in_flight(), 0);
+    assert_eq!(bulkhead.queued(), 0);
+}
+
+fn gives_the_permits_back_once_timed_out() {
+    let bulkhead = Bulkhead::new(spawn_group(), 1)
+        .with_max_queued(0)
+        .with_timeout(Duration::from_millis(50));
+
+    assert!(matches!(
+        run!(bulkhead.ask(Slow(200))),
+        Err(AskError::Timeout(_))
+    ));
+    assert_eq!(bulkhead.in_flight(), 0);
+
+    // Answered once the element handled the question which timed out.
+    assert!(run!(bulkhead.with_timeout(Duration::from_secs(1)).ask(Slow(0))).is_ok());
+}
+
+fn run() {
+    setup();
+    queues_and_rejects_the_questions_over_the_bounds();
+    gives_the_permits_back_once_timed_out();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn bulkhead() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn bulkhead() {
+        super::run();
+    }
+}
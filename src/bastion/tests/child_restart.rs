@@ -0,0 +1,137 @@
+// With the `scaling` feature, the groups are scaled back up to their
+// redundancy once their elements are dropped.
+#![cfg(not(feature = "scaling"))]
+mod common;
+
+use bastion::prelude::*;
+use common::wait_for;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// Makes the element return `Ok(())`.
+#[derive(Debug)]
+struct Exit;
+
+// Makes the element return `Err(())`.
+#[derive(Debug)]
+struct Fail;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+// Spawns a group of `redundancy` elements configured by `init`,
+// returning it along with the number of times its elements started.
+fn spawn_group<I>(redundancy: usize, init: I) -> (ChildrenRef, Arc<AtomicUsize>)
+where
+    I: Fn(Children) -> Children + Send + 'static,
+{
+    let started = Arc::new(AtomicUsize::new(0));
+
+    let counted = started.clone();
+    let children = Bastion::children(move |children| {
+        let started = counted.clone();
+        init(children)
+            .with_redundancy(redundancy)
+            .with_exec(move |ctx: BastionContext| {
+                let started = started.clone();
+                async move {
+                    started.fetch_add(1, Ordering::SeqCst);
+                    loop {
+                        msg! { ctx.recv().await?,
+                            _exit: Exit => return Ok(());
+                            _fail: Fail => return Err(());
+                            _: _ => ();
+                        }
+                    }
+                }
+            })
+    })
+    .expect("Couldn't create the children group.");
+
+    wait_for(&started, redundancy);
+    (children, started)
+}
+
+// Waits for the restarts which could happen, and returns the number
+// of times the elements started.
+fn settled(started: &AtomicUsize) -> usize {
+    thread::sleep(Duration::from_millis(200));
+    started.load(Ordering::SeqCst)
+}
+
+fn transient_by_default() {
+    let (children, started) = spawn_group(1, |children| children);
+    children.elems()[0].tell_anonymously(Exit).unwrap();
+    assert_eq!(settled(&started), 1);
+
+    let (children, started) = spawn_group(1, |children| {
+        children.with_child_restart(ChildRestart::Transient)
+    });
+    children.elems()[0].tell_anonymously(Fail).unwrap();
+    wait_for(&started, 2);
+    assert_eq!(settled(&started), 2);
+}
+
+fn permanent_restarts_on_exit() {
+    let (children, started) = spawn_group(1, |children| {
+        children.with_child_restart(ChildRestart::Permanent)
+    });
+    children.elems()[0].tell_anonymously(Exit).unwrap();
+    wait_for(&started, 2);
+    assert_eq!(settled(&started), 2);
+}
+
+fn temporary_never_restarts() {
+    let (children, started) = spawn_group(1, |children| {
+        children.with_child_restart(ChildRestart::Temporary)
+    });
+    children.elems()[0].tell_anonymously(Fail).unwrap();
+    assert_eq!(settled(&started), 1);
+}
+
+fn element_restart_overrides_the_group() {
+    let (children, started) = spawn_group(2, |children| {
+        children
+            .with_child_restart(ChildRestart::Permanent)
+            .with_element_restart(0, ChildRestart::Temporary)
+    });
+    for element in children.elems() {
+        element.tell_anonymously(Exit).unwrap();
+    }
+    wait_for(&started, 3);
+    assert_eq!(settled(&started), 3);
+}
+
+fn run() {
+    setup();
+    transient_by_default();
+    permanent_restarts_on_exit();
+    temporary_never_restarts();
+    element_restart_overrides_the_group();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn child_restart() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn child_restart() {
+        super::run();
+    }
+}
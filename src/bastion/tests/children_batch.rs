@@ -0,0 +1,103 @@
+mod common;
+
+use bastion::prelude::*;
+use common::wait_for;
+use std::sync::{Arc, Mutex};
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+// Returns the specs of `count` groups whose elements record the index
+// of their group when they start, the last group being given its own
+// redundancy.
+fn specs(
+    count: usize,
+    started: Arc<Mutex<Vec<usize>>>,
+) -> impl Iterator<Item = impl FnOnce(Children) -> Children> {
+    (0..count).map(move |index| {
+        let started = started.clone();
+        move |children: Children| {
+            let children = if index == count - 1 {
+                children.with_redundancy(1)
+            } else {
+                children
+            };
+
+            children.with_exec(move |ctx: BastionContext| {
+                let started = started.clone();
+                async move {
+                    started.lock().unwrap().push(index);
+                    loop {
+                        ctx.recv().await?;
+                    }
+                }
+            })
+        }
+    })
+}
+
+// Checks that the groups were created in order, configured by the
+// defaults then by their own spec.
+fn check(groups: Vec<ChildrenRef>, started: Arc<Mutex<Vec<usize>>>) {
+    let redundancies: Vec<_> = groups.iter().map(|group| group.elems().len()).collect();
+    assert_eq!(redundancies, [2, 2, 1]);
+
+    wait_for(&started, 5);
+    let mut started = started.lock().unwrap().clone();
+    started.sort_unstable();
+    assert_eq!(started, [0, 0, 1, 1, 2]);
+}
+
+fn creates_the_groups_of_the_system() {
+    let started = Arc::new(Mutex::new(vec![]));
+    let groups = Bastion::children_batch(
+        |children| children.with_redundancy(2),
+        specs(3, started.clone()),
+    )
+    .expect("Couldn't create the children groups.");
+
+    check(groups, started);
+}
+
+fn creates_the_groups_of_a_supervisor() {
+    let started = Arc::new(Mutex::new(vec![]));
+    let supervisor = Bastion::supervisor(|sp| sp).expect("Couldn't create the supervisor.");
+    let groups = supervisor
+        .children_batch(
+            |children| children.with_redundancy(2),
+            specs(3, started.clone()),
+        )
+        .expect("Couldn't create the children groups.");
+
+    check(groups, started);
+}
+
+fn run() {
+    setup();
+    creates_the_groups_of_the_system();
+    creates_the_groups_of_a_supervisor();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn children_batch() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn children_batch() {
+        super::run();
+    }
+}
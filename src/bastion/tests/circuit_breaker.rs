@@ -0,0 +1,171 @@
+use bastion::circuit_breaker::{CircuitBreaker, CircuitState};
+use bastion::prelude::*;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// Makes the element answer after the given number of milliseconds,
+// or crash without answering if `answer` is `false`.
+#[derive(Debug, Clone)]
+struct Slow {
+    millis: u64,
+    answer: bool,
+}
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+fn slow(millis: u64, answer: bool) -> Slow {
+    Slow { millis, answer }
+}
+
+fn spawn_group() -> ChildrenRef {
+    Bastion::children(|children| {
+        children
+            .with_redundancy(3)
+            .with_exec(|ctx: BastionContext| async move {
+                loop {
+                    msg! { ctx.recv().await?,
+                        slow: Slow =!> {
+                            thread::sleep(Duration::from_millis(slow.millis));
+                            if !slow.answer {
+                                return Err(());
+                            }
+                            answer!(ctx, "done").ok();
+                        };
+                        _: _ => ();
+                    }
+                }
+            })
+    })
+    .expect("Couldn't create the children group.")
+}
+
+// Returns a circuit breaker opening after a single failure, and
+// the states it went through.
+fn breaker(children: ChildrenRef) -> (CircuitBreaker, Arc<Mutex<Vec<CircuitState>>>) {
+    let states = Arc::new(Mutex::new(vec![]));
+    let changed = states.clone();
+    let breaker = CircuitBreaker::for_group(children)
+        .with_failure_threshold(1)
+        .with_reset_timeout(Duration::from_millis(100))
+        .with_timeout(Duration::from_secs(2))
+        .with_on_state_change(move |_, to| changed.lock().unwrap().push(to));
+
+    (breaker, states)
+}
+
+fn opens_and_closes_again() {
+    let (breaker, states) = breaker(spawn_group());
+
+    assert!(run!(breaker.ask(slow(0, true))).is_ok());
+    assert_eq!(breaker.state(), CircuitState::Closed);
+
+    assert!(matches!(
+        run!(breaker.ask(slow(0, false))),
+        Err(AskError::Unavailable)
+    ));
+    assert_eq!(breaker.state(), CircuitState::Open);
+    assert!(matches!(
+        run!(breaker.ask(slow(0, true))),
+        Err(AskError::Open)
+    ));
+
+    thread::sleep(Duration::from_millis(150));
+    assert!(run!(breaker.ask(slow(0, true))).is_ok());
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    assert_eq!(
+        *states.lock().unwrap(),
+        vec![
+            CircuitState::Open,
+            CircuitState::HalfOpen,
+            CircuitState::Closed
+        ]
+    );
+}
+
+// Asks a question which is answered (or times out) once the circuit
+// is half-open and being probed, and checks that only the probe
+// closes the circuit.
+fn only_the_probe_leaves_the_half_open_state(in_flight_answer: bool) {
+    let (breaker, states) = breaker(spawn_group());
+
+    // Asked to the first element while the circuit is closed.
+    let in_flight = {
+        let breaker = breaker.clone();
+        thread::spawn(move || {
+            if in_flight_answer {
+                run!(breaker.ask(slow(400, true)))
+            } else {
+                let breaker = breaker.with_timeout(Duration::from_millis(400));
+                run!(breaker.ask(slow(1000, true)))
+            }
+        })
+    };
+    thread::sleep(Duration::from_millis(20));
+
+    // Asked to the second element, opening the circuit.
+    assert!(matches!(
+        run!(breaker.ask(slow(0, false))),
+        Err(AskError::Unavailable)
+    ));
+    assert_eq!(breaker.state(), CircuitState::Open);
+    thread::sleep(Duration::from_millis(150));
+
+    // Asked to the third element.
+    let probe = {
+        let breaker = breaker.clone();
+        thread::spawn(move || run!(breaker.ask(slow(300, true))))
+    };
+    thread::sleep(Duration::from_millis(20));
+    assert_eq!(breaker.state(), CircuitState::HalfOpen);
+    assert!(matches!(
+        run!(breaker.ask(slow(0, true))),
+        Err(AskError::Open)
+    ));
+
+    assert_eq!(in_flight.join().unwrap().is_ok(), in_flight_answer);
+    assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+    assert!(probe.join().unwrap().is_ok());
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    assert_eq!(
+        *states.lock().unwrap(),
+        vec![
+            CircuitState::Open,
+            CircuitState::HalfOpen,
+            CircuitState::Closed
+        ]
+    );
+}
+
+fn run() {
+    setup();
+    opens_and_closes_again();
+    only_the_probe_leaves_the_half_open_state(true);
+    only_the_probe_leaves_the_half_open_state(false);
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn circuit_breaker() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn circuit_breaker() {
+        super::run();
+    }
+}
@@ -0,0 +1,36 @@
+// Helpers shared by the integration tests.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// What the tests wait for to reach a count.
+pub trait Counted {
+    fn count(&self) -> usize;
+}
+
+impl<T> Counted for Mutex<Vec<T>> {
+    fn count(&self) -> usize {
+        self.lock().unwrap().len()
+    }
+}
+
+impl<C: Counted> Counted for Arc<C> {
+    fn count(&self) -> usize {
+        (**self).count()
+    }
+}
+
+impl Counted for AtomicUsize {
+    fn count(&self) -> usize {
+        self.load(Ordering::SeqCst)
+    }
+}
+
+// Waits until `counted` reaches `expected`, for up to 5s.
+pub fn wait_for<C: Counted>(counted: &C, expected: usize) {
+    let started = Instant::now();
+    while counted.count() < expected && started.elapsed() < Duration::from_secs(5) {
+        thread::sleep(Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,156 @@
+#![cfg(feature = "ffi")]
+mod common;
+
+use bastion::ffi::*;
+use common::wait_for;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+// Counts the messages it receives and answers the questions with
+// their bytes reversed, or doesn't answer them if they start with
+// `sleep`.
+extern "C" fn reverse(
+    user_data: *mut c_void,
+    data: *const u8,
+    len: usize,
+    reply: *mut BastionReply,
+) -> c_int {
+    let received = unsafe { &*(user_data as *const AtomicUsize) };
+    received.fetch_add(1, Ordering::SeqCst);
+
+    let data = unsafe { slice::from_raw_parts(data, len) };
+    if reply.is_null() {
+        return BASTION_OK;
+    }
+    if data.starts_with(b"sleep") {
+        thread::sleep(Duration::from_millis(200));
+        return BASTION_OK;
+    }
+
+    let reversed: Vec<u8> = data.iter().rev().copied().collect();
+    unsafe { bastion_reply(reply, reversed.as_ptr(), reversed.len()) }
+}
+
+fn spawn_group(redundancy: usize, received: &'static AtomicUsize) -> *mut BastionGroup {
+    let group = unsafe {
+        bastion_spawn_group(
+            redundancy,
+            reverse,
+            received as *const AtomicUsize as *mut c_void,
+        )
+    };
+    assert!(!group.is_null());
+
+    group
+}
+
+fn tell() {
+    static RECEIVED: AtomicUsize = AtomicUsize::new(0);
+    let group = spawn_group(2, &RECEIVED);
+
+    for _ in 0..4 {
+        let status = unsafe { bastion_tell(group, b"ping".as_ptr(), 4) };
+        assert_eq!(status, BASTION_OK);
+    }
+    // Empty payloads can be sent too.
+    assert_eq!(unsafe { bastion_tell(group, ptr::null(), 0) }, BASTION_OK);
+
+    wait_for(&RECEIVED, 5);
+    assert_eq!(RECEIVED.load(Ordering::SeqCst), 5);
+
+    assert_eq!(unsafe { bastion_group_stop(group) }, BASTION_OK);
+    unsafe { bastion_group_free(group) };
+}
+
+fn ask() {
+    static RECEIVED: AtomicUsize = AtomicUsize::new(0);
+    let group = spawn_group(1, &RECEIVED);
+
+    let mut out_data = ptr::null_mut();
+    let mut out_len = 0;
+    let status = unsafe {
+        bastion_ask(
+            group,
+            b"marco".as_ptr(),
+            5,
+            5_000,
+            &mut out_data,
+            &mut out_len,
+        )
+    };
+    assert_eq!(status, BASTION_OK);
+    assert_eq!(
+        unsafe { slice::from_raw_parts(out_data, out_len) },
+        b"ocram"
+    );
+    unsafe { bastion_bytes_free(out_data, out_len) };
+
+    // Nothing is written if the question isn't answered on time.
+    let mut out_data = ptr::null_mut();
+    let mut out_len = 0;
+    let status =
+        unsafe { bastion_ask(group, b"sleep".as_ptr(), 5, 10, &mut out_data, &mut out_len) };
+    assert_eq!(status, BASTION_TIMEOUT);
+    assert!(out_data.is_null());
+
+    assert_eq!(unsafe { bastion_group_stop(group) }, BASTION_OK);
+    unsafe { bastion_group_free(group) };
+}
+
+fn null_pointers() {
+    let mut out_data = ptr::null_mut();
+    let mut out_len = 0;
+    unsafe {
+        assert_eq!(bastion_tell(ptr::null(), ptr::null(), 0), BASTION_ERROR);
+        assert_eq!(
+            bastion_ask(ptr::null(), ptr::null(), 0, 10, &mut out_data, &mut out_len),
+            BASTION_ERROR
+        );
+        assert_eq!(
+            bastion_reply(ptr::null_mut(), ptr::null(), 0),
+            BASTION_ERROR
+        );
+        assert_eq!(bastion_group_stop(ptr::null()), BASTION_ERROR);
+        bastion_group_free(ptr::null_mut());
+        bastion_bytes_free(ptr::null_mut(), 0);
+    }
+
+    static RECEIVED: AtomicUsize = AtomicUsize::new(0);
+    let group = spawn_group(1, &RECEIVED);
+    let status = unsafe { bastion_ask(group, ptr::null(), 0, 10, ptr::null_mut(), &mut out_len) };
+    assert_eq!(status, BASTION_ERROR);
+
+    unsafe {
+        bastion_group_stop(group);
+        bastion_group_free(group);
+    }
+}
+
+fn run() {
+    assert_eq!(bastion_init(), BASTION_OK);
+    assert_eq!(bastion_start(), BASTION_OK);
+    tell();
+    ask();
+    null_pointers();
+    assert_eq!(bastion_stop(), BASTION_OK);
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn ffi() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn ffi() {
+        super::run();
+    }
+}
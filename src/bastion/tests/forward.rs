@@ -0,0 +1,105 @@
+mod common;
+
+use bastion::prelude::*;
+use common::wait_for;
+use std::sync::{Arc, Mutex};
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+fn keeps_the_original_signature() {
+    // The ids of the askers as seen by the backend.
+    let seen = Arc::new(Mutex::new(vec![]));
+    // The id of the asker and the answer it got.
+    let asked = Arc::new(Mutex::new(vec![]));
+
+    let recorded = seen.clone();
+    let backend = Bastion::children(move |children| {
+        let seen = recorded.clone();
+        children.with_exec(move |ctx: BastionContext| {
+            let seen = seen.clone();
+            async move {
+                loop {
+                    let msg: SignedMessage = ctx.recv().await?;
+                    seen.lock()
+                        .unwrap()
+                        .push(msg.signature().path().id().clone());
+                    answer!(msg, "pong").unwrap();
+                }
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    let backend = backend.elems()[0].clone();
+    let proxy = Bastion::children(move |children| {
+        let backend = backend.clone();
+        children.with_exec(move |ctx: BastionContext| {
+            let backend = backend.clone();
+            async move {
+                loop {
+                    let msg: SignedMessage = ctx.recv().await?;
+                    ctx.forward(msg, &backend.addr()).unwrap();
+                }
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    let proxy = proxy.elems()[0].clone();
+    let answers = asked.clone();
+    Bastion::children(move |children| {
+        let (proxy, answers) = (proxy.clone(), answers.clone());
+        children.with_exec(move |ctx: BastionContext| {
+            let (proxy, answers) = (proxy.clone(), answers.clone());
+            async move {
+                let answer = ctx.ask(&proxy.addr(), "ping").unwrap().await?;
+                msg! { answer,
+                    answer: &'static str => {
+                        answers.lock().unwrap().push((ctx.current().id().clone(), answer));
+                    };
+                    _: _ => ();
+                }
+                // Waits instead of stopping, so that it isn't restarted.
+                loop {
+                    ctx.recv().await?;
+                }
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    wait_for(&asked, 1);
+    let (asker, answer) = asked.lock().unwrap()[0].clone();
+    assert_eq!(answer, "pong");
+    assert_eq!(*seen.lock().unwrap(), [asker]);
+}
+
+fn run() {
+    setup();
+    keeps_the_original_signature();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn forward() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn forward() {
+        super::run();
+    }
+}
@@ -0,0 +1,55 @@
+use bastion::prelude::*;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+fn takes_the_state_over_once() {
+    let supervisor = Bastion::supervisor(|sp| sp).expect("Couldn't create the supervisor.");
+    assert_eq!(supervisor.take_over::<u64>("leader"), None);
+
+    supervisor.hand_over("leader", 1u64);
+    supervisor.hand_over("leader", 2u64);
+    // A state of another type isn't taken over and is kept.
+    assert_eq!(supervisor.take_over::<String>("leader"), None);
+    assert_eq!(supervisor.clone().take_over::<u64>("leader"), Some(2));
+    assert_eq!(supervisor.take_over::<u64>("leader"), None);
+}
+
+fn keeps_the_states_by_supervisor() {
+    let first = Bastion::supervisor(|sp| sp).expect("Couldn't create the supervisor.");
+    let second = Bastion::supervisor(|sp| sp).expect("Couldn't create the supervisor.");
+
+    first.hand_over("leader", 1u64);
+    assert_eq!(second.take_over::<u64>("leader"), None);
+    assert_eq!(first.take_over::<u64>("leader"), Some(1));
+}
+
+fn run() {
+    setup();
+    takes_the_state_over_once();
+    keeps_the_states_by_supervisor();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn handovers() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn handovers() {
+        super::run();
+    }
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use bastion::prelude::*;
+use common::wait_for;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// Broadcasted to the group to launch its elements.
+#[derive(Debug, Clone)]
+struct WakeUp;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+fn launches_on_the_first_message() {
+    let started = Arc::new(AtomicUsize::new(0));
+    let woken = Arc::new(AtomicUsize::new(0));
+
+    let (counted, received) = (started.clone(), woken.clone());
+    let children = Bastion::children(move |children| {
+        let (started, woken) = (counted.clone(), received.clone());
+        children
+            .with_lazy_start()
+            .with_redundancy(2)
+            .with_exec(move |ctx: BastionContext| {
+                let (started, woken) = (started.clone(), woken.clone());
+                async move {
+                    started.fetch_add(1, Ordering::SeqCst);
+                    loop {
+                        msg! { ctx.recv().await?,
+                            ref _wake_up: WakeUp => {
+                                woken.fetch_add(1, Ordering::SeqCst);
+                            };
+                            _: _ => ();
+                        }
+                    }
+                }
+            })
+    })
+    .expect("Couldn't create the children group.");
+
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(started.load(Ordering::SeqCst), 0);
+    assert!(children.elems().is_empty());
+
+    // The message is buffered until the elements are launched.
+    children.broadcast(WakeUp).unwrap();
+    wait_for(&woken, 2);
+    assert_eq!(started.load(Ordering::SeqCst), 2);
+}
+
+fn run() {
+    setup();
+    launches_on_the_first_message();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn lazy_start() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn lazy_start() {
+        super::run();
+    }
+}
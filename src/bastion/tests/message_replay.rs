@@ -0,0 +1,107 @@
+// With the `scaling` feature, the faulted elements can be dropped by
+// the resizer before being restarted.
+#![cfg(not(feature = "scaling"))]
+mod common;
+
+use bastion::prelude::*;
+use common::wait_for;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+struct Put(u64);
+
+#[derive(Debug, Clone)]
+struct Poison;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+// Spawns a group replaying the last `last` messages of type `M`,
+// whose element records every message it receives and faults when
+// it receives a `Poison`.
+fn spawn_group<M: Message + Clone>(received: Arc<Mutex<Vec<String>>>, last: usize) -> ChildrenRef {
+    Bastion::children(move |children| {
+        children
+            .with_message_replay::<M>(last)
+            .with_exec(move |ctx: BastionContext| {
+                let received = received.clone();
+                async move {
+                    loop {
+                        msg! { ctx.recv().await?,
+                            put: Put => {
+                                received.lock().unwrap().push(format!("put {}", put.0));
+                            };
+                            _poison: Poison => {
+                                received.lock().unwrap().push("poison".to_string());
+                                return Err(());
+                            };
+                            _: _ => ();
+                        }
+                    }
+                }
+            })
+    })
+    .expect("Couldn't create the children group.")
+}
+
+fn replays_the_processed_messages() {
+    let received = Arc::new(Mutex::new(vec![]));
+    let children = spawn_group::<Put>(received.clone(), 2);
+    let element = &children.elems()[0];
+    element.tell_anonymously(Put(1)).unwrap();
+    element.tell_anonymously(Put(2)).unwrap();
+    element.tell_anonymously(Put(3)).unwrap();
+    element.tell_anonymously(Poison).unwrap();
+    element.tell_anonymously(Put(4)).unwrap();
+
+    wait_for(&received, 7);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec!["put 1", "put 2", "put 3", "poison", "put 2", "put 3", "put 4"]
+    );
+}
+
+fn never_replays_the_message_that_faulted() {
+    let received = Arc::new(Mutex::new(vec![]));
+    let children = spawn_group::<Poison>(received.clone(), 10);
+    let element = &children.elems()[0];
+    element.tell_anonymously(Poison).unwrap();
+    element.tell_anonymously(Put(1)).unwrap();
+
+    wait_for(&received, 2);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(*received.lock().unwrap(), vec!["poison", "put 1"]);
+}
+
+fn run() {
+    setup();
+    replays_the_processed_messages();
+    never_replays_the_message_that_faulted();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn message_replay() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn message_replay() {
+        super::run();
+    }
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use bastion::prelude::*;
+use common::wait_for;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// Makes the handler of the element panic.
+#[derive(Debug)]
+struct Panic;
+
+// Recorded by the handler of the element.
+#[derive(Debug)]
+struct Record;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+// Spawns an element handling its messages with `BastionContext::handle`,
+// returning it along with the number of times it started and the
+// outcomes of its handlers.
+fn spawn_element(isolate: bool) -> (ChildRef, Arc<AtomicUsize>, Arc<Mutex<Vec<bool>>>) {
+    let started = Arc::new(AtomicUsize::new(0));
+    let handled = Arc::new(Mutex::new(vec![]));
+
+    let (counted, recorded) = (started.clone(), handled.clone());
+    let children = Bastion::children(move |children| {
+        let children = if isolate {
+            children.with_panic_isolation()
+        } else {
+            children
+        };
+
+        children.with_exec(move |ctx: BastionContext| {
+            let (started, handled) = (counted.clone(), recorded.clone());
+            async move {
+                started.fetch_add(1, Ordering::SeqCst);
+                loop {
+                    let msg = ctx.recv().await?;
+                    let outcome = ctx
+                        .handle(msg, |msg| async move {
+                            msg! { msg,
+                                _panic: Panic => panic!("Panic received.");
+                                _record: Record => ();
+                                _: _ => ();
+                            }
+                        })
+                        .await;
+                    handled.lock().unwrap().push(outcome.is_ok());
+                }
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    wait_for(&started, 1);
+    (children.elems()[0].clone(), started, handled)
+}
+
+fn isolates_the_panics() {
+    let (element, started, handled) = spawn_element(true);
+    element.tell_anonymously(Panic).unwrap();
+    element.tell_anonymously(Record).unwrap();
+
+    wait_for(&handled, 2);
+    assert_eq!(*handled.lock().unwrap(), vec![false, true]);
+    assert_eq!(started.load(Ordering::SeqCst), 1);
+}
+
+fn faults_without_isolation() {
+    let (element, started, handled) = spawn_element(false);
+    element.tell_anonymously(Panic).unwrap();
+
+    wait_for(&started, 2);
+    thread::sleep(Duration::from_millis(100));
+    assert!(handled.lock().unwrap().is_empty());
+    assert_eq!(started.load(Ordering::SeqCst), 2);
+}
+
+fn run() {
+    setup();
+    isolates_the_panics();
+    faults_without_isolation();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn panic_isolation() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn panic_isolation() {
+        super::run();
+    }
+}
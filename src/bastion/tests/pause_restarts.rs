@@ -0,0 +1,78 @@
+mod common;
+
+use bastion::prelude::*;
+use common::wait_for;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// Makes the element fault.
+#[derive(Debug)]
+struct Fail;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+fn restarts_once_resumed() {
+    let started = Arc::new(AtomicUsize::new(0));
+
+    let supervisor = Bastion::supervisor(|sp| sp).expect("Couldn't create the supervisor.");
+    let counted = started.clone();
+    let children = supervisor
+        .children(move |children| {
+            children.with_exec(move |ctx: BastionContext| {
+                let started = counted.clone();
+                async move {
+                    started.fetch_add(1, Ordering::SeqCst);
+                    msg! { ctx.recv().await?,
+                        _fail: Fail => return Err(());
+                        _: _ => ();
+                    }
+                    Ok(())
+                }
+            })
+        })
+        .expect("Couldn't create the children group.");
+    wait_for(&started, 1);
+
+    supervisor.pause_restarts().unwrap();
+    // Lets the supervisor pause its restarts before the element faults.
+    thread::sleep(Duration::from_millis(50));
+    children.elems()[0].tell_anonymously(Fail).unwrap();
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(started.load(Ordering::SeqCst), 1);
+
+    supervisor.resume_restarts().unwrap();
+    wait_for(&started, 2);
+    assert_eq!(started.load(Ordering::SeqCst), 2);
+}
+
+fn run() {
+    setup();
+    restarts_once_resumed();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn pause_restarts() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn pause_restarts() {
+        super::run();
+    }
+}
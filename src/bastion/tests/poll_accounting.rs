@@ -0,0 +1,143 @@
+use bastion::prelude::*;
+use bastion::tree::{GroupStats, SupervisorStats};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug)]
+struct Work;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+fn find_group(supervisors: &[SupervisorStats], id: &BastionId) -> Option<GroupStats> {
+    supervisors.iter().find_map(|supervisor| {
+        supervisor
+            .groups()
+            .iter()
+            .find(|group| group.id() == id)
+            .cloned()
+            .or_else(|| find_group(supervisor.supervisors(), id))
+    })
+}
+
+// Spawns a group whose elements wait for messages.
+fn spawn_group() -> ChildrenRef {
+    Bastion::children(|children| {
+        let children = children.with_redundancy(2);
+        #[cfg(feature = "metrics")]
+        let children = children.with_stats_collector(Duration::from_millis(20));
+
+        children.with_exec(|ctx: BastionContext| async move {
+            loop {
+                ctx.recv().await?;
+            }
+        })
+    })
+    .expect("Couldn't create the children group.")
+}
+
+fn poll_work(children: &ChildrenRef) {
+    for elem in children.elems() {
+        for _ in 0..10 {
+            elem.tell_anonymously(Work).unwrap();
+        }
+    }
+
+    thread::sleep(Duration::from_millis(200));
+}
+
+fn the_tree_holds_the_poll_accounting() {
+    let children = spawn_group();
+    poll_work(&children);
+
+    let group = find_group(&Bastion::tree(), children.id()).expect("The group isn't in the tree.");
+    let poll_count: u64 = children.elems().iter().map(ChildRef::poll_count).sum();
+    assert!(poll_count > 0);
+    assert!(group.poll_count() >= poll_count);
+    assert!(group.poll_time() > Duration::from_nanos(0));
+}
+
+#[cfg(feature = "metrics")]
+fn the_metrics_hold_the_poll_accounting() {
+    use bastion::metrics::{self, Value};
+
+    let children = spawn_group();
+    poll_work(&children);
+
+    let group = children.id().to_string();
+    for elem in children.elems() {
+        let child = elem.id().to_string();
+        let value = |name: &str| {
+            metrics::registry()
+                .snapshot()
+                .into_iter()
+                .find(|sample| {
+                    sample.name() == name
+                        && sample
+                            .labels()
+                            .iter()
+                            .any(|(label, value)| label == "children" && *value == group)
+                        && sample
+                            .labels()
+                            .iter()
+                            .any(|(label, value)| label == "child" && *value == child)
+                })
+                .map(|sample| sample.value())
+        };
+
+        match value("bastion_polls_total") {
+            Some(Value::Counter(polls)) => assert!(polls > 0),
+            value => panic!("Unexpected poll count: {:?}", value),
+        }
+        assert!(matches!(
+            value("bastion_poll_time_microseconds_total"),
+            Some(Value::Counter(_))
+        ));
+    }
+}
+
+#[cfg(feature = "inspector")]
+fn the_inspection_holds_the_poll_accounting() {
+    let children = spawn_group();
+    poll_work(&children);
+
+    for elem in children.elems() {
+        let inspection = elem.inspect();
+        assert!(inspection.mailbox().is_empty());
+        assert!(inspection.poll_count() > 0);
+        assert!(inspection.poll_count() <= elem.poll_count());
+    }
+}
+
+fn run() {
+    setup();
+    the_tree_holds_the_poll_accounting();
+    #[cfg(feature = "metrics")]
+    the_metrics_hold_the_poll_accounting();
+    #[cfg(feature = "inspector")]
+    the_inspection_holds_the_poll_accounting();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn poll_accounting() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn poll_accounting() {
+        super::run();
+    }
+}
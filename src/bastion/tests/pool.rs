@@ -0,0 +1,73 @@
+use bastion::pool::Pool;
+use bastion::prelude::*;
+use std::thread;
+use std::time::Duration;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+// Spawns a pool doubling the requests, whose handler panics when
+// asked to double `0`.
+fn spawn_pool() -> Pool<u64, u64> {
+    Bastion::pool(2, |req: u64| async move {
+        if req == 0 {
+            panic!("Can't double 0.");
+        }
+
+        req * 2
+    })
+    .expect("Couldn't create the pool.")
+}
+
+fn answers_the_requests() {
+    let pool = spawn_pool();
+    assert_eq!(pool.children().elems().len(), 2);
+
+    for req in 1..=10 {
+        assert_eq!(run!(pool.submit(req)).ok(), Some(req * 2));
+    }
+}
+
+fn restarts_the_workers_which_panicked() {
+    let pool = spawn_pool();
+
+    assert!(matches!(run!(pool.submit(0)), Err(AskError::Unavailable)));
+
+    // The other worker keeps answering while the one which panicked
+    // is restarted.
+    assert_eq!(run!(pool.submit(1)).ok(), Some(2));
+    thread::sleep(Duration::from_millis(300));
+    for req in 1..=4 {
+        assert_eq!(run!(pool.submit(req)).ok(), Some(req * 2));
+    }
+}
+
+fn run() {
+    setup();
+    answers_the_requests();
+    restarts_the_workers_which_panicked();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn pool() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn pool() {
+        super::run();
+    }
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use bastion::prelude::*;
+use common::wait_for;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// Makes the element signal its readiness.
+#[derive(Debug)]
+struct Ready;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+fn waits_for_every_element() {
+    let children = Bastion::children(|children| {
+        children
+            .with_readiness()
+            .with_redundancy(2)
+            .with_exec(|ctx: BastionContext| async move {
+                loop {
+                    msg! { ctx.recv().await?,
+                        _ready: Ready => ctx.notify_started();
+                        _: _ => ();
+                    }
+                }
+            })
+    })
+    .expect("Couldn't create the children group.");
+
+    let started = Arc::new(AtomicUsize::new(0));
+    let (waited, signaled) = (children.clone(), started.clone());
+    thread::spawn(move || {
+        run!(waited.wait_started());
+        signaled.fetch_add(1, Ordering::SeqCst);
+    });
+
+    let elems = children.elems();
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(started.load(Ordering::SeqCst), 0);
+
+    elems[0].tell_anonymously(Ready).unwrap();
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(started.load(Ordering::SeqCst), 0);
+
+    elems[1].tell_anonymously(Ready).unwrap();
+    wait_for(&started, 1);
+}
+
+fn run() {
+    setup();
+    waits_for_every_element();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn readiness() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn readiness() {
+        super::run();
+    }
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use bastion::prelude::*;
+use bastion::retry::{Retriable, RetryPolicy};
+use common::wait_for;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+struct Put;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+fn policy() -> RetryPolicy {
+    RetryPolicy::new()
+        .with_max_retries(3)
+        .with_backoff(Duration::from_millis(10), 1.0)
+}
+
+// Spawns a group whose handler fails to handle the `Put`s until
+// they were delivered `handled_at` times, and whose elements fault
+// right after having failed to handle one if `fault` is set.
+fn spawn_group(deliveries: Arc<AtomicUsize>, handled_at: usize, fault: bool) -> ChildrenRef {
+    Bastion::children(move |children| {
+        children
+            .with_redelivery::<Put>(policy())
+            .with_exec(move |ctx: BastionContext| {
+                let deliveries = deliveries.clone();
+                async move {
+                    loop {
+                        let msg = ctx.recv().await?;
+                        let deliveries = deliveries.clone();
+                        let handled = ctx
+                            .handle_retriable(msg, |msg| async move {
+                                msg! { msg,
+                                    _put: Put => {
+                                        let delivered = deliveries.fetch_add(1, Ordering::SeqCst) + 1;
+                                        if delivered < handled_at {
+                                            return Err(Retriable::new("store unavailable"));
+                                        }
+                                    };
+                                    _: _ => ();
+                                }
+                                Ok(())
+                            })
+                            .await?;
+
+                        if fault && handled.is_err() {
+                            return Err(());
+                        }
+                    }
+                }
+            })
+    })
+    .expect("Couldn't create the children group.")
+}
+
+fn redelivers_until_handled() {
+    let deliveries = Arc::new(AtomicUsize::new(0));
+    let children = spawn_group(deliveries.clone(), 3, false);
+    children.elems()[0].tell_anonymously(Put).unwrap();
+
+    wait_for(&deliveries, 3);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(deliveries.load(Ordering::SeqCst), 3);
+}
+
+fn gives_up_after_the_max_retries() {
+    let deliveries = Arc::new(AtomicUsize::new(0));
+    let children = spawn_group(deliveries.clone(), usize::MAX, false);
+    children.elems()[0].tell_anonymously(Put).unwrap();
+
+    wait_for(&deliveries, 4);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(deliveries.load(Ordering::SeqCst), 4);
+}
+
+fn redelivers_to_the_restarted_elements() {
+    let deliveries = Arc::new(AtomicUsize::new(0));
+    let children = spawn_group(deliveries.clone(), 3, true);
+    children.elems()[0].tell_anonymously(Put).unwrap();
+
+    wait_for(&deliveries, 3);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(deliveries.load(Ordering::SeqCst), 3);
+}
+
+fn run() {
+    setup();
+    redelivers_until_handled();
+    gives_up_after_the_max_retries();
+    redelivers_to_the_restarted_elements();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn redelivery() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn redelivery() {
+        super::run();
+    }
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use bastion::prelude::*;
+use common::wait_for;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug)]
+struct Fail;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+// Starts a supervisor restarting its groups with the `RestForOne`
+// strategy, and three groups whose element records its index in
+// `started` every time it starts, and faults when it receives `Fail`.
+fn spawn_groups(started: &Arc<Mutex<Vec<usize>>>) -> Vec<ChildrenRef> {
+    let supervisor = Bastion::supervisor(|sp| sp.with_strategy(SupervisionStrategy::RestForOne))
+        .expect("Couldn't create the supervisor.");
+
+    (0..3)
+        .map(|index| {
+            let started = started.clone();
+            supervisor
+                .children(move |children| {
+                    children.with_exec(move |ctx: BastionContext| {
+                        let started = started.clone();
+                        async move {
+                            started.lock().unwrap().push(index);
+                            loop {
+                                msg! { ctx.recv().await?,
+                                    _fail: Fail => return Err(());
+                                    _: _ => ();
+                                }
+                            }
+                        }
+                    })
+                })
+                .expect("Couldn't create the children group.")
+        })
+        .collect()
+}
+
+// Makes the group at `failed` fault and returns the groups which
+// were restarted.
+fn restarted_after_failure_of(failed: usize) -> Vec<usize> {
+    let started = Arc::new(Mutex::new(vec![]));
+    let groups = spawn_groups(&started);
+    wait_for(&started, 3);
+    started.lock().unwrap().clear();
+
+    groups[failed].elems()[0].tell_anonymously(Fail).unwrap();
+    wait_for(&started, 3 - failed);
+    thread::sleep(Duration::from_millis(200));
+
+    let mut restarted = started.lock().unwrap().clone();
+    restarted.sort_unstable();
+    restarted
+}
+
+fn restarts_the_failed_group_and_the_ones_started_after_it() {
+    assert_eq!(restarted_after_failure_of(0), vec![0, 1, 2]);
+    assert_eq!(restarted_after_failure_of(1), vec![1, 2]);
+    assert_eq!(restarted_after_failure_of(2), vec![2]);
+}
+
+fn run() {
+    setup();
+    restarts_the_failed_group_and_the_ones_started_after_it();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn rest_for_one() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn rest_for_one() {
+        super::run();
+    }
+}
@@ -0,0 +1,133 @@
+// With the `scaling` feature, the faulted elements can be dropped by
+// the resizer before being restarted.
+#![cfg(not(feature = "scaling"))]
+mod common;
+
+use bastion::prelude::*;
+use common::wait_for;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// Makes the element sleep for the given number of milliseconds.
+#[derive(Debug)]
+struct Sleep(u64);
+
+#[derive(Debug)]
+struct Fail;
+
+// Recorded by the element which handles it.
+#[derive(Debug)]
+struct Count(usize);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    Started(Option<RestartState>),
+    Counted(usize),
+}
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+// Starts a group whose element records when it starts and the counts
+// it handles, makes it fault while counts are waiting in its mailbox
+// and returns what it recorded.
+fn restarts_with(restart_state: Option<RestartState>) -> Vec<Event> {
+    let events = Arc::new(Mutex::new(vec![]));
+
+    let recorded = events.clone();
+    let children = Bastion::children(move |children| {
+        let children = match restart_state {
+            Some(restart_state) => children.with_restart_state(restart_state),
+            None => children,
+        };
+
+        let events = recorded.clone();
+        children.with_exec(move |ctx: BastionContext| {
+            let events = events.clone();
+            async move {
+                events.lock().unwrap().push(Event::Started(ctx.restarted()));
+                loop {
+                    msg! { ctx.recv().await?,
+                        sleep: Sleep => thread::sleep(Duration::from_millis(sleep.0));
+                        _fail: Fail => return Err(());
+                        count: Count => events.lock().unwrap().push(Event::Counted(count.0));
+                        _: _ => ();
+                    }
+                }
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    wait_for(&events, 1);
+    let element = &children.elems()[0];
+    element.tell_anonymously(Sleep(100)).unwrap();
+    element.tell_anonymously(Fail).unwrap();
+    for count in 0..3 {
+        element.tell_anonymously(Count(count)).unwrap();
+    }
+
+    wait_for(&events, 2);
+    thread::sleep(Duration::from_millis(200));
+
+    let events = events.lock().unwrap().clone();
+    events
+}
+
+fn restores_the_mailbox_by_default() {
+    assert_eq!(
+        restarts_with(None),
+        vec![
+            Event::Started(None),
+            Event::Started(Some(RestartState::Restore)),
+            Event::Counted(0),
+            Event::Counted(1),
+            Event::Counted(2),
+        ]
+    );
+    assert_eq!(
+        restarts_with(Some(RestartState::Restore)),
+        restarts_with(None)
+    );
+}
+
+fn drops_the_mailbox_when_fresh() {
+    assert_eq!(
+        restarts_with(Some(RestartState::Fresh)),
+        vec![
+            Event::Started(None),
+            Event::Started(Some(RestartState::Fresh)),
+        ]
+    );
+}
+
+fn run() {
+    setup();
+    restores_the_mailbox_by_default();
+    drops_the_mailbox_when_fresh();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn restart_state() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn restart_state() {
+        super::run();
+    }
+}
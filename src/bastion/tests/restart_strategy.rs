@@ -7,6 +7,19 @@ fn check_default_values() {
 
     assert_eq!(restart_strategy.restart_policy(), RestartPolicy::Always);
     assert_eq!(restart_strategy.strategy(), ActorRestartStrategy::Immediate);
+    assert_eq!(restart_strategy.restart_intensity(), None);
+}
+
+#[test]
+fn override_restart_intensity() {
+    let restart_strategy =
+        RestartStrategy::default().with_restart_intensity(5, Duration::from_secs(30));
+
+    assert_eq!(
+        restart_strategy.restart_intensity(),
+        Some((5, Duration::from_secs(30)))
+    );
+    assert_eq!(restart_strategy.restart_policy(), RestartPolicy::Always);
 }
 
 #[test]
@@ -101,3 +114,39 @@ fn calculate_exp_strategy_with_multiplier_non_zero() {
         Some(Duration::from_millis(100 + 99 * 5 * 100))
     );
 }
+
+#[test]
+fn calculate_exp_strategy_with_jitter_zero() {
+    let strategy = ActorRestartStrategy::ExponentialBackOffWithJitter {
+        timeout: Duration::from_millis(100),
+        max: Duration::from_secs(1),
+        jitter: 0.0,
+    };
+
+    assert_eq!(strategy.calculate(0), Some(Duration::from_millis(100)));
+    assert_eq!(strategy.calculate(1), Some(Duration::from_millis(200)));
+    assert_eq!(strategy.calculate(3), Some(Duration::from_millis(800)));
+    assert_eq!(strategy.calculate(4), Some(Duration::from_secs(1)));
+    assert_eq!(strategy.calculate(100), Some(Duration::from_secs(1)));
+}
+
+#[test]
+fn calculate_exp_strategy_with_jitter() {
+    let strategy = ActorRestartStrategy::ExponentialBackOffWithJitter {
+        timeout: Duration::from_millis(100),
+        max: Duration::from_secs(1),
+        jitter: 0.5,
+    };
+
+    // The jittered delays are spread over the whole range.
+    let mut quarters = [0; 4];
+    for _ in 0..1000 {
+        let delay = strategy.calculate(2).unwrap();
+        assert!(delay > Duration::from_millis(200));
+        assert!(delay <= Duration::from_millis(400));
+
+        let quarter = (delay - Duration::from_millis(200)).as_micros() / 50_000;
+        quarters[(quarter as usize).min(3)] += 1;
+    }
+    assert!(quarters.iter().all(|&count| count > 0), "{:?}", quarters);
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use bastion::prelude::*;
+use bastion::router::Router;
+use common::wait_for;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+// Spawns `count` routees recording their index along with the
+// messages they receive.
+fn spawn_routees(count: usize, received: &Arc<Mutex<Vec<(usize, u64)>>>) -> Vec<ChildRef> {
+    (0..count)
+        .map(|index| {
+            let received = received.clone();
+            let children = Bastion::children(move |children| {
+                let received = received.clone();
+                children.with_exec(move |ctx: BastionContext| {
+                    let received = received.clone();
+                    async move {
+                        loop {
+                            msg! { ctx.recv().await?,
+                                msg: u64 => {
+                                    received.lock().unwrap().push((index, msg));
+                                };
+                                _: _ => ();
+                            }
+                        }
+                    }
+                })
+            })
+            .expect("Couldn't create the children group.");
+
+            children.elems()[0].clone()
+        })
+        .collect()
+}
+
+fn routes_in_a_round_robin_fashion() {
+    let received = Arc::new(Mutex::new(vec![]));
+    let mut routees = spawn_routees(3, &received);
+    let last = routees.pop().unwrap();
+    let router = Router::new(routees).with_routee(last);
+    assert_eq!(router.routees().len(), 3);
+
+    let router = router.spawn().expect("Couldn't spawn the router.");
+    for msg in 0..6 {
+        router.tell_anonymously(msg as u64).unwrap();
+    }
+
+    wait_for(&received, 6);
+    let mut received = received.lock().unwrap().clone();
+    received.sort_unstable();
+    assert_eq!(
+        received,
+        vec![(0, 0), (0, 3), (1, 1), (1, 4), (2, 2), (2, 5)]
+    );
+}
+
+fn routes_with_the_given_logic() {
+    let received = Arc::new(Mutex::new(vec![]));
+    let routees = spawn_routees(2, &received);
+    // Routes the even messages to the first routee and the odd ones
+    // to a routee which doesn't exist, dropping them along with the
+    // messages which aren't routed.
+    let router = Router::new(routees)
+        .with_logic(|msg: &SignedMessage, _: &[ChildRef]| {
+            match msg.msg().as_ref().downcast_ref::<u64>() {
+                Some(msg) if *msg % 2 == 0 => Some(0),
+                Some(_) => Some(2),
+                None => None,
+            }
+        })
+        .spawn()
+        .expect("Couldn't spawn the router.");
+
+    for msg in 0..4 {
+        router.tell_anonymously(msg as u64).unwrap();
+    }
+    router.tell_anonymously("unrouted").unwrap();
+
+    wait_for(&received, 2);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(*received.lock().unwrap(), vec![(0, 0), (0, 2)]);
+}
+
+fn run() {
+    setup();
+    routes_in_a_round_robin_fashion();
+    routes_with_the_given_logic();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn router() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn router() {
+        super::run();
+    }
+}
@@ -0,0 +1,158 @@
+mod common;
+
+use bastion::persistence::{MemorySnapshotStore, SnapshotStore};
+use bastion::prelude::*;
+use bastion::schedule::{Scheduled, Scheduler};
+use common::wait_for;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+// Spawns an element recording the messages it receives, behind the
+// alias named `name`.
+fn spawn_target(name: &str) -> Arc<Mutex<Vec<String>>> {
+    let received = Arc::new(Mutex::new(vec![]));
+    let recorded = received.clone();
+    let children = Bastion::children(move |children| {
+        let received = recorded.clone();
+        children.with_exec(move |ctx: BastionContext| {
+            let received = received.clone();
+            async move {
+                loop {
+                    msg! { ctx.recv().await?,
+                        msg: String => {
+                            received.lock().unwrap().push(msg);
+                        };
+                        _: _ => ();
+                    }
+                }
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+    Bastion::alias(name, children.elems()[0].clone());
+
+    received
+}
+
+// Returns a message scheduled to be sent to `target` at `at`, as it
+// would have been saved by a previous run.
+fn saved(id: &str, target: &str, message: &str, at: SystemTime) -> Scheduled {
+    let at = at.duration_since(UNIX_EPOCH).unwrap();
+    let json = format!(
+        r#"{{"id":"{}","target":"{}","message":"{}","at":{{"secs_since_epoch":{},"nanos_since_epoch":{}}}}}"#,
+        id,
+        target,
+        message,
+        at.as_secs(),
+        at.subsec_nanos()
+    );
+
+    serde_json::from_str(&json).unwrap()
+}
+
+fn sends_the_messages_once_due() {
+    let received = spawn_target("due");
+    let scheduler = Scheduler::new();
+
+    let later = scheduler
+        .send_after("due", "later", Duration::from_millis(100))
+        .unwrap();
+    let sooner = scheduler
+        .send_after("due", "sooner", Duration::from_millis(50))
+        .unwrap();
+    let pending = scheduler.pending();
+    let ids = pending.iter().map(Scheduled::id).collect::<Vec<_>>();
+    assert_eq!(ids, vec![sooner.as_str(), later.as_str()]);
+    assert_eq!(pending[0].target(), "due");
+    assert_eq!(pending[0].message(), "sooner");
+
+    wait_for(&received, 2);
+    assert_eq!(*received.lock().unwrap(), vec!["sooner", "later"]);
+    assert!(scheduler.pending().is_empty());
+}
+
+fn drops_the_cancelled_messages() {
+    let received = spawn_target("cancelled");
+    let scheduler = Scheduler::new();
+
+    let cancelled = scheduler
+        .send_after("cancelled", "cancelled", Duration::from_millis(50))
+        .unwrap();
+    scheduler
+        .send_after("cancelled", "sent", Duration::from_millis(100))
+        .unwrap();
+    assert!(scheduler.cancel(&cancelled));
+    assert!(!scheduler.cancel(&cancelled));
+
+    wait_for(&received, 1);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(*received.lock().unwrap(), vec!["sent"]);
+}
+
+fn recovers_the_saved_messages() {
+    let received = spawn_target("recovered");
+    let store = MemorySnapshotStore::new();
+    let now = SystemTime::now();
+    let saved = vec![
+        saved("due", "recovered", "due", now + Duration::from_millis(50)),
+        saved(
+            "missed",
+            "recovered",
+            "missed",
+            now - Duration::from_secs(60),
+        ),
+        saved("late", "recovered", "late", now - Duration::from_millis(10)),
+    ];
+    store.save("bastion_schedules", 1, &saved).unwrap();
+
+    // The hook drops the missed message, while the late one is
+    // still within the grace period.
+    let scheduler = Scheduler::new()
+        .with_store(store.clone())
+        .with_missed_hook(|scheduled: &Scheduled| scheduled.id() != "missed")
+        .with_grace(Duration::from_secs(1));
+    assert_eq!(scheduler.recover(), Ok(2));
+
+    wait_for(&received, 2);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(*received.lock().unwrap(), vec!["late", "due"]);
+
+    // The messages which were sent aren't saved anymore.
+    let (_, saved) = store.load("bastion_schedules").unwrap().unwrap();
+    assert!(saved.is_empty());
+}
+
+fn run() {
+    setup();
+    sends_the_messages_once_due();
+    drops_the_cancelled_messages();
+    recovers_the_saved_messages();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn schedule() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn schedule() {
+        super::run();
+    }
+}
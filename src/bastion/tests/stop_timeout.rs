@@ -0,0 +1,87 @@
+mod common;
+
+use bastion::prelude::*;
+use common::wait_for;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+// Starts a supervisor whose children group takes two seconds to drain
+// once told to stop and returns whether the supervisor stopped
+// within half a second.
+fn stops_with(stop_timeout: Option<Duration>) -> bool {
+    let started = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicUsize::new(0));
+
+    let (counted, after_stop) = (started.clone(), stopped.clone());
+    let supervisor = Bastion::supervisor(move |sp| {
+        let sp = match stop_timeout {
+            Some(stop_timeout) => sp.with_stop_timeout(stop_timeout),
+            None => sp,
+        };
+
+        let (started, after_stop) = (counted.clone(), after_stop.clone());
+        sp.with_callbacks(Callbacks::new().with_after_stop(move || {
+            after_stop.fetch_add(1, Ordering::SeqCst);
+        }))
+        .children(move |children| {
+            children
+                .with_drain_deadline(Duration::from_secs(2))
+                .with_exec(move |ctx: BastionContext| {
+                    let started = started.clone();
+                    async move {
+                        // Never drops the tracked value.
+                        let _tracked = ctx.track(());
+                        started.fetch_add(1, Ordering::SeqCst);
+                        loop {
+                            ctx.recv().await?;
+                        }
+                    }
+                })
+        })
+    })
+    .expect("Couldn't create the supervisor.");
+    wait_for(&started, 1);
+
+    supervisor.stop().unwrap();
+    thread::sleep(Duration::from_millis(500));
+    stopped.load(Ordering::SeqCst) == 1
+}
+
+fn gives_up_on_the_stuck_groups() {
+    assert!(!stops_with(None));
+    assert!(stops_with(Some(Duration::from_millis(100))));
+}
+
+fn run() {
+    setup();
+    gives_up_on_the_stuck_groups();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn stop_timeout() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn stop_timeout() {
+        super::run();
+    }
+}
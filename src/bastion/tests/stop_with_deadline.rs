@@ -0,0 +1,88 @@
+use bastion::prelude::*;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+// Spawns a group whose `redundancy` elements can't handle the `Stop`
+// they receive before `busy` has elapsed, since they block their
+// thread until then.
+fn spawn_group(redundancy: usize, busy: Duration) -> ChildrenRef {
+    Bastion::children(move |children| {
+        children
+            .with_redundancy(redundancy)
+            .with_exec(move |ctx: BastionContext| async move {
+                thread::sleep(busy);
+                loop {
+                    ctx.recv().await?;
+                }
+            })
+    })
+    .expect("Couldn't create the children group.")
+}
+
+fn stops_the_elements_before_the_deadline() {
+    let children = spawn_group(1, Duration::from_millis(0));
+    thread::sleep(Duration::from_millis(50));
+
+    let killed = run!(children.stop_with_deadline(Duration::from_secs(1)))
+        .expect("Couldn't stop the children group.");
+    assert!(killed.is_empty());
+}
+
+fn kills_the_elements_ignoring_the_stop() {
+    let children = spawn_group(1, Duration::from_millis(500));
+    thread::sleep(Duration::from_millis(50));
+
+    let started_at = Instant::now();
+    let killed = run!(children.stop_with_deadline(Duration::from_millis(100)))
+        .expect("Couldn't stop the children group.");
+    assert!(started_at.elapsed() < Duration::from_millis(400));
+    assert_eq!(killed.len(), 1);
+    assert_eq!(killed[0].id(), children.elems()[0].id());
+}
+
+fn kills_the_elements_once_short_deadlines_elapsed() {
+    // Some of the deadlines elapse while the elements are being checked.
+    for deadline in 1..=50 {
+        let children = spawn_group(20, Duration::from_micros(100));
+        thread::sleep(Duration::from_millis(2));
+
+        run!(children.stop_with_deadline(Duration::from_micros(deadline)))
+            .expect("Couldn't stop the children group.");
+    }
+}
+
+fn run() {
+    setup();
+    stops_the_elements_before_the_deadline();
+    kills_the_elements_ignoring_the_stop();
+    kills_the_elements_once_short_deadlines_elapsed();
+    // Lets the elements that were killed unblock their threads.
+    thread::sleep(Duration::from_millis(500));
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn stop_with_deadline() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn stop_with_deadline() {
+        super::run();
+    }
+}
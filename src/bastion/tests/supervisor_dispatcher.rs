@@ -0,0 +1,108 @@
+mod common;
+
+use bastion::prelude::*;
+use common::wait_for;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+// Configures a group of `redundancy` elements counting when they
+// start and recording `name` for each message they are dispatched.
+fn recording(
+    children: Children,
+    name: &'static str,
+    redundancy: usize,
+    started: Arc<AtomicUsize>,
+    dispatched: Arc<Mutex<Vec<&'static str>>>,
+) -> Children {
+    children
+        .with_redundancy(redundancy)
+        .with_exec(move |ctx: BastionContext| {
+            let (started, dispatched) = (started.clone(), dispatched.clone());
+            async move {
+                started.fetch_add(1, Ordering::SeqCst);
+                loop {
+                    msg! { ctx.recv().await?,
+                        // The dispatchers deliver the broadcasted messages
+                        // wrapped into an `Arc`.
+                        _msg: Arc<SignedMessage> => dispatched.lock().unwrap().push(name);
+                        _: _ => ();
+                    }
+                }
+            }
+        })
+}
+
+fn registers_the_groups_of_the_subtree() {
+    let started = Arc::new(AtomicUsize::new(0));
+    let dispatched = Arc::new(Mutex::new(vec![]));
+
+    let (counted, recorded) = (started.clone(), dispatched.clone());
+    Bastion::supervisor(move |sp| {
+        let (started, dispatched) = (counted.clone(), recorded.clone());
+        let (nested_started, nested_dispatched) = (counted.clone(), recorded.clone());
+        sp.with_dispatcher(Dispatcher::with_type(DispatcherType::Named(
+            "workers".to_string(),
+        )))
+        .children(move |children| recording(children, "direct", 2, started, dispatched))
+        .supervisor(move |sp| {
+            sp.children(move |children| {
+                recording(children, "nested", 1, nested_started, nested_dispatched)
+            })
+        })
+    })
+    .expect("Couldn't create the supervisor.");
+    wait_for(&started, 3);
+
+    // The messages are dispatched to one element after the other.
+    Bastion::children(|children| {
+        children.with_exec(|ctx: BastionContext| async move {
+            for _ in 0..3 {
+                let target = BroadcastTarget::Group("workers".to_string());
+                ctx.broadcast_message(target, "job");
+            }
+
+            // Waits instead of stopping, so that it isn't restarted.
+            loop {
+                ctx.recv().await?;
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    wait_for(&dispatched, 3);
+    let mut dispatched = dispatched.lock().unwrap().clone();
+    dispatched.sort_unstable();
+    assert_eq!(dispatched, ["direct", "direct", "nested"]);
+}
+
+fn run() {
+    setup();
+    registers_the_groups_of_the_subtree();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn supervisor_dispatcher() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn supervisor_dispatcher() {
+        super::run();
+    }
+}
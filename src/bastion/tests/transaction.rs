@@ -0,0 +1,148 @@
+use bastion::prelude::*;
+use bastion::transaction::{
+    Abort, Commit, Coordinator, Decision, MemoryDecisions, Prepare, Transaction, Vote,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+struct Account {
+    child: ChildRef,
+    balance: Arc<Mutex<i64>>,
+    // Whether the account acknowledges the decisions.
+    acks: Arc<AtomicBool>,
+}
+
+// Spawns an account voting to commit the changes which don't make
+// its balance negative.
+fn account(initial: i64) -> Account {
+    let balance = Arc::new(Mutex::new(initial));
+    let acks = Arc::new(AtomicBool::new(true));
+
+    let (shared, acked) = (balance.clone(), acks.clone());
+    let children = Bastion::children(move |children| {
+        let (balance, acks) = (shared.clone(), acked.clone());
+        children.with_exec(move |ctx: BastionContext| {
+            let (balance, acks) = (balance.clone(), acks.clone());
+            async move {
+                let mut prepared = None;
+                loop {
+                    msg! { ctx.recv().await?,
+                        prepare: Prepare<i64> =!> {
+                            let vote = if *balance.lock().unwrap() + prepare.change() >= 0 {
+                                prepared = Some(*prepare.change());
+                                Vote::Yes
+                            } else {
+                                Vote::No
+                            };
+                            answer!(ctx, vote).unwrap();
+                        };
+                        _commit: Commit =!> {
+                            *balance.lock().unwrap() += prepared.take().unwrap_or(0);
+                            if acks.load(Ordering::SeqCst) {
+                                answer!(ctx, ()).unwrap();
+                            }
+                        };
+                        _abort: Abort =!> {
+                            prepared = None;
+                            answer!(ctx, ()).unwrap();
+                        };
+                        _: _ => ();
+                    }
+                }
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    Account {
+        child: children.elems()[0].clone(),
+        balance,
+        acks,
+    }
+}
+
+fn transfer(id: &str, from: &Account, to: &Account, amount: i64) -> Transaction<i64> {
+    Transaction::new(id)
+        .with_participant(from.child.clone(), -amount)
+        .with_participant(to.child.clone(), amount)
+}
+
+fn balances(accounts: &[&Account]) -> Vec<i64> {
+    accounts
+        .iter()
+        .map(|account| *account.balance.lock().unwrap())
+        .collect()
+}
+
+fn coordinator() -> Coordinator {
+    Coordinator::new(MemoryDecisions::new()).with_timeout(Duration::from_millis(200))
+}
+
+fn commits_when_every_participant_votes_yes() {
+    let (alice, bob) = (account(100), account(0));
+
+    let res = run!(coordinator().run(transfer("commit", &alice, &bob, 50)));
+    assert_eq!(res, Ok(()));
+    assert_eq!(balances(&[&alice, &bob]), vec![50, 50]);
+}
+
+fn aborts_when_a_participant_votes_no() {
+    let (alice, bob) = (account(100), account(0));
+
+    let res = run!(coordinator().run(transfer("abort", &alice, &bob, 150)));
+    assert_eq!(res, Err(TransactionError::Aborted));
+    assert_eq!(balances(&[&alice, &bob]), vec![100, 0]);
+}
+
+fn tells_the_decision_again_until_acknowledged() {
+    let (alice, bob) = (account(100), account(0));
+    bob.acks.store(false, Ordering::SeqCst);
+    let coordinator = coordinator();
+
+    let res = run!(coordinator.run(transfer("in-doubt", &alice, &bob, 50)));
+    assert_eq!(res, Err(TransactionError::CommitInDoubt(1)));
+    assert_eq!(coordinator.decision("in-doubt"), Some(Decision::Commit));
+
+    // The participants aren't prepared again, and apply the decision
+    // only once.
+    bob.acks.store(true, Ordering::SeqCst);
+    let res = run!(coordinator.run(transfer("in-doubt", &alice, &bob, 1000)));
+    assert_eq!(res, Ok(()));
+    assert_eq!(coordinator.decision("in-doubt"), None);
+    assert_eq!(balances(&[&alice, &bob]), vec![50, 50]);
+}
+
+fn run() {
+    setup();
+    commits_when_every_participant_votes_yes();
+    aborts_when_a_participant_votes_no();
+    tells_the_decision_again_until_acknowledged();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn transaction() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn transaction() {
+        super::run();
+    }
+}
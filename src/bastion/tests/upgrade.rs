@@ -0,0 +1,96 @@
+mod common;
+
+use bastion::prelude::*;
+use common::wait_for;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// Broadcasted and recorded, along with their version, by the elements.
+#[derive(Debug, Clone)]
+struct Ping;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    Started(usize),
+    Pinged(usize),
+}
+
+fn setup() {
+    Bastion::init();
+    Bastion::start();
+}
+
+fn teardown() {
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
+
+// Returns an exec closure recording when the element starts and the
+// pings it handles with the given version.
+fn version(
+    version: usize,
+    events: Arc<Mutex<Vec<Event>>>,
+) -> impl Fn(BastionContext) -> futures::future::BoxFuture<'static, Result<(), ()>> {
+    move |ctx: BastionContext| {
+        let events = events.clone();
+        Box::pin(async move {
+            events.lock().unwrap().push(Event::Started(version));
+            loop {
+                msg! { ctx.recv().await?,
+                    ref _ping: Ping => events.lock().unwrap().push(Event::Pinged(version));
+                    _: _ => ();
+                }
+            }
+        })
+    }
+}
+
+fn rolls_the_elements_over() {
+    let events = Arc::new(Mutex::new(vec![]));
+
+    let v1 = version(1, events.clone());
+    let children = Bastion::children(move |children| children.with_redundancy(2).with_exec(v1))
+        .expect("Couldn't create the children group.");
+    wait_for(&events, 2);
+
+    children.upgrade(version(2, events.clone())).unwrap();
+    wait_for(&events, 4);
+    assert_eq!(
+        events.lock().unwrap()[2..],
+        [Event::Started(2), Event::Started(2)]
+    );
+
+    // Lets the old elements drain their mailboxes and stop.
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(children.elems().len(), 2);
+    children.broadcast(Ping).unwrap();
+    wait_for(&events, 6);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(
+        events.lock().unwrap()[4..],
+        [Event::Pinged(2), Event::Pinged(2)]
+    );
+}
+
+fn run() {
+    setup();
+    rolls_the_elements_over();
+    teardown();
+}
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn upgrade() {
+        super::run();
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod no_tokio_tests {
+    #[test]
+    fn upgrade() {
+        super::run();
+    }
+}
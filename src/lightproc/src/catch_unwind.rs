@@ -1,7 +1,7 @@
+use crate::proc_panic::{self, ProcPanic};
 use pin_utils::unsafe_pinned;
-use std::any::Any;
 use std::future::Future;
-use std::panic::{catch_unwind, AssertUnwindSafe, UnwindSafe};
+use std::panic::{AssertUnwindSafe, UnwindSafe};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
@@ -28,9 +28,9 @@ impl<F> Future for CatchUnwind<F>
 where
     F: Future + UnwindSafe,
 {
-    type Output = Result<F::Output, Box<dyn Any + Send>>;
+    type Output = Result<F::Output, ProcPanic>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-        catch_unwind(AssertUnwindSafe(|| self.future().poll(cx)))?.map(Ok)
+        proc_panic::catch(AssertUnwindSafe(|| self.future().poll(cx)))?.map(Ok)
     }
 }
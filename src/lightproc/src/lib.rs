@@ -29,6 +29,7 @@ mod state;
 
 pub mod lightproc;
 pub mod proc_handle;
+pub mod proc_panic;
 pub mod proc_stack;
 pub mod proc_state;
 pub mod recoverable_handle;
@@ -39,6 +40,7 @@ pub mod recoverable_handle;
 pub mod prelude {
     pub use crate::lightproc::*;
     pub use crate::proc_handle::*;
+    pub use crate::proc_panic::*;
     pub use crate::proc_stack::*;
     pub use crate::proc_state::*;
     pub use crate::recoverable_handle::*;
@@ -0,0 +1,175 @@
+//!
+//! Information about the panics and cancellations of processes
+//!
+//! Recoverable processes catch the panics of their futures. The caught panic
+//! is described by a [ProcPanic], which holds the panic payload along with
+//! the location of the panic and a backtrace captured at the time of the
+//! panic (if backtraces are enabled with `RUST_BACKTRACE`).
+//!
+//! The location and the backtrace are recorded by a panic hook which has
+//! to be installed with [install_hook]. Without it, [ProcPanic::location]
+//! and [ProcPanic::backtrace] always return `None`.
+//!
+//! Capturing the backtraces relies on [std::backtrace], which requires
+//! Rust 1.65 or later.
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::{Cell, RefCell};
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::panic;
+use std::sync::Once;
+
+/// The reason why a recoverable process didn't resolve to an output.
+#[derive(Debug)]
+pub enum ProcError {
+    /// The process was cancelled before completing.
+    Cancelled,
+    /// The future of the process panicked.
+    Panicked(ProcPanic),
+}
+
+/// A panic caught while polling the future of a recoverable process.
+pub struct ProcPanic {
+    payload: Box<dyn Any + Send>,
+    location: Option<String>,
+    backtrace: Option<Backtrace>,
+}
+
+thread_local! {
+    // Whether the future of a recoverable process is being polled
+    // by this thread.
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+    // The location and backtrace of the last panic which happened
+    // while `CATCHING` was set.
+    static LAST_PANIC: RefCell<Option<(Option<String>, Backtrace)>> = const { RefCell::new(None) };
+}
+
+static INSTALL_HOOK: Once = Once::new();
+
+impl ProcPanic {
+    /// Returns the payload the future panicked with.
+    pub fn payload(&self) -> &(dyn Any + Send) {
+        &*self.payload
+    }
+
+    /// Consumes the panic, returning its payload (eg. to resume
+    /// unwinding with [std::panic::resume_unwind]).
+    pub fn into_payload(self) -> Box<dyn Any + Send> {
+        self.payload
+    }
+
+    /// Returns the message the future panicked with, if the payload
+    /// is a string.
+    pub fn message(&self) -> Option<&str> {
+        if let Some(msg) = self.payload.downcast_ref::<&'static str>() {
+            Some(msg)
+        } else if let Some(msg) = self.payload.downcast_ref::<String>() {
+            Some(msg)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the location (`file:line:column`) of the panic.
+    pub fn location(&self) -> Option<&str> {
+        self.location.as_deref()
+    }
+
+    /// Returns the backtrace captured when the future panicked.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_ref()
+    }
+}
+
+/// Runs `f` (which polls the future of a recoverable process),
+/// building a [ProcPanic] out of its panic if it panics.
+pub(crate) fn catch<F, R>(f: F) -> Result<R, ProcPanic>
+where
+    F: FnOnce() -> R + panic::UnwindSafe,
+{
+    let catching = CATCHING.with(|catching| catching.replace(true));
+    let res = panic::catch_unwind(f);
+    CATCHING.with(|c| c.set(catching));
+
+    res.map_err(|payload| {
+        let last = LAST_PANIC.with(|last| last.borrow_mut().take());
+        let (location, backtrace) = match last {
+            Some((location, backtrace)) => (location, Some(backtrace)),
+            None => (None, None),
+        };
+
+        ProcPanic {
+            payload,
+            location,
+            backtrace,
+        }
+    })
+}
+
+/// Chains a panic hook recording the location and the backtrace of
+/// the panics happening while polling recoverable processes to the
+/// current one.
+///
+/// The current hook is still called for every panic, so this should be
+/// called after the application installed its own hook (if any), as
+/// a hook set afterward would replace this one instead of chaining it.
+/// Only the first call installs the hook.
+pub fn install_hook() {
+    INSTALL_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if CATCHING.with(Cell::get) {
+                let location = info.location().map(|loc| loc.to_string());
+                let backtrace = Backtrace::capture();
+                LAST_PANIC.with(|last| *last.borrow_mut() = Some((location, backtrace)));
+            }
+
+            previous(info);
+        }));
+    });
+}
+
+impl Debug for ProcPanic {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("ProcPanic")
+            .field("message", &self.message())
+            .field("location", &self.location)
+            .field("backtrace", &self.backtrace)
+            .finish()
+    }
+}
+
+impl Display for ProcPanic {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "process panicked")?;
+        if let Some(msg) = self.message() {
+            write!(fmt, " with '{}'", msg)?;
+        }
+        if let Some(location) = &self.location {
+            write!(fmt, " at {}", location)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for ProcPanic {}
+
+impl Display for ProcError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            ProcError::Cancelled => write!(fmt, "process was cancelled"),
+            ProcError::Panicked(panic) => Display::fmt(panic, fmt),
+        }
+    }
+}
+
+impl Error for ProcError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ProcError::Cancelled => None,
+            ProcError::Panicked(panic) => Some(panic),
+        }
+    }
+}
@@ -5,12 +5,17 @@
 //!
 //! If we want to make an analogy, stack abstraction is similar to actor lifecycle abstractions
 //! in frameworks like Akka, but tailored version for Rust environment.
+use super::proc_panic::ProcPanic;
 use super::proc_state::*;
 
 use std::fmt::{self, Debug, Formatter};
 
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Hook called with the caught panic of a process
+type PanicHook = Arc<dyn Fn(&ProcPanic) + Send + Sync>;
 
 /// Stack abstraction for lightweight processes
 ///
@@ -33,11 +38,31 @@ pub struct ProcStack {
 
     pub(crate) state: ProcState,
 
+    /// Poll accounting of the process, shared between the clones of the stack
+    pub(crate) stats: Arc<ProcStats>,
+
+    /// Scheduling weight of the process
+    ///
+    /// Executors give processes with a greater weight proportionally more
+    /// polls each time they are run.
+    pub(crate) weight: u32,
+
     /// Before start callback
     ///
     /// This callback is called before we start to inner future of the process
     pub(crate) before_start: Option<Arc<dyn Fn(ProcState) + Send + Sync>>,
 
+    /// Before poll callback
+    ///
+    /// This callback is called every time right before the inner future of the process is polled
+    pub(crate) before_poll: Option<Arc<dyn Fn(ProcState) + Send + Sync>>,
+
+    /// After poll callback
+    ///
+    /// This callback is called every time right after the inner future of the process has been
+    /// polled, whether it resolved to an output or not
+    pub(crate) after_poll: Option<Arc<dyn Fn(ProcState) + Send + Sync>>,
+
     /// After complete callback
     ///
     /// This callback is called after future resolved to it's output.
@@ -51,6 +76,49 @@ pub struct ProcStack {
     /// This callback is only called when a panic has been occurred.
     /// Mind that [ProcHandle](proc_handle/struct.ProcHandle.html) is not using this
     pub(crate) after_panic: Option<Arc<dyn Fn(ProcState) + Send + Sync>>,
+
+    /// Panic hook
+    ///
+    /// This callback is called with the caught panic when a panic has been occurred,
+    /// before the after panic callback.
+    pub(crate) panic_hook: Option<PanicHook>,
+}
+
+/// Cumulative poll accounting of a lightweight process
+///
+/// # Example
+///
+/// ```rust
+/// use lightproc::proc_stack::{ProcStack, ProcStats};
+/// use std::sync::Arc;
+///
+/// let stats = Arc::new(ProcStats::default());
+/// let stack = ProcStack::default().with_stats(stats.clone());
+///
+/// assert_eq!(stats.polls(), 0);
+/// ```
+#[derive(Debug, Default)]
+pub struct ProcStats {
+    polls: AtomicU64,
+    poll_time_nanos: AtomicU64,
+}
+
+impl ProcStats {
+    /// Returns the number of times the process' future has been polled.
+    pub fn polls(&self) -> u64 {
+        self.polls.load(Ordering::Relaxed)
+    }
+
+    /// Returns the cumulative time spent polling the process' future.
+    pub fn poll_time(&self) -> Duration {
+        Duration::from_nanos(self.poll_time_nanos.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn record_poll(&self, elapsed: Duration) {
+        self.polls.fetch_add(1, Ordering::Relaxed);
+        self.poll_time_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
 }
 
 impl ProcStack {
@@ -92,6 +160,63 @@ impl ProcStack {
         self
     }
 
+    /// Sets the poll accounting of the process which is going to take this stack, allowing
+    /// to share it with the rest of the program.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::{ProcStack, ProcStats};
+    /// use std::sync::Arc;
+    ///
+    /// ProcStack::default()
+    ///     .with_stats(Arc::new(ProcStats::default()));
+    /// ```
+    pub fn with_stats(mut self, stats: Arc<ProcStats>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Sets the scheduling weight of the process which is going to take this
+    /// stack, `1` by default. A weight of `0` is treated as `1`.
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::ProcStack;
+    ///
+    /// ProcStack::default()
+    ///     .with_weight(4);
+    /// ```
+    pub fn with_weight(mut self, weight: u32) -> Self {
+        self.weight = weight.max(1);
+        self
+    }
+
+    /// Returns the scheduling weight of the process which takes this stack.
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::ProcStack;
+    ///
+    /// let stack = ProcStack::default();
+    ///
+    /// assert_eq!(stack.weight(), 1);
+    /// ```
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+
+    /// Returns the poll accounting of the process which takes this stack.
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::ProcStack;
+    ///
+    /// let stack = ProcStack::default();
+    ///
+    /// assert_eq!(stack.stats().polls(), 0);
+    /// ```
+    pub fn stats(&self) -> &Arc<ProcStats> {
+        &self.stats
+    }
+
     /// Adds a callback that will be executed before polling inner future to the stack
     ///
     /// ```rust
@@ -110,6 +235,42 @@ impl ProcStack {
         self
     }
 
+    /// Adds a callback that will be executed every time before polling inner future to the stack
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::ProcStack;
+    /// use lightproc::proc_state::EmptyProcState;
+    ///
+    /// ProcStack::default()
+    ///     .with_before_poll(|s: &mut EmptyProcState| { println!("Before poll"); });
+    /// ```
+    pub fn with_before_poll<C, S>(mut self, callback: C) -> Self
+    where
+        S: State,
+        C: Fn(&mut S) + Send + Sync + 'static,
+    {
+        self.before_poll = Some(self.wrap_callback(callback));
+        self
+    }
+
+    /// Adds a callback that will be executed every time after polling inner future to the stack
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::ProcStack;
+    /// use lightproc::proc_state::EmptyProcState;
+    ///
+    /// ProcStack::default()
+    ///     .with_after_poll(|s: &mut EmptyProcState| { println!("After poll"); });
+    /// ```
+    pub fn with_after_poll<C, S>(mut self, callback: C) -> Self
+    where
+        S: State,
+        C: Fn(&mut S) + Send + Sync + 'static,
+    {
+        self.after_poll = Some(self.wrap_callback(callback));
+        self
+    }
+
     /// Adds a callback that will be executed after inner future resolves to an output to the stack
     ///
     /// ```rust
@@ -146,6 +307,22 @@ impl ProcStack {
         self
     }
 
+    /// Adds a hook that will be executed with the caught panic after inner future panics
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::ProcStack;
+    ///
+    /// ProcStack::default()
+    ///     .with_panic_hook(|panic| { println!("Panicked: {}", panic); });
+    /// ```
+    pub fn with_panic_hook<C>(mut self, hook: C) -> Self
+    where
+        C: Fn(&ProcPanic) + Send + Sync + 'static,
+    {
+        self.panic_hook = Some(Arc::new(hook));
+        self
+    }
+
     /// Utility function to get_pid for the implementation of executors.
     ///
     /// ```rust
@@ -211,9 +388,14 @@ impl Default for ProcStack {
         ProcStack {
             pid: AtomicUsize::new(0xDEAD_BEEF),
             state: Arc::new(Mutex::new(EmptyState)),
+            stats: Arc::new(ProcStats::default()),
+            weight: 1,
             before_start: None,
+            before_poll: None,
+            after_poll: None,
             after_complete: None,
             after_panic: None,
+            panic_hook: None,
         }
     }
 }
@@ -223,9 +405,14 @@ impl Debug for ProcStack {
         fmt.debug_struct("ProcStack")
             .field("pid", &self.pid.load(Ordering::SeqCst))
             .field("state", &self.state)
+            .field("stats", &self.stats)
+            .field("weight", &self.weight)
             .field("before_start", &self.before_start.is_some())
+            .field("before_poll", &self.before_poll.is_some())
+            .field("after_poll", &self.after_poll.is_some())
             .field("after_complete", &self.after_complete.is_some())
             .field("after_panic", &self.after_panic.is_some())
+            .field("panic_hook", &self.panic_hook.is_some())
             .finish()
     }
 }
@@ -235,9 +422,14 @@ impl Clone for ProcStack {
         ProcStack {
             pid: AtomicUsize::new(self.pid.load(Ordering::Acquire)),
             state: self.state.clone(),
+            stats: self.stats.clone(),
+            weight: self.weight,
             before_start: self.before_start.clone(),
+            before_poll: self.before_poll.clone(),
+            after_poll: self.after_poll.clone(),
             after_complete: self.after_complete.clone(),
             after_panic: self.after_panic.clone(),
+            panic_hook: self.panic_hook.clone(),
         }
     }
 }
@@ -16,6 +16,7 @@ use std::ptr::NonNull;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::time::Instant;
 
 /// Raw pointers to the fields of a proc.
 pub(crate) struct RawProc<F, R, S> {
@@ -400,9 +401,19 @@ where
             (*before_start_cb.clone())((*raw.stack).state.clone());
         }
 
+        if let Some(before_poll_cb) = &(*raw.stack).before_poll {
+            (*before_poll_cb.clone())((*raw.stack).state.clone());
+        }
+
+        let started = Instant::now();
         let poll = <F as Future>::poll(Pin::new_unchecked(&mut *raw.future), cx);
+        (*raw.stack).stats.record_poll(started.elapsed());
         mem::forget(guard);
 
+        if let Some(after_poll_cb) = &(*raw.stack).after_poll {
+            (*after_poll_cb.clone())((*raw.stack).state.clone());
+        }
+
         match poll {
             Poll::Ready(out) => {
                 // Replace the future with its output.
@@ -2,18 +2,23 @@
 //! Handle for recoverable process
 use crate::proc_data::ProcData;
 use crate::proc_handle::ProcHandle;
+use crate::proc_panic::{ProcError, ProcPanic};
 use crate::proc_stack::ProcStack;
 use crate::state::State;
 use std::fmt::{self, Debug, Formatter};
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
-use std::thread;
 
 /// Recoverable handle which encapsulates a standard Proc Handle and contain all panics inside.
 ///
-/// Execution of `after_panic` will be immediate on polling the [RecoverableHandle]'s future.
-pub struct RecoverableHandle<R>(pub(crate) ProcHandle<thread::Result<R>>);
+/// Execution of `panic_hook` and `after_panic` will be immediate on polling the
+/// [RecoverableHandle]'s future.
+pub struct RecoverableHandle<R>(pub(crate) ProcHandle<Result<R, ProcPanic>>);
+
+/// Future returned by [RecoverableHandle::outcome], resolving to the output of the
+/// process or to the reason why it didn't complete.
+pub struct Outcome<R>(RecoverableHandle<R>);
 
 impl<R> RecoverableHandle<R> {
     /// Cancels the proc.
@@ -34,27 +39,75 @@ impl<R> RecoverableHandle<R> {
     pub fn state(&self) -> State {
         self.0.state()
     }
-}
 
-impl<R> Future for RecoverableHandle<R> {
-    type Output = Option<R>;
+    /// Turns the handle into a future which tells apart a cancelled process from
+    /// a panicked one, exposing the caught panic in the latter case.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use lightproc::prelude::*;
+    /// # use std::sync::{Arc, Mutex};
+    /// #
+    /// # let queue = Arc::new(Mutex::new(Vec::new()));
+    /// # let scheduled = queue.clone();
+    /// # let schedule = move |proc: LightProc| scheduled.lock().unwrap().push(proc);
+    /// #
+    /// let (proc, handle) = LightProc::recoverable(
+    ///     async { panic!("oops") },
+    ///     schedule,
+    ///     ProcStack::default(),
+    /// );
+    ///
+    /// proc.schedule();
+    /// # let proc = queue.lock().unwrap().pop().unwrap();
+    /// proc.run();
+    ///
+    /// let outcome: Result<(), ProcError> = futures_executor::block_on(handle.outcome());
+    /// match outcome {
+    ///     Err(ProcError::Panicked(panic)) => assert_eq!(panic.message(), Some("oops")),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn outcome(self) -> Outcome<R> {
+        Outcome(self)
+    }
 
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+    fn poll_outcome(&mut self, cx: &mut Context) -> Poll<Result<R, ProcError>> {
         match Pin::new(&mut self.0).poll(cx) {
             Poll::Pending => Poll::Pending,
-            Poll::Ready(None) => Poll::Ready(None),
-            Poll::Ready(Some(Ok(val))) => Poll::Ready(Some(val)),
-            Poll::Ready(Some(Err(_))) => {
+            Poll::Ready(None) => Poll::Ready(Err(ProcError::Cancelled)),
+            Poll::Ready(Some(Ok(val))) => Poll::Ready(Ok(val)),
+            Poll::Ready(Some(Err(panic))) => {
+                if let Some(panic_hook) = self.0.stack().panic_hook.clone() {
+                    (*panic_hook)(&panic);
+                }
+
                 if let Some(after_panic_cb) = self.0.stack().after_panic.clone() {
                     (*after_panic_cb.clone())(self.0.stack().state.clone());
                 }
 
-                Poll::Ready(None)
+                Poll::Ready(Err(ProcError::Panicked(panic)))
             }
         }
     }
 }
 
+impl<R> Future for RecoverableHandle<R> {
+    type Output = Option<R>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        self.poll_outcome(cx).map(Result::ok)
+    }
+}
+
+impl<R> Future for Outcome<R> {
+    type Output = Result<R, ProcError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        self.0.poll_outcome(cx)
+    }
+}
+
 impl<R> Debug for RecoverableHandle<R> {
     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
         let ptr = self.0.raw_proc.as_ptr();
@@ -66,3 +119,9 @@ impl<R> Debug for RecoverableHandle<R> {
             .finish()
     }
 }
+
+impl<R> Debug for Outcome<R> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_tuple("Outcome").field(&self.0).finish()
+    }
+}
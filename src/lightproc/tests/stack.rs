@@ -1,5 +1,55 @@
-use lightproc::proc_stack::ProcStack;
+use lightproc::prelude::*;
+use lightproc::proc_panic;
 use lightproc::proc_state::EmptyProcState;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+type Queue = Arc<Mutex<Vec<LightProc>>>;
+
+fn spawn<F, R>(future: F, stack: ProcStack) -> (Queue, RecoverableHandle<R>)
+where
+    F: Future<Output = R> + Send + 'static,
+    R: Send + 'static,
+{
+    let queue = Queue::default();
+    let scheduled = queue.clone();
+    let schedule = move |proc| scheduled.lock().unwrap().push(proc);
+    let (proc, handle) = LightProc::recoverable(future, schedule, stack);
+    proc.schedule();
+
+    (queue, handle)
+}
+
+fn run(queue: &Queue) {
+    loop {
+        let proc = queue.lock().unwrap().pop();
+        match proc {
+            Some(proc) => proc.run(),
+            None => break,
+        }
+    }
+}
+
+// A future which is pending the first time it is polled.
+#[derive(Default)]
+struct YieldOnce(bool);
+
+impl Future for YieldOnce {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
 
 #[test]
 fn stack_copy() {
@@ -13,3 +63,103 @@ fn stack_copy() {
 
     assert_eq!(stack2.get_pid(), 12);
 }
+
+#[test]
+fn outcome_completed() {
+    let (queue, handle) = spawn(async { 42 }, ProcStack::default());
+    run(&queue);
+
+    let outcome = futures_executor::block_on(handle.outcome());
+    assert_eq!(outcome.unwrap(), 42);
+}
+
+#[test]
+fn outcome_panicked() {
+    let (queue, handle) = spawn(async { panic!("oops") }, ProcStack::default());
+    run(&queue);
+
+    let outcome: Result<(), ProcError> = futures_executor::block_on(handle.outcome());
+    match outcome {
+        Err(ProcError::Panicked(panic)) => assert_eq!(panic.message(), Some("oops")),
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+}
+
+#[test]
+fn outcome_cancelled() {
+    let (queue, handle) = spawn(async { 42 }, ProcStack::default());
+    handle.cancel();
+    run(&queue);
+
+    let outcome = futures_executor::block_on(handle.outcome());
+    assert!(matches!(outcome, Err(ProcError::Cancelled)));
+}
+
+#[test]
+fn panic_hook() {
+    proc_panic::install_hook();
+
+    let caught = Arc::new(Mutex::new(None));
+    let hooked = caught.clone();
+    let stack = ProcStack::default().with_panic_hook(move |panic| {
+        let location = panic.location().map(str::to_string);
+        *hooked.lock().unwrap() = Some((panic.message().map(str::to_string), location));
+    });
+
+    let (queue, handle) = spawn(async { panic!("hooked") }, stack);
+    run(&queue);
+    assert!(caught.lock().unwrap().is_none());
+
+    let outcome: Result<(), ProcError> = futures_executor::block_on(handle.outcome());
+    assert!(matches!(outcome, Err(ProcError::Panicked(_))));
+
+    let (message, location) = caught.lock().unwrap().take().unwrap();
+    assert_eq!(message.as_deref(), Some("hooked"));
+    assert!(location.unwrap().starts_with(file!()));
+}
+
+#[test]
+fn poll_hooks() {
+    let before = Arc::new(AtomicUsize::new(0));
+    let after = Arc::new(AtomicUsize::new(0));
+    let (before_poll, after_poll) = (before.clone(), after.clone());
+    let stack = ProcStack::default()
+        .with_before_poll(move |_s: &mut EmptyProcState| {
+            before_poll.fetch_add(1, Ordering::SeqCst);
+        })
+        .with_after_poll(move |_s: &mut EmptyProcState| {
+            after_poll.fetch_add(1, Ordering::SeqCst);
+        });
+
+    let (queue, handle) = spawn(YieldOnce::default(), stack);
+    run(&queue);
+    futures_executor::block_on(handle);
+
+    assert_eq!(before.load(Ordering::SeqCst), 2);
+    assert_eq!(after.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn stats() {
+    let stats = Arc::new(ProcStats::default());
+    let stack = ProcStack::default().with_stats(stats.clone());
+    assert!(Arc::ptr_eq(stack.clone().stats(), &stats));
+
+    let (queue, handle) = spawn(YieldOnce::default(), stack);
+    assert_eq!(stats.polls(), 0);
+
+    run(&queue);
+    futures_executor::block_on(handle);
+
+    assert_eq!(stats.polls(), 2);
+}
+
+#[test]
+fn weight() {
+    assert_eq!(ProcStack::default().weight(), 1);
+    assert_eq!(ProcStack::default().with_weight(0).weight(), 1);
+
+    let stack = ProcStack::default().with_weight(4);
+    assert_eq!(stack.weight(), 4);
+    assert_eq!(stack.clone().weight(), 4);
+}